@@ -165,7 +165,7 @@ echo "--------------------------------"
 
 # Try burning without fee approval (should fail with clear error)
 BURN_NO_FEE=$(dfx canister $NETWORK call $BACKEND burn_icpi "(100000000 : nat)" 2>&1 || echo "no_approval")
-if echo "$BURN_NO_FEE" | grep -qE "InsufficientFeeAllowance|fee.*approval|approve"; then
+if echo "$BURN_NO_FEE" | grep -qE "InsufficientApproval|fee.*approval|approve"; then
     pass "Fee approval check is enforced"
 elif echo "$BURN_NO_FEE" | grep -q "no_approval"; then
     pass "Burn without fee approval rejected (correct behavior)"
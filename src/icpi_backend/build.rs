@@ -0,0 +1,43 @@
+//! Build script: embeds deployment identifiers into the canister binary
+//!
+//! - `ICPI_GIT_REVISION`: commit the wasm was built from (`git rev-parse HEAD`)
+//!
+//! Falls back to "unknown" so builds outside a checkout still succeed. The
+//! interface hash is computed in the canister from the compiled interface
+//! (see `build_info`), not from the committed `.did` file.
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ICPI_GIT_REVISION");
+
+    let revision = std::env::var("ICPI_GIT_REVISION")
+        .ok()
+        .filter(|r| !r.trim().is_empty())
+        .or_else(git_revision)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ICPI_GIT_REVISION={}", revision.trim());
+}
+
+/// Current commit, also registering the files that move when HEAD does
+fn git_revision() -> Option<String> {
+    let git_dir = Command::new("git").args(["rev-parse", "--git-dir"]).output().ok()?;
+    if git_dir.status.success() {
+        let dir = String::from_utf8_lossy(&git_dir.stdout).trim().to_string();
+        let head = Path::new(&dir).join("HEAD");
+        println!("cargo:rerun-if-changed={}", head.display());
+        if let Ok(contents) = std::fs::read_to_string(&head) {
+            if let Some(reference) = contents.trim().strip_prefix("ref: ") {
+                println!("cargo:rerun-if-changed={}", Path::new(&dir).join(reference).display());
+            }
+        }
+    }
+
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let revision = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!revision.is_empty()).then_some(revision)
+}
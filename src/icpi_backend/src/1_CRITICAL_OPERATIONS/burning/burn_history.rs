@@ -0,0 +1,142 @@
+//! Burn history - bounded log of completed burns
+//!
+//! Pending mints already double as mint history; burns complete in a single
+//! call, so they are recorded here once tokens have been distributed.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use std::cell::RefCell;
+
+use super::BurnResult;
+use crate::infrastructure::Result;
+
+const MAX_BURN_HISTORY: usize = 1000;
+
+/// A completed burn, as returned by `get_my_burns`
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct BurnRecord {
+    pub user: Principal,
+    pub icpi_burned: Nat,
+    pub timestamp: u64,
+    pub memo: Option<String>,
+    pub successful_transfers: u32,
+    pub failed_transfers: u32,
+    pub request_id: Option<String>,
+    /// Tokens not redeemed and why (None for burns recorded before this was tracked)
+    pub skipped_tokens: Option<Vec<(String, super::redemption_calculator::SkipReason)>>,
+}
+
+thread_local! {
+    static BURN_HISTORY: RefCell<Vec<BurnRecord>> = RefCell::new(Vec::new());
+}
+
+/// Record the outcome of a burn whose ICPI has been taken
+///
+/// A distribution error still burned the user's ICPI, so it is recorded
+/// with no transfers. `redeemed_value_e6` is the ckUSDT value of the
+/// tokens delivered.
+pub fn record_burn(user: Principal, icpi_burned: &Nat, memo: Option<String>, result: &Result<BurnResult>, redeemed_value_e6: &Nat) {
+    let (successful_transfers, failed_transfers, skipped_tokens) = match result {
+        Ok(r) => (r.successful_transfers.len() as u32, r.failed_transfers.len() as u32, Some(r.skipped_tokens.clone())),
+        Err(_) => (0, 0, None),
+    };
+
+    crate::_5_INFORMATIONAL::daily_totals::record_burn(crate::infrastructure::runtime::time(), icpi_burned);
+    if result.is_ok() {
+        crate::_5_INFORMATIONAL::user_activity::record_burn(
+            user,
+            icpi_burned,
+            redeemed_value_e6,
+            crate::infrastructure::runtime::time(),
+            &crate::infrastructure::config::get_config(),
+        );
+    }
+    crate::_1_CRITICAL_OPERATIONS::rebalancing::invalidate_projection();
+
+    if let Some(m) = memo.as_deref() {
+        crate::log_op!("Burn of {} ICPI by {} ledger memo tag {}", icpi_burned, user, crate::infrastructure::memo::ledger_tag(m));
+    }
+
+    push_record(BurnRecord {
+        user,
+        icpi_burned: icpi_burned.clone(),
+        timestamp: crate::infrastructure::runtime::time(),
+        memo,
+        successful_transfers,
+        failed_transfers,
+        request_id: crate::infrastructure::logging::current_request_id(),
+        skipped_tokens,
+    });
+}
+
+fn push_record(record: BurnRecord) {
+    BURN_HISTORY.with(|history| {
+        let mut history = history.borrow_mut();
+        history.push(record);
+        let len = history.len();
+        if len > MAX_BURN_HISTORY {
+            history.drain(0..(len - MAX_BURN_HISTORY));
+        }
+    });
+}
+
+/// A user's burns, newest first
+pub fn get_user_burns(user: &Principal) -> Vec<BurnRecord> {
+    BURN_HISTORY.with(|history| {
+        history.borrow()
+            .iter()
+            .rev()
+            .filter(|r| r.user == *user)
+            .cloned()
+            .collect()
+    })
+}
+
+/// Export burn history for stable storage
+pub fn export_history() -> Vec<BurnRecord> {
+    BURN_HISTORY.with(|history| history.borrow().clone())
+}
+
+/// Import burn history from stable storage
+pub fn import_history(records: Vec<BurnRecord>) {
+    BURN_HISTORY.with(|history| *history.borrow_mut() = records);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(user: Principal, timestamp: u64, memo: Option<&str>) -> BurnRecord {
+        BurnRecord {
+            user,
+            icpi_burned: Nat::from(1_000_000u64),
+            timestamp,
+            memo: memo.map(str::to_string),
+            successful_transfers: 4,
+            failed_transfers: 0,
+            request_id: None,
+            skipped_tokens: None,
+        }
+    }
+
+    #[test]
+    fn test_user_burns_newest_first_and_bounded() {
+        import_history(Vec::new());
+        let alice = Principal::from_slice(&[1]);
+        let bob = Principal::from_slice(&[2]);
+
+        push_record(record(alice, 1, Some("first")));
+        push_record(record(bob, 2, None));
+        push_record(record(alice, 3, Some("second")));
+
+        let burns = get_user_burns(&alice);
+        assert_eq!(burns.len(), 2);
+        assert_eq!(burns[0].memo.as_deref(), Some("second"));
+        assert_eq!(burns[1].memo.as_deref(), Some("first"));
+
+        for i in 0..MAX_BURN_HISTORY as u64 {
+            push_record(record(bob, 10 + i, None));
+        }
+        assert_eq!(export_history().len(), MAX_BURN_HISTORY);
+        assert!(get_user_burns(&alice).is_empty());
+    }
+}
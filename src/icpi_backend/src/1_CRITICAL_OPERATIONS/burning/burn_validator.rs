@@ -1,32 +1,61 @@
 //! Validation for burn operations
 
 use candid::{Nat, Principal};
-use crate::infrastructure::{Result, IcpiError, ValidationError, BurnError};
-use crate::infrastructure::constants::MIN_BURN_AMOUNT;
+use crate::infrastructure::{Result, IcpiError, ValidationError, BurnError, RequiredApproval, apply_bps};
+use crate::infrastructure::constants::{MIN_BURN_AMOUNT, MAX_BURN_SUPPLY_BPS, MINT_FEE_AMOUNT};
 
 pub fn validate_burn_request(caller: &Principal, amount: &Nat) -> Result<()> {
-    // Check principal
-    if caller == &Principal::anonymous() {
-        return Err(IcpiError::Validation(ValidationError::InvalidPrincipal {
-            principal: caller.to_text(),
-        }));
-    }
+    validate_burn_caller(caller)?;
+    validate_burn_amount(amount)?;
+
+    // Rate limiting
+    crate::infrastructure::rate_limiting::check_operation_rate_limit(
+        crate::infrastructure::rate_limiting::RateLimitedOperation::Burn,
+        caller,
+    )?;
 
-    // Check minimum amount
+    Ok(())
+}
+
+/// Check a burn amount is above the minimum
+pub fn validate_burn_amount(amount: &Nat) -> Result<()> {
     if amount < &Nat::from(MIN_BURN_AMOUNT) {
         return Err(IcpiError::Burn(BurnError::AmountBelowMinimum {
             amount: amount.to_string(),
             minimum: MIN_BURN_AMOUNT.to_string(),
         }));
     }
+    Ok(())
+}
+
+/// Allowances a burn of `amount` ICPI draws on, per token
+///
+/// The ckUSDT fee `transfer_from` consumes its ledger fee from the
+/// allowance; the ICPI one goes to the minting account, a burn, which
+/// carries no fee.
+pub fn required_burn_approvals(amount: &Nat, ckusdt_fee: u64) -> Vec<(&'static str, Nat)> {
+    vec![
+        ("ckUSDT", Nat::from(MINT_FEE_AMOUNT) + Nat::from(ckusdt_fee)),
+        ("ICPI", amount.clone()),
+    ]
+}
+
+/// Caller checks that don't depend on the amount
+pub fn validate_burn_caller(caller: &Principal) -> Result<()> {
+    // Check principal
+    if caller == &Principal::anonymous() {
+        return Err(IcpiError::Validation(ValidationError::InvalidPrincipal {
+            principal: caller.to_text(),
+        }));
+    }
 
-    // Rate limiting
-    crate::infrastructure::rate_limiting::check_rate_limit(
-        &format!("burn_{}", caller),
-        1_000_000_000 // 1 second
-    )?;
+    // Allowlist/denylist enforcement
+    crate::infrastructure::access_control::check_principal_access(caller)
+}
 
-    Ok(())
+/// Maximum burn allowed against `supply` (10%, rounded down)
+pub fn max_burn_amount(supply: &Nat) -> Result<Nat> {
+    apply_bps(supply, MAX_BURN_SUPPLY_BPS)
 }
 
 /// Validates burn amount does not exceed maximum (10% of supply)
@@ -35,9 +64,9 @@ pub fn validate_burn_request(caller: &Principal, amount: &Nat) -> Result<()> {
 /// Uses Nat (BigUint) arithmetic directly - no artificial u128 ceiling.
 ///
 /// # Algorithm
-/// Check if: amount * 100 > supply * 10
-/// This is equivalent to: amount / supply > 0.10
-/// But uses integer arithmetic to avoid floating point precision loss.
+/// Check if: amount > floor(supply × 1,000 bps ÷ 10,000)
+/// For whole amounts this is exactly amount / supply > 0.10, with no
+/// floating point involved.
 ///
 /// # Arguments
 /// * `amount` - The amount of ICPI tokens to burn
@@ -47,29 +76,110 @@ pub fn validate_burn_request(caller: &Principal, amount: &Nat) -> Result<()> {
 /// * `Ok(())` if amount is within the 10% limit
 /// * `Err(IcpiError::Burn(AmountExceedsMaximum))` if amount exceeds limit
 pub fn validate_burn_limit(amount: &Nat, supply: &Nat) -> Result<()> {
-    // Use Nat arithmetic directly (supports BigUint, no u128 ceiling)
-    // Check if: amount * 100 > supply * 10
-    let amount_scaled = amount.clone() * Nat::from(100u64);
-    let supply_scaled = supply.clone() * Nat::from(10u64);
-
-    if amount_scaled > supply_scaled {
-        // Calculate maximum allowed burn: (supply * 10) / 100
-        let maximum_burn = supply.clone() * Nat::from(10u64) / Nat::from(100u64);
+    let maximum = max_burn_amount(supply)?;
 
+    if amount > &maximum {
         return Err(IcpiError::Burn(BurnError::AmountExceedsMaximum {
             amount: amount.to_string(),
-            maximum: maximum_burn.to_string(),
-            percentage_limit: "10%".to_string(),
+            maximum: maximum.to_string(),
+            percentage_limit: format!("{}%", MAX_BURN_SUPPLY_BPS / 100),
         }));
     }
 
     Ok(())
 }
 
+/// Check both approvals a burn needs before anything is collected
+///
+/// An allowance that couldn't be read (`None`) isn't reported as missing;
+/// the later `transfer_from` surfaces it instead.
+pub fn validate_burn_approvals(fee_allowance: Option<&Nat>, icpi_allowance: Option<&Nat>, amount: &Nat) -> Result<()> {
+    let approved = [fee_allowance, icpi_allowance];
+    let checks: Vec<(&str, Nat, Option<&Nat>)> = required_burn_approvals(amount, crate::infrastructure::ledger_fees::ckusdt_fee())
+        .into_iter()
+        .zip(approved)
+        .map(|((token, required), approved)| (token, required, approved))
+        .collect();
+
+    let missing: Vec<String> = checks.iter()
+        .filter(|(_, required, approved)| approved.is_some_and(|a| a < required))
+        .map(|(token, _, _)| token.to_string())
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let required = checks.into_iter()
+        .map(|(token, required, approved)| RequiredApproval {
+            token: token.to_string(),
+            required: required.to_string(),
+            approved: approved.map(|a| a.to_string()),
+        })
+        .collect();
+    Err(IcpiError::Burn(BurnError::InsufficientApproval { required, missing }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn missing_approvals(result: Result<()>) -> Vec<String> {
+        match result {
+            Err(IcpiError::Burn(BurnError::InsufficientApproval { required, missing })) => {
+                assert_eq!(required.len(), 2, "both approvals are always listed");
+                missing
+            }
+            other => panic!("Expected InsufficientApproval, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_burn_approvals_sufficient() {
+        let amount = Nat::from(100_000_000u64);
+        let fee = Nat::from(MINT_FEE_AMOUNT + crate::infrastructure::ledger_fees::ckusdt_fee());
+        assert!(validate_burn_approvals(Some(&fee), Some(&amount), &amount).is_ok());
+
+        // The ckUSDT fee transfer also pays its ledger fee out of the allowance
+        let bare_fee = Nat::from(MINT_FEE_AMOUNT);
+        assert_eq!(missing_approvals(validate_burn_approvals(Some(&bare_fee), Some(&amount), &amount)), vec!["ckUSDT"]);
+    }
+
+    #[test]
+    fn test_burn_approvals_reports_each_missing() {
+        let amount = Nat::from(100_000_000u64);
+        let fee = Nat::from(MINT_FEE_AMOUNT + crate::infrastructure::ledger_fees::ckusdt_fee());
+        let zero = Nat::from(0u64);
+
+        assert_eq!(missing_approvals(validate_burn_approvals(Some(&zero), Some(&amount), &amount)), vec!["ckUSDT"]);
+        assert_eq!(missing_approvals(validate_burn_approvals(Some(&fee), Some(&zero), &amount)), vec!["ICPI"]);
+        assert_eq!(
+            missing_approvals(validate_burn_approvals(Some(&zero), Some(&zero), &amount)),
+            vec!["ckUSDT", "ICPI"]
+        );
+    }
+
+    #[test]
+    fn test_burn_approvals_lists_amounts() {
+        let amount = Nat::from(100_000_000u64);
+        let short = Nat::from(99_999_999u64);
+        match validate_burn_approvals(None, Some(&short), &amount) {
+            Err(IcpiError::Burn(BurnError::InsufficientApproval { required, missing })) => {
+                assert_eq!(missing, vec!["ICPI"]);
+                assert_eq!(required[0].approved, None);
+                assert_eq!(required[1].required, amount.to_string());
+                assert_eq!(required[1].approved, Some(short.to_string()));
+            }
+            other => panic!("Expected InsufficientApproval, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_burn_approvals_unreadable_not_missing() {
+        let amount = Nat::from(100_000_000u64);
+        assert!(validate_burn_approvals(None, None, &amount).is_ok());
+    }
+
     #[test]
     fn test_anonymous_principal_rejected() {
         let result = validate_burn_request(&Principal::anonymous(), &Nat::from(MIN_BURN_AMOUNT));
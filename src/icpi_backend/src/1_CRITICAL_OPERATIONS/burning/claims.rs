@@ -0,0 +1,236 @@
+//! Redemption claims - redemptions a burn couldn't send in the same call
+//!
+//! When a burn distribution runs out of instruction budget, the transfers
+//! it didn't start are stored here and the user collects them later with
+//! `claim_redemptions`. Claims whose transfer fails stay claimable, as do
+//! burn transfers whose automatic retries are exhausted.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use std::cell::RefCell;
+
+use super::token_distributor::{self, RedemptionAdjustment};
+use crate::infrastructure::{Result, InstructionBudget};
+
+/// A redemption owed to a user
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct RedemptionClaim {
+    pub user: Principal,
+    pub token: String,
+    pub amount: Nat,
+    pub created_at: u64,
+    pub request_id: Option<String>,
+}
+
+/// Outcome of `claim_redemptions`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ClaimResult {
+    pub successful_transfers: Vec<(String, Nat)>,
+    pub failed_transfers: Vec<(String, Nat, String)>,
+    /// Still owed: failed, deferred again by the instruction budget, or
+    /// short of balance
+    pub pending_claims: Vec<(String, Nat)>,
+}
+
+thread_local! {
+    static CLAIMS: RefCell<Vec<RedemptionClaim>> = RefCell::new(Vec::new());
+}
+
+/// Store redemptions owed to `user`
+pub fn add_claims(user: Principal, redemptions: &[(String, Nat)]) {
+    push_claims(
+        user,
+        redemptions,
+        crate::infrastructure::runtime::time(),
+        crate::infrastructure::logging::current_request_id(),
+    );
+}
+
+/// Store redemptions owed to `user`, attributed to `request_id`
+pub fn push_claims(user: Principal, redemptions: &[(String, Nat)], now: u64, request_id: Option<String>) {
+    CLAIMS.with(|claims| {
+        claims.borrow_mut().extend(redemptions.iter().map(|(token, amount)| RedemptionClaim {
+            user,
+            token: token.clone(),
+            amount: amount.clone(),
+            created_at: now,
+            request_id: request_id.clone(),
+        }));
+    });
+}
+
+/// A user's outstanding claims, oldest first
+pub fn get_user_claims(user: &Principal) -> Vec<RedemptionClaim> {
+    CLAIMS.with(|claims| {
+        claims.borrow().iter().filter(|c| c.user == *user).cloned().collect()
+    })
+}
+
+/// Total claimed per token, across all users
+pub fn owed_by_token() -> Vec<(String, Nat)> {
+    CLAIMS.with(|claims| super::sum_by_token(claims.borrow().iter().map(|c| (&c.token, &c.amount))))
+}
+
+/// Remove and return a user's claims
+fn take_user_claims(user: &Principal) -> Vec<(String, Nat)> {
+    CLAIMS.with(|claims| {
+        let mut claims = claims.borrow_mut();
+        let (taken, kept): (Vec<_>, Vec<_>) = claims.drain(..).partition(|c| c.user == *user);
+        *claims = kept;
+        taken.into_iter().map(|c| (c.token, c.amount)).collect()
+    })
+}
+
+/// Part of each clamped claim neither delivered nor paid in ckUSDT
+///
+/// Claims are already owed, so the balance check may delay them but must
+/// not write them off.
+fn unpaid_shortfalls(adjustments: &[RedemptionAdjustment]) -> Vec<(String, Nat)> {
+    adjustments.iter()
+        .filter(|a| a.ckusdt_compensation.is_none() && a.requested > a.delivered)
+        .map(|a| (a.token.clone(), a.requested.clone() - a.delivered.clone()))
+        .collect()
+}
+
+/// Transfer a user's outstanding claims
+pub async fn claim_redemptions(user: Principal) -> Result<ClaimResult> {
+    crate::infrastructure::check_not_paused()?;
+
+    // Same guard as burns, so a claim can't race a burn's distribution
+    let _guard = crate::infrastructure::BurnGuard::acquire(user)?;
+
+    let owed = take_user_claims(&user);
+    if owed.is_empty() {
+        return Ok(ClaimResult {
+            successful_transfers: Vec::new(),
+            failed_transfers: Vec::new(),
+            pending_claims: Vec::new(),
+        });
+    }
+
+    let ledger_memo = crate::infrastructure::memo::ledger_memo("ICPI redeem claim", None);
+    let outcome = token_distributor::transfer_within_budget(
+        user,
+        &owed,
+        &ledger_memo,
+        &InstructionBudget::from_config(),
+    ).await;
+
+    let mut pending_claims: Vec<(String, Nat)> = outcome.failed.iter()
+        .map(|(token, amount, _)| (token.clone(), amount.clone()))
+        .collect();
+    pending_claims.extend(outcome.remaining);
+    pending_claims.extend(unpaid_shortfalls(&outcome.adjustments));
+    add_claims(user, &pending_claims);
+
+    crate::log_op!(
+        "Claims for {}: {} sent, {} still owed",
+        user, outcome.successful.len(), pending_claims.len()
+    );
+
+    Ok(ClaimResult {
+        successful_transfers: outcome.successful,
+        failed_transfers: outcome.failed,
+        pending_claims,
+    })
+}
+
+/// Export claims for stable storage
+pub fn export_claims() -> Vec<RedemptionClaim> {
+    CLAIMS.with(|claims| claims.borrow().clone())
+}
+
+/// Import claims from stable storage
+pub fn import_claims(claims: Vec<RedemptionClaim>) {
+    CLAIMS.with(|c| *c.borrow_mut() = claims);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(text: &str) -> Principal {
+        Principal::from_text(text).unwrap()
+    }
+
+    #[test]
+    fn test_claims_are_per_user() {
+        let alice = principal("2vxsx-fae");
+        let bob = principal("aaaaa-aa");
+
+        import_claims(vec![
+            RedemptionClaim { user: alice, token: "ALEX".to_string(), amount: Nat::from(5u64), created_at: 1, request_id: None },
+            RedemptionClaim { user: bob, token: "KONG".to_string(), amount: Nat::from(7u64), created_at: 1, request_id: None },
+            RedemptionClaim { user: alice, token: "ZERO".to_string(), amount: Nat::from(9u64), created_at: 2, request_id: None },
+        ]);
+
+        assert_eq!(get_user_claims(&alice).len(), 2);
+
+        let taken = take_user_claims(&alice);
+        assert_eq!(taken, vec![
+            ("ALEX".to_string(), Nat::from(5u64)),
+            ("ZERO".to_string(), Nat::from(9u64)),
+        ]);
+        assert!(get_user_claims(&alice).is_empty());
+        assert_eq!(get_user_claims(&bob).len(), 1);
+        assert!(take_user_claims(&alice).is_empty());
+    }
+
+    #[test]
+    fn test_claims_round_trip() {
+        let alice = principal("2vxsx-fae");
+        let claims = vec![RedemptionClaim {
+            user: alice,
+            token: "ckUSDT".to_string(),
+            amount: Nat::from(1_000_000u64),
+            created_at: 42,
+            request_id: Some("burn-0000beef".to_string()),
+        }];
+
+        import_claims(claims.clone());
+        assert_eq!(export_claims(), claims);
+    }
+
+    #[test]
+    fn test_owed_by_token_sums_all_users() {
+        let alice = principal("2vxsx-fae");
+        let bob = principal("aaaaa-aa");
+        let claim = |user, token: &str, amount: u64| RedemptionClaim {
+            user,
+            token: token.to_string(),
+            amount: Nat::from(amount),
+            created_at: 1,
+            request_id: None,
+        };
+
+        import_claims(vec![
+            claim(alice, "ALEX", 5),
+            claim(bob, "ckUSDT", 7),
+            claim(bob, "ALEX", 9),
+        ]);
+        assert_eq!(owed_by_token(), vec![
+            ("ALEX".to_string(), Nat::from(14u64)),
+            ("ckUSDT".to_string(), Nat::from(7u64)),
+        ]);
+    }
+
+    #[test]
+    fn test_unpaid_shortfalls_stay_owed() {
+        let adjustment = |token: &str, requested: u64, delivered: u64, compensation: Option<u64>| RedemptionAdjustment {
+            token: token.to_string(),
+            requested: Nat::from(requested),
+            delivered: Nat::from(delivered),
+            ckusdt_compensation: compensation.map(Nat::from),
+        };
+
+        let owed = unpaid_shortfalls(&[
+            adjustment("ALEX", 1_000, 600, None),
+            // Paid in ckUSDT instead
+            adjustment("KONG", 1_000, 0, Some(50)),
+            adjustment("ZERO", 500, 0, None),
+        ]);
+        assert_eq!(owed, vec![
+            ("ALEX".to_string(), Nat::from(400u64)),
+            ("ZERO".to_string(), Nat::from(500u64)),
+        ]);
+    }
+}
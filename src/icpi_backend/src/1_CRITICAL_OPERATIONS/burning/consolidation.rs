@@ -0,0 +1,265 @@
+//! Consolidation of redemption tokens into ckUSDT
+//!
+//! Shared by `exit_position`, which liquidates every leg, and burns capped
+//! to the top-K tokens by value, which liquidate only the remainder. A leg
+//! whose swap fails is delivered in kind instead.
+
+use candid::Nat;
+
+use super::exit::ExitLeg;
+use crate::_4_TRADING_EXECUTION::slippage::MAX_TRADE_SLIPPAGE;
+use crate::types::TrackedToken;
+
+/// A leg delivered as-is instead of swapped
+fn in_kind_leg(symbol: String, amount: Nat, details: String) -> ExitLeg {
+    ExitLeg {
+        token: symbol,
+        amount_in: amount,
+        ckusdt_out: Nat::from(0u64),
+        slippage: 0.0,
+        success: false,
+        details,
+    }
+}
+
+/// Swap each leg to ckUSDT (sequentially - Kongswap limitation)
+///
+/// The swaps spend the same balances the rebalancer trades. Callers hold a
+/// `BurnGuard`, which keeps the global operation on `Burning` (or `Minting`,
+/// which it shares with) so no cycle can start meanwhile; without that,
+/// every leg is delivered in kind.
+pub(super) async fn liquidate(legs: Vec<(String, Nat)>) -> Vec<ExitLeg> {
+    use crate::infrastructure::reentrancy::{get_current_operation, GlobalOperation};
+
+    let current = get_current_operation();
+    if !matches!(current, GlobalOperation::Burning | GlobalOperation::Minting) {
+        crate::log_op!("⚠️ Consolidation outside a burn (global operation: {:?}), delivering in kind", current);
+        let details = format!("not swapped: global operation is {}", current.as_str());
+        return legs.into_iter()
+            .map(|(symbol, amount)| in_kind_leg(symbol, amount, details.clone()))
+            .collect();
+    }
+
+    let mut results = Vec::new();
+    for (symbol, leg_amount) in legs {
+        let swap_result = match TrackedToken::from_symbol(&symbol) {
+            Ok(token) => crate::_4_TRADING_EXECUTION::swaps::execute_swap(
+                &token,
+                leg_amount.clone(),
+                &TrackedToken::ckUSDT,
+                MAX_TRADE_SLIPPAGE,
+            ).await,
+            Err(e) => Err(crate::infrastructure::IcpiError::Other(e)),
+        };
+
+        match swap_result {
+            Ok(reply) => {
+                // Holdings changed; the next preview must not reuse the old projection
+                crate::_1_CRITICAL_OPERATIONS::rebalancing::invalidate_projection();
+                results.push(ExitLeg {
+                    token: symbol,
+                    amount_in: reply.pay_amount,
+                    ckusdt_out: reply.receive_amount,
+                    slippage: reply.slippage,
+                    success: true,
+                    details: String::new(),
+                });
+            }
+            Err(e) => {
+                crate::log_op!("⚠️ Consolidation of {} failed, delivering in kind: {}", symbol, e);
+                results.push(in_kind_leg(symbol, leg_amount, e.to_string()));
+            }
+        }
+    }
+    results
+}
+
+/// Quote each redemption's value in ckUSDT (e6)
+///
+/// ckUSDT is valued at face; a token whose quote fails is valued at zero
+/// so it is consolidated rather than kept.
+pub(super) async fn quote_values(redemptions: &[(String, Nat)]) -> Vec<(String, Nat, Nat)> {
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    let mut valued = Vec::new();
+    for (symbol, amount) in redemptions {
+        let value = if symbol == ckusdt {
+            amount.clone()
+        } else {
+            match crate::_4_TRADING_EXECUTION::swaps::query_swap_amounts_reply(symbol, amount.clone(), ckusdt).await {
+                Ok(reply) => reply.receive_amount,
+                Err(e) => {
+                    crate::log_op!("⚠️ Could not value {} redemption: {}", symbol, e);
+                    Nat::from(0u64)
+                }
+            }
+        };
+        valued.push((symbol.clone(), amount.clone(), value));
+    }
+    valued
+}
+
+/// ckUSDT value (e6) of delivered tokens at current quotes
+///
+/// A token whose quote fails counts as zero.
+pub(super) async fn delivered_value_e6(delivered: &[(String, Nat)]) -> Nat {
+    quote_values(delivered).await.into_iter()
+        .fold(Nat::from(0u64), |total, (_, _, value)| total + value)
+}
+
+/// Split valued redemptions into those delivered as-is and those to consolidate
+///
+/// With more than `max_tokens` distinct tokens, ckUSDT takes one slot (it
+/// receives the consolidated remainder) and the `max_tokens - 1` most
+/// valuable other tokens keep theirs. `max_tokens` must be at least 1.
+pub fn select_top_k(valued: Vec<(String, Nat, Nat)>, max_tokens: usize) -> (Vec<(String, Nat)>, Vec<(String, Nat)>) {
+    if valued.len() <= max_tokens {
+        return (valued.into_iter().map(|(symbol, amount, _)| (symbol, amount)).collect(), Vec::new());
+    }
+
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    let (mut kept, mut others): (Vec<_>, Vec<_>) = valued.into_iter()
+        .partition(|(symbol, _, _)| symbol == ckusdt);
+    // Stable sort: equal values keep redemption order
+    others.sort_by(|a, b| b.2.cmp(&a.2));
+
+    let slots = max_tokens.saturating_sub(1);
+    let consolidate = others.split_off(slots.min(others.len()));
+    kept.extend(others);
+
+    let strip = |v: Vec<(String, Nat, Nat)>| v.into_iter().map(|(symbol, amount, _)| (symbol, amount)).collect();
+    (strip(kept), strip(consolidate))
+}
+
+/// Deliveries after consolidation: kept tokens, with swapped proceeds added
+/// to ckUSDT and failed legs appended in kind
+pub fn merge_consolidation(kept: Vec<(String, Nat)>, legs: &[ExitLeg]) -> Vec<(String, Nat)> {
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    let proceeds = legs.iter()
+        .filter(|leg| leg.success)
+        .fold(Nat::from(0u64), |total, leg| total + leg.ckusdt_out.clone());
+
+    let mut deliveries = kept;
+    match deliveries.iter_mut().find(|(symbol, _)| symbol == ckusdt) {
+        Some((_, amount)) => *amount += proceeds,
+        None if proceeds > Nat::from(0u64) => deliveries.push((ckusdt.to_string(), proceeds)),
+        None => {}
+    }
+
+    deliveries.extend(legs.iter()
+        .filter(|leg| !leg.success)
+        .map(|leg| (leg.token.clone(), leg.amount_in.clone())));
+    deliveries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valued(symbol: &str, amount: u64, value: u64) -> (String, Nat, Nat) {
+        (symbol.to_string(), Nat::from(amount), Nat::from(value))
+    }
+
+    fn leg(token: &str, amount_in: u64, ckusdt_out: u64, success: bool) -> ExitLeg {
+        ExitLeg {
+            token: token.to_string(),
+            amount_in: Nat::from(amount_in),
+            ckusdt_out: Nat::from(ckusdt_out),
+            slippage: 0.0,
+            success,
+            details: String::new(),
+        }
+    }
+
+    fn symbols(v: &[(String, Nat)]) -> Vec<&str> {
+        v.iter().map(|(s, _)| s.as_str()).collect()
+    }
+
+    fn redemptions() -> Vec<(String, Nat, Nat)> {
+        vec![
+            valued("ALEX", 1_000, 4_000_000),
+            valued("ZERO", 2_000, 500_000),
+            valued("KONG", 3_000, 9_000_000),
+            valued("BOB", 4_000, 500_000),
+            valued("ckUSDT", 1_500_000, 1_500_000),
+        ]
+    }
+
+    #[test]
+    fn test_top_k_within_cap_keeps_all() {
+        let (kept, consolidate) = select_top_k(redemptions(), 5);
+        assert_eq!(symbols(&kept), vec!["ALEX", "ZERO", "KONG", "BOB", "ckUSDT"]);
+        assert!(consolidate.is_empty());
+    }
+
+    #[test]
+    fn test_top_k_keeps_most_valuable() {
+        let (kept, consolidate) = select_top_k(redemptions(), 3);
+        // ckUSDT holds a slot for the proceeds; KONG and ALEX are worth most
+        assert_eq!(symbols(&kept), vec!["ckUSDT", "KONG", "ALEX"]);
+        // Ties keep redemption order
+        assert_eq!(symbols(&consolidate), vec!["ZERO", "BOB"]);
+        assert_eq!(consolidate[0].1, Nat::from(2_000u64));
+    }
+
+    #[test]
+    fn test_top_k_of_one_is_all_ckusdt() {
+        let (kept, consolidate) = select_top_k(redemptions(), 1);
+        assert_eq!(symbols(&kept), vec!["ckUSDT"]);
+        assert_eq!(consolidate.len(), 4);
+
+        // No ckUSDT in the redemption: the slot is still reserved
+        let (kept, consolidate) = select_top_k(redemptions()[..4].to_vec(), 2);
+        assert_eq!(symbols(&kept), vec!["KONG"]);
+        assert_eq!(symbols(&consolidate), vec!["ALEX", "ZERO", "BOB"]);
+    }
+
+    #[test]
+    fn test_merge_adds_proceeds_to_ckusdt() {
+        let kept = vec![
+            ("KONG".to_string(), Nat::from(3_000u64)),
+            ("ckUSDT".to_string(), Nat::from(1_500_000u64)),
+        ];
+        let legs = vec![leg("ZERO", 2_000, 490_000, true), leg("BOB", 4_000, 495_000, true)];
+
+        assert_eq!(merge_consolidation(kept, &legs), vec![
+            ("KONG".to_string(), Nat::from(3_000u64)),
+            ("ckUSDT".to_string(), Nat::from(2_485_000u64)),
+        ]);
+    }
+
+    #[test]
+    fn test_liquidate_requires_burn_lock() {
+        use crate::infrastructure::reentrancy::{self, GlobalOperation};
+
+        let _runtime = crate::infrastructure::runtime::mock::MockRuntime::install(1_700_000_000_000_000_000);
+        let legs = vec![("ZERO".to_string(), Nat::from(2_000u64)), ("BOB".to_string(), Nat::from(4_000u64))];
+
+        // Nothing holds off the rebalancer: no swap is attempted
+        let results = futures::executor::block_on(liquidate(legs.clone()));
+        assert!(results.iter().all(|leg| !leg.success && leg.ckusdt_out == Nat::from(0u64)));
+        assert_eq!(merge_consolidation(Vec::new(), &results), legs);
+
+        reentrancy::try_start_global_operation(GlobalOperation::Rebalancing).unwrap();
+        let results = futures::executor::block_on(liquidate(legs));
+        assert!(results.iter().all(|leg| leg.details.contains("rebalancing")));
+        reentrancy::end_global_operation(GlobalOperation::Rebalancing);
+    }
+
+    #[test]
+    fn test_merge_delivers_failed_legs_in_kind() {
+        let kept = vec![("KONG".to_string(), Nat::from(3_000u64))];
+        let legs = vec![leg("ZERO", 2_000, 490_000, true), leg("BOB", 4_000, 0, false)];
+
+        assert_eq!(merge_consolidation(kept.clone(), &legs), vec![
+            ("KONG".to_string(), Nat::from(3_000u64)),
+            ("ckUSDT".to_string(), Nat::from(490_000u64)),
+            ("BOB".to_string(), Nat::from(4_000u64)),
+        ]);
+
+        // Nothing swapped: no empty ckUSDT transfer is added
+        assert_eq!(merge_consolidation(kept.clone(), &[leg("BOB", 4_000, 0, false)]), vec![
+            ("KONG".to_string(), Nat::from(3_000u64)),
+            ("BOB".to_string(), Nat::from(4_000u64)),
+        ]);
+    }
+}
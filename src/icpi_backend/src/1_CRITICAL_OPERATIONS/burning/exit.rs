@@ -0,0 +1,257 @@
+//! Exit position - burn ICPI and receive the redemption as ckUSDT
+//!
+//! Chains a normal burn with liquidation of every non-ckUSDT redemption.
+//! Each leg is quoted before anything is swapped; if the quotes can't
+//! guarantee `min_ckusdt_out` even at the maximum allowed slippage, no swap
+//! runs and the redemption is delivered in kind, as `burn_icpi` would.
+//!
+//! The swaps trade the same balances as the rebalancer. An exit holds a
+//! `BurnGuard` from start to finish, which keeps the global operation on
+//! `Burning`: it can't start while a cycle runs, and no cycle can start
+//! until it ends.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+
+use super::{burn_history, consolidation, redemption_calculator, token_distributor, BurnResult};
+use crate::infrastructure::{Result, InstructionBudget};
+use crate::_4_TRADING_EXECUTION::slippage::{Slippage, MAX_TRADE_SLIPPAGE};
+use crate::types::TrackedToken;
+
+/// One token swapped to ckUSDT during an exit or consolidation
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ExitLeg {
+    pub token: String,
+    pub amount_in: Nat,
+    pub ckusdt_out: Nat,
+    pub slippage: f64,
+    pub success: bool,
+    pub details: String,
+}
+
+/// Summary of an `exit_position` call
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ExitResult {
+    pub icpi_burned: Nat,
+    pub burn_block: Nat,
+    /// False when the minimum couldn't be guaranteed and tokens were delivered in kind
+    pub liquidated: bool,
+    pub ckusdt_delivered: Nat,
+    pub legs: Vec<ExitLeg>,
+    /// Tokens sent as-is: all of them on fallback, or legs whose swap failed
+    pub in_kind_transfers: Vec<(String, Nat)>,
+    pub failed_transfers: Vec<(String, Nat, String)>,
+    pub pending_claims: Vec<(String, Nat)>,
+    /// Ledger block of each transfer to the user, by token
+    pub transfer_blocks: Vec<(String, Nat)>,
+    pub timestamp: u64,
+}
+
+/// Lowest output a swap quoted at `quoted` can fill at within `max_slippage`
+pub fn worst_case_output(quoted: &Nat, max_slippage: Slippage) -> Nat {
+    crate::_4_TRADING_EXECUTION::slippage::calculate_min_receive(quoted, max_slippage)
+}
+
+/// ckUSDT an exit is guaranteed to deliver: the direct ckUSDT redemption
+/// plus every quoted leg at its worst case
+pub fn guaranteed_ckusdt(direct_ckusdt: &Nat, quotes: &[Nat], max_slippage: Slippage) -> Nat {
+    quotes.iter().fold(direct_ckusdt.clone(), |total, quoted| {
+        total + worst_case_output(quoted, max_slippage)
+    })
+}
+
+/// Split redemptions into the ckUSDT amount and the legs to liquidate
+fn split_redemptions(redemptions: Vec<(String, Nat)>) -> (Nat, Vec<(String, Nat)>) {
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    let mut direct = Nat::from(0u64);
+    let mut legs = Vec::new();
+    for (symbol, amount) in redemptions {
+        if symbol == ckusdt {
+            direct += amount;
+        } else {
+            legs.push((symbol, amount));
+        }
+    }
+    (direct, legs)
+}
+
+/// Burn `amount` ICPI and deliver the redemption as ckUSDT
+///
+/// Guarded and limited exactly like `burn_icpi`.
+pub async fn exit_position(caller: Principal, amount: Nat, min_ckusdt_out: Nat) -> Result<ExitResult> {
+    let result = exit_position_steps(caller, amount, min_ckusdt_out).await;
+    if let Err(e) = &result {
+        super::record_burn_failure(caller, e);
+    }
+    result
+}
+
+async fn exit_position_steps(caller: Principal, amount: Nat, min_ckusdt_out: Nat) -> Result<ExitResult> {
+    crate::infrastructure::check_not_paused()?;
+
+    let _guard = crate::infrastructure::BurnGuard::acquire(caller)?;
+    crate::_1_CRITICAL_OPERATIONS::operation_status::check_burn_allowed(&caller)?;
+
+    let (current_supply, burn_block) = super::take_icpi_for_burn(caller, amount.clone(), Some("exit")).await?;
+    let plan = redemption_calculator::calculate_redemptions(&amount, &current_supply).await?;
+    let redemptions = plan.redemptions;
+
+    let (direct_ckusdt, legs) = split_redemptions(redemptions.clone());
+
+    // Quote every leg before swapping anything
+    let mut quotes = Vec::new();
+    let mut quote_error = None;
+    for (symbol, leg_amount) in &legs {
+        match crate::_4_TRADING_EXECUTION::swaps::query_swap_amounts_reply(
+            symbol, leg_amount.clone(), TrackedToken::ckUSDT.to_symbol()
+        ).await {
+            Ok(reply) => quotes.push(reply.receive_amount),
+            Err(e) => {
+                quote_error = Some(format!("{} quote failed: {}", symbol, e));
+                break;
+            }
+        }
+    }
+
+    let guaranteed = guaranteed_ckusdt(&direct_ckusdt, &quotes, MAX_TRADE_SLIPPAGE);
+    let fallback_reason = match quote_error {
+        Some(reason) => Some(reason),
+        None if guaranteed < min_ckusdt_out => Some(format!(
+            "guaranteed {} ckUSDT below minimum {}", guaranteed, min_ckusdt_out
+        )),
+        None => None,
+    };
+
+    let liquidated = fallback_reason.is_none();
+    let mut exit_legs = Vec::new();
+    let in_kind;
+    let to_deliver = if let Some(reason) = fallback_reason {
+        crate::log_op!("⚠️ Exit for {} not liquidated ({}); delivering redemptions in kind", caller, reason);
+        in_kind = redemptions;
+        in_kind.clone()
+    } else {
+        exit_legs = consolidation::liquidate(legs).await;
+        in_kind = exit_legs.iter()
+            .filter(|leg| !leg.success)
+            .map(|leg| (leg.token.clone(), leg.amount_in.clone()))
+            .collect();
+        consolidation::merge_consolidation(
+            vec![(TrackedToken::ckUSDT.to_symbol().to_string(), direct_ckusdt)],
+            &exit_legs,
+        )
+    };
+
+    let ledger_memo = crate::infrastructure::memo::ledger_memo("ICPI exit", None);
+    let outcome = token_distributor::transfer_within_budget(
+        caller,
+        &to_deliver,
+        &ledger_memo,
+        &InstructionBudget::from_config(),
+    ).await;
+    if !outcome.remaining.is_empty() {
+        super::claims::add_claims(caller, &outcome.remaining);
+    }
+    super::transfer_retries::enqueue_failed(caller, &outcome.failed, &ledger_memo, outcome.created_at_time);
+
+    let ckusdt_delivered = outcome.successful.iter()
+        .filter(|(symbol, _)| symbol == TrackedToken::ckUSDT.to_symbol())
+        .fold(Nat::from(0u64), |total, (_, amount)| total + amount.clone());
+
+    let redeemed_value_e6 = consolidation::delivered_value_e6(&outcome.successful).await;
+    let timestamp = crate::infrastructure::runtime::time();
+    burn_history::record_burn(caller, &amount, Some("exit".to_string()), &Ok(BurnResult {
+        successful_transfers: outcome.successful.clone(),
+        failed_transfers: outcome.failed.clone(),
+        icpi_burned: amount.clone(),
+        timestamp,
+        pending_claims: outcome.remaining.clone(),
+        consolidated: exit_legs.clone(),
+        skipped_tokens: plan.skipped,
+        adjustments: outcome.adjustments.clone(),
+    }), &redeemed_value_e6);
+
+    crate::log_op!(
+        "🚪 Exit for {}: burned {} ICPI, {} ckUSDT delivered (liquidated: {})",
+        caller, amount, ckusdt_delivered, liquidated
+    );
+
+    Ok(ExitResult {
+        icpi_burned: amount,
+        burn_block,
+        liquidated,
+        ckusdt_delivered,
+        legs: exit_legs,
+        in_kind_transfers: in_kind,
+        failed_transfers: outcome.failed,
+        pending_claims: outcome.remaining,
+        transfer_blocks: outcome.blocks,
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worst_case_output() {
+        let quoted = Nat::from(10_000_000u64);
+        let pct = |p: f64| Slippage::from_percent(p).unwrap();
+        assert_eq!(worst_case_output(&quoted, pct(0.0)), quoted);
+        assert_eq!(worst_case_output(&quoted, pct(5.0)), Nat::from(9_500_000u64));
+        assert_eq!(worst_case_output(&quoted, pct(0.25)), Nat::from(9_975_000u64));
+        assert_eq!(worst_case_output(&quoted, pct(10.0)), Nat::from(9_000_000u64));
+    }
+
+    #[test]
+    fn test_guaranteed_ckusdt_against_minimum() {
+        let direct = Nat::from(2_000_000u64);
+        let quotes = vec![Nat::from(4_000_000u64), Nat::from(6_000_000u64)];
+
+        // 2 + (4 + 6) * 0.95 = 11.5 ckUSDT
+        let guaranteed = guaranteed_ckusdt(&direct, &quotes, MAX_TRADE_SLIPPAGE);
+        assert_eq!(guaranteed, Nat::from(11_500_000u64));
+        // A minimum above that aborts before any swap
+        assert!(guaranteed < Nat::from(11_500_001u64));
+
+        // Nothing to swap: only the direct ckUSDT counts
+        assert_eq!(guaranteed_ckusdt(&direct, &[], MAX_TRADE_SLIPPAGE), direct);
+    }
+
+    #[test]
+    fn test_exit_serialized_against_rebalancing() {
+        use crate::infrastructure::reentrancy::{self, GlobalOperation};
+        use crate::infrastructure::{IcpiError, SystemError};
+
+        let _runtime = crate::infrastructure::runtime::mock::MockRuntime::install(1_700_000_000_000_000_000);
+        let user = Principal::from_slice(&[1]);
+
+        // No exit while a cycle trades
+        reentrancy::try_start_global_operation(GlobalOperation::Rebalancing).unwrap();
+        let result = futures::executor::block_on(exit_position(user, Nat::from(100_000_000u64), Nat::from(0u64)));
+        assert!(matches!(result, Err(IcpiError::System(SystemError::RebalancingInProgress))));
+        reentrancy::end_global_operation(GlobalOperation::Rebalancing);
+
+        // No cycle while an exit holds its guard
+        let _runtime = crate::infrastructure::runtime::mock::MockRuntime::install(1_800_000_000_000_000_000);
+        let guard = crate::infrastructure::BurnGuard::acquire(user).unwrap();
+        assert!(matches!(
+            reentrancy::try_start_global_operation(GlobalOperation::Rebalancing),
+            Err(IcpiError::System(SystemError::CriticalOperationInProgress { .. }))
+        ));
+        drop(guard);
+    }
+
+    #[test]
+    fn test_split_redemptions() {
+        let (direct, legs) = split_redemptions(vec![
+            ("ALEX".to_string(), Nat::from(10u64)),
+            ("ckUSDT".to_string(), Nat::from(7u64)),
+            ("KONG".to_string(), Nat::from(3u64)),
+        ]);
+        assert_eq!(direct, Nat::from(7u64));
+        assert_eq!(legs, vec![
+            ("ALEX".to_string(), Nat::from(10u64)),
+            ("KONG".to_string(), Nat::from(3u64)),
+        ]);
+    }
+}
@@ -4,20 +4,55 @@
 pub mod burn_validator;
 pub mod redemption_calculator;
 pub mod token_distributor;
+pub mod burn_history;
+pub mod claims;
+pub mod exit;
+pub mod consolidation;
+pub mod transfer_retries;
 
 #[cfg(test)]
 mod tests;
 
 use candid::{CandidType, Deserialize, Nat, Principal};
-use crate::infrastructure::{Result, IcpiError};
+use crate::infrastructure::{Result, IcpiError, LedgerError, ValidationError, icpi_ledger, ckusdt_ledger};
 
 // Burn result structure
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct BurnResult {
     pub successful_transfers: Vec<(String, Nat)>,    // (token_symbol, amount)
-    pub failed_transfers: Vec<(String, Nat, String)>, // (token_symbol, amount, error)
+    pub failed_transfers: Vec<(String, Nat, String)>, // (token_symbol, amount, error); retried automatically
     pub icpi_burned: Nat,
     pub timestamp: u64,
+    /// Redemptions deferred by the instruction budget; collect with `claim_redemptions`
+    pub pending_claims: Vec<(String, Nat)>,
+    /// Tokens swapped to ckUSDT instead of delivered (burns capped by `max_tokens`)
+    pub consolidated: Vec<exit::ExitLeg>,
+    /// Tokens with nothing to redeem, and why
+    pub skipped_tokens: Vec<(String, redemption_calculator::SkipReason)>,
+    /// Redemptions reduced because the backend's balance fell short
+    pub adjustments: Vec<token_distributor::RedemptionAdjustment>,
+}
+
+/// Tokens held for burners but not yet delivered, per token
+///
+/// Already redeemed, so not part of the portfolio: excluded from TVL and
+/// from what the rebalancer may sell, like reserved mint deposits.
+pub fn owed_balances() -> Vec<(String, Nat)> {
+    let claims = claims::owed_by_token();
+    let retries = transfer_retries::owed_by_token();
+    sum_by_token(claims.iter().chain(retries.iter()).map(|(token, amount)| (token, amount)))
+}
+
+/// Sum amounts per token, in first-seen order (pure function)
+pub(super) fn sum_by_token<'a>(amounts: impl IntoIterator<Item = (&'a String, &'a Nat)>) -> Vec<(String, Nat)> {
+    let mut totals: Vec<(String, Nat)> = Vec::new();
+    for (token, amount) in amounts {
+        match totals.iter_mut().find(|(t, _)| t == token) {
+            Some((_, total)) => *total += amount.clone(),
+            None => totals.push((token.clone(), amount.clone())),
+        }
+    }
+    totals
 }
 
 // Main burn orchestration function
@@ -30,67 +65,120 @@ pub struct BurnResult {
 // 5. Backend collects 0.1 ckUSDT fee via ICRC-2 transfer_from (from ckUSDT approval)
 // 6. Backend pulls ICPI from user via ICRC-2 transfer_from (atomically burns it)
 // 7. Backend calculates proportional redemptions based on current portfolio
+//    (with `max_tokens`, all but the top-K by value are swapped to ckUSDT)
 // 8. Backend distributes redemption tokens to user
 //
 // SECURITY: ICRC-2 prevents race conditions because each burn atomically pulls
 // from the specific user's approved tokens, not from a shared pool
-pub async fn burn_icpi(caller: Principal, amount: Nat) -> Result<BurnResult> {
+pub async fn burn_icpi(caller: Principal, amount: Nat, memo: Option<String>, max_tokens: Option<u32>) -> Result<BurnResult> {
+    let result = burn_icpi_steps(caller, amount, memo, max_tokens).await;
+    if let Err(e) = &result {
+        record_burn_failure(caller, e);
+    }
+    result
+}
+
+/// Record a failed burn or exit as the user's last burn failure
+///
+/// Only terminal errors are recorded; a retryable one (busy guard,
+/// rebalance in progress, unreachable ledger) is not a final outcome.
+fn record_burn_failure(caller: Principal, error: &IcpiError) {
+    if !crate::_1_CRITICAL_OPERATIONS::operation_status::last_failure::is_terminal(error) {
+        return;
+    }
+    crate::_1_CRITICAL_OPERATIONS::operation_status::last_failure::record_failure(
+        caller,
+        crate::_1_CRITICAL_OPERATIONS::operation_status::last_failure::FailedOperation::Burn,
+        error,
+        crate::infrastructure::logging::current_request_id(),
+    );
+}
+
+async fn burn_icpi_steps(caller: Principal, amount: Nat, memo: Option<String>, max_tokens: Option<u32>) -> Result<BurnResult> {
     // Check not paused (Phase 2: H-1)
     crate::infrastructure::check_not_paused()?;
 
+    let memo = crate::infrastructure::memo::validate_memo(memo)?;
+
+    if max_tokens == Some(0) {
+        return Err(IcpiError::Validation(ValidationError::InvalidAmount {
+            amount: "0".to_string(),
+            reason: "max_tokens must be at least 1".to_string(),
+        }));
+    }
+
     // Acquire reentrancy guard - prevents concurrent burns by same user
     let _guard = crate::infrastructure::BurnGuard::acquire(caller)?;
 
+    // Refuse while this user has a mint in flight (fee pulls would interleave)
+    crate::_1_CRITICAL_OPERATIONS::operation_status::check_burn_allowed(&caller)?;
+
+    let (current_supply, _burn_block) = take_icpi_for_burn(caller, amount.clone(), memo.as_deref()).await?;
+
+    // Calculate redemptions
+    let plan = redemption_calculator::calculate_redemptions(&amount, &current_supply).await?;
+    let mut redemptions = plan.redemptions;
+
+    // Swap everything outside the top-K tokens by value into ckUSDT
+    let mut consolidated = Vec::new();
+    if let Some(max_tokens) = max_tokens.map(|k| k as usize).filter(|k| redemptions.len() > *k) {
+        let valued = consolidation::quote_values(&redemptions).await;
+        let (kept, remainder) = consolidation::select_top_k(valued, max_tokens);
+        crate::log_op!("Consolidating {} of {} redemption tokens to ckUSDT", remainder.len(), redemptions.len());
+        consolidated = consolidation::liquidate(remainder).await;
+        redemptions = consolidation::merge_consolidation(kept, &consolidated);
+    }
+
+    // Distribute tokens to user (passing actual burn amount)
+    let result = token_distributor::distribute_tokens(caller, redemptions, plan.skipped, amount.clone(), memo.as_deref()).await
+        .map(|result| BurnResult { consolidated, ..result });
+    let redeemed_value_e6 = match &result {
+        Ok(r) => consolidation::delivered_value_e6(&r.successful_transfers).await,
+        Err(_) => Nat::from(0u64),
+    };
+    burn_history::record_burn(caller, &amount, memo, &result, &redeemed_value_e6);
+
+    result
+}
+
+/// Validate a burn, collect the fee and pull the ICPI (steps 4-6 above)
+///
+/// Returns the supply read before the burn and the ICPI ledger block. The
+/// caller must hold the user's `BurnGuard`.
+pub(crate) async fn take_icpi_for_burn(caller: Principal, amount: Nat, memo: Option<&str>) -> Result<(Nat, Nat)> {
     // Validate request
     burn_validator::validate_burn_request(&caller, &amount)?;
 
-    // CRITICAL: Check fee approval BEFORE other validations (Phase 3: M-2)
-    // This prevents user from wasting gas on validations if they can't afford the fee
-    // User must have approved backend for 0.1 ckUSDT on ckUSDT ledger
-    ic_cdk::println!("Checking ckUSDT fee approval for user {}", caller);
-    let ckusdt_canister = Principal::from_text(crate::infrastructure::constants::CKUSDT_CANISTER_ID)
-        .map_err(|e| IcpiError::Other(format!("Invalid ckUSDT principal: {}", e)))?;
-
-    use crate::types::icrc::{AllowanceArgs, Allowance};
-
-    let allowance_result: std::result::Result<(Allowance,), _> = ic_cdk::call(
-        ckusdt_canister,
-        "icrc2_allowance",
-        (AllowanceArgs {
-            account: crate::types::icrc::Account {
-                owner: caller,
-                subaccount: None,
-            },
-            spender: crate::types::icrc::Account {
-                owner: ic_cdk::api::id(), // Backend canister
-                subaccount: None,
-            },
-        },)
-    ).await;
-
-    match allowance_result {
-        Ok((allowance,)) => {
-            let required_fee = Nat::from(crate::infrastructure::constants::MINT_FEE_AMOUNT);
-            if allowance.allowance < required_fee {
-                ic_cdk::println!(
-                    "⚠️ Insufficient fee approval: user approved {} e6, required {} e6",
-                    allowance.allowance, required_fee
-                );
-                return Err(IcpiError::Burn(crate::infrastructure::BurnError::InsufficientFeeAllowance {
-                    required: required_fee.to_string(),
-                    approved: allowance.allowance.to_string(),
-                }));
-            }
-            ic_cdk::println!("✅ Fee approval sufficient: {} e6 approved", allowance.allowance);
-        },
-        Err((code, msg)) => {
-            // Warning only - proceed with burn, fee collection will fail with clear error if needed
-            ic_cdk::println!(
-                "⚠️ Could not check fee allowance: {:?} - {}. Proceeding...",
-                code, msg
-            );
+    // CRITICAL: Check both approvals BEFORE other validations (Phase 3: M-2)
+    // This prevents user from paying the fee for a burn that can't pull its ICPI
+    // User must have approved backend for 0.1 ckUSDT and for the ICPI amount
+    crate::log_op!("Checking ckUSDT fee and ICPI approvals for user {}", caller);
+    let (fee_allowance, icpi_allowance) = futures::join!(
+        ckusdt_ledger::allowance(caller),
+        icpi_ledger::allowance(caller),
+    );
+
+    // Warning only - proceed, the transfer_from calls fail with clear errors if needed
+    let fee_allowance = match fee_allowance {
+        Ok(a) => Some(a.allowance),
+        Err(e) => {
+            crate::log_op!("⚠️ Could not check fee allowance: {}. Proceeding...", e);
+            None
         }
+    };
+    let icpi_allowance = match icpi_allowance {
+        Ok(a) => Some(a.allowance),
+        Err(e) => {
+            crate::log_op!("⚠️ Could not check ICPI allowance: {}. Proceeding...", e);
+            None
+        }
+    };
+
+    if let Err(e) = burn_validator::validate_burn_approvals(fee_allowance.as_ref(), icpi_allowance.as_ref(), &amount) {
+        crate::log_op!("⚠️ Missing burn approvals for {}: {}", caller, e);
+        return Err(e);
     }
+    crate::log_op!("✅ Burn approvals sufficient");
 
     // Get current supply atomically BEFORE collecting fee
     let current_supply = crate::_2_CRITICAL_DATA::supply_tracker::get_icpi_supply_uncached().await?;
@@ -105,27 +193,7 @@ pub async fn burn_icpi(caller: Principal, amount: Nat) -> Result<BurnResult> {
 
     // CRITICAL: Check user has sufficient ICPI balance BEFORE collecting fee
     // This prevents user from paying fee if burn will fail anyway
-    let icpi_canister = Principal::from_text(crate::infrastructure::constants::ICPI_CANISTER_ID)
-        .map_err(|e| IcpiError::Other(format!("Invalid ICPI principal: {}", e)))?;
-
-    let user_balance_result: std::result::Result<(Nat,), _> = ic_cdk::call(
-        icpi_canister,
-        "icrc1_balance_of",
-        (crate::types::icrc::Account {
-            owner: caller,
-            subaccount: None,
-        },)
-    ).await;
-
-    let user_icpi_balance = match user_balance_result {
-        Ok((balance,)) => balance,
-        Err((code, msg)) => {
-            return Err(IcpiError::Query(crate::infrastructure::errors::QueryError::CanisterUnreachable {
-                canister: "ICPI ledger".to_string(),
-                reason: format!("Balance query failed: {:?} - {}", code, msg),
-            }));
-        }
-    };
+    let user_icpi_balance = icpi_ledger::balance_of(caller).await?;
 
     if user_icpi_balance < amount {
         return Err(IcpiError::Burn(crate::infrastructure::BurnError::InsufficientBalance {
@@ -134,88 +202,60 @@ pub async fn burn_icpi(caller: Principal, amount: Nat) -> Result<BurnResult> {
         }));
     }
 
-    ic_cdk::println!("User {} has {} ICPI, burning {} ICPI", caller, user_icpi_balance, amount);
+    crate::log_op!("User {} has {} ICPI, burning {} ICPI", caller, user_icpi_balance, amount);
 
     // NOW collect fee (after all validations passed)
     // Fee is 0.1 ckUSDT - user must have approved backend for this amount
     // Same fee structure as minting (prevents spam, covers compute costs)
-    ic_cdk::println!("Collecting 0.1 ckUSDT burn fee from user {}", caller);
+    crate::log_op!("Collecting 0.1 ckUSDT burn fee from user {}", caller);
     match crate::_1_CRITICAL_OPERATIONS::minting::fee_handler::collect_mint_fee(caller).await {
-        Ok(_) => {
-            ic_cdk::println!("Fee collected successfully for burn from user {}", caller);
+        Ok(fee) => {
+            crate::log_op!("Fee collected successfully for burn from user {}", caller);
+            crate::_5_INFORMATIONAL::costs::record_cost(
+                crate::_5_INFORMATIONAL::costs::CostKind::BurnFee,
+                num_traits::ToPrimitive::to_u64(&fee.0).unwrap_or(0),
+            );
         }
         Err(e) => {
-            ic_cdk::println!("⚠️ Fee collection failed for burn: {}", e);
-            ic_cdk::println!("User must approve backend for 0.1 ckUSDT on ckUSDT ledger first");
+            crate::log_op!("⚠️ Fee collection failed for burn: {}", e);
+            crate::log_op!("User must approve backend for 0.1 ckUSDT on ckUSDT ledger first");
             return Err(e);
         }
     }
 
-    ic_cdk::println!("Burning {} ICPI from supply of {}", amount, current_supply);
+    crate::log_op!("Burning {} ICPI from supply of {}", amount, current_supply);
 
     // CRITICAL: Transfer ICPI from user to backend (which automatically burns it)
     // Uses ICRC-2 transfer_from so user keeps custody until burn confirmed
     // IMPORTANT: User must have called icrc2_approve on ICPI ledger first to approve backend
     // Backend is the burning account - tokens transferred to it are automatically burned
-    let icpi_canister = Principal::from_text(crate::infrastructure::constants::ICPI_CANISTER_ID)
-        .map_err(|e| IcpiError::Other(format!("Invalid ICPI principal: {}", e)))?;
-
-    use crate::types::icrc::{TransferFromArgs, TransferFromError};
-
-    let transfer_from_args = TransferFromArgs {
-        from: crate::types::icrc::Account {
-            owner: caller,
-            subaccount: None,
-        },
-        to: crate::types::icrc::Account {
-            owner: ic_cdk::id(),
-            subaccount: None,
-        },
-        amount: amount.clone(),
-        fee: None,
-        memo: Some(b"ICPI burn".to_vec()),
-        created_at_time: Some(ic_cdk::api::time()),
-    };
-
-    let transfer_result: std::result::Result<(std::result::Result<Nat, TransferFromError>,), _> = ic_cdk::call(
-        icpi_canister,
-        "icrc2_transfer_from",
-        (transfer_from_args,)
-    ).await;
-
-    match transfer_result {
-        Ok((Ok(block),)) => {
-            ic_cdk::println!("✅ ICPI transferred to burning account at block {} via ICRC-2", block);
+    let burn_memo = crate::infrastructure::memo::ledger_memo("ICPI burn", memo);
+    let burn_block = match icpi_ledger::transfer_from(caller, amount.clone(), burn_memo.as_bytes()).await {
+        Ok(block) => {
+            crate::log_op!("✅ ICPI transferred to burning account at block {} via ICRC-2", block);
+            block
         }
-        Ok((Err(TransferFromError::InsufficientAllowance { allowance }),)) => {
-            ic_cdk::println!("⚠️ Insufficient ICPI approval: required {}, approved {}", amount, allowance);
-            ic_cdk::println!("User must call icrc2_approve on ICPI ledger to approve backend first");
+        Err(IcpiError::Ledger(LedgerError::InsufficientAllowance { allowance, .. })) => {
+            // Approval was lowered after the pre-check
+            crate::log_op!("⚠️ Insufficient ICPI approval: required {}, approved {}", amount, allowance);
+            crate::log_op!("User must call icrc2_approve on ICPI ledger to approve backend first");
             return Err(IcpiError::Burn(crate::infrastructure::BurnError::InsufficientApproval {
-                required: amount.to_string(),
-                approved: allowance.to_string(),
+                required: vec![crate::infrastructure::RequiredApproval {
+                    token: "ICPI".to_string(),
+                    required: amount.to_string(),
+                    approved: Some(allowance),
+                }],
+                missing: vec!["ICPI".to_string()],
             }));
         }
-        Ok((Err(e),)) => {
-            return Err(IcpiError::Burn(crate::infrastructure::BurnError::TokenTransferFailed {
-                token: "ICPI".to_string(),
-                amount: amount.to_string(),
-                reason: format!("ICRC-2 error: {:?}", e),
-            }));
-        }
-        Err((code, msg)) => {
+        Err(e) => {
             return Err(IcpiError::Burn(crate::infrastructure::BurnError::TokenTransferFailed {
                 token: "ICPI".to_string(),
                 amount: amount.to_string(),
-                reason: format!("Transfer call failed: {:?} - {}", code, msg),
+                reason: e.to_string(),
             }));
         }
-    }
-
-    // Calculate redemptions
-    let redemptions = redemption_calculator::calculate_redemptions(&amount, &current_supply).await?;
-
-    // Distribute tokens to user (passing actual burn amount)
-    let result = token_distributor::distribute_tokens(caller, redemptions, amount.clone()).await?;
+    };
 
-    Ok(result)
+    Ok((current_supply, burn_block))
 }
@@ -1,58 +1,119 @@
 //! Calculate redemption amounts for burn operations
 //! Pure functions for proportional distribution
 
-use candid::Nat;
+use candid::{CandidType, Deserialize, Nat};
 use crate::infrastructure::{Result, IcpiError, CalculationError};
 use crate::types::TrackedToken;
 
+/// Why a token was left out of a redemption
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum SkipReason {
+    /// The index holds none of the token
+    ZeroBalance,
+    /// The proportional share doesn't cover the transfer fee
+    DustBelowFee,
+    /// Balance unavailable
+    Excluded,
+    /// The proportional share couldn't be computed
+    ShareFailed { error: String },
+}
+
+/// Redemption amounts, plus the tokens that won't be sent and why
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RedemptionPlan {
+    pub redemptions: Vec<(String, Nat)>,
+    pub skipped: Vec<(String, SkipReason)>,
+}
+
 /// Calculate redemption amounts for all tokens based on burn amount and current supply
 pub async fn calculate_redemptions(
     burn_amount: &Nat,
     current_supply: &Nat,
-) -> Result<Vec<(String, Nat)>> {
-    let mut redemptions = Vec::new();
-
+) -> Result<RedemptionPlan> {
     // Get all token balances
     let balances = crate::_2_CRITICAL_DATA::token_queries::get_all_balances_uncached().await?;
 
-    // Calculate proportional redemption for each token
-    for (token_symbol, balance) in balances {
-        if balance > Nat::from(0u32) {
-            // Calculate: (burn_amount * balance) / current_supply
-            let redemption_amount = match crate::infrastructure::math::multiply_and_divide(
-                burn_amount,
-                &balance,
-                current_supply
-            ) {
-                Ok(amount) => amount,
-                Err(e) => {
-                    ic_cdk::println!("Warning: Calculation failed for {}: {}", token_symbol, e);
-                    continue;
-                }
-            };
-
-            // Check if amount is above dust threshold (transfer fee + buffer)
-            const TRANSFER_FEE: u32 = 10_000; // Standard ICRC-1 fee
-            const MIN_BUFFER: u32 = 1_000;    // Small buffer above fee
-
-            if redemption_amount > Nat::from(TRANSFER_FEE + MIN_BUFFER) {
-                let amount_after_fee = redemption_amount - Nat::from(TRANSFER_FEE);
-                redemptions.push((token_symbol, amount_after_fee));
-            } else {
-                ic_cdk::println!("Skipping {} redemption: {} below dust threshold",
-                    token_symbol, redemption_amount);
-            }
-        }
+    let plan = plan_redemptions(
+        burn_amount,
+        current_supply,
+        &balances,
+        crate::infrastructure::ledger_fees::transfer_fee,
+    );
+    for (token_symbol, reason) in &plan.skipped {
+        crate::log_op!("Skipping {} redemption: {:?}", token_symbol, reason);
     }
 
-    if redemptions.is_empty() {
+    if plan.redemptions.is_empty() {
         return Err(IcpiError::Burn(crate::infrastructure::BurnError::NoRedemptionsPossible {
             reason: "No tokens available for redemption or all amounts below dust threshold".to_string(),
         }));
     }
 
-    ic_cdk::println!("Calculated {} token redemptions for burn", redemptions.len());
-    Ok(redemptions)
+    crate::log_op!("Calculated {} token redemptions for burn", plan.redemptions.len());
+    Ok(plan)
+}
+
+/// Proportional redemption of each balance, net of its transfer fee (pure function)
+///
+/// Every tracked token and ckUSDT is either redeemed or listed as skipped;
+/// one missing from `balances` (its balance query failed) is `Excluded`.
+pub fn plan_redemptions(
+    burn_amount: &Nat,
+    current_supply: &Nat,
+    balances: &[(String, Nat)],
+    transfer_fee: impl Fn(&str) -> u64,
+) -> RedemptionPlan {
+    let mut plan = RedemptionPlan::default();
+
+    for (token_symbol, balance) in balances {
+        if *balance == Nat::from(0u32) {
+            plan.skipped.push((token_symbol.clone(), SkipReason::ZeroBalance));
+            continue;
+        }
+
+        // Calculate: (burn_amount * balance) / current_supply
+        let redemption_amount = match calculate_proportional_share(burn_amount, balance, current_supply) {
+            Ok(amount) => amount,
+            Err(e) => {
+                plan.skipped.push((token_symbol.clone(), SkipReason::ShareFailed { error: e.to_string() }));
+                continue;
+            }
+        };
+
+        // Check if amount is above dust threshold (transfer fee + buffer)
+        match redemption_after_fee(&redemption_amount, transfer_fee(token_symbol)) {
+            Some(amount_after_fee) => plan.redemptions.push((token_symbol.clone(), amount_after_fee)),
+            None => plan.skipped.push((token_symbol.clone(), SkipReason::DustBelowFee)),
+        }
+    }
+
+    let expected = TrackedToken::all().iter().chain(std::iter::once(&TrackedToken::ckUSDT));
+    for token in expected {
+        let symbol = token.to_symbol();
+        if !balances.iter().any(|(s, _)| s == symbol) {
+            plan.skipped.push((symbol.to_string(), SkipReason::Excluded));
+        }
+    }
+    plan
+}
+
+/// Margin a redemption must clear above the transfer fee
+pub const REDEMPTION_FEE_BUFFER: u64 = 1_000;
+
+/// Smallest redemption of a token with transfer fee `fee` that is sent
+pub fn min_redemption(fee: u64) -> Nat {
+    Nat::from(fee.saturating_add(REDEMPTION_FEE_BUFFER)) + Nat::from(1u64)
+}
+
+/// Redemption sent after deducting the transfer fee (pure function)
+///
+/// `None` below `min_redemption` (dust).
+pub fn redemption_after_fee(redemption_amount: &Nat, fee: u64) -> Option<Nat> {
+    if *redemption_amount >= min_redemption(fee) {
+        Some(redemption_amount.clone() - Nat::from(fee))
+    } else {
+        None
+    }
 }
 
 /// Calculate proportional share for a single token (pure function)
@@ -86,6 +147,57 @@ mod tests {
         assert_eq!(result, Nat::from(50_000_000u64));
     }
 
+    #[test]
+    fn test_dust_threshold_follows_fee() {
+        // Default fee: threshold is 11_000
+        assert_eq!(redemption_after_fee(&Nat::from(11_000u64), 10_000), None);
+        assert_eq!(redemption_after_fee(&Nat::from(11_001u64), 10_000), Some(Nat::from(1_001u64)));
+
+        // Raised fee moves the threshold with it
+        assert_eq!(redemption_after_fee(&Nat::from(11_001u64), 20_000), None);
+        assert_eq!(redemption_after_fee(&Nat::from(50_000u64), 20_000), Some(Nat::from(30_000u64)));
+    }
+
+    #[test]
+    fn test_skipped_tokens_reported_with_reason() {
+        let balances = vec![
+            ("ALEX".to_string(), Nat::from(500_000_000u64)),
+            // The index holds no KONG
+            ("KONG".to_string(), Nat::from(0u64)),
+            // 10% of 50_000 BOB doesn't cover the fee
+            ("BOB".to_string(), Nat::from(50_000u64)),
+            ("ckUSDT".to_string(), Nat::from(10_000_000u64)),
+            // ZERO's balance query failed, so it is missing
+        ];
+        let plan = plan_redemptions(
+            &Nat::from(100_000_000u64),
+            &Nat::from(1_000_000_000u64),
+            &balances,
+            |_| 10_000,
+        );
+
+        assert_eq!(plan.redemptions, vec![
+            ("ALEX".to_string(), Nat::from(49_990_000u64)),
+            ("ckUSDT".to_string(), Nat::from(990_000u64)),
+        ]);
+        assert_eq!(plan.skipped, vec![
+            ("KONG".to_string(), SkipReason::ZeroBalance),
+            ("BOB".to_string(), SkipReason::DustBelowFee),
+            ("ZERO".to_string(), SkipReason::Excluded),
+        ]);
+
+        // A share that can't be computed is skipped with its error rather than failing the burn
+        let plan = plan_redemptions(&Nat::from(1u64), &Nat::from(0u64), &balances[..1], |_| 10_000);
+        assert!(plan.redemptions.is_empty());
+        match &plan.skipped[0] {
+            (symbol, SkipReason::ShareFailed { error }) => {
+                assert_eq!(symbol, "ALEX");
+                assert!(error.contains("proportional_share"), "error: {}", error);
+            }
+            other => panic!("expected a failed share, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_division_by_zero() {
         let burn_amount = Nat::from(100u64);
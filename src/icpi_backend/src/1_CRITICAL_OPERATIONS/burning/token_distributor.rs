@@ -1,68 +1,341 @@
 //! Token distribution after burn
 //! Handles the actual token transfers to users
 
-use candid::{Nat, Principal};
-use crate::infrastructure::{Result, IcpiError, BurnError};
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+use crate::infrastructure::{Result, IcpiError, BurnError, InstructionBudget, BURN_DISTRIBUTION_BATCH_SIZE};
 use crate::types::{TrackedToken, Account, TransferArgs, TransferResult};
+use crate::types::icrc::TransferError;
 use super::BurnResult;
+use super::redemption_calculator::SkipReason;
+
+/// What to do when the backend holds less of a token than a redemption needs
+///
+/// A rebalance sell between redemption calculation and distribution can
+/// leave the balance short.
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ShortfallPolicy {
+    /// Send what the balance covers
+    #[default]
+    Clamp,
+    /// Send what the balance covers and pay the shortfall's value in ckUSDT
+    ConsolidateToCkusdt,
+}
+
+/// A redemption reduced by the just-in-time balance check
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct RedemptionAdjustment {
+    pub token: String,
+    pub requested: Nat,
+    pub delivered: Nat,
+    /// ckUSDT paid for the shortfall under `ConsolidateToCkusdt`
+    pub ckusdt_compensation: Option<Nat>,
+}
+
+/// Transfers made, failed and not started by `transfer_within_budget`
+pub(super) struct TransferOutcome {
+    pub successful: Vec<(String, Nat)>,
+    /// Ledger block of each successful transfer, by token
+    pub blocks: Vec<(String, Nat)>,
+    pub failed: Vec<(String, Nat, String)>,
+    pub remaining: Vec<(String, Nat)>,
+    /// `created_at_time` shared by every transfer attempted
+    pub created_at_time: u64,
+    pub adjustments: Vec<RedemptionAdjustment>,
+}
+
+/// Batch clamped to the balances read just before sending it
+#[derive(Debug, PartialEq)]
+pub(super) struct BalanceCheck {
+    pub transfers: Vec<(String, Nat)>,
+    pub adjustments: Vec<RedemptionAdjustment>,
+    /// Undeliverable amount per token
+    pub shortfalls: Vec<(String, Nat)>,
+}
+
+/// Clamp each redemption to its balance net of the transfer fee (pure function)
+///
+/// A token whose balance couldn't be read (`None`) is sent unchanged; the
+/// transfer reports any real shortage itself.
+pub(super) fn clamp_to_balances(
+    batch: &[(String, Nat)],
+    balances: &[(String, Option<Nat>)],
+    transfer_fee: impl Fn(&str) -> u64,
+) -> BalanceCheck {
+    let mut check = BalanceCheck { transfers: Vec::new(), adjustments: Vec::new(), shortfalls: Vec::new() };
+
+    for (symbol, requested) in batch {
+        let balance = balances.iter().find(|(s, _)| s == symbol).and_then(|(_, b)| b.clone());
+        let available = match balance {
+            Some(balance) => {
+                let fee = Nat::from(transfer_fee(symbol));
+                if balance > fee { balance - fee } else { Nat::from(0u64) }
+            }
+            None => requested.clone(),
+        };
+
+        if available >= *requested {
+            check.transfers.push((symbol.clone(), requested.clone()));
+            continue;
+        }
+
+        if available > Nat::from(0u64) {
+            check.transfers.push((symbol.clone(), available.clone()));
+        }
+        check.shortfalls.push((symbol.clone(), requested.clone() - available.clone()));
+        check.adjustments.push(RedemptionAdjustment {
+            token: symbol.clone(),
+            requested: requested.clone(),
+            delivered: available,
+            ckusdt_compensation: None,
+        });
+    }
+    check
+}
+
+/// Add `amount` to the transfer of `symbol`, or append one
+fn add_transfer(transfers: &mut Vec<(String, Nat)>, symbol: &str, amount: Nat) {
+    match transfers.iter_mut().find(|(s, _)| s == symbol) {
+        Some((_, existing)) => *existing += amount,
+        None => transfers.push((symbol.to_string(), amount)),
+    }
+}
+
+/// Add each quoted shortfall to the ckUSDT transfer while `ckusdt_available` covers it (pure function)
+///
+/// `ckusdt_available` is the sendable ckUSDT (net of reserves and the
+/// transfer fee); what the batch already sends in ckUSDT comes first. A
+/// shortfall whose quote doesn't fit stays clamped.
+pub(super) fn apply_compensation(check: &mut BalanceCheck, quotes: &[(String, Nat)], ckusdt_available: &Nat) {
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    let sent: Nat = check.transfers.iter()
+        .filter(|(s, _)| s == ckusdt)
+        .fold(Nat::from(0u64), |total, (_, amount)| total + amount.clone());
+    let mut headroom = if *ckusdt_available > sent { ckusdt_available.clone() - sent } else { Nat::from(0u64) };
+
+    for (symbol, quote) in quotes {
+        if *quote > headroom {
+            crate::log_op!("⚠️ ckUSDT can't cover the {} shortfall ({} needed), clamping", symbol, quote);
+            continue;
+        }
+        headroom -= quote.clone();
+        if let Some(adjustment) = check.adjustments.iter_mut().find(|a| a.token == *symbol) {
+            adjustment.ckusdt_compensation = Some(quote.clone());
+        }
+        add_transfer(&mut check.transfers, ckusdt, quote.clone());
+    }
+}
+
+/// Read each batch token's balance (ckUSDT net of reserves) in parallel
+async fn batch_balances(batch: &[(String, Nat)]) -> Vec<(String, Option<Nat>)> {
+    let reads = batch.iter().map(|(symbol, _)| async move {
+        let balance = match TrackedToken::from_symbol(symbol) {
+            Ok(TrackedToken::ckUSDT) => crate::_2_CRITICAL_DATA::token_queries::get_ckusdt_balance().await,
+            Ok(token) => crate::_2_CRITICAL_DATA::token_queries::get_token_balance_uncached(&token).await,
+            Err(e) => Err(IcpiError::Other(e)),
+        };
+        (symbol.clone(), balance.ok())
+    });
+    futures::future::join_all(reads).await
+}
+
+/// Re-check balances before sending `batch`, applying the shortfall policy
+///
+/// Under `ConsolidateToCkusdt`, each shortfall is quoted in ckUSDT and the
+/// quote is added to the batch's ckUSDT transfer (one ckUSDT transfer per
+/// batch keeps ledger dedup keys distinct) as far as the ckUSDT balance net
+/// of reserves covers it; a failed or uncovered quote falls back to clamping.
+async fn check_batch_balances(batch: &[(String, Nat)], policy: ShortfallPolicy) -> BalanceCheck {
+    let balances = batch_balances(batch).await;
+    let mut check = clamp_to_balances(batch, &balances, crate::infrastructure::ledger_fees::transfer_fee);
+    if check.shortfalls.is_empty() {
+        return check;
+    }
+
+    for adjustment in &check.adjustments {
+        crate::log_op!(
+            "⚠️ Redemption of {} {} exceeds balance; sending {} ({:?})",
+            adjustment.requested, adjustment.token, adjustment.delivered, policy
+        );
+    }
+    if policy == ShortfallPolicy::Clamp {
+        return check;
+    }
+
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    let mut quotes = Vec::new();
+    for (symbol, shortfall) in check.shortfalls.clone() {
+        if symbol == ckusdt {
+            continue;
+        }
+        match crate::_4_TRADING_EXECUTION::swaps::query_swap_amounts_reply(&symbol, shortfall, ckusdt).await {
+            Ok(reply) if reply.receive_amount > Nat::from(0u64) => quotes.push((symbol, reply.receive_amount)),
+            Ok(_) => {}
+            Err(e) => crate::log_op!("⚠️ Could not quote {} shortfall in ckUSDT, clamping: {}", symbol, e),
+        }
+    }
+    if quotes.is_empty() {
+        return check;
+    }
+
+    // Compensation must not dip into reserved mint deposits either
+    let ckusdt_balance = match balances.iter().find(|(s, _)| s == ckusdt) {
+        Some((_, balance)) => balance.clone(),
+        None => crate::_2_CRITICAL_DATA::token_queries::get_ckusdt_balance().await.ok(),
+    };
+    match ckusdt_balance {
+        Some(balance) => {
+            let fee = Nat::from(crate::infrastructure::ledger_fees::ckusdt_fee());
+            let available = if balance > fee { balance - fee } else { Nat::from(0u64) };
+            apply_compensation(&mut check, &quotes, &available);
+        }
+        None => crate::log_op!("⚠️ Could not read the ckUSDT balance, clamping shortfalls"),
+    }
+    check
+}
 
 /// Distribute calculated redemption amounts to user
 /// Now with parallel execution for efficiency
+///
+/// Redemptions not started before the instruction budget runs out become
+/// claims and are listed in `pending_claims`. Failed transfers are queued
+/// for automatic retry.
 pub async fn distribute_tokens(
     recipient: Principal,
     redemptions: Vec<(String, Nat)>,
+    skipped_tokens: Vec<(String, SkipReason)>,
     icpi_burn_amount: Nat,
+    memo: Option<&str>,
 ) -> Result<BurnResult> {
+    let ledger_memo = crate::infrastructure::memo::ledger_memo("ICPI redeem", memo);
+
     let mut result = BurnResult {
         successful_transfers: Vec::new(),
         failed_transfers: Vec::new(),
         icpi_burned: icpi_burn_amount,
-        timestamp: ic_cdk::api::time(),
+        timestamp: crate::infrastructure::runtime::time(),
+        pending_claims: Vec::new(),
+        consolidated: Vec::new(),
+        skipped_tokens,
+        adjustments: Vec::new(),
     };
 
-    // Execute all transfers in parallel
-    let transfer_futures: Vec<_> = redemptions.iter()
-        .map(|(token_symbol, amount)| {
-            let symbol = token_symbol.clone();
-            let amt = amount.clone();
-            async move {
-                (symbol.clone(), amt.clone(), transfer_token(&symbol, recipient, amt).await)
-            }
-        })
-        .collect();
-
-    let transfer_results = futures::future::join_all(transfer_futures).await;
-
-    // Process results
-    for (token_symbol, amount, transfer_result) in transfer_results {
-        match transfer_result {
-            Ok(block_index) => {
-                ic_cdk::println!("✓ Transferred {} {} to {} (block: {})",
-                    amount, token_symbol, recipient, block_index);
-                result.successful_transfers.push((token_symbol, amount));
-            }
-            Err(e) => {
-                ic_cdk::println!("✗ Failed to transfer {} {}: {}",
-                    amount, token_symbol, e);
-                result.failed_transfers.push((token_symbol, amount, e.to_string()));
-            }
-        }
+    let outcome = transfer_within_budget(
+        recipient,
+        &redemptions,
+        &ledger_memo,
+        &InstructionBudget::from_config(),
+    ).await;
+    super::transfer_retries::enqueue_failed(recipient, &outcome.failed, &ledger_memo, outcome.created_at_time);
+    result.successful_transfers = outcome.successful;
+    result.failed_transfers = outcome.failed;
+    result.adjustments = outcome.adjustments;
+
+    if !outcome.remaining.is_empty() {
+        crate::log_op!(
+            "⏸ Stopping distribution early: {} redemptions stored as claims for {}",
+            outcome.remaining.len(), recipient
+        );
+        super::claims::add_claims(recipient, &outcome.remaining);
+        result.pending_claims = outcome.remaining;
     }
 
     // Check if all transfers failed
-    if result.successful_transfers.is_empty() && !result.failed_transfers.is_empty() {
+    if result.successful_transfers.is_empty()
+        && !result.failed_transfers.is_empty()
+        && result.pending_claims.is_empty()
+    {
         return Err(IcpiError::Burn(BurnError::NoRedemptionsPossible {
-            reason: "All token transfers failed".to_string(),
+            reason: "All token transfers failed; queued for automatic retry".to_string(),
         }));
     }
 
     Ok(result)
 }
 
-async fn transfer_token(
+/// Transfer redemptions in parallel batches, checking the budget between batches
+pub(super) async fn transfer_within_budget(
+    recipient: Principal,
+    redemptions: &[(String, Nat)],
+    ledger_memo: &str,
+    budget: &InstructionBudget,
+) -> TransferOutcome {
+    let mut outcome = TransferOutcome {
+        successful: Vec::new(),
+        blocks: Vec::new(),
+        failed: Vec::new(),
+        remaining: Vec::new(),
+        created_at_time: crate::infrastructure::runtime::time(),
+        adjustments: Vec::new(),
+    };
+
+    // Resends with the same memo and time are deduplicated by the ledger
+    let created_at_time = Some(outcome.created_at_time);
+    let policy = crate::infrastructure::config::get_config().redemption_shortfall_policy();
+
+    for (batch_index, batch) in redemptions.chunks(BURN_DISTRIBUTION_BATCH_SIZE).enumerate() {
+        if budget.exhausted() {
+            outcome.remaining = redemptions[batch_index * BURN_DISTRIBUTION_BATCH_SIZE..].to_vec();
+            break;
+        }
+
+        // A rebalance may have sold some of a token since redemptions were calculated
+        let check = check_batch_balances(batch, policy).await;
+        outcome.adjustments.extend(check.adjustments);
+
+        let transfer_futures: Vec<_> = check.transfers.iter()
+            .map(|(token_symbol, amount)| {
+                let symbol = token_symbol.clone();
+                let amt = amount.clone();
+                async move {
+                    (symbol.clone(), amt.clone(), transfer_token(&symbol, recipient, amt, ledger_memo, created_at_time).await)
+                }
+            })
+            .collect();
+
+        let transfer_results = futures::future::join_all(transfer_futures).await;
+
+        // Process results
+        for (token_symbol, amount, transfer_result) in transfer_results {
+            match transfer_result {
+                Ok(block_index) => {
+                    crate::log_op!("✓ Transferred {} {} to {} (block: {})",
+                        amount, token_symbol, recipient, block_index);
+                    record_delivery(&token_symbol, &amount);
+                    outcome.blocks.push((token_symbol.clone(), block_index));
+                    outcome.successful.push((token_symbol, amount));
+                }
+                Err(e) => {
+                    crate::log_op!("✗ Failed to transfer {} {}: {}",
+                        amount, token_symbol, e);
+                    outcome.failed.push((token_symbol, amount, e.to_string()));
+                }
+            }
+        }
+    }
+
+    outcome
+}
+
+/// Account for a delivered redemption in the ckUSDT reserves
+pub(super) fn record_delivery(token_symbol: &str, amount: &Nat) {
+    if token_symbol == TrackedToken::ckUSDT.to_symbol() {
+        crate::_2_CRITICAL_DATA::ckusdt_reserves::record_outflow(
+            &(amount.clone() + Nat::from(crate::infrastructure::ledger_fees::ckusdt_fee()))
+        );
+    }
+}
+
+/// Send one redemption transfer
+///
+/// With `created_at_time` set the ledger deduplicates resends of the same
+/// transfer; a duplicate is reported as success with the original block.
+pub(super) async fn transfer_token(
     token_symbol: &str,
     recipient: Principal,
     amount: Nat,
+    memo: &str,
+    created_at_time: Option<u64>,
 ) -> Result<Nat> {
     let token_canister = get_token_canister(token_symbol)?;
 
@@ -75,12 +348,12 @@ async fn transfer_token(
         },
         amount,
         fee: None,
-        memo: Some(b"ICPI burn redemption".to_vec()),
+        memo: Some(memo.as_bytes().to_vec()),
         from_subaccount: None,
-        created_at_time: None,
+        created_at_time,
     };
 
-    let result: std::result::Result<(TransferResult,), _> = ic_cdk::call(
+    let result: std::result::Result<(TransferResult,), _> = crate::infrastructure::runtime::call(
         token_canister,
         "icrc1_transfer",
         (transfer_args,)
@@ -88,6 +361,7 @@ async fn transfer_token(
 
     match result {
         Ok((TransferResult::Ok(block),)) => Ok(block),
+        Ok((TransferResult::Err(TransferError::Duplicate { duplicate_of }),)) => Ok(duplicate_of),
         Ok((TransferResult::Err(e),)) => {
             Err(IcpiError::Burn(BurnError::TokenTransferFailed {
                 token: token_symbol.to_string(),
@@ -123,3 +397,72 @@ fn get_token_canister(symbol: &str) -> Result<Principal> {
         }))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(symbol: &str, amount: u64) -> (String, Nat) {
+        (symbol.to_string(), Nat::from(amount))
+    }
+
+    #[test]
+    fn test_insufficient_balance_clamped() {
+        let batch = vec![entry("ALEX", 1_000_000), entry("KONG", 500_000), entry("BOB", 300_000)];
+        let balances = vec![
+            // A rebalance sold ALEX since the redemption was calculated
+            ("ALEX".to_string(), Some(Nat::from(610_000u64))),
+            ("KONG".to_string(), Some(Nat::from(5_000_000u64))),
+            // Unreadable balance: sent as calculated
+            ("BOB".to_string(), None),
+        ];
+        let check = clamp_to_balances(&batch, &balances, |_| 10_000);
+
+        assert_eq!(check.transfers, vec![entry("ALEX", 600_000), entry("KONG", 500_000), entry("BOB", 300_000)]);
+        assert_eq!(check.shortfalls, vec![entry("ALEX", 400_000)]);
+        assert_eq!(check.adjustments, vec![RedemptionAdjustment {
+            token: "ALEX".to_string(),
+            requested: Nat::from(1_000_000u64),
+            delivered: Nat::from(600_000u64),
+            ckusdt_compensation: None,
+        }]);
+
+        // Nothing left after the fee: the transfer is dropped entirely
+        let check = clamp_to_balances(&batch[..1], &[("ALEX".to_string(), Some(Nat::from(10_000u64)))], |_| 10_000);
+        assert!(check.transfers.is_empty());
+        assert_eq!(check.adjustments[0].delivered, Nat::from(0u64));
+    }
+
+    #[test]
+    fn test_compensation_merged_into_ckusdt_transfer() {
+        let mut transfers = vec![entry("ALEX", 600_000), entry("ckUSDT", 2_000_000)];
+        add_transfer(&mut transfers, "ckUSDT", Nat::from(150_000u64));
+        assert_eq!(transfers, vec![entry("ALEX", 600_000), entry("ckUSDT", 2_150_000)]);
+
+        let mut alone = vec![entry("ALEX", 600_000)];
+        add_transfer(&mut alone, "ckUSDT", Nat::from(150_000u64));
+        assert_eq!(alone, vec![entry("ALEX", 600_000), entry("ckUSDT", 150_000)]);
+    }
+
+    #[test]
+    fn test_compensation_limited_to_ckusdt_balance() {
+        let shortfall = |token: &str| RedemptionAdjustment {
+            token: token.to_string(),
+            requested: Nat::from(1_000_000u64),
+            delivered: Nat::from(0u64),
+            ckusdt_compensation: None,
+        };
+        let mut check = BalanceCheck {
+            transfers: vec![entry("ckUSDT", 2_000_000)],
+            adjustments: vec![shortfall("ALEX"), shortfall("KONG")],
+            shortfalls: vec![entry("ALEX", 1_000_000), entry("KONG", 1_000_000)],
+        };
+        let quotes = vec![entry("ALEX", 300_000), entry("KONG", 400_000)];
+
+        // 2.5 ckUSDT sendable: the batch's own 2.0 plus ALEX's 0.3 fit, KONG's 0.4 doesn't
+        apply_compensation(&mut check, &quotes, &Nat::from(2_500_000u64));
+
+        assert_eq!(check.transfers, vec![entry("ckUSDT", 2_300_000)]);
+        assert_eq!(check.adjustments[0].ckusdt_compensation, Some(Nat::from(300_000u64)));
+        assert_eq!(check.adjustments[1].ckusdt_compensation, None);
+    }
+}
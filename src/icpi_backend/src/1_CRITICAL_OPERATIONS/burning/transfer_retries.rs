@@ -0,0 +1,359 @@
+//! Automatic retries for redemption transfers that failed during a burn
+//!
+//! Failed transfers are queued with the memo and `created_at_time` of the
+//! original attempt, so a retry of a transfer the ledger actually executed
+//! is rejected as a duplicate instead of paying twice. A timer retries due
+//! entries with exponential backoff; after `BURN_RETRY_MAX_ATTEMPTS` failed
+//! retries the entry becomes a redemption claim and a permanent failure
+//! event is recorded. Queued amounts are still owed, so they are held out
+//! of TVL and the rebalancer's balances like claims.
+//!
+//! Each run attempts at most `BURN_RETRY_BATCH_SIZE` entries; the rest
+//! wait for the next run. The count is what bounds a run: every retry
+//! awaits a ledger, and the instruction counter restarts after each await.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use std::cell::RefCell;
+use std::time::Duration;
+
+use super::{claims, token_distributor};
+use crate::infrastructure::{
+    BURN_RETRY_BASE_DELAY_SECONDS, BURN_RETRY_BATCH_SIZE, BURN_RETRY_INTERVAL_SECONDS, BURN_RETRY_MAX_ATTEMPTS,
+    MAX_BURN_RETRY_EVENTS,
+};
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// A failed redemption transfer awaiting retry
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransferRetry {
+    pub id: u64,
+    pub user: Principal,
+    pub token: String,
+    pub amount: Nat,
+    /// Ledger memo of the original transfer
+    pub memo: String,
+    /// `created_at_time` of the original transfer; with the memo, the idempotency key
+    pub created_at_time: u64,
+    /// Retries made so far (the original transfer isn't counted)
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: String,
+    pub request_id: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum RetryEvent {
+    /// Retries exhausted; the amount was moved into the user's claims
+    PermanentFailure {
+        user: Principal,
+        token: String,
+        amount: Nat,
+        attempts: u32,
+        last_error: String,
+        timestamp: u64,
+    },
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RetryQueueState {
+    pub next_id: u64,
+    pub entries: Vec<TransferRetry>,
+    /// Most recent events, oldest first
+    pub events: Vec<RetryEvent>,
+}
+
+thread_local! {
+    static QUEUE: RefCell<RetryQueueState> = RefCell::new(RetryQueueState::default());
+    static RETRY_IN_PROGRESS: RefCell<bool> = RefCell::new(false);
+}
+
+/// Delay before retry number `attempts + 1`: base, 2x base, 4x base...
+pub fn backoff_seconds(attempts: u32) -> u64 {
+    BURN_RETRY_BASE_DELAY_SECONDS.saturating_mul(1u64 << attempts.min(32))
+}
+
+fn push_event(state: &mut RetryQueueState, event: RetryEvent) {
+    state.events.push(event);
+    if state.events.len() > MAX_BURN_RETRY_EVENTS {
+        let excess = state.events.len() - MAX_BURN_RETRY_EVENTS;
+        state.events.drain(..excess);
+    }
+}
+
+/// Queue failed transfers from one distribution
+pub fn enqueue(
+    state: &mut RetryQueueState,
+    user: Principal,
+    failed: &[(String, Nat, String)],
+    memo: &str,
+    created_at_time: u64,
+    request_id: Option<String>,
+    now: u64,
+) {
+    for (token, amount, error) in failed {
+        state.entries.push(TransferRetry {
+            id: state.next_id,
+            user,
+            token: token.clone(),
+            amount: amount.clone(),
+            memo: memo.to_string(),
+            created_at_time,
+            attempts: 0,
+            next_attempt_at: now + backoff_seconds(0) * NANOS_PER_SECOND,
+            last_error: error.clone(),
+            request_id: request_id.clone(),
+        });
+        state.next_id += 1;
+    }
+}
+
+/// Up to `limit` entries whose next attempt is due at `now`, in queue order
+pub fn due_entries(state: &RetryQueueState, now: u64, limit: usize) -> Vec<TransferRetry> {
+    state.entries.iter().filter(|e| e.next_attempt_at <= now).take(limit).cloned().collect()
+}
+
+/// Total queued per token, across all users
+pub fn owed_by_token() -> Vec<(String, Nat)> {
+    QUEUE.with(|q| super::sum_by_token(q.borrow().entries.iter().map(|e| (&e.token, &e.amount))))
+}
+
+/// Drop an entry whose retry succeeded
+pub fn record_success(state: &mut RetryQueueState, id: u64) {
+    state.entries.retain(|e| e.id != id);
+}
+
+/// Count a failed retry, rescheduling it with backoff
+///
+/// Returns the entry, removed from the queue, once its retries are exhausted.
+pub fn record_failure(state: &mut RetryQueueState, id: u64, error: String, now: u64) -> Option<TransferRetry> {
+    let index = state.entries.iter().position(|e| e.id == id)?;
+    let entry = &mut state.entries[index];
+    entry.attempts += 1;
+    entry.last_error = error;
+
+    if entry.attempts < BURN_RETRY_MAX_ATTEMPTS {
+        entry.next_attempt_at = now + backoff_seconds(entry.attempts) * NANOS_PER_SECOND;
+        return None;
+    }
+
+    let entry = state.entries.remove(index);
+    push_event(state, RetryEvent::PermanentFailure {
+        user: entry.user,
+        token: entry.token.clone(),
+        amount: entry.amount.clone(),
+        attempts: entry.attempts,
+        last_error: entry.last_error.clone(),
+        timestamp: now,
+    });
+    Some(entry)
+}
+
+/// Count a failed retry; exhausted entries become claims
+fn fail_attempt(id: u64, error: String, now: u64) {
+    let exhausted = QUEUE.with(|q| record_failure(&mut q.borrow_mut(), id, error, now));
+    if let Some(entry) = exhausted {
+        ic_cdk::println!(
+            "🚨 Redemption transfer of {} {} to {} failed {} retries, moved to claims: {}",
+            entry.amount, entry.token, entry.user, entry.attempts, entry.last_error
+        );
+        claims::push_claims(
+            entry.user,
+            &[(entry.token, entry.amount)],
+            now,
+            entry.request_id,
+        );
+    }
+}
+
+/// Queue transfers that failed during a burn or exit distribution
+pub fn enqueue_failed(user: Principal, failed: &[(String, Nat, String)], memo: &str, created_at_time: u64) {
+    if failed.is_empty() {
+        return;
+    }
+    let request_id = crate::infrastructure::logging::current_request_id();
+    let now = crate::infrastructure::runtime::time();
+    QUEUE.with(|q| enqueue(&mut q.borrow_mut(), user, failed, memo, created_at_time, request_id, now));
+    crate::log_op!("🔁 Queued {} failed redemption transfers for {} for retry", failed.len(), user);
+}
+
+pub fn start_retry_timer() {
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::BurnTransferRetry,
+        Duration::from_secs(BURN_RETRY_INTERVAL_SECONDS),
+        false,
+        || ic_cdk::spawn(retry_due_transfers()),
+    );
+}
+
+/// Retry up to `BURN_RETRY_BATCH_SIZE` due entries once
+///
+/// Skipped while paused or while a previous run is still awaiting ledgers.
+/// Entries of a user with a burn or claim in progress wait for the next run.
+async fn retry_due_transfers() {
+    if crate::infrastructure::check_not_paused().is_err() {
+        return;
+    }
+    let Some(_run_guard) = crate::infrastructure::reentrancy::RunFlagGuard::try_acquire(&RETRY_IN_PROGRESS) else {
+        return;
+    };
+
+    let now = crate::infrastructure::runtime::time();
+    let due = QUEUE.with(|q| due_entries(&q.borrow(), now, BURN_RETRY_BATCH_SIZE));
+    for entry in due {
+        let _guard = match crate::infrastructure::BurnGuard::acquire(entry.user) {
+            Ok(guard) => guard,
+            Err(_) => continue,
+        };
+
+        let result = token_distributor::transfer_token(
+            &entry.token,
+            entry.user,
+            entry.amount.clone(),
+            &entry.memo,
+            Some(entry.created_at_time),
+        ).await;
+
+        match result {
+            Ok(block_index) => {
+                token_distributor::record_delivery(&entry.token, &entry.amount);
+                QUEUE.with(|q| record_success(&mut q.borrow_mut(), entry.id));
+                ic_cdk::println!(
+                    "✓ Retried transfer of {} {} to {} (block: {})",
+                    entry.amount, entry.token, entry.user, block_index
+                );
+            }
+            Err(e) => fail_attempt(entry.id, e.to_string(), crate::infrastructure::runtime::time()),
+        }
+    }
+}
+
+/// Queued retries and recent events (admin)
+pub fn get_queue() -> RetryQueueState {
+    QUEUE.with(|q| q.borrow().clone())
+}
+
+/// Export the queue for stable storage
+pub fn export_state() -> RetryQueueState {
+    get_queue()
+}
+
+/// Import the queue from stable storage
+pub fn import_state(state: RetryQueueState) {
+    QUEUE.with(|q| *q.borrow_mut() = state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = NANOS_PER_SECOND;
+
+    fn user() -> Principal {
+        Principal::from_text("2vxsx-fae").unwrap()
+    }
+
+    fn queued(now: u64) -> RetryQueueState {
+        let mut state = RetryQueueState::default();
+        enqueue(
+            &mut state,
+            user(),
+            &[("ALEX".to_string(), Nat::from(500u64), "TemporarilyUnavailable".to_string())],
+            "ICPI redeem",
+            now,
+            Some("burn-0000beef".to_string()),
+            now,
+        );
+        state
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        assert_eq!(backoff_seconds(0), BURN_RETRY_BASE_DELAY_SECONDS);
+        assert_eq!(backoff_seconds(1), BURN_RETRY_BASE_DELAY_SECONDS * 2);
+        assert_eq!(backoff_seconds(3), BURN_RETRY_BASE_DELAY_SECONDS * 8);
+        assert_eq!(backoff_seconds(u32::MAX), BURN_RETRY_BASE_DELAY_SECONDS.saturating_mul(1 << 32));
+    }
+
+    #[test]
+    fn test_retries_scheduled_with_backoff() {
+        let start = 1_000 * SECOND;
+        let mut state = queued(start);
+        let first = start + BURN_RETRY_BASE_DELAY_SECONDS * SECOND;
+
+        assert!(due_entries(&state, first - 1, BURN_RETRY_BATCH_SIZE).is_empty());
+        assert_eq!(due_entries(&state, first, BURN_RETRY_BATCH_SIZE).len(), 1);
+
+        assert!(record_failure(&mut state, 0, "again".to_string(), first).is_none());
+        let entry = &state.entries[0];
+        assert_eq!(entry.attempts, 1);
+        assert_eq!(entry.next_attempt_at, first + 2 * BURN_RETRY_BASE_DELAY_SECONDS * SECOND);
+        assert_eq!(entry.last_error, "again");
+        // The idempotency key never changes between attempts
+        assert_eq!((entry.created_at_time, entry.memo.as_str()), (start, "ICPI redeem"));
+
+        record_success(&mut state, 0);
+        assert!(state.entries.is_empty());
+        assert!(state.events.is_empty());
+    }
+
+    #[test]
+    fn test_due_entries_bounded_by_count() {
+        let now = 1_000 * SECOND;
+        let mut state = RetryQueueState::default();
+        let failed: Vec<(String, Nat, String)> = (0..5)
+            .map(|i| ("ALEX".to_string(), Nat::from(100u64 + i), "TemporarilyUnavailable".to_string()))
+            .collect();
+        enqueue(&mut state, user(), &failed, "ICPI redeem", now, None, now);
+
+        let due_at = now + BURN_RETRY_BASE_DELAY_SECONDS * SECOND;
+        let due = due_entries(&state, due_at, 3);
+        assert_eq!(due.iter().map(|e| e.id).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_queued_amounts_are_owed() {
+        let now = 1_000 * SECOND;
+        let mut state = queued(now);
+        enqueue(
+            &mut state,
+            user(),
+            &[("ALEX".to_string(), Nat::from(250u64), "TemporarilyUnavailable".to_string())],
+            "ICPI redeem",
+            now,
+            None,
+            now,
+        );
+        import_state(state);
+
+        assert_eq!(owed_by_token(), vec![("ALEX".to_string(), Nat::from(750u64))]);
+    }
+
+    #[test]
+    fn test_exhausted_retry_becomes_claim() {
+        let mut now = 1_000 * SECOND;
+        import_state(queued(now));
+        claims::import_claims(Vec::new());
+
+        for attempt in 1..=BURN_RETRY_MAX_ATTEMPTS {
+            now = get_queue().entries[0].next_attempt_at;
+            fail_attempt(0, format!("failure {}", attempt), now);
+        }
+
+        let queue = get_queue();
+        assert!(queue.entries.is_empty());
+        assert_eq!(queue.events, vec![RetryEvent::PermanentFailure {
+            user: user(),
+            token: "ALEX".to_string(),
+            amount: Nat::from(500u64),
+            attempts: BURN_RETRY_MAX_ATTEMPTS,
+            last_error: format!("failure {}", BURN_RETRY_MAX_ATTEMPTS),
+            timestamp: now,
+        }]);
+
+        let owed = claims::get_user_claims(&user());
+        assert_eq!(owed.len(), 1);
+        assert_eq!((owed[0].token.as_str(), owed[0].amount.clone()), ("ALEX", Nat::from(500u64)));
+        assert_eq!(owed[0].request_id.as_deref(), Some("burn-0000beef"));
+    }
+}
@@ -1,56 +1,28 @@
 //! Fee handling for mint operations
 
 use candid::{Nat, Principal};
-use crate::infrastructure::{Result, IcpiError, MintError};
-use crate::infrastructure::constants::{MINT_FEE_AMOUNT, CKUSDT_CANISTER_ID};
-use crate::types::{Account, TransferArgs};
+use crate::infrastructure::{Result, IcpiError, MintError, ckusdt_ledger};
+use crate::infrastructure::constants::MINT_FEE_AMOUNT;
 
 /// Collect minting fee from user
 pub async fn collect_mint_fee(user: Principal) -> Result<Nat> {
     let fee_amount = Nat::from(MINT_FEE_AMOUNT);
 
-    ic_cdk::println!("Collecting mint fee of {} from {}", fee_amount, user);
-
-    let ckusdt = Principal::from_text(CKUSDT_CANISTER_ID)
-        .map_err(|e| IcpiError::Mint(MintError::FeeCollectionFailed {
-            user: user.to_text(),
-            reason: format!("Invalid ckUSDT principal: {}", e),
-        }))?;
+    crate::log_op!("Collecting mint fee of {} from {}", fee_amount, user);
 
     // ICRC-2 transfer_from requires approval first
     // User must have called icrc2_approve before this
-    use crate::types::icrc::{TransferFromArgs, TransferFromError};
-
-    let args = TransferFromArgs {
-        from: Account { owner: user, subaccount: None },
-        to: Account { owner: ic_cdk::id(), subaccount: None },
-        amount: fee_amount.clone(),
-        fee: None,
-        memo: Some(b"ICPI mint fee".to_vec()),
-        created_at_time: Some(ic_cdk::api::time()),
-    };
-
-    let result: std::result::Result<(std::result::Result<Nat, TransferFromError>,), _> = ic_cdk::call(
-        ckusdt,
-        "icrc2_transfer_from",
-        (args,)
-    ).await;
-
-    match result {
-        Ok((Ok(block_index),)) => {
-            ic_cdk::println!("✅ Fee collected: block {}", block_index);
+    match ckusdt_ledger::transfer_from(user, fee_amount.clone(), b"ICPI mint fee").await {
+        Ok(block_index) => {
+            crate::log_op!("✅ Fee collected: block {}", block_index);
+            crate::_2_CRITICAL_DATA::ckusdt_reserves::record_inflow(&fee_amount);
+            crate::_2_CRITICAL_DATA::ckusdt_reserves::record_fee_collected(&fee_amount);
             Ok(fee_amount)
         }
-        Ok((Err(e),)) => {
+        Err(e) => {
             Err(IcpiError::Mint(MintError::FeeCollectionFailed {
                 user: user.to_text(),
-                reason: format!("ICRC-2 error: {:?}", e),
-            }))
-        }
-        Err((code, msg)) => {
-            Err(IcpiError::Mint(MintError::FeeCollectionFailed {
-                user: user.to_text(),
-                reason: format!("Call failed: {:?} - {}", code, msg),
+                reason: e.to_string(),
             }))
         }
     }
@@ -62,50 +34,20 @@ pub async fn collect_deposit(
     amount: Nat,
     memo: String,
 ) -> Result<Nat> {
-    ic_cdk::println!("Collecting deposit of {} from {} (memo: {})", amount, user, memo);
-
-    let ckusdt = Principal::from_text(CKUSDT_CANISTER_ID)
-        .map_err(|e| IcpiError::Mint(MintError::DepositCollectionFailed {
-            user: user.to_text(),
-            amount: amount.to_string(),
-            reason: format!("Invalid ckUSDT principal: {}", e),
-        }))?;
+    crate::log_op!("Collecting deposit of {} from {} (memo: {})", amount, user, memo);
 
-    use crate::types::icrc::{TransferFromArgs, TransferFromError};
-
-    let args = TransferFromArgs {
-        from: Account { owner: user, subaccount: None },
-        to: Account { owner: ic_cdk::id(), subaccount: None },
-        amount: amount.clone(),
-        fee: None,
-        memo: Some(memo.into_bytes()),
-        created_at_time: Some(ic_cdk::api::time()),
-    };
-
-    let result: std::result::Result<(std::result::Result<Nat, TransferFromError>,), _> = ic_cdk::call(
-        ckusdt,
-        "icrc2_transfer_from",
-        (args,)
-    ).await;
-
-    match result {
-        Ok((Ok(block_index),)) => {
-            ic_cdk::println!("✅ Deposit collected: block {}", block_index);
+    match ckusdt_ledger::transfer_from(user, amount.clone(), memo.as_bytes()).await {
+        Ok(block_index) => {
+            crate::log_op!("✅ Deposit collected: block {}", block_index);
+            crate::_2_CRITICAL_DATA::ckusdt_reserves::record_inflow(&amount);
             Ok(amount)
         }
-        Ok((Err(e),)) => {
+        Err(e) => {
             Err(IcpiError::Mint(MintError::DepositCollectionFailed {
                 user: user.to_text(),
                 amount: amount.to_string(),
-                reason: format!("ICRC-2 error: {:?}", e),
-            }))
-        }
-        Err((code, msg)) => {
-            Err(IcpiError::Mint(MintError::DepositCollectionFailed {
-                user: user.to_text(),
-                amount: amount.to_string(),
-                reason: format!("Call failed: {:?} - {}", code, msg),
+                reason: e.to_string(),
             }))
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,166 @@
+//! Mint impact simulation for prospective depositors
+//!
+//! Read-only estimate of how a deposit would move the index. Supply and TVL
+//! come from the same atomic snapshot a mint takes; positions and targets
+//! come from the cached index state. The figures are estimates valid at
+//! `snapshot_at`: prices, supply and targets move, and the rebalance
+//! projection assumes full fills at current prices.
+
+use candid::{CandidType, Deserialize, Nat};
+
+use crate::_1_CRITICAL_OPERATIONS::rebalancing::RebalanceAction;
+use crate::_1_CRITICAL_OPERATIONS::rebalancing::simulation::{self, SimulatedPortfolio, MAX_SIMULATED_CYCLES};
+use crate::infrastructure::{IcpiError, Result, ValidationError};
+use crate::types::portfolio::IndexState;
+
+/// Estimated effect of minting with `amount` ckUSDT
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MintImpact {
+    /// ckUSDT deposit (e6)
+    pub amount: Nat,
+    /// When the supply/TVL snapshot was taken (ns)
+    pub snapshot_at: u64,
+    pub icpi_minted: Nat,
+    pub supply_before: Nat,
+    pub supply_after: Nat,
+    pub tvl_before_e6: Nat,
+    pub tvl_after_e6: Nat,
+    pub nav_before_e8: u64,
+    pub nav_after_e8: u64,
+    /// Depositor's share of post-mint supply
+    pub depositor_share_bps: u64,
+    pub ckusdt_reserve_pct_before: f64,
+    pub ckusdt_reserve_pct_after: f64,
+    /// Rebalance actions projected after the mint, one per cycle
+    pub projected_actions: Vec<RebalanceAction>,
+    /// Buys beyond what the rebalancer would make without the deposit
+    pub extra_buy_usd: f64,
+}
+
+/// Simulate a mint of `amount` ckUSDT against the current snapshot
+pub async fn simulate_mint_impact(amount: Nat) -> Result<MintImpact> {
+    let (supply, tvl) = crate::_2_CRITICAL_DATA::get_supply_and_tvl_atomic().await?;
+    let snapshot_at = crate::infrastructure::runtime::time();
+    let state = crate::_5_INFORMATIONAL::display::get_index_state_uncached().await?;
+    mint_impact(amount, supply, tvl, &state, snapshot_at)
+}
+
+/// Mint impact from a supply/TVL snapshot and index state
+pub fn mint_impact(amount: Nat, supply: Nat, tvl: Nat, state: &IndexState, snapshot_at: u64) -> Result<MintImpact> {
+    if amount == Nat::from(0u64) {
+        return Err(IcpiError::Validation(ValidationError::InvalidAmount {
+            amount: amount.to_string(),
+            reason: "Deposit must be positive".to_string(),
+        }));
+    }
+
+    let icpi_minted = crate::infrastructure::math::calculate_mint_amount(&amount, &supply, &tvl)?;
+    let supply_after = supply.clone() + icpi_minted.clone();
+    let tvl_after = tvl.clone() + amount.clone();
+
+    let to_u64 = |n: &Nat, what: &str| num_traits::ToPrimitive::to_u64(&n.0).ok_or_else(|| {
+        IcpiError::Calculation(crate::infrastructure::CalculationError::Overflow {
+            operation: format!("{} {}", what, n),
+        })
+    });
+    let nav_before_e8 = crate::_5_INFORMATIONAL::history::calculate_nav_e8(to_u64(&tvl, "tvl")?, &supply)?;
+    let nav_after_e8 = crate::_5_INFORMATIONAL::history::calculate_nav_e8(to_u64(&tvl_after, "tvl")?, &supply_after)?;
+    let depositor_share_bps = to_u64(
+        &crate::infrastructure::multiply_and_divide(&icpi_minted, &Nat::from(10_000u64), &supply_after)?,
+        "share",
+    )?;
+
+    let deposit_usd = num_traits::ToPrimitive::to_f64(&amount.0).unwrap_or(0.0) / 1_000_000.0;
+    let mut baseline = SimulatedPortfolio::from_state(state);
+    let mut after = baseline.clone();
+    after.ckusdt_usd += deposit_usd;
+    let ckusdt_reserve_pct_before = baseline.ckusdt_pct();
+    let ckusdt_reserve_pct_after = after.ckusdt_pct();
+
+    let baseline_actions = simulation::simulate_cycles(&mut baseline, MAX_SIMULATED_CYCLES)?;
+    let projected_actions = simulation::simulate_cycles(&mut after, MAX_SIMULATED_CYCLES)?;
+    let extra_buy_usd = (simulation::total_bought_usd(&projected_actions)
+        - simulation::total_bought_usd(&baseline_actions)).max(0.0);
+
+    Ok(MintImpact {
+        amount,
+        snapshot_at,
+        icpi_minted,
+        supply_before: supply,
+        supply_after,
+        tvl_before_e6: tvl,
+        tvl_after_e6: tvl_after,
+        nav_before_e8,
+        nav_after_e8,
+        depositor_share_bps,
+        ckusdt_reserve_pct_before,
+        ckusdt_reserve_pct_after,
+        projected_actions,
+        extra_buy_usd,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TrackedToken;
+    use crate::types::portfolio::CurrentPosition;
+    use crate::types::rebalancing::TargetAllocation;
+
+    /// $1,000 evenly held across the four tokens at their targets
+    fn balanced_state() -> IndexState {
+        IndexState {
+            total_value: 1_000.0,
+            current_positions: TrackedToken::all().iter().map(|t| CurrentPosition {
+                token: t.clone(),
+                balance: Nat::from(1u64),
+                usd_value: 250.0,
+                percentage: 25.0,
+                pool_unavailable_since: None,
+            }).collect(),
+            target_allocations: TrackedToken::all().iter().map(|t| TargetAllocation {
+                token: t.clone(),
+                target_percentage: 25.0,
+                target_usd_value: 250.0,
+                weight_cap: None,
+            }).collect(),
+            deviations: Vec::new(),
+            timestamp: 0,
+            ckusdt_balance: Nat::from(0u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        }
+    }
+
+    #[test]
+    fn test_mint_impact_for_large_deposit() {
+        // 1,000 ICPI outstanding against $1,000: NAV $1
+        let supply = Nat::from(1_000u64 * 100_000_000);
+        let tvl = Nat::from(1_000u64 * 1_000_000);
+        let deposit = Nat::from(1_000u64 * 1_000_000);
+
+        let impact = mint_impact(deposit, supply, tvl, &balanced_state(), 42).unwrap();
+
+        assert_eq!(impact.snapshot_at, 42);
+        assert_eq!(impact.icpi_minted, Nat::from(1_000u64 * 100_000_000));
+        assert_eq!(impact.supply_after, Nat::from(2_000u64 * 100_000_000));
+        assert_eq!(impact.tvl_after_e6, Nat::from(2_000u64 * 1_000_000));
+        // A mint at NAV leaves NAV unchanged
+        assert_eq!(impact.nav_before_e8, 100_000_000);
+        assert_eq!(impact.nav_after_e8, 100_000_000);
+        assert_eq!(impact.depositor_share_bps, 5_000);
+        assert_eq!(impact.ckusdt_reserve_pct_before, 0.0);
+        assert_eq!(impact.ckusdt_reserve_pct_after, 50.0);
+
+        // A balanced index only buys because of the deposit
+        assert!(!impact.projected_actions.is_empty());
+        assert!(impact.projected_actions.iter().all(|a| matches!(a, RebalanceAction::Buy { .. })));
+        assert!(impact.extra_buy_usd > 900.0 && impact.extra_buy_usd <= 1_000.0);
+    }
+
+    #[test]
+    fn test_mint_impact_rejects_zero_deposit() {
+        let result = mint_impact(Nat::from(0u64), Nat::from(1u64), Nat::from(1u64), &balanced_state(), 0);
+        assert!(result.is_err());
+    }
+}
@@ -2,20 +2,37 @@
 
 use candid::{Nat, Principal};
 use crate::infrastructure::{Result, IcpiError, MintError};
-use crate::infrastructure::constants::ICPI_CANISTER_ID;
-use super::mint_state::{MintStatus, PendingMint, MintSnapshot, store_pending_mint, get_pending_mint, update_mint_status};
-use super::mint_validator::validate_mint_request;
+use super::mint_state::{MintStatus, PendingMint, MintSnapshot, store_pending_mint, get_pending_mint, update_mint_status, get_pending_count, check_pending_capacity, begin_ledger_mint};
+use super::mint_validator::{validate_mint_request, validate_supply_unchanged, validate_mint_credited};
 use super::fee_handler::{collect_mint_fee, collect_deposit};
 use super::refund_handler::refund_deposit;
+use crate::infrastructure::icpi_ledger::MintAuthorization;
+
+/// Base of the ICPI ledger mint memo, followed by the mint id's hash tag
+pub const MINT_LEDGER_MEMO_BASE: &str = "ICPI mint id";
+
+/// Memo of the ICPI ledger mint for `mint_id`
+///
+/// Recovery matches an interrupted mint to its ledger block by this memo.
+pub fn mint_ledger_memo(mint_id: &str) -> String {
+    crate::infrastructure::memo::ledger_memo(MINT_LEDGER_MEMO_BASE, Some(mint_id))
+}
 
 /// Initiate a new mint request
-pub async fn initiate_mint(caller: Principal, amount: Nat) -> Result<String> {
+///
+/// `memo` is an optional caller tag (max 64 bytes) kept with the mint.
+pub async fn initiate_mint(caller: Principal, amount: Nat, memo: Option<String>) -> Result<String> {
     // Validate request
     validate_mint_request(&caller, &amount)?;
+    let memo = crate::infrastructure::memo::validate_memo(memo)?;
+    check_pending_capacity(
+        get_pending_count() as u64,
+        crate::infrastructure::config::get_config().max_pending_mints(),
+    )?;
 
     // Generate unique mint ID
-    let mint_id = format!("mint_{}_{}", caller.to_text(), ic_cdk::api::time());
-    let now = ic_cdk::api::time();
+    let mint_id = format!("mint_{}_{}", caller.to_text(), crate::infrastructure::runtime::time());
+    let now = crate::infrastructure::runtime::time();
 
     // Create pending mint
     let pending_mint = PendingMint {
@@ -26,24 +43,58 @@ pub async fn initiate_mint(caller: Principal, amount: Nat) -> Result<String> {
         created_at: now,
         last_updated: now,
         snapshot: None,
+        memo,
+        request_id: crate::infrastructure::logging::current_request_id(),
     };
 
+    let memo_tag = pending_mint.memo.as_deref().map(crate::infrastructure::memo::ledger_tag);
+
     // Store pending mint
     store_pending_mint(pending_mint)?;
 
-    ic_cdk::println!("Mint initiated: {} for user {} amount {}", mint_id, caller, amount);
+    crate::log_op!("Mint initiated: {} for user {} amount {}", mint_id, caller, amount);
+    if let Some(tag) = memo_tag {
+        crate::log_op!("Mint {} ledger memo tag {}", mint_id, tag);
+    }
 
     Ok(mint_id)
 }
 
 /// Complete a pending mint request
+///
+/// A mint that ends in a failed status is recorded as the user's last mint failure.
 pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
+    let result = complete_mint_steps(caller, mint_id.clone()).await;
+    if let Err(e) = &result {
+        let failed = matches!(
+            get_pending_mint(&mint_id),
+            Ok(Some(PendingMint { user, status: MintStatus::Failed(_) | MintStatus::FailedRefunded(_) | MintStatus::FailedNoRefund(_), .. }))
+                if user == caller
+        );
+        if failed {
+            crate::_1_CRITICAL_OPERATIONS::operation_status::last_failure::record_failure(
+                caller,
+                crate::_1_CRITICAL_OPERATIONS::operation_status::last_failure::FailedOperation::Mint,
+                e,
+                Some(mint_id),
+            );
+        }
+    }
+    result
+}
+
+async fn complete_mint_steps(caller: Principal, mint_id: String) -> Result<Nat> {
     // Check not paused (Phase 2: H-1)
     crate::infrastructure::check_not_paused()?;
+    crate::infrastructure::dependency_compat::check_dependencies_compatible()?;
 
-    // Acquire reentrancy guard - prevents concurrent mints by same user
+    // Acquire reentrancy guard - prevents concurrent mints by same user and
+    // bounds in-flight mints system-wide
     let _guard = crate::infrastructure::MintGuard::acquire(caller)?;
 
+    // Refuse while this user has a burn in flight (fee pulls would interleave)
+    crate::_1_CRITICAL_OPERATIONS::operation_status::check_mint_allowed(&caller)?;
+
     // Get pending mint
     let pending_mint = get_pending_mint(&mint_id)?
         .ok_or_else(|| IcpiError::Mint(MintError::InvalidMintId {
@@ -67,8 +118,12 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
     update_mint_status(&mint_id, MintStatus::CollectingFee)?;
 
     match collect_mint_fee(caller).await {
-        Ok(_) => {
-            ic_cdk::println!("Fee collected for mint {}", mint_id);
+        Ok(fee) => {
+            crate::log_op!("Fee collected for mint {}", mint_id);
+            crate::_5_INFORMATIONAL::costs::record_cost(
+                crate::_5_INFORMATIONAL::costs::CostKind::MintFee,
+                num_traits::ToPrimitive::to_u64(&fee.0).unwrap_or(0),
+            );
         }
         Err(e) => {
             update_mint_status(&mint_id, MintStatus::Failed(format!("Fee collection failed: {}", e)))?;
@@ -102,7 +157,7 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
         }));
     }
 
-    ic_cdk::println!("Pre-deposit TVL: {} ckUSDT (e6), Supply: {} ICPI (e8)", current_tvl, current_supply);
+    crate::log_op!("Pre-deposit TVL: {} ckUSDT (e6), Supply: {} ICPI (e8)", current_tvl, current_supply);
 
     // CRITICAL TIMING: Snapshot MUST be taken BEFORE collecting user's deposit (Phase 3: M-1)
     //
@@ -130,7 +185,7 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
     let snapshot = MintSnapshot {
         supply: current_supply.clone(),
         tvl: current_tvl.clone(),
-        timestamp: ic_cdk::api::time(),
+        timestamp: crate::infrastructure::runtime::time(),
     };
 
     // Update mint with snapshot
@@ -143,11 +198,11 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
     // Check for stale snapshot (warning at 30s, error at 60s)
     const SNAPSHOT_WARNING_AGE_NANOS: u64 = 30_000_000_000; // 30 seconds
     const SNAPSHOT_MAX_AGE_NANOS: u64 = 60_000_000_000; // 60 seconds (hard limit)
-    let snapshot_age = ic_cdk::api::time() - snapshot.timestamp;
+    let snapshot_age = crate::infrastructure::runtime::time() - snapshot.timestamp;
     let snapshot_age_seconds = snapshot_age / 1_000_000_000;
 
     if snapshot_age > SNAPSHOT_MAX_AGE_NANOS {
-        ic_cdk::println!(
+        crate::log_op!(
             "🚨 CRITICAL: Snapshot {} seconds old exceeds maximum allowed age (60s)",
             snapshot_age_seconds
         );
@@ -164,7 +219,7 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
     }
 
     if snapshot_age > SNAPSHOT_WARNING_AGE_NANOS {
-        ic_cdk::println!(
+        crate::log_op!(
             "⚠️ WARNING: Using snapshot {} seconds old (recommended max: 30s, hard limit: 60s)",
             snapshot_age_seconds
         );
@@ -173,9 +228,10 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
     // Step 3: NOW collect deposit (after TVL snapshot taken)
     update_mint_status(&mint_id, MintStatus::CollectingDeposit)?;
 
-    match collect_deposit(caller, pending_mint.amount.clone(), "ICPI mint".to_string()).await {
+    let deposit_memo = crate::infrastructure::memo::ledger_memo("ICPI mint", pending_mint.memo.as_deref());
+    match collect_deposit(caller, pending_mint.amount.clone(), deposit_memo).await {
         Ok(_) => {
-            ic_cdk::println!("Deposit collected for mint {}", mint_id);
+            crate::log_op!("Deposit collected for mint {}", mint_id);
         }
         Err(e) => {
             update_mint_status(&mint_id, MintStatus::Failed(format!("Deposit collection failed: {}", e)))?;
@@ -194,7 +250,7 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
         &current_tvl,          // ckUSDT in e6 decimals
     ) {
         Ok(amount) => {
-            ic_cdk::println!(
+            crate::log_op!(
                 "  Mint calculation: deposit={} e6, supply={} e8, tvl={} e6 → icpi={} e8",
                 pending_mint.amount, current_supply, current_tvl, amount
             );
@@ -211,14 +267,40 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
         }
     };
 
-    ic_cdk::println!("Calculated ICPI to mint: {}", icpi_to_mint);
+    crate::log_op!("Calculated ICPI to mint: {}", icpi_to_mint);
+
+    // Step 5: Re-read supply; a concurrent burn since the snapshot would make the ratio stale
+    let config = crate::infrastructure::config::get_config();
+    if config.mint_supply_recheck() {
+        let recheck = match crate::_2_CRITICAL_DATA::get_icpi_supply_uncached().await {
+            Ok(supply_now) => validate_supply_unchanged(&current_supply, &supply_now, config.mint_supply_tolerance_bps()),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = recheck {
+            crate::log_op!("⚠️ Aborting mint {}: {}", mint_id, e);
+            handle_mint_failure(
+                &mint_id,
+                caller,
+                pending_mint.amount.clone(),
+                format!("Supply re-validation failed: {}", e)
+            ).await?;
+            return Err(e);
+        }
+    }
 
-    // Step 5: Mint ICPI tokens on the actual ICPI ledger
-    update_mint_status(&mint_id, MintStatus::Minting)?;
+    // Step 6: Mint ICPI tokens on the actual ICPI ledger
+    // Re-check the status in this message: anything but Calculating means
+    // another path got here first, so abort without touching the mint
+    if let Err(e) = begin_ledger_mint(&mint_id) {
+        crate::log_op!("🚨 CRITICAL: Refusing ledger mint for {}: {}", mint_id, e);
+        return Err(e);
+    }
 
-    match mint_icpi_on_ledger(caller, icpi_to_mint.clone()).await {
+    let auth = authorize_ledger_mint(&mint_id, caller, &icpi_to_mint)?;
+    match mint_icpi_on_ledger(auth, caller, icpi_to_mint.clone(), &mint_id).await {
         Ok(block_index) => {
-            ic_cdk::println!("Minted {} ICPI to {} (block: {})", icpi_to_mint, caller, block_index);
+            crate::log_op!("Minted {} ICPI to {} (block: {})", icpi_to_mint, caller, block_index);
         }
         Err(e) => {
             handle_mint_failure(
@@ -231,8 +313,17 @@ pub async fn complete_mint(caller: Principal, mint_id: String) -> Result<Nat> {
         }
     }
 
-    // Step 6: Mark as complete
+    // Step 7: Mark as complete
     update_mint_status(&mint_id, MintStatus::Complete(icpi_to_mint.clone()))?;
+    crate::_5_INFORMATIONAL::daily_totals::record_mint(crate::infrastructure::runtime::time(), &icpi_to_mint, &pending_mint.amount);
+    crate::_5_INFORMATIONAL::user_activity::record_mint(
+        caller,
+        &pending_mint.amount,
+        &icpi_to_mint,
+        crate::infrastructure::runtime::time(),
+        &crate::infrastructure::config::get_config(),
+    );
+    crate::_1_CRITICAL_OPERATIONS::rebalancing::invalidate_projection();
 
     Ok(icpi_to_mint)
 }
@@ -248,13 +339,13 @@ async fn handle_mint_failure(
 
     match refund_deposit(user, amount.clone()).await {
         Ok(_) => {
-            ic_cdk::println!("Successfully refunded {} to {}", amount, user);
+            crate::log_op!("Successfully refunded {} to {}", amount, user);
             update_mint_status(mint_id, MintStatus::FailedRefunded(
                 format!("{}, deposit refunded", reason)
             ))?;
         }
         Err(refund_err) => {
-            ic_cdk::println!("ERROR: Failed to refund deposit: {}", refund_err);
+            crate::log_op!("ERROR: Failed to refund deposit: {}", refund_err);
             update_mint_status(mint_id, MintStatus::FailedNoRefund(
                 format!("{}. Refund failed: {}. Amount: {}. Contact support.", reason, refund_err, amount)
             ))?;
@@ -264,51 +355,71 @@ async fn handle_mint_failure(
     Ok(())
 }
 
-/// Mint ICPI tokens on the ledger
-pub async fn mint_icpi_on_ledger(recipient: Principal, amount: Nat) -> Result<Nat> {
-    let icpi_ledger = Principal::from_text(ICPI_CANISTER_ID)
-        .map_err(|e| IcpiError::Mint(MintError::LedgerInteractionFailed {
-            operation: "parse_principal".to_string(),
-            details: format!("Invalid ICPI principal: {}", e),
-        }))?;
+/// Issue the ledger mint authorization for a mint that has reached `Minting`
+///
+/// The mint must belong to `recipient`; anything else indicates a bug
+/// trying to reuse the mint transfer.
+fn authorize_ledger_mint(mint_id: &str, recipient: Principal, amount: &Nat) -> Result<MintAuthorization> {
+    let mint = get_pending_mint(mint_id)?
+        .ok_or_else(|| IcpiError::Mint(MintError::InvalidMintId { id: mint_id.to_string() }))?;
 
-    // Call the ledger to mint tokens using icrc1_transfer
-    // Backend is the minting account, so transfers create new tokens
-    use crate::types::icrc::TransferArgs;
+    if mint.user != recipient || !matches!(mint.status, MintStatus::Minting) {
+        return Err(IcpiError::Mint(MintError::Unauthorized {
+            principal: recipient.to_text(),
+            mint_id: mint_id.to_string(),
+        }));
+    }
 
-    let transfer_args = TransferArgs {
-        from_subaccount: None,
-        to: crate::types::Account {
-            owner: recipient,
-            subaccount: None,
-        },
-        amount: amount.clone(),
-        fee: None, // No fee for minting
-        memo: Some(b"ICPI minting".to_vec()),
-        created_at_time: Some(ic_cdk::api::time()),
+    Ok(MintAuthorization::issue(recipient, amount.clone()))
+}
+
+/// Mint ICPI tokens on the ledger
+///
+/// Backend is the minting account, so transfers out of it create new tokens
+///
+/// With `mint_balance_check` enabled the recipient's balance is read before
+/// and after the transfer; a missing credit is flagged as a critical Mint
+/// error. The mint itself still succeeds since the ledger issued a block.
+/// The transfer memo tags the block with `mint_id` (`mint_ledger_memo`).
+pub async fn mint_icpi_on_ledger(auth: MintAuthorization, recipient: Principal, amount: Nat, mint_id: &str) -> Result<Nat> {
+    let balance_before = if crate::infrastructure::config::get_config().mint_balance_check() {
+        match crate::infrastructure::icpi_ledger::balance_of(recipient).await {
+            Ok(balance) => Some(balance),
+            Err(e) => {
+                crate::log_op!("⚠️ Skipping post-mint balance check, pre-mint balance unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
-    let result: std::result::Result<(crate::types::icrc::TransferResult,), _> = ic_cdk::call(
-        icpi_ledger,
-        "icrc1_transfer",
-        (transfer_args,)
-    ).await;
+    let ledger_memo = mint_ledger_memo(mint_id);
+    let block_index = crate::infrastructure::icpi_ledger::transfer(auth, recipient, amount.clone(), ledger_memo.as_bytes()).await
+        .map_err(|e| IcpiError::Mint(MintError::LedgerInteractionFailed {
+            operation: "mint".to_string(),
+            details: e.to_string(),
+        }))?;
 
-    match result {
-        Ok((crate::types::icrc::TransferResult::Ok(block),)) => {
-            Ok(block)
-        }
-        Ok((crate::types::icrc::TransferResult::Err(e),)) => {
-            Err(IcpiError::Mint(MintError::LedgerInteractionFailed {
-                operation: "mint".to_string(),
-                details: format!("Mint error: {:?}", e),
-            }))
-        }
-        Err((code, msg)) => {
-            Err(IcpiError::Mint(MintError::LedgerInteractionFailed {
-                operation: "mint".to_string(),
-                details: format!("Call failed: {:?} - {}", code, msg),
-            }))
+    if let Some(before) = balance_before {
+        match crate::infrastructure::icpi_ledger::balance_of(recipient).await {
+            Ok(after) => {
+                if let Err(e) = validate_mint_credited(&before, &after, &amount) {
+                    crate::log_op!(
+                        "🚨 CRITICAL: mint block {} to {} not reflected in balance ({} -> {}): {}. Check the ledger minting account.",
+                        block_index, recipient, before, after, e
+                    );
+                    crate::infrastructure::logging::record_error(
+                        crate::infrastructure::logging::Subsystem::Mint,
+                        format!("Mint block {} not credited: {}", block_index, e),
+                    );
+                }
+            }
+            Err(e) => {
+                crate::log_op!("⚠️ Post-mint balance check failed to read balance: {}", e);
+            }
         }
     }
-}
\ No newline at end of file
+
+    Ok(block_index)
+}
@@ -4,7 +4,7 @@
 use candid::{CandidType, Deserialize, Nat, Principal};
 use std::cell::RefCell;
 use std::collections::HashMap;
-use crate::infrastructure::{Result, IcpiError, MintError};
+use crate::infrastructure::{Result, IcpiError, MintError, SystemError};
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub enum MintStatus {
@@ -22,6 +22,31 @@ pub enum MintStatus {
     Expired,
 }
 
+impl MintStatus {
+    /// Whether the mint has finished (successfully or not)
+    pub fn is_terminal(&self) -> bool {
+        matches!(self,
+            MintStatus::Complete(_) |
+            MintStatus::Failed(_) |
+            MintStatus::FailedRefunded(_) |
+            MintStatus::FailedNoRefund(_) |
+            MintStatus::Expired)
+    }
+
+    /// Whether the backend holds the mint's deposit while its outcome is open
+    ///
+    /// Covers deposits collected but not yet minted against, and failed
+    /// mints whose refund is in flight or still owed.
+    pub fn holds_deposit(&self) -> bool {
+        matches!(self,
+            MintStatus::CollectingDeposit |
+            MintStatus::Calculating |
+            MintStatus::Minting |
+            MintStatus::Refunding |
+            MintStatus::FailedNoRefund(_))
+    }
+}
+
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct MintSnapshot {
     pub supply: Nat,
@@ -38,6 +63,19 @@ pub struct PendingMint {
     pub created_at: u64,
     pub last_updated: u64,
     pub snapshot: Option<MintSnapshot>,
+    pub memo: Option<String>,
+    /// Request id of the `initiate_mint` call
+    pub request_id: Option<String>,
+}
+
+impl PendingMint {
+    /// Whether the mint was never started and is past `MINT_TIMEOUT_NANOS`
+    ///
+    /// Such a mint holds no funds and only waits for cleanup to remove it.
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        matches!(self.status, MintStatus::Pending)
+            && now.saturating_sub(self.created_at) > crate::infrastructure::MINT_TIMEOUT_NANOS
+    }
 }
 
 /// BUGFIX (PR #8 Review): Keep internal state private to maintain encapsulation
@@ -60,21 +98,13 @@ pub fn get_pending_mint(mint_id: &str) -> Result<Option<PendingMint>> {
     })
 }
 
-/// Get mint status for a given mint ID
-/// Used by public API for checking mint progress
-pub fn get_mint_status(mint_id: &str) -> Result<Option<MintStatus>> {
-    PENDING_MINTS.with(|mints| {
-        Ok(mints.borrow().get(mint_id).map(|mint| mint.status.clone()))
-    })
-}
-
 pub fn update_mint_status(mint_id: &str, status: MintStatus) -> Result<()> {
     PENDING_MINTS.with(|mints| {
         let mut mints = mints.borrow_mut();
         match mints.get_mut(mint_id) {
             Some(mint) => {
                 mint.status = status;
-                mint.last_updated = ic_cdk::api::time();
+                mint.last_updated = crate::infrastructure::runtime::time();
                 Ok(())
             }
             None => Err(IcpiError::Mint(MintError::InvalidMintId {
@@ -84,50 +114,209 @@ pub fn update_mint_status(mint_id: &str, status: MintStatus) -> Result<()> {
     })
 }
 
-pub fn cleanup_expired_mints() -> Result<u32> {
-    const TIMEOUT_NANOS: u64 = 180_000_000_000; // 3 minutes
-    const COMPLETED_RETENTION_NANOS: u64 = 86_400_000_000_000; // 24 hours
-    let now = ic_cdk::api::time();
-    let mut cleaned = 0u32;
+/// Move a mint from `Calculating` to `Minting` right before the ledger mint
+///
+/// Status is checked and set with no await in between, so if a bug ever let
+/// a second path reach this point for the same mint (already `Minting` or
+/// `Complete`), it is refused instead of issuing ICPI twice. The status is
+/// left untouched on refusal.
+pub fn begin_ledger_mint(mint_id: &str) -> Result<()> {
+    begin_ledger_mint_at(mint_id, crate::infrastructure::runtime::time())
+}
 
+fn begin_ledger_mint_at(mint_id: &str, now: u64) -> Result<()> {
     PENDING_MINTS.with(|mints| {
         let mut mints = mints.borrow_mut();
-        mints.retain(|_id, mint| {
-            let age = now - mint.created_at;
+        let mint = mints.get_mut(mint_id).ok_or_else(|| IcpiError::Mint(MintError::InvalidMintId {
+            id: mint_id.to_string(),
+        }))?;
+        if !matches!(mint.status, MintStatus::Calculating) {
+            return Err(IcpiError::System(SystemError::StateCorrupted {
+                reason: format!("Mint {} is {:?} before ledger mint, expected Calculating", mint_id, mint.status),
+            }));
+        }
+        mint.status = MintStatus::Minting;
+        mint.last_updated = now;
+        Ok(())
+    })
+}
 
-            // Remove failed/expired pending mints after 3 minutes
-            if age > TIMEOUT_NANOS && !matches!(mint.status, MintStatus::Complete(_)) {
-                cleaned += 1;
-                false
+/// Start the hourly cleanup of completed and expired mints
+///
+/// Prevents the pending-mint map from growing without bound.
+pub fn start_cleanup_timer() {
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::MintCleanup,
+        std::time::Duration::from_secs(3600), // 1 hour
+        false,
+        || {
+            let max = crate::infrastructure::config::get_config().max_pending_mints();
+            let pending = get_pending_count();
+            crate::log_op!(
+                "📈 Pending mints: {}/{} ({:.1}% occupancy)",
+                pending, max, occupancy_pct(pending as u64, max)
+            );
+            match cleanup_expired_mints() {
+                Ok(count) if count > 0 => {
+                    crate::log_op!("🧹 Periodic cleanup: removed {} expired mints", count);
+                }
+                Ok(_) => {}, // No mints to clean
+                Err(e) => crate::log_op!("⚠️ Periodic cleanup failed: {}", e),
             }
-            // Remove completed mints after 24 hours to prevent memory leak
-            else if age > COMPLETED_RETENTION_NANOS && matches!(mint.status, MintStatus::Complete(_)) {
+        }
+    );
+}
+
+/// How long mints are kept before cleanup removes them
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CleanupWindows {
+    /// Mints that were initiated but never started completing
+    not_started_nanos: u64,
+    /// Failed, expired or in-flight mints
+    unfinished_nanos: u64,
+    /// Completed mints
+    completed_nanos: u64,
+}
+
+impl CleanupWindows {
+    const NORMAL: CleanupWindows = CleanupWindows {
+        not_started_nanos: 180_000_000_000,      // 3 minutes
+        unfinished_nanos: 180_000_000_000,       // 3 minutes
+        completed_nanos: 86_400_000_000_000,     // 24 hours
+    };
+
+    /// Used above the occupancy threshold. In-flight mints keep the normal
+    /// window so a slow complete_mint isn't pulled out from under itself.
+    const AGGRESSIVE: CleanupWindows = CleanupWindows {
+        not_started_nanos: 60_000_000_000,       // 1 minute
+        unfinished_nanos: 180_000_000_000,       // 3 minutes
+        completed_nanos: 3_600_000_000_000,      // 1 hour
+    };
+
+    fn for_occupancy(occupancy_pct: f64, aggressive_enabled: bool) -> CleanupWindows {
+        if aggressive_enabled && occupancy_pct > crate::infrastructure::AGGRESSIVE_CLEANUP_OCCUPANCY_PCT {
+            Self::AGGRESSIVE
+        } else {
+            Self::NORMAL
+        }
+    }
+
+    fn is_expired(&self, mint: &PendingMint, now: u64) -> bool {
+        let age = now.saturating_sub(mint.created_at);
+        match mint.status {
+            MintStatus::Complete(_) => age > self.completed_nanos,
+            MintStatus::Pending => age > self.not_started_nanos,
+            _ => age > self.unfinished_nanos,
+        }
+    }
+}
+
+/// Share of the pending-mint cap in use, as a percentage
+pub fn occupancy_pct(pending: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 100.0;
+    }
+    pending as f64 / max as f64 * 100.0
+}
+
+/// Refuse a new mint once the global pending-mint cap is reached
+///
+/// Initiation is free, so without this a wave of principals could grow the
+/// pending-mint map without bound.
+pub fn check_pending_capacity(pending: u64, max: u64) -> Result<()> {
+    if pending >= max {
+        return Err(IcpiError::Mint(MintError::PendingMintCapReached { pending, max }));
+    }
+    Ok(())
+}
+
+pub fn cleanup_expired_mints() -> Result<u32> {
+    let now = crate::infrastructure::runtime::time();
+    let config = crate::infrastructure::config::get_config();
+    let occupancy = occupancy_pct(get_pending_count() as u64, config.max_pending_mints());
+    let windows = CleanupWindows::for_occupancy(occupancy, config.aggressive_mint_cleanup());
+    if windows == CleanupWindows::AGGRESSIVE {
+        crate::log_op!("⚠️ Pending mint occupancy {:.1}%, using aggressive cleanup", occupancy);
+    }
+
+    Ok(remove_expired(now, windows))
+}
+
+fn remove_expired(now: u64, windows: CleanupWindows) -> u32 {
+    let mut cleaned = 0u32;
+
+    PENDING_MINTS.with(|mints| {
+        mints.borrow_mut().retain(|_id, mint| {
+            if windows.is_expired(mint, now) {
                 cleaned += 1;
-                ic_cdk::println!("Cleaned up completed mint {} after 24 hours", _id);
                 false
-            }
-            else {
+            } else {
                 true
             }
         });
     });
 
-    Ok(cleaned)
+    cleaned
 }
 
+/// Unfinished mints counted against the pending-mint cap
+///
+/// Expired mints are left out, so abandoned ones can't block new mints
+/// while they wait for cleanup.
 pub fn get_pending_count() -> usize {
+    PENDING_MINTS.with(|mints| count_pending(mints.borrow().values(), crate::infrastructure::runtime::time()))
+}
+
+fn count_pending<'a>(mints: impl Iterator<Item = &'a PendingMint>, now: u64) -> usize {
+    mints
+        .filter(|m| !m.status.is_terminal() && !m.is_expired_at(now))
+        .count()
+}
+
+/// ckUSDT reserved for deposits whose mint has not settled (e6)
+///
+/// Refunds settle against this reserve: a mint leaves `Refunding` once its
+/// refund lands, releasing the deposit it held.
+pub fn reserved_deposits() -> Nat {
+    PENDING_MINTS.with(|mints| reserved_in(mints.borrow().values()))
+}
+
+fn reserved_in<'a>(mints: impl Iterator<Item = &'a PendingMint>) -> Nat {
+    mints
+        .filter(|m| m.status.holds_deposit())
+        .fold(Nat::from(0u64), |total, m| total + m.amount.clone())
+}
+
+/// Find the user's oldest mint that has not reached a terminal state
+///
+/// Expired mints are skipped: one the user abandoned must not block their
+/// burns until cleanup gets to it.
+pub fn find_unfinished_mint(user: &Principal) -> Option<PendingMint> {
+    find_unfinished_mint_at(user, crate::infrastructure::runtime::time())
+}
+
+fn find_unfinished_mint_at(user: &Principal, now: u64) -> Option<PendingMint> {
     PENDING_MINTS.with(|mints| {
         mints.borrow()
             .values()
-            .filter(|m| matches!(m.status,
-                MintStatus::Pending |
-                MintStatus::CollectingFee |
-                MintStatus::Snapshotting |
-                MintStatus::CollectingDeposit |
-                MintStatus::Calculating |
-                MintStatus::Minting |
-                MintStatus::Refunding))
-            .count()
+            .filter(|m| m.user == *user && !m.status.is_terminal() && !m.is_expired_at(now))
+            .min_by_key(|m| m.created_at)
+            .cloned()
+    })
+}
+
+/// A user's mints still held in state, newest first
+///
+/// Completed mints are kept for 24 hours, failed ones for a few minutes.
+pub fn get_user_mints(user: &Principal) -> Vec<PendingMint> {
+    PENDING_MINTS.with(|mints| {
+        let mut user_mints: Vec<PendingMint> = mints.borrow()
+            .values()
+            .filter(|m| m.user == *user)
+            .cloned()
+            .collect();
+        user_mints.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        user_mints
     })
 }
 
@@ -141,4 +330,162 @@ pub fn import_state(state: HashMap<String, PendingMint>) {
     PENDING_MINTS.with(|mints| {
         *mints.borrow_mut() = state;
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mint(id: &str, user: Principal, status: MintStatus, created_at: u64) -> PendingMint {
+        PendingMint {
+            id: id.to_string(),
+            user,
+            amount: Nat::from(1_000_000u64),
+            status,
+            created_at,
+            last_updated: created_at,
+            snapshot: None,
+            memo: None,
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn test_terminal_statuses() {
+        assert!(!MintStatus::Pending.is_terminal());
+        assert!(!MintStatus::CollectingDeposit.is_terminal());
+        assert!(!MintStatus::Refunding.is_terminal());
+        assert!(MintStatus::Complete(Nat::from(1u64)).is_terminal());
+        assert!(MintStatus::FailedRefunded(String::new()).is_terminal());
+        assert!(MintStatus::Expired.is_terminal());
+    }
+
+    #[test]
+    fn test_reserved_deposits_count_held_statuses() {
+        let user = Principal::anonymous();
+        let mints = vec![
+            mint("a", user, MintStatus::Pending, 1),
+            mint("b", user, MintStatus::Calculating, 2),
+            mint("c", user, MintStatus::Refunding, 3),
+            mint("d", user, MintStatus::FailedNoRefund(String::new()), 4),
+            mint("e", user, MintStatus::FailedRefunded(String::new()), 5),
+            mint("f", user, MintStatus::Complete(Nat::from(1u64)), 6),
+        ];
+        assert_eq!(reserved_in(mints.iter()), Nat::from(3_000_000u64));
+    }
+
+    #[test]
+    fn test_expired_mints_not_counted_against_cap() {
+        const SECOND: u64 = 1_000_000_000;
+        let user = Principal::anonymous();
+        let now = 10_000 * SECOND;
+        let timeout = crate::infrastructure::MINT_TIMEOUT_NANOS;
+        let mints = vec![
+            mint("fresh", user, MintStatus::Pending, now - timeout),
+            mint("abandoned", user, MintStatus::Pending, now - timeout - 1),
+            // Started mints are never expired, however old
+            mint("in_flight", user, MintStatus::CollectingDeposit, now - 2 * timeout),
+            mint("done", user, MintStatus::Complete(Nat::from(1u64)), now),
+        ];
+
+        assert!(!mints[0].is_expired_at(now));
+        assert!(mints[1].is_expired_at(now));
+        assert_eq!(count_pending(mints.iter(), now), 2);
+    }
+
+    #[test]
+    fn test_find_unfinished_mint() {
+        let alice = Principal::from_text("2vxsx-fae").unwrap();
+        let bob = Principal::from_text("aaaaa-aa").unwrap();
+
+        import_state(HashMap::new());
+        store_pending_mint(mint("done", alice, MintStatus::Complete(Nat::from(1u64)), 1)).unwrap();
+        store_pending_mint(mint("other_user", bob, MintStatus::Pending, 2)).unwrap();
+        assert!(find_unfinished_mint_at(&alice, 30).is_none());
+
+        store_pending_mint(mint("newer", alice, MintStatus::Pending, 20)).unwrap();
+        store_pending_mint(mint("older", alice, MintStatus::CollectingFee, 10)).unwrap();
+        assert_eq!(find_unfinished_mint_at(&alice, 30).unwrap().id, "older");
+
+        import_state(HashMap::new());
+    }
+
+    #[test]
+    fn test_expired_mint_not_unfinished() {
+        let alice = Principal::from_text("2vxsx-fae").unwrap();
+        let expired_at = 1 + crate::infrastructure::MINT_TIMEOUT_NANOS + 1;
+
+        import_state(HashMap::new());
+        store_pending_mint(mint("abandoned", alice, MintStatus::Pending, 1)).unwrap();
+        assert!(find_unfinished_mint_at(&alice, expired_at - 1).is_some());
+        assert!(find_unfinished_mint_at(&alice, expired_at).is_none());
+
+        // A started mint still blocks
+        store_pending_mint(mint("started", alice, MintStatus::CollectingFee, 1)).unwrap();
+        assert_eq!(find_unfinished_mint_at(&alice, expired_at).unwrap().id, "started");
+
+        import_state(HashMap::new());
+    }
+
+    #[test]
+    fn test_ledger_mint_requires_calculating_status() {
+        let alice = Principal::from_text("2vxsx-fae").unwrap();
+        import_state(HashMap::new());
+        store_pending_mint(mint("m", alice, MintStatus::Calculating, 1)).unwrap();
+
+        assert!(begin_ledger_mint_at("m", 5).is_ok());
+        let stored = get_pending_mint("m").unwrap().unwrap();
+        assert!(matches!(stored.status, MintStatus::Minting));
+        assert_eq!(stored.last_updated, 5);
+
+        // A second path reaching the ledger mint is refused
+        assert!(matches!(
+            begin_ledger_mint_at("m", 6),
+            Err(IcpiError::System(SystemError::StateCorrupted { .. }))
+        ));
+
+        for status in [MintStatus::Complete(Nat::from(1u64)), MintStatus::Refunding, MintStatus::Pending] {
+            store_pending_mint(mint("m", alice, status, 1)).unwrap();
+            assert!(begin_ledger_mint_at("m", 7).is_err());
+            // Refusal leaves the status as it was
+            assert!(!matches!(get_pending_mint("m").unwrap().unwrap().status, MintStatus::Minting));
+        }
+        assert!(begin_ledger_mint_at("missing", 7).is_err());
+
+        import_state(HashMap::new());
+    }
+
+    #[test]
+    fn test_pending_capacity() {
+        assert!(check_pending_capacity(0, 10_000).is_ok());
+        assert!(check_pending_capacity(9_999, 10_000).is_ok());
+        assert!(matches!(
+            check_pending_capacity(10_000, 10_000),
+            Err(IcpiError::Mint(MintError::PendingMintCapReached { pending: 10_000, max: 10_000 }))
+        ));
+        assert!(check_pending_capacity(0, 0).is_err());
+
+        assert_eq!(occupancy_pct(8_000, 10_000), 80.0);
+        assert_eq!(occupancy_pct(1, 0), 100.0);
+    }
+
+    #[test]
+    fn test_aggressive_cleanup_above_threshold() {
+        assert_eq!(CleanupWindows::for_occupancy(80.0, true), CleanupWindows::NORMAL);
+        assert_eq!(CleanupWindows::for_occupancy(80.1, true), CleanupWindows::AGGRESSIVE);
+        assert_eq!(CleanupWindows::for_occupancy(99.0, false), CleanupWindows::NORMAL);
+
+        const SECOND: u64 = 1_000_000_000;
+        let user = Principal::from_text("2vxsx-fae").unwrap();
+        let now = 10_000 * SECOND;
+        import_state(HashMap::new());
+        store_pending_mint(mint("idle", user, MintStatus::Pending, now - 90 * SECOND)).unwrap();
+        store_pending_mint(mint("in_flight", user, MintStatus::CollectingDeposit, now - 90 * SECOND)).unwrap();
+        store_pending_mint(mint("done", user, MintStatus::Complete(Nat::from(1u64)), now - 7_200 * SECOND)).unwrap();
+
+        assert_eq!(remove_expired(now, CleanupWindows::NORMAL), 0);
+        assert_eq!(remove_expired(now, CleanupWindows::AGGRESSIVE), 2);
+        assert!(get_pending_mint("in_flight").unwrap().is_some());
+
+        import_state(HashMap::new());
+    }
+}
@@ -2,17 +2,23 @@
 
 use candid::{Nat, Principal};
 use crate::infrastructure::{Result, IcpiError, ValidationError, MintError};
-use crate::infrastructure::constants::{MIN_MINT_AMOUNT, MAX_MINT_AMOUNT};
+use crate::infrastructure::constants::{MIN_MINT_AMOUNT, MAX_MINT_AMOUNT, MINT_FEE_AMOUNT};
 
 pub fn validate_mint_request(caller: &Principal, amount: &Nat) -> Result<()> {
-    // Check principal is not anonymous
-    if caller == &Principal::anonymous() {
-        return Err(IcpiError::Validation(ValidationError::InvalidPrincipal {
-            principal: caller.to_text(),
-        }));
-    }
+    validate_mint_caller(caller)?;
+    validate_mint_amount(amount)?;
+
+    // Rate limiting check
+    crate::infrastructure::rate_limiting::check_operation_rate_limit(
+        crate::infrastructure::rate_limiting::RateLimitedOperation::Mint,
+        caller,
+    )?;
+
+    Ok(())
+}
 
-    // Check amount bounds
+/// Check a deposit amount is within the mint bounds
+pub fn validate_mint_amount(amount: &Nat) -> Result<()> {
     if amount < &Nat::from(MIN_MINT_AMOUNT) {
         return Err(IcpiError::Mint(MintError::AmountBelowMinimum {
             amount: amount.to_string(),
@@ -27,11 +33,133 @@ pub fn validate_mint_request(caller: &Principal, amount: &Nat) -> Result<()> {
         }));
     }
 
-    // Rate limiting check
-    crate::infrastructure::rate_limiting::check_rate_limit(
-        &format!("mint_{}", caller),
-        1_000_000_000 // 1 second
-    )?;
+    Ok(())
+}
+
+/// Allowances a mint of `amount` ckUSDT draws on, per token
+///
+/// `complete_mint` makes two ckUSDT `transfer_from` calls (fee, then
+/// deposit), and each one consumes its ledger fee from the allowance.
+pub fn required_mint_approvals(amount: &Nat, ckusdt_fee: u64) -> Vec<(&'static str, Nat)> {
+    vec![("ckUSDT", amount.clone() + Nat::from(MINT_FEE_AMOUNT) + Nat::from(2 * ckusdt_fee))]
+}
+
+/// Check supply hasn't drifted from the mint snapshot by more than `tolerance_bps`
+///
+/// A concurrent burn (or another user's mint) between snapshot and ledger
+/// mint would otherwise mint at a stale supply/TVL ratio.
+pub fn validate_supply_unchanged(snapshot: &Nat, current: &Nat, tolerance_bps: u64) -> Result<()> {
+    let drift = if current > snapshot {
+        current.clone() - snapshot.clone()
+    } else {
+        snapshot.clone() - current.clone()
+    };
+
+    if drift * Nat::from(10_000u64) > snapshot.clone() * Nat::from(tolerance_bps) {
+        return Err(IcpiError::Mint(MintError::SupplyChanged {
+            snapshot: snapshot.to_string(),
+            current: current.to_string(),
+            tolerance_bps,
+        }));
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Check a ledger mint actually credited the recipient
+///
+/// A block index alone doesn't prove new ICPI was created: if the backend
+/// isn't the ledger's minting account the transfer moves existing tokens or
+/// lands elsewhere. Anything short of `before + minted` is treated as a
+/// failed credit.
+pub fn validate_mint_credited(before: &Nat, after: &Nat, minted: &Nat) -> Result<()> {
+    if *after >= before.clone() + minted.clone() {
+        return Ok(());
+    }
+
+    let observed = if after > before {
+        after.clone() - before.clone()
+    } else {
+        Nat::from(0u64)
+    };
+    Err(IcpiError::Mint(MintError::BalanceNotCredited {
+        expected_increase: minted.to_string(),
+        observed_increase: observed.to_string(),
+    }))
+}
+
+/// Caller checks that don't depend on the amount
+pub fn validate_mint_caller(caller: &Principal) -> Result<()> {
+    // Check principal is not anonymous
+    if caller == &Principal::anonymous() {
+        return Err(IcpiError::Validation(ValidationError::InvalidPrincipal {
+            principal: caller.to_text(),
+        }));
+    }
+
+    // Allowlist/denylist enforcement
+    crate::infrastructure::access_control::check_principal_access(caller)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supply_within_tolerance() {
+        let snapshot = Nat::from(1_000_000_000u64);
+        assert!(validate_supply_unchanged(&snapshot, &snapshot, 100).is_ok());
+        // Exactly 1% either way is still accepted at 100 bps
+        assert!(validate_supply_unchanged(&snapshot, &Nat::from(1_010_000_000u64), 100).is_ok());
+        assert!(validate_supply_unchanged(&snapshot, &Nat::from(990_000_000u64), 100).is_ok());
+    }
+
+    #[test]
+    fn test_supply_drift_aborts_mint() {
+        let snapshot = Nat::from(1_000_000_000u64);
+        // Concurrent burn removed 5% of supply
+        let result = validate_supply_unchanged(&snapshot, &Nat::from(950_000_000u64), 100);
+        assert!(matches!(
+            result,
+            Err(IcpiError::Mint(MintError::SupplyChanged { tolerance_bps: 100, .. }))
+        ));
+
+        assert!(validate_supply_unchanged(&snapshot, &Nat::from(1_010_000_001u64), 100).is_err());
+    }
+
+    #[test]
+    fn test_mint_credit_verified() {
+        let minted = Nat::from(500_000_000u64);
+        assert!(validate_mint_credited(&Nat::from(0u64), &minted, &minted).is_ok());
+        assert!(validate_mint_credited(&Nat::from(100u64), &Nat::from(500_000_100u64), &minted).is_ok());
+        // An unrelated incoming transfer in the same window is fine
+        assert!(validate_mint_credited(&Nat::from(100u64), &Nat::from(600_000_100u64), &minted).is_ok());
+    }
+
+    #[test]
+    fn test_mint_credit_missing() {
+        let minted = Nat::from(500_000_000u64);
+
+        // Block returned but balance unchanged
+        let result = validate_mint_credited(&Nat::from(100u64), &Nat::from(100u64), &minted);
+        match result {
+            Err(IcpiError::Mint(MintError::BalanceNotCredited { expected_increase, observed_increase })) => {
+                assert_eq!(expected_increase, minted.to_string());
+                assert_eq!(observed_increase, "0");
+            }
+            other => panic!("expected BalanceNotCredited, got {:?}", other),
+        }
+
+        // Short by one unit
+        assert!(validate_mint_credited(&Nat::from(0u64), &Nat::from(499_999_999u64), &minted).is_err());
+
+        // Balance went down
+        assert!(validate_mint_credited(&Nat::from(1_000u64), &Nat::from(10u64), &minted).is_err());
+    }
+
+    #[test]
+    fn test_supply_drift_from_zero() {
+        let zero = Nat::from(0u64);
+        assert!(validate_supply_unchanged(&zero, &zero, 100).is_ok());
+        assert!(validate_supply_unchanged(&zero, &Nat::from(1u64), 100).is_err());
+    }
+}
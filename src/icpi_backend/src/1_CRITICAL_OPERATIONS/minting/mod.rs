@@ -6,8 +6,10 @@ pub mod mint_validator;
 pub mod refund_handler;
 pub mod mint_orchestrator;
 pub mod fee_handler;
+pub mod impact;
+pub mod recovery;
 
 // Re-export main functions
-pub use mint_state::{MintStatus, PendingMint, MintSnapshot};
+pub use mint_state::{MintStatus, PendingMint, MintSnapshot, get_user_mints};
 pub use mint_orchestrator::{initiate_mint, complete_mint};
 pub use fee_handler::collect_mint_fee;
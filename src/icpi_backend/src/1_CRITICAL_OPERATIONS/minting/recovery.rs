@@ -0,0 +1,534 @@
+//! Recovery of mints interrupted mid-`complete_mint`
+//!
+//! Pending mints survive upgrades, but a mint whose `complete_mint` never
+//! finished keeps its in-progress status with no call left to move it on,
+//! and the deposit it may hold is stranded. Shortly after each upgrade
+//! every such mint is resolved from how far it got:
+//!
+//! - `CollectingFee`, `Snapshotting`: no deposit taken yet → `Failed`
+//! - `Calculating`: deposit held, no ICPI issued → refunded
+//! - `Minting`: the recent ICPI ledger log is searched for the mint block,
+//!   tagged with the mint id and with the amount recomputed from the mint's
+//!   snapshot. Found → `Complete`; provably absent → refunded
+//! - `CollectingDeposit`, `Refunding`, or a `Minting` search that can't
+//!   decide: whether the ledger transfer went through is unknown, so the
+//!   mint becomes `FailedNoRefund` for manual review instead of risking a
+//!   double payout
+//!
+//! `Pending` mints haven't started and are left for the user to complete.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use num_traits::ToPrimitive;
+use std::cell::RefCell;
+use std::time::Duration;
+
+use super::mint_state::{MintStatus, PendingMint, export_state, update_mint_status};
+use super::refund_handler::refund_deposit;
+use super::mint_orchestrator::{mint_ledger_memo, MINT_LEDGER_MEMO_BASE};
+use crate::infrastructure::{Result, IcpiError, MINT_RECOVERY_SCAN_LENGTH, MAX_MINT_RECOVERIES};
+use crate::types::icrc::{LedgerAccount, LedgerMint, LedgerTransaction};
+
+/// What to do with a mint found in an in-progress status
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecoveryPlan {
+    /// No deposit was taken; fail the mint
+    MarkFailed,
+    /// The deposit is held and no ICPI was issued
+    Refund,
+    /// Search the ICPI ledger for the mint block before deciding
+    CheckLedgerMint,
+    /// Progress can't be determined; leave it to an admin
+    Review,
+}
+
+/// How an interrupted mint was resolved
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum RecoveryOutcome {
+    Failed,
+    Completed { block_index: u64, icpi_amount: Nat },
+    Refunded,
+    RefundFailed { reason: String },
+    NeedsReview { reason: String },
+}
+
+/// An interrupted mint and how it was resolved
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MintRecovery {
+    pub mint_id: String,
+    pub user: Principal,
+    pub amount: Nat,
+    pub interrupted_status: MintStatus,
+    pub outcome: RecoveryOutcome,
+    pub timestamp: u64,
+}
+
+/// Result of searching the ICPI ledger for an interrupted mint's block
+#[derive(Clone, Debug, PartialEq)]
+pub enum LedgerMintSearch {
+    Found(u64),
+    Absent,
+    Inconclusive(String),
+}
+
+thread_local! {
+    static RECOVERIES: RefCell<Vec<MintRecovery>> = RefCell::new(Vec::new());
+}
+
+/// Recovery for a mint in `status`; `None` when it needs none
+pub fn plan_recovery(status: &MintStatus) -> Option<RecoveryPlan> {
+    match status {
+        MintStatus::CollectingFee | MintStatus::Snapshotting => Some(RecoveryPlan::MarkFailed),
+        MintStatus::Calculating => Some(RecoveryPlan::Refund),
+        MintStatus::Minting => Some(RecoveryPlan::CheckLedgerMint),
+        MintStatus::CollectingDeposit | MintStatus::Refunding => Some(RecoveryPlan::Review),
+        MintStatus::Pending
+        | MintStatus::Complete(_)
+        | MintStatus::Failed(_)
+        | MintStatus::FailedRefunded(_)
+        | MintStatus::FailedNoRefund(_)
+        | MintStatus::Expired => None,
+    }
+}
+
+/// ICPI the interrupted mint was issuing, recomputed from its snapshot
+pub fn expected_mint_amount(mint: &PendingMint) -> Result<Nat> {
+    let snapshot = mint.snapshot.as_ref()
+        .ok_or_else(|| IcpiError::Other(format!("Mint {} has no snapshot", mint.id)))?;
+    crate::infrastructure::math::calculate_mint_amount(&mint.amount, &snapshot.supply, &snapshot.tvl)
+}
+
+/// Look for the mint `expected` (recipient, amount and memo) at or after `since`
+///
+/// `transactions` start at `first_index`. Mints of the same amount tagged
+/// with another mint id belong to other mints and are skipped; one with no
+/// mint id tag (issued before mints were tagged) could be this one, so it
+/// leaves the search undecided. `complete` is false when part of the window
+/// was archived; `from_log_start` when the window begins at the first
+/// block. Absence is only concluded when the window reaches back past
+/// `since` (or to the start of the log) with nothing missing.
+pub fn search_ledger_mints(
+    transactions: &[LedgerTransaction],
+    first_index: u64,
+    complete: bool,
+    from_log_start: bool,
+    expected: &LedgerMint,
+    since: u64,
+) -> LedgerMintSearch {
+    let tag_prefix = format!("{} #", MINT_LEDGER_MEMO_BASE);
+    let candidates: Vec<(usize, Option<&[u8]>)> = transactions.iter().enumerate()
+        .filter_map(|(position, tx)| {
+            let mint = tx.mint.as_ref()?;
+            let matches = tx.timestamp >= since && mint.to == expected.to && mint.amount == expected.amount;
+            matches.then_some((position, mint.memo.as_ref().map(|m| m.as_slice())))
+        })
+        .collect();
+
+    let memo = expected.memo.as_ref().map(|m| m.as_slice());
+    if let Some((position, _)) = candidates.iter().find(|(_, m)| memo.is_some() && *m == memo) {
+        return LedgerMintSearch::Found(first_index + *position as u64);
+    }
+    if candidates.iter().any(|(_, m)| !m.is_some_and(|m| m.starts_with(tag_prefix.as_bytes()))) {
+        return LedgerMintSearch::Inconclusive("a mint of the same amount has no mint id tag".to_string());
+    }
+
+    if !complete {
+        return LedgerMintSearch::Inconclusive("part of the searched ledger range is archived".to_string());
+    }
+    let reaches_since = transactions.first().is_some_and(|tx| tx.timestamp < since);
+    if from_log_start || reaches_since {
+        LedgerMintSearch::Absent
+    } else {
+        LedgerMintSearch::Inconclusive(format!(
+            "the mint may predate the last {} ledger transactions", MINT_RECOVERY_SCAN_LENGTH
+        ))
+    }
+}
+
+/// Resolve an interrupted mint, returning its final status and the outcome
+///
+/// `refund` returns the deposit; `find_mint` searches the ledger for a mint
+/// of the given ICPI amount. Neither is called unless the plan needs it.
+pub async fn resolve_with<R, RF, L, LF>(mint: &PendingMint, refund: R, find_mint: L) -> Option<(MintStatus, RecoveryOutcome)>
+where
+    R: FnOnce() -> RF,
+    RF: std::future::Future<Output = Result<Nat>>,
+    L: FnOnce(Nat) -> LF,
+    LF: std::future::Future<Output = Result<LedgerMintSearch>>,
+{
+    let interrupted = format!("Interrupted in {:?} by an upgrade", mint.status);
+    let review = |reason: String| (
+        MintStatus::FailedNoRefund(format!("{}: {}. Amount: {}. Needs manual review.", interrupted, reason, mint.amount)),
+        RecoveryOutcome::NeedsReview { reason },
+    );
+
+    let resolved = match plan_recovery(&mint.status)? {
+        RecoveryPlan::MarkFailed => (
+            MintStatus::Failed(format!("{}, before the deposit was collected", interrupted)),
+            RecoveryOutcome::Failed,
+        ),
+        RecoveryPlan::Review => review("the ledger transfer may or may not have executed".to_string()),
+        RecoveryPlan::Refund => refund_outcome(&interrupted, &mint.amount, refund().await),
+        RecoveryPlan::CheckLedgerMint => match expected_mint_amount(mint) {
+            Err(e) => review(format!("mint amount unknown: {}", e)),
+            Ok(icpi_amount) => match find_mint(icpi_amount.clone()).await {
+                Ok(LedgerMintSearch::Found(block_index)) => (
+                    MintStatus::Complete(icpi_amount.clone()),
+                    RecoveryOutcome::Completed { block_index, icpi_amount },
+                ),
+                Ok(LedgerMintSearch::Absent) => refund_outcome(&interrupted, &mint.amount, refund().await),
+                Ok(LedgerMintSearch::Inconclusive(reason)) => review(reason),
+                Err(e) => review(format!("ICPI ledger search failed: {}", e)),
+            },
+        },
+    };
+    Some(resolved)
+}
+
+fn refund_outcome(interrupted: &str, amount: &Nat, refund: Result<Nat>) -> (MintStatus, RecoveryOutcome) {
+    match refund {
+        Ok(_) => (
+            MintStatus::FailedRefunded(format!("{}, deposit refunded", interrupted)),
+            RecoveryOutcome::Refunded,
+        ),
+        Err(e) => (
+            MintStatus::FailedNoRefund(format!("{}. Refund failed: {}. Amount: {}. Contact support.", interrupted, e, amount)),
+            RecoveryOutcome::RefundFailed { reason: e.to_string() },
+        ),
+    }
+}
+
+/// Search the recent ICPI ledger log for an interrupted mint's block
+async fn find_ledger_mint(mint_id: &str, user: Principal, amount: Nat, since: u64) -> Result<LedgerMintSearch> {
+    use crate::infrastructure::icpi_ledger;
+
+    let to_u64 = |value: &Nat, field: &str| value.0.to_u64()
+        .ok_or_else(|| IcpiError::Other(format!("ICPI ledger {} out of range: {}", field, value)));
+
+    let head = icpi_ledger::get_transactions(0, 0).await?;
+    let start = to_u64(&head.log_length, "log_length")?.saturating_sub(MINT_RECOVERY_SCAN_LENGTH);
+    let page = icpi_ledger::get_transactions(start, MINT_RECOVERY_SCAN_LENGTH).await?;
+    let first_index = to_u64(&page.first_index, "first_index")?;
+    let complete = page.archived_transactions.is_empty()
+        && (page.transactions.is_empty() || first_index == start);
+
+    let expected = LedgerMint {
+        to: LedgerAccount { owner: user, subaccount: None },
+        amount,
+        memo: Some(serde_bytes::ByteBuf::from(mint_ledger_memo(mint_id).into_bytes())),
+    };
+    Ok(search_ledger_mints(&page.transactions, first_index, complete, start == 0, &expected, since))
+}
+
+/// Recover interrupted mints once the upgrade has finished, then run cleanup
+///
+/// Cleanup waits for recovery so it can't drop a mint still holding a deposit.
+pub fn schedule_recovery() {
+    ic_cdk_timers::set_timer(Duration::ZERO, || {
+        ic_cdk::spawn(async {
+            recover_interrupted_mints().await;
+            match super::mint_state::cleanup_expired_mints() {
+                Ok(count) if count > 0 => crate::log_op!("🧹 Cleaned up {} expired mints after upgrade", count),
+                Ok(_) => {}
+                Err(e) => crate::log_op!("⚠️ Failed to cleanup expired mints: {}", e),
+            }
+        })
+    });
+}
+
+async fn recover_interrupted_mints() {
+    let mut interrupted: Vec<PendingMint> = export_state().into_values()
+        .filter(|m| plan_recovery(&m.status).is_some())
+        .collect();
+    interrupted.sort_by_key(|m| m.created_at);
+    if interrupted.is_empty() {
+        return;
+    }
+    crate::log_op!("🔧 Recovering {} mints interrupted by the upgrade", interrupted.len());
+
+    for mint in &interrupted {
+        let _guard = match crate::infrastructure::MintGuard::acquire_within(mint.user, u64::MAX) {
+            Ok(guard) => guard,
+            Err(e) => {
+                crate::log_op!("⚠️ Skipping recovery of mint {}: {}", mint.id, e);
+                continue;
+            }
+        };
+
+        let since = mint.last_updated;
+        let resolved = resolve_with(
+            mint,
+            || async move {
+                update_mint_status(&mint.id, MintStatus::Refunding)?;
+                refund_deposit(mint.user, mint.amount.clone()).await
+            },
+            |icpi_amount| find_ledger_mint(&mint.id, mint.user, icpi_amount, since),
+        ).await;
+        let Some((status, outcome)) = resolved else { continue };
+
+        if let Err(e) = update_mint_status(&mint.id, status) {
+            crate::log_op!("🚨 CRITICAL: Could not record recovery of mint {}: {}", mint.id, e);
+        }
+        let now = crate::infrastructure::runtime::time();
+        if let RecoveryOutcome::Completed { icpi_amount, .. } = &outcome {
+            crate::_5_INFORMATIONAL::daily_totals::record_mint(now, icpi_amount, &mint.amount);
+            crate::_5_INFORMATIONAL::user_activity::record_mint(
+                mint.user, &mint.amount, icpi_amount, now, &crate::infrastructure::config::get_config(),
+            );
+            crate::_1_CRITICAL_OPERATIONS::rebalancing::invalidate_projection();
+        }
+        crate::log_op!("🔧 Mint {} ({:?}) recovered: {:?}", mint.id, mint.status, outcome);
+        record_recovery(MintRecovery {
+            mint_id: mint.id.clone(),
+            user: mint.user,
+            amount: mint.amount.clone(),
+            interrupted_status: mint.status.clone(),
+            outcome,
+            timestamp: now,
+        });
+    }
+}
+
+fn record_recovery(recovery: MintRecovery) {
+    RECOVERIES.with(|r| {
+        let mut r = r.borrow_mut();
+        r.push(recovery);
+        if r.len() > MAX_MINT_RECOVERIES {
+            let excess = r.len() - MAX_MINT_RECOVERIES;
+            r.drain(..excess);
+        }
+    });
+}
+
+/// Interrupted mints resolved since the last upgrade, oldest first
+pub fn get_mint_recoveries() -> Vec<MintRecovery> {
+    RECOVERIES.with(|r| r.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::mint_state::MintSnapshot;
+    use std::cell::Cell;
+
+    fn user() -> Principal {
+        Principal::from_text("2vxsx-fae").unwrap()
+    }
+
+    fn mint(status: MintStatus) -> PendingMint {
+        PendingMint {
+            id: "m".to_string(),
+            user: user(),
+            amount: Nat::from(10_000_000u64), // $10
+            status,
+            created_at: 1,
+            last_updated: 100,
+            // $100 TVL, 100 ICPI: $10 mints 10 ICPI
+            snapshot: Some(MintSnapshot {
+                supply: Nat::from(10_000_000_000u64),
+                tvl: Nat::from(100_000_000u64),
+                timestamp: 50,
+            }),
+            memo: None,
+            request_id: None,
+        }
+    }
+
+    fn ledger_mint(to: Principal, amount: u64, mint_id: &str) -> LedgerMint {
+        LedgerMint {
+            to: LedgerAccount { owner: to, subaccount: None },
+            amount: Nat::from(amount),
+            memo: Some(serde_bytes::ByteBuf::from(mint_ledger_memo(mint_id).into_bytes())),
+        }
+    }
+
+    fn mint_tx(to: Principal, amount: u64, timestamp: u64, mint_id: &str) -> LedgerTransaction {
+        LedgerTransaction {
+            kind: "mint".to_string(),
+            mint: Some(ledger_mint(to, amount, mint_id)),
+            burn: None,
+            transfer: None,
+            approve: None,
+            timestamp,
+        }
+    }
+
+    /// Resolve with mocked effects, counting refunds and ledger searches
+    fn resolve(mint: &PendingMint, refund: Result<Nat>, search: Result<LedgerMintSearch>) -> (Option<(MintStatus, RecoveryOutcome)>, u32, u32) {
+        let refunds = Cell::new(0u32);
+        let searches = Cell::new(0u32);
+        let resolved = futures::executor::block_on(resolve_with(
+            mint,
+            || {
+                refunds.set(refunds.get() + 1);
+                async move { refund }
+            },
+            |amount| {
+                searches.set(searches.get() + 1);
+                assert_eq!(amount, Nat::from(1_000_000_000u64));
+                async move { search }
+            },
+        ));
+        (resolved, refunds.get(), searches.get())
+    }
+
+    fn refunded() -> Result<Nat> {
+        Ok(Nat::from(7u64))
+    }
+
+    #[test]
+    fn test_no_recovery_for_pending_or_terminal() {
+        for status in [
+            MintStatus::Pending,
+            MintStatus::Complete(Nat::from(1u64)),
+            MintStatus::Failed(String::new()),
+            MintStatus::FailedRefunded(String::new()),
+            MintStatus::FailedNoRefund(String::new()),
+            MintStatus::Expired,
+        ] {
+            assert_eq!(plan_recovery(&status), None);
+            let (resolved, refunds, searches) = resolve(&mint(status), refunded(), Ok(LedgerMintSearch::Absent));
+            assert!(resolved.is_none());
+            assert_eq!((refunds, searches), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_recover_before_deposit_marks_failed() {
+        for status in [MintStatus::CollectingFee, MintStatus::Snapshotting] {
+            let (resolved, refunds, searches) = resolve(&mint(status), refunded(), Ok(LedgerMintSearch::Absent));
+            let (status, outcome) = resolved.unwrap();
+            assert!(matches!(status, MintStatus::Failed(_)));
+            assert_eq!(outcome, RecoveryOutcome::Failed);
+            assert_eq!((refunds, searches), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_recover_collecting_deposit_needs_review() {
+        let (resolved, refunds, searches) = resolve(&mint(MintStatus::CollectingDeposit), refunded(), Ok(LedgerMintSearch::Absent));
+        let (status, outcome) = resolved.unwrap();
+        // Still counted as holding the deposit until reviewed
+        assert!(status.holds_deposit());
+        assert!(matches!(outcome, RecoveryOutcome::NeedsReview { .. }));
+        assert_eq!((refunds, searches), (0, 0));
+    }
+
+    #[test]
+    fn test_recover_refunding_needs_review() {
+        let (resolved, refunds, _) = resolve(&mint(MintStatus::Refunding), refunded(), Ok(LedgerMintSearch::Absent));
+        let (status, outcome) = resolved.unwrap();
+        assert!(matches!(status, MintStatus::FailedNoRefund(_)));
+        assert!(matches!(outcome, RecoveryOutcome::NeedsReview { .. }));
+        // Never refunded a second time
+        assert_eq!(refunds, 0);
+    }
+
+    #[test]
+    fn test_recover_calculating_refunds() {
+        let (resolved, refunds, searches) = resolve(&mint(MintStatus::Calculating), refunded(), Ok(LedgerMintSearch::Absent));
+        let (status, outcome) = resolved.unwrap();
+        assert!(matches!(status, MintStatus::FailedRefunded(_)));
+        assert_eq!(outcome, RecoveryOutcome::Refunded);
+        assert_eq!((refunds, searches), (1, 0));
+
+        let failed = Err(IcpiError::Other("ledger down".to_string()));
+        let (resolved, _, _) = resolve(&mint(MintStatus::Calculating), failed, Ok(LedgerMintSearch::Absent));
+        let (status, outcome) = resolved.unwrap();
+        assert!(matches!(status, MintStatus::FailedNoRefund(_)));
+        assert_eq!(outcome, RecoveryOutcome::RefundFailed { reason: "ledger down".to_string() });
+    }
+
+    #[test]
+    fn test_recover_minting_found_on_ledger_completes() {
+        let (resolved, refunds, searches) = resolve(&mint(MintStatus::Minting), refunded(), Ok(LedgerMintSearch::Found(42)));
+        let (status, outcome) = resolved.unwrap();
+        assert!(matches!(status, MintStatus::Complete(ref amount) if *amount == Nat::from(1_000_000_000u64)));
+        assert_eq!(outcome, RecoveryOutcome::Completed { block_index: 42, icpi_amount: Nat::from(1_000_000_000u64) });
+        assert_eq!((refunds, searches), (0, 1));
+    }
+
+    #[test]
+    fn test_recover_minting_absent_from_ledger_refunds() {
+        let (resolved, refunds, searches) = resolve(&mint(MintStatus::Minting), refunded(), Ok(LedgerMintSearch::Absent));
+        let (status, outcome) = resolved.unwrap();
+        assert!(matches!(status, MintStatus::FailedRefunded(_)));
+        assert_eq!(outcome, RecoveryOutcome::Refunded);
+        assert_eq!((refunds, searches), (1, 1));
+    }
+
+    #[test]
+    fn test_recover_minting_undecided_needs_review() {
+        let inconclusive = Ok(LedgerMintSearch::Inconclusive("archived".to_string()));
+        let (resolved, refunds, _) = resolve(&mint(MintStatus::Minting), refunded(), inconclusive);
+        assert!(matches!(resolved.unwrap().1, RecoveryOutcome::NeedsReview { ref reason } if reason == "archived"));
+        assert_eq!(refunds, 0);
+
+        let search_failed = Err(IcpiError::Other("ledger down".to_string()));
+        let (resolved, refunds, _) = resolve(&mint(MintStatus::Minting), refunded(), search_failed);
+        assert!(matches!(resolved.unwrap().1, RecoveryOutcome::NeedsReview { .. }));
+        assert_eq!(refunds, 0);
+
+        // Without a snapshot the amount to look for is unknown
+        let no_snapshot = PendingMint { snapshot: None, ..mint(MintStatus::Minting) };
+        let (resolved, refunds, searches) = resolve(&no_snapshot, refunded(), Ok(LedgerMintSearch::Absent));
+        assert!(matches!(resolved.unwrap().1, RecoveryOutcome::NeedsReview { .. }));
+        assert_eq!((refunds, searches), (0, 0));
+    }
+
+    #[test]
+    fn test_search_ledger_mints() {
+        let other = Principal::from_text("aaaaa-aa").unwrap();
+        let expected = ledger_mint(user(), 1_000_000_000, "m");
+        let txs = vec![
+            mint_tx(user(), 1_000_000_000, 90, "m"),    // Before the mint began
+            mint_tx(other, 1_000_000_000, 110, "m"),    // Someone else
+            mint_tx(user(), 999, 120, "m"),             // Different amount
+            mint_tx(user(), 1_000_000_000, 130, "m"),   // The interrupted mint
+        ];
+        assert_eq!(search_ledger_mints(&txs, 500, true, false, &expected, 100), LedgerMintSearch::Found(503));
+
+        // Window reaches back before the mint began: provably absent
+        assert_eq!(search_ledger_mints(&txs[..3], 500, true, false, &expected, 100), LedgerMintSearch::Absent);
+        // Window starts after the mint began and isn't the whole log
+        assert!(matches!(
+            search_ledger_mints(&txs[1..3], 501, true, false, &expected, 100),
+            LedgerMintSearch::Inconclusive(_)
+        ));
+        // ...unless it starts at the first block
+        assert_eq!(search_ledger_mints(&txs[1..3], 0, true, true, &expected, 100), LedgerMintSearch::Absent);
+        // Archived gaps leave absence unproven
+        assert!(matches!(
+            search_ledger_mints(&txs[..3], 500, false, false, &expected, 100),
+            LedgerMintSearch::Inconclusive(_)
+        ));
+    }
+
+    #[test]
+    fn test_same_amount_mints_resolve_to_their_own_blocks() {
+        let search = |txs: &[LedgerTransaction], mint_id: &str| {
+            search_ledger_mints(txs, 500, true, false, &ledger_mint(user(), 1_000_000_000, mint_id), 100)
+        };
+        let txs = vec![
+            mint_tx(user(), 1_000_000_000, 90, "m0"),
+            mint_tx(user(), 1_000_000_000, 120, "m1"),
+            mint_tx(user(), 1_000_000_000, 130, "m2"),
+            mint_tx(user(), 1_000_000_000, 140, "later"),
+        ];
+
+        assert_eq!(search(&txs, "m1"), LedgerMintSearch::Found(501));
+        assert_eq!(search(&txs, "m2"), LedgerMintSearch::Found(502));
+        // Another same-amount mint, interrupted before its block: not matched
+        // to its neighbours' blocks
+        assert_eq!(search(&txs, "m3"), LedgerMintSearch::Absent);
+
+        // A same-amount mint without the tag could be any of them
+        let mut untagged = mint_tx(user(), 1_000_000_000, 150, "m4");
+        untagged.mint.as_mut().unwrap().memo = Some(serde_bytes::ByteBuf::from(b"ICPI minting".to_vec()));
+        assert!(matches!(search(&[txs[0].clone(), untagged], "m3"), LedgerMintSearch::Inconclusive(_)));
+    }
+
+    #[test]
+    fn test_expected_amount_matches_original_calculation() {
+        assert_eq!(expected_mint_amount(&mint(MintStatus::Minting)).unwrap(), Nat::from(1_000_000_000u64));
+    }
+}
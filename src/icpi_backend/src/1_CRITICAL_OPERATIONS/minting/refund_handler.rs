@@ -1,56 +1,75 @@
 //! Refund handling for failed mints
 
 use candid::{Nat, Principal};
-use crate::infrastructure::{Result, IcpiError, MintError};
-use crate::infrastructure::constants::CKUSDT_CANISTER_ID;
-use crate::types::{Account, TransferArgs};
+use crate::infrastructure::{Result, IcpiError, MintError, ckusdt_ledger, ledger_fees};
+
+/// Amount sent back for a `deposit`: the deposit minus the ledger fee
+///
+/// `None` when the deposit doesn't cover the fee.
+pub fn refund_amount(deposit: &Nat, fee: u64) -> Option<Nat> {
+    let fee = Nat::from(fee);
+    if *deposit > fee {
+        Some(deposit.clone() - fee)
+    } else {
+        None
+    }
+}
 
 pub async fn refund_deposit(user: Principal, amount: Nat) -> Result<Nat> {
-    ic_cdk::println!("Refunding {} to {}", amount, user);
-
-    let ckusdt = Principal::from_text(CKUSDT_CANISTER_ID)
-        .map_err(|e| IcpiError::Mint(MintError::RefundFailed {
-            user: user.to_text(),
-            amount: amount.to_string(),
-            reason: format!("Invalid ckUSDT principal: {}", e),
-        }))?;
-
-    let transfer_args = TransferArgs {
-        to: Account {
-            owner: user,
-            subaccount: None,
-        },
-        amount: amount.clone(),
-        fee: None,
-        memo: Some(b"ICPI mint refund".to_vec()),
-        from_subaccount: None,
-        created_at_time: None,
-    };
-
-    let result: std::result::Result<(crate::types::icrc::TransferResult,), _> = ic_cdk::call(
-        ckusdt,
-        "icrc1_transfer",
-        (transfer_args,)
-    ).await;
-
-    match result {
-        Ok((crate::types::icrc::TransferResult::Ok(block),)) => {
-            ic_cdk::println!("Refund successful: block {}", block);
+    let fee = ledger_fees::ckusdt_fee();
+    let refund = refund_amount(&amount, fee).ok_or_else(|| IcpiError::Mint(MintError::RefundFailed {
+        user: user.to_text(),
+        amount: amount.to_string(),
+        reason: format!("Deposit does not cover the {} ledger fee", fee),
+    }))?;
+
+    crate::log_op!("Refunding {} to {} (deposit {}, fee {})", refund, user, amount, fee);
+
+    match ckusdt_ledger::transfer(user, refund.clone(), b"ICPI mint refund").await {
+        Ok(block) => {
+            crate::log_op!("Refund successful: block {}", block);
+            crate::_2_CRITICAL_DATA::ckusdt_reserves::record_outflow(&(refund + Nat::from(fee)));
+            crate::_5_INFORMATIONAL::costs::record_cost(
+                crate::_5_INFORMATIONAL::costs::CostKind::LedgerFee,
+                fee,
+            );
             Ok(block)
         }
-        Ok((crate::types::icrc::TransferResult::Err(e),)) => {
-            Err(IcpiError::Mint(MintError::RefundFailed {
-                user: user.to_text(),
-                amount: amount.to_string(),
-                reason: format!("{:?}", e),
-            }))
-        }
-        Err((code, msg)) => {
+        Err(e) => {
             Err(IcpiError::Mint(MintError::RefundFailed {
                 user: user.to_text(),
                 amount: amount.to_string(),
-                reason: format!("Call failed: {:?} - {}", code, msg),
+                reason: e.to_string(),
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refund_deducts_default_fee() {
+        let deposit = Nat::from(10_000_000u64);
+        assert_eq!(refund_amount(&deposit, crate::infrastructure::CKUSDT_TRANSFER_FEE), Some(Nat::from(9_990_000u64)));
+    }
+
+    #[test]
+    fn test_refund_with_non_default_fees() {
+        let deposit = Nat::from(10_000_000u64);
+        // Raised fee
+        assert_eq!(refund_amount(&deposit, 50_000), Some(Nat::from(9_950_000u64)));
+        // Lowered fee
+        assert_eq!(refund_amount(&deposit, 1_000), Some(Nat::from(9_999_000u64)));
+        // Fee-free ledger refunds everything
+        assert_eq!(refund_amount(&deposit, 0), Some(deposit.clone()));
+    }
+
+    #[test]
+    fn test_refund_not_covering_fee() {
+        assert_eq!(refund_amount(&Nat::from(20_000u64), 20_000), None);
+        assert_eq!(refund_amount(&Nat::from(19_999u64), 20_000), None);
+        assert_eq!(refund_amount(&Nat::from(20_001u64), 20_000), Some(Nat::from(1u64)));
+    }
+}
@@ -4,6 +4,8 @@
 pub mod minting;
 pub mod burning;
 pub mod rebalancing;
+pub mod operation_status;
+pub mod stray_deposits;
 
 // Re-export main functions
 pub use minting::{initiate_mint, complete_mint};
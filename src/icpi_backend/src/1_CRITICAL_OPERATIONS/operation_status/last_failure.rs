@@ -0,0 +1,187 @@
+//! Last terminal failure of each user's mint and burn
+//!
+//! Lets users and support see why an operation failed without scanning
+//! logs. One entry per principal, capped at `MAX_FAILURE_ENTRIES`; the
+//! principal whose failure was recorded longest ago is evicted first.
+
+use candid::{CandidType, Deserialize, Principal};
+use std::cell::RefCell;
+
+use crate::infrastructure::IcpiError;
+use crate::infrastructure::errors::{classify, error_code};
+
+const MAX_FAILURE_ENTRIES: usize = 1000;
+
+/// Operation a failure is recorded for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailedOperation {
+    Mint,
+    Burn,
+}
+
+/// A terminal failure, as returned by `get_my_last_failures`
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct OperationFailure {
+    /// Error variant, e.g. `Mint.InsufficientTVL`
+    pub code: String,
+    pub message: String,
+    pub timestamp: u64,
+    /// Mint id for mints, request id for burns
+    pub operation_id: Option<String>,
+}
+
+/// A user's most recent mint and burn failures
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct LastFailures {
+    pub mint: Option<OperationFailure>,
+    pub burn: Option<OperationFailure>,
+}
+
+/// Persisted entry
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserFailures {
+    pub user: Principal,
+    pub failures: LastFailures,
+}
+
+thread_local! {
+    /// Least recently written first
+    static LAST_FAILURES: RefCell<Vec<UserFailures>> = RefCell::new(Vec::new());
+}
+
+/// Record `error` as the user's last failure of `operation`
+pub fn record_failure(user: Principal, operation: FailedOperation, error: &IcpiError, operation_id: Option<String>) {
+    record(user, operation, OperationFailure {
+        code: error_code(error),
+        message: error.to_string(),
+        timestamp: crate::infrastructure::runtime::time(),
+        operation_id,
+    });
+}
+
+/// Whether `error` ends the operation for good
+///
+/// Retryable errors (a busy guard, a rebalance in progress, an unreachable
+/// canister) leave nothing for support to look at: the same call may
+/// succeed as is, so they are not recorded as the user's last failure.
+pub fn is_terminal(error: &IcpiError) -> bool {
+    let (_, retryable) = classify(error);
+    !retryable
+}
+
+fn record(user: Principal, operation: FailedOperation, failure: OperationFailure) {
+    LAST_FAILURES.with(|entries| {
+        let mut entries = entries.borrow_mut();
+        let mut entry = match entries.iter().position(|e| e.user == user) {
+            Some(i) => entries.remove(i),
+            None => UserFailures { user, failures: LastFailures::default() },
+        };
+        match operation {
+            FailedOperation::Mint => entry.failures.mint = Some(failure),
+            FailedOperation::Burn => entry.failures.burn = Some(failure),
+        }
+        entries.push(entry);
+
+        let len = entries.len();
+        if len > MAX_FAILURE_ENTRIES {
+            entries.drain(0..(len - MAX_FAILURE_ENTRIES));
+        }
+    });
+}
+
+/// A user's last failures (both None if nothing is recorded)
+pub fn get_last_failures(user: &Principal) -> LastFailures {
+    LAST_FAILURES.with(|entries| {
+        entries.borrow()
+            .iter()
+            .find(|e| e.user == *user)
+            .map(|e| e.failures.clone())
+            .unwrap_or_default()
+    })
+}
+
+/// Export failures for stable storage
+pub fn export_failures() -> Vec<UserFailures> {
+    LAST_FAILURES.with(|entries| entries.borrow().clone())
+}
+
+/// Import failures from stable storage
+pub fn import_failures(entries: Vec<UserFailures>) {
+    LAST_FAILURES.with(|state| *state.borrow_mut() = entries);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(n: u32) -> Principal {
+        Principal::from_slice(&n.to_be_bytes())
+    }
+
+    fn failure(timestamp: u64) -> OperationFailure {
+        OperationFailure {
+            code: "Mint.InsufficientTVL".to_string(),
+            message: String::new(),
+            timestamp,
+            operation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_mint_and_burn_kept_separately() {
+        import_failures(Vec::new());
+        let alice = user(1);
+
+        record(alice, FailedOperation::Mint, failure(1));
+        record(alice, FailedOperation::Burn, failure(2));
+        record(alice, FailedOperation::Mint, failure(3));
+
+        let last = get_last_failures(&alice);
+        assert_eq!(last.mint.map(|f| f.timestamp), Some(3));
+        assert_eq!(last.burn.map(|f| f.timestamp), Some(2));
+        assert_eq!(get_last_failures(&user(2)), LastFailures::default());
+        assert_eq!(export_failures().len(), 1);
+    }
+
+    #[test]
+    fn test_only_non_retryable_errors_are_terminal() {
+        use crate::infrastructure::errors::{BurnError, SystemError};
+
+        assert!(is_terminal(&IcpiError::Burn(BurnError::NoSupply)));
+        assert!(!is_terminal(&IcpiError::System(SystemError::RebalancingInProgress)));
+        assert!(!is_terminal(&IcpiError::System(SystemError::OperationInProgress {
+            operation: "burn".to_string(),
+            user: String::new(),
+        })));
+    }
+
+    #[test]
+    fn test_least_recently_failed_evicted() {
+        import_failures(Vec::new());
+        for n in 0..MAX_FAILURE_ENTRIES as u32 {
+            record(user(n), FailedOperation::Burn, failure(n as u64));
+        }
+        // A new failure for the oldest user moves it to the back
+        record(user(0), FailedOperation::Mint, failure(5_000));
+        record(user(9_999), FailedOperation::Burn, failure(5_001));
+
+        assert_eq!(export_failures().len(), MAX_FAILURE_ENTRIES);
+        assert!(get_last_failures(&user(0)).mint.is_some());
+        assert!(get_last_failures(&user(0)).burn.is_some());
+        assert_eq!(get_last_failures(&user(1)), LastFailures::default());
+        assert!(get_last_failures(&user(9_999)).burn.is_some());
+    }
+
+    #[test]
+    fn test_oversized_import_trimmed_on_next_write() {
+        let entries = (0..MAX_FAILURE_ENTRIES as u32 + 10)
+            .map(|n| UserFailures { user: user(n), failures: LastFailures::default() })
+            .collect();
+        import_failures(entries);
+        record(user(50_000), FailedOperation::Mint, failure(1));
+
+        assert_eq!(export_failures().len(), MAX_FAILURE_ENTRIES);
+        assert_eq!(get_last_failures(&user(10)), LastFailures::default());
+        assert!(get_last_failures(&user(50_000)).mint.is_some());
+    }
+}
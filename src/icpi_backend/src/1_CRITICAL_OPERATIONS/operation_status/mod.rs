@@ -0,0 +1,145 @@
+//! Cross-operation conflict checks
+//!
+//! A user's mint and burn must not interleave: both pull the 0.1 ckUSDT fee
+//! and check balances, so overlapping them produces confusing failures.
+//!
+//! - `burn_icpi` is refused while the user has an unfinished mint
+//! - `complete_mint` is refused while the user holds a BurnGuard
+
+pub mod last_failure;
+
+use candid::{CandidType, Deserialize, Principal};
+use crate::infrastructure::{Result, IcpiError, SystemError};
+use crate::infrastructure::reentrancy;
+use super::minting::{MintStatus, PendingMint};
+use super::minting::mint_state;
+
+/// The operation blocking another one
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct OperationConflict {
+    pub operation: String,
+    pub operation_id: Option<String>,
+}
+
+/// Per-user view of in-flight operations
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OperationStatus {
+    pub global_operation: String,
+    pub mint_in_progress: bool,
+    pub burn_in_progress: bool,
+    pub unfinished_mint_id: Option<String>,
+    pub unfinished_mint_status: Option<MintStatus>,
+    pub burn_blocked_by: Option<OperationConflict>,
+    pub mint_blocked_by: Option<OperationConflict>,
+}
+
+/// Conflict for a new burn, given the user's unfinished mint (if any)
+pub fn burn_conflict(unfinished_mint: Option<&PendingMint>) -> Option<OperationConflict> {
+    unfinished_mint.map(|mint| OperationConflict {
+        operation: "mint".to_string(),
+        operation_id: Some(mint.id.clone()),
+    })
+}
+
+/// Conflict for completing a mint, given whether the user is burning
+pub fn mint_conflict(burn_in_progress: bool) -> Option<OperationConflict> {
+    if burn_in_progress {
+        Some(OperationConflict {
+            operation: "burn".to_string(),
+            operation_id: None,
+        })
+    } else {
+        None
+    }
+}
+
+fn conflict_error(conflict: OperationConflict) -> IcpiError {
+    IcpiError::System(SystemError::ConflictingOperation {
+        operation: conflict.operation,
+        operation_id: conflict.operation_id,
+    })
+}
+
+/// Refuse a burn while the user has an unfinished mint
+pub fn check_burn_allowed(user: &Principal) -> Result<()> {
+    let unfinished = mint_state::find_unfinished_mint(user);
+    match burn_conflict(unfinished.as_ref()) {
+        Some(conflict) => Err(conflict_error(conflict)),
+        None => Ok(()),
+    }
+}
+
+/// Refuse completing a mint while the user is burning
+pub fn check_mint_allowed(user: &Principal) -> Result<()> {
+    match mint_conflict(reentrancy::is_burning(user)) {
+        Some(conflict) => Err(conflict_error(conflict)),
+        None => Ok(()),
+    }
+}
+
+/// Get the in-flight operation status for a user
+pub fn get_operation_status(user: &Principal) -> OperationStatus {
+    let unfinished = mint_state::find_unfinished_mint(user);
+    let burn_in_progress = reentrancy::is_burning(user);
+
+    OperationStatus {
+        global_operation: reentrancy::get_current_operation().as_str().to_string(),
+        mint_in_progress: reentrancy::is_minting(user),
+        burn_in_progress,
+        unfinished_mint_id: unfinished.as_ref().map(|m| m.id.clone()),
+        unfinished_mint_status: unfinished.as_ref().map(|m| m.status.clone()),
+        burn_blocked_by: burn_conflict(unfinished.as_ref()),
+        mint_blocked_by: mint_conflict(burn_in_progress),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Nat;
+
+    fn pending_mint(id: &str) -> PendingMint {
+        PendingMint {
+            id: id.to_string(),
+            user: Principal::anonymous(),
+            amount: Nat::from(1_000_000u64),
+            status: MintStatus::CollectingDeposit,
+            created_at: 0,
+            last_updated: 0,
+            snapshot: None,
+            memo: None,
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn test_burn_conflict_names_mint() {
+        assert!(burn_conflict(None).is_none());
+
+        let mint = pending_mint("mint_abc_1");
+        assert_eq!(burn_conflict(Some(&mint)), Some(OperationConflict {
+            operation: "mint".to_string(),
+            operation_id: Some("mint_abc_1".to_string()),
+        }));
+    }
+
+    #[test]
+    fn test_mint_conflict_names_burn() {
+        assert!(mint_conflict(false).is_none());
+        assert_eq!(mint_conflict(true).unwrap().operation, "burn");
+    }
+
+    #[test]
+    fn test_check_mint_allowed_while_burning() {
+        let _runtime = crate::infrastructure::runtime::mock::MockRuntime::install(1_700_000_000_000_000_000);
+        let user = Principal::from_text("2vxsx-fae").unwrap();
+        assert!(check_mint_allowed(&user).is_ok());
+
+        let _guard = reentrancy::BurnGuard::acquire(user).unwrap();
+        let result = check_mint_allowed(&user);
+        assert!(matches!(
+            result,
+            Err(IcpiError::System(SystemError::ConflictingOperation { ref operation, .. })) if operation == "burn"
+        ));
+    }
+}
@@ -0,0 +1,321 @@
+//! Dust Sweep
+//!
+//! Redemptions truncate down, so the backend slowly accumulates tiny
+//! balances that are too small to matter for allocation but still sit
+//! outside ckUSDT. The sweep converts token balances worth less than
+//! `DUST_SWEEP_THRESHOLD_USD` back to ckUSDT and records what was swept.
+//!
+//! Admin-triggered only; runs under the same locks as a rebalance cycle.
+
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat};
+use crate::infrastructure::{Result, DUST_SWEEP_THRESHOLD_USD};
+use crate::_4_TRADING_EXECUTION::slippage::MAX_TRADE_SLIPPAGE;
+use crate::types::{TrackedToken, portfolio::CurrentPosition};
+
+/// Maximum number of sweep records kept
+const MAX_SWEEP_LOG: usize = 500;
+
+/// A token balance identified as dust
+#[derive(Debug, Clone, PartialEq)]
+pub struct DustCandidate {
+    pub token: TrackedToken,
+    pub sweep_amount: Nat,
+    pub estimated_value_usd: f64,
+}
+
+/// Record of one swept token
+#[derive(Debug, Clone, CandidType, Deserialize, serde::Serialize)]
+pub struct DustSweepRecord {
+    pub timestamp: u64,
+    pub token: TrackedToken,
+    pub amount_swept: Nat,
+    pub estimated_value_usd: f64,
+    pub ckusdt_received: Nat,
+    pub success: bool,
+    pub details: String,
+}
+
+/// Summary of one sweep run
+#[derive(Debug, Clone, CandidType, Deserialize, serde::Serialize)]
+pub struct DustSweepReport {
+    pub tokens_swept: u32,
+    pub tokens_failed: u32,
+    pub total_ckusdt_received: Nat,
+    pub total_estimated_value_usd: f64,
+    pub records: Vec<DustSweepRecord>,
+}
+
+thread_local! {
+    static SWEEP_LOG: RefCell<Vec<DustSweepRecord>> = RefCell::new(Vec::new());
+}
+
+/// Whether a position's value is in the dust range: positive but below
+/// `threshold_usd` (ckUSDT is never dust)
+fn in_dust_range(position: &CurrentPosition, threshold_usd: f64) -> bool {
+    position.token != TrackedToken::ckUSDT
+        && position.usd_value > 0.0
+        && position.usd_value < threshold_usd
+}
+
+/// Identify dust balances in the current positions
+///
+/// A position is dust if its value is positive but below `threshold_usd`.
+/// Two of the token's own transfer fees (approval + swap pull), from
+/// `fees`, are held back from the swept amount; positions that can't cover
+/// them, or whose fee is unknown, are left alone.
+pub fn identify_dust(positions: &[CurrentPosition], threshold_usd: f64, fees: &[(TrackedToken, u64)]) -> Vec<DustCandidate> {
+    positions.iter()
+        .filter(|p| in_dust_range(p, threshold_usd))
+        .filter_map(|p| {
+            let fee = fees.iter().find(|(token, _)| *token == p.token).map(|(_, fee)| *fee)?;
+            let fee_reserve = Nat::from(fee.saturating_mul(2));
+            (p.balance > fee_reserve).then(|| DustCandidate {
+                token: p.token.clone(),
+                sweep_amount: p.balance.clone() - fee_reserve,
+                estimated_value_usd: p.usd_value,
+            })
+        })
+        .collect()
+}
+
+/// Live ledger fees of the tokens in the dust range
+///
+/// A token whose fee can't be fetched is logged and left out.
+async fn dust_token_fees(positions: &[CurrentPosition], threshold_usd: f64) -> Vec<(TrackedToken, u64)> {
+    let fee_futures = positions.iter()
+        .filter(|p| in_dust_range(p, threshold_usd))
+        .map(|p| async move { (p.token.clone(), crate::infrastructure::ledger_fees::token_fee(&p.token).await) });
+
+    let mut fees = Vec::new();
+    for (token, fee) in futures::future::join_all(fee_futures).await {
+        match fee {
+            Ok(fee) => fees.push((token, fee)),
+            Err(e) => ic_cdk::println!("⚠️ Not sweeping {}: ledger fee unavailable: {}", token.to_symbol(), e),
+        }
+    }
+    fees
+}
+
+/// Summarize sweep records into a report
+pub fn summarize_sweep(records: Vec<DustSweepRecord>) -> DustSweepReport {
+    let mut total_ckusdt_received = Nat::from(0u64);
+    let mut total_estimated_value_usd = 0.0;
+    let mut tokens_swept = 0;
+    let mut tokens_failed = 0;
+
+    for record in &records {
+        if record.success {
+            tokens_swept += 1;
+            total_ckusdt_received += record.ckusdt_received.clone();
+            total_estimated_value_usd += record.estimated_value_usd;
+        } else {
+            tokens_failed += 1;
+        }
+    }
+
+    DustSweepReport {
+        tokens_swept,
+        tokens_failed,
+        total_ckusdt_received,
+        total_estimated_value_usd,
+        records,
+    }
+}
+
+/// Sweep dust balances to ckUSDT (admin only, enforced at the API layer)
+pub async fn sweep_dust() -> Result<DustSweepReport> {
+    crate::infrastructure::check_not_paused()?;
+
+    super::begin_exclusive_rebalance()?;
+    let result = sweep_dust_inner().await;
+    super::end_exclusive_rebalance();
+
+    result
+}
+
+async fn sweep_dust_inner() -> Result<DustSweepReport> {
+    ic_cdk::println!("🧹 Starting dust sweep (threshold: ${:.2})", DUST_SWEEP_THRESHOLD_USD);
+
+    let state = crate::_2_CRITICAL_DATA::portfolio_value::get_portfolio_state_uncached().await?;
+    let fees = dust_token_fees(&state.current_positions, DUST_SWEEP_THRESHOLD_USD).await;
+    let candidates = identify_dust(&state.current_positions, DUST_SWEEP_THRESHOLD_USD, &fees);
+
+    if candidates.is_empty() {
+        ic_cdk::println!("✅ No dust to sweep");
+        return Ok(summarize_sweep(Vec::new()));
+    }
+
+    // Sequential swaps (Kongswap limitation)
+    let budget = crate::infrastructure::InstructionBudget::from_config();
+    let total = candidates.len();
+    let mut records = Vec::new();
+    for (index, candidate) in candidates.into_iter().enumerate() {
+        if budget.exhausted() {
+            ic_cdk::println!("⏸ Dust sweep stopped early, {} tokens left for the next sweep", total - index);
+            break;
+        }
+
+        let swap_result = crate::_4_TRADING_EXECUTION::swaps::execute_swap(
+            &candidate.token,
+            candidate.sweep_amount.clone(),
+            &TrackedToken::ckUSDT,
+            MAX_TRADE_SLIPPAGE,
+        ).await;
+
+        let record = match swap_result {
+            Ok(reply) => {
+                ic_cdk::println!(
+                    "✅ Swept {} {} for {} ckUSDT",
+                    candidate.sweep_amount,
+                    candidate.token.to_symbol(),
+                    reply.receive_amount
+                );
+                DustSweepRecord {
+                    timestamp: crate::infrastructure::runtime::time(),
+                    token: candidate.token,
+                    amount_swept: candidate.sweep_amount,
+                    estimated_value_usd: candidate.estimated_value_usd,
+                    ckusdt_received: reply.receive_amount,
+                    success: true,
+                    details: format!("slippage: {:.4}%", reply.slippage),
+                }
+            }
+            Err(e) => {
+                ic_cdk::println!("❌ Dust sweep failed for {}: {}", candidate.token.to_symbol(), e);
+                DustSweepRecord {
+                    timestamp: crate::infrastructure::runtime::time(),
+                    token: candidate.token,
+                    amount_swept: Nat::from(0u64),
+                    estimated_value_usd: candidate.estimated_value_usd,
+                    ckusdt_received: Nat::from(0u64),
+                    success: false,
+                    details: e.to_string(),
+                }
+            }
+        };
+        records.push(record);
+    }
+
+    SWEEP_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        log.extend(records.iter().cloned());
+        let len = log.len();
+        if len > MAX_SWEEP_LOG {
+            log.drain(0..(len - MAX_SWEEP_LOG));
+        }
+    });
+
+    let report = summarize_sweep(records);
+    ic_cdk::println!(
+        "🧹 Dust sweep complete: {} swept, {} failed, {} ckUSDT received",
+        report.tokens_swept,
+        report.tokens_failed,
+        report.total_ckusdt_received
+    );
+    Ok(report)
+}
+
+/// Get the dust sweep log
+pub fn get_sweep_log() -> Vec<DustSweepRecord> {
+    SWEEP_LOG.with(|log| log.borrow().clone())
+}
+
+/// Import sweep log from stable storage
+pub fn import_sweep_log(records: Vec<DustSweepRecord>) {
+    SWEEP_LOG.with(|log| *log.borrow_mut() = records);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(token: TrackedToken, balance: u64, usd_value: f64) -> CurrentPosition {
+        CurrentPosition {
+            token,
+            balance: Nat::from(balance),
+            usd_value,
+            percentage: 0.0,
+            pool_unavailable_since: None,
+        }
+    }
+
+    fn record(success: bool, received: u64, value: f64) -> DustSweepRecord {
+        DustSweepRecord {
+            timestamp: 0,
+            token: TrackedToken::ALEX,
+            amount_swept: Nat::from(1_000_000u64),
+            estimated_value_usd: value,
+            ckusdt_received: Nat::from(received),
+            success,
+            details: String::new(),
+        }
+    }
+
+    fn fees() -> Vec<(TrackedToken, u64)> {
+        vec![
+            (TrackedToken::ALEX, 10_000),
+            (TrackedToken::ZERO, 10_000),
+            (TrackedToken::KONG, 10_000),
+            (TrackedToken::BOB, 10_000),
+        ]
+    }
+
+    #[test]
+    fn test_identify_dust() {
+        let positions = vec![
+            position(TrackedToken::ALEX, 5_000_000, 0.05),     // dust
+            position(TrackedToken::ZERO, 500_000_000, 50.0),   // real position
+            position(TrackedToken::KONG, 0, 0.0),              // empty
+            position(TrackedToken::BOB, 15_000, 0.00001),      // can't cover fees
+            position(TrackedToken::ckUSDT, 10_000, 0.01),      // ckUSDT is never dust
+        ];
+
+        let dust = identify_dust(&positions, 0.10, &fees());
+        assert_eq!(dust.len(), 1);
+        assert_eq!(dust[0].token, TrackedToken::ALEX);
+        assert_eq!(dust[0].sweep_amount, Nat::from(5_000_000u64 - 2 * 10_000));
+        assert_eq!(dust[0].estimated_value_usd, 0.05);
+    }
+
+    #[test]
+    fn test_fee_reserve_uses_each_tokens_fee() {
+        let positions = vec![
+            position(TrackedToken::ALEX, 5_000_000, 0.05),
+            position(TrackedToken::KONG, 300_000, 0.02),
+            position(TrackedToken::BOB, 300_000, 0.02),
+        ];
+        let fees = vec![
+            (TrackedToken::ALEX, 100_000),
+            // KONG's fee eats the whole balance
+            (TrackedToken::KONG, 200_000),
+            // BOB's fee couldn't be fetched
+        ];
+
+        let dust = identify_dust(&positions, 0.10, &fees);
+        assert_eq!(dust.len(), 1);
+        assert_eq!(dust[0].token, TrackedToken::ALEX);
+        assert_eq!(dust[0].sweep_amount, Nat::from(5_000_000u64 - 2 * 100_000));
+    }
+
+    #[test]
+    fn test_threshold_is_exclusive() {
+        let positions = vec![position(TrackedToken::ALEX, 5_000_000, 0.10)];
+        assert!(identify_dust(&positions, 0.10, &fees()).is_empty());
+    }
+
+    #[test]
+    fn test_sweep_accounting() {
+        let report = summarize_sweep(vec![
+            record(true, 40_000, 0.05),
+            record(true, 20_000, 0.03),
+            record(false, 0, 0.07),
+        ]);
+
+        assert_eq!(report.tokens_swept, 2);
+        assert_eq!(report.tokens_failed, 1);
+        assert_eq!(report.total_ckusdt_received, Nat::from(60_000u64));
+        assert!((report.total_estimated_value_usd - 0.08).abs() < 1e-9);
+        assert_eq!(report.records.len(), 3);
+    }
+}
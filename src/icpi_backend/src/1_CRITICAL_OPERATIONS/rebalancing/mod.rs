@@ -35,10 +35,16 @@
 //! - Keeps last MAX_REBALANCE_HISTORY records for audit
 //! - Comprehensive logging for diagnostics
 
+pub mod dust_sweep;
+pub mod poke;
+pub mod simulation;
+
 use std::cell::RefCell;
-use candid::{CandidType, Deserialize, Nat};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use num_traits::ToPrimitive;
-use crate::infrastructure::{Result, IcpiError, errors::RebalanceError, REBALANCE_INTERVAL_SECONDS, MIN_TRADE_SIZE_USD, MAX_SLIPPAGE_PERCENT};
+use crate::infrastructure::{Result, IcpiError, errors::RebalanceError, ValidationError, SystemError, REBALANCE_INTERVAL_SECONDS, MIN_TRADE_SIZE_USD, DEVIATION_IMBALANCE_TOLERANCE_PCT};
+use crate::_4_TRADING_EXECUTION::slippage::MAX_TRADE_SLIPPAGE;
+use crate::_4_TRADING_EXECUTION::swaps::SwapAudit;
 use crate::types::{TrackedToken, rebalancing::AllocationDeviation};
 
 /// Maximum number of rebalance records to keep in recent history (fast queries)
@@ -48,10 +54,13 @@ const MAX_REBALANCE_HISTORY: usize = 10;
 /// At 24 trades/day, 10,000 records = ~416 days of history
 const MAX_FULL_HISTORY: usize = 10_000;
 
+/// Largest page returned by `export_trade_history_chunked`
+const MAX_HISTORY_EXPORT_CHUNK: u64 = 500;
+
 // === TYPES ===
 
 /// Rebalance action to execute
-#[derive(Debug, Clone, CandidType, Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, CandidType, Deserialize, serde::Serialize)]
 pub enum RebalanceAction {
     None,
     Buy { token: TrackedToken, usdt_amount: f64 },
@@ -65,6 +74,73 @@ pub struct RebalanceRecord {
     pub action: RebalanceAction,
     pub success: bool,
     pub details: String,
+    pub request_id: Option<String>,
+    /// What started the cycle; None for records from before this was tracked
+    pub trigger: Option<RebalanceTrigger>,
+    /// Admin or poker who started a manual or poked cycle
+    pub triggered_by: Option<Principal>,
+    /// Approval block and Kongswap outcome of a successful swap
+    pub swap: Option<SwapAudit>,
+}
+
+/// What started a rebalance cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CandidType, Deserialize, serde::Serialize)]
+pub enum RebalanceTrigger {
+    Timer,
+    /// `perform_rebalance` or `trigger_manual_rebalance`
+    Manual,
+    /// `poke_rebalance`
+    Poke,
+}
+
+/// Trigger and caller of the cycle being run, stamped on its records
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CycleOrigin {
+    pub trigger: RebalanceTrigger,
+    pub caller: Option<Principal>,
+}
+
+impl CycleOrigin {
+    pub fn timer() -> Self {
+        Self { trigger: RebalanceTrigger::Timer, caller: None }
+    }
+
+    pub fn by(trigger: RebalanceTrigger, caller: Principal) -> Self {
+        Self { trigger, caller: Some(caller) }
+    }
+}
+
+/// Criteria for listing trade history; unset fields match everything
+#[derive(Debug, Clone, Default, CandidType, Deserialize, serde::Serialize)]
+pub struct TradeHistoryFilter {
+    pub trigger: Option<RebalanceTrigger>,
+    pub triggered_by: Option<Principal>,
+}
+
+impl TradeHistoryFilter {
+    pub fn matches(&self, record: &RebalanceRecord) -> bool {
+        self.trigger.is_none_or(|t| record.trigger == Some(t))
+            && self.triggered_by.is_none_or(|p| record.triggered_by == Some(p))
+    }
+}
+
+/// How imported records combine with the existing trade history
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, serde::Serialize, PartialEq)]
+pub enum HistoryImportMode {
+    /// Add after the existing records (must not predate the latest one)
+    Append,
+    /// Discard the existing records
+    Replace,
+}
+
+/// One page of trade history, for migrations
+#[derive(Debug, Clone, CandidType, Deserialize, serde::Serialize)]
+pub struct TradeHistoryChunk {
+    pub records: Vec<RebalanceRecord>,
+    pub offset: u64,
+    pub total: u64,
+    /// Offset of the next page, None after the last one
+    pub next_offset: Option<u64>,
 }
 
 /// Rebalancer status for monitoring
@@ -72,8 +148,91 @@ pub struct RebalanceRecord {
 pub struct RebalancerStatus {
     pub timer_active: bool,
     pub last_rebalance: Option<u64>,
+    /// The pending override if any, else last + interval
     pub next_rebalance: Option<u64>,
+    /// One-shot time set by `set_next_rebalance_time`, cleared once it runs
+    pub next_rebalance_override: Option<u64>,
     pub recent_history: Vec<RebalanceRecord>,
+    /// Seconds until rebalancing resumes after an emergency unpause
+    pub unpause_cooldown_remaining_seconds: u64,
+    /// ckUSDT currently held for unsettled mint deposits (e6)
+    pub reserved_ckusdt: Nat,
+    /// ckUSDT split seen by the last cycle, None before the first one
+    pub last_ckusdt_budget: Option<CkusdtBudget>,
+    /// PROJECTION: what the next cycle would do against the cached state.
+    /// Not a commitment; prices, balances and targets may change before then.
+    pub projected_next_action: Option<ProjectedAction>,
+    /// Age of the TVL data behind the latest targets seen, None before any
+    pub targets_age_seconds: Option<u64>,
+    /// Latest cycle that refused to trade
+    pub last_skip: Option<RebalanceSkip>,
+}
+
+/// Why a cycle refused to trade
+#[derive(CandidType, Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub enum RebalanceSkipReason {
+    /// The TVL data behind the targets is older than the configured maximum;
+    /// `age_seconds` is None when the targets carry no TVL timestamp
+    StaleTargets { age_seconds: Option<u64>, max_age_seconds: u64 },
+}
+
+impl std::fmt::Display for RebalanceSkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StaleTargets { age_seconds: Some(age), max_age_seconds } => write!(
+                f, "target allocations come from TVL data {}s old (max {}s)", age, max_age_seconds
+            ),
+            Self::StaleTargets { age_seconds: None, .. } => {
+                write!(f, "target allocations have no TVL timestamp")
+            }
+        }
+    }
+}
+
+/// A refused cycle
+#[derive(CandidType, Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct RebalanceSkip {
+    pub timestamp: u64,
+    pub reason: RebalanceSkipReason,
+}
+
+/// Action the next cycle is expected to take, computed by the cache pre-warmer
+///
+/// Cleared whenever a mint, burn or trade completes, until the next refresh.
+#[derive(CandidType, Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct ProjectedAction {
+    pub action: RebalanceAction,
+    pub reason: String,
+    /// When the projection was made
+    pub projected_at: u64,
+    /// Timestamp of the portfolio state it was made against
+    pub state_timestamp: u64,
+}
+
+/// ckUSDT balance split into what the rebalancer may spend and what it may not
+#[derive(CandidType, Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct CkusdtBudget {
+    pub balance: Nat,
+    /// Held for unsettled mint deposits and owed refunds
+    pub reserved: Nat,
+    /// Spendable on buys: balance minus reserved, floored at zero
+    pub free: Nat,
+}
+
+impl CkusdtBudget {
+    pub fn new(balance: &Nat, reserved: &Nat) -> Self {
+        let free = if balance > reserved {
+            balance.clone() - reserved.clone()
+        } else {
+            Nat::from(0u64)
+        };
+        Self { balance: balance.clone(), reserved: reserved.clone(), free }
+    }
+
+    /// Budget against the live deposit reserve
+    pub fn current(balance: &Nat) -> Self {
+        Self::new(balance, &crate::_1_CRITICAL_OPERATIONS::minting::mint_state::reserved_deposits())
+    }
 }
 
 // === STATE ===
@@ -93,6 +252,13 @@ pub struct RebalancerStatus {
 struct RebalanceState {
     last_rebalance: Option<u64>,
     history: Vec<RebalanceRecord>,
+    last_ckusdt_budget: Option<CkusdtBudget>,
+    projected_next_action: Option<ProjectedAction>,
+    /// When the TVL behind the latest seen targets was computed
+    targets_tvl_timestamp: Option<u64>,
+    last_skip: Option<RebalanceSkip>,
+    /// One-shot time for the next cycle, set by `set_next_rebalance_time`
+    next_rebalance_override: Option<u64>,
 }
 
 impl Default for RebalanceState {
@@ -100,16 +266,22 @@ impl Default for RebalanceState {
         Self {
             last_rebalance: None,
             history: Vec::new(),
+            last_ckusdt_budget: None,
+            projected_next_action: None,
+            targets_tvl_timestamp: None,
+            last_skip: None,
+            next_rebalance_override: None,
         }
     }
 }
 
 thread_local! {
     static REBALANCE_STATE: RefCell<RebalanceState> = RefCell::new(RebalanceState::default());
-    static TIMER_ACTIVE: RefCell<bool> = RefCell::new(false);
     static REBALANCING_IN_PROGRESS: RefCell<bool> = RefCell::new(false);
     /// Full history in stable storage (loaded at startup, persisted on upgrade)
     static FULL_HISTORY: RefCell<Vec<RebalanceRecord>> = RefCell::new(Vec::new());
+    /// Timer firing at the override time, replaced when the override changes
+    static OVERRIDE_TIMER: RefCell<Option<ic_cdk_timers::TimerId>> = RefCell::new(None);
 }
 
 // === PUBLIC API ===
@@ -118,83 +290,151 @@ thread_local! {
 ///
 /// Called during canister init and post_upgrade.
 /// Executes `hourly_rebalance()` every 3600 seconds (1 hour).
+/// Calling it again replaces the existing timer.
 pub fn start_rebalancing_timer() {
-    ic_cdk::println!("🕐 Starting rebalancing timer (hourly)");
-
-    // Mark timer as active
-    TIMER_ACTIVE.with(|active| {
-        *active.borrow_mut() = true;
-    });
+    crate::log_op!("🕐 Starting rebalancing timer (hourly)");
 
     // Set up recurring timer
-    ic_cdk_timers::set_timer_interval(
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::Rebalancing,
         std::time::Duration::from_secs(REBALANCE_INTERVAL_SECONDS),
-        || {
-            // Check if rebalancing is already in progress (local guard)
-            let already_running = REBALANCING_IN_PROGRESS.with(|flag| {
-                let is_running = *flag.borrow();
-                if !is_running {
-                    *flag.borrow_mut() = true;
-                }
-                is_running
-            });
+        false,
+        rebalance_timer_tick,
+    );
 
-            if already_running {
-                ic_cdk::println!("⚠️ Rebalancing already in progress, skipping this cycle");
-                return;
-            }
+    crate::log_op!("✅ Rebalancing timer active");
+}
+
+/// One tick of the rebalancing timer, or of a one-shot override timer
+///
+/// While an override from `set_next_rebalance_time` is pending, ticks
+/// before it do nothing; the first tick at or after it runs the cycle and
+/// clears the override, returning to the normal cadence.
+fn rebalance_timer_tick() {
+    let (run, remaining) = check_next_rebalance_override(next_rebalance_override(), crate::infrastructure::runtime::time());
+    if !run {
+        crate::log_op!("⏭️ Deferring rebalance cycle to the scheduled override");
+        return;
+    }
+
+    // Check if rebalancing is already in progress (local guard)
+    let already_running = REBALANCING_IN_PROGRESS.with(|flag| {
+        let is_running = *flag.borrow();
+        if !is_running {
+            *flag.borrow_mut() = true;
+        }
+        is_running
+    });
+
+    if already_running {
+        crate::log_op!("⚠️ Rebalancing already in progress, skipping this cycle");
+        return;
+    }
+
+    // M-4: Try to acquire global operation lock
+    match crate::infrastructure::reentrancy::try_start_global_operation(
+        crate::infrastructure::reentrancy::GlobalOperation::Rebalancing
+    ) {
+        Ok(()) => {
+            // Only a cycle that actually starts uses up the override
+            REBALANCE_STATE.with(|s| s.borrow_mut().next_rebalance_override = remaining);
+
+            // Proceed with rebalancing
+            ic_cdk::spawn(async {
+                let (_, result) = crate::infrastructure::logging::run_request("rebal", hourly_rebalance(false, CycleOrigin::timer())).await;
+                crate::infrastructure::logging::record_result(
+                    crate::infrastructure::logging::Subsystem::Rebalance, &result
+                );
+
+                // Always end global operation (success or failure)
+                crate::infrastructure::reentrancy::end_global_operation(
+                    crate::infrastructure::reentrancy::GlobalOperation::Rebalancing
+                );
 
-            // M-4: Try to acquire global operation lock
-            match crate::infrastructure::reentrancy::try_start_global_operation(
-                crate::infrastructure::reentrancy::GlobalOperation::Rebalancing
-            ) {
-                Ok(()) => {
-                    // Proceed with rebalancing
-                    ic_cdk::spawn(async {
-                        let result = hourly_rebalance().await;
-
-                        // Always end global operation (success or failure)
-                        crate::infrastructure::reentrancy::end_global_operation(
-                            crate::infrastructure::reentrancy::GlobalOperation::Rebalancing
-                        );
-
-                        // Clear the local in-progress flag
-                        REBALANCING_IN_PROGRESS.with(|flag| {
-                            *flag.borrow_mut() = false;
-                        });
-
-                        match result {
-                            Ok(msg) => ic_cdk::println!("✅ Rebalance: {}", msg),
-                            Err(e) => ic_cdk::println!("❌ Rebalance failed: {}", e),
-                        }
-                    });
-                },
-                Err(e) => {
-                    // Global operation blocked (mints/burns active or grace period)
-                    ic_cdk::println!("⏭️ Skipping rebalance cycle: {}", e);
-
-                    // Clear local flag since we're not proceeding
-                    REBALANCING_IN_PROGRESS.with(|flag| {
-                        *flag.borrow_mut() = false;
-                    });
+                // Clear the local in-progress flag
+                REBALANCING_IN_PROGRESS.with(|flag| {
+                    *flag.borrow_mut() = false;
+                });
+
+                match result {
+                    Ok(msg) => crate::log_op!("✅ Rebalance: {}", msg),
+                    Err(e) => crate::log_op!("❌ Rebalance failed: {}", e),
                 }
-            }
+            });
+        },
+        Err(e) => {
+            // Global operation blocked (mints/burns active or grace period)
+            crate::log_op!("⏭️ Skipping rebalance cycle: {}", e);
+
+            // Clear local flag since we're not proceeding
+            REBALANCING_IN_PROGRESS.with(|flag| {
+                *flag.borrow_mut() = false;
+            });
         }
-    );
+    }
+}
+
+/// Whether a timer tick at `now` runs a cycle, and the override left pending
+fn check_next_rebalance_override(override_at: Option<u64>, now: u64) -> (bool, Option<u64>) {
+    match override_at {
+        Some(at) if now < at => (false, Some(at)),
+        _ => (true, None),
+    }
+}
+
+fn next_rebalance_override() -> Option<u64> {
+    REBALANCE_STATE.with(|s| s.borrow().next_rebalance_override)
+}
+
+/// Schedule the next cycle at `timestamp` (ns) instead of the normal cadence (admin only, enforced at the API layer)
+///
+/// Applies to one cycle; setting it again replaces the pending override.
+/// Not persisted across upgrades.
+pub fn set_next_rebalance_time(timestamp: u64) -> Result<()> {
+    let now = crate::infrastructure::runtime::time();
+    if timestamp <= now {
+        return Err(IcpiError::Other(format!(
+            "Next rebalance time {} is not in the future (now {})", timestamp, now
+        )));
+    }
+
+    REBALANCE_STATE.with(|s| s.borrow_mut().next_rebalance_override = Some(timestamp));
+    let delay = std::time::Duration::from_nanos(timestamp - now);
+    let timer = ic_cdk_timers::set_timer(delay, rebalance_timer_tick);
+    if let Some(previous) = OVERRIDE_TIMER.with(|t| t.borrow_mut().replace(timer)) {
+        ic_cdk_timers::clear_timer(previous);
+    }
 
-    ic_cdk::println!("✅ Rebalancing timer active");
+    crate::log_op!("🕐 Next rebalance scheduled for {} ({}s from now)", timestamp, delay.as_secs());
+    Ok(())
 }
 
 /// Manual rebalancing trigger (admin only)
 ///
 /// Executes a single rebalancing cycle immediately.
 /// Useful for testing or emergency interventions.
-pub async fn perform_rebalance() -> Result<String> {
+/// Refuses to trade on stale targets unless `override_stale_targets` is set.
+pub async fn perform_rebalance(override_stale_targets: bool) -> Result<String> {
     // Check not paused (Phase 2: H-1 fix)
     crate::infrastructure::check_not_paused()?;
+    crate::infrastructure::dependency_compat::check_dependencies_compatible()?;
 
-    ic_cdk::println!("🔧 Manual rebalance triggered");
+    crate::log_op!("🔧 Manual rebalance triggered");
 
+    begin_exclusive_rebalance()?;
+    let origin = CycleOrigin::by(RebalanceTrigger::Manual, crate::infrastructure::runtime::caller());
+    let (_, result) = crate::infrastructure::logging::run_request("rebal", hourly_rebalance(override_stale_targets, origin)).await;
+    end_exclusive_rebalance();
+    crate::infrastructure::logging::record_result(crate::infrastructure::logging::Subsystem::Rebalance, &result);
+
+    result
+}
+
+/// Acquire the local in-progress flag and the global operation lock
+///
+/// Used by manual operations that trade out of the portfolio. Every
+/// successful call must be paired with `end_exclusive_rebalance`.
+pub(crate) fn begin_exclusive_rebalance() -> Result<()> {
     // Check if rebalancing is already in progress (local guard)
     let already_running = REBALANCING_IN_PROGRESS.with(|flag| {
         let is_running = *flag.borrow();
@@ -209,47 +449,116 @@ pub async fn perform_rebalance() -> Result<String> {
     }
 
     // M-4: Try to acquire global operation lock
-    crate::infrastructure::reentrancy::try_start_global_operation(
-        crate::infrastructure::reentrancy::GlobalOperation::Rebalancing
-    )?;
+    if let Err(e) = crate::infrastructure::reentrancy::try_start_global_operation_for(
+        crate::infrastructure::reentrancy::GlobalOperation::Rebalancing,
+        Some(crate::infrastructure::runtime::caller()),
+    ) {
+        REBALANCING_IN_PROGRESS.with(|flag| *flag.borrow_mut() = false);
+        return Err(e);
+    }
 
-    let result = hourly_rebalance().await;
+    Ok(())
+}
 
+/// Release the locks taken by `begin_exclusive_rebalance`
+pub(crate) fn end_exclusive_rebalance() {
     // Always end global operation (success or failure)
-    crate::infrastructure::reentrancy::end_global_operation(
-        crate::infrastructure::reentrancy::GlobalOperation::Rebalancing
+    crate::infrastructure::reentrancy::end_global_operation_for(
+        crate::infrastructure::reentrancy::GlobalOperation::Rebalancing,
+        Some(crate::infrastructure::runtime::caller()),
     );
 
     // Clear the local in-progress flag
     REBALANCING_IN_PROGRESS.with(|flag| {
         *flag.borrow_mut() = false;
     });
-
-    result
 }
 
 /// Trigger manual rebalance (alias for perform_rebalance)
 pub async fn trigger_manual_rebalance() -> Result<String> {
-    perform_rebalance().await
+    perform_rebalance(false).await
 }
 
 /// Get current rebalancer status
 pub fn get_rebalancer_status() -> RebalancerStatus {
-    let timer_active = TIMER_ACTIVE.with(|active| *active.borrow());
+    rebalancer_status_at(crate::infrastructure::runtime::time())
+}
+
+/// Rebalancer status as of `now`
+pub fn rebalancer_status_at(now: u64) -> RebalancerStatus {
+    let timer_active = crate::infrastructure::timers::is_registered(
+        crate::infrastructure::timers::TimerKind::Rebalancing
+    );
+    let unpause_cooldown_remaining_seconds = crate::infrastructure::admin::unpause_cooldown_remaining_at(now);
 
     REBALANCE_STATE.with(|state| {
         let state = state.borrow();
         RebalancerStatus {
             timer_active,
             last_rebalance: state.last_rebalance,
-            next_rebalance: state.last_rebalance.map(|last| {
+            next_rebalance: state.next_rebalance_override.or_else(|| state.last_rebalance.map(|last| {
                 last + (REBALANCE_INTERVAL_SECONDS * 1_000_000_000)
-            }),
+            })),
+            next_rebalance_override: state.next_rebalance_override,
             recent_history: state.history.clone(),
+            unpause_cooldown_remaining_seconds,
+            reserved_ckusdt: crate::_1_CRITICAL_OPERATIONS::minting::mint_state::reserved_deposits(),
+            last_ckusdt_budget: state.last_ckusdt_budget.clone(),
+            projected_next_action: state.projected_next_action.clone(),
+            targets_age_seconds: state.targets_tvl_timestamp.map(|ts| now.saturating_sub(ts) / 1_000_000_000),
+            last_skip: state.last_skip.clone(),
         }
     })
 }
 
+/// What a cycle would decide against `state`, mirroring `rebalance_with_state`
+pub fn project_action(
+    state: &crate::types::portfolio::IndexState,
+    reserved: &Nat,
+    unavailable: &[TrackedToken],
+    now: u64,
+) -> ProjectedAction {
+    let budget = CkusdtBudget::new(&state.ckusdt_balance, reserved);
+    let deviations = tradable_deviations(&state.deviations, unavailable);
+    let explanation = explain_rebalancing_action(&deviations, &budget.free);
+    ProjectedAction {
+        action: explanation.action,
+        reason: explanation.reason,
+        projected_at: now,
+        state_timestamp: state.timestamp,
+    }
+}
+
+/// Recompute the projected next action from a freshly cached state
+pub fn refresh_projection(state: &crate::types::portfolio::IndexState, now: u64) {
+    let projection = project_action(
+        state,
+        &crate::_1_CRITICAL_OPERATIONS::minting::mint_state::reserved_deposits(),
+        &crate::_3_KONG_LIQUIDITY::pool_status::unavailable_tokens(),
+        now,
+    );
+    REBALANCE_STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        s.projected_next_action = Some(projection);
+        s.targets_tvl_timestamp = state.targets_tvl_timestamp;
+    });
+}
+
+/// Drop the projection; a mint, burn or trade changed what it was based on
+pub fn invalidate_projection() {
+    REBALANCE_STATE.with(|s| s.borrow_mut().projected_next_action = None);
+}
+
+/// ckUSDT split seen by the last rebalance cycle
+pub(crate) fn last_ckusdt_budget() -> Option<CkusdtBudget> {
+    REBALANCE_STATE.with(|state| state.borrow().last_ckusdt_budget.clone())
+}
+
+/// Timestamp of the last recorded cycle, if any since install/upgrade
+pub(crate) fn last_rebalance_time() -> Option<u64> {
+    REBALANCE_STATE.with(|state| state.borrow().last_rebalance)
+}
+
 /// Get full trade history (all trades since deployment)
 pub fn get_full_trade_history() -> Vec<RebalanceRecord> {
     FULL_HISTORY.with(|h| h.borrow().clone())
@@ -273,13 +582,28 @@ pub fn get_trade_history_paginated(offset: u64, limit: u64) -> (Vec<RebalanceRec
     })
 }
 
+/// Page of the trade history records matching `filter`, with the match count
+pub fn get_trade_history_filtered(filter: &TradeHistoryFilter, offset: u64, limit: u64) -> (Vec<RebalanceRecord>, u64) {
+    FULL_HISTORY.with(|h| filter_history(&h.borrow(), filter, offset, limit))
+}
+
+fn filter_history(history: &[RebalanceRecord], filter: &TradeHistoryFilter, offset: u64, limit: u64) -> (Vec<RebalanceRecord>, u64) {
+    let matching: Vec<&RebalanceRecord> = history.iter().filter(|r| filter.matches(r)).collect();
+    let page = matching.iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .map(|r| (*r).clone())
+        .collect();
+    (page, matching.len() as u64)
+}
+
 /// Load history from stable storage (called in post_upgrade)
 pub fn load_history_from_stable(history: Vec<RebalanceRecord>) {
     let count = history.len();
     FULL_HISTORY.with(|h| {
         *h.borrow_mut() = history;
     });
-    ic_cdk::println!("✅ Loaded {} trades from stable storage", count);
+    crate::log_op!("✅ Loaded {} trades from stable storage", count);
 }
 
 /// Export history for stable storage (called in pre_upgrade)
@@ -287,6 +611,99 @@ pub fn export_history_for_stable() -> Vec<RebalanceRecord> {
     FULL_HISTORY.with(|h| h.borrow().clone())
 }
 
+/// Export a page of the full trade history (at most MAX_HISTORY_EXPORT_CHUNK records)
+pub fn export_trade_history_chunked(offset: u64, limit: u64) -> TradeHistoryChunk {
+    let (records, total) = get_trade_history_paginated(offset, limit.min(MAX_HISTORY_EXPORT_CHUNK));
+    let end = offset.saturating_add(records.len() as u64);
+    TradeHistoryChunk {
+        records,
+        offset,
+        total,
+        next_offset: (end < total).then_some(end),
+    }
+}
+
+/// Check imported records are well-formed and in time order
+///
+/// `after` is the timestamp the first record must not predate (the latest
+/// existing record when appending).
+pub fn validate_history_import(records: &[RebalanceRecord], after: Option<u64>, now: u64) -> Result<()> {
+    let invalid = |index: usize, reason: &str| IcpiError::Validation(ValidationError::DataInconsistency {
+        reason: format!("record {}: {}", index, reason),
+    });
+
+    if records.len() > MAX_FULL_HISTORY {
+        return Err(IcpiError::Validation(ValidationError::DataInconsistency {
+            reason: format!("{} records exceeds history capacity {}", records.len(), MAX_FULL_HISTORY),
+        }));
+    }
+
+    let mut previous = after;
+    for (index, record) in records.iter().enumerate() {
+        if record.timestamp == 0 || record.timestamp > now {
+            return Err(invalid(index, "timestamp is zero or in the future"));
+        }
+        if previous.is_some_and(|p| record.timestamp < p) {
+            return Err(invalid(index, "timestamps are not monotonic"));
+        }
+        let amount = match &record.action {
+            RebalanceAction::None => 0.0,
+            RebalanceAction::Buy { usdt_amount, .. } => *usdt_amount,
+            RebalanceAction::Sell { usdt_value, .. } => *usdt_value,
+        };
+        if !amount.is_finite() || amount < 0.0 {
+            return Err(invalid(index, "trade amount is negative or not finite"));
+        }
+        previous = Some(record.timestamp);
+    }
+    Ok(())
+}
+
+/// Validate and write imported records into the full history
+///
+/// Returns the history length afterwards.
+fn apply_history_import(records: Vec<RebalanceRecord>, mode: HistoryImportMode, now: u64) -> Result<u64> {
+    FULL_HISTORY.with(|h| {
+        let mut history = h.borrow_mut();
+        let after = match mode {
+            HistoryImportMode::Append => history.last().map(|r| r.timestamp),
+            HistoryImportMode::Replace => None,
+        };
+        validate_history_import(&records, after, now)?;
+
+        if mode == HistoryImportMode::Replace {
+            history.clear();
+        }
+        history.extend(records);
+        if history.len() > MAX_FULL_HISTORY {
+            let excess = history.len() - MAX_FULL_HISTORY;
+            history.drain(0..excess);
+        }
+        Ok(history.len() as u64)
+    })
+}
+
+/// Import exported trade history (admin only, enforced at the API layer)
+///
+/// Refused unless rebalancing is stopped: the system is paused, so no
+/// cycle can start, and none is in flight.
+pub fn import_trade_history(records: Vec<RebalanceRecord>, mode: HistoryImportMode) -> Result<u64> {
+    if !crate::infrastructure::is_paused() {
+        return Err(IcpiError::System(SystemError::ConflictingOperation {
+            operation: "rebalancing (pause the system before importing trade history)".to_string(),
+            operation_id: None,
+        }));
+    }
+    if REBALANCING_IN_PROGRESS.with(|flag| *flag.borrow()) {
+        return Err(IcpiError::Rebalance(RebalanceError::RebalancingInProgress));
+    }
+
+    let count = records.len();
+    let total = apply_history_import(records, mode, crate::infrastructure::runtime::time())?;
+    crate::log_op!("📥 Imported {} trade records ({:?}); history now {}", count, mode, total);
+    Ok(total)
+}
+
 // === CORE LOGIC ===
 
 /// Execute one hourly rebalancing cycle
@@ -296,47 +713,164 @@ pub fn export_history_for_stable() -> Vec<RebalanceRecord> {
 /// 2. Analyze deviations to determine action
 /// 3. Execute buy or sell based on priority
 /// 4. Record result for history
-async fn hourly_rebalance() -> Result<String> {
+///
+/// `allow_stale_targets` trades even when the targets' TVL data is too old.
+async fn hourly_rebalance(allow_stale_targets: bool, origin: CycleOrigin) -> Result<String> {
     // Check not paused (Phase 2: H-1 fix)
     // Emergency pause should block ALL state-changing operations including rebalancing
     if let Err(e) = crate::infrastructure::check_not_paused() {
-        ic_cdk::println!("⏭️ Skipping rebalance cycle: System is paused");
+        crate::log_op!("⏭️ Skipping rebalance cycle: {}", e);
         return Err(e);
     }
+    match preflight_skip(crate::infrastructure::runtime::time(), crate::infrastructure::runtime::cycles_balance()) {
+        Err(e) => {
+            crate::log_op!("⏭️ Skipping rebalance cycle: {}", e);
+            return Err(e);
+        }
+        Ok(Some(reason)) => {
+            let msg = format!("Skipped: {}", reason);
+            crate::log_op!("⏭️ {}", msg);
+            return Ok(msg);
+        }
+        Ok(None) => {}
+    }
 
-    ic_cdk::println!("🔄 Starting hourly rebalance cycle...");
+    crate::log_op!("🔄 Starting hourly rebalance cycle...");
+
+    // Get current portfolio state (includes deviations), Kongswap prices only
+    let state = crate::_5_INFORMATIONAL::display::refresh_index_state_with_policy(
+        crate::_3_KONG_LIQUIDITY::pools::PricingPolicy::PrimaryOnly,
+    ).await?;
+
+    if let Some(reason) = stale_targets_skip(&state, crate::infrastructure::runtime::time()) {
+        if !allow_stale_targets {
+            let msg = format!("Skipped: {}", reason);
+            crate::log_op!("⏭️ {}", msg);
+            return Ok(msg);
+        }
+        crate::log_op!("⚠️ Trading despite stale targets (admin override): {}", reason);
+    }
+
+    rebalance_with_state(&state, origin).await
+}
 
-    // Get current portfolio state (includes deviations)
-    let state = crate::_5_INFORMATIONAL::display::get_index_state_cached().await?;
+/// Why no cycle may start at `now` with `cycles_balance` cycles, checked
+/// before reading portfolio state
+///
+/// Shared by the timer, manual and poke paths so none of them can trade
+/// around it. An incompatible dependency is an error, the rest are skips.
+pub(crate) fn preflight_skip(now: u64, cycles_balance: u128) -> Result<Option<String>> {
+    crate::infrastructure::dependency_compat::check_dependencies_compatible()?;
+
+    // Caches may still hold data from before the pause
+    let cooldown = crate::infrastructure::admin::unpause_cooldown_remaining_at(now);
+    if cooldown > 0 {
+        return Ok(Some(format!("{}s of post-unpause cooldown remaining", cooldown)));
+    }
+
+    // Targets come from Kong Locker TVL; don't trade on an outdated picture
+    if let Err(reason) = crate::_3_KONG_LIQUIDITY::tvl::generation::require_recent_generation(now) {
+        return Ok(Some(reason));
+    }
+
+    // A cycle makes many inter-canister calls; don't start one we can't finish
+    let budget = crate::infrastructure::config::get_config().rebalance_cycles_budget();
+    if let Err(reason) = check_cycles_budget(cycles_balance, budget) {
+        return Ok(Some(reason));
+    }
+
+    Ok(None)
+}
+
+/// Whether targets computed from TVL at `targets_tvl_timestamp` are recent enough
+pub fn check_targets_age(
+    targets_tvl_timestamp: Option<u64>,
+    now: u64,
+    max_age_seconds: u64,
+) -> std::result::Result<(), RebalanceSkipReason> {
+    let age_seconds = targets_tvl_timestamp.map(|ts| now.saturating_sub(ts) / 1_000_000_000);
+    match age_seconds {
+        Some(age) if age <= max_age_seconds => Ok(()),
+        _ => Err(RebalanceSkipReason::StaleTargets { age_seconds, max_age_seconds }),
+    }
+}
 
-    ic_cdk::println!(
-        "📊 Portfolio: ${:.2} total, ${} ckUSDT available",
+/// Check the targets of `state`, recording a skip if they are stale
+///
+/// The skip is recorded even when an admin override trades anyway.
+pub(crate) fn stale_targets_skip(state: &crate::types::portfolio::IndexState, now: u64) -> Option<RebalanceSkipReason> {
+    let max_age = crate::infrastructure::config::get_config().max_target_age_seconds();
+    let result = check_targets_age(state.targets_tvl_timestamp, now, max_age);
+    REBALANCE_STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        s.targets_tvl_timestamp = state.targets_tvl_timestamp;
+        if let Err(reason) = &result {
+            s.last_skip = Some(RebalanceSkip { timestamp: now, reason: reason.clone() });
+        }
+    });
+    result.err()
+}
+
+/// Whether `available` cycles cover the per-cycle rebalance `budget`
+fn check_cycles_budget(available: u128, budget: u128) -> std::result::Result<(), String> {
+    if available < budget {
+        return Err(format!(
+            "{} cycles available, below the {} cycle rebalance budget",
+            available, budget
+        ));
+    }
+    Ok(())
+}
+
+/// Run one cycle against an already-fetched portfolio state
+pub(crate) async fn rebalance_with_state(state: &crate::types::portfolio::IndexState, origin: CycleOrigin) -> Result<String> {
+    // Deposits of unsettled mints may still have to be refunded
+    let budget = CkusdtBudget::current(&state.ckusdt_balance);
+    crate::log_op!(
+        "📊 Portfolio: ${:.2} total, {} ckUSDT available ({} reserved for pending mints)",
         state.total_value,
-        state.ckusdt_balance
+        budget.free,
+        budget.reserved
     );
+    REBALANCE_STATE.with(|s| s.borrow_mut().last_ckusdt_budget = Some(budget.clone()));
+
+    // Tokens whose pool is gone can't be traded; rebalance the rest
+    let unavailable = crate::_3_KONG_LIQUIDITY::pool_status::unavailable_tokens();
+    if !unavailable.is_empty() {
+        crate::log_op!("⏭️ Excluding tokens with unavailable pools: {:?}", unavailable);
+    }
+    let deviations = tradable_deviations(&state.deviations, &unavailable);
 
     // Determine what action to take
-    let action = get_rebalancing_action(&state.deviations, &state.ckusdt_balance)?;
+    let action = get_rebalancing_action(&deviations, &budget.free)?;
 
     // Execute trade if needed
     let result = match action.clone() {
         RebalanceAction::None => {
             let msg = "No rebalancing needed (all tokens within tolerance)".to_string();
-            ic_cdk::println!("✅ {}", msg);
-            record_rebalance(action, true, &msg);
+            crate::log_op!("✅ {}", msg);
+            record_rebalance(action, true, &msg, origin, None);
             Ok(msg)
         }
         RebalanceAction::Buy { token, usdt_amount } => {
-            execute_buy_action(&token, usdt_amount).await
+            execute_buy_action(&token, usdt_amount, origin).await
         }
         RebalanceAction::Sell { token, usdt_value } => {
-            execute_sell_action(&token, usdt_value).await
+            execute_sell_action(&token, usdt_value, origin).await
         }
     };
 
     result
 }
 
+/// Deviations of tokens that can currently be traded
+fn tradable_deviations(deviations: &[AllocationDeviation], unavailable: &[TrackedToken]) -> Vec<AllocationDeviation> {
+    deviations.iter()
+        .filter(|d| !unavailable.contains(&d.token))
+        .cloned()
+        .collect()
+}
+
 /// Determine rebalancing action based on current state
 ///
 /// ## Priority Logic
@@ -351,58 +885,215 @@ pub fn get_rebalancing_action(
     deviations: &[AllocationDeviation],
     ckusdt_balance: &Nat,
 ) -> Result<RebalanceAction> {
+    let explanation = explain_rebalancing_action(deviations, ckusdt_balance);
+
+    if let Some(anomaly) = &explanation.anomaly {
+        crate::log_op!(
+            "🚨 Deviation anomaly, skipping trade: underweight ${:.2}, overweight ${:.2}, ckUSDT ${:.2}, tolerance ${:.2}",
+            anomaly.underweight_usd,
+            anomaly.overweight_usd,
+            anomaly.ckusdt_usd,
+            anomaly.tolerance_usd
+        );
+        crate::infrastructure::logging::record_error(
+            crate::infrastructure::logging::Subsystem::Rebalance,
+            explanation.reason.clone(),
+        );
+        return Ok(explanation.action);
+    }
+
+    match &explanation.action {
+        RebalanceAction::Buy { .. } => crate::log_op!("📈 Buy signal: {}", explanation.reason),
+        RebalanceAction::Sell { .. } => crate::log_op!("📉 Sell signal: {}", explanation.reason),
+        RebalanceAction::None => crate::log_op!("⚖️  Portfolio balanced (no significant deviations)"),
+    }
+
+    Ok(explanation.action)
+}
+
+/// Why the rebalancer picked its next action
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct RebalanceExplanation {
+    pub action: RebalanceAction,
+    /// Token with the largest deficit, if any is underweight
+    pub most_underweight: Option<AllocationDeviation>,
+    /// Token with the largest excess, if any is overweight
+    pub most_overweight: Option<AllocationDeviation>,
+    pub ckusdt_available_usd: f64,
+    pub min_trade_size_usd: f64,
+    /// Tokens left out because their Kongswap pool is unavailable
+    pub excluded_tokens: Vec<TrackedToken>,
+    /// Each check made, in order
+    pub decision_path: Vec<String>,
+    /// Why `action` was chosen
+    pub reason: String,
+    /// Set when the deviations were too inconsistent to trade on
+    pub anomaly: Option<DeviationAnomaly>,
+}
+
+/// Deviations that are overweight on net, which no valuation should produce
+#[derive(Debug, Clone, PartialEq, CandidType, Deserialize)]
+pub struct DeviationAnomaly {
+    /// Sum of the positive `usd_difference`s
+    pub underweight_usd: f64,
+    /// Sum of the negative `usd_difference`s, as a positive amount
+    pub overweight_usd: f64,
+    pub ckusdt_usd: f64,
+    /// Net overweight allowed before trading stops
+    pub tolerance_usd: f64,
+}
+
+/// Flag deviations that are overweight on net beyond the tolerance
+///
+/// Targets are shares of a total that includes ckUSDT, so token deviations
+/// sum to about +ckUSDT: cash can only make tokens underweight in
+/// aggregate. A net overweight means valuation and targets disagree, and
+/// trading on it would sell the most overweight token into ckUSDT every
+/// cycle.
+pub fn check_deviation_balance(deviations: &[AllocationDeviation], ckusdt_usd: f64) -> Option<DeviationAnomaly> {
+    let underweight_usd: f64 = deviations.iter().map(|d| d.usd_difference.max(0.0)).sum();
+    let overweight_usd: f64 = deviations.iter().map(|d| (-d.usd_difference).max(0.0)).sum();
+    let gross = underweight_usd + overweight_usd;
+    let tolerance_usd = (gross * DEVIATION_IMBALANCE_TOLERANCE_PCT / 100.0).max(MIN_TRADE_SIZE_USD);
+
+    if overweight_usd - underweight_usd > tolerance_usd {
+        Some(DeviationAnomaly { underweight_usd, overweight_usd, ckusdt_usd, tolerance_usd })
+    } else {
+        None
+    }
+}
+
+/// Decide the next action and record the decision path
+pub fn explain_rebalancing_action(
+    deviations: &[AllocationDeviation],
+    ckusdt_balance: &Nat,
+) -> RebalanceExplanation {
     // Convert ckUSDT balance to USD
     let ckusdt_usd = ckusdt_balance.0.to_u64().unwrap_or(0) as f64 / 1_000_000.0;
+    let mut path = Vec::new();
 
     // Find most underweight token (largest positive usd_difference)
     let most_underweight = deviations.iter()
         .filter(|d| d.usd_difference > 0.0) // Needs more tokens
         .max_by(|a, b| a.usd_difference.partial_cmp(&b.usd_difference)
-            .unwrap_or(std::cmp::Ordering::Equal));
+            .unwrap_or(std::cmp::Ordering::Equal))
+        .cloned();
+
+    // Find most overweight token (largest negative usd_difference)
+    let most_overweight = deviations.iter()
+        .filter(|d| d.usd_difference < 0.0) // Has excess tokens
+        .min_by(|a, b| a.usd_difference.partial_cmp(&b.usd_difference)
+            .unwrap_or(std::cmp::Ordering::Equal))
+        .cloned();
+
+    let explanation = |action, path, reason| RebalanceExplanation {
+        action,
+        most_underweight: most_underweight.clone(),
+        most_overweight: most_overweight.clone(),
+        ckusdt_available_usd: ckusdt_usd,
+        min_trade_size_usd: MIN_TRADE_SIZE_USD,
+        excluded_tokens: Vec::new(),
+        decision_path: path,
+        reason,
+        anomaly: None,
+    };
+
+    if let Some(anomaly) = check_deviation_balance(deviations, ckusdt_usd) {
+        let reason = format!(
+            "Tokens are ${:.2} overweight against ${:.2} underweight, beyond the ${:.2} tolerance; not trading on inconsistent deviations",
+            anomaly.overweight_usd, anomaly.underweight_usd, anomaly.tolerance_usd
+        );
+        path.push(reason.clone());
+        return RebalanceExplanation {
+            anomaly: Some(anomaly),
+            ..explanation(RebalanceAction::None, path, reason)
+        };
+    }
 
     // Check if we can buy
-    if ckusdt_usd >= MIN_TRADE_SIZE_USD {
-        if let Some(deficit) = most_underweight {
-            if deficit.usd_difference > MIN_TRADE_SIZE_USD {
-                ic_cdk::println!(
-                    "📈 Buy signal: {} is {:.2}% underweight (deficit: ${:.2})",
+    if ckusdt_usd < MIN_TRADE_SIZE_USD {
+        path.push(format!(
+            "ckUSDT ${:.2} is below the ${:.2} minimum trade, so no buy",
+            ckusdt_usd, MIN_TRADE_SIZE_USD
+        ));
+    } else {
+        match &most_underweight {
+            None => path.push("No token is underweight, so no buy".to_string()),
+            Some(deficit) if deficit.usd_difference <= MIN_TRADE_SIZE_USD => path.push(format!(
+                "Largest deficit {} ${:.2} is below the ${:.2} minimum trade, so no buy",
+                deficit.token.to_symbol(), deficit.usd_difference, MIN_TRADE_SIZE_USD
+            )),
+            Some(deficit) => {
+                // 10% of the deficit, but never more than the spendable ckUSDT
+                let buy_usd = deficit.trade_size_usd.min(ckusdt_usd);
+                let reason = format!(
+                    "{} is {:.2}% underweight (deficit: ${:.2}) and ${:.2} ckUSDT is available; buying ${:.2}",
                     deficit.token.to_symbol(),
                     deficit.deviation_pct.abs(),
-                    deficit.usd_difference
+                    deficit.usd_difference,
+                    ckusdt_usd,
+                    buy_usd
                 );
-
-                return Ok(RebalanceAction::Buy {
+                path.push(reason.clone());
+                let action = RebalanceAction::Buy {
                     token: deficit.token.clone(),
-                    usdt_amount: deficit.trade_size_usd, // Already 10% of deficit
-                });
+                    usdt_amount: buy_usd,
+                };
+                return explanation(action, path, reason);
             }
         }
     }
 
-    // Find most overweight token (largest negative usd_difference)
-    let most_overweight = deviations.iter()
-        .filter(|d| d.usd_difference < 0.0) // Has excess tokens
-        .min_by(|a, b| a.usd_difference.partial_cmp(&b.usd_difference)
-            .unwrap_or(std::cmp::Ordering::Equal));
-
-    if let Some(excess) = most_overweight {
-        if excess.usd_difference.abs() > MIN_TRADE_SIZE_USD {
-            ic_cdk::println!(
-                "📉 Sell signal: {} is {:.2}% overweight (excess: ${:.2})",
+    match &most_overweight {
+        None => path.push("No token is overweight, so no sell".to_string()),
+        Some(excess) if excess.usd_difference.abs() <= MIN_TRADE_SIZE_USD => path.push(format!(
+            "Largest excess {} ${:.2} is below the ${:.2} minimum trade, so no sell",
+            excess.token.to_symbol(), excess.usd_difference.abs(), MIN_TRADE_SIZE_USD
+        )),
+        Some(excess) => {
+            let reason = format!(
+                "{} is {:.2}% overweight (excess: ${:.2}); selling ${:.2} for ckUSDT",
                 excess.token.to_symbol(),
                 excess.deviation_pct.abs(),
-                excess.usd_difference.abs()
+                excess.usd_difference.abs(),
+                excess.trade_size_usd
             );
-
-            return Ok(RebalanceAction::Sell {
+            path.push(reason.clone());
+            let action = RebalanceAction::Sell {
                 token: excess.token.clone(),
                 usdt_value: excess.trade_size_usd, // Already 10% of excess
-            });
+            };
+            return explanation(action, path, reason);
         }
     }
 
-    ic_cdk::println!("⚖️  Portfolio balanced (no significant deviations)");
-    Ok(RebalanceAction::None)
+    let reason = "No buy or sell clears the minimum trade size".to_string();
+    explanation(RebalanceAction::None, path, reason)
+}
+
+/// Explain the action the next rebalance cycle would take, without trading
+pub async fn get_rebalance_action_explanation() -> Result<RebalanceExplanation> {
+    let state = crate::_5_INFORMATIONAL::display::get_index_state_uncached().await?;
+
+    let unavailable = crate::_3_KONG_LIQUIDITY::pool_status::unavailable_tokens();
+    let deviations = tradable_deviations(&state.deviations, &unavailable);
+
+    let budget = CkusdtBudget::current(&state.ckusdt_balance);
+    let mut explanation = explain_rebalancing_action(&deviations, &budget.free);
+    if budget.reserved > Nat::from(0u64) {
+        explanation.decision_path.insert(0, format!(
+            "Reserved {} ckUSDT (e6) for pending mint deposits",
+            budget.reserved
+        ));
+    }
+    if !unavailable.is_empty() {
+        explanation.decision_path.insert(0, format!(
+            "Excluded tokens with unavailable pools: {}",
+            unavailable.iter().map(|t| t.to_symbol()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    explanation.excluded_tokens = unavailable;
+    Ok(explanation)
 }
 
 /// Execute a buy action (ckUSDT → token)
@@ -411,26 +1102,34 @@ pub fn get_rebalancing_action(
 /// 1. Convert USD amount to ckUSDT (e6 decimals)
 /// 2. Execute swap via Zone 4
 /// 3. Log results and update history
-async fn execute_buy_action(token: &TrackedToken, usd_amount: f64) -> Result<String> {
+async fn execute_buy_action(token: &TrackedToken, usd_amount: f64, origin: CycleOrigin) -> Result<String> {
     let ckusdt_amount = Nat::from((usd_amount * 1_000_000.0).round() as u64);
 
-    ic_cdk::println!(
+    crate::log_op!(
         "💰 Buying {} with ${:.2} ({} ckUSDT)",
         token.to_symbol(),
         usd_amount,
         ckusdt_amount
     );
 
+    if let Some(msg) = precheck_priceable(token).await {
+        return Ok(msg);
+    }
+
+    if let Some(msg) = precheck_pool_depth(&TrackedToken::ckUSDT, &ckusdt_amount, token).await {
+        return Ok(msg);
+    }
+
     // Execute swap via Zone 4
-    let swap_result = crate::_4_TRADING_EXECUTION::swaps::execute_swap(
+    let swap_result = crate::_4_TRADING_EXECUTION::swaps::execute_swap_audited(
         &TrackedToken::ckUSDT,
         ckusdt_amount.clone(),
         token,
-        MAX_SLIPPAGE_PERCENT, // Kongswap expects percentage value (e.g., 5.0 = 5%)
+        MAX_TRADE_SLIPPAGE,
     ).await;
 
     match swap_result {
-        Ok(reply) => {
+        Ok((reply, audit)) => {
             let msg = format!(
                 "Bought {} {} with ${:.2} (slippage: {:.4}%)",
                 reply.receive_amount,
@@ -438,21 +1137,25 @@ async fn execute_buy_action(token: &TrackedToken, usd_amount: f64) -> Result<Str
                 usd_amount,
                 reply.slippage
             );
-            ic_cdk::println!("✅ {}", msg);
+            crate::log_op!("✅ {}", msg);
             record_rebalance(
                 RebalanceAction::Buy { token: token.clone(), usdt_amount: usd_amount },
                 true,
-                &msg
+                &msg,
+                origin,
+                Some(audit),
             );
             Ok(msg)
         }
         Err(e) => {
             let msg = format!("Buy failed: {}", e);
-            ic_cdk::println!("❌ {}", msg);
+            crate::log_op!("❌ {}", msg);
             record_rebalance(
                 RebalanceAction::Buy { token: token.clone(), usdt_amount: usd_amount },
                 false,
-                &msg
+                &msg,
+                origin,
+                None,
             );
             Err(e)
         }
@@ -462,31 +1165,38 @@ async fn execute_buy_action(token: &TrackedToken, usd_amount: f64) -> Result<Str
 /// Execute a sell action (token → ckUSDT)
 ///
 /// ## Process
-/// 1. Get current token price from Zone 3
-/// 2. Calculate token amount to sell (USD value / price)
+/// 1. Get current token rate from Zone 3
+/// 2. Calculate token amount to sell (USD value ÷ rate, in Nat)
 /// 3. Execute swap via Zone 4
 /// 4. Log results and update history
-async fn execute_sell_action(token: &TrackedToken, usd_value: f64) -> Result<String> {
-    // Get current token price
-    let price = crate::_3_KONG_LIQUIDITY::pools::get_token_price_in_usdt(token).await?;
-
-    // Calculate token amount to sell (in token's base units)
-    let token_decimals = token.get_decimals() as u32;
-    let decimal_multiplier = 10f64.powi(token_decimals as i32);
-    let token_amount_f64 = (usd_value / price) * decimal_multiplier;
-    let token_amount = Nat::from(token_amount_f64.round() as u64);
-
-    // Check if we have sufficient balance
-    let balance = crate::_2_CRITICAL_DATA::token_queries::get_token_balance_uncached(token).await?;
-    if balance < token_amount {
+async fn execute_sell_action(token: &TrackedToken, usd_value: f64, origin: CycleOrigin) -> Result<String> {
+    // Get current token rate (exact swap_amounts ratio)
+    let rate = crate::_3_KONG_LIQUIDITY::pools::get_token_rate_in_usdt(token).await?;
+    let price = rate.price_usdt();
+
+    // Calculate token amount to sell (in token's base units), never more than we hold
+    // outside what burners are owed, leaving the approve and transfer_from fees
+    let balance = crate::_2_CRITICAL_DATA::token_queries::get_spendable_token_balance(token).await?;
+    let fees = Nat::from(2 * crate::infrastructure::ledger_fees::transfer_fee(token.to_symbol()));
+    let token_amount = crate::infrastructure::math::calculate_sell_amount(
+        usd_value,
+        &rate.receive_e6,
+        &rate.pay_e8,
+        &balance,
+        &fees,
+    )?;
+    if token_amount == Nat::from(0u64) {
         return Err(IcpiError::Rebalance(RebalanceError::InsufficientBalance {
             token: token.to_symbol().to_string(),
             available: balance.to_string(),
-            required: token_amount.to_string(),
+            required: format!("${:.2}", usd_value),
         }));
     }
+    if token_amount.clone() + fees >= balance {
+        crate::log_op!("⚠️ Sell of ${:.2} {} clamped to full balance {}", usd_value, token.to_symbol(), balance);
+    }
 
-    ic_cdk::println!(
+    crate::log_op!(
         "💸 Selling {} {} (~${:.2}) for ckUSDT (price: ${:.6})",
         token_amount,
         token.to_symbol(),
@@ -494,16 +1204,20 @@ async fn execute_sell_action(token: &TrackedToken, usd_value: f64) -> Result<Str
         price
     );
 
+    if let Some(msg) = precheck_pool_depth(token, &token_amount, &TrackedToken::ckUSDT).await {
+        return Ok(msg);
+    }
+
     // Execute swap via Zone 4
-    let swap_result = crate::_4_TRADING_EXECUTION::swaps::execute_swap(
+    let swap_result = crate::_4_TRADING_EXECUTION::swaps::execute_swap_audited(
         token,
         token_amount.clone(),
         &TrackedToken::ckUSDT,
-        MAX_SLIPPAGE_PERCENT, // Kongswap expects percentage value (e.g., 5.0 = 5%)
+        MAX_TRADE_SLIPPAGE,
     ).await;
 
     match swap_result {
-        Ok(reply) => {
+        Ok((reply, audit)) => {
             let received_usd = reply.receive_amount.0.to_u64().unwrap_or(0) as f64 / 1_000_000.0;
             let msg = format!(
                 "Sold {} {} for ${:.2} (slippage: {:.4}%)",
@@ -512,43 +1226,144 @@ async fn execute_sell_action(token: &TrackedToken, usd_value: f64) -> Result<Str
                 received_usd,
                 reply.slippage
             );
-            ic_cdk::println!("✅ {}", msg);
+            crate::log_op!("✅ {}", msg);
             record_rebalance(
                 RebalanceAction::Sell { token: token.clone(), usdt_value: usd_value },
                 true,
-                &msg
+                &msg,
+                origin,
+                Some(audit),
             );
             Ok(msg)
         }
         Err(e) => {
             let msg = format!("Sell failed: {}", e);
-            ic_cdk::println!("❌ {}", msg);
+            crate::log_op!("❌ {}", msg);
             record_rebalance(
                 RebalanceAction::Sell { token: token.clone(), usdt_value: usd_value },
                 false,
-                &msg
+                &msg,
+                origin,
+                None,
             );
             Err(e)
         }
     }
 }
 
+/// Confirm a token can be priced before buying it
+///
+/// Every valuation must price each held token, and minting only accepts
+/// Kongswap prices, so buying a token that can't be priced that way would
+/// leave the portfolio unpriceable. Returns a skip message in that case;
+/// skipped trades are not recorded in history.
+async fn precheck_priceable(token: &TrackedToken) -> Option<String> {
+    use crate::_3_KONG_LIQUIDITY::pools::{get_token_price_quote, PricingPolicy};
+
+    precheck_priceable_with(token, |t| async move {
+        get_token_price_quote(&t, PricingPolicy::PrimaryOnly).await.map(|_| ())
+    }).await
+}
+
+/// `precheck_priceable` with the price query supplied by the caller
+async fn precheck_priceable_with<P, PF>(token: &TrackedToken, fetch_price: P) -> Option<String>
+where
+    P: FnOnce(TrackedToken) -> PF,
+    PF: std::future::Future<Output = Result<()>>,
+{
+    match fetch_price(token.clone()).await {
+        Ok(()) => None,
+        Err(e) => {
+            let msg = format!(
+                "Skipped buy of {}: token can't be priced for valuation ({})",
+                token.to_symbol(),
+                e
+            );
+            crate::log_op!("⏭️ {}", msg);
+            Some(msg)
+        }
+    }
+}
+
+/// Probe pool depth before spending an approval on a swap
+///
+/// Returns a skip message if the pool is too shallow for the trade. Skipped
+/// trades are not recorded in history. A failed probe doesn't block the
+/// trade - the swap's own slippage check still applies.
+async fn precheck_pool_depth(
+    pay_token: &TrackedToken,
+    pay_amount: &Nat,
+    receive_token: &TrackedToken,
+) -> Option<String> {
+    use crate::_4_TRADING_EXECUTION::depth::{check_pool_depth, DepthCheck};
+
+    match check_pool_depth(pay_token, pay_amount.clone(), receive_token, MAX_TRADE_SLIPPAGE).await {
+        Ok(DepthCheck::Sufficient { .. }) => None,
+        Ok(DepthCheck::Insufficient { expected_slippage, max_allowed }) => {
+            let msg = format!(
+                "Skipped {} {} → {}: insufficient pool depth (expected slippage {:.2}%, max {:.2}%)",
+                pay_amount,
+                pay_token.to_symbol(),
+                receive_token.to_symbol(),
+                expected_slippage,
+                max_allowed
+            );
+            crate::log_op!("⏭️ {}", msg);
+            Some(msg)
+        }
+        Err(e) => {
+            crate::log_op!("⚠️ Pool depth probe failed, proceeding with swap: {}", e);
+            None
+        }
+    }
+}
+
 /// Record rebalance result in history
 ///
 /// Keeps last MAX_REBALANCE_HISTORY records for recent history (fast queries)
 /// and adds to full history (persistent, bounded at MAX_FULL_HISTORY).
-fn record_rebalance(action: RebalanceAction, success: bool, details: &str) {
-    let record = RebalanceRecord {
-        timestamp: ic_cdk::api::time(),
-        action: action.clone(),
+fn record_rebalance(action: RebalanceAction, success: bool, details: &str, origin: CycleOrigin, swap: Option<SwapAudit>) {
+    let now = crate::infrastructure::runtime::time();
+    REBALANCE_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.last_rebalance = Some(now);
+        state.projected_next_action = None;
+    });
+
+    push_record(RebalanceRecord {
+        timestamp: now,
+        action,
         success,
         details: details.to_string(),
-    };
+        request_id: crate::infrastructure::logging::current_request_id(),
+        trigger: Some(origin.trigger),
+        triggered_by: origin.caller,
+        swap,
+    });
+}
 
+/// Record a poke that stopped before running a cycle
+///
+/// Stored in the rebalance history with the `Poke` trigger and no action;
+/// unlike a cycle it leaves the last rebalance time alone.
+pub(crate) fn record_poke(caller: Principal, success: bool, details: &str, now: u64) {
+    push_record(RebalanceRecord {
+        timestamp: now,
+        action: RebalanceAction::None,
+        success,
+        details: details.to_string(),
+        request_id: crate::infrastructure::logging::current_request_id(),
+        trigger: Some(RebalanceTrigger::Poke),
+        triggered_by: Some(caller),
+        swap: None,
+    });
+}
+
+/// Append a record to the recent and full histories
+fn push_record(record: RebalanceRecord) {
     // Update recent history (last 10, for get_rebalancer_status)
     REBALANCE_STATE.with(|state| {
         let mut state = state.borrow_mut();
-        state.last_rebalance = Some(ic_cdk::api::time());
         state.history.push(record.clone());
 
         // Keep only last MAX_REBALANCE_HISTORY records
@@ -569,3 +1384,455 @@ fn record_rebalance(action: RebalanceAction, success: bool, details: &str) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycles_budget_gate() {
+        let budget = crate::infrastructure::REBALANCE_CYCLES_BUDGET;
+        assert!(check_cycles_budget(budget, budget).is_ok());
+        assert!(check_cycles_budget(budget * 10, budget).is_ok());
+        let err = check_cycles_budget(budget - 1, budget).unwrap_err();
+        assert!(err.contains("below"), "{}", err);
+        // A zero budget disables the gate
+        assert!(check_cycles_budget(0, 0).is_ok());
+    }
+
+    fn skip(now: u64, cycles_balance: u128) -> Option<String> {
+        preflight_skip(now, cycles_balance).unwrap()
+    }
+
+    /// A TVL generation completed at `now`, so the pre-flight accepts the targets
+    fn complete_generation(now: u64) {
+        use crate::_3_KONG_LIQUIDITY::tvl::generation;
+        generation::import_state(generation::TvlGenerationState {
+            last_generation: 1,
+            building: None,
+            completed: Some(generation::CompletedTvlGeneration {
+                generation: 1,
+                started_at: now,
+                completed_at: now,
+                tvl: Vec::new(),
+                report: Default::default(),
+            }),
+        });
+    }
+
+    #[test]
+    fn test_preflight_blocks_during_unpause_cooldown() {
+        let now = 1_700_000_000 * 1_000_000_000;
+        complete_generation(now);
+        crate::infrastructure::admin::import_pause_info(crate::infrastructure::admin::PauseInfo {
+            unpaused_at: Some(now - 10 * 1_000_000_000),
+            ..Default::default()
+        });
+        let reason = skip(now, crate::infrastructure::REBALANCE_CYCLES_BUDGET).unwrap();
+        assert!(reason.contains("post-unpause cooldown"), "{}", reason);
+
+        crate::infrastructure::config::update_config(|c| c.unpause_cooldown_seconds = Some(5));
+        assert_eq!(skip(now, crate::infrastructure::REBALANCE_CYCLES_BUDGET), None);
+    }
+
+    #[test]
+    fn test_preflight_blocks_below_cycles_budget() {
+        let now = 1_700_000_000 * 1_000_000_000;
+        complete_generation(now);
+        let reason = skip(now, crate::infrastructure::REBALANCE_CYCLES_BUDGET - 1).unwrap();
+        assert!(reason.contains("rebalance budget"), "{}", reason);
+        assert_eq!(skip(now, crate::infrastructure::REBALANCE_CYCLES_BUDGET), None);
+    }
+
+    #[test]
+    fn test_preflight_requires_recent_generation() {
+        let now = 1_700_000_000 * 1_000_000_000;
+        let budget = crate::infrastructure::REBALANCE_CYCLES_BUDGET;
+        assert!(skip(now, budget).unwrap().contains("no TVL generation"));
+
+        complete_generation(now);
+        assert_eq!(skip(now, budget), None);
+        let max_age = crate::infrastructure::TVL_GENERATION_MAX_AGE_SECONDS;
+        assert!(skip(now + (max_age + 1) * 1_000_000_000, budget).is_some());
+    }
+
+    #[test]
+    fn test_next_rebalance_override_is_one_shot() {
+        let at = 10_000;
+
+        // Ticks before the override defer and keep it pending
+        assert_eq!(check_next_rebalance_override(Some(at), at - 1), (false, Some(at)));
+        // The first tick at or after it runs and consumes it
+        assert_eq!(check_next_rebalance_override(Some(at), at), (true, None));
+        assert_eq!(check_next_rebalance_override(Some(at), at + 5), (true, None));
+        // With nothing pending every tick runs: the normal cadence
+        let (run, remaining) = check_next_rebalance_override(None, at + 3_600);
+        assert!(run);
+        assert_eq!(remaining, None);
+    }
+
+    #[test]
+    fn test_stale_targets_gate() {
+        let max_age = crate::infrastructure::MAX_TARGET_AGE_SECONDS;
+        assert_eq!(max_age, 2 * crate::infrastructure::TVL_REFRESH_INTERVAL_SECONDS);
+        let tvl_at = 1_000_000_000_000u64;
+        let after = |seconds: u64| tvl_at + seconds * 1_000_000_000;
+
+        assert!(check_targets_age(Some(tvl_at), after(0), max_age).is_ok());
+        assert!(check_targets_age(Some(tvl_at), after(max_age), max_age).is_ok());
+        assert_eq!(
+            check_targets_age(Some(tvl_at), after(max_age + 1), max_age),
+            Err(RebalanceSkipReason::StaleTargets { age_seconds: Some(max_age + 1), max_age_seconds: max_age })
+        );
+
+        // Targets with no TVL timestamp are never trusted
+        let reason = check_targets_age(None, after(0), max_age).unwrap_err();
+        assert_eq!(reason, RebalanceSkipReason::StaleTargets { age_seconds: None, max_age_seconds: max_age });
+        assert!(reason.to_string().contains("no TVL timestamp"));
+    }
+
+    fn deviation(token: TrackedToken, usd_difference: f64) -> AllocationDeviation {
+        AllocationDeviation {
+            token,
+            current_pct: 0.0,
+            target_pct: 0.0,
+            deviation_pct: 0.0,
+            usd_difference,
+            trade_size_usd: usd_difference.abs() / 10.0,
+        }
+    }
+
+    #[test]
+    fn test_unavailable_pool_excluded_from_rebalancing() {
+        let deviations = vec![
+            deviation(TrackedToken::BOB, 500.0),
+            deviation(TrackedToken::ALEX, 200.0),
+            deviation(TrackedToken::KONG, -50.0),
+        ];
+        let ckusdt = Nat::from(100_000_000u64); // $100
+
+        match get_rebalancing_action(&deviations, &ckusdt).unwrap() {
+            RebalanceAction::Buy { token, .. } => assert_eq!(token, TrackedToken::BOB),
+            other => panic!("Expected buy, got {:?}", other),
+        }
+
+        let tradable = tradable_deviations(&deviations, &[TrackedToken::BOB]);
+        assert_eq!(tradable.len(), 2);
+        match get_rebalancing_action(&tradable, &ckusdt).unwrap() {
+            RebalanceAction::Buy { token, .. } => assert_eq!(token, TrackedToken::ALEX),
+            other => panic!("Expected buy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reserved_deposits_reduce_buy_budget() {
+        let deviations = vec![deviation(TrackedToken::ALEX, 400.0)];
+        let balance = Nat::from(50_000_000u64); // $50
+
+        let full = explain_rebalancing_action(&deviations, &CkusdtBudget::new(&balance, &Nat::from(0u64)).free);
+        let RebalanceAction::Buy { usdt_amount: full_buy, .. } = full.action else {
+            panic!("Expected buy, got {:?}", full.action);
+        };
+        assert_eq!(full_buy, 40.0);
+
+        // $45 of the balance is a pending mint deposit
+        let budget = CkusdtBudget::new(&balance, &Nat::from(45_000_000u64));
+        assert_eq!(budget.free, Nat::from(5_000_000u64));
+        let reduced = explain_rebalancing_action(&deviations, &budget.free);
+        let RebalanceAction::Buy { usdt_amount: reduced_buy, .. } = reduced.action else {
+            panic!("Expected buy, got {:?}", reduced.action);
+        };
+        assert_eq!(reduced_buy, 5.0);
+
+        // Reserve covering the whole balance leaves nothing to buy with
+        let budget = CkusdtBudget::new(&balance, &Nat::from(60_000_000u64));
+        assert_eq!(budget.free, Nat::from(0u64));
+        assert!(matches!(explain_rebalancing_action(&deviations, &budget.free).action, RebalanceAction::None));
+    }
+
+    #[test]
+    fn test_projection_mirrors_cycle_and_is_invalidated() {
+        let state = crate::types::portfolio::IndexState {
+            total_value: 100.0,
+            current_positions: Vec::new(),
+            target_allocations: Vec::new(),
+            deviations: vec![deviation(TrackedToken::ALEX, 400.0), deviation(TrackedToken::KONG, 300.0)],
+            timestamp: 5,
+            ckusdt_balance: Nat::from(50_000_000u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        };
+
+        let projection = project_action(&state, &Nat::from(45_000_000u64), &[], 9);
+        assert_eq!(projection.action, RebalanceAction::Buy { token: TrackedToken::ALEX, usdt_amount: 5.0 });
+        assert_eq!((projection.projected_at, projection.state_timestamp), (9, 5));
+
+        // Unavailable pools are skipped as in a real cycle
+        let projection = project_action(&state, &Nat::from(0u64), &[TrackedToken::ALEX], 9);
+        assert!(matches!(projection.action, RebalanceAction::Buy { token: TrackedToken::KONG, .. }));
+
+        REBALANCE_STATE.with(|s| s.borrow_mut().projected_next_action = Some(projection.clone()));
+        assert_eq!(stored_projection(), Some(projection));
+        invalidate_projection();
+        assert_eq!(stored_projection(), None);
+    }
+
+    fn stored_projection() -> Option<ProjectedAction> {
+        REBALANCE_STATE.with(|s| s.borrow().projected_next_action.clone())
+    }
+
+    #[test]
+    fn test_explanation_for_known_deviations() {
+        let mut alex = deviation(TrackedToken::ALEX, 40.0);
+        alex.deviation_pct = 8.0;
+        let mut kong = deviation(TrackedToken::KONG, -25.0);
+        kong.deviation_pct = -5.0;
+        let deviations = vec![alex, deviation(TrackedToken::ZERO, 10.0), kong];
+
+        // $5 ckUSDT: enough to buy the most underweight token
+        let explanation = explain_rebalancing_action(&deviations, &Nat::from(5_000_000u64));
+        assert!(matches!(
+            explanation.action,
+            RebalanceAction::Buy { token: TrackedToken::ALEX, usdt_amount } if usdt_amount == 4.0
+        ));
+        assert_eq!(explanation.most_underweight.as_ref().unwrap().token, TrackedToken::ALEX);
+        assert_eq!(explanation.most_overweight.as_ref().unwrap().token, TrackedToken::KONG);
+        assert_eq!(explanation.ckusdt_available_usd, 5.0);
+        assert_eq!(explanation.min_trade_size_usd, MIN_TRADE_SIZE_USD);
+        assert_eq!(explanation.decision_path.len(), 1);
+        assert!(explanation.reason.contains("ALEX is 8.00% underweight"));
+
+        // No ckUSDT: falls through to selling the most overweight token
+        let explanation = explain_rebalancing_action(&deviations, &Nat::from(0u64));
+        assert!(matches!(
+            explanation.action,
+            RebalanceAction::Sell { token: TrackedToken::KONG, usdt_value } if usdt_value == 2.5
+        ));
+        assert_eq!(explanation.decision_path.len(), 2);
+        assert!(explanation.decision_path[0].contains("below the $1.00 minimum trade"));
+        assert!(explanation.reason.contains("KONG is 5.00% overweight"));
+
+        // Agrees with the action the rebalancer would execute
+        assert!(matches!(
+            get_rebalancing_action(&deviations, &Nat::from(0u64)).unwrap(),
+            RebalanceAction::Sell { token: TrackedToken::KONG, .. }
+        ));
+    }
+
+    #[test]
+    fn test_all_overweight_takes_no_action() {
+        // Every token overweight: the targets add up to less than is held
+        let deviations = vec![
+            deviation(TrackedToken::ALEX, -40.0),
+            deviation(TrackedToken::ZERO, -30.0),
+            deviation(TrackedToken::KONG, -20.0),
+            deviation(TrackedToken::BOB, -10.0),
+        ];
+
+        let explanation = explain_rebalancing_action(&deviations, &Nat::from(0u64));
+        assert!(matches!(explanation.action, RebalanceAction::None));
+        let anomaly = explanation.anomaly.unwrap();
+        assert_eq!((anomaly.underweight_usd, anomaly.overweight_usd), (0.0, 100.0));
+        assert_eq!(anomaly.tolerance_usd, 10.0);
+        assert!(explanation.reason.contains("inconsistent deviations"));
+
+        // Even with ckUSDT available nothing is bought or sold
+        let explanation = explain_rebalancing_action(&deviations, &Nat::from(500_000_000u64));
+        assert!(matches!(explanation.action, RebalanceAction::None));
+        assert!(explanation.anomaly.is_some());
+    }
+
+    #[test]
+    fn test_deviation_balance_tolerance() {
+        // Cash makes tokens underweight on net: consistent
+        let deviations = vec![deviation(TrackedToken::ALEX, 60.0), deviation(TrackedToken::BOB, -10.0)];
+        assert_eq!(check_deviation_balance(&deviations, 50.0), None);
+
+        // Small net overweight from price drift is tolerated
+        let deviations = vec![deviation(TrackedToken::ALEX, 45.0), deviation(TrackedToken::BOB, -50.0)];
+        assert_eq!(check_deviation_balance(&deviations, 0.0), None);
+
+        // Tiny portfolios get the minimum trade size as tolerance
+        let deviations = vec![deviation(TrackedToken::BOB, -0.9)];
+        assert_eq!(check_deviation_balance(&deviations, 0.0), None);
+
+        let deviations = vec![deviation(TrackedToken::ALEX, 10.0), deviation(TrackedToken::BOB, -50.0)];
+        assert!(check_deviation_balance(&deviations, 0.0).is_some());
+    }
+
+    #[test]
+    fn test_explanation_when_balanced() {
+        let deviations = vec![deviation(TrackedToken::ALEX, 0.5), deviation(TrackedToken::BOB, -0.5)];
+        let explanation = explain_rebalancing_action(&deviations, &Nat::from(100_000_000u64));
+
+        assert!(matches!(explanation.action, RebalanceAction::None));
+        assert_eq!(explanation.decision_path.len(), 2);
+        assert!(explanation.decision_path[0].starts_with("Largest deficit ALEX"));
+        assert!(explanation.decision_path[1].starts_with("Largest excess BOB"));
+    }
+
+    #[test]
+    fn test_buy_into_unpriceable_token_is_skipped() {
+        let skipped = futures::executor::block_on(precheck_priceable_with(&TrackedToken::KONG, |_| async {
+            Err(IcpiError::Other("no KONG/ckUSDT pool".to_string()))
+        }));
+        let msg = skipped.expect("unpriceable buy should be skipped");
+        assert!(msg.starts_with("Skipped buy of KONG: token can't be priced for valuation"));
+        assert!(msg.contains("no KONG/ckUSDT pool"));
+
+        let priced = futures::executor::block_on(precheck_priceable_with(&TrackedToken::KONG, |_| async { Ok(()) }));
+        assert_eq!(priced, None);
+    }
+
+    fn history_record(timestamp: u64, action: RebalanceAction) -> RebalanceRecord {
+        RebalanceRecord {
+            timestamp,
+            action,
+            success: true,
+            details: format!("trade at {}", timestamp),
+            request_id: Some(format!("req-{}", timestamp)),
+            trigger: Some(RebalanceTrigger::Timer),
+            triggered_by: None,
+            swap: None,
+        }
+    }
+
+    fn sample_history(count: u64) -> Vec<RebalanceRecord> {
+        (1..=count).map(|i| history_record(i * 1_000, if i % 2 == 0 {
+            RebalanceAction::Buy { token: TrackedToken::ALEX, usdt_amount: i as f64 }
+        } else {
+            RebalanceAction::Sell { token: TrackedToken::KONG, usdt_value: i as f64 }
+        })).collect()
+    }
+
+    fn debug_all(records: &[RebalanceRecord]) -> Vec<String> {
+        records.iter().map(|r| format!("{:?}", r)).collect()
+    }
+
+    #[test]
+    fn test_poke_recorded_in_history() {
+        let poker = Principal::from_text("aaaaa-aa").unwrap();
+        let before = last_rebalance_time();
+
+        record_poke(poker, true, "Poke not needed: Cooldown", 7_000);
+
+        let record = get_full_trade_history().pop().unwrap();
+        assert_eq!((record.timestamp, record.action, record.success), (7_000, RebalanceAction::None, true));
+        assert_eq!((record.trigger, record.triggered_by), (Some(RebalanceTrigger::Poke), Some(poker)));
+        // A poke that didn't trade doesn't count as a cycle
+        assert_eq!(last_rebalance_time(), before);
+    }
+
+    #[test]
+    fn test_history_filtered_by_trigger() {
+        let admin = Principal::from_text("2vxsx-fae").unwrap();
+        let poker = Principal::from_text("aaaaa-aa").unwrap();
+        let mut history = sample_history(6);
+        history[1].trigger = Some(RebalanceTrigger::Manual);
+        history[1].triggered_by = Some(admin);
+        history[3].trigger = Some(RebalanceTrigger::Poke);
+        history[3].triggered_by = Some(poker);
+        history[4].trigger = Some(RebalanceTrigger::Manual);
+        history[4].triggered_by = Some(admin);
+        // Recorded before triggers were tracked
+        history[5].trigger = None;
+
+        let manual = TradeHistoryFilter { trigger: Some(RebalanceTrigger::Manual), triggered_by: None };
+        let (page, total) = filter_history(&history, &manual, 0, 10);
+        assert_eq!(total, 2);
+        assert_eq!(page.iter().map(|r| r.timestamp).collect::<Vec<_>>(), vec![2_000, 5_000]);
+
+        // Pagination applies to the matching records
+        let (page, total) = filter_history(&history, &manual, 1, 10);
+        assert_eq!((page.len(), total), (1, 2));
+        assert_eq!(page[0].timestamp, 5_000);
+
+        let by_poker = TradeHistoryFilter { trigger: None, triggered_by: Some(poker) };
+        let (page, _) = filter_history(&history, &by_poker, 0, 10);
+        assert_eq!(page.iter().map(|r| r.timestamp).collect::<Vec<_>>(), vec![4_000]);
+
+        let timer = TradeHistoryFilter { trigger: Some(RebalanceTrigger::Timer), triggered_by: None };
+        assert_eq!(filter_history(&history, &timer, 0, 10).1, 2);
+        assert_eq!(filter_history(&history, &TradeHistoryFilter::default(), 0, 10).1, 6);
+    }
+
+    #[test]
+    fn test_trade_history_candid_round_trip() {
+        let records = sample_history(5);
+        let bytes = candid::encode_args((records.clone(), HistoryImportMode::Append)).unwrap();
+        let (decoded, mode): (Vec<RebalanceRecord>, HistoryImportMode) = candid::decode_args(&bytes).unwrap();
+        assert_eq!(debug_all(&decoded), debug_all(&records));
+        assert_eq!(mode, HistoryImportMode::Append);
+
+        FULL_HISTORY.with(|h| *h.borrow_mut() = records.clone());
+        let chunk = export_trade_history_chunked(1, 2);
+        let decoded: TradeHistoryChunk = candid::decode_one(&candid::encode_one(&chunk).unwrap()).unwrap();
+        assert_eq!(debug_all(&decoded.records), debug_all(&records[1..3]));
+        assert_eq!((decoded.offset, decoded.total, decoded.next_offset), (1, 5, Some(3)));
+    }
+
+    #[test]
+    fn test_chunked_export_then_import_round_trip() {
+        let records = sample_history(7);
+        FULL_HISTORY.with(|h| *h.borrow_mut() = records.clone());
+
+        let mut exported = Vec::new();
+        let mut offset = Some(0);
+        while let Some(next) = offset {
+            let chunk = export_trade_history_chunked(next, 3);
+            exported.extend(chunk.records);
+            offset = chunk.next_offset;
+        }
+        assert_eq!(debug_all(&exported), debug_all(&records));
+
+        FULL_HISTORY.with(|h| h.borrow_mut().clear());
+        assert_eq!(apply_history_import(exported, HistoryImportMode::Replace, 10_000).unwrap(), 7);
+        assert_eq!(debug_all(&get_full_trade_history()), debug_all(&records));
+    }
+
+    #[test]
+    fn test_export_chunk_is_capped() {
+        FULL_HISTORY.with(|h| *h.borrow_mut() = sample_history(MAX_HISTORY_EXPORT_CHUNK + 10));
+        let chunk = export_trade_history_chunked(0, u64::MAX);
+        assert_eq!(chunk.records.len() as u64, MAX_HISTORY_EXPORT_CHUNK);
+        assert_eq!(chunk.next_offset, Some(MAX_HISTORY_EXPORT_CHUNK));
+
+        let last = export_trade_history_chunked(MAX_HISTORY_EXPORT_CHUNK, u64::MAX);
+        assert_eq!(last.records.len(), 10);
+        assert_eq!(last.next_offset, None);
+    }
+
+    #[test]
+    fn test_history_import_modes() {
+        FULL_HISTORY.with(|h| *h.borrow_mut() = sample_history(3));
+
+        // Append may not predate the latest existing record
+        let early = vec![history_record(2_500, RebalanceAction::None)];
+        assert!(apply_history_import(early.clone(), HistoryImportMode::Append, 10_000).is_err());
+        assert_eq!(get_full_trade_history().len(), 3);
+
+        let later = vec![history_record(3_000, RebalanceAction::None), history_record(4_000, RebalanceAction::None)];
+        assert_eq!(apply_history_import(later, HistoryImportMode::Append, 10_000).unwrap(), 5);
+
+        // Replace starts over, so older records are fine
+        assert_eq!(apply_history_import(early, HistoryImportMode::Replace, 10_000).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_history_import_validation() {
+        let now = 10_000;
+        assert!(validate_history_import(&sample_history(5), None, now).is_ok());
+        assert!(validate_history_import(&[], Some(now), now).is_ok());
+
+        let out_of_order = vec![history_record(2_000, RebalanceAction::None), history_record(1_000, RebalanceAction::None)];
+        assert!(validate_history_import(&out_of_order, None, now).is_err());
+
+        assert!(validate_history_import(&[history_record(0, RebalanceAction::None)], None, now).is_err());
+        assert!(validate_history_import(&[history_record(now + 1, RebalanceAction::None)], None, now).is_err());
+
+        let bad_amount = history_record(1_000, RebalanceAction::Buy { token: TrackedToken::BOB, usdt_amount: f64::NAN });
+        assert!(validate_history_import(&[bad_amount], None, now).is_err());
+        let negative = history_record(1_000, RebalanceAction::Sell { token: TrackedToken::BOB, usdt_value: -1.0 });
+        assert!(validate_history_import(&[negative], None, now).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Public Rebalance Poke
+//!
+//! Lets anyone nudge a visibly drifted portfolio without waiting for the
+//! hourly tick. A poke only runs a cycle if the last cycle is older than
+//! half the rebalance interval and some deviation exceeds the deadband;
+//! otherwise it returns why it wasn't needed. At most one poke per
+//! `POKE_COOLDOWN_SECONDS` may run a cycle, and all the usual pause and
+//! operation locks and pre-flight checks apply. Every poke lands in the
+//! rebalance history with the `Poke` trigger: a cycle records itself, and a
+//! poke that stops short is recorded with its reason.
+
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Principal};
+use crate::infrastructure::{
+    Result, REBALANCE_INTERVAL_SECONDS, MIN_DEVIATION_PERCENT,
+    POKE_COOLDOWN_SECONDS,
+};
+use crate::types::rebalancing::AllocationDeviation;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Why a poke didn't run a cycle
+#[derive(Debug, Clone, PartialEq, CandidType, Deserialize, serde::Serialize)]
+pub enum PokeNotNeeded {
+    /// Another poke ran a cycle recently
+    Cooldown { retry_after_seconds: u64 },
+    /// The last cycle is too recent
+    RecentCycle { seconds_since_last: u64, min_seconds: u64 },
+    /// No token deviates beyond the deadband
+    WithinDeadband { max_deviation_pct: f64, deadband_pct: f64 },
+    /// The targets come from TVL data that is too old to trade on
+    StaleTargets(super::RebalanceSkipReason),
+    /// A check every cycle must pass refused it (see `preflight_skip`)
+    Blocked { reason: String },
+}
+
+/// Result of a poke
+#[derive(Debug, Clone, PartialEq, CandidType, Deserialize, serde::Serialize)]
+pub enum PokeOutcome {
+    Rebalanced { details: String },
+    NotNeeded(PokeNotNeeded),
+}
+
+thread_local! {
+    static LAST_SUCCESSFUL_POKE: RefCell<Option<u64>> = RefCell::new(None);
+}
+
+/// Timing checks that need no portfolio state
+pub fn check_poke_timing(now: u64, last_poke: Option<u64>, last_rebalance: Option<u64>) -> Option<PokeNotNeeded> {
+    if let Some(last) = last_poke {
+        let elapsed = now.saturating_sub(last) / NANOS_PER_SECOND;
+        if elapsed < POKE_COOLDOWN_SECONDS {
+            return Some(PokeNotNeeded::Cooldown {
+                retry_after_seconds: POKE_COOLDOWN_SECONDS - elapsed,
+            });
+        }
+    }
+
+    if let Some(last) = last_rebalance {
+        let elapsed = now.saturating_sub(last) / NANOS_PER_SECOND;
+        let min_seconds = REBALANCE_INTERVAL_SECONDS / 2;
+        if elapsed < min_seconds {
+            return Some(PokeNotNeeded::RecentCycle { seconds_since_last: elapsed, min_seconds });
+        }
+    }
+
+    None
+}
+
+/// Deadband check on current deviations
+pub fn check_poke_deviation(deviations: &[AllocationDeviation]) -> Option<PokeNotNeeded> {
+    let max_deviation_pct = deviations.iter()
+        .map(|d| d.deviation_pct.abs())
+        .filter(|d| d.is_finite())
+        .fold(0.0, f64::max);
+
+    if max_deviation_pct > MIN_DEVIATION_PERCENT {
+        None
+    } else {
+        Some(PokeNotNeeded::WithinDeadband { max_deviation_pct, deadband_pct: MIN_DEVIATION_PERCENT })
+    }
+}
+
+/// Run a rebalance cycle if one is needed (public)
+pub async fn poke_rebalance(caller: Principal) -> Result<PokeOutcome> {
+    let last_poke = LAST_SUCCESSFUL_POKE.with(|p| *p.borrow());
+    let result = poke_inner(caller).await;
+
+    let outcome = match &result {
+        Ok(PokeOutcome::Rebalanced { details }) => format!("rebalanced: {}", details),
+        Ok(PokeOutcome::NotNeeded(reason)) => format!("not needed: {:?}", reason),
+        Err(e) => format!("error: {}", e),
+    };
+    ic_cdk::println!("👉 Rebalance poke by {}: {}", caller, outcome);
+
+    // A cycle that ran is already in the history
+    let cycle_ran = LAST_SUCCESSFUL_POKE.with(|p| *p.borrow()) != last_poke;
+    if !cycle_ran {
+        super::record_poke(caller, result.is_ok(), &format!("Poke {}", outcome), crate::infrastructure::runtime::time());
+    }
+
+    result
+}
+
+async fn poke_inner(caller: Principal) -> Result<PokeOutcome> {
+    crate::infrastructure::check_not_paused()?;
+
+    let last_poke = LAST_SUCCESSFUL_POKE.with(|p| *p.borrow());
+    if let Some(reason) = check_poke_timing(crate::infrastructure::runtime::time(), last_poke, super::last_rebalance_time()) {
+        return Ok(PokeOutcome::NotNeeded(reason));
+    }
+
+    crate::infrastructure::rate_limiting::check_operation_rate_limit(
+        crate::infrastructure::rate_limiting::RateLimitedOperation::ManualRebalance,
+        &caller,
+    )?;
+
+    super::begin_exclusive_rebalance()?;
+    let result = poke_locked().await;
+    super::end_exclusive_rebalance();
+
+    result
+}
+
+async fn poke_locked() -> Result<PokeOutcome> {
+    let now = crate::infrastructure::runtime::time();
+    if let Some(reason) = super::preflight_skip(now, crate::infrastructure::runtime::cycles_balance())? {
+        return Ok(PokeOutcome::NotNeeded(PokeNotNeeded::Blocked { reason }));
+    }
+
+    let state = crate::_5_INFORMATIONAL::display::refresh_index_state_with_policy(
+        crate::_3_KONG_LIQUIDITY::pools::PricingPolicy::PrimaryOnly,
+    ).await?;
+
+    if let Some(reason) = super::stale_targets_skip(&state, crate::infrastructure::runtime::time()) {
+        return Ok(PokeOutcome::NotNeeded(PokeNotNeeded::StaleTargets(reason)));
+    }
+    if let Some(reason) = check_poke_deviation(&state.deviations) {
+        return Ok(PokeOutcome::NotNeeded(reason));
+    }
+
+    // The cooldown starts once a cycle runs, whatever its result
+    LAST_SUCCESSFUL_POKE.with(|p| *p.borrow_mut() = Some(crate::infrastructure::runtime::time()));
+
+    let origin = super::CycleOrigin::by(super::RebalanceTrigger::Poke, crate::infrastructure::runtime::caller());
+    let details = super::rebalance_with_state(&state, origin).await?;
+    Ok(PokeOutcome::Rebalanced { details })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TrackedToken;
+
+    const MINUTE: u64 = 60 * NANOS_PER_SECOND;
+
+    fn deviation(deviation_pct: f64) -> AllocationDeviation {
+        AllocationDeviation {
+            token: TrackedToken::ALEX,
+            current_pct: 25.0 - deviation_pct,
+            target_pct: 25.0,
+            deviation_pct,
+            usd_difference: deviation_pct,
+            trade_size_usd: deviation_pct / 10.0,
+        }
+    }
+
+    #[test]
+    fn test_poke_cooldown() {
+        let now = 100 * MINUTE;
+        assert_eq!(
+            check_poke_timing(now, Some(now - 5 * MINUTE), None),
+            Some(PokeNotNeeded::Cooldown { retry_after_seconds: 600 })
+        );
+        assert_eq!(check_poke_timing(now, Some(now - 15 * MINUTE), None), None);
+    }
+
+    #[test]
+    fn test_poke_requires_stale_cycle() {
+        let now = 100 * MINUTE;
+        assert_eq!(
+            check_poke_timing(now, None, Some(now - 10 * MINUTE)),
+            Some(PokeNotNeeded::RecentCycle { seconds_since_last: 600, min_seconds: 1800 })
+        );
+        assert_eq!(check_poke_timing(now, None, Some(now - 30 * MINUTE)), None);
+        // No cycle since install/upgrade
+        assert_eq!(check_poke_timing(now, None, None), None);
+    }
+
+    #[test]
+    fn test_poke_deadband() {
+        assert_eq!(
+            check_poke_deviation(&[deviation(0.5), deviation(-0.8)]),
+            Some(PokeNotNeeded::WithinDeadband { max_deviation_pct: 0.8, deadband_pct: MIN_DEVIATION_PERCENT })
+        );
+        assert_eq!(check_poke_deviation(&[deviation(0.5), deviation(-3.0)]), None);
+        assert!(check_poke_deviation(&[]).is_some());
+    }
+}
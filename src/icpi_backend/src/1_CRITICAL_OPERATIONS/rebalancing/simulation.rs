@@ -0,0 +1,331 @@
+//! Rebalance cycle simulation
+//!
+//! Replays the rebalancer's decision rule against a portfolio held at fixed
+//! prices: each cycle takes the action `explain_rebalancing_action` would,
+//! filled in full with no slippage or fees. For planning estimates only;
+//! nothing here trades.
+
+use candid::{CandidType, Deserialize, Nat};
+
+use super::{explain_rebalancing_action, RebalanceAction};
+use crate::infrastructure::{Result, bps_of, MIN_TRADE_SIZE_USD, REBALANCE_INTERVAL_SECONDS};
+use crate::types::TrackedToken;
+use crate::types::portfolio::IndexState;
+use crate::types::rebalancing::AllocationDeviation;
+
+/// Upper bound on simulated cycles (about three weeks of hourly rebalances)
+pub const MAX_SIMULATED_CYCLES: u32 = 500;
+
+/// Portfolio in USD at fixed prices
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedPortfolio {
+    /// USD value held of each tracked token
+    pub holdings: Vec<(TrackedToken, f64)>,
+    /// Target percentage of total value for each token
+    pub targets: Vec<(TrackedToken, f64)>,
+    pub ckusdt_usd: f64,
+}
+
+impl SimulatedPortfolio {
+    /// Holdings and targets of an index state
+    pub fn from_state(state: &IndexState) -> Self {
+        SimulatedPortfolio {
+            holdings: state.current_positions.iter()
+                .filter(|p| p.token != TrackedToken::ckUSDT)
+                .map(|p| (p.token.clone(), p.usd_value))
+                .collect(),
+            targets: state.target_allocations.iter()
+                .map(|t| (t.token.clone(), t.target_percentage))
+                .collect(),
+            ckusdt_usd: num_traits::ToPrimitive::to_f64(&state.ckusdt_balance.0).unwrap_or(0.0) / 1_000_000.0,
+        }
+    }
+
+    /// Total value including ckUSDT
+    pub fn total_usd(&self) -> f64 {
+        self.holdings.iter().map(|(_, v)| v).sum::<f64>() + self.ckusdt_usd
+    }
+
+    /// ckUSDT share of total value in percent
+    pub fn ckusdt_pct(&self) -> f64 {
+        let total = self.total_usd();
+        if total > 0.0 { self.ckusdt_usd / total * 100.0 } else { 0.0 }
+    }
+
+    fn held(&self, token: &TrackedToken) -> f64 {
+        self.holdings.iter().find(|(t, _)| t == token).map(|(_, v)| *v).unwrap_or(0.0)
+    }
+
+    /// Deviations computed the way the portfolio state does: current
+    /// percentages are whole basis points of the e6 total
+    pub fn deviations(&self) -> Result<Vec<AllocationDeviation>> {
+        let total = self.total_usd();
+        let total_e6 = Nat::from(to_e6(total));
+        self.targets.iter().map(|(token, target_pct)| {
+            let current_usd = self.held(token);
+            let current_pct = if total_e6 > Nat::from(0u64) {
+                bps_of(&Nat::from(to_e6(current_usd)), &total_e6)? as f64 / 100.0
+            } else {
+                0.0
+            };
+            let deviation_pct = target_pct - current_pct;
+            let usd_difference = total * (target_pct / 100.0) - current_usd;
+            Ok(AllocationDeviation {
+                token: token.clone(),
+                current_pct,
+                target_pct: *target_pct,
+                deviation_pct,
+                usd_difference,
+                trade_size_usd: crate::_2_CRITICAL_DATA::trade_intensity::trade_size_usd(usd_difference, deviation_pct)?,
+            })
+        }).collect()
+    }
+
+    /// Fill an action in full; buys are capped at the ckUSDT held
+    pub fn apply(&mut self, action: &RebalanceAction) -> RebalanceAction {
+        let (token, delta) = match action {
+            RebalanceAction::Buy { token, usdt_amount } => {
+                let amount = usdt_amount.min(self.ckusdt_usd);
+                (token, amount)
+            }
+            RebalanceAction::Sell { token, usdt_value } => {
+                let amount = usdt_value.min(self.held(token));
+                (token, -amount)
+            }
+            RebalanceAction::None => return RebalanceAction::None,
+        };
+
+        match self.holdings.iter_mut().find(|(t, _)| t == token) {
+            Some((_, value)) => *value += delta,
+            None => self.holdings.push((token.clone(), delta)),
+        }
+        self.ckusdt_usd -= delta;
+
+        if delta >= 0.0 {
+            RebalanceAction::Buy { token: token.clone(), usdt_amount: delta }
+        } else {
+            RebalanceAction::Sell { token: token.clone(), usdt_value: -delta }
+        }
+    }
+}
+
+/// USD to ckUSDT e6, rounded to the nearest unit
+fn to_e6(usd: f64) -> u128 {
+    (usd.max(0.0) * 1_000_000.0).round() as u128
+}
+
+/// The action the rebalancer would take next
+fn next_action(portfolio: &SimulatedPortfolio) -> Result<RebalanceAction> {
+    let ckusdt_e6 = Nat::from((portfolio.ckusdt_usd.max(0.0) * 1_000_000.0) as u64);
+    Ok(explain_rebalancing_action(&portfolio.deviations()?, &ckusdt_e6).action)
+}
+
+/// Actions taken over at most `max_cycles` cycles, stopping at the first
+/// cycle with nothing to do; `portfolio` is left in its final state
+pub fn simulate_cycles(portfolio: &mut SimulatedPortfolio, max_cycles: u32) -> Result<Vec<RebalanceAction>> {
+    let mut actions = Vec::new();
+    for _ in 0..max_cycles.min(MAX_SIMULATED_CYCLES) {
+        let decision = next_action(portfolio)?;
+        if matches!(decision, RebalanceAction::None) {
+            break;
+        }
+        actions.push(portfolio.apply(&decision));
+    }
+    Ok(actions)
+}
+
+/// When a token is projected to settle within tolerance
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenConvergence {
+    pub token: TrackedToken,
+    /// Current deviation from target in percentage points
+    pub deviation_pct: f64,
+    /// Cycles until the token is within tolerance for good (0 if it already
+    /// is); None if it is still outside when the simulation stops
+    pub cycles: Option<u32>,
+    pub seconds: Option<u64>,
+}
+
+/// Projected time for the portfolio to reach tolerance
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConvergenceEstimate {
+    /// False if the rebalancer would still be trading after `MAX_SIMULATED_CYCLES`
+    pub converged: bool,
+    /// Cycles until the rebalancer has nothing left to do
+    pub cycles: u32,
+    /// `cycles` at one cycle per rebalance interval
+    pub seconds: u64,
+    pub interval_seconds: u64,
+    pub tokens: Vec<TokenConvergence>,
+}
+
+/// Whether the rebalancer leaves a token alone: its gap to target is no
+/// larger than the minimum trade
+fn within_tolerance(deviation: &AllocationDeviation) -> bool {
+    deviation.usd_difference.abs() <= MIN_TRADE_SIZE_USD
+}
+
+/// Simulate cycles until the rebalancer has nothing to do
+///
+/// Wall-clock times assume one cycle per `REBALANCE_INTERVAL_SECONDS`,
+/// counted from the next cycle.
+pub fn simulate_convergence(portfolio: &mut SimulatedPortfolio) -> Result<ConvergenceEstimate> {
+    let initial = portfolio.deviations()?;
+    let mut settled_at: Vec<Option<u32>> = initial.iter()
+        .map(|d| within_tolerance(d).then_some(0))
+        .collect();
+
+    let mut cycles = 0;
+    let mut converged = false;
+    while cycles < MAX_SIMULATED_CYCLES {
+        let decision = next_action(portfolio)?;
+        if matches!(decision, RebalanceAction::None) {
+            converged = true;
+            break;
+        }
+        portfolio.apply(&decision);
+        cycles += 1;
+
+        for (settled, deviation) in settled_at.iter_mut().zip(portfolio.deviations()?) {
+            if !within_tolerance(&deviation) {
+                *settled = None;
+            } else if settled.is_none() {
+                *settled = Some(cycles);
+            }
+        }
+    }
+    if !converged {
+        converged = matches!(next_action(portfolio)?, RebalanceAction::None);
+    }
+
+    let to_seconds = |c: u32| c as u64 * REBALANCE_INTERVAL_SECONDS;
+    Ok(ConvergenceEstimate {
+        converged,
+        cycles,
+        seconds: to_seconds(cycles),
+        interval_seconds: REBALANCE_INTERVAL_SECONDS,
+        tokens: initial.into_iter().zip(settled_at).map(|(deviation, settled)| TokenConvergence {
+            token: deviation.token,
+            deviation_pct: deviation.deviation_pct,
+            cycles: settled,
+            seconds: settled.map(to_seconds),
+        }).collect(),
+    })
+}
+
+/// Estimate convergence from the cached index state
+pub async fn estimate_convergence() -> Result<ConvergenceEstimate> {
+    let state = crate::_5_INFORMATIONAL::display::get_index_state_uncached().await?;
+    simulate_convergence(&mut SimulatedPortfolio::from_state(&state))
+}
+
+/// Total ckUSDT spent on buys
+pub fn total_bought_usd(actions: &[RebalanceAction]) -> f64 {
+    actions.iter()
+        .map(|a| match a {
+            RebalanceAction::Buy { usdt_amount, .. } => *usdt_amount,
+            _ => 0.0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn portfolio(holdings: &[(TrackedToken, f64)], ckusdt_usd: f64) -> SimulatedPortfolio {
+        SimulatedPortfolio {
+            holdings: holdings.to_vec(),
+            targets: TrackedToken::all().iter().map(|t| (t.clone(), 25.0)).collect(),
+            ckusdt_usd,
+        }
+    }
+
+    #[test]
+    fn test_balanced_portfolio_takes_no_action() {
+        let mut p = portfolio(&[
+            (TrackedToken::ALEX, 25.0), (TrackedToken::ZERO, 25.0),
+            (TrackedToken::KONG, 25.0), (TrackedToken::BOB, 25.0),
+        ], 0.0);
+        assert!(simulate_cycles(&mut p, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ckusdt_inflow_is_deployed_by_buys() {
+        let mut p = portfolio(&[
+            (TrackedToken::ALEX, 250.0), (TrackedToken::ZERO, 250.0),
+            (TrackedToken::KONG, 250.0), (TrackedToken::BOB, 250.0),
+        ], 1_000.0);
+        let before = p.total_usd();
+
+        let actions = simulate_cycles(&mut p, MAX_SIMULATED_CYCLES).unwrap();
+        assert!(!actions.is_empty());
+        assert!(actions.iter().all(|a| matches!(a, RebalanceAction::Buy { .. })));
+        // First buy is 10% of a $250 deficit
+        match &actions[0] {
+            RebalanceAction::Buy { usdt_amount, .. } => assert!((usdt_amount - 25.0).abs() < 1e-9),
+            other => panic!("expected a buy, got {:?}", other),
+        }
+
+        // Value is conserved and most of the ckUSDT ends up deployed
+        assert!((p.total_usd() - before).abs() < 1e-6);
+        assert!((total_bought_usd(&actions) - (1_000.0 - p.ckusdt_usd)).abs() < 1e-6);
+        assert!(p.ckusdt_pct() < 5.0);
+    }
+
+    #[test]
+    fn test_buys_capped_at_ckusdt_held() {
+        let mut p = portfolio(&[(TrackedToken::ALEX, 100.0)], 5.0);
+        let filled = p.apply(&RebalanceAction::Buy { token: TrackedToken::BOB, usdt_amount: 50.0 });
+        assert!(matches!(filled, RebalanceAction::Buy { usdt_amount, .. } if usdt_amount == 5.0));
+        assert_eq!(p.ckusdt_usd, 0.0);
+        assert_eq!(p.held(&TrackedToken::BOB), 5.0);
+    }
+
+    #[test]
+    fn test_convergence_of_known_deviations() {
+        // ALEX $200 over target, BOB $200 under, everything else on target
+        let mut p = portfolio(&[
+            (TrackedToken::ALEX, 450.0), (TrackedToken::ZERO, 250.0),
+            (TrackedToken::KONG, 250.0), (TrackedToken::BOB, 50.0),
+        ], 0.0);
+        let estimate = simulate_convergence(&mut p).unwrap();
+
+        assert!(estimate.converged);
+        assert!(estimate.cycles > 0);
+        assert_eq!(estimate.seconds, estimate.cycles as u64 * REBALANCE_INTERVAL_SECONDS);
+
+        let token = |t: TrackedToken| estimate.tokens.iter().find(|c| c.token == t).unwrap().clone();
+        assert_eq!(token(TrackedToken::ZERO).cycles, Some(0));
+        assert_eq!(token(TrackedToken::KONG).cycles, Some(0));
+        let alex = token(TrackedToken::ALEX);
+        let bob = token(TrackedToken::BOB);
+        assert!((alex.deviation_pct + 20.0).abs() < 1e-9);
+        assert!((bob.deviation_pct - 20.0).abs() < 1e-9);
+        // Selling ALEX down funds the BOB buys; ALEX settles on the last sell
+        assert_eq!(alex.cycles, Some(estimate.cycles - 1));
+        assert_eq!(alex.seconds, Some((estimate.cycles as u64 - 1) * REBALANCE_INTERVAL_SECONDS));
+        // BOB is left short by more than a minimum trade, with less than a
+        // minimum trade of ckUSDT to close it: the rebalancer stops anyway
+        assert_eq!(bob.cycles, None);
+        assert!(p.ckusdt_usd < MIN_TRADE_SIZE_USD);
+    }
+
+    #[test]
+    fn test_balanced_portfolio_already_converged() {
+        let mut p = portfolio(&[
+            (TrackedToken::ALEX, 25.0), (TrackedToken::ZERO, 25.0),
+            (TrackedToken::KONG, 25.0), (TrackedToken::BOB, 25.0),
+        ], 0.0);
+        let estimate = simulate_convergence(&mut p).unwrap();
+        assert!(estimate.converged);
+        assert_eq!(estimate.cycles, 0);
+        assert!(estimate.tokens.iter().all(|t| t.cycles == Some(0) && t.seconds == Some(0)));
+    }
+
+    #[test]
+    fn test_cycle_cap_respected() {
+        let mut p = portfolio(&[(TrackedToken::ALEX, 0.0)], 10_000.0);
+        assert_eq!(simulate_cycles(&mut p, 3).unwrap().len(), 3);
+    }
+}
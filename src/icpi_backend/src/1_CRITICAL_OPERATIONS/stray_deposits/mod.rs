@@ -0,0 +1,115 @@
+//! Stray ckUSDT deposits
+//!
+//! Daily job that compares the backend's ckUSDT balance with the reserve
+//! accounting in `ckusdt_reserves`, quarantining unexplained excess, plus the
+//! admin operations that resolve quarantined funds and forward collected fees.
+
+use candid::{Nat, Principal};
+use std::time::Duration;
+use crate::infrastructure::{Result, IcpiError, ValidationError, ckusdt_ledger, ledger_fees};
+use crate::infrastructure::constants::{
+    UNATTRIBUTED_CKUSDT_THRESHOLD, STRAY_DEPOSIT_SWEEP_INTERVAL_SECONDS,
+};
+use crate::_2_CRITICAL_DATA::ckusdt_reserves::{self, Reconciliation};
+
+/// Start the daily stray-deposit sweep (first run immediately)
+pub fn start_stray_deposit_sweep_timer() {
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::StrayDepositSweep,
+        Duration::from_secs(STRAY_DEPOSIT_SWEEP_INTERVAL_SECONDS),
+        true,
+        || ic_cdk::spawn(run_sweep()),
+    );
+}
+
+async fn run_sweep() {
+    match sweep_stray_deposits().await {
+        Ok(Some(Reconciliation::Unattributed { excess })) => {
+            ic_cdk::println!("🚨 Quarantined {} ckUSDT (e6) of unattributed deposits", excess);
+        }
+        Ok(Some(other)) => ic_cdk::println!("✅ ckUSDT reserves reconciled: {:?}", other),
+        Ok(None) => ic_cdk::println!("⏭️ ckUSDT reserve sweep skipped: operations in flight"),
+        Err(e) => ic_cdk::println!("⚠️ ckUSDT reserve sweep failed: {}", e),
+    }
+}
+
+fn busy() -> bool {
+    crate::infrastructure::reentrancy::has_active_operations() || ckusdt_reserves::has_debits_in_flight()
+}
+
+/// Reconcile the ckUSDT balance against expectation
+///
+/// Returns `None` if a mint, burn, rebalance or refund was in flight before
+/// or after the balance query, since its flows may not be recorded yet.
+pub async fn sweep_stray_deposits() -> Result<Option<Reconciliation>> {
+    if busy() {
+        return Ok(None);
+    }
+
+    let balance = ckusdt_ledger::balance_of(crate::infrastructure::runtime::canister_id()).await?;
+
+    if busy() {
+        return Ok(None);
+    }
+
+    Ok(Some(ckusdt_reserves::reconcile_balance(
+        &balance,
+        &Nat::from(UNATTRIBUTED_CKUSDT_THRESHOLD),
+        crate::infrastructure::runtime::time(),
+    )))
+}
+
+/// Refund quarantined ckUSDT to `to` (admin only, enforced at the API layer)
+///
+/// `to` receives `amount`; the ledger fee is also taken from quarantine.
+pub async fn refund_unattributed(to: Principal, amount: Nat) -> Result<Nat> {
+    if to == Principal::anonymous() {
+        return Err(IcpiError::Validation(ValidationError::InvalidPrincipal {
+            principal: to.to_text(),
+        }));
+    }
+
+    let debit = amount.clone() + Nat::from(ledger_fees::ckusdt_fee());
+    ckusdt_reserves::begin_debit(&debit)?;
+
+    // Bypasses the portfolio outflow hook: these funds were never in expectation
+    let result = ckusdt_ledger::transfer(to, amount.clone(), b"ICPI unattributed refund").await;
+
+    match result {
+        Ok(block_index) => {
+            ckusdt_reserves::finish_debit(&debit, Some((to, amount.clone(), block_index.clone())), crate::infrastructure::runtime::time());
+            ic_cdk::println!("✅ Refunded {} unattributed ckUSDT to {} (block {})", amount, to, block_index);
+            Ok(block_index)
+        }
+        Err(e) => {
+            ckusdt_reserves::finish_debit(&debit, None, crate::infrastructure::runtime::time());
+            Err(e)
+        }
+    }
+}
+
+/// Forward collected fees to `to` (admin only, enforced at the API layer)
+///
+/// `to` receives `amount`; the ledger fee is also taken from the fees.
+pub async fn forward_fees(to: Principal, amount: Nat) -> Result<Nat> {
+    if to == Principal::anonymous() {
+        return Err(IcpiError::Validation(ValidationError::InvalidPrincipal {
+            principal: to.to_text(),
+        }));
+    }
+
+    let debit = amount.clone() + Nat::from(ledger_fees::ckusdt_fee());
+    ckusdt_reserves::begin_fee_forward(&debit)?;
+
+    let result = ckusdt_ledger::transfer(to, amount.clone(), b"ICPI fee forward").await;
+    ckusdt_reserves::finish_fee_forward(&debit, result.is_ok());
+
+    let block_index = result?;
+    ic_cdk::println!("✅ Forwarded {} ckUSDT of fees to {} (block {})", amount, to, block_index);
+    Ok(block_index)
+}
+
+/// Release quarantined ckUSDT into the portfolio (admin only, enforced at the API layer)
+pub fn release_unattributed(amount: Nat) -> Result<()> {
+    ckusdt_reserves::release_quarantined(&amount, crate::infrastructure::runtime::time())
+}
@@ -0,0 +1,461 @@
+//! ckUSDT reserve accounting
+//!
+//! Tracks how much ckUSDT the backend *expects* to hold (reserves + fees +
+//! deposits), adjusted by every known inflow and outflow. ckUSDT sent
+//! directly to the backend shows up as an excess over that figure; it is
+//! parked in a quarantined counter that is excluded from TVL until an admin
+//! refunds it or releases it into the portfolio.
+//!
+//! Missed outflows (e.g. fees on a failed swap) only lower the balance and
+//! are re-based away. Missed inflows (e.g. a Kongswap refund) can end up in
+//! quarantine and need an explicit release.
+//!
+//! Mint and burn fees are also held in ckUSDT until an admin forwards them;
+//! they are counted as uncollected fees and excluded from TVL as well.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use std::cell::RefCell;
+use crate::infrastructure::{Result, IcpiError, ValidationError};
+use crate::infrastructure::constants::MAX_RESERVE_EVENTS;
+
+/// Reserve accounting event
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReserveEvent {
+    /// ckUSDT above expectation that no operation accounts for
+    UnattributedDeposit { amount: Nat, observed_balance: Nat, expected_balance: Nat, timestamp: u64 },
+    /// Quarantined ckUSDT sent back to a principal
+    Refunded { to: Principal, amount: Nat, block_index: Nat, timestamp: u64 },
+    /// Quarantined ckUSDT released into the portfolio
+    Released { amount: Nat, timestamp: u64 },
+}
+
+/// Persisted reserve accounting
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct ReserveState {
+    /// Expected portfolio ckUSDT (None until the first sweep sets a baseline)
+    pub expected: Option<Nat>,
+    /// ckUSDT held but excluded from TVL
+    pub quarantined: Nat,
+    pub events: Vec<ReserveEvent>,
+    /// Collected fees awaiting forwarding, excluded from TVL (None before tracking)
+    pub uncollected_fees: Option<Nat>,
+}
+
+/// Backend ckUSDT split into index cash and amounts held for others
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct CkusdtBalance {
+    /// Ledger balance
+    pub gross: Nat,
+    pub quarantined: Nat,
+    pub uncollected_fees: Nat,
+    /// ckUSDT belonging to the index
+    pub net: Nat,
+}
+
+/// Outcome of comparing the ledger balance with expectation
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reconciliation {
+    /// No expectation yet; balance adopted as the baseline
+    Baseline,
+    /// Excess below the threshold, absorbed as accounting noise
+    WithinTolerance { drift: Nat },
+    /// Excess at or above the threshold, moved to quarantine
+    Unattributed { excess: Nat },
+    /// Less ckUSDT than expected (untracked fees); expectation lowered
+    Shortfall { missing: Nat },
+}
+
+thread_local! {
+    static RESERVES: RefCell<ReserveState> = RefCell::new(ReserveState::default());
+    /// Quarantine debits awaiting a ledger transfer
+    static DEBITS_IN_FLIGHT: RefCell<u32> = RefCell::new(0);
+}
+
+fn saturating_sub(a: &Nat, b: &Nat) -> Nat {
+    if a > b { a.clone() - b.clone() } else { Nat::from(0u64) }
+}
+
+fn push_event(state: &mut ReserveState, event: ReserveEvent) {
+    state.events.push(event);
+    if state.events.len() > MAX_RESERVE_EVENTS {
+        let excess = state.events.len() - MAX_RESERVE_EVENTS;
+        state.events.drain(0..excess);
+    }
+}
+
+/// Collected fees not yet forwarded
+pub fn uncollected_fees(state: &ReserveState) -> Nat {
+    state.uncollected_fees.clone().unwrap_or_else(|| Nat::from(0u64))
+}
+
+/// Split a raw ckUSDT balance into index cash and excluded amounts
+pub fn breakdown(state: &ReserveState, raw_balance: &Nat) -> CkusdtBalance {
+    let fees = uncollected_fees(state);
+    let net = saturating_sub(&saturating_sub(raw_balance, &state.quarantined), &fees);
+    CkusdtBalance {
+        gross: raw_balance.clone(),
+        quarantined: state.quarantined.clone(),
+        uncollected_fees: fees,
+        net,
+    }
+}
+
+fn check_uncollected_fees(state: &ReserveState, amount: &Nat) -> Result<()> {
+    let fees = uncollected_fees(state);
+    if amount > &fees {
+        return Err(IcpiError::Validation(ValidationError::InvalidAmount {
+            amount: amount.to_string(),
+            reason: format!("exceeds uncollected fees ({})", fees),
+        }));
+    }
+    Ok(())
+}
+
+/// Compare `balance` with expectation and update `state` accordingly
+pub fn reconcile(state: &mut ReserveState, balance: &Nat, threshold: &Nat, now: u64) -> Reconciliation {
+    if balance < &state.quarantined {
+        state.quarantined = balance.clone();
+    }
+    let unquarantined = saturating_sub(balance, &state.quarantined);
+
+    let Some(expected) = state.expected.clone() else {
+        state.expected = Some(unquarantined);
+        return Reconciliation::Baseline;
+    };
+
+    if unquarantined <= expected {
+        state.expected = Some(unquarantined.clone());
+        return Reconciliation::Shortfall { missing: expected - unquarantined };
+    }
+
+    let excess = unquarantined.clone() - expected.clone();
+    if &excess < threshold {
+        state.expected = Some(unquarantined);
+        return Reconciliation::WithinTolerance { drift: excess };
+    }
+
+    state.quarantined += excess.clone();
+    push_event(state, ReserveEvent::UnattributedDeposit {
+        amount: excess.clone(),
+        observed_balance: balance.clone(),
+        expected_balance: expected,
+        timestamp: now,
+    });
+    Reconciliation::Unattributed { excess }
+}
+
+/// Move `amount` from quarantine into the portfolio
+pub fn release(state: &mut ReserveState, amount: &Nat, now: u64) -> Result<()> {
+    check_quarantined(state, amount)?;
+    state.quarantined -= amount.clone();
+    if let Some(expected) = state.expected.as_mut() {
+        *expected += amount.clone();
+    }
+    push_event(state, ReserveEvent::Released { amount: amount.clone(), timestamp: now });
+    Ok(())
+}
+
+fn check_quarantined(state: &ReserveState, amount: &Nat) -> Result<()> {
+    if amount > &state.quarantined {
+        return Err(IcpiError::Validation(ValidationError::InvalidAmount {
+            amount: amount.to_string(),
+            reason: format!("exceeds quarantined ckUSDT ({})", state.quarantined),
+        }));
+    }
+    Ok(())
+}
+
+/// Record ckUSDT received by a known operation
+pub fn record_inflow(amount: &Nat) {
+    RESERVES.with(|r| {
+        if let Some(expected) = r.borrow_mut().expected.as_mut() {
+            *expected += amount.clone();
+        }
+    });
+}
+
+/// Record ckUSDT spent by a known operation (including ledger fees)
+pub fn record_outflow(amount: &Nat) {
+    RESERVES.with(|r| {
+        let mut r = r.borrow_mut();
+        if let Some(expected) = r.expected.as_mut() {
+            *expected = saturating_sub(expected, amount);
+        }
+    });
+}
+
+/// Record a mint or burn fee received (on top of `record_inflow`)
+pub fn record_fee_collected(amount: &Nat) {
+    RESERVES.with(|r| {
+        let mut r = r.borrow_mut();
+        let fees = uncollected_fees(&r) + amount.clone();
+        r.uncollected_fees = Some(fees);
+    });
+}
+
+/// Gross/net split of a raw ckUSDT balance under current accounting
+///
+/// `net` excludes quarantined deposits and uncollected fees.
+pub fn get_breakdown(raw_balance: &Nat) -> CkusdtBalance {
+    RESERVES.with(|r| breakdown(&r.borrow(), raw_balance))
+}
+
+/// Reconcile the live ledger balance against expectation
+pub fn reconcile_balance(balance: &Nat, threshold: &Nat, now: u64) -> Reconciliation {
+    RESERVES.with(|r| reconcile(&mut r.borrow_mut(), balance, threshold, now))
+}
+
+/// Release quarantined ckUSDT into the portfolio
+pub fn release_quarantined(amount: &Nat, now: u64) -> Result<()> {
+    RESERVES.with(|r| release(&mut r.borrow_mut(), amount, now))
+}
+
+/// Take `amount` out of quarantine ahead of a refund transfer
+///
+/// Must be paired with `finish_debit` once the transfer settles.
+pub fn begin_debit(amount: &Nat) -> Result<()> {
+    RESERVES.with(|r| -> Result<()> {
+        let mut r = r.borrow_mut();
+        check_quarantined(&r, amount)?;
+        r.quarantined -= amount.clone();
+        Ok(())
+    })?;
+    DEBITS_IN_FLIGHT.with(|d| *d.borrow_mut() += 1);
+    Ok(())
+}
+
+/// Settle a debit: record the refund, or return the amount to quarantine
+pub fn finish_debit(amount: &Nat, refund: Option<(Principal, Nat, Nat)>, now: u64) {
+    RESERVES.with(|r| {
+        let mut r = r.borrow_mut();
+        match refund {
+            Some((to, sent, block_index)) => push_event(&mut r, ReserveEvent::Refunded {
+                to,
+                amount: sent,
+                block_index,
+                timestamp: now,
+            }),
+            None => r.quarantined += amount.clone(),
+        }
+    });
+    DEBITS_IN_FLIGHT.with(|d| {
+        let mut d = d.borrow_mut();
+        *d = d.saturating_sub(1);
+    });
+}
+
+/// Take `amount` out of uncollected fees ahead of a forwarding transfer
+///
+/// Must be paired with `finish_fee_forward` once the transfer settles.
+pub fn begin_fee_forward(amount: &Nat) -> Result<()> {
+    RESERVES.with(|r| -> Result<()> {
+        let mut r = r.borrow_mut();
+        check_uncollected_fees(&r, amount)?;
+        r.uncollected_fees = Some(uncollected_fees(&r) - amount.clone());
+        Ok(())
+    })?;
+    DEBITS_IN_FLIGHT.with(|d| *d.borrow_mut() += 1);
+    Ok(())
+}
+
+/// Settle a fee forward: record the outflow, or return the amount to fees
+pub fn finish_fee_forward(amount: &Nat, forwarded: bool) {
+    if forwarded {
+        record_outflow(amount);
+    } else {
+        record_fee_collected(amount);
+    }
+    DEBITS_IN_FLIGHT.with(|d| {
+        let mut d = d.borrow_mut();
+        *d = d.saturating_sub(1);
+    });
+}
+
+/// Whether a quarantine refund is mid-transfer
+pub fn has_debits_in_flight() -> bool {
+    DEBITS_IN_FLIGHT.with(|d| *d.borrow() > 0)
+}
+
+/// Current reserve accounting
+pub fn get_state() -> ReserveState {
+    RESERVES.with(|r| r.borrow().clone())
+}
+
+/// Export reserve accounting for stable storage
+pub fn export_state() -> ReserveState {
+    get_state()
+}
+
+/// Import reserve accounting from stable storage
+pub fn import_state(state: ReserveState) {
+    RESERVES.with(|r| *r.borrow_mut() = state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nat(n: u64) -> Nat {
+        Nat::from(n)
+    }
+
+    fn baselined(expected: u64) -> ReserveState {
+        ReserveState { expected: Some(nat(expected)), ..Default::default() }
+    }
+
+    #[test]
+    fn test_first_reconcile_sets_baseline() {
+        let mut state = ReserveState::default();
+        assert_eq!(reconcile(&mut state, &nat(5_000_000), &nat(1_000_000), 1), Reconciliation::Baseline);
+        assert_eq!(state.expected, Some(nat(5_000_000)));
+        assert_eq!(state.quarantined, nat(0));
+    }
+
+    #[test]
+    fn test_stray_transfer_is_quarantined() {
+        let mut state = baselined(10_000_000);
+        let result = reconcile(&mut state, &nat(35_000_000), &nat(1_000_000), 7);
+
+        assert_eq!(result, Reconciliation::Unattributed { excess: nat(25_000_000) });
+        assert_eq!(state.quarantined, nat(25_000_000));
+        assert_eq!(state.expected, Some(nat(10_000_000)));
+        assert_eq!(state.events, vec![ReserveEvent::UnattributedDeposit {
+            amount: nat(25_000_000),
+            observed_balance: nat(35_000_000),
+            expected_balance: nat(10_000_000),
+            timestamp: 7,
+        }]);
+
+        // Second sweep with no new transfers finds nothing further
+        assert_eq!(
+            reconcile(&mut state, &nat(35_000_000), &nat(1_000_000), 8),
+            Reconciliation::Shortfall { missing: nat(0) }
+        );
+        assert_eq!(state.quarantined, nat(25_000_000));
+        assert_eq!(state.events.len(), 1);
+    }
+
+    #[test]
+    fn test_small_drift_absorbed() {
+        let mut state = baselined(10_000_000);
+        let result = reconcile(&mut state, &nat(10_500_000), &nat(1_000_000), 1);
+        assert_eq!(result, Reconciliation::WithinTolerance { drift: nat(500_000) });
+        assert_eq!(state.expected, Some(nat(10_500_000)));
+        assert!(state.events.is_empty());
+    }
+
+    #[test]
+    fn test_shortfall_rebases_expectation() {
+        let mut state = baselined(10_000_000);
+        let result = reconcile(&mut state, &nat(9_980_000), &nat(1_000_000), 1);
+        assert_eq!(result, Reconciliation::Shortfall { missing: nat(20_000) });
+        assert_eq!(state.expected, Some(nat(9_980_000)));
+    }
+
+    #[test]
+    fn test_release_moves_quarantine_into_expected() {
+        let mut state = baselined(10_000_000);
+        reconcile(&mut state, &nat(30_000_000), &nat(1_000_000), 1);
+
+        assert!(release(&mut state, &nat(30_000_000), 2).is_err());
+        release(&mut state, &nat(20_000_000), 2).unwrap();
+        assert_eq!(state.quarantined, nat(0));
+        assert_eq!(state.expected, Some(nat(30_000_000)));
+
+        // Released funds are no longer flagged on the next sweep
+        assert_eq!(
+            reconcile(&mut state, &nat(30_000_000), &nat(1_000_000), 3),
+            Reconciliation::Shortfall { missing: nat(0) }
+        );
+    }
+
+    #[test]
+    fn test_event_log_is_bounded() {
+        let mut state = ReserveState::default();
+        for i in 0..(MAX_RESERVE_EVENTS as u64 + 5) {
+            push_event(&mut state, ReserveEvent::Released { amount: nat(1), timestamp: i });
+        }
+        assert_eq!(state.events.len(), MAX_RESERVE_EVENTS);
+        assert_eq!(state.events[0], ReserveEvent::Released { amount: nat(1), timestamp: 5 });
+    }
+
+    #[test]
+    fn test_flows_adjust_expected() {
+        import_state(baselined(1_000_000));
+        record_inflow(&nat(500_000));
+        record_outflow(&nat(200_000));
+        assert_eq!(get_state().expected, Some(nat(1_300_000)));
+
+        record_outflow(&nat(5_000_000));
+        assert_eq!(get_state().expected, Some(nat(0)));
+    }
+
+    #[test]
+    fn test_debit_restored_on_failed_refund() {
+        import_state(ReserveState { expected: Some(nat(0)), quarantined: nat(100), ..Default::default() });
+
+        assert!(begin_debit(&nat(101)).is_err());
+        begin_debit(&nat(60)).unwrap();
+        assert!(has_debits_in_flight());
+        assert_eq!(get_state().quarantined, nat(40));
+        assert_eq!(get_breakdown(&nat(1_040)).net, nat(1_000));
+
+        finish_debit(&nat(60), None, 1);
+        assert!(!has_debits_in_flight());
+        assert_eq!(get_state().quarantined, nat(100));
+    }
+
+    #[test]
+    fn test_breakdown_excludes_pending_fees() {
+        let state = ReserveState {
+            quarantined: nat(2_000_000),
+            uncollected_fees: Some(nat(300_000)),
+            ..baselined(10_000_000)
+        };
+        assert_eq!(breakdown(&state, &nat(12_300_000)), CkusdtBalance {
+            gross: nat(12_300_000),
+            quarantined: nat(2_000_000),
+            uncollected_fees: nat(300_000),
+            net: nat(10_000_000),
+        });
+
+        // Never negative when the ledger lags the accounting
+        assert_eq!(breakdown(&state, &nat(1_000_000)).net, nat(0));
+
+        // State saved before fee tracking has no fees held back
+        let legacy = ReserveState { uncollected_fees: None, ..state };
+        assert_eq!(breakdown(&legacy, &nat(12_300_000)).net, nat(10_300_000));
+    }
+
+    #[test]
+    fn test_collected_fees_excluded_from_tvl() {
+        import_state(baselined(1_000_000));
+        // A mint fee arrives alongside index cash
+        record_inflow(&nat(100_000));
+        record_fee_collected(&nat(100_000));
+        record_fee_collected(&nat(100_000));
+        assert_eq!(get_breakdown(&nat(1_200_000)).net, nat(1_000_000));
+        assert_eq!(get_breakdown(&nat(1_200_000)).gross, nat(1_200_000));
+    }
+
+    #[test]
+    fn test_fee_forwarding() {
+        import_state(ReserveState { uncollected_fees: Some(nat(300_000)), ..baselined(1_300_000) });
+
+        assert!(begin_fee_forward(&nat(300_001)).is_err());
+        begin_fee_forward(&nat(200_000)).unwrap();
+        assert!(has_debits_in_flight());
+        assert_eq!(uncollected_fees(&get_state()), nat(100_000));
+
+        // Failed transfer puts the fees back
+        finish_fee_forward(&nat(200_000), false);
+        assert!(!has_debits_in_flight());
+        assert_eq!(uncollected_fees(&get_state()), nat(300_000));
+
+        begin_fee_forward(&nat(300_000)).unwrap();
+        finish_fee_forward(&nat(300_000), true);
+        assert_eq!(uncollected_fees(&get_state()), nat(0));
+        assert_eq!(get_state().expected, Some(nat(1_000_000)));
+        assert_eq!(get_breakdown(&nat(1_000_000)).net, nat(1_000_000));
+    }
+}
@@ -1,9 +1,12 @@
 //! Critical Data - Portfolio calculations and validation
 //! Source of truth for all financial data
 
+pub mod ckusdt_reserves;
 pub mod portfolio_value;
 pub mod supply_tracker;
+pub mod target_smoothing;
 pub mod token_queries;
+pub mod trade_intensity;
 pub mod validation;
 
 use crate::infrastructure::Result;
@@ -37,7 +40,7 @@ pub async fn get_supply_and_tvl_atomic() -> Result<(Nat, Nat)> {
 
         // Query both in parallel using futures::join!
         let supply_future = supply_tracker::get_icpi_supply_uncached();
-        let tvl_future = portfolio_value::calculate_portfolio_value_atomic();
+        let tvl_future = portfolio_value::calculate_portfolio_value_atomic(None);
 
         let (supply_result, tvl_result) = futures::join!(supply_future, tvl_future);
 
@@ -78,10 +81,33 @@ pub async fn get_supply_and_tvl_atomic() -> Result<(Nat, Nat)> {
 
 /// Helper function to validate and return snapshot
 async fn validate_and_return_snapshot(supply: Nat, tvl: Nat) -> Result<(Nat, Nat)> {
+    validate_snapshot(supply, tvl, crate::infrastructure::runtime::time(), crate::infrastructure::runtime::canister_id())
+}
 
+/// Reject an inconsistent snapshot, pausing the canister if configured
+///
+/// The inconsistency means serious corruption: every later mint and burn
+/// would hit it too, so `auto_pause_on_corruption` stops them until an
+/// admin looks.
+fn validate_snapshot(supply: Nat, tvl: Nat, now: u64, canister: candid::Principal) -> Result<(Nat, Nat)> {
+    if let Err(e) = check_snapshot_consistency(&supply, &tvl) {
+        if crate::infrastructure::config::get_config().auto_pause_on_corruption() {
+            crate::infrastructure::admin::auto_pause(format!("Data inconsistency: {}", e), canister, now);
+        }
+        return Err(e);
+    }
+
+    ic_cdk::println!("  Supply: {} ICPI (e8)", supply);
+    ic_cdk::println!("  TVL: {} ckUSDT (e6)", tvl);
+
+    Ok((supply, tvl))
+}
+
+/// Supply without TVL, or TVL without supply, is a hard error
+fn check_snapshot_consistency(supply: &Nat, tvl: &Nat) -> Result<()> {
     // Phase 4 Enhancement: Make inconsistent state detection a hard error
     // Validation: detect inconsistent state - this indicates serious data corruption
-    if supply > Nat::from(0u32) && tvl == Nat::from(0u32) {
+    if *supply > Nat::from(0u32) && *tvl == Nat::from(0u32) {
         ic_cdk::println!("🚨 CRITICAL: Supply exists ({}) but TVL is zero - data corruption detected", supply);
         return Err(crate::infrastructure::IcpiError::Validation(
             crate::infrastructure::errors::ValidationError::DataInconsistency {
@@ -94,7 +120,7 @@ async fn validate_and_return_snapshot(supply: Nat, tvl: Nat) -> Result<(Nat, Nat
         ));
     }
 
-    if supply == Nat::from(0u32) && tvl > Nat::from(0u32) {
+    if *supply == Nat::from(0u32) && *tvl > Nat::from(0u32) {
         ic_cdk::println!("🚨 CRITICAL: TVL exists ({}) but supply is zero - data corruption detected", tvl);
         return Err(crate::infrastructure::IcpiError::Validation(
             crate::infrastructure::errors::ValidationError::DataInconsistency {
@@ -107,10 +133,7 @@ async fn validate_and_return_snapshot(supply: Nat, tvl: Nat) -> Result<(Nat, Nat
         ));
     }
 
-    ic_cdk::println!("  Supply: {} ICPI (e8)", supply);
-    ic_cdk::println!("  TVL: {} ckUSDT (e6)", tvl);
-
-    Ok((supply, tvl))
+    Ok(())
 }
 
 #[cfg(test)]
@@ -166,6 +189,36 @@ mod tests {
         assert!(!is_inconsistent, "Both positive should be consistent");
     }
 
+    #[test]
+    fn test_inconsistency_triggers_pause() {
+        let canister = candid::Principal::from_slice(&[9]);
+
+        assert!(validate_snapshot(Nat::from(1_000_000u64), Nat::from(0u64), 5, canister).is_err());
+        let pause = crate::infrastructure::admin::get_pause_info();
+        assert!(pause.paused);
+        assert!(pause.reason.unwrap().starts_with("Data inconsistency: "));
+        assert_eq!((pause.paused_by, pause.paused_at), (Some(canister), Some(5)));
+        let log = crate::infrastructure::admin::get_admin_log();
+        assert_eq!(log.last().map(|a| a.action.as_str()), Some("AUTO_PAUSE"));
+    }
+
+    #[test]
+    fn test_inconsistency_pause_can_be_disabled() {
+        crate::infrastructure::config::update_config(|c| c.auto_pause_on_corruption = Some(false));
+        let canister = candid::Principal::from_slice(&[9]);
+
+        assert!(validate_snapshot(Nat::from(0u64), Nat::from(100_000u64), 5, canister).is_err());
+        assert!(!crate::infrastructure::admin::is_paused());
+    }
+
+    #[test]
+    fn test_consistent_snapshot_does_not_pause() {
+        let canister = candid::Principal::from_slice(&[9]);
+        assert!(validate_snapshot(Nat::from(1_000_000u64), Nat::from(500_000u64), 5, canister).is_ok());
+        assert!(validate_snapshot(Nat::from(0u64), Nat::from(0u64), 5, canister).is_ok());
+        assert!(!crate::infrastructure::admin::is_paused());
+    }
+
     #[test]
     fn test_max_retries_constant() {
         // Verify retry configuration is reasonable
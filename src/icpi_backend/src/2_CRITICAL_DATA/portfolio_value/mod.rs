@@ -5,55 +5,132 @@
 use candid::Nat;
 use num_traits::ToPrimitive;
 use crate::infrastructure::Result;
-use crate::types::portfolio::IndexState;
+use crate::types::portfolio::{DataQuality, IndexState};
 use crate::types::TrackedToken;
+use crate::_3_KONG_LIQUIDITY::pools::{PricingPolicy, PriceRate, PriceSource};
 
-/// Calculate total portfolio value atomically
-///
-/// Sums: (all token balances × token prices) + ckUSDT reserves
-///
-/// For tracked tokens (ALEX, ZERO, KONG, BOB), we query Kongswap pools
-/// to get their ckUSDT exchange rate and calculate USD value.
+/// Balances and prices fetched once for a single portfolio read
 ///
-/// Formula: TVL = ckUSDT + Σ(token_balance × token_price_in_ckusdt)
-pub async fn calculate_portfolio_value_atomic() -> Result<Nat> {
-    ic_cdk::println!("CALC: Computing total portfolio value");
+/// Total value and per-token positions derived from the same inputs agree
+/// with each other, and the external calls are made only once.
+#[derive(Clone, Debug)]
+pub struct PortfolioInputs {
+    pub balances: Vec<(String, Nat)>,
+    /// Rate into ckUSDT for each tracked token with a non-zero balance
+    pub prices: Vec<(TrackedToken, PriceRate)>,
+    /// Policy the prices were fetched under
+    pub policy: PricingPolicy,
+    /// Tokens priced by the fallback oracle rather than Kongswap
+    pub fallback_priced: Vec<TrackedToken>,
+    pub fetched_at: u64,
+}
 
-    // Get all balances in parallel
-    let balances = crate::_2_CRITICAL_DATA::token_queries::get_all_balances_uncached().await?;
+impl PortfolioInputs {
+    /// Fetch all balances, then price every non-zero tracked token
+    pub async fn fetch(policy: PricingPolicy) -> Result<Self> {
+        crate::_2_CRITICAL_DATA::validation::validate_token_identities()?;
+        Self::fetch_with(
+            policy,
+            crate::_2_CRITICAL_DATA::token_queries::get_all_balances_uncached,
+            |token| async move {
+                ic_cdk::println!("  Querying Kongswap for {} price...", token.to_symbol());
+                let quote = crate::_3_KONG_LIQUIDITY::pools::get_token_price_quote(&token, policy).await?;
+                if quote.is_fallback() {
+                    ic_cdk::println!("  ⚠️ {} valued with FALLBACK oracle price: ${:.6} per token", token.to_symbol(), quote.price_usdt);
+                } else {
+                    ic_cdk::println!("  ✅ {} price: ${:.6} per token", token.to_symbol(), quote.price_usdt);
+                }
+                Ok((quote.rate, quote.source))
+            },
+        ).await
+    }
 
-    let mut total_value_e6: u128 = 0;
+    /// `fetch` with the balance and price sources supplied by the caller
+    async fn fetch_with<B, BF, P, PF>(policy: PricingPolicy, fetch_balances: B, fetch_price: P) -> Result<Self>
+    where
+        B: FnOnce() -> BF,
+        BF: std::future::Future<Output = Result<Vec<(String, Nat)>>>,
+        P: Fn(TrackedToken) -> PF,
+        PF: std::future::Future<Output = Result<(PriceRate, PriceSource)>>,
+    {
+        ic_cdk::println!("CALC: Fetching portfolio balances and prices");
+        let balances = fetch_balances().await?;
+
+        let mut prices = Vec::new();
+        let mut fallback_priced = Vec::new();
+        for (symbol, balance) in &balances {
+            let token = TrackedToken::from_symbol(symbol)
+                .map_err(crate::infrastructure::IcpiError::Other)?;
+            if token == TrackedToken::ckUSDT {
+                continue;
+            }
+            if *balance == Nat::from(0u64) {
+                ic_cdk::println!("🔍 Skipping {} pricing (zero balance)", symbol);
+                continue;
+            }
 
-    for (symbol, balance) in balances {
-        if symbol == "ckUSDT" {
+            // CRITICAL: Fail if any token pricing fails to ensure accurate TVL
+            let (rate, source) = fetch_price(token.clone()).await.map_err(|e| {
+                ic_cdk::println!("  ❌ Error pricing {}: {}", symbol, e);
+                crate::infrastructure::IcpiError::Other(
+                    format!("Failed to value token {}: {}", symbol, e)
+                )
+            })?;
+            if source == PriceSource::FallbackOracle {
+                fallback_priced.push(token.clone());
+            }
+            prices.push((token, rate));
+        }
+
+        Ok(PortfolioInputs {
+            balances,
+            prices,
+            policy,
+            fallback_priced,
+            fetched_at: crate::infrastructure::runtime::time(),
+        })
+    }
+
+    /// USD value of `balance` of `symbol` in e6, using the fetched prices
+    ///
+    /// ckUSDT is valued 1:1; any symbol that isn't a tracked token or
+    /// ckUSDT (e.g. ICPI) is an error.
+    pub fn usd_value_e6(&self, symbol: &str, balance: &Nat) -> Result<u64> {
+        let token = TrackedToken::from_symbol(symbol)
+            .map_err(crate::infrastructure::IcpiError::Other)?;
+
+        if token == TrackedToken::ckUSDT {
             // ckUSDT is 1:1 with USD, already in e6 decimals
-            // Safely convert balance, return error if overflow
-            let value = balance.0.to_u64()
-                .ok_or_else(|| {
-                    crate::infrastructure::IcpiError::Other(
-                        format!("ckUSDT balance {} too large to process", balance)
-                    )
-                })?;
+            return balance.0.to_u64().ok_or_else(|| {
+                crate::infrastructure::IcpiError::Other(
+                    format!("ckUSDT balance {} too large to process", balance)
+                )
+            });
+        }
 
-            // Use checked addition to prevent overflow
-            total_value_e6 = total_value_e6.checked_add(value as u128)
-                .ok_or_else(|| {
-                    crate::infrastructure::IcpiError::Other(
-                        "Portfolio value overflow when adding ckUSDT".to_string()
-                    )
-                })?;
+        if *balance == Nat::from(0u64) {
+            return Ok(0);
+        }
 
-            ic_cdk::println!("  ckUSDT: {} (e6) = ${}", balance, value as f64 / 1_000_000.0);
-        } else {
-            // For tracked tokens, get price from Kongswap and calculate value
-            // CRITICAL: Fail if any token pricing fails to ensure accurate TVL
-            let value_e6 = get_token_usd_value(&symbol, &balance).await
-                .map_err(|e| {
-                    ic_cdk::println!("  ❌ Error valuing {}: {}", symbol, e);
-                    crate::infrastructure::IcpiError::Other(
-                        format!("Failed to value token {}: {}", symbol, e)
-                    )
-                })?;
+        let rate = self.prices.iter()
+            .find(|(t, _)| *t == token)
+            .map(|(_, rate)| rate)
+            .ok_or_else(|| crate::infrastructure::IcpiError::Other(
+                format!("No price fetched for {}", symbol)
+            ))?;
+
+        token_value_e6(symbol, balance, rate)
+    }
+
+    /// Sum the value of all balances
+    ///
+    /// Returns the total and the symbols that were valued.
+    pub fn total_value(&self) -> Result<(Nat, Vec<String>)> {
+        let mut total_value_e6: u128 = 0;
+        let mut valued_symbols = Vec::with_capacity(self.balances.len());
+
+        for (symbol, balance) in &self.balances {
+            let value_e6 = self.usd_value_e6(symbol, balance)?;
 
             // Use checked addition to prevent overflow
             total_value_e6 = total_value_e6.checked_add(value_e6 as u128)
@@ -63,106 +140,119 @@ pub async fn calculate_portfolio_value_atomic() -> Result<Nat> {
                     )
                 })?;
 
-            ic_cdk::println!("  {}: {} tokens = ${}", symbol, balance, value_e6 as f64 / 1_000_000.0);
+            ic_cdk::println!("  {}: {} = ${}", symbol, balance, value_e6 as f64 / 1_000_000.0);
+            valued_symbols.push(symbol.clone());
         }
-    }
-
-    // Validate the total value is reasonable (under $1 trillion as sanity check)
-    const MAX_REASONABLE_VALUE_E6: u128 = 1_000_000_000_000 * 1_000_000; // $1 trillion in e6
-    if total_value_e6 > MAX_REASONABLE_VALUE_E6 {
-        return Err(crate::infrastructure::IcpiError::Other(
-            format!("Portfolio value {} exceeds maximum reasonable limit", total_value_e6)
-        ));
-    }
 
-    let total_value = Nat::from(total_value_e6);
-    ic_cdk::println!("✅ Total portfolio value: ${} (e6 ckUSDT)", total_value_e6 as f64 / 1_000_000.0);
+        // Validate the total value is reasonable (under $1 trillion as sanity check)
+        const MAX_REASONABLE_VALUE_E6: u128 = 1_000_000_000_000 * 1_000_000; // $1 trillion in e6
+        if total_value_e6 > MAX_REASONABLE_VALUE_E6 {
+            return Err(crate::infrastructure::IcpiError::Other(
+                format!("Portfolio value {} exceeds maximum reasonable limit", total_value_e6)
+            ));
+        }
 
-    Ok(total_value)
+        ic_cdk::println!("✅ Total portfolio value: ${} (e6 ckUSDT)", total_value_e6 as f64 / 1_000_000.0);
+        Ok((Nat::from(total_value_e6), valued_symbols))
+    }
 }
 
-/// Get USD value of a token amount
-/// Returns value in e6 (ckUSDT decimals)
+/// Calculate total portfolio value atomically
 ///
-/// Queries Kongswap for real-time token prices and calculates USD value.
-/// Returns error if pricing fails - no fallback prices to ensure accuracy.
-async fn get_token_usd_value(token_symbol: &str, amount: &Nat) -> Result<u64> {
-    // Early return for zero amounts - no need to query price
-    let amount_e8 = amount.0.to_u64()
-        .ok_or_else(|| {
-            crate::infrastructure::IcpiError::Other(
-                format!("Amount {} too large to process", amount)
-            )
-        })?;
-
-    if amount_e8 == 0 {
-        ic_cdk::println!("🔍 Skipping {} pricing (zero balance)", token_symbol);
-        return Ok(0u64);
-    }
-
-    ic_cdk::println!("🔍 Pricing {} ({} tokens)", token_symbol, amount);
-
-    // Get token enum from symbol
-    let token = match token_symbol {
-        "ALEX" => TrackedToken::ALEX,
-        "ZERO" => TrackedToken::ZERO,
-        "KONG" => TrackedToken::KONG,
-        "BOB" => TrackedToken::BOB,
+/// Sums: (all token balances × token prices) + ckUSDT reserves
+///
+/// For tracked tokens (ALEX, ZERO, KONG, BOB), we query Kongswap pools
+/// to get their ckUSDT exchange rate and calculate USD value.
+///
+/// Formula: TVL = ckUSDT + Σ(token_balance × token_price_in_ckusdt)
+///
+/// Uses Kongswap prices only (no fallback oracle) since this value feeds
+/// the mint and burn snapshots. Pre-fetched `inputs` are used if they were
+/// priced the same way; otherwise fresh inputs are fetched.
+///
+/// The snapshot TVL must cover every tracked token plus ckUSDT. A token
+/// whose balance query failed is silently absent from the balance list, so
+/// coverage is asserted explicitly; otherwise the mint ratio would be
+/// computed against a TVL that excludes part of the portfolio.
+pub async fn calculate_portfolio_value_atomic(inputs: Option<&PortfolioInputs>) -> Result<Nat> {
+    let fetched;
+    let inputs = match inputs {
+        Some(inputs) if inputs.policy == PricingPolicy::PrimaryOnly => inputs,
         _ => {
-            ic_cdk::println!("❌ Unknown token: {}", token_symbol);
-            return Err(crate::infrastructure::IcpiError::Other(
-                format!("Unknown token: {}", token_symbol)
-            ));
+            fetched = PortfolioInputs::fetch(PricingPolicy::PrimaryOnly).await?;
+            &fetched
         }
     };
 
-    // Get real-time price from Kongswap - fail if unavailable
-    ic_cdk::println!("  Querying Kongswap for {} price...", token_symbol);
-    let price_result = crate::_3_KONG_LIQUIDITY::pools::get_token_price_in_usdt(&token).await;
+    let (total_value, valued_symbols) = inputs.total_value()?;
+    assert_valuation_coverage(&valued_symbols)?;
+    Ok(total_value)
+}
 
-    let price_usdt_f64 = match price_result {
-        Ok(price) => {
-            ic_cdk::println!("  ✅ {} price: ${:.6} per token", token_symbol, price);
-            price
-        }
-        Err(e) => {
-            ic_cdk::println!("  ❌ {} pricing failed: {}", token_symbol, e);
-            return Err(e);
-        }
-    };
+/// Calculate total portfolio value with an explicit pricing policy
+pub async fn calculate_portfolio_value_with_policy(policy: PricingPolicy) -> Result<Nat> {
+    PortfolioInputs::fetch(policy).await?.total_value().map(|(total_value, _)| total_value)
+}
 
-    // Convert price to e6 format (ckUSDT decimals)
-    let price_per_token_e6 = (price_usdt_f64 * 1_000_000.0) as u64;
+/// Tracked tokens and ckUSDT absent from `valued_symbols`
+pub fn missing_valuation_symbols(valued_symbols: &[String]) -> Vec<String> {
+    TrackedToken::all().iter()
+        .map(|t| t.to_symbol())
+        .chain(std::iter::once(TrackedToken::ckUSDT.to_symbol()))
+        .filter(|symbol| !valued_symbols.iter().any(|v| v == symbol))
+        .map(|symbol| symbol.to_string())
+        .collect()
+}
 
-    // Calculate: (amount_e8 * price_e6) / 1e8 with overflow protection
-    let amount_u128 = amount_e8 as u128;
-    let price_u128 = price_per_token_e6 as u128;
+/// Verify a valuation covered every tracked token and ckUSDT
+pub fn assert_valuation_coverage(valued_symbols: &[String]) -> Result<()> {
+    let missing = missing_valuation_symbols(valued_symbols);
 
-    // Check for potential overflow before multiplication
-    let product = amount_u128.checked_mul(price_u128)
-        .ok_or_else(|| {
-            crate::infrastructure::IcpiError::Other(
-                format!("Arithmetic overflow in price calculation: {} * {}", amount_e8, price_per_token_e6)
-            )
-        })?;
+    if !missing.is_empty() {
+        ic_cdk::println!("🚨 Valuation incomplete, missing: {:?}", missing);
+        return Err(crate::infrastructure::IcpiError::Validation(
+            crate::infrastructure::ValidationError::IncompleteValuation {
+                missing_tokens: missing,
+            }
+        ));
+    }
 
-    let value_e6_u128 = product / 100_000_000;
+    Ok(())
+}
 
-    // Ensure result fits in u64
-    if value_e6_u128 > u64::MAX as u128 {
-        return Err(crate::infrastructure::IcpiError::Other(
-            format!("Value overflow: {} exceeds u64 max", value_e6_u128)
-        ));
+/// Quality of a state built from `inputs` and TVL that may be last-good
+///
+/// A missing balance (its query failed) or a fallback price makes the
+/// numbers themselves unreliable, which outranks targets from older TVL.
+pub fn assess_data_quality(inputs: &PortfolioInputs, tvl_is_stale: bool) -> DataQuality {
+    let fetched: Vec<String> = inputs.balances.iter().map(|(symbol, _)| symbol.clone()).collect();
+    if !missing_valuation_symbols(&fetched).is_empty() || !inputs.fallback_priced.is_empty() {
+        DataQuality::Degraded
+    } else if tvl_is_stale {
+        DataQuality::Cached
+    } else {
+        DataQuality::Live
     }
+}
 
-    let value_e6 = value_e6_u128 as u64;
+/// USD value in e6 of `amount_e8` tokens at `rate`
+///
+/// Computed from the integer rate (amount × receive_e6 ÷ pay_e8), so
+/// sub-micro prices still contribute; the f64 price is only logged.
+fn token_value_e6(token_symbol: &str, amount_e8: &Nat, rate: &PriceRate) -> Result<u64> {
+    let value_e6 = rate.value_e6(amount_e8)?.0.to_u64()
+        .ok_or_else(|| {
+            crate::infrastructure::IcpiError::Other(
+                format!("Value overflow: {} of {} exceeds u64 max", amount_e8, token_symbol)
+            )
+        })?;
 
     ic_cdk::println!(
         "  {} tokens of {}: ${} (@ ${}/token)",
-        amount_e8 as f64 / 100_000_000.0,
+        amount_e8.0.to_f64().unwrap_or(0.0) / 100_000_000.0,
         token_symbol,
         value_e6 as f64 / 1_000_000.0,
-        price_per_token_e6 as f64 / 1_000_000.0
+        rate.price_usdt()
     );
 
     Ok(value_e6)
@@ -170,15 +260,22 @@ async fn get_token_usd_value(token_symbol: &str, amount: &Nat) -> Result<u64> {
 
 /// Get portfolio state without caching
 ///
-/// Returns complete portfolio state for display
+/// Returns complete portfolio state for display (fallback oracle allowed)
 pub async fn get_portfolio_state_uncached() -> Result<IndexState> {
-    ic_cdk::println!("CALC: Building portfolio state");
+    get_portfolio_state_with_policy(PricingPolicy::AllowFallback).await
+}
 
-    // Get all balances
-    let balances = crate::_2_CRITICAL_DATA::token_queries::get_all_balances_uncached().await?;
+/// `get_portfolio_state_uncached` priced under `policy`
+///
+/// Rebalancing uses `PrimaryOnly`, so which token it trades is never
+/// decided on fallback oracle prices.
+pub async fn get_portfolio_state_with_policy(policy: PricingPolicy) -> Result<IndexState> {
+    ic_cdk::println!("CALC: Building portfolio state");
 
-    // Calculate total value
-    let total_value_nat = calculate_portfolio_value_atomic().await?;
+    // Fetch balances and prices once; value and positions both derive from them
+    let inputs = PortfolioInputs::fetch(policy).await?;
+    let balances = &inputs.balances;
+    let (total_value_nat, _) = inputs.total_value()?;
     // Handle u128 values properly - convert to f64 safely with validation
     let total_value_u128 = total_value_nat.0.to_u128()
         .ok_or_else(|| crate::infrastructure::IcpiError::Other(
@@ -201,68 +298,62 @@ pub async fn get_portfolio_state_uncached() -> Result<IndexState> {
 
     // Build positions with proper USD values and percentages
     let mut current_positions = Vec::new();
-    for (symbol, balance) in &balances {
-        let token = match symbol.as_str() {
-            "ALEX" => Some(TrackedToken::ALEX),
-            "ZERO" => Some(TrackedToken::ZERO),
-            "KONG" => Some(TrackedToken::KONG),
-            "BOB" => Some(TrackedToken::BOB),
-            "ckUSDT" => Some(TrackedToken::ckUSDT),
-            _ => None,
+    for (symbol, balance) in balances {
+        let token = TrackedToken::from_symbol(symbol)
+            .map_err(crate::infrastructure::IcpiError::Other)?;
+
+        // Same prices as the total above - propagate errors to fail safely
+        let usd_value_e6 = inputs.usd_value_e6(symbol, balance)?;
+
+        let usd_value = usd_value_e6 as f64 / 1_000_000.0;
+
+        // Percentage of total portfolio, from exact basis points
+        let percentage = if total_value_u128 > 0 {
+            crate::infrastructure::bps_of(&Nat::from(usd_value_e6), &total_value_nat)? as f64 / 100.0
+        } else {
+            0.0
         };
 
-        if let Some(t) = token {
-            // Calculate USD value - propagate errors to fail safely
-            let usd_value_e6 = if symbol == "ckUSDT" {
-                // ckUSDT is 1:1 with USD
-                balance.0.to_u64().ok_or_else(|| {
-                    crate::infrastructure::IcpiError::Other(
-                        format!("ckUSDT balance {} exceeds u64 maximum", balance)
-                    )
-                })?
-            } else {
-                // Get USD value from token pricing - propagate errors instead of silently failing
-                get_token_usd_value(symbol, balance).await?
-            };
-
-            let usd_value = usd_value_e6 as f64 / 1_000_000.0;
-
-            // Calculate percentage of total portfolio
-            let percentage = if total_value_f64 > 0.0 {
-                (usd_value / total_value_f64) * 100.0
-            } else {
-                0.0
-            };
-
-            current_positions.push(CurrentPosition {
-                token: t,
-                balance: balance.clone(),
-                usd_value,
-                percentage,
-            });
-        }
+        current_positions.push(CurrentPosition {
+            pool_unavailable_since: crate::_3_KONG_LIQUIDITY::pool_status::unavailable_since(&token),
+            token,
+            balance: balance.clone(),
+            usd_value,
+            percentage,
+        });
     }
 
     // Calculate target allocations from Kong Locker TVL
     // This ensures portfolio tracks real market liquidity distribution
-    let tvl_data = crate::_3_KONG_LIQUIDITY::tvl::calculate_kong_locker_tvl().await?;
+    let tvl_reading = crate::_3_KONG_LIQUIDITY::tvl::calculate_kong_locker_tvl_or_last_good().await?;
+    if tvl_reading.is_stale {
+        ic_cdk::println!("⚠️ Targets use stale TVL from {} (Kong Locker unavailable)", tvl_reading.computed_at);
+    }
+    let targets_tvl_timestamp = tvl_reading.computed_at;
+    let data_quality = assess_data_quality(&inputs, tvl_reading.is_stale);
+    let tvl_data = tvl_reading.tvl;
     let total_tvl: f64 = tvl_data.iter().map(|(_, v)| v).sum();
 
     ic_cdk::println!("📊 Target allocations from Kong Locker TVL (total: ${:.2}):", total_tvl);
 
-    let target_allocations: Vec<TargetAllocation> = tvl_data.iter()
-        .map(|(token, tvl_usd)| {
-            let target_percentage = if total_tvl > 0.0 {
-                (tvl_usd / total_tvl) * 100.0
-            } else {
-                // Fallback to equal allocation if TVL is zero
-                25.0
-            };
+    // Smoothed so a TVL collapse winds a token down over several cycles
+    let smoothed = crate::_2_CRITICAL_DATA::target_smoothing::smoothed_targets(&tvl_data);
+
+    // Capped so no single token can dominate the index
+    let config = crate::infrastructure::config::get_config();
+    let caps: Vec<f64> = smoothed.iter().map(|(token, _)| config.max_token_weight(token)).collect();
+    let smoothed_pcts: Vec<f64> = smoothed.iter().map(|(_, pct)| *pct).collect();
+    let capped = crate::infrastructure::math::cap_weights(&smoothed_pcts, &caps);
 
+    let target_allocations: Vec<TargetAllocation> = tvl_data.iter()
+        .zip(smoothed_pcts.iter().zip(&caps).zip(capped))
+        .map(|((token, tvl_usd), ((smoothed_pct, cap), target_percentage))| {
             ic_cdk::println!(
-                "  {}: ${:.2} = {:.2}% of TVL",
+                "  {}: ${:.2} = {:.2}% of TVL (smoothed target {:.2}%, capped target {:.2}%)",
                 token.to_symbol(),
                 tvl_usd,
+                if total_tvl > 0.0 { tvl_usd / total_tvl * 100.0 } else { 0.0 },
+                smoothed_pct,
                 target_percentage
             );
 
@@ -270,10 +361,17 @@ pub async fn get_portfolio_state_uncached() -> Result<IndexState> {
                 token: token.clone(),
                 target_percentage,
                 target_usd_value: total_value_f64 * (target_percentage / 100.0),
+                weight_cap: (*smoothed_pct > *cap).then_some(*cap),
             }
         })
         .collect();
 
+    // Deviations against targets that don't sum to 100% would all be skewed
+    if let Err(e) = crate::_2_CRITICAL_DATA::validation::validate_target_allocations(&target_allocations) {
+        ic_cdk::println!("🚨 Refusing to compute deviations: {}", e);
+        return Err(e);
+    }
+
     // Calculate deviations comparing current vs target allocations
     use crate::types::rebalancing::AllocationDeviation;
 
@@ -294,7 +392,7 @@ pub async fn get_portfolio_state_uncached() -> Result<IndexState> {
         // Calculate deviation
         let deviation_pct = target.target_percentage - current_pct;
         let usd_difference = target.target_usd_value - current_usd;
-        let trade_size_usd = usd_difference.abs() * crate::infrastructure::TRADE_INTENSITY;
+        let trade_size_usd = crate::_2_CRITICAL_DATA::trade_intensity::trade_size_usd(usd_difference, deviation_pct)?;
 
         deviations.push(AllocationDeviation {
             token: target.token.clone(),
@@ -312,13 +410,16 @@ pub async fn get_portfolio_state_uncached() -> Result<IndexState> {
         .map(|(_, b)| b.clone())
         .unwrap_or(Nat::from(0u64));
 
-    Ok(IndexState {
+    // Never ship NaN/inf or inconsistent percentages to callers
+    crate::_2_CRITICAL_DATA::validation::validate_index_state(IndexState {
         total_value: total_value_f64,
         current_positions,
         target_allocations,
         deviations,
         ckusdt_balance,
-        timestamp: ic_cdk::api::time(),
+        timestamp: crate::infrastructure::runtime::time(),
+        targets_tvl_timestamp: Some(targets_tvl_timestamp),
+        data_quality: Some(data_quality),
     })
 }
 
@@ -342,4 +443,191 @@ mod tests {
         assert_eq!(get_token_decimals("ALEX"), 8);
         assert_eq!(get_token_decimals("unknown"), 8);
     }
+
+    fn symbols(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_valuation_coverage_complete() {
+        let valued = symbols(&["ALEX", "ZERO", "KONG", "BOB", "ckUSDT"]);
+        assert!(assert_valuation_coverage(&valued).is_ok());
+    }
+
+    #[test]
+    fn test_valuation_coverage_missing_token() {
+        // ZERO's balance query failed so it was never valued
+        let valued = symbols(&["ALEX", "KONG", "BOB", "ckUSDT"]);
+        match assert_valuation_coverage(&valued) {
+            Err(crate::infrastructure::IcpiError::Validation(
+                crate::infrastructure::ValidationError::IncompleteValuation { missing_tokens }
+            )) => assert_eq!(missing_tokens, vec!["ZERO".to_string()]),
+            other => panic!("Expected IncompleteValuation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_valuation_coverage_missing_ckusdt() {
+        let valued = symbols(&["ALEX", "ZERO", "KONG", "BOB"]);
+        assert!(assert_valuation_coverage(&valued).is_err());
+    }
+
+    #[test]
+    fn test_inputs_fetched_once_per_state_read() {
+        use std::cell::Cell;
+
+        let balance_calls = Cell::new(0u32);
+        let price_calls = Cell::new(0u32);
+
+        let inputs = futures::executor::block_on(PortfolioInputs::fetch_with(
+            PricingPolicy::AllowFallback,
+            || {
+                balance_calls.set(balance_calls.get() + 1);
+                async {
+                    Ok(vec![
+                        ("ALEX".to_string(), Nat::from(200_000_000u64)),   // 2 ALEX
+                        ("ZERO".to_string(), Nat::from(0u64)),
+                        ("KONG".to_string(), Nat::from(1_000_000_000u64)), // 10 KONG
+                        ("BOB".to_string(), Nat::from(50_000_000u64)),     // 0.5 BOB
+                        ("ckUSDT".to_string(), Nat::from(5_000_000u64)),   // $5
+                    ])
+                }
+            },
+            |token| {
+                price_calls.set(price_calls.get() + 1);
+                async move {
+                    Ok((PriceRate::from_price(match token {
+                        TrackedToken::ALEX => 1.5,
+                        TrackedToken::KONG => 0.25,
+                        _ => 4.0,
+                    }), PriceSource::Kongswap))
+                }
+            },
+        )).unwrap();
+
+        // Deriving the total and every position makes no further calls
+        let (total, valued) = inputs.total_value().unwrap();
+        let position_sum: u64 = inputs.balances.iter()
+            .map(|(symbol, balance)| inputs.usd_value_e6(symbol, balance).unwrap())
+            .sum();
+
+        assert_eq!(balance_calls.get(), 1);
+        assert_eq!(price_calls.get(), 3); // zero ZERO balance and ckUSDT aren't priced
+        assert_eq!(valued.len(), 5);
+        // $3 + $0 + $2.50 + $2 + $5
+        assert_eq!(total, Nat::from(12_500_000u64));
+        assert_eq!(Nat::from(position_sum), total);
+    }
+
+    #[test]
+    fn test_inputs_price_failure_propagates() {
+        let result = futures::executor::block_on(PortfolioInputs::fetch_with(
+            PricingPolicy::PrimaryOnly,
+            || async { Ok(vec![("ALEX".to_string(), Nat::from(1u64))]) },
+            |_| async { Err(crate::infrastructure::IcpiError::Other("pool down".to_string())) },
+        ));
+        assert!(result.is_err());
+    }
+
+    fn fetch_inputs(balances: Vec<(String, Nat)>, fallback: TrackedToken) -> PortfolioInputs {
+        futures::executor::block_on(PortfolioInputs::fetch_with(
+            PricingPolicy::AllowFallback,
+            || async move { Ok(balances) },
+            |token| {
+                let source = if token == fallback { PriceSource::FallbackOracle } else { PriceSource::Kongswap };
+                async move { Ok((PriceRate::from_price(1.0), source)) }
+            },
+        )).unwrap()
+    }
+
+    fn full_balances() -> Vec<(String, Nat)> {
+        ["ALEX", "ZERO", "KONG", "BOB", "ckUSDT"].iter()
+            .map(|s| (s.to_string(), Nat::from(100_000_000u64)))
+            .collect()
+    }
+
+    #[test]
+    fn test_data_quality_live_and_cached() {
+        let inputs = fetch_inputs(full_balances(), TrackedToken::ckUSDT);
+        assert!(inputs.fallback_priced.is_empty());
+        assert_eq!(assess_data_quality(&inputs, false), DataQuality::Live);
+        assert_eq!(assess_data_quality(&inputs, true), DataQuality::Cached);
+    }
+
+    #[test]
+    fn test_partial_data_is_degraded() {
+        // ZERO's balance query failed and was dropped
+        let mut balances = full_balances();
+        balances.retain(|(symbol, _)| symbol != "ZERO");
+        let inputs = fetch_inputs(balances, TrackedToken::ckUSDT);
+        assert_eq!(assess_data_quality(&inputs, false), DataQuality::Degraded);
+        // Missing data outranks stale TVL
+        assert_eq!(assess_data_quality(&inputs, true), DataQuality::Degraded);
+
+        // Every balance present, but KONG priced by the fallback oracle
+        let inputs = fetch_inputs(full_balances(), TrackedToken::KONG);
+        assert_eq!(inputs.fallback_priced, vec![TrackedToken::KONG]);
+        assert_eq!(assess_data_quality(&inputs, false), DataQuality::Degraded);
+    }
+
+    fn unpriced_inputs() -> PortfolioInputs {
+        PortfolioInputs {
+            balances: Vec::new(),
+            prices: Vec::new(),
+            policy: PricingPolicy::PrimaryOnly,
+            fallback_priced: Vec::new(),
+            fetched_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_ckusdt_valued_one_to_one() {
+        let inputs = unpriced_inputs();
+        assert_eq!(inputs.usd_value_e6("ckUSDT", &Nat::from(5_250_000u64)).unwrap(), 5_250_000);
+        assert_eq!(inputs.usd_value_e6("ckUSDT", &Nat::from(0u64)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_unknown_symbol_rejected() {
+        let inputs = unpriced_inputs();
+        for symbol in ["ICPI", "ICP", "ckusdt"] {
+            let err = inputs.usd_value_e6(symbol, &Nat::from(1u64)).unwrap_err();
+            assert!(err.to_string().contains("Unknown tracked token symbol"), "{}: {}", symbol, err);
+        }
+        // Even a zero balance of an unknown token is an error
+        assert!(inputs.usd_value_e6("ICPI", &Nat::from(0u64)).is_err());
+
+        // A tracked token without a price is a different failure
+        let err = inputs.usd_value_e6("ALEX", &Nat::from(1u64)).unwrap_err();
+        assert!(err.to_string().contains("No price fetched"));
+    }
+
+    #[test]
+    fn test_sub_micro_prices_value_non_zero() {
+        let inputs = PortfolioInputs {
+            balances: vec![
+                ("ALEX".to_string(), Nat::from(500_000u64 * 100_000_000)), // 500k ALEX
+                ("BOB".to_string(), Nat::from(3_000_000u64 * 100_000_000)), // 3M BOB
+            ],
+            prices: vec![
+                // $0.0000004 per token from the fallback oracle
+                (TrackedToken::ALEX, PriceRate::from_price(0.000_000_4)),
+                // Kongswap: 7 e6 units of ckUSDT for 10 tokens ($0.0000007 each)
+                (TrackedToken::BOB, PriceRate::from_swap(Nat::from(7u64), Nat::from(1_000_000_000u64))),
+            ],
+            policy: PricingPolicy::AllowFallback,
+            fallback_priced: vec![TrackedToken::ALEX],
+            fetched_at: 0,
+        };
+
+        // An e6 price would floor both to zero
+        for (_, rate) in &inputs.prices {
+            assert_eq!((rate.price_usdt() * 1_000_000.0) as u64, 0);
+        }
+
+        // $0.20 and $2.10
+        assert_eq!(inputs.usd_value_e6("ALEX", &inputs.balances[0].1).unwrap(), 200_000);
+        assert_eq!(inputs.usd_value_e6("BOB", &inputs.balances[1].1).unwrap(), 2_100_000);
+        assert_eq!(inputs.total_value().unwrap().0, Nat::from(2_300_000u64));
+    }
 }
@@ -1,10 +1,9 @@
 //! Supply tracking module
 
-use candid::{Nat, Principal};
+use candid::Nat;
 use num_traits::ToPrimitive;
 use crate::infrastructure::{Result, IcpiError};
-use crate::infrastructure::constants::ICPI_CANISTER_ID;
-use crate::infrastructure::errors::{QueryError, ValidationError};
+use crate::infrastructure::errors::ValidationError;
 
 /// Get ICPI supply without caching
 ///
@@ -14,34 +13,17 @@ pub async fn get_icpi_supply_uncached() -> Result<Nat> {
     // Assert no caching for critical operation
     ic_cdk::println!("CRITICAL: Querying ICPI supply (uncached)");
 
-    // Parse ICPI canister principal
-    let icpi_canister = Principal::from_text(ICPI_CANISTER_ID)
-        .map_err(|e| IcpiError::Query(QueryError::CanisterUnreachable {
-            canister: ICPI_CANISTER_ID.to_string(),
-            reason: format!("Invalid principal: {}", e),
-        }))?;
-
-    // ICRC-1 total_supply call - no arguments
-    let result: std::result::Result<(Nat,), _> = ic_cdk::call(
-        icpi_canister,
-        "icrc1_total_supply",
-        ()
-    ).await;
-
-    match result {
-        Ok((supply,)) => {
+    match crate::infrastructure::icpi_ledger::total_supply().await {
+        Ok(supply) => {
             // Validate supply is reasonable
             validate_supply(&supply)?;
 
             ic_cdk::println!("✅ ICPI total supply: {}", supply);
             Ok(supply)
         }
-        Err((code, msg)) => {
-            ic_cdk::println!("❌ Supply query failed: {:?} - {}", code, msg);
-            Err(IcpiError::Query(QueryError::CanisterUnreachable {
-                canister: ICPI_CANISTER_ID.to_string(),
-                reason: format!("Call failed: {:?} - {}", code, msg),
-            }))
+        Err(e) => {
+            ic_cdk::println!("❌ Supply query failed: {}", e);
+            Err(e)
         }
     }
 }
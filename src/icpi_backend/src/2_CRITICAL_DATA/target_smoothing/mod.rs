@@ -0,0 +1,316 @@
+//! Target weight smoothing
+//!
+//! Raw Kong Locker TVL can collapse between cycles (a large lock expires,
+//! liquidity is pulled). Feeding that straight into targets would flip a
+//! token to ~0% and trigger a large sell in one cycle. Instead targets are
+//! derived from an exponentially weighted moving average of TVL, and each
+//! token's target may move at most a configured number of percentage points
+//! per rebalance cycle.
+
+use candid::{CandidType, Deserialize};
+use std::cell::RefCell;
+use crate::types::TrackedToken;
+
+/// Persisted smoothing state
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct SmoothingState {
+    /// EWMA of each token's locked TVL (USD)
+    pub smoothed_tvl: Vec<(TrackedToken, f64)>,
+    /// Last emitted target percentages (sum to 100)
+    pub targets: Vec<(TrackedToken, f64)>,
+    /// Nanosecond timestamp of the last cycle
+    pub last_update: u64,
+    pub cycles: u64,
+}
+
+/// Smoothing parameters
+#[derive(Clone, Copy, Debug)]
+pub struct SmoothingParams {
+    pub half_life_seconds: u64,
+    /// Max per-cycle change of any target in percentage points (<= 0 disables)
+    pub max_change_pct: f64,
+    /// Minimum spacing between cycles; calls in between reuse stored targets
+    pub cycle_seconds: u64,
+}
+
+impl SmoothingParams {
+    pub fn from_config() -> Self {
+        let config = crate::infrastructure::config::get_config();
+        Self {
+            half_life_seconds: config.target_smoothing_half_life_seconds(),
+            max_change_pct: config.max_target_change_pct(),
+            cycle_seconds: crate::infrastructure::REBALANCE_INTERVAL_SECONDS,
+        }
+    }
+}
+
+thread_local! {
+    static SMOOTHING_STATE: RefCell<Option<SmoothingState>> = RefCell::new(None);
+}
+
+/// Fold one TVL sample into the average
+///
+/// The weight of the old average halves every `half_life_seconds`; a zero
+/// half-life disables smoothing.
+pub fn ewma_step(previous: f64, sample: f64, elapsed_seconds: u64, half_life_seconds: u64) -> f64 {
+    if half_life_seconds == 0 {
+        return sample;
+    }
+    let retained = 0.5f64.powf(elapsed_seconds as f64 / half_life_seconds as f64);
+    previous * retained + sample * (1.0 - retained)
+}
+
+/// Convert TVL values to percentages summing to 100 (equal split if all zero)
+pub fn normalize_targets(tvl: &[(TrackedToken, f64)]) -> Vec<(TrackedToken, f64)> {
+    let total: f64 = tvl.iter().map(|(_, v)| v.max(0.0)).sum();
+    tvl.iter()
+        .map(|(token, value)| {
+            let pct = if total > 0.0 {
+                value.max(0.0) / total * 100.0
+            } else {
+                100.0 / tvl.len() as f64
+            };
+            (token.clone(), pct)
+        })
+        .collect()
+}
+
+/// Move from `previous` towards `desired`, changing no target by more than `max_change_pct`
+///
+/// All targets move by the same fraction of their desired change, so the
+/// result still sums to 100 when both inputs do. Tokens without a previous
+/// target take their desired value directly.
+pub fn cap_target_changes(
+    previous: &[(TrackedToken, f64)],
+    desired: &[(TrackedToken, f64)],
+    max_change_pct: f64,
+) -> Vec<(TrackedToken, f64)> {
+    let prev_of = |token: &TrackedToken| {
+        previous.iter().find(|(t, _)| t == token).map(|(_, v)| *v)
+    };
+
+    let largest_change = desired.iter()
+        .filter_map(|(token, target)| prev_of(token).map(|prev| (target - prev).abs()))
+        .fold(0.0, f64::max);
+
+    let fraction = if max_change_pct > 0.0 && largest_change > max_change_pct {
+        max_change_pct / largest_change
+    } else {
+        1.0
+    };
+
+    desired.iter()
+        .map(|(token, target)| {
+            let value = match prev_of(token) {
+                Some(prev) => prev + (target - prev) * fraction,
+                None => *target,
+            };
+            (token.clone(), value)
+        })
+        .collect()
+}
+
+/// Advance smoothing state by one cycle with a fresh TVL sample
+pub fn advance(
+    state: Option<&SmoothingState>,
+    tvl: &[(TrackedToken, f64)],
+    now: u64,
+    params: SmoothingParams,
+) -> SmoothingState {
+    let Some(state) = state else {
+        return SmoothingState {
+            smoothed_tvl: tvl.to_vec(),
+            targets: normalize_targets(tvl),
+            last_update: now,
+            cycles: 1,
+        };
+    };
+
+    let elapsed_seconds = now.saturating_sub(state.last_update) / 1_000_000_000;
+    let smoothed_tvl: Vec<(TrackedToken, f64)> = tvl.iter()
+        .map(|(token, sample)| {
+            let value = match state.smoothed_tvl.iter().find(|(t, _)| t == token) {
+                Some((_, prev)) => ewma_step(*prev, *sample, elapsed_seconds, params.half_life_seconds),
+                None => *sample,
+            };
+            (token.clone(), value)
+        })
+        .collect();
+
+    let desired = normalize_targets(&smoothed_tvl);
+    let targets = cap_target_changes(&state.targets, &desired, params.max_change_pct);
+
+    SmoothingState {
+        smoothed_tvl,
+        targets,
+        last_update: now,
+        cycles: state.cycles + 1,
+    }
+}
+
+/// Whether a new cycle is due (or the stored state doesn't cover `tvl`)
+pub fn needs_advance(state: Option<&SmoothingState>, tvl: &[(TrackedToken, f64)], now: u64, params: SmoothingParams) -> bool {
+    match state {
+        None => true,
+        Some(state) => {
+            let elapsed_seconds = now.saturating_sub(state.last_update) / 1_000_000_000;
+            elapsed_seconds >= params.cycle_seconds
+                || tvl.iter().any(|(token, _)| !state.targets.iter().any(|(t, _)| t == token))
+        }
+    }
+}
+
+/// Smoothed target percentages for the given raw TVL
+///
+/// Advances the state at most once per rebalance cycle; calls in between
+/// return the targets from the current cycle.
+pub fn smoothed_targets(tvl: &[(TrackedToken, f64)]) -> Vec<(TrackedToken, f64)> {
+    let params = SmoothingParams::from_config();
+    let now = crate::infrastructure::runtime::time();
+
+    SMOOTHING_STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        if needs_advance(s.as_ref(), tvl, now, params) {
+            *s = Some(advance(s.as_ref(), tvl, now, params));
+        }
+        let state = s.as_ref().expect("smoothing state set above");
+        tvl.iter()
+            .filter_map(|(token, _)| state.targets.iter().find(|(t, _)| t == token).cloned())
+            .collect()
+    })
+}
+
+/// Current smoothing state (for diagnostics)
+pub fn get_state() -> Option<SmoothingState> {
+    SMOOTHING_STATE.with(|s| s.borrow().clone())
+}
+
+/// Export smoothing state for stable storage
+pub fn export_state() -> Option<SmoothingState> {
+    get_state()
+}
+
+/// Import smoothing state from stable storage
+pub fn import_state(state: Option<SmoothingState>) {
+    SMOOTHING_STATE.with(|s| *s.borrow_mut() = state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_NANOS: u64 = 3_600_000_000_000;
+
+    fn params(max_change_pct: f64) -> SmoothingParams {
+        SmoothingParams { half_life_seconds: 6 * 3600, max_change_pct, cycle_seconds: 3600 }
+    }
+
+    fn tvl(alex: f64, zero: f64, kong: f64, bob: f64) -> Vec<(TrackedToken, f64)> {
+        vec![
+            (TrackedToken::ALEX, alex),
+            (TrackedToken::ZERO, zero),
+            (TrackedToken::KONG, kong),
+            (TrackedToken::BOB, bob),
+        ]
+    }
+
+    fn target(state: &SmoothingState, token: TrackedToken) -> f64 {
+        state.targets.iter().find(|(t, _)| *t == token).unwrap().1
+    }
+
+    fn sum(targets: &[(TrackedToken, f64)]) -> f64 {
+        targets.iter().map(|(_, v)| v).sum()
+    }
+
+    #[test]
+    fn test_ewma_half_life() {
+        assert!((ewma_step(100.0, 0.0, 3600, 3600) - 50.0).abs() < 1e-9);
+        assert!((ewma_step(100.0, 0.0, 7200, 3600) - 25.0).abs() < 1e-9);
+        assert_eq!(ewma_step(100.0, 0.0, 0, 3600), 100.0);
+        assert_eq!(ewma_step(100.0, 7.0, 60, 0), 7.0);
+    }
+
+    #[test]
+    fn test_normalize_targets() {
+        let targets = normalize_targets(&tvl(50.0, 25.0, 25.0, 0.0));
+        assert!((targets[0].1 - 50.0).abs() < 1e-9);
+        assert_eq!(targets[3].1, 0.0);
+
+        let equal = normalize_targets(&tvl(0.0, 0.0, 0.0, 0.0));
+        assert!(equal.iter().all(|(_, v)| (*v - 25.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_cap_preserves_sum_and_bounds_change() {
+        let previous = tvl(25.0, 25.0, 25.0, 25.0);
+        let desired = tvl(0.0, 40.0, 30.0, 30.0);
+        let capped = cap_target_changes(&previous, &desired, 2.0);
+
+        assert!((sum(&capped) - 100.0).abs() < 1e-9);
+        for ((_, prev), (_, new)) in previous.iter().zip(&capped) {
+            assert!((new - prev).abs() <= 2.0 + 1e-9);
+        }
+        // Largest mover hits the cap exactly
+        assert!((capped[0].1 - 23.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cap_disabled_or_not_binding() {
+        let previous = tvl(25.0, 25.0, 25.0, 25.0);
+        let desired = tvl(24.0, 26.0, 25.0, 25.0);
+        assert_eq!(cap_target_changes(&previous, &desired, 2.0), desired);
+
+        let desired = tvl(0.0, 50.0, 25.0, 25.0);
+        assert_eq!(cap_target_changes(&previous, &desired, 0.0), desired);
+    }
+
+    #[test]
+    fn test_first_cycle_uses_raw_targets() {
+        let state = advance(None, &tvl(40.0, 20.0, 20.0, 20.0), HOUR_NANOS, params(2.0));
+        assert!((target(&state, TrackedToken::ALEX) - 40.0).abs() < 1e-9);
+        assert_eq!(state.cycles, 1);
+    }
+
+    #[test]
+    fn test_tvl_collapse_winds_down_gradually() {
+        let mut state = advance(None, &tvl(25.0, 25.0, 25.0, 25.0), 0, params(2.0));
+
+        // ALEX liquidity evaporates and stays gone
+        let shocked = tvl(0.0, 25.0, 25.0, 25.0);
+        let mut previous = target(&state, TrackedToken::ALEX);
+        for cycle in 1..=48 {
+            state = advance(Some(&state), &shocked, cycle * HOUR_NANOS, params(2.0));
+            let alex = target(&state, TrackedToken::ALEX);
+            assert!(alex <= previous + 1e-9, "target must not rebound");
+            assert!(previous - alex <= 2.0 + 1e-9, "cycle {} moved {:.3}pp", cycle, previous - alex);
+            assert!((sum(&state.targets) - 100.0).abs() < 1e-6);
+            previous = alex;
+        }
+
+        // After two days of zero TVL the target has mostly wound down
+        assert!(previous < 5.0, "ALEX target still {:.2}%", previous);
+    }
+
+    #[test]
+    fn test_brief_spike_is_damped() {
+        let state = advance(None, &tvl(25.0, 25.0, 25.0, 25.0), 0, params(0.0));
+        // One-cycle 10x spike in KONG TVL
+        let state = advance(Some(&state), &tvl(25.0, 25.0, 250.0, 25.0), HOUR_NANOS, params(0.0));
+        let kong = target(&state, TrackedToken::KONG);
+        let raw = normalize_targets(&tvl(25.0, 25.0, 250.0, 25.0))[2].1;
+        assert!(kong > 25.0 && kong < raw * 0.6, "spike not damped: {:.2}%", kong);
+    }
+
+    #[test]
+    fn test_needs_advance_once_per_cycle() {
+        let sample = tvl(25.0, 25.0, 25.0, 25.0);
+        assert!(needs_advance(None, &sample, 0, params(2.0)));
+
+        let state = advance(None, &sample, 0, params(2.0));
+        assert!(!needs_advance(Some(&state), &sample, HOUR_NANOS / 2, params(2.0)));
+        assert!(needs_advance(Some(&state), &sample, HOUR_NANOS, params(2.0)));
+
+        let partial = SmoothingState { targets: state.targets[..2].to_vec(), ..state.clone() };
+        assert!(needs_advance(Some(&partial), &sample, 0, params(2.0)));
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Queries token balances without caching for financial accuracy
 
-use candid::{Nat, Principal};
+use candid::Nat;
 use crate::infrastructure::{Result, IcpiError};
 use crate::infrastructure::errors::{QueryError};
 use crate::types::{TrackedToken, Account};
@@ -17,14 +17,14 @@ pub async fn get_token_balance_uncached(token: &TrackedToken) -> Result<Nat> {
     let token_canister = token.get_canister_id()?;
 
     // Backend's account (no subaccount)
-    let backend_principal = ic_cdk::id();
+    let backend_principal = crate::infrastructure::runtime::canister_id();
     let account = Account {
         owner: backend_principal,
         subaccount: None,
     };
 
     // ICRC-1 balance_of call
-    let result: std::result::Result<(Nat,), _> = ic_cdk::call(
+    let result: std::result::Result<(Nat,), _> = crate::infrastructure::runtime::call(
         token_canister,
         "icrc1_balance_of",
         (account,)
@@ -48,9 +48,35 @@ pub async fn get_token_balance_uncached(token: &TrackedToken) -> Result<Nat> {
     }
 }
 
+/// A tracked token's balance net of what is owed to burners
+///
+/// What the rebalancer may sell.
+pub async fn get_spendable_token_balance(token: &TrackedToken) -> Result<Nat> {
+    let balance = get_token_balance_uncached(token).await?;
+    let owed = crate::_1_CRITICAL_OPERATIONS::burning::owed_balances();
+    let symbol = token.to_symbol().to_string();
+    Ok(net_of_owed(vec![(symbol, balance)], &owed).remove(0).1)
+}
+
+/// Subtract the amounts owed to burners from `balances`, floored at zero (pure function)
+pub fn net_of_owed(balances: Vec<(String, Nat)>, owed: &[(String, Nat)]) -> Vec<(String, Nat)> {
+    balances.into_iter()
+        .map(|(symbol, balance)| {
+            let net = match owed.iter().find(|(s, _)| *s == symbol) {
+                Some((_, amount)) if balance > *amount => balance - amount.clone(),
+                Some(_) => Nat::from(0u64),
+                None => balance,
+            };
+            (symbol, net)
+        })
+        .collect()
+}
+
 /// Get all token balances without caching
 ///
-/// Queries all tracked tokens + ckUSDT in parallel for efficiency
+/// Queries all tracked tokens + ckUSDT in parallel for efficiency. Amounts
+/// owed to burners are subtracted: they back no outstanding ICPI, so they
+/// count toward neither TVL nor redemptions.
 pub async fn get_all_balances_uncached() -> Result<Vec<(String, Nat)>> {
     ic_cdk::println!("QUERY: Getting all token balances in parallel");
 
@@ -96,43 +122,26 @@ pub async fn get_all_balances_uncached() -> Result<Vec<(String, Nat)>> {
     balances.push(("ckUSDT".to_string(), ckusdt_balance));
 
     ic_cdk::println!("✅ Retrieved {} token balances", balances.len());
-    Ok(balances)
+    Ok(net_of_owed(balances, &crate::_1_CRITICAL_OPERATIONS::burning::owed_balances()))
 }
 
 /// Get ckUSDT balance specifically
+///
+/// Excludes quarantined stray deposits and uncollected fees, which are not
+/// part of the portfolio.
 pub async fn get_ckusdt_balance() -> Result<Nat> {
-    use crate::infrastructure::constants::CKUSDT_CANISTER_ID;
-
-    let ckusdt = Principal::from_text(CKUSDT_CANISTER_ID)
-        .map_err(|e| IcpiError::Query(QueryError::CanisterUnreachable {
-            canister: CKUSDT_CANISTER_ID.to_string(),
-            reason: format!("Invalid principal: {}", e),
-        }))?;
-
-    let backend_principal = ic_cdk::id();
-    let account = Account {
-        owner: backend_principal,
-        subaccount: None,
-    };
-
-    let result: std::result::Result<(Nat,), _> = ic_cdk::call(
-        ckusdt,
-        "icrc1_balance_of",
-        (account,)
-    ).await;
+    Ok(get_ckusdt_breakdown().await?.net)
+}
 
-    match result {
-        Ok((balance,)) => {
-            ic_cdk::println!("✅ ckUSDT balance: {}", balance);
-            Ok(balance)
-        }
-        Err((code, msg)) => {
-            Err(IcpiError::Query(QueryError::CanisterUnreachable {
-                canister: CKUSDT_CANISTER_ID.to_string(),
-                reason: format!("{:?}: {}", code, msg),
-            }))
-        }
-    }
+/// Gross ckUSDT balance and the index's net share of it
+pub async fn get_ckusdt_breakdown() -> Result<crate::_2_CRITICAL_DATA::ckusdt_reserves::CkusdtBalance> {
+    let raw_balance = crate::infrastructure::ckusdt_ledger::balance_of(crate::infrastructure::runtime::canister_id()).await?;
+    let breakdown = crate::_2_CRITICAL_DATA::ckusdt_reserves::get_breakdown(&raw_balance);
+    ic_cdk::println!(
+        "✅ ckUSDT balance: {} (raw {}, quarantined {}, fees {})",
+        breakdown.net, breakdown.gross, breakdown.quarantined, breakdown.uncollected_fees
+    );
+    Ok(breakdown)
 }
 
 #[cfg(test)]
@@ -148,4 +157,23 @@ mod tests {
         let tokens = TrackedToken::all();
         assert!(tokens.len() >= 4); // ALEX, ZERO, KONG, BOB minimum
     }
+
+    #[test]
+    fn test_net_of_owed() {
+        let balances = vec![
+            ("ALEX".to_string(), Nat::from(1_000u64)),
+            ("KONG".to_string(), Nat::from(50u64)),
+            ("ckUSDT".to_string(), Nat::from(700u64)),
+        ];
+        let owed = vec![
+            ("ALEX".to_string(), Nat::from(400u64)),
+            ("KONG".to_string(), Nat::from(80u64)),
+        ];
+
+        assert_eq!(net_of_owed(balances, &owed), vec![
+            ("ALEX".to_string(), Nat::from(600u64)),
+            ("KONG".to_string(), Nat::from(0u64)),
+            ("ckUSDT".to_string(), Nat::from(700u64)),
+        ]);
+    }
 }
@@ -0,0 +1,115 @@
+//! Trade intensity
+//!
+//! Fraction of a token's USD deviation traded per rebalance cycle. The
+//! default is a flat 10%; the ramped curves trade a larger fraction of big
+//! deviations (faster convergence) and a smaller fraction of small ones
+//! (less churn), capped at `TRADE_INTENSITY_MAX`.
+
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use crate::infrastructure::Result;
+use crate::infrastructure::math::calculate_trade_size;
+use crate::infrastructure::constants::{
+    TRADE_INTENSITY, TRADE_INTENSITY_MIN, TRADE_INTENSITY_MAX,
+    MIN_DEVIATION_PERCENT, INTENSITY_RAMP_FULL_DEVIATION_PERCENT,
+};
+
+/// How trade intensity scales with deviation
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IntensityCurve {
+    /// `TRADE_INTENSITY` regardless of deviation
+    #[default]
+    Flat,
+    /// Rises linearly from min to max between the trigger threshold and
+    /// `INTENSITY_RAMP_FULL_DEVIATION_PERCENT`
+    Linear,
+    /// Fixed bands: <2% → min, <5% → flat, <10% → 20%, else max
+    Stepped,
+}
+
+/// Fraction of the USD deviation to trade for a deviation of `deviation_pct`
+/// percentage points (sign ignored)
+pub fn trade_intensity(curve: IntensityCurve, deviation_pct: f64) -> f64 {
+    let deviation = deviation_pct.abs();
+    match curve {
+        IntensityCurve::Flat => TRADE_INTENSITY,
+        IntensityCurve::Linear => {
+            let span = INTENSITY_RAMP_FULL_DEVIATION_PERCENT - MIN_DEVIATION_PERCENT;
+            let progress = ((deviation - MIN_DEVIATION_PERCENT) / span).clamp(0.0, 1.0);
+            TRADE_INTENSITY_MIN + (TRADE_INTENSITY_MAX - TRADE_INTENSITY_MIN) * progress
+        }
+        IntensityCurve::Stepped => {
+            if deviation < 2.0 {
+                TRADE_INTENSITY_MIN
+            } else if deviation < 5.0 {
+                TRADE_INTENSITY
+            } else if deviation < INTENSITY_RAMP_FULL_DEVIATION_PERCENT {
+                0.2
+            } else {
+                TRADE_INTENSITY_MAX
+            }
+        }
+    }
+}
+
+/// Trade size in USD for a deviation, using the configured curve
+pub fn trade_size_usd(usd_difference: f64, deviation_pct: f64) -> Result<f64> {
+    let curve = crate::infrastructure::config::get_config().trade_intensity_curve();
+    trade_size_usd_with(curve, usd_difference, deviation_pct)
+}
+
+/// Trade size in USD for a deviation on `curve`
+///
+/// Sized in ckUSDT e6 by `calculate_trade_size`, so the intensity is
+/// applied in whole basis points and the size rounds down.
+pub fn trade_size_usd_with(curve: IntensityCurve, usd_difference: f64, deviation_pct: f64) -> Result<f64> {
+    let size_e6 = calculate_trade_size(usd_difference, trade_intensity(curve, deviation_pct))?;
+    Ok(num_traits::ToPrimitive::to_f64(&size_e6.0).unwrap_or(0.0) / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(curve: IntensityCurve, usd_difference: f64, deviation_pct: f64) -> f64 {
+        trade_size_usd_with(curve, usd_difference, deviation_pct).unwrap()
+    }
+
+    #[test]
+    fn test_flat_is_default_and_constant() {
+        assert_eq!(IntensityCurve::default(), IntensityCurve::Flat);
+        for deviation in [0.5, 1.5, 5.0, 25.0, -25.0] {
+            assert_eq!(trade_intensity(IntensityCurve::Flat, deviation), TRADE_INTENSITY);
+        }
+    }
+
+    #[test]
+    fn test_ramped_sizes_vs_flat() {
+        // Small deviation: $15 off, 1.5pp
+        let flat_small = size(IntensityCurve::Flat, 15.0, 1.5);
+        assert!(size(IntensityCurve::Linear, 15.0, 1.5) < flat_small);
+        assert!(size(IntensityCurve::Stepped, 15.0, 1.5) < flat_small);
+
+        // Large deviation: -$200 off (overweight), 20pp
+        let flat_large = size(IntensityCurve::Flat, -200.0, -20.0);
+        assert!((flat_large - 20.0).abs() < 1e-9);
+        assert!((size(IntensityCurve::Linear, -200.0, -20.0) - 60.0).abs() < 1e-9);
+        assert!((size(IntensityCurve::Stepped, -200.0, -20.0) - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ramp_bounds() {
+        for curve in [IntensityCurve::Linear, IntensityCurve::Stepped] {
+            let mut previous = 0.0;
+            for tenth in 0..1_000 {
+                let intensity = trade_intensity(curve, tenth as f64 / 10.0);
+                assert!(intensity >= TRADE_INTENSITY_MIN && intensity <= TRADE_INTENSITY_MAX);
+                assert!(intensity >= previous, "{:?} not monotonic at {}", curve, tenth);
+                previous = intensity;
+            }
+        }
+
+        assert_eq!(trade_intensity(IntensityCurve::Linear, MIN_DEVIATION_PERCENT), TRADE_INTENSITY_MIN);
+        assert_eq!(trade_intensity(IntensityCurve::Linear, INTENSITY_RAMP_FULL_DEVIATION_PERCENT), TRADE_INTENSITY_MAX);
+    }
+}
@@ -1,7 +1,16 @@
 //! Data validation module
 
 use candid::Nat;
-use crate::infrastructure::{Result, IcpiError, ValidationError};
+use crate::infrastructure::{Result, IcpiError, ValidationError, CalculationError};
+use crate::infrastructure::config::RuntimeConfig;
+use crate::types::TrackedToken;
+use crate::types::portfolio::IndexState;
+use crate::types::rebalancing::TargetAllocation;
+
+/// Allowed distance of a percentage sum from 100
+const PERCENTAGE_SUM_TOLERANCE: f64 = 0.5;
+/// Float noise outside [0, 100] that is clamped rather than rejected
+const PERCENTAGE_CLAMP_EPSILON: f64 = 1e-6;
 
 /// Validate external supply data
 pub fn validate_supply(new_supply: &Nat, cached_supply: Option<&Nat>) -> Result<()> {
@@ -36,3 +45,277 @@ pub fn validate_price(
 
     Ok(())
 }
+
+/// Validate a constructed index state before it is served or acted on
+///
+/// Rejects non-finite or negative values and percentage sets that don't sum
+/// to 100 ± 0.5; clamps percentages that stray outside [0, 100] by float
+/// noise only. An all-zero portfolio may have all-zero current percentages.
+pub fn validate_index_state(mut state: IndexState) -> Result<IndexState> {
+    check_non_negative("total_value", state.total_value)?;
+
+    for position in &mut state.current_positions {
+        let symbol = position.token.to_symbol().to_string();
+        check_non_negative(&format!("{} usd_value", symbol), position.usd_value)?;
+        position.percentage = clamp_percentage(&format!("{} percentage", symbol), position.percentage)?;
+    }
+
+    for target in &mut state.target_allocations {
+        let symbol = target.token.to_symbol().to_string();
+        target.target_percentage = clamp_percentage(&format!("{} target_percentage", symbol), target.target_percentage)?;
+        check_non_negative(&format!("{} target_usd_value", symbol), target.target_usd_value)?;
+    }
+
+    for deviation in &state.deviations {
+        let symbol = deviation.token.to_symbol();
+        for (field, value) in [
+            ("current_pct", deviation.current_pct),
+            ("target_pct", deviation.target_pct),
+            ("deviation_pct", deviation.deviation_pct),
+            ("usd_difference", deviation.usd_difference),
+            ("trade_size_usd", deviation.trade_size_usd),
+        ] {
+            check_finite(&format!("{} {}", symbol, field), value)?;
+        }
+    }
+
+    if state.total_value > 0.0 {
+        check_percentage_sum("current positions", state.current_positions.iter().map(|p| p.percentage))?;
+    }
+    validate_target_allocations(&state.target_allocations)?;
+
+    Ok(state)
+}
+
+/// Validate target allocations before deviations are computed from them
+///
+/// Targets are TVL-derived, smoothed and capped; if any of those steps
+/// produces a set that doesn't sum to 100 ± 0.5, every deviation would be
+/// skewed by the shortfall. No targets (no TVL data) is allowed.
+pub fn validate_target_allocations(targets: &[TargetAllocation]) -> Result<()> {
+    if targets.is_empty() {
+        return Ok(());
+    }
+    check_percentage_sum("target allocations", targets.iter().map(|t| t.target_percentage))
+}
+
+/// Check that no two tokens share a symbol, Kongswap symbol or ledger
+///
+/// Two tokens resolving to the same Kongswap symbol would have the same LP
+/// positions and prices counted twice in TVL and portfolio value.
+pub fn check_token_identities(tokens: &[TrackedToken], config: &RuntimeConfig) -> Result<()> {
+    let mut identities: Vec<(&str, String, &TrackedToken)> = Vec::new();
+    for token in tokens {
+        let canister_id = token.get_canister_id().map_err(IcpiError::Other)?;
+        identities.push(("symbol", token.to_symbol().to_string(), token));
+        identities.push(("kongswap_symbol", config.kongswap_symbol(token), token));
+        identities.push(("canister_id", canister_id.to_text(), token));
+    }
+
+    for (i, (field, value, token)) in identities.iter().enumerate() {
+        let clashes: Vec<String> = identities[i + 1..].iter()
+            .filter(|(f, v, _)| f == field && v == value)
+            .map(|(_, _, t)| t.to_symbol().to_string())
+            .collect();
+        if !clashes.is_empty() {
+            let mut tokens = vec![token.to_symbol().to_string()];
+            tokens.extend(clashes);
+            return Err(IcpiError::Validation(ValidationError::DuplicateToken {
+                field: field.to_string(),
+                value: value.clone(),
+                tokens,
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// Check the tracked tokens plus ckUSDT against the current config
+pub fn validate_token_identities() -> Result<()> {
+    let mut tokens = TrackedToken::all_vec();
+    tokens.push(TrackedToken::ckUSDT);
+    check_token_identities(&tokens, &crate::infrastructure::config::get_config())
+}
+
+fn invalid_state(reason: String) -> IcpiError {
+    IcpiError::Calculation(CalculationError::InvalidPortfolioState { reason })
+}
+
+fn check_finite(field: &str, value: f64) -> Result<()> {
+    if !value.is_finite() {
+        return Err(invalid_state(format!("{} is not finite ({})", field, value)));
+    }
+    Ok(())
+}
+
+fn check_non_negative(field: &str, value: f64) -> Result<()> {
+    check_finite(field, value)?;
+    if value < 0.0 {
+        return Err(invalid_state(format!("{} is negative ({})", field, value)));
+    }
+    Ok(())
+}
+
+fn clamp_percentage(field: &str, value: f64) -> Result<f64> {
+    check_finite(field, value)?;
+    if value < -PERCENTAGE_CLAMP_EPSILON || value > 100.0 + PERCENTAGE_CLAMP_EPSILON {
+        return Err(invalid_state(format!("{} out of range ({})", field, value)));
+    }
+    Ok(value.clamp(0.0, 100.0))
+}
+
+fn check_percentage_sum(label: &str, percentages: impl Iterator<Item = f64>) -> Result<()> {
+    let sum: f64 = percentages.sum();
+    if (sum - 100.0).abs() > PERCENTAGE_SUM_TOLERANCE {
+        return Err(invalid_state(format!("{} sum to {:.4}%, expected 100%", label, sum)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TrackedToken;
+    use crate::types::portfolio::CurrentPosition;
+
+    fn position(token: TrackedToken, usd_value: f64, percentage: f64) -> CurrentPosition {
+        CurrentPosition { token, balance: Nat::from(1u64), usd_value, percentage, pool_unavailable_since: None }
+    }
+
+    fn target(token: TrackedToken, target_percentage: f64) -> TargetAllocation {
+        TargetAllocation { token, target_percentage, target_usd_value: target_percentage, weight_cap: None }
+    }
+
+    fn state(total_value: f64, positions: Vec<CurrentPosition>) -> IndexState {
+        IndexState {
+            total_value,
+            current_positions: positions,
+            target_allocations: vec![target(TrackedToken::ALEX, 50.0), target(TrackedToken::KONG, 50.0)],
+            deviations: vec![],
+            timestamp: 0,
+            ckusdt_balance: Nat::from(0u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        }
+    }
+
+    fn is_invalid_state(result: Result<IndexState>) -> bool {
+        matches!(result, Err(IcpiError::Calculation(CalculationError::InvalidPortfolioState { .. })))
+    }
+
+    #[test]
+    fn test_valid_state_passes() {
+        let s = state(100.0, vec![
+            position(TrackedToken::ALEX, 60.0, 60.0),
+            position(TrackedToken::KONG, 40.0, 40.0),
+        ]);
+        assert!(validate_index_state(s).is_ok());
+    }
+
+    #[test]
+    fn test_nan_from_bad_price_rejected() {
+        // A NaN price propagates into usd_value and then percentage
+        let usd_value = f64::NAN * 1_000.0;
+        let s = state(100.0, vec![
+            position(TrackedToken::ALEX, usd_value, usd_value / 100.0 * 100.0),
+            position(TrackedToken::KONG, 40.0, 40.0),
+        ]);
+        assert!(is_invalid_state(validate_index_state(s)));
+    }
+
+    #[test]
+    fn test_infinite_percentage_rejected() {
+        // Dividing by a denormal total yields inf
+        let total = f64::MIN_POSITIVE / 1e10;
+        let s = state(total, vec![position(TrackedToken::ALEX, 1.0, 1.0 / total * 100.0)]);
+        assert!(is_invalid_state(validate_index_state(s)));
+    }
+
+    #[test]
+    fn test_negative_total_rejected() {
+        assert!(is_invalid_state(validate_index_state(state(-1.0, vec![]))));
+    }
+
+    #[test]
+    fn test_bad_percentage_sum_rejected() {
+        let s = state(100.0, vec![
+            position(TrackedToken::ALEX, 60.0, 60.0),
+            position(TrackedToken::KONG, 39.0, 39.0),
+        ]);
+        assert!(is_invalid_state(validate_index_state(s)));
+
+        let mut s = state(100.0, vec![position(TrackedToken::ALEX, 100.0, 100.0)]);
+        s.target_allocations = vec![target(TrackedToken::ALEX, 90.0)];
+        assert!(is_invalid_state(validate_index_state(s)));
+    }
+
+    #[test]
+    fn test_malformed_targets_detected() {
+        let even: Vec<TargetAllocation> = [TrackedToken::ALEX, TrackedToken::ZERO, TrackedToken::KONG, TrackedToken::BOB]
+            .into_iter()
+            .map(|t| target(t, 25.0))
+            .collect();
+        assert!(validate_target_allocations(&even).is_ok());
+        assert!(validate_target_allocations(&[]).is_ok());
+
+        // A capped token whose excess was never redistributed
+        let mut short = even.clone();
+        short[0].target_percentage = 20.0;
+        assert!(matches!(
+            validate_target_allocations(&short),
+            Err(IcpiError::Calculation(CalculationError::InvalidPortfolioState { .. }))
+        ));
+
+        // A token counted twice
+        let mut over = even.clone();
+        over.push(target(TrackedToken::ALEX, 25.0));
+        assert!(validate_target_allocations(&over).is_err());
+
+        // Float noise within tolerance
+        let mut noisy = even;
+        noisy[3].target_percentage += 0.3;
+        assert!(validate_target_allocations(&noisy).is_ok());
+    }
+
+    #[test]
+    fn test_float_noise_clamped() {
+        let s = state(100.0, vec![
+            position(TrackedToken::ALEX, 100.0, 100.0 + 1e-9),
+            position(TrackedToken::KONG, 0.0, -1e-9),
+        ]);
+        let validated = validate_index_state(s).unwrap();
+        assert_eq!(validated.current_positions[0].percentage, 100.0);
+        assert_eq!(validated.current_positions[1].percentage, 0.0);
+    }
+
+    #[test]
+    fn test_empty_portfolio_allowed() {
+        let s = state(0.0, vec![position(TrackedToken::ckUSDT, 0.0, 0.0)]);
+        assert!(validate_index_state(s).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_token_identities_rejected() {
+        let mut tokens = TrackedToken::all_vec();
+        tokens.push(TrackedToken::ckUSDT);
+        let mut config = RuntimeConfig::default();
+        assert!(check_token_identities(&tokens, &config).is_ok());
+
+        // Aliasing BOB to ALEX's pool symbol would count ALEX liquidity twice
+        config.kongswap_symbols = Some(vec![(TrackedToken::BOB, "ALEX".to_string())]);
+        match check_token_identities(&tokens, &config) {
+            Err(IcpiError::Validation(ValidationError::DuplicateToken { field, value, tokens })) => {
+                assert_eq!((field.as_str(), value.as_str()), ("kongswap_symbol", "ALEX"));
+                assert_eq!(tokens, vec!["ALEX".to_string(), "BOB".to_string()]);
+            }
+            other => panic!("expected DuplicateToken, got {:?}", other),
+        }
+
+        // The same token listed twice clashes on every identity; symbol is reported first
+        let config = RuntimeConfig::default();
+        match check_token_identities(&[TrackedToken::KONG, TrackedToken::KONG], &config) {
+            Err(IcpiError::Validation(ValidationError::DuplicateToken { field, .. })) => assert_eq!(field, "symbol"),
+            other => panic!("expected DuplicateToken, got {:?}", other),
+        }
+    }
+}
@@ -19,7 +19,7 @@ pub async fn get_all_lock_canisters() -> Result<Vec<(Principal, Principal)>> {
     let kong_locker = Principal::from_text(KONG_LOCKER_ID)
         .map_err(|e| IcpiError::Other(format!("Invalid kong_locker canister ID: {}", e)))?;
 
-    let (canisters,): (Vec<(Principal, Principal)>,) = ic_cdk::call(
+    let (canisters,): (Vec<(Principal, Principal)>,) = crate::infrastructure::runtime::call(
         kong_locker,
         "get_all_lock_canisters",
         ()
@@ -15,6 +15,10 @@
 //! Queries Kongswap for token prices using swap_amounts endpoint.
 //! Returns how much ckUSDT you'd receive for 1 token.
 //!
+//! ### pool_status/
+//! Flags tokens whose Kongswap pool is removed, suspended or drained.
+//! Set and cleared by price queries; flagged tokens aren't rebalanced.
+//!
 //! ### tvl/
 //! Calculates total value locked across all kong_locker positions.
 //! Queries each lock canister's balances from Kongswap and sums by token.
@@ -28,11 +32,12 @@
 //! let tvl = _3_KONG_LIQUIDITY::tvl::calculate_kong_locker_tvl().await?;
 //! // Returns: [(ALEX, $22500), (ZERO, $640), (KONG, $48), (BOB, $2)]
 //!
-//! // Get current token price
-//! let alex_price = _3_KONG_LIQUIDITY::pools::get_token_price_in_usdt(&TrackedToken::ALEX).await?;
-//! // Returns: 0.0012 (meaning 1 ALEX = 0.0012 ckUSDT)
+//! // Get current token rate (exact swap_amounts ratio)
+//! let alex_rate = _3_KONG_LIQUIDITY::pools::get_token_rate_in_usdt(&TrackedToken::ALEX).await?;
+//! // Returns: 120_000 e6 ckUSDT per 1e8 ALEX (1 ALEX = 0.0012 ckUSDT)
 //! ```
 
 pub mod locker;
 pub mod pools;
+pub mod pool_status;
 pub mod tvl;
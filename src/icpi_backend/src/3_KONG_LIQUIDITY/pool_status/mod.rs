@@ -0,0 +1,112 @@
+//! Pool availability
+//!
+//! A tracked token whose Kongswap pool has been removed, suspended or
+//! drained can't be priced or traded. The first "pool gone" error from a
+//! price query flags the token with a timestamp; the rebalancer skips
+//! flagged tokens and positions surface the flag. The next successful
+//! price query clears it.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::infrastructure::Result;
+use crate::infrastructure::logging::{record_error_at, Subsystem};
+use crate::types::TrackedToken;
+
+thread_local! {
+    static UNAVAILABLE_SINCE: RefCell<HashMap<TrackedToken, u64>> = RefCell::new(HashMap::new());
+}
+
+/// Whether a Kongswap error means the pool itself is gone (as opposed to a
+/// transient call failure)
+pub fn is_pool_unavailable_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    (message.contains("pool") && message.contains("not found"))
+        || (message.contains("insufficient") && message.contains("in pool"))
+        || message.contains("suspended or removed")
+        || (message.contains("token") && message.contains("not found"))
+        || message.contains("symbol not found")
+}
+
+/// Update the token's flag from a Kongswap price query result
+pub fn observe_price_result<T>(token: &TrackedToken, result: &Result<T>, now: u64) {
+    match result {
+        Ok(_) => {
+            let cleared = UNAVAILABLE_SINCE.with(|flags| flags.borrow_mut().remove(token));
+            if let Some(since) = cleared {
+                ic_cdk::println!("✅ {} pool available again (unavailable since {})", token.to_symbol(), since);
+            }
+        }
+        Err(e) if is_pool_unavailable_error(&e.to_string()) => {
+            let newly_flagged = UNAVAILABLE_SINCE.with(|flags| {
+                let mut flags = flags.borrow_mut();
+                if flags.contains_key(token) {
+                    false
+                } else {
+                    flags.insert(token.clone(), now);
+                    true
+                }
+            });
+            if newly_flagged {
+                let message = format!("{} pool unavailable on Kongswap: {}", token.to_symbol(), e);
+                ic_cdk::println!("🚨 CRITICAL: {}", message);
+                record_error_at(Subsystem::Pricing, message, now);
+            }
+        }
+        // Transient failures say nothing about the pool
+        Err(_) => {}
+    }
+}
+
+/// When the token's pool was first seen unavailable, if it still is
+pub fn unavailable_since(token: &TrackedToken) -> Option<u64> {
+    UNAVAILABLE_SINCE.with(|flags| flags.borrow().get(token).copied())
+}
+
+/// Tokens whose pools are currently flagged unavailable
+pub fn unavailable_tokens() -> Vec<TrackedToken> {
+    TrackedToken::all().iter()
+        .filter(|token| unavailable_since(token).is_some())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::IcpiError;
+
+    fn kong_err(message: &str) -> Result<()> {
+        Err(IcpiError::Other(format!("Kongswap returned error: {}", message)))
+    }
+
+    #[test]
+    fn test_pool_unavailable_classification() {
+        assert!(is_pool_unavailable_error("Kongswap returned error: Pool BOB_ckUSDT not found"));
+        assert!(is_pool_unavailable_error("Kongswap returned error: Insufficient ckUSDT in pool"));
+        assert!(is_pool_unavailable_error("Pay token is suspended or removed"));
+        assert!(is_pool_unavailable_error("Token BOB not found or duplicate symbols/addresses exist"));
+
+        assert!(!is_pool_unavailable_error("Kongswap price query failed: \"canister is stopped\""));
+        assert!(!is_pool_unavailable_error("Unrealistic price for ALEX: 0 (expected 0.000001 to 100)"));
+    }
+
+    #[test]
+    fn test_flag_set_once_and_cleared_on_success() {
+        let token = TrackedToken::ZERO;
+        observe_price_result(&token, &Ok(()), 1);
+        assert_eq!(unavailable_since(&token), None);
+
+        observe_price_result(&token, &kong_err("Pool ZERO_ckUSDT not found"), 100);
+        observe_price_result(&token, &kong_err("Pool ZERO_ckUSDT not found"), 200);
+        assert_eq!(unavailable_since(&token), Some(100));
+        assert!(unavailable_tokens().contains(&token));
+
+        // A transient error leaves the flag alone
+        observe_price_result(&token, &Err::<(), _>(IcpiError::Other("call rejected".to_string())), 300);
+        assert_eq!(unavailable_since(&token), Some(100));
+
+        observe_price_result(&token, &Ok(()), 400);
+        assert_eq!(unavailable_since(&token), None);
+        assert!(!unavailable_tokens().contains(&token));
+    }
+}
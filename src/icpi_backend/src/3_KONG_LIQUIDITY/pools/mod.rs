@@ -2,40 +2,268 @@
 //!
 //! Queries Kongswap for token prices via swap_amounts endpoint.
 //! Used to value portfolio tokens in USD equivalent.
+//!
+//! Pricing source priority:
+//! 1. Kongswap `swap_amounts` (primary, always tried first)
+//! 2. Admin-configured fallback oracle (only when Kongswap fails and the
+//!    caller's `PricingPolicy` allows it)
+//!
+//! Quotes from the fallback are flagged via `PriceSource::FallbackOracle`.
 
-use candid::{Nat, Principal};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use num_traits::ToPrimitive;
 use crate::infrastructure::{Result, IcpiError, KONGSWAP_BACKEND_ID};
 use crate::types::TrackedToken;
 use crate::types::kongswap::SwapAmountsResult;
 
-/// Get token price in ckUSDT
+/// Where a price came from
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceSource {
+    Kongswap,
+    FallbackOracle,
+}
+
+/// Exchange rate into ckUSDT as an integer ratio: `receive_e6` ckUSDT
+/// for `pay_e8` of the token
 ///
-/// Uses Kongswap's swap_amounts to query how much ckUSDT you'd receive
-/// for 1 token (in e8 decimals).
+/// Balances are valued straight from the ratio. Going through an e6 price
+/// first floors micro-priced tokens (e.g. $0.0000004) to zero.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct PriceRate {
+    pub receive_e6: Nat,
+    pub pay_e8: Nat,
+}
+
+/// Extra precision for rates converted from an f64 price (fallback oracle)
+const FLOAT_RATE_PAY_E8: u64 = 100_000_000 * 100_000_000; // 1e8 tokens
+
+impl PriceRate {
+    /// Rate from a `swap_amounts` quote
+    pub fn from_swap(receive_e6: Nat, pay_e8: Nat) -> Self {
+        PriceRate { receive_e6, pay_e8 }
+    }
+
+    /// Rate from a USD price per whole token
+    pub fn from_price(price_usdt: f64) -> Self {
+        // ckUSDT e6 received for 1e8 whole tokens
+        let receive_e6 = (price_usdt * 100_000_000.0 * 1_000_000.0).round() as u128;
+        PriceRate {
+            receive_e6: Nat::from(receive_e6),
+            pay_e8: Nat::from(FLOAT_RATE_PAY_E8),
+        }
+    }
+
+    /// USD price per whole token (display and sanity checks only)
+    pub fn price_usdt(&self) -> f64 {
+        let receive_e6 = self.receive_e6.0.to_f64().unwrap_or(f64::NAN);
+        let pay_e8 = self.pay_e8.0.to_f64().unwrap_or(f64::NAN);
+        (receive_e6 / 1_000_000.0) / (pay_e8 / 100_000_000.0)
+    }
+
+    /// ckUSDT value in e6 of `amount_e8` of the token: amount × receive ÷ pay
+    pub fn value_e6(&self, amount_e8: &Nat) -> Result<Nat> {
+        crate::infrastructure::multiply_and_divide(amount_e8, &self.receive_e6, &self.pay_e8)
+    }
+
+    /// Amount in e8 of the token worth `value_e6` ckUSDT: value × pay ÷ receive
+    pub fn amount_e8(&self, value_e6: &Nat) -> Result<Nat> {
+        crate::infrastructure::multiply_and_divide(value_e6, &self.pay_e8, &self.receive_e6)
+    }
+}
+
+/// Token price in ckUSDT with its source flagged
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct PriceQuote {
+    pub token: TrackedToken,
+    /// Derived from `rate`, for display
+    pub price_usdt: f64,
+    pub rate: PriceRate,
+    pub source: PriceSource,
+}
+
+impl PriceQuote {
+    pub fn is_fallback(&self) -> bool {
+        self.source == PriceSource::FallbackOracle
+    }
+}
+
+/// Whether a caller accepts fallback oracle prices
 ///
-/// Returns: Price in ckUSDT per token (as f64)
+/// Mint uses `PrimaryOnly` so depositors are always priced against the
+/// same market that the index trades on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PricingPolicy {
+    PrimaryOnly,
+    AllowFallback,
+}
+
+/// Get token price, falling back to the configured oracle if allowed
 ///
-/// Example: get_token_price_in_usdt(&TrackedToken::ALEX) -> 0.0012
-/// Means 1 ALEX = 0.0012 ckUSDT
-pub async fn get_token_price_in_usdt(token: &TrackedToken) -> Result<f64> {
+/// The fallback oracle is only queried when Kongswap fails, the policy
+/// allows it, and an oracle principal is configured.
+pub async fn get_token_price_quote(token: &TrackedToken, policy: PricingPolicy) -> Result<PriceQuote> {
+    let primary = get_token_rate_in_usdt(token).await;
+
+    let fallback = match (&primary, policy) {
+        (Err(e), PricingPolicy::AllowFallback) => {
+            match crate::infrastructure::config::get_config().price_oracle {
+                Some(oracle) => {
+                    ic_cdk::println!("⚠️ Kongswap pricing failed for {} ({}), querying fallback oracle", token.to_symbol(), e);
+                    Some(query_fallback_oracle(oracle, token).await.map(PriceRate::from_price))
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    };
+
+    let quote = select_price_quote(token, primary, fallback);
+    crate::infrastructure::logging::record_result(crate::infrastructure::logging::Subsystem::Pricing, &quote);
+    quote
+}
+
+/// Choose the price to use given the primary and (optional) fallback results
+///
+/// `fallback` is `None` when no fallback was attempted.
+pub fn select_price_quote(
+    token: &TrackedToken,
+    primary: Result<PriceRate>,
+    fallback: Option<Result<PriceRate>>,
+) -> Result<PriceQuote> {
+    let primary_err = match primary {
+        Ok(rate) => {
+            return Ok(PriceQuote {
+                token: token.clone(),
+                price_usdt: rate.price_usdt(),
+                rate,
+                source: PriceSource::Kongswap,
+            });
+        }
+        Err(e) => e,
+    };
+
+    match fallback {
+        Some(Ok(rate)) => {
+            let price_usdt = rate.price_usdt();
+            ic_cdk::println!("⚠️ Using FALLBACK oracle price for {}: {} ckUSDT", token.to_symbol(), price_usdt);
+            Ok(PriceQuote {
+                token: token.clone(),
+                price_usdt,
+                rate,
+                source: PriceSource::FallbackOracle,
+            })
+        }
+        Some(Err(fallback_err)) => Err(IcpiError::Other(format!(
+            "All price sources failed for {}: kongswap: {}; fallback oracle: {}",
+            token.to_symbol(), primary_err, fallback_err
+        ))),
+        None => Err(primary_err),
+    }
+}
+
+/// Query the fallback oracle for a token price
+///
+/// Expected oracle interface: `get_usd_price : (text) -> (variant { Ok : float64; Err : text }) query`
+async fn query_fallback_oracle(oracle: Principal, token: &TrackedToken) -> Result<f64> {
     let symbol = token.to_symbol();
 
-    // Special case: ckUSDT price is always 1.0
-    if symbol == "ckUSDT" {
-        return Ok(1.0);
+    let (result,): (std::result::Result<f64, String>,) = crate::infrastructure::runtime::call(
+        oracle,
+        "get_usd_price",
+        (symbol.to_string(),)
+    ).await.map_err(|e| {
+        IcpiError::Other(format!("Fallback oracle query failed: {:?}", e.1))
+    })?;
+
+    let price_usdt = result
+        .map_err(|e| IcpiError::Other(format!("Fallback oracle returned error: {}", e)))?;
+
+    validate_price_range(symbol, price_usdt)?;
+    Ok(price_usdt)
+}
+
+/// Sanity check: Prices should be reasonable for crypto assets
+/// Range: $0.000001 (very small tokens) to $100 (large tokens like BTC)
+fn validate_price_range(symbol: &str, price_usdt: f64) -> Result<()> {
+    if !(price_usdt > 0.0 && price_usdt <= 100.0) {
+        ic_cdk::println!("⚠️ Unrealistic price for {}: {} ckUSDT", symbol, price_usdt);
+        return Err(IcpiError::Other(format!(
+            "Unrealistic price for {}: {} (expected 0.000001 to 100)",
+            symbol, price_usdt
+        )));
     }
+    Ok(())
+}
 
-    let kongswap = Principal::from_text(KONGSWAP_BACKEND_ID)
-        .map_err(|e| IcpiError::Other(format!("Invalid kongswap canister ID: {}", e)))?;
+/// Get the token's exchange rate into ckUSDT
+///
+/// The `swap_amounts` output is kept as an integer ratio so valuations
+/// don't lose micro prices to f64 → e6 truncation. The result also sets or
+/// clears the token's pool availability flag.
+pub async fn get_token_rate_in_usdt(token: &TrackedToken) -> Result<PriceRate> {
+    let result = query_token_rate(token).await;
+    super::pool_status::observe_price_result(token, &result, crate::infrastructure::runtime::time());
+    result
+}
+
+async fn query_token_rate(token: &TrackedToken) -> Result<PriceRate> {
+    // Special case: ckUSDT price is always 1.0
+    if *token == TrackedToken::ckUSDT {
+        return Ok(PriceRate::from_price(1.0));
+    }
 
     // Query how much ckUSDT we'd get for 1 token (100_000_000 atomic units = 1.0 token)
     let one_token = Nat::from(100_000_000u64); // 1.0 in e8 decimals
+    let args = price_query_args(&crate::infrastructure::config::get_config(), token, one_token);
+    query_swap_rate(token.to_symbol(), args).await
+}
 
-    let (result,): (SwapAmountsResult,) = ic_cdk::call(
+/// An asset priced against ckUSDT for quoting, but not held by the index
+///
+/// Not a `TrackedToken`: it never gets a target, position or pool
+/// availability flag.
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteAsset {
+    ICP,
+}
+
+impl QuoteAsset {
+    /// Symbol on Kongswap and in API arguments
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            QuoteAsset::ICP => "ICP",
+        }
+    }
+
+    pub fn decimals(&self) -> u8 {
+        match self {
+            QuoteAsset::ICP => 8,
+        }
+    }
+}
+
+/// Get a quote asset's exchange rate into ckUSDT from Kongswap
+///
+/// Kongswap only; the fallback oracle is reserved for tracked tokens.
+pub async fn get_quote_asset_rate(asset: QuoteAsset) -> Result<PriceRate> {
+    let config = crate::infrastructure::config::get_config();
+    let one_unit = Nat::from(10u64.pow(asset.decimals() as u32));
+    let args = (asset.symbol().to_string(), one_unit, config.kongswap_symbol(&TrackedToken::ckUSDT));
+    let result = query_swap_rate(asset.symbol(), args).await;
+    crate::infrastructure::logging::record_result(crate::infrastructure::logging::Subsystem::Pricing, &result);
+    result
+}
+
+/// Rate from a `swap_amounts` quote of `(pay symbol, 1 whole token, ckUSDT)`
+async fn query_swap_rate(symbol: &str, args: (String, Nat, String)) -> Result<PriceRate> {
+    let kongswap = Principal::from_text(KONGSWAP_BACKEND_ID)
+        .map_err(|e| IcpiError::Other(format!("Invalid kongswap canister ID: {}", e)))?;
+    let one_token = args.1.clone();
+
+    let (result,): (SwapAmountsResult,) = crate::infrastructure::runtime::call(
         kongswap,
         "swap_amounts",
-        (symbol, one_token, "ckUSDT".to_string()) // Removed unnecessary clone
+        args
     ).await.map_err(|e| {
         ic_cdk::println!("Failed to query kongswap.swap_amounts for {}: {:?}", symbol, e);
         IcpiError::Other(format!("Kongswap price query failed: {:?}", e.1))
@@ -44,26 +272,16 @@ pub async fn get_token_price_in_usdt(token: &TrackedToken) -> Result<f64> {
     match result {
         SwapAmountsResult::Ok(reply) => {
             // Decimal handling:
-            // - Input: 100_000_000 (1.0 token in e8 decimals for ALEX/ZERO/KONG/BOB)
+            // - Input: 100_000_000 (1.0 token in e8 decimals for ALEX/ZERO/KONG/BOB and ICP)
             // - Output: ckUSDT amount in e6 decimals (ckUSDT uses 6 decimals, not 8)
-            // - Conversion: divide by 1_000_000 to get float price
-            let receive_e6 = reply.receive_amount.0.to_u64()
-                .ok_or_else(|| IcpiError::Other(format!("Price amount overflow for {}", symbol)))?;
-
-            let price_usdt = receive_e6 as f64 / 1_000_000.0; // e6 → f64
-
-            // Sanity check: Prices should be reasonable for crypto assets
-            // Range: $0.000001 (very small tokens) to $100 (large tokens like BTC)
-            if price_usdt <= 0.0 || price_usdt > 100.0 {
-                ic_cdk::println!("⚠️ Unrealistic price for {}: {} ckUSDT", symbol, price_usdt);
-                return Err(IcpiError::Other(format!(
-                    "Unrealistic price for {}: {} (expected 0.000001 to 100)",
-                    symbol, price_usdt
-                )));
-            }
+            // - Kept as a ratio; the float price is only for logging and range checks
+            let rate = rate_from_quote(symbol, reply.receive_amount, one_token)?;
+            let price_usdt = rate.price_usdt();
+
+            validate_price_range(symbol, price_usdt)?;
 
             ic_cdk::println!("✅ {} price: {} ckUSDT", symbol, price_usdt);
-            Ok(price_usdt)
+            Ok(rate)
         }
         SwapAmountsResult::Err(e) => {
             ic_cdk::println!("Kongswap price query error for {}: {}", symbol, e);
@@ -72,10 +290,54 @@ pub async fn get_token_price_in_usdt(token: &TrackedToken) -> Result<f64> {
     }
 }
 
+/// Rate from a one-token quote, refusing a quote that rounded to zero
+///
+/// Kongswap reports whole e6 units, so a token worth under $0.000001 quotes
+/// 0. That is a price too small to represent, not a worthless token; valuing
+/// it at zero would drop the whole position from TVL.
+fn rate_from_quote(symbol: &str, receive_e6: Nat, one_token: Nat) -> Result<PriceRate> {
+    if receive_e6 == Nat::from(0u64) {
+        return Err(IcpiError::Other(format!(
+            "{} quotes below 0.000001 ckUSDT per token, too small to price", symbol
+        )));
+    }
+    Ok(PriceRate::from_swap(receive_e6, one_token))
+}
+
+/// Arguments for the `swap_amounts` price query, using Kongswap symbols
+fn price_query_args(
+    config: &crate::infrastructure::config::RuntimeConfig,
+    token: &TrackedToken,
+    amount: Nat,
+) -> (String, Nat, String) {
+    (config.kongswap_symbol(token), amount, config.kongswap_symbol(&TrackedToken::ckUSDT))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_price_query_uses_kongswap_alias() {
+        let config = crate::infrastructure::config::RuntimeConfig {
+            kongswap_symbols: Some(vec![(TrackedToken::KONG, "KONG.o7oak".to_string())]),
+            ..Default::default()
+        };
+        let (pay, _, receive) = price_query_args(&config, &TrackedToken::KONG, Nat::from(1u64));
+        assert_eq!((pay.as_str(), receive.as_str()), ("KONG.o7oak", "ckUSDT"));
+    }
+
+    #[test]
+    fn test_sub_micro_quote_rejected() {
+        let one_token = Nat::from(100_000_000u64);
+        assert!(rate_from_quote("BOB", Nat::from(0u64), one_token.clone()).is_err());
+
+        // The smallest representable quote keeps its exact ratio
+        let rate = rate_from_quote("BOB", Nat::from(1u64), one_token.clone()).unwrap();
+        assert_eq!(rate, PriceRate::from_swap(Nat::from(1u64), one_token));
+        assert_eq!(rate.value_e6(&Nat::from(500_000_000u64)).unwrap(), Nat::from(5u64));
+    }
+
     #[test]
     fn test_kongswap_canister_id() {
         assert!(Principal::from_text(KONGSWAP_BACKEND_ID).is_ok());
@@ -86,4 +348,106 @@ mod tests {
         // Can't test async in unit test, but can verify logic path
         assert_eq!(KONGSWAP_BACKEND_ID, "2ipq2-uqaaa-aaaar-qailq-cai");
     }
+
+    #[test]
+    fn test_primary_success_uses_kongswap() {
+        let quote = select_price_quote(&TrackedToken::ALEX, Ok(PriceRate::from_price(0.0012)), None).unwrap();
+        assert_eq!(quote.price_usdt, 0.0012);
+        assert_eq!(quote.source, PriceSource::Kongswap);
+        assert!(!quote.is_fallback());
+    }
+
+    #[test]
+    fn test_primary_success_ignores_fallback() {
+        let quote = select_price_quote(&TrackedToken::ALEX, Ok(PriceRate::from_price(0.0012)), Some(Ok(PriceRate::from_price(0.5)))).unwrap();
+        assert_eq!(quote.price_usdt, 0.0012);
+        assert_eq!(quote.source, PriceSource::Kongswap);
+    }
+
+    #[test]
+    fn test_primary_fail_fallback_success_is_flagged() {
+        let primary = Err(IcpiError::Other("kongswap down".to_string()));
+        let quote = select_price_quote(&TrackedToken::ZERO, primary, Some(Ok(PriceRate::from_price(0.02)))).unwrap();
+        assert_eq!(quote.price_usdt, 0.02);
+        assert_eq!(quote.source, PriceSource::FallbackOracle);
+        assert!(quote.is_fallback());
+    }
+
+    #[test]
+    fn test_both_sources_fail() {
+        let primary = Err(IcpiError::Other("kongswap down".to_string()));
+        let fallback = Some(Err(IcpiError::Other("oracle down".to_string())));
+        let err = select_price_quote(&TrackedToken::KONG, primary, fallback).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("kongswap down"));
+        assert!(msg.contains("oracle down"));
+    }
+
+    #[test]
+    fn test_primary_fail_without_fallback_returns_primary_error() {
+        let primary = Err(IcpiError::Other("kongswap down".to_string()));
+        let err = select_price_quote(&TrackedToken::BOB, primary, None).unwrap_err();
+        assert_eq!(err.to_string(), "kongswap down");
+    }
+
+    #[test]
+    fn test_price_range_validation() {
+        assert!(validate_price_range("ALEX", 0.0012).is_ok());
+        assert!(validate_price_range("ALEX", 0.0).is_err());
+        assert!(validate_price_range("ALEX", 101.0).is_err());
+        assert!(validate_price_range("ALEX", f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_rate_values_sub_micro_prices() {
+        // Fallback oracle price of $0.0000004 floors to 0 as an e6 price
+        let rate = PriceRate::from_price(0.000_000_4);
+        assert_eq!((rate.price_usdt() * 1_000_000.0) as u64, 0);
+        // 1M tokens are still worth $0.40
+        let balance = Nat::from(1_000_000u64 * 100_000_000);
+        assert_eq!(rate.value_e6(&balance).unwrap(), Nat::from(400_000u64));
+
+        // Kongswap quote: 3 e6 units for 1 token, held 12.5 tokens
+        let rate = PriceRate::from_swap(Nat::from(3u64), Nat::from(100_000_000u64));
+        assert_eq!(rate.value_e6(&Nat::from(1_250_000_000u64)).unwrap(), Nat::from(37u64));
+        assert!((rate.price_usdt() - 0.000_003).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_rate_value_exact_where_float_floors() {
+        // Kongswap quote: 1 e6 unit of ckUSDT per token ($0.000001)
+        let rate = PriceRate::from_swap(Nat::from(1u64), Nat::from(100_000_000u64));
+        let balance = Nat::from(100u64 * 100_000_000);
+
+        // Float path: price × tokens × 1e6 lands just under 100 and floors
+        let float_e6 = (rate.price_usdt() * 100.0 * 1_000_000.0) as u64;
+        assert_eq!(float_e6, 99);
+        assert_eq!(rate.value_e6(&balance).unwrap(), Nat::from(100u64));
+
+        // $0.29 has no exact f64 form: 100 tokens float to $28.999999
+        let rate = PriceRate::from_swap(Nat::from(290_000u64), Nat::from(100_000_000u64));
+        assert_eq!((0.29 * 100.0 * 1_000_000.0) as u64, 28_999_999);
+        assert_eq!(rate.value_e6(&balance).unwrap(), Nat::from(29_000_000u64));
+    }
+
+    #[test]
+    fn test_rate_amount_inverts_value() {
+        // Kongswap quote: 4.25 ckUSDT for 1 ICP
+        let rate = PriceRate::from_swap(Nat::from(4_250_000u64), Nat::from(100_000_000u64));
+        assert_eq!(rate.amount_e8(&Nat::from(8_500_000u64)).unwrap(), Nat::from(200_000_000u64));
+        assert_eq!(rate.value_e6(&Nat::from(200_000_000u64)).unwrap(), Nat::from(8_500_000u64));
+        // A zero quote can't be inverted
+        let zero = PriceRate::from_swap(Nat::from(0u64), Nat::from(100_000_000u64));
+        assert!(zero.amount_e8(&Nat::from(1u64)).is_err());
+    }
+
+    #[test]
+    fn test_rate_round_trips_price() {
+        for price in [0.0012, 0.02, 1.0, 4.5, 100.0] {
+            assert_eq!(PriceRate::from_price(price).price_usdt(), price);
+        }
+        // 2 tokens at $1.50
+        let rate = PriceRate::from_price(1.5);
+        assert_eq!(rate.value_e6(&Nat::from(200_000_000u64)).unwrap(), Nat::from(3_000_000u64));
+    }
 }
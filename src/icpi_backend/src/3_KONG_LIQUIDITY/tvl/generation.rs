@@ -0,0 +1,406 @@
+//! Incremental TVL calculation
+//!
+//! Walks the lock canisters a page per timer tick so no single message has
+//! to query every locker. Partial sums are kept in a build tagged with a
+//! generation number and persisted across upgrades; the TVL cache keeps
+//! serving the last completed generation until the build finishes.
+
+use candid::{CandidType, Deserialize};
+use std::cell::RefCell;
+
+use super::TvlAccumulator;
+use crate::infrastructure::{Result, IcpiError, TVL_GENERATION_PAGE_SIZE};
+use crate::types::TrackedToken;
+use crate::types::kongswap::UserBalancesResult;
+use crate::types::portfolio::TvlCalculationReport;
+
+/// Running totals of a build, in storable form
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PartialTvl {
+    pub tvl: Vec<(String, f64)>,
+    pub lp_positions: Vec<(String, u64)>,
+    pub lp_entries_processed: u64,
+    pub lp_entries_skipped: u64,
+    pub queries_succeeded: u64,
+    pub queries_failed: u64,
+}
+
+impl PartialTvl {
+    fn into_accumulator(self) -> TvlAccumulator {
+        TvlAccumulator {
+            tvl: self.tvl.into_iter().collect(),
+            lp_positions: self.lp_positions.into_iter().collect(),
+            lp_entries_processed: self.lp_entries_processed,
+            lp_entries_skipped: self.lp_entries_skipped,
+        }
+    }
+
+    fn from_accumulator(acc: TvlAccumulator, queries_succeeded: u64, queries_failed: u64) -> Self {
+        let mut tvl: Vec<(String, f64)> = acc.tvl.into_iter().collect();
+        let mut lp_positions: Vec<(String, u64)> = acc.lp_positions.into_iter().collect();
+        tvl.sort_by(|a, b| a.0.cmp(&b.0));
+        lp_positions.sort();
+        Self {
+            tvl,
+            lp_positions,
+            lp_entries_processed: acc.lp_entries_processed,
+            lp_entries_skipped: acc.lp_entries_skipped,
+            queries_succeeded,
+            queries_failed,
+        }
+    }
+}
+
+/// A generation being built
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TvlBuild {
+    pub generation: u64,
+    pub started_at: u64,
+    /// Lock canisters as listed when the generation started
+    pub lock_ids: Vec<String>,
+    /// Index of the first lock canister not yet queried
+    pub next_index: u64,
+    pub partial: PartialTvl,
+}
+
+/// The latest generation that finished with a reliable TVL
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct CompletedTvlGeneration {
+    pub generation: u64,
+    pub started_at: u64,
+    pub completed_at: u64,
+    pub tvl: Vec<(TrackedToken, f64)>,
+    pub report: TvlCalculationReport,
+}
+
+/// Persisted generation state
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TvlGenerationState {
+    /// Number of the most recently started generation
+    pub last_generation: u64,
+    pub building: Option<TvlBuild>,
+    pub completed: Option<CompletedTvlGeneration>,
+}
+
+/// Progress of the generation being built
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TvlBuildProgress {
+    pub generation: u64,
+    pub started_at: u64,
+    pub lock_canisters_done: u64,
+    pub lock_canisters_total: u64,
+    pub queries_failed: u64,
+}
+
+/// As returned by `get_tvl_generation_status`
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TvlGenerationStatus {
+    pub building: Option<TvlBuildProgress>,
+    pub completed_generation: Option<u64>,
+    pub completed_at: Option<u64>,
+    pub completed_age_seconds: Option<u64>,
+    /// Oldest completed generation the rebalancer accepts
+    pub max_age_seconds: u64,
+}
+
+thread_local! {
+    static GENERATION: RefCell<TvlGenerationState> = RefCell::new(TvlGenerationState::default());
+    static STEP_IN_PROGRESS: RefCell<bool> = RefCell::new(false);
+}
+
+/// Start a new generation over the current lock canister list
+///
+/// Errors if a generation is already being built.
+pub async fn begin_generation() -> Result<u64> {
+    if let Some(generation) = building_generation() {
+        return Err(IcpiError::Other(format!("TVL generation {} is still building", generation)));
+    }
+    crate::_2_CRITICAL_DATA::validation::validate_token_identities()?;
+
+    let lock_canisters = super::super::locker::get_all_lock_canisters().await?;
+    let lock_ids = lock_canisters.iter().map(|(_, lock)| lock.to_text()).collect::<Vec<_>>();
+    let total = lock_ids.len();
+    let now = crate::infrastructure::runtime::time();
+
+    match GENERATION.with(|state| start_build(&mut state.borrow_mut(), lock_ids, now)) {
+        Some(generation) => {
+            ic_cdk::println!("📊 Started TVL generation {} over {} lock canisters", generation, total);
+            Ok(generation)
+        }
+        None => Err(IcpiError::Other("A TVL generation started while listing lock canisters".to_string())),
+    }
+}
+
+/// Query the next page of the generation being built, completing it after the last page
+///
+/// Does nothing if no generation is building or a step is already running.
+pub async fn step_generation() -> Result<()> {
+    let Some(_run_guard) = crate::infrastructure::reentrancy::RunFlagGuard::try_acquire(&STEP_IN_PROGRESS) else {
+        return Ok(());
+    };
+    run_step().await
+}
+
+async fn run_step() -> Result<()> {
+    let page = GENERATION.with(|state| {
+        state.borrow().building.as_ref().map(|build| (build.generation, next_page(build, TVL_GENERATION_PAGE_SIZE)))
+    });
+    let Some((generation, page)) = page else {
+        return Ok(());
+    };
+
+    if !page.is_empty() {
+        let results = super::query_lock_balances(page).await?;
+        GENERATION.with(|state| {
+            if let Some(build) = state.borrow_mut().building.as_mut().filter(|b| b.generation == generation) {
+                apply_page(build, results);
+            }
+        });
+    }
+
+    let now = crate::infrastructure::runtime::time();
+    match GENERATION.with(|state| finish_if_complete(&mut state.borrow_mut(), now)) {
+        Some(Ok(done)) => {
+            ic_cdk::println!("📊 TVL generation {} complete", done.generation);
+            super::publish_tvl(&done.tvl, &done.report, now);
+        }
+        Some(Err(e)) => {
+            ic_cdk::println!("⚠️ TVL generation {} discarded: {}", generation, e);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+pub(super) async fn begin_in_background() {
+    if let Err(e) = begin_generation().await {
+        ic_cdk::println!("⚠️ Could not start TVL generation: {}", e);
+    }
+}
+
+pub(super) async fn step_in_background() {
+    if let Err(e) = step_generation().await {
+        ic_cdk::println!("⚠️ TVL generation step failed: {}", e);
+    }
+}
+
+fn building_generation() -> Option<u64> {
+    GENERATION.with(|state| state.borrow().building.as_ref().map(|b| b.generation))
+}
+
+/// Begin building the next generation; None if one is already building
+fn start_build(state: &mut TvlGenerationState, lock_ids: Vec<String>, now: u64) -> Option<u64> {
+    if state.building.is_some() {
+        return None;
+    }
+    state.last_generation += 1;
+    state.building = Some(TvlBuild {
+        generation: state.last_generation,
+        started_at: now,
+        lock_ids,
+        next_index: 0,
+        partial: PartialTvl::default(),
+    });
+    Some(state.last_generation)
+}
+
+/// Lock canisters to query next
+fn next_page(build: &TvlBuild, page_size: usize) -> Vec<String> {
+    build.lock_ids.iter()
+        .skip(build.next_index as usize)
+        .take(page_size)
+        .cloned()
+        .collect()
+}
+
+/// Fold a page of `user_balances` results into the build's partial sums
+fn apply_page(build: &mut TvlBuild, results: Vec<(String, Option<UserBalancesResult>)>) {
+    let queried = results.len() as u64;
+    let partial = std::mem::take(&mut build.partial);
+    let (succeeded, failed) = (partial.queries_succeeded, partial.queries_failed);
+
+    let mut acc = partial.into_accumulator();
+    let (page_succeeded, page_failed) = super::accumulate_query_results(&mut acc, results);
+
+    build.partial = PartialTvl::from_accumulator(acc, succeeded + page_succeeded, failed + page_failed);
+    build.next_index += queried;
+}
+
+/// Close the build once every lock canister has been queried
+///
+/// A build with too many failed queries is discarded and the previous
+/// completed generation is kept.
+fn finish_if_complete(state: &mut TvlGenerationState, now: u64) -> Option<Result<CompletedTvlGeneration>> {
+    let complete = state.building.as_ref()
+        .is_some_and(|b| b.next_index as usize >= b.lock_ids.len());
+    if !complete {
+        return None;
+    }
+    let build = state.building.take()?;
+
+    let total = build.lock_ids.len() as u64;
+    let min_successful = crate::infrastructure::config::get_config().min_successful_lockers();
+    if let Err(e) = super::check_query_success(build.partial.queries_succeeded, total, min_successful) {
+        return Some(Err(e));
+    }
+
+    let failed = build.partial.queries_failed;
+    let (tvl, mut report) = build.partial.into_accumulator().finish(total, failed);
+    report.computed_at = now;
+    let done = CompletedTvlGeneration {
+        generation: build.generation,
+        started_at: build.started_at,
+        completed_at: now,
+        tvl,
+        report,
+    };
+    state.completed = Some(done.clone());
+    Some(Ok(done))
+}
+
+/// Require a completed generation no older than `max_age_seconds`
+pub fn check_generation_age(completed_at: Option<u64>, now: u64, max_age_seconds: u64) -> std::result::Result<(), String> {
+    match completed_at {
+        None => Err("no TVL generation has completed yet".to_string()),
+        Some(at) => {
+            let age = now.saturating_sub(at) / 1_000_000_000;
+            if age > max_age_seconds {
+                Err(format!("latest TVL generation is {}s old (max {}s)", age, max_age_seconds))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Check the latest completed generation against the configured maximum age
+pub fn require_recent_generation(now: u64) -> std::result::Result<(), String> {
+    let max_age = crate::infrastructure::config::get_config().max_tvl_generation_age_seconds();
+    let completed_at = GENERATION.with(|state| state.borrow().completed.as_ref().map(|c| c.completed_at));
+    check_generation_age(completed_at, now, max_age)
+}
+
+/// The last completed generation, if any
+pub fn latest_completed() -> Option<CompletedTvlGeneration> {
+    GENERATION.with(|state| state.borrow().completed.clone())
+}
+
+/// Progress of the current build and age of the last completed generation
+pub fn get_generation_status(now: u64) -> TvlGenerationStatus {
+    let max_age_seconds = crate::infrastructure::config::get_config().max_tvl_generation_age_seconds();
+    GENERATION.with(|state| status_of(&state.borrow(), now, max_age_seconds))
+}
+
+fn status_of(state: &TvlGenerationState, now: u64, max_age_seconds: u64) -> TvlGenerationStatus {
+    TvlGenerationStatus {
+        building: state.building.as_ref().map(|b| TvlBuildProgress {
+            generation: b.generation,
+            started_at: b.started_at,
+            lock_canisters_done: b.next_index,
+            lock_canisters_total: b.lock_ids.len() as u64,
+            queries_failed: b.partial.queries_failed,
+        }),
+        completed_generation: state.completed.as_ref().map(|c| c.generation),
+        completed_at: state.completed.as_ref().map(|c| c.completed_at),
+        completed_age_seconds: state.completed.as_ref()
+            .map(|c| now.saturating_sub(c.completed_at) / 1_000_000_000),
+        max_age_seconds,
+    }
+}
+
+/// Export generation state for stable storage
+pub fn export_state() -> TvlGenerationState {
+    GENERATION.with(|state| state.borrow().clone())
+}
+
+/// Import generation state from stable storage
+///
+/// The completed generation is republished so the TVL cache serves it
+/// straight after an upgrade.
+pub fn import_state(imported: TvlGenerationState) {
+    if let Some(done) = &imported.completed {
+        super::publish_tvl(&done.tvl, &done.report, done.completed_at);
+    }
+    GENERATION.with(|state| *state.borrow_mut() = imported);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::lp;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn lock_ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("lock-{:03}", i)).collect()
+    }
+
+    fn ok(lock_id: &str, symbol: &str, usd: f64) -> (String, Option<UserBalancesResult>) {
+        (lock_id.to_string(), Some(UserBalancesResult::Ok(vec![lp(symbol, usd, "ckUSDT", usd)])))
+    }
+
+    #[test]
+    fn test_generation_built_across_pages() {
+        let mut state = TvlGenerationState::default();
+        assert_eq!(start_build(&mut state, lock_ids(3), 1), Some(1));
+        // Only one build at a time
+        assert_eq!(start_build(&mut state, lock_ids(3), 2), None);
+
+        let build = state.building.as_mut().unwrap();
+        assert_eq!(next_page(build, 2), vec!["lock-000", "lock-001"]);
+        apply_page(build, vec![ok("lock-000", "ALEX", 100.0), ok("lock-001", "ALEX", 50.0)]);
+        assert_eq!(next_page(build, 2), vec!["lock-002"]);
+        assert!(finish_if_complete(&mut state, 3).is_none());
+
+        let build = state.building.as_mut().unwrap();
+        apply_page(build, vec![ok("lock-002", "ZERO", 25.0)]);
+        let done = finish_if_complete(&mut state, 4).unwrap().unwrap();
+
+        assert_eq!(done.generation, 1);
+        assert_eq!(done.completed_at, 4);
+        assert!(done.tvl.contains(&(TrackedToken::ALEX, 150.0)));
+        assert!(done.tvl.contains(&(TrackedToken::ZERO, 25.0)));
+        assert_eq!(done.report.canisters_total, 3);
+        assert_eq!(done.report.lp_entries_processed, 3);
+        assert!(state.building.is_none());
+        assert_eq!(state.completed, Some(done));
+    }
+
+    #[test]
+    fn test_unreliable_generation_keeps_previous() {
+        let mut state = TvlGenerationState::default();
+        start_build(&mut state, lock_ids(1), 1);
+        apply_page(state.building.as_mut().unwrap(), vec![ok("lock-000", "ALEX", 10.0)]);
+        let first = finish_if_complete(&mut state, 2).unwrap().unwrap();
+
+        assert_eq!(start_build(&mut state, lock_ids(2), 3), Some(2));
+        apply_page(state.building.as_mut().unwrap(), vec![
+            ("lock-000".to_string(), None),
+            ("lock-001".to_string(), Some(UserBalancesResult::Err("down".to_string()))),
+        ]);
+        assert!(finish_if_complete(&mut state, 4).unwrap().is_err());
+        assert_eq!(state.completed, Some(first));
+        assert!(state.building.is_none());
+    }
+
+    #[test]
+    fn test_status_reports_progress() {
+        let mut state = TvlGenerationState::default();
+        start_build(&mut state, lock_ids(4), 0);
+        apply_page(state.building.as_mut().unwrap(), vec![ok("lock-000", "ALEX", 1.0), ("lock-001".to_string(), None)]);
+
+        let status = status_of(&state, 10 * SECOND, 60);
+        let progress = status.building.unwrap();
+        assert_eq!((progress.lock_canisters_done, progress.lock_canisters_total), (2, 4));
+        assert_eq!(progress.queries_failed, 1);
+        assert_eq!(status.completed_generation, None);
+        assert_eq!(status.completed_age_seconds, None);
+    }
+
+    #[test]
+    fn test_generation_age_check() {
+        assert!(check_generation_age(None, 0, 60).is_err());
+        assert!(check_generation_age(Some(0), 60 * SECOND, 60).is_ok());
+        assert!(check_generation_age(Some(0), 61 * SECOND, 60).is_err());
+    }
+}
@@ -3,42 +3,69 @@
 //! Calculates total value locked across all kong_locker positions for tracked tokens.
 //! Used to determine target portfolio allocations.
 
+pub mod generation;
+
 use candid::Principal;
-use crate::infrastructure::{Result, IcpiError, KONGSWAP_BACKEND_ID};
+use crate::infrastructure::{Result, IcpiError, KONGSWAP_BACKEND_ID, TVL_CACHE_TTL_SECONDS, TVL_REFRESH_INTERVAL_SECONDS, TVL_GENERATION_STEP_SECONDS};
 use crate::types::TrackedToken;
+use crate::types::portfolio::{TokenTvl, TokenTvlContribution, TvlCalculationReport, TvlSummary};
 use crate::types::kongswap::{UserBalancesResult, UserBalancesReply};
 use std::cell::RefCell;
+use std::collections::HashMap;
 
 /// Cached TVL data
 /// Structure: (tvl_data, timestamp)
 /// Cache duration: 1 hour (3600 seconds)
 thread_local! {
     static TVL_CACHE: RefCell<Option<(Vec<(TrackedToken, f64)>, u64)>> = RefCell::new(None);
+
+    /// Last successfully computed TVL, kept across cache clears and expiry
+    /// so it can stand in during a Kong Locker outage
+    static LAST_GOOD_TVL: RefCell<Option<(Vec<(TrackedToken, f64)>, u64)>> = RefCell::new(None);
+
+    /// Report from the most recent successful TVL calculation
+    static LATEST_TVL_REPORT: RefCell<Option<TvlCalculationReport>> = RefCell::new(None);
 }
 
-const TVL_CACHE_DURATION_NANOS: u64 = 3_600_000_000_000; // 1 hour in nanoseconds
+/// TVL distribution together with where it came from
+#[derive(Clone, Debug)]
+pub struct TvlReading {
+    pub tvl: Vec<(TrackedToken, f64)>,
+    /// When the underlying TVL was computed
+    pub computed_at: u64,
+    /// True when served from the last-good snapshot because the live query failed
+    pub is_stale: bool,
+}
+
+const TVL_CACHE_DURATION_NANOS: u64 = TVL_CACHE_TTL_SECONDS * 1_000_000_000;
 
 /// Calculate TVL from Kong Locker positions (with 1-hour caching)
 ///
-/// Returns: Vec<(TrackedToken, usd_value)>
+/// Returns: (Vec<(TrackedToken, usd_value)>, report of the calculation)
 ///
-/// Uses cached data if available and less than 1 hour old, otherwise
-/// fetches fresh data from Kong Locker. This prevents excessive
-/// inter-canister calls since TVL changes slowly.
+/// Uses cached data if available and less than 1 hour old, otherwise the
+/// last completed TVL generation. Generations query the lockers a page at
+/// a time in the background, so no call here fans out to every locker.
 ///
 /// Cache invalidation: Automatic after 1 hour, or via clear_tvl_cache()
-pub async fn calculate_kong_locker_tvl() -> Result<Vec<(TrackedToken, f64)>> {
-    let now = ic_cdk::api::time();
+pub async fn calculate_kong_locker_tvl() -> Result<(Vec<(TrackedToken, f64)>, TvlCalculationReport)> {
+    let result = compute_kong_locker_tvl().await;
+    crate::infrastructure::logging::record_result(crate::infrastructure::logging::Subsystem::Tvl, &result);
+    result
+}
+
+async fn compute_kong_locker_tvl() -> Result<(Vec<(TrackedToken, f64)>, TvlCalculationReport)> {
+    let now = crate::infrastructure::runtime::time();
 
     // Check if cache is valid
     let cached_data = TVL_CACHE.with(|cache| {
         let cache_ref = cache.borrow();
         if let Some((data, timestamp)) = cache_ref.as_ref() {
-            if now - timestamp < TVL_CACHE_DURATION_NANOS {
-                ic_cdk::println!("📊 Using cached TVL data (age: {}s)", (now - timestamp) / 1_000_000_000);
+            if is_cache_fresh(*timestamp, now) {
+                ic_cdk::println!("📊 Using cached TVL data (age: {}s)", now.saturating_sub(*timestamp) / 1_000_000_000);
                 Some(data.clone())
             } else {
-                ic_cdk::println!("📊 TVL cache expired (age: {}s), refreshing...", (now - timestamp) / 1_000_000_000);
+                ic_cdk::println!("📊 TVL cache expired (age: {}s), refreshing...", now.saturating_sub(*timestamp) / 1_000_000_000);
                 None
             }
         } else {
@@ -47,21 +74,218 @@ pub async fn calculate_kong_locker_tvl() -> Result<Vec<(TrackedToken, f64)>> {
         }
     });
 
-    // Return cached data if valid
+    // Return cached data if valid, with the report it was computed with.
+    // Without one (never fabricate an empty report) serve the generation.
     if let Some(data) = cached_data {
-        return Ok(data);
+        match get_latest_tvl_report() {
+            Some(report) => return Ok((data, report)),
+            None => ic_cdk::println!("📊 No report for the cached TVL, serving the last generation"),
+        }
     }
 
-    // Fetch fresh data
-    let fresh_data = calculate_kong_locker_tvl_uncached().await?;
+    tvl_from_generation(generation::latest_completed().as_ref(), now)
+}
 
-    // Update cache
+/// TVL of the last completed generation, for a cache miss
+///
+/// Its age is judged by the callers from `report.computed_at`; the
+/// rebalancer refuses generations older than its configured maximum.
+fn tvl_from_generation(
+    completed: Option<&generation::CompletedTvlGeneration>,
+    now: u64,
+) -> Result<(Vec<(TrackedToken, f64)>, TvlCalculationReport)> {
+    match completed {
+        Some(done) => {
+            ic_cdk::println!("📊 Serving TVL generation {} (age: {}s)",
+                done.generation, now.saturating_sub(done.completed_at) / 1_000_000_000);
+            Ok((done.tvl.clone(), done.report.clone()))
+        }
+        None => Err(IcpiError::Other(
+            "No TVL generation has completed yet, refresh pending".to_string()
+        )),
+    }
+}
+
+/// Recompute TVL from Kong Locker and replace the cache
+///
+/// Used by the background refresh timer and the admin live-recompute endpoint.
+pub async fn refresh_kong_locker_tvl() -> Result<(Vec<(TrackedToken, f64)>, TvlCalculationReport)> {
+    crate::_2_CRITICAL_DATA::validation::validate_token_identities()?;
+    let (fresh_data, mut report) = calculate_kong_locker_tvl_uncached().await?;
+
+    // Stamp with completion time - the locker queries can span several rounds
+    let now = crate::infrastructure::runtime::time();
+    report.computed_at = now;
+    publish_tvl(&fresh_data, &report, now);
+    Ok((fresh_data, report))
+}
+
+/// Replace the cache, last-good snapshot and latest report with a fresh result
+fn publish_tvl(fresh_data: &[(TrackedToken, f64)], report: &TvlCalculationReport, now: u64) {
+    let fresh_data = fresh_data.to_vec();
     TVL_CACHE.with(|cache| {
         *cache.borrow_mut() = Some((fresh_data.clone(), now));
     });
+    LAST_GOOD_TVL.with(|last| {
+        *last.borrow_mut() = Some((fresh_data.clone(), now));
+    });
+    LATEST_TVL_REPORT.with(|latest| {
+        *latest.borrow_mut() = Some(report.clone());
+    });
 
     ic_cdk::println!("📊 TVL cache updated");
-    Ok(fresh_data)
+}
+
+/// Calculate TVL from Kong Locker without reading or updating the cache
+///
+/// For auditing the cache against live data.
+pub async fn compute_live_tvl() -> Result<Vec<(TrackedToken, f64)>> {
+    calculate_kong_locker_tvl_uncached().await.map(|(tvl, _)| tvl)
+}
+
+/// Raw TVL cache contents, regardless of freshness
+pub fn peek_tvl_cache() -> Option<(Vec<(TrackedToken, f64)>, u64)> {
+    TVL_CACHE.with(|cache| cache.borrow().clone())
+}
+
+/// Report from the most recent successful TVL calculation
+pub fn get_latest_tvl_report() -> Option<TvlCalculationReport> {
+    LATEST_TVL_REPORT.with(|latest| latest.borrow().clone())
+}
+
+/// Calculate TVL, falling back to the last-good snapshot if configured
+///
+/// Intended for display and rebalancing targets. When the live calculation
+/// fails (e.g. Kong Locker unreachable) and `tvl_outage_fallback` is enabled,
+/// the last successfully computed TVL is returned flagged as stale.
+/// Minting never goes through this path.
+pub async fn calculate_kong_locker_tvl_or_last_good() -> Result<TvlReading> {
+    let live = calculate_kong_locker_tvl().await.map(|(tvl, _)| {
+        let computed_at = TVL_CACHE.with(|cache| cache.borrow().as_ref().map(|(_, ts)| *ts))
+            .unwrap_or_else(crate::infrastructure::runtime::time);
+        (tvl, computed_at)
+    });
+    let allow_fallback = crate::infrastructure::config::get_config().tvl_outage_fallback();
+    LAST_GOOD_TVL.with(|last| select_tvl_reading(live, last.borrow().as_ref(), allow_fallback))
+}
+
+/// Choose between a live TVL result and the last-good snapshot
+fn select_tvl_reading(
+    live: Result<(Vec<(TrackedToken, f64)>, u64)>,
+    last_good: Option<&(Vec<(TrackedToken, f64)>, u64)>,
+    allow_fallback: bool,
+) -> Result<TvlReading> {
+    match live {
+        Ok((tvl, computed_at)) => Ok(TvlReading { tvl, computed_at, is_stale: false }),
+        Err(e) => match last_good {
+            Some((tvl, computed_at)) if allow_fallback => {
+                ic_cdk::println!(
+                    "⚠️ Live TVL unavailable ({}), using last-good TVL computed at {}",
+                    e, computed_at
+                );
+                Ok(TvlReading { tvl: tvl.clone(), computed_at: *computed_at, is_stale: true })
+            }
+            _ => Err(e),
+        },
+    }
+}
+
+fn is_cache_fresh(cached_at: u64, now: u64) -> bool {
+    now.saturating_sub(cached_at) < TVL_CACHE_DURATION_NANOS
+}
+
+/// Build a TVL summary with per-token percentages
+pub fn build_tvl_summary(tvl_data: &[(TrackedToken, f64)], timestamp: u64) -> TvlSummary {
+    let total_tvl: f64 = tvl_data.iter().map(|(_, v)| v).sum();
+
+    let tokens = tvl_data.iter().map(|(token, usd_value)| {
+        TokenTvl {
+            token: token.clone(),
+            tvl_usd: *usd_value,
+            percentage: if total_tvl > 0.0 { (usd_value / total_tvl) * 100.0 } else { 0.0 },
+        }
+    }).collect();
+
+    TvlSummary {
+        total_tvl_usd: total_tvl,
+        tokens,
+        timestamp,
+        is_stale: false,
+    }
+}
+
+fn summary_from_reading(reading: &TvlReading) -> TvlSummary {
+    TvlSummary {
+        is_stale: reading.is_stale,
+        ..build_tvl_summary(&reading.tvl, reading.computed_at)
+    }
+}
+
+fn summary_from_cache(
+    cache: Option<&(Vec<(TrackedToken, f64)>, u64)>,
+    now: u64,
+) -> Result<TvlSummary> {
+    match cache {
+        Some((data, cached_at)) if is_cache_fresh(*cached_at, now) => {
+            Ok(build_tvl_summary(data, *cached_at))
+        }
+        Some((_, cached_at)) => Err(IcpiError::Other(format!(
+            "TVL summary expired ({}s old), refresh pending",
+            now.saturating_sub(*cached_at) / 1_000_000_000
+        ))),
+        None => Err(IcpiError::Other("TVL summary not yet computed, refresh pending".to_string())),
+    }
+}
+
+/// TVL summary served from cache only (no inter-canister calls)
+///
+/// `timestamp` is when the underlying TVL was computed. Errors if the cache
+/// is empty or older than the TVL cache TTL, unless the outage fallback is
+/// enabled, in which case the last-good TVL is returned flagged as stale.
+pub fn get_cached_tvl_summary() -> Result<TvlSummary> {
+    let now = crate::infrastructure::runtime::time();
+    let cached = TVL_CACHE.with(|cache| summary_from_cache(cache.borrow().as_ref(), now));
+    match cached {
+        Ok(summary) => Ok(summary),
+        Err(e) => {
+            let allow_fallback = crate::infrastructure::config::get_config().tvl_outage_fallback();
+            LAST_GOOD_TVL.with(|last| select_tvl_reading(Err(e), last.borrow().as_ref(), allow_fallback))
+                .map(|reading| summary_from_reading(&reading))
+        }
+    }
+}
+
+/// TVL summary, recomputing only if the cache has expired
+pub async fn get_tvl_summary() -> Result<TvlSummary> {
+    let reading = calculate_kong_locker_tvl_or_last_good().await?;
+    Ok(summary_from_reading(&reading))
+}
+
+/// Start the background TVL refresh timers
+///
+/// A new TVL generation starts immediately, then every
+/// `TVL_REFRESH_INTERVAL_SECONDS` - well inside the cache TTL so cached
+/// reads never see an expired summary while Kong Locker is reachable. The
+/// generation is built a page of lock canisters per step timer tick.
+pub fn start_tvl_refresh_timer() {
+    ic_cdk::println!("🕐 Starting TVL refresh timer (every {}s)", TVL_REFRESH_INTERVAL_SECONDS);
+
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::TvlRefresh,
+        std::time::Duration::from_secs(TVL_REFRESH_INTERVAL_SECONDS),
+        true,
+        || {
+            ic_cdk::spawn(generation::begin_in_background());
+        }
+    );
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::TvlGeneration,
+        std::time::Duration::from_secs(TVL_GENERATION_STEP_SECONDS),
+        false,
+        || {
+            ic_cdk::spawn(generation::step_in_background());
+        }
+    );
 }
 
 /// Clear TVL cache (for testing or manual refresh)
@@ -74,7 +298,7 @@ pub fn clear_tvl_cache() {
 
 /// Calculate TVL from Kong Locker positions (no caching)
 ///
-/// Returns: Vec<(TrackedToken, usd_value)>
+/// Returns: (Vec<(TrackedToken, usd_value)>, report of the calculation)
 ///
 /// Process:
 /// 1. Get all lock canisters from kong_locker
@@ -86,149 +310,133 @@ pub fn clear_tvl_cache() {
 ///
 /// IMPORTANT: Use calculate_kong_locker_tvl() instead for normal operations
 /// to benefit from caching. This function is for internal use only.
-async fn calculate_kong_locker_tvl_uncached() -> Result<Vec<(TrackedToken, f64)>> {
+async fn calculate_kong_locker_tvl_uncached() -> Result<(Vec<(TrackedToken, f64)>, TvlCalculationReport)> {
     ic_cdk::println!("📊 Calculating Kong Locker TVL...");
 
     // Get all lock canisters - allow this to fail hard as it's a critical dependency
     let lock_canisters = super::locker::get_all_lock_canisters().await?;
     ic_cdk::println!("  Found {} lock canisters", lock_canisters.len());
 
+    // Initialize TVL accumulator for each tracked token
+    let mut acc = TvlAccumulator::default();
+    for token in TrackedToken::all() {
+        acc.tvl.insert(token.to_symbol().to_string(), 0.0);
+    }
+
     if lock_canisters.is_empty() {
         ic_cdk::println!("⚠️  No lock canisters found, returning zero TVL");
-        return Ok(vec![
-            (TrackedToken::ALEX, 0.0),
-            (TrackedToken::ZERO, 0.0),
-            (TrackedToken::KONG, 0.0),
-            (TrackedToken::BOB, 0.0),
-        ]);
+        return Ok(acc.finish(0, 0));
     }
 
-    // Initialize TVL accumulator for each tracked token
-    let tracked_tokens = TrackedToken::all();
-    let mut tvl_map: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
-    for token in tracked_tokens {
-        tvl_map.insert(token.to_symbol().to_string(), 0.0);
+    // Query balances for each lock canister in parallel
+    let lock_ids = lock_canisters.iter().map(|(_, lock_principal)| lock_principal.to_text()).collect();
+    let balance_results = query_lock_balances(lock_ids).await?;
+    let (successful_queries, failed_queries) = accumulate_query_results(&mut acc, balance_results);
+
+    let total_canisters = lock_canisters.len();
+    ic_cdk::println!(
+        "✅ Queried {}/{} lock canisters successfully ({} failed)",
+        successful_queries,
+        total_canisters,
+        failed_queries
+    );
+
+    let min_successful = crate::infrastructure::config::get_config().min_successful_lockers();
+    check_query_success(successful_queries, total_canisters as u64, min_successful)?;
+
+    // Convert to output format
+    let (tvl_vec, report) = acc.finish(total_canisters as u64, failed_queries);
+    ic_cdk::println!(
+        "  LP entries: {} processed, {} skipped",
+        report.lp_entries_processed,
+        report.lp_entries_skipped
+    );
+
+    // Log totals
+    let total_tvl: f64 = tvl_vec.iter().map(|(_, v)| v).sum();
+    ic_cdk::println!("📊 Total Kong Locker TVL: ${:.2}", total_tvl);
+    for (token, value) in &tvl_vec {
+        ic_cdk::println!("  {}: ${:.2}", token.to_symbol(), value);
     }
 
+    Ok((tvl_vec, report))
+}
+
+/// Query `user_balances` for each lock canister in parallel
+///
+/// A failed call yields `None` for that canister rather than failing the
+/// whole batch, so partial outages still produce a TVL.
+async fn query_lock_balances(lock_ids: Vec<String>) -> Result<Vec<(String, Option<UserBalancesResult>)>> {
     let kongswap = Principal::from_text(KONGSWAP_BACKEND_ID)
         .map_err(|e| IcpiError::Other(format!("Invalid kongswap canister ID: {}", e)))?;
 
-    // Query balances for each lock canister in parallel
-    // CRITICAL: We use Result<Option<...>> to allow partial failures
-    // If one canister query fails, we return Ok(None) and continue with others
-    let balance_futures: Vec<_> = lock_canisters.iter().map(|(_, lock_principal)| {
-        let lock_id = lock_principal.to_text();
+    let balance_futures: Vec<_> = lock_ids.into_iter().map(|lock_id| {
         async move {
-            match ic_cdk::call::<_, (UserBalancesResult,)>(
+            match crate::infrastructure::runtime::call::<_, (UserBalancesResult,)>(
                 kongswap,
                 "user_balances",
                 (lock_id.clone(),)
             ).await {
-                Ok((result,)) => Ok::<_, IcpiError>(Some((lock_id, result))),
+                Ok((result,)) => (lock_id, Some(result)),
                 Err(e) => {
-                    // Log error but don't fail entire TVL - return None for this canister
+                    // Log error but don't fail entire TVL - skip this canister
                     ic_cdk::println!("  ⚠️  Failed to query balances for {}: {:?}", lock_id, e.1);
-                    Ok(None) // Partial failure - skip this canister
+                    (lock_id, None)
                 }
             }
         }
     }).collect();
 
-    let balance_results = futures::future::join_all(balance_futures).await;
+    Ok(futures::future::join_all(balance_futures).await)
+}
 
-    // Process results - partial failures are Ok(None)
+/// Add each successful `user_balances` result to `acc`
+///
+/// Returns (successful, failed) query counts.
+fn accumulate_query_results(
+    acc: &mut TvlAccumulator,
+    results: Vec<(String, Option<UserBalancesResult>)>,
+) -> (u64, u64) {
     let mut successful_queries = 0;
     let mut failed_queries = 0;
-    for result in balance_results {
+    for (lock_id, result) in results {
         match result {
-            Ok(Some((lock_id, UserBalancesResult::Ok(balances)))) => {
+            Some(UserBalancesResult::Ok(balances)) => {
                 successful_queries += 1;
-
-                // Process each LP balance entry
-                for balance_entry in balances {
-                    let UserBalancesReply::LP(lp) = balance_entry;  // UserBalancesReply only has LP variant
-
-                    // CRITICAL: LP positions have two sides (e.g., ALEX/ckUSDT)
-                    // usd_balance = total USD value of both sides
-                    // usd_amount_0 = USD value of symbol_0 side only
-                    // usd_amount_1 = USD value of symbol_1 side only
-                    // We must use usd_amount_X to avoid double-counting!
-
-                    // Check symbol_0 and symbol_1 for tracked tokens
-                    let mut tracked_found = false;
-                    for token in tracked_tokens {
-                        let tracked_symbol = token.to_symbol();
-                        if lp.symbol_0 == tracked_symbol {
-                            // Add only this token's side of the LP
-                            *tvl_map.get_mut(tracked_symbol).unwrap() += lp.usd_amount_0;
-
-                            ic_cdk::println!(
-                                "  {} (side 0) in {}: ${:.2}",
-                                tracked_symbol,
-                                &lock_id[..8],
-                                lp.usd_amount_0
-                            );
-                            tracked_found = true;
-                        }
-                        if lp.symbol_1 == tracked_symbol {
-                            // Add only this token's side of the LP
-                            *tvl_map.get_mut(tracked_symbol).unwrap() += lp.usd_amount_1;
-
-                            ic_cdk::println!(
-                                "  {} (side 1) in {}: ${:.2}",
-                                tracked_symbol,
-                                &lock_id[..8],
-                                lp.usd_amount_1
-                            );
-                            tracked_found = true;
-                        }
-                    }
-
-                    // Defensive check: If both sides are tracked tokens (e.g., ALEX/ZERO pool),
-                    // we correctly count both sides. This is intentional and expected.
-                    if !tracked_found {
-                        // This LP position doesn't contain any tracked tokens - skip it
-                        ic_cdk::println!(
-                            "  Skipping {}/{} pool in {} (no tracked tokens)",
-                            lp.symbol_0,
-                            lp.symbol_1,
-                            &lock_id[..8]
-                        );
-                    }
-                }
+                accumulate_lp_balances(acc, balances, &lock_id);
             }
-            Ok(Some((lock_id, UserBalancesResult::Err(e)))) => {
-                ic_cdk::println!("  ⚠️  Kongswap error for {}: {}", &lock_id[..8], e);
+            Some(UserBalancesResult::Err(e)) => {
+                ic_cdk::println!("  ⚠️  Kongswap error for {}: {}", &lock_id[..lock_id.len().min(8)], e);
                 failed_queries += 1;
             }
-            Ok(None) => {
+            None => {
                 // Query failed (network error, timeout, etc.) - already logged
                 failed_queries += 1;
             }
-            Err(e) => {
-                // This should never happen with our new error handling, but handle defensively
-                ic_cdk::println!("  ⚠️  Unexpected error in TVL calculation: {:?}", e);
-                failed_queries += 1;
-            }
         }
     }
+    (successful_queries, failed_queries)
+}
 
-    let total_canisters = lock_canisters.len();
-    ic_cdk::println!(
-        "✅ Queried {}/{} lock canisters successfully ({} failed)",
-        successful_queries,
-        total_canisters,
-        failed_queries
-    );
-
-    // Validate that we have enough successful queries for reliable TVL
-    // If more than 50% of queries fail, the TVL data is unreliable
+/// Reject a TVL built from too few lock canisters to be reliable
+///
+/// More than half of the queries, and at least `min_successful` of them,
+/// must have succeeded.
+fn check_query_success(successful_queries: u64, total_canisters: u64, min_successful: u64) -> Result<()> {
     if total_canisters > 0 && successful_queries == 0 {
         return Err(IcpiError::Other(
             "TVL calculation failed: all lock canister queries failed".to_string()
         ));
     }
 
+    if successful_queries < min_successful {
+        return Err(IcpiError::Other(format!(
+            "TVL calculation unreliable: only {} lock canister queries succeeded, need at least {}",
+            successful_queries,
+            min_successful
+        )));
+    }
+
     let success_rate = successful_queries as f64 / total_canisters as f64;
     if success_rate < 0.5 {
         return Err(IcpiError::Other(format!(
@@ -238,31 +446,363 @@ async fn calculate_kong_locker_tvl_uncached() -> Result<Vec<(TrackedToken, f64)>
             success_rate * 100.0
         )));
     }
+    Ok(())
+}
 
-    // Convert to output format
-    let tvl_vec = vec![
-        (TrackedToken::ALEX, *tvl_map.get("ALEX").unwrap()),
-        (TrackedToken::ZERO, *tvl_map.get("ZERO").unwrap()),
-        (TrackedToken::KONG, *tvl_map.get("KONG").unwrap()),
-        (TrackedToken::BOB, *tvl_map.get("BOB").unwrap()),
-    ];
+/// Running totals for one TVL calculation, keyed by token symbol
+#[derive(Default)]
+struct TvlAccumulator {
+    tvl: HashMap<String, f64>,
+    lp_positions: HashMap<String, u64>,
+    lp_entries_processed: u64,
+    lp_entries_skipped: u64,
+}
 
-    // Log totals
-    let total_tvl: f64 = tvl_vec.iter().map(|(_, v)| v).sum();
-    ic_cdk::println!("📊 Total Kong Locker TVL: ${:.2}", total_tvl);
-    for (token, value) in &tvl_vec {
-        ic_cdk::println!("  {}: ${:.2}", token.to_symbol(), value);
+impl TvlAccumulator {
+    /// Per-token TVL in tracked-token order, plus the calculation report
+    fn finish(self, canisters_total: u64, canisters_failed: u64) -> (Vec<(TrackedToken, f64)>, TvlCalculationReport) {
+        let per_token_contributions: Vec<TokenTvlContribution> = TrackedToken::all().iter()
+            .map(|token| {
+                let symbol = token.to_symbol();
+                TokenTvlContribution {
+                    tvl_usd: self.tvl.get(symbol).copied().unwrap_or(0.0),
+                    lp_positions: self.lp_positions.get(symbol).copied().unwrap_or(0),
+                    token: token.clone(),
+                }
+            })
+            .collect();
+
+        let tvl_vec = per_token_contributions.iter()
+            .map(|c| (c.token.clone(), c.tvl_usd))
+            .collect();
+
+        (tvl_vec, TvlCalculationReport {
+            computed_at: 0,
+            canisters_total,
+            canisters_failed,
+            lp_entries_processed: self.lp_entries_processed,
+            lp_entries_skipped: self.lp_entries_skipped,
+            per_token_contributions,
+        })
     }
+}
+
+/// Add the tracked-token sides of a lock canister's LP positions to `acc`
+///
+/// Pools without a tracked token don't count towards the index and are
+/// tallied as skipped.
+fn accumulate_lp_balances(
+    acc: &mut TvlAccumulator,
+    balances: Vec<UserBalancesReply>,
+    lock_id: &str,
+) {
+    let lock_prefix = &lock_id[..lock_id.len().min(8)];
+    let config = crate::infrastructure::config::get_config();
+
+    for balance_entry in balances {
+        let UserBalancesReply::LP(lp) = balance_entry;
+
+        // CRITICAL: LP positions have two sides (e.g., ALEX/ckUSDT)
+        // usd_balance = total USD value of both sides
+        // usd_amount_0 = USD value of symbol_0 side only
+        // usd_amount_1 = USD value of symbol_1 side only
+        // We must use usd_amount_X to avoid double-counting!
+
+        // Check symbol_0 and symbol_1 for tracked tokens
+        let mut tracked_found = false;
+        for token in TrackedToken::all() {
+            let tracked_symbol = token.to_symbol();
+            // Lock canisters report Kongswap's symbols
+            let kong_symbol = config.kongswap_symbol(&token);
+            if lp.symbol_0 == kong_symbol {
+                // Add only this token's side of the LP
+                *acc.tvl.entry(tracked_symbol.to_string()).or_insert(0.0) += lp.usd_amount_0;
+                *acc.lp_positions.entry(tracked_symbol.to_string()).or_insert(0) += 1;
+
+                ic_cdk::println!(
+                    "  {} (side 0) in {}: ${:.2}",
+                    tracked_symbol,
+                    lock_prefix,
+                    lp.usd_amount_0
+                );
+                tracked_found = true;
+            }
+            if lp.symbol_1 == kong_symbol {
+                // Add only this token's side of the LP
+                *acc.tvl.entry(tracked_symbol.to_string()).or_insert(0.0) += lp.usd_amount_1;
+                *acc.lp_positions.entry(tracked_symbol.to_string()).or_insert(0) += 1;
+
+                ic_cdk::println!(
+                    "  {} (side 1) in {}: ${:.2}",
+                    tracked_symbol,
+                    lock_prefix,
+                    lp.usd_amount_1
+                );
+                tracked_found = true;
+            }
+        }
 
-    Ok(tvl_vec)
+        // Defensive check: If both sides are tracked tokens (e.g., ALEX/ZERO pool),
+        // we correctly count both sides. This is intentional and expected.
+        if tracked_found {
+            acc.lp_entries_processed += 1;
+        } else {
+            // This LP position doesn't contain any tracked tokens - skip it
+            acc.lp_entries_skipped += 1;
+            ic_cdk::println!(
+                "  Skipping {}/{} pool in {} (no tracked tokens)",
+                lp.symbol_0,
+                lp.symbol_1,
+                lock_prefix
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const SECOND: u64 = 1_000_000_000;
+
+    fn tvl() -> Vec<(TrackedToken, f64)> {
+        vec![
+            (TrackedToken::ALEX, 750.0),
+            (TrackedToken::ZERO, 250.0),
+            (TrackedToken::KONG, 0.0),
+            (TrackedToken::BOB, 0.0),
+        ]
+    }
+
     #[test]
     fn test_kongswap_canister_id() {
         assert!(Principal::from_text(KONGSWAP_BACKEND_ID).is_ok());
     }
+
+    #[test]
+    fn test_build_tvl_summary() {
+        let summary = build_tvl_summary(&tvl(), 42);
+        assert_eq!(summary.total_tvl_usd, 1000.0);
+        assert_eq!(summary.timestamp, 42);
+        assert_eq!(summary.tokens[0].percentage, 75.0);
+        assert_eq!(summary.tokens[1].percentage, 25.0);
+        assert_eq!(summary.tokens[2].percentage, 0.0);
+    }
+
+    #[test]
+    fn test_build_tvl_summary_zero_total() {
+        let zero: Vec<_> = tvl().into_iter().map(|(t, _)| (t, 0.0)).collect();
+        let summary = build_tvl_summary(&zero, 0);
+        assert!(summary.tokens.iter().all(|t| t.percentage == 0.0));
+    }
+
+    #[test]
+    fn test_cache_miss_serves_last_generation() {
+        assert!(tvl_from_generation(None, 5_000 * SECOND).is_err());
+
+        let done = generation::CompletedTvlGeneration {
+            generation: 7,
+            started_at: 900 * SECOND,
+            completed_at: 1_000 * SECOND,
+            tvl: tvl(),
+            report: TvlCalculationReport { computed_at: 1_000 * SECOND, ..Default::default() },
+        };
+        let (served, report) = tvl_from_generation(Some(&done), 5_000 * SECOND).unwrap();
+        assert_eq!(served, tvl());
+        // Still stamped with when it was computed, so its age shows
+        assert_eq!(report.computed_at, 1_000 * SECOND);
+    }
+
+    #[test]
+    fn test_cached_tvl_served_only_with_its_report() {
+        use futures::executor::block_on;
+        let _runtime = crate::infrastructure::runtime::mock::MockRuntime::install(1_000 * SECOND);
+
+        // A cached TVL without a report isn't served with an all-zero one
+        TVL_CACHE.with(|cache| *cache.borrow_mut() = Some((tvl(), 1_000 * SECOND)));
+        assert!(block_on(compute_kong_locker_tvl()).is_err());
+
+        let report = TvlCalculationReport { computed_at: 1_000 * SECOND, canisters_total: 3, ..Default::default() };
+        LATEST_TVL_REPORT.with(|latest| *latest.borrow_mut() = Some(report.clone()));
+        let (served, served_report) = block_on(compute_kong_locker_tvl()).unwrap();
+        assert_eq!(served, tvl());
+        assert_eq!(served_report, report);
+    }
+
+    #[test]
+    fn test_cached_summary_freshness() {
+        let cached_at = 1_000 * SECOND;
+        let cache = (tvl(), cached_at);
+
+        // Fresh right up to the TTL, keeps the computation timestamp
+        let summary = summary_from_cache(Some(&cache), cached_at).unwrap();
+        assert_eq!(summary.timestamp, cached_at);
+        let just_before = cached_at + TVL_CACHE_TTL_SECONDS * SECOND - 1;
+        assert!(summary_from_cache(Some(&cache), just_before).is_ok());
+
+        // Expired at the TTL
+        let at_ttl = cached_at + TVL_CACHE_TTL_SECONDS * SECOND;
+        assert!(summary_from_cache(Some(&cache), at_ttl).is_err());
+    }
+
+    #[test]
+    fn test_cached_summary_empty_cache() {
+        assert!(summary_from_cache(None, 0).is_err());
+    }
+
+    pub(super) fn lp(symbol_0: &str, usd_0: f64, symbol_1: &str, usd_1: f64) -> UserBalancesReply {
+        UserBalancesReply::LP(crate::types::kongswap::LPBalancesReply {
+            name: format!("{}_{} Liquidity Pool", symbol_0, symbol_1),
+            symbol: format!("{}_{}", symbol_0, symbol_1),
+            lp_token_id: 1,
+            balance: 1.0,
+            usd_balance: usd_0 + usd_1,
+            chain_0: "IC".to_string(),
+            symbol_0: symbol_0.to_string(),
+            address_0: String::new(),
+            amount_0: 1.0,
+            usd_amount_0: usd_0,
+            chain_1: "IC".to_string(),
+            symbol_1: symbol_1.to_string(),
+            address_1: String::new(),
+            amount_1: 1.0,
+            usd_amount_1: usd_1,
+            ts: 0,
+        })
+    }
+
+    #[test]
+    fn test_both_lp_sides_counted() {
+        let balances = vec![
+            lp("ALEX", 100.0, "ckUSDT", 100.0),
+            lp("ALEX", 10.0, "ZERO", 20.0),
+        ];
+
+        let mut acc = TvlAccumulator::default();
+        accumulate_lp_balances(&mut acc, balances, "lock-canister-id");
+
+        assert_eq!(acc.lp_entries_skipped, 0);
+        assert_eq!(acc.lp_entries_processed, 2);
+        // Only tracked sides count, each by its own USD amount
+        assert_eq!(acc.tvl.get("ALEX"), Some(&110.0));
+        assert_eq!(acc.tvl.get("ZERO"), Some(&20.0));
+        assert!(acc.tvl.get("ckUSDT").is_none());
+    }
+
+    #[test]
+    fn test_untracked_pools_are_skipped() {
+        let mut acc = TvlAccumulator::default();
+        accumulate_lp_balances(&mut acc, vec![lp("ICP", 50.0, "ckUSDT", 50.0)], "lock-canister-id");
+        assert_eq!(acc.lp_entries_skipped, 1);
+        assert_eq!(acc.lp_entries_processed, 0);
+        assert!(acc.tvl.is_empty());
+    }
+
+    #[test]
+    fn test_lp_sides_matched_by_kongswap_alias() {
+        crate::infrastructure::config::update_config(|c| {
+            c.kongswap_symbols = Some(vec![(TrackedToken::ALEX, "ALEX.ysy5f".to_string())]);
+        });
+        let mut acc = TvlAccumulator::default();
+        accumulate_lp_balances(&mut acc, vec![
+            lp("ALEX.ysy5f", 100.0, "ckUSDT", 100.0),
+            lp("ALEX", 10.0, "ckUSDT", 10.0),
+        ], "lock-canister-id");
+        crate::infrastructure::config::import_config(Default::default());
+
+        // Totals stay keyed by the index symbol
+        assert_eq!(acc.tvl.get("ALEX"), Some(&100.0));
+        assert_eq!(acc.lp_entries_skipped, 1);
+    }
+
+    #[test]
+    fn test_short_lock_id_does_not_panic() {
+        let mut acc = TvlAccumulator::default();
+        accumulate_lp_balances(&mut acc, vec![lp("KONG", 1.0, "BOB", 2.0)], "abc");
+        assert_eq!(acc.tvl.get("BOB"), Some(&2.0));
+    }
+
+    #[test]
+    fn test_calculation_report() {
+        let mut acc = TvlAccumulator::default();
+        accumulate_lp_balances(&mut acc, vec![
+            lp("ALEX", 100.0, "ckUSDT", 100.0),
+            lp("ALEX", 10.0, "ZERO", 20.0),
+        ], "lock-a");
+        accumulate_lp_balances(&mut acc, vec![lp("ICP", 1.0, "ckUSDT", 1.0)], "lock-b");
+
+        let (tvl_vec, report) = acc.finish(3, 1);
+        assert_eq!(report.canisters_total, 3);
+        assert_eq!(report.canisters_failed, 1);
+        assert_eq!(report.lp_entries_processed, 2);
+        assert_eq!(report.lp_entries_skipped, 1);
+
+        // Every tracked token is reported, in order, even with no positions
+        assert_eq!(tvl_vec.len(), TrackedToken::all().len());
+        assert_eq!(tvl_vec[0], (TrackedToken::ALEX, 110.0));
+        let alex = &report.per_token_contributions[0];
+        assert_eq!((alex.lp_positions, alex.tvl_usd), (2, 110.0));
+        let zero = &report.per_token_contributions[1];
+        assert_eq!((zero.lp_positions, zero.tvl_usd), (1, 20.0));
+        let bob = &report.per_token_contributions[3];
+        assert_eq!((bob.lp_positions, bob.tvl_usd), (0, 0.0));
+    }
+
+    fn locker_down() -> Result<(Vec<(TrackedToken, f64)>, u64)> {
+        Err(IcpiError::Other("Kong Locker unreachable".to_string()))
+    }
+
+    #[test]
+    fn test_live_tvl_is_not_stale() {
+        let last_good = (vec![(TrackedToken::ALEX, 1.0)], 5 * SECOND);
+        let reading = select_tvl_reading(Ok((tvl(), 10 * SECOND)), Some(&last_good), true).unwrap();
+        assert!(!reading.is_stale);
+        assert_eq!(reading.computed_at, 10 * SECOND);
+        assert_eq!(reading.tvl, tvl());
+    }
+
+    #[test]
+    fn test_outage_falls_back_to_last_good() {
+        let last_good = (tvl(), 5 * SECOND);
+        let reading = select_tvl_reading(locker_down(), Some(&last_good), true).unwrap();
+        assert!(reading.is_stale);
+        assert_eq!(reading.computed_at, 5 * SECOND);
+        assert_eq!(reading.tvl, tvl());
+
+        let summary = summary_from_reading(&reading);
+        assert!(summary.is_stale);
+        assert_eq!(summary.timestamp, 5 * SECOND);
+        assert_eq!(summary.total_tvl_usd, 1000.0);
+    }
+
+    #[test]
+    fn test_outage_without_fallback_fails() {
+        let last_good = (tvl(), 5 * SECOND);
+        assert!(select_tvl_reading(locker_down(), Some(&last_good), false).is_err());
+    }
+
+    #[test]
+    fn test_outage_without_last_good_fails() {
+        assert!(select_tvl_reading(locker_down(), None, true).is_err());
+    }
+
+    #[test]
+    fn test_min_successful_lockers() {
+        // 2 of 3 clears the success rate but not a minimum of 3
+        assert!(check_query_success(2, 3, 1).is_ok());
+        match check_query_success(2, 3, 3) {
+            Err(IcpiError::Other(msg)) => assert!(msg.contains("need at least 3")),
+            other => panic!("Expected an unreliable TVL error, got {:?}", other),
+        }
+
+        assert!(check_query_success(3, 3, 3).is_ok());
+        assert!(check_query_success(4, 6, 3).is_ok());
+        // The success rate still applies above the minimum
+        assert!(check_query_success(3, 7, 3).is_err());
+    }
+
+    #[test]
+    fn test_refresh_interval_within_ttl() {
+        assert!(TVL_REFRESH_INTERVAL_SECONDS < TVL_CACHE_TTL_SECONDS);
+    }
 }
@@ -13,14 +13,24 @@
 //! - 15-minute expiry balances security and network congestion handling
 //! - Each approval is single-use per swap
 //! - Amount exactly matches swap requirement
+//! - Swaps check the approval's `expires_at` before calling Kongswap and
+//!   re-approve if too little time remains
 
-use candid::{Nat, Principal};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use crate::types::{TrackedToken, icrc::{Account, ApproveArgs, ApproveResult}};
-use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BACKEND_ID, BURN_FEE_BUFFER};
+use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BACKEND_ID, ledger_fees};
 
 /// Token approval expiry time in nanoseconds (15 minutes)
 /// Increased from 5 minutes to handle potential network congestion
-const APPROVAL_EXPIRY_NANOS: u64 = 900_000_000_000;
+pub const APPROVAL_EXPIRY_NANOS: u64 = 900_000_000_000;
+
+/// A granted approval and the deadline for using it
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct SwapApproval {
+    pub block_index: Nat,
+    /// Ledger time (ns) after which the allowance is void
+    pub expires_at: u64,
+}
 
 /// Approve Kongswap to spend our tokens for a swap
 ///
@@ -29,19 +39,19 @@ const APPROVAL_EXPIRY_NANOS: u64 = 900_000_000_000;
 /// - `amount`: Exact amount Kongswap can spend
 ///
 /// ## Returns
-/// - `Ok(Nat)`: Approval block index on success
+/// - `Ok(SwapApproval)`: Approval block index and expiry on success
 /// - `Err`: If approval fails (insufficient balance, network error, etc.)
 ///
 /// ## Process
 /// 1. Get token canister Principal
 /// 2. Get Kongswap backend Principal
-/// 3. Call `icrc2_approve` with 5-minute expiry
-/// 4. Return block index for tracking
+/// 3. Call `icrc2_approve` with 15-minute expiry
+/// 4. Return block index and expiry for tracking
 ///
 /// ## Example
 /// ```rust
 /// // Approve Kongswap to spend 1 ALEX (e8 decimals)
-/// let approval_block = approve_token_for_swap(
+/// let approval = approve_token_for_swap(
 ///     &TrackedToken::ALEX,
 ///     Nat::from(100_000_000u64)
 /// ).await?;
@@ -49,7 +59,7 @@ const APPROVAL_EXPIRY_NANOS: u64 = 900_000_000_000;
 pub async fn approve_token_for_swap(
     token: &TrackedToken,
     amount: Nat,
-) -> Result<Nat> {
+) -> Result<SwapApproval> {
     // Get token canister ID
     let token_canister = token.get_canister_id()
         .map_err(|e| IcpiError::Trading(TradingError::InvalidTokenCanister {
@@ -67,18 +77,21 @@ pub async fn approve_token_for_swap(
 
     // CRITICAL: Approve amount + transfer fee because Kongswap's transfer_from
     // deducts the fee from allowance before checking sufficiency
-    let approval_amount = amount.clone() + Nat::from(BURN_FEE_BUFFER);
+    let fee = ledger_fees::transfer_fee(token.to_symbol());
+    let approval_amount = amount.clone() + Nat::from(fee);
 
-    ic_cdk::println!(
+    crate::log_op!(
         "📝 Approving {} {} for Kongswap (trade: {}, fee: {}, canister: {})",
         approval_amount,
         token.to_symbol(),
         amount,
-        BURN_FEE_BUFFER,
+        fee,
         KONGSWAP_BACKEND_ID
     );
 
     // Prepare approval args
+    let now = crate::infrastructure::runtime::time();
+    let expires_at = now + APPROVAL_EXPIRY_NANOS;
     let approve_args = ApproveArgs {
         from_subaccount: None,
         spender: Account {
@@ -87,21 +100,21 @@ pub async fn approve_token_for_swap(
         },
         amount: approval_amount,
         expected_allowance: None,
-        expires_at: Some(ic_cdk::api::time() + APPROVAL_EXPIRY_NANOS),
+        expires_at: Some(expires_at),
         fee: None, // Use default
         memo: Some(b"ICPI rebalancing".to_vec()),
-        created_at_time: Some(ic_cdk::api::time()),
+        created_at_time: Some(now),
     };
 
     // Call icrc2_approve
-    let (result,): (ApproveResult,) = ic_cdk::call(
+    let (result,): (ApproveResult,) = crate::infrastructure::runtime::call(
         token_canister,
         "icrc2_approve",
         (approve_args,)
     )
     .await
     .map_err(|(code, msg)| {
-        ic_cdk::println!("❌ Approval call failed: {:?} - {}", code, msg);
+        crate::log_op!("❌ Approval call failed: {:?} - {}", code, msg);
         IcpiError::Trading(TradingError::ApprovalFailed {
             token: token.to_symbol().to_string(),
             amount: amount.to_string(),
@@ -112,16 +125,16 @@ pub async fn approve_token_for_swap(
     // Handle approval result
     match result {
         ApproveResult::Ok(block_index) => {
-            ic_cdk::println!(
+            crate::log_op!(
                 "✅ Approval successful: {} {} (block: {})",
                 amount,
                 token.to_symbol(),
                 block_index
             );
-            Ok(block_index)
+            Ok(SwapApproval { block_index, expires_at })
         }
         ApproveResult::Err(err) => {
-            ic_cdk::println!("❌ Approval rejected: {:?}", err);
+            crate::log_op!("❌ Approval rejected: {:?}", err);
             Err(IcpiError::Trading(TradingError::ApprovalFailed {
                 token: token.to_symbol().to_string(),
                 amount: amount.to_string(),
@@ -131,6 +144,16 @@ pub async fn approve_token_for_swap(
     }
 }
 
+/// Whole seconds left before `deadline`, zero once it has passed
+pub fn seconds_remaining(deadline: u64, now: u64) -> u64 {
+    deadline.saturating_sub(now) / 1_000_000_000
+}
+
+/// Whether an approval expiring at `deadline` should be renewed before use
+pub fn needs_renewal(deadline: u64, now: u64, min_remaining_seconds: u64) -> bool {
+    now >= deadline || seconds_remaining(deadline, now) < min_remaining_seconds
+}
+
 /// Check current allowance for Kongswap (for debugging)
 ///
 /// Not used in production flow, but useful for diagnostics
@@ -151,7 +174,7 @@ pub async fn check_kongswap_allowance(
         }))?;
 
     let backend_account = Account {
-        owner: ic_cdk::id(),
+        owner: crate::infrastructure::runtime::canister_id(),
         subaccount: None,
     };
 
@@ -160,7 +183,7 @@ pub async fn check_kongswap_allowance(
         subaccount: None,
     };
 
-    let (allowance,): (Nat,) = ic_cdk::call(
+    let (allowance,): (Nat,) = crate::infrastructure::runtime::call(
         token_canister,
         "icrc2_allowance",
         (backend_account, spender_account)
@@ -175,3 +198,39 @@ pub async fn check_kongswap_allowance(
 
     Ok(allowance)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_seconds_remaining() {
+        let granted_at = 1_700_000_000 * SECOND;
+        let deadline = granted_at + APPROVAL_EXPIRY_NANOS;
+
+        assert_eq!(seconds_remaining(deadline, granted_at), 900);
+        assert_eq!(seconds_remaining(deadline, deadline - SECOND / 2), 0);
+        assert_eq!(seconds_remaining(deadline, deadline), 0);
+        assert_eq!(seconds_remaining(deadline, deadline + 60 * SECOND), 0);
+    }
+
+    #[test]
+    fn test_needs_renewal_near_expiry() {
+        let granted_at = 1_700_000_000 * SECOND;
+        let deadline = granted_at + APPROVAL_EXPIRY_NANOS;
+
+        // Fresh approval, and a stalled quote that still leaves enough time
+        assert!(!needs_renewal(deadline, granted_at, 120));
+        assert!(!needs_renewal(deadline, deadline - 120 * SECOND, 120));
+
+        // Within the margin, or already expired
+        assert!(needs_renewal(deadline, deadline - 119 * SECOND, 120));
+        assert!(needs_renewal(deadline, deadline + SECOND, 120));
+
+        // Zero margin only renews once expired
+        assert!(!needs_renewal(deadline, deadline - SECOND, 0));
+        assert!(needs_renewal(deadline, deadline, 0));
+    }
+}
@@ -0,0 +1,103 @@
+//! # Pool Depth Precheck
+//!
+//! Probes Kongswap `swap_amounts` with the exact intended trade before any
+//! approval is spent. If the quoted slippage already exceeds the allowed
+//! maximum (minus headroom), the swap would almost certainly fail its
+//! slippage check, so the trade is skipped instead.
+
+use candid::Nat;
+use crate::types::TrackedToken;
+use crate::types::kongswap::SwapAmountsReply;
+use crate::infrastructure::{Result, POOL_DEPTH_SLIPPAGE_HEADROOM};
+use super::slippage::Slippage;
+
+/// Outcome of a pool depth check
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepthCheck {
+    Sufficient { expected_slippage: f64 },
+    Insufficient { expected_slippage: f64, max_allowed: f64 },
+}
+
+/// Decide whether a probed trade fits within slippage limits
+///
+/// Kongswap reports slippage in percent, so `max_slippage` is compared in
+/// percent too. A zero receive amount means the pool can't fill the trade at all.
+pub fn assess_pool_depth(probe: &SwapAmountsReply, max_slippage: Slippage) -> DepthCheck {
+    let max_allowed = max_slippage.as_percent() * POOL_DEPTH_SLIPPAGE_HEADROOM;
+    let expected_slippage = probe.slippage;
+
+    if probe.receive_amount == Nat::from(0u64)
+        || !expected_slippage.is_finite()
+        || expected_slippage > max_allowed
+    {
+        DepthCheck::Insufficient { expected_slippage, max_allowed }
+    } else {
+        DepthCheck::Sufficient { expected_slippage }
+    }
+}
+
+/// Probe the pool for the intended trade and assess its depth
+pub async fn check_pool_depth(
+    pay_token: &TrackedToken,
+    pay_amount: Nat,
+    receive_token: &TrackedToken,
+    max_slippage: Slippage,
+) -> Result<DepthCheck> {
+    let probe = super::swaps::query_swap_amounts_reply(
+        pay_token.to_symbol(),
+        pay_amount,
+        receive_token.to_symbol(),
+    ).await?;
+
+    Ok(assess_pool_depth(&probe, max_slippage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn five_pct() -> Slippage {
+        Slippage::from_percent(5.0).unwrap()
+    }
+
+    fn sufficient(check: DepthCheck) -> bool {
+        matches!(check, DepthCheck::Sufficient { .. })
+    }
+
+    fn probe(receive_amount: u64, slippage: f64) -> SwapAmountsReply {
+        SwapAmountsReply {
+            pay_symbol: "ckUSDT".to_string(),
+            receive_symbol: "ALEX".to_string(),
+            pay_amount: Nat::from(10_000_000u64),
+            receive_amount: Nat::from(receive_amount),
+            mid_price: 1.0,
+            price: 1.0,
+            slippage,
+        }
+    }
+
+    #[test]
+    fn test_deep_pool_is_sufficient() {
+        let check = assess_pool_depth(&probe(1_000_000, 0.3), five_pct());
+        assert_eq!(check, DepthCheck::Sufficient { expected_slippage: 0.3 });
+    }
+
+    #[test]
+    fn test_shallow_pool_is_insufficient() {
+        assert!(!sufficient(assess_pool_depth(&probe(1_000_000, 7.5), five_pct())));
+    }
+
+    #[test]
+    fn test_headroom_applied() {
+        // Just over the headroom-adjusted limit is rejected even though it's under the max
+        let max_allowed = 5.0 * POOL_DEPTH_SLIPPAGE_HEADROOM;
+        assert!(!sufficient(assess_pool_depth(&probe(1_000_000, max_allowed + 0.01), five_pct())));
+        assert!(sufficient(assess_pool_depth(&probe(1_000_000, max_allowed), five_pct())));
+    }
+
+    #[test]
+    fn test_empty_pool_is_insufficient() {
+        assert!(!sufficient(assess_pool_depth(&probe(0, 0.0), five_pct())));
+        assert!(!sufficient(assess_pool_depth(&probe(1_000_000, f64::NAN), five_pct())));
+    }
+}
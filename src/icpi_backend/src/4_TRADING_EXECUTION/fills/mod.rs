@@ -0,0 +1,202 @@
+//! Swap fill reconciliation
+//!
+//! Swaps are sent with a fixed pay amount, but the `SwapReply` reports the
+//! pay amount Kongswap actually consumed. A reply consuming less (partial
+//! fill) or more than requested is detected here, the backend's ckUSDT
+//! accounting is booked against the consumed amount, and the discrepancy is
+//! logged. The configured `PartialFillPolicy` decides whether a partial
+//! fill still counts as a successful swap.
+
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::cell::RefCell;
+use crate::types::{TrackedToken, kongswap::SwapReply};
+use crate::infrastructure::{Result, IcpiError, errors::TradingError, ledger_fees};
+
+const MAX_FILL_LOG: usize = 100;
+
+/// What to do when a swap consumes less than the requested pay amount
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PartialFillPolicy {
+    /// Book the consumed amount and treat the swap as successful
+    #[default]
+    Accept,
+    /// Book the consumed amount but fail the swap so the cycle is recorded
+    /// as failed (the trade itself has already executed)
+    Fail,
+}
+
+/// How the consumed pay amount compares with the request
+#[derive(Clone, Debug, PartialEq)]
+pub enum FillCheck {
+    Full,
+    Partial { unspent: Nat },
+    Overfill { excess: Nat },
+}
+
+/// A swap whose consumed pay amount differed from the request
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct FillDiscrepancy {
+    pub timestamp: u64,
+    pub pay_token: TrackedToken,
+    pub receive_token: TrackedToken,
+    pub requested_pay: Nat,
+    pub consumed_pay: Nat,
+    pub received: Nat,
+}
+
+thread_local! {
+    static FILL_LOG: RefCell<Vec<FillDiscrepancy>> = RefCell::new(Vec::new());
+}
+
+/// Compare the pay amount Kongswap consumed with what was requested
+pub fn check_fill(requested_pay: &Nat, consumed_pay: &Nat) -> FillCheck {
+    if consumed_pay < requested_pay {
+        FillCheck::Partial { unspent: requested_pay.clone() - consumed_pay.clone() }
+    } else if consumed_pay > requested_pay {
+        FillCheck::Overfill { excess: consumed_pay.clone() - requested_pay.clone() }
+    } else {
+        FillCheck::Full
+    }
+}
+
+/// Book a completed swap against the backend's accounting
+///
+/// ckUSDT reserve accounting uses the consumed pay amount (plus the
+/// approve and transfer_from fees), not the requested one. Any discrepancy
+/// is logged at `now`; a partial fill under `PartialFillPolicy::Fail` is
+/// returned as an error after booking.
+pub fn reconcile_swap(
+    pay_token: &TrackedToken,
+    receive_token: &TrackedToken,
+    requested_pay: &Nat,
+    reply: &SwapReply,
+    policy: PartialFillPolicy,
+    now: u64,
+) -> Result<()> {
+    let consumed_pay = &reply.pay_amount;
+
+    if *pay_token == TrackedToken::ckUSDT {
+        crate::_2_CRITICAL_DATA::ckusdt_reserves::record_outflow(
+            &(consumed_pay.clone() + Nat::from(2 * ledger_fees::ckusdt_fee()))
+        );
+    }
+    if *receive_token == TrackedToken::ckUSDT {
+        crate::_2_CRITICAL_DATA::ckusdt_reserves::record_inflow(&reply.receive_amount);
+    }
+
+    let check = check_fill(requested_pay, consumed_pay);
+    if check == FillCheck::Full {
+        return Ok(());
+    }
+
+    crate::log_op!(
+        "⚠️ Swap fill discrepancy {} → {}: requested {} paid, consumed {} ({:?})",
+        pay_token.to_symbol(), receive_token.to_symbol(), requested_pay, consumed_pay, check
+    );
+    push_discrepancy(FillDiscrepancy {
+        timestamp: now,
+        pay_token: pay_token.clone(),
+        receive_token: receive_token.clone(),
+        requested_pay: requested_pay.clone(),
+        consumed_pay: consumed_pay.clone(),
+        received: reply.receive_amount.clone(),
+    });
+
+    match check {
+        FillCheck::Partial { .. } if policy == PartialFillPolicy::Fail => {
+            Err(IcpiError::Trading(TradingError::PartialFill {
+                requested: requested_pay.clone(),
+                consumed: consumed_pay.clone(),
+            }))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn push_discrepancy(entry: FillDiscrepancy) {
+    FILL_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        log.push(entry);
+        let len = log.len();
+        if len > MAX_FILL_LOG {
+            log.drain(0..(len - MAX_FILL_LOG));
+        }
+    });
+}
+
+/// Swaps whose consumed pay amount differed from the request
+pub fn get_fill_discrepancies() -> Vec<FillDiscrepancy> {
+    FILL_LOG.with(|log| log.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::CKUSDT_TRANSFER_FEE;
+
+    fn reply(pay_amount: u64, receive_amount: u64) -> SwapReply {
+        SwapReply {
+            tx_id: 1,
+            request_id: 1,
+            status: "Success".to_string(),
+            pay_chain: "IC".to_string(),
+            pay_address: String::new(),
+            pay_symbol: "ckUSDT".to_string(),
+            pay_amount: Nat::from(pay_amount),
+            receive_chain: "IC".to_string(),
+            receive_address: String::new(),
+            receive_symbol: "ALEX".to_string(),
+            receive_amount: Nat::from(receive_amount),
+            mid_price: 1.0,
+            price: 1.0,
+            slippage: 0.0,
+            txs: Vec::new(),
+            transfer_ids: Vec::new(),
+            claim_ids: Vec::new(),
+            ts: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_fill() {
+        assert_eq!(check_fill(&Nat::from(100u64), &Nat::from(100u64)), FillCheck::Full);
+        assert_eq!(check_fill(&Nat::from(100u64), &Nat::from(60u64)), FillCheck::Partial { unspent: Nat::from(40u64) });
+        assert_eq!(check_fill(&Nat::from(100u64), &Nat::from(101u64)), FillCheck::Overfill { excess: Nat::from(1u64) });
+    }
+
+    #[test]
+    fn test_partial_fill_reconciles_consumed_amount() {
+        use crate::_2_CRITICAL_DATA::ckusdt_reserves;
+
+        // Baseline: backend expects to hold $50
+        ckusdt_reserves::reconcile_balance(&Nat::from(50_000_000u64), &Nat::from(1_000_000u64), 0);
+
+        // Asked to pay $10, Kongswap only consumed $6
+        let partial = reply(6_000_000, 5_000_000_000);
+        let requested = Nat::from(10_000_000u64);
+        reconcile_swap(&TrackedToken::ckUSDT, &TrackedToken::ALEX, &requested, &partial, PartialFillPolicy::Accept, 1).unwrap();
+
+        // Expected balance dropped by $6 + two ledger fees, not by $10
+        let expected = ckusdt_reserves::get_state().expected.unwrap();
+        assert_eq!(expected, Nat::from(44_000_000u64 - 2 * CKUSDT_TRANSFER_FEE));
+
+        let log = get_fill_discrepancies();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].requested_pay, requested);
+        assert_eq!(log[0].consumed_pay, Nat::from(6_000_000u64));
+        assert_eq!(log[0].timestamp, 1);
+
+        // Fail policy books the same way but surfaces an error
+        let result = reconcile_swap(&TrackedToken::ckUSDT, &TrackedToken::ALEX, &requested, &partial, PartialFillPolicy::Fail, 2);
+        assert!(matches!(result, Err(IcpiError::Trading(TradingError::PartialFill { .. }))));
+        assert_eq!(
+            ckusdt_reserves::get_state().expected.unwrap(),
+            Nat::from(38_000_000u64 - 4 * CKUSDT_TRANSFER_FEE)
+        );
+
+        // Full fills leave no discrepancy
+        reconcile_swap(&TrackedToken::ckUSDT, &TrackedToken::ALEX, &requested, &reply(10_000_000, 1), PartialFillPolicy::Fail, 3).unwrap();
+        assert_eq!(get_fill_discrepancies().len(), 2);
+    }
+}
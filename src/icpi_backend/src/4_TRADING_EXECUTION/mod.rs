@@ -6,17 +6,19 @@
 //! - **approvals/**: ICRC-2 approval flow for secure token spending
 //! - **swaps/**: Kongswap swap execution (always via ckUSDT intermediary)
 //! - **slippage/**: Slippage protection calculations and validation
+//! - **depth/**: Pre-trade pool depth probe (skip trades the pool can't absorb)
 //!
 //! ## Key Constraints
 //! - **ICRC-2 Only**: All swaps use approval flow (`pay_tx_id: None`)
 //! - **ckUSDT Intermediary**: Every swap routes through ckUSDT
 //! - **Sequential Execution**: No parallel swaps (Kongswap limitation)
-//! - **Slippage Protected**: Default 2% max slippage enforced
+//! - **Slippage Protected**: Tolerance passed as `Slippage` (basis points), max 10%
 //!
 //! ## Usage Example
 //!
 //! ```rust,no_run
 //! use crate::_4_TRADING_EXECUTION;
+//! use crate::_4_TRADING_EXECUTION::slippage::Slippage;
 //! use crate::types::TrackedToken;
 //! use candid::Nat;
 //!
@@ -25,7 +27,7 @@
 //!     &TrackedToken::ckUSDT,
 //!     Nat::from(1_000_000u64),
 //!     &TrackedToken::ALEX,
-//!     0.02 // 2% max slippage
+//!     Slippage::from_percent(2.0)?
 //! ).await?;
 //!
 //! println!("Received {} ALEX", swap_result.receive_amount);
@@ -48,3 +50,5 @@
 pub mod approvals;
 pub mod swaps;
 pub mod slippage;
+pub mod depth;
+pub mod fills;
@@ -4,6 +4,7 @@
 //! to ensure trades execute within acceptable price ranges.
 //!
 //! ## Key Functions
+//! - `Slippage`: Tolerance in basis points, with explicit percent/decimal conversions
 //! - `calculate_min_receive`: Get minimum acceptable output amount
 //! - `validate_swap_result`: Verify actual slippage is within limits
 //!
@@ -13,26 +14,85 @@
 //! - Actual slippage must not exceed max_slippage parameter
 
 use candid::Nat;
-use crate::infrastructure::{Result, IcpiError, errors::TradingError};
-use num_traits::ToPrimitive;
+use crate::infrastructure::{Result, IcpiError, errors::TradingError, MAX_SLIPPAGE_BPS, bps_of, apply_bps};
+
+/// Slippage tolerance, stored in basis points (100 bps = 1%)
+///
+/// Kongswap takes percent (2.0 = 2%), ratios elsewhere are decimals
+/// (0.02 = 2%). Constructing through `from_percent` or `from_decimal` makes
+/// the unit explicit at every call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Slippage {
+    bps: u32,
+}
+
+/// Tolerance for rebalancing, consolidation and exit swaps
+pub const MAX_TRADE_SLIPPAGE: Slippage = Slippage { bps: MAX_SLIPPAGE_BPS };
+
+const _: () = assert!(MAX_SLIPPAGE_BPS <= Slippage::MAX_BPS);
+
+impl Slippage {
+    /// Highest tolerance a swap accepts (10%)
+    pub const MAX_BPS: u32 = 1_000;
+
+    pub fn from_bps(bps: u32) -> Result<Self> {
+        if bps > Self::MAX_BPS {
+            return Err(Self::out_of_range(format!("{:.2}%", bps as f64 / 100.0)));
+        }
+        Ok(Slippage { bps })
+    }
+
+    /// From percent form (5.0 = 5%), rounded to the nearest basis point
+    pub fn from_percent(percent: f64) -> Result<Self> {
+        if !percent.is_finite() || percent < 0.0 || percent * 100.0 > Self::MAX_BPS as f64 {
+            return Err(Self::out_of_range(format!("{}%", percent)));
+        }
+        Self::from_bps((percent * 100.0).round() as u32)
+    }
+
+    /// From decimal form (0.05 = 5%), rounded to the nearest basis point
+    pub fn from_decimal(decimal: f64) -> Result<Self> {
+        if !decimal.is_finite() || decimal < 0.0 || decimal * 10_000.0 > Self::MAX_BPS as f64 {
+            return Err(Self::out_of_range(format!("{} (decimal)", decimal)));
+        }
+        Self::from_bps((decimal * 10_000.0).round() as u32)
+    }
+
+    pub fn bps(self) -> u32 {
+        self.bps
+    }
+
+    /// Percent form, as Kongswap's `max_slippage` expects
+    pub fn as_percent(self) -> f64 {
+        self.bps as f64 / 100.0
+    }
+
+    pub fn as_decimal(self) -> f64 {
+        self.bps as f64 / 10_000.0
+    }
+
+    fn out_of_range(got: String) -> IcpiError {
+        IcpiError::Trading(TradingError::InvalidSwapAmount {
+            reason: format!("Max slippage must be between 0% and 10%, got {}", got),
+        })
+    }
+}
 
 /// Calculate minimum acceptable receive amount based on slippage tolerance
 ///
 /// ## Example
 /// - Expected: 100 tokens
-/// - Max slippage: 2.0 (2.0%)
+/// - Max slippage: 200 bps (2%)
 /// - Result: 98 tokens (will accept down to 98)
 ///
 /// ## Edge Cases
 /// - Returns 0 if expected amount is 0 (will be caught by validation)
-/// - Handles Nat conversion safely
+/// - Rounds down, computed exactly in Nat
 pub fn calculate_min_receive(
     expected_amount: &Nat,
-    max_slippage: f64,
+    max_slippage: Slippage,
 ) -> Nat {
-    let expected_f64 = expected_amount.0.to_u64().unwrap_or(0) as f64;
-    let min_f64 = expected_f64 * (1.0 - max_slippage / 100.0);
-    Nat::from(min_f64 as u64)
+    expected_amount.clone() * Nat::from(10_000 - max_slippage.bps()) / Nat::from(10_000u32)
 }
 
 /// Validate that swap result meets slippage requirements
@@ -40,7 +100,7 @@ pub fn calculate_min_receive(
 /// ## Parameters
 /// - `expected`: Amount we expected to receive based on pre-swap query
 /// - `actual`: Amount actually received from the swap
-/// - `max_slippage`: Maximum allowed slippage as percentage (e.g., 2.0 = 2.0%)
+/// - `max_slippage`: Maximum allowed slippage
 ///
 /// ## Returns
 /// - `Ok(())` if slippage is acceptable
@@ -49,57 +109,52 @@ pub fn calculate_min_receive(
 /// ## Examples
 /// ```
 /// // Good: 2% slippage with 2% max
-/// validate_swap_result(&Nat::from(100), &Nat::from(98), 2.0)?; // OK
+/// let max = Slippage::from_percent(2.0)?;
+/// validate_swap_result(&Nat::from(100), &Nat::from(98), max)?; // OK
 ///
 /// // Good: Positive slippage (got more than expected)
-/// validate_swap_result(&Nat::from(100), &Nat::from(102), 2.0)?; // OK
+/// validate_swap_result(&Nat::from(100), &Nat::from(102), max)?; // OK
 ///
 /// // Bad: 5% slippage with 2% max
-/// validate_swap_result(&Nat::from(100), &Nat::from(95), 2.0)?; // Error
+/// validate_swap_result(&Nat::from(100), &Nat::from(95), max)?; // Error
 /// ```
 pub fn validate_swap_result(
     expected: &Nat,
     actual: &Nat,
-    max_slippage: f64,
+    max_slippage: Slippage,
 ) -> Result<()> {
-    // Handle potential overflow for very large Nat values
-    let expected_f64 = expected.0.to_u64()
-        .or_else(|| expected.0.to_u128().map(|v| v.min(u64::MAX as u128) as u64))
-        .unwrap_or(u64::MAX) as f64;
-    let actual_f64 = actual.0.to_u64()
-        .or_else(|| actual.0.to_u128().map(|v| v.min(u64::MAX as u128) as u64))
-        .unwrap_or(u64::MAX) as f64;
-
     // Zero expected amount is invalid
-    if expected_f64 == 0.0 {
+    if *expected == Nat::from(0u64) {
         return Err(IcpiError::Trading(TradingError::InvalidSwapAmount {
             reason: "Expected amount cannot be zero".to_string(),
         }));
     }
 
-    // Calculate actual slippage as decimal and percentage
-    let actual_slippage_decimal = (expected_f64 - actual_f64) / expected_f64;
-    let actual_slippage_pct = actual_slippage_decimal * 100.0;
-
     // Positive slippage (got more than expected) is always good
-    if actual_slippage_decimal < 0.0 {
-        ic_cdk::println!("✅ Positive slippage: expected {}, got {} ({:.4}% better)",
-            expected_f64, actual_f64, -actual_slippage_pct);
+    if actual >= expected {
+        let gain_bps = bps_of(&(actual.clone() - expected.clone()), expected).unwrap_or(u32::MAX);
+        crate::log_op!("✅ Positive slippage: expected {}, got {} ({:.2}% better)",
+            expected, actual, gain_bps as f64 / 100.0);
         return Ok(());
     }
 
-    // Check if slippage exceeds maximum (both now in percentage format)
-    if actual_slippage_pct > max_slippage {
+    // Exact check: the shortfall may not exceed max_slippage of expected,
+    // which `apply_bps` rounds down
+    let shortfall = expected.clone() - actual.clone();
+    let allowed_shortfall = apply_bps(expected, max_slippage.bps())?;
+    let actual_slippage_pct = bps_of(&shortfall, expected)? as f64 / 100.0;
+
+    if shortfall > allowed_shortfall {
         return Err(IcpiError::Trading(TradingError::SlippageExceeded {
             expected: expected.clone(),
             actual: actual.clone(),
-            max_allowed: max_slippage,
-            actual_slippage: actual_slippage_decimal,
+            max_allowed: max_slippage.as_percent(),
+            actual_slippage: actual_slippage_pct,
         }));
     }
 
-    ic_cdk::println!("✅ Slippage acceptable: {:.4}% (max: {:.2}%)",
-        actual_slippage_pct, max_slippage);
+    crate::log_op!("✅ Slippage acceptable: {:.2}% (max: {:.2}%)",
+        actual_slippage_pct, max_slippage.as_percent());
 
     Ok(())
 }
@@ -108,17 +163,55 @@ pub fn validate_swap_result(
 mod tests {
     use super::*;
 
+    fn pct(percent: f64) -> Slippage {
+        Slippage::from_percent(percent).unwrap()
+    }
+
+    #[test]
+    fn test_slippage_unit_conversions() {
+        let s = Slippage::from_percent(2.5).unwrap();
+        assert_eq!(s.bps(), 250);
+        assert_eq!(s.as_percent(), 2.5);
+        assert_eq!(s.as_decimal(), 0.025);
+        assert_eq!(Slippage::from_decimal(0.025).unwrap(), s);
+        assert_eq!(Slippage::from_bps(250).unwrap(), s);
+
+        // Float noise rounds to the nearest basis point
+        assert_eq!(Slippage::from_decimal(0.1 * 0.2).unwrap().bps(), 200);
+        assert_eq!(MAX_TRADE_SLIPPAGE.as_percent(), 5.0);
+        assert_eq!(Slippage::from_percent(10.0).unwrap().bps(), Slippage::MAX_BPS);
+        assert_eq!(Slippage::from_percent(0.0).unwrap().bps(), 0);
+    }
+
+    #[test]
+    fn test_slippage_rejects_out_of_range() {
+        assert!(Slippage::from_percent(15.0).is_err());
+        assert!(Slippage::from_percent(-1.0).is_err());
+        assert!(Slippage::from_percent(f64::NAN).is_err());
+        assert!(Slippage::from_percent(f64::INFINITY).is_err());
+        assert!(Slippage::from_decimal(0.15).is_err());
+        assert!(Slippage::from_decimal(-0.01).is_err());
+        assert!(Slippage::from_bps(1_001).is_err());
+        // A percent value passed as a decimal is caught, not silently 100x'd
+        assert!(Slippage::from_decimal(5.0).is_err());
+    }
+
     #[test]
     fn test_calculate_min_receive() {
         // 2% slippage on 100 tokens = 98 minimum
         let expected = Nat::from(100u64);
-        let min = calculate_min_receive(&expected, 2.0);
+        let min = calculate_min_receive(&expected, pct(2.0));
         assert_eq!(min, Nat::from(98u64));
 
         // 5% slippage on 1000 tokens = 950 minimum
         let expected = Nat::from(1000u64);
-        let min = calculate_min_receive(&expected, 5.0);
+        let min = calculate_min_receive(&expected, pct(5.0));
         assert_eq!(min, Nat::from(950u64));
+
+        // Rounds down without going through f64
+        let expected = Nat::from(u64::MAX) * Nat::from(1_000u64);
+        let min = calculate_min_receive(&expected, pct(1.0));
+        assert_eq!(min, Nat::from(u64::MAX) * Nat::from(990u64));
     }
 
     #[test]
@@ -126,7 +219,7 @@ mod tests {
         // 2% slippage with 2% max should pass
         let expected = Nat::from(100u64);
         let actual = Nat::from(98u64);
-        assert!(validate_swap_result(&expected, &actual, 2.0).is_ok());
+        assert!(validate_swap_result(&expected, &actual, pct(2.0)).is_ok());
     }
 
     #[test]
@@ -134,7 +227,7 @@ mod tests {
         // Got more than expected should always pass
         let expected = Nat::from(100u64);
         let actual = Nat::from(105u64);
-        assert!(validate_swap_result(&expected, &actual, 2.0).is_ok());
+        assert!(validate_swap_result(&expected, &actual, pct(2.0)).is_ok());
     }
 
     #[test]
@@ -142,7 +235,27 @@ mod tests {
         // 5% slippage with 2% max should fail
         let expected = Nat::from(100u64);
         let actual = Nat::from(95u64);
-        assert!(validate_swap_result(&expected, &actual, 2.0).is_err());
+        assert!(validate_swap_result(&expected, &actual, pct(2.0)).is_err());
+    }
+
+    #[test]
+    fn test_validate_swap_result_exact_boundary() {
+        // 2% of 1,000,000,001 rounds down to 20,000,000 allowed shortfall
+        let expected = Nat::from(1_000_000_001u64);
+        assert!(validate_swap_result(&expected, &Nat::from(980_000_001u64), pct(2.0)).is_ok());
+        // One unit more is just over 2%, even though it measures 200 bps
+        match validate_swap_result(&expected, &Nat::from(980_000_000u64), pct(2.0)) {
+            Err(IcpiError::Trading(TradingError::SlippageExceeded { actual_slippage, max_allowed, .. })) => {
+                assert_eq!((actual_slippage, max_allowed), (2.0, 2.0));
+            }
+            other => panic!("Expected SlippageExceeded, got {:?}", other),
+        }
+
+        // Beyond u64 without saturating
+        let expected = Nat::from(u128::MAX);
+        let actual = expected.clone() - apply_bps(&expected, 300).unwrap();
+        assert!(validate_swap_result(&expected, &actual, pct(2.0)).is_err());
+        assert!(validate_swap_result(&expected, &actual, pct(3.0)).is_ok());
     }
 
     #[test]
@@ -150,6 +263,6 @@ mod tests {
         // Zero expected amount should fail
         let expected = Nat::from(0u64);
         let actual = Nat::from(100u64);
-        assert!(validate_swap_result(&expected, &actual, 2.0).is_err());
+        assert!(validate_swap_result(&expected, &actual, pct(2.0)).is_err());
     }
 }
@@ -6,9 +6,11 @@
 //! ## Swap Flow
 //! 1. Approve Kongswap to spend pay_token
 //! 2. Query expected receive amount (for slippage check)
-//! 3. Execute swap with `pay_tx_id: None` (ICRC-2)
-//! 4. Validate actual slippage vs max_slippage
-//! 5. Log results
+//! 3. Check the approval deadline, re-approving if it is about to expire
+//! 4. Execute swap with `pay_tx_id: None` (ICRC-2)
+//! 5. Validate actual slippage vs max_slippage
+//! 6. Reconcile the consumed pay amount (partial fills)
+//! 7. Log results
 //!
 //! ## Key Constraints
 //! - **ICRC-2 Only**: Must use approval flow (`pay_tx_id: None`)
@@ -16,9 +18,63 @@
 //! - **Sequential**: No parallel swaps (Kongswap limitation)
 //! - **Slippage Protected**: Enforces max 2% default slippage
 
-use candid::{Nat, Principal};
+use candid::{CandidType, Deserialize, Nat, Principal};
+use std::cell::RefCell;
 use crate::types::{TrackedToken, kongswap::{SwapArgs, SwapReply, SwapAmountsReply, SwapAmountsResult}};
 use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BACKEND_ID};
+use super::approvals::{self, SwapApproval};
+use super::slippage::Slippage;
+
+const MAX_RENEWAL_LOG: usize = 100;
+
+/// An approval renewed because it was close to expiry when the swap was due
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ApprovalRenewal {
+    pub timestamp: u64,
+    pub pay_token: TrackedToken,
+    pub receive_token: TrackedToken,
+    pub amount: Nat,
+    /// Seconds left on the previous approval when it was replaced
+    pub seconds_remaining: u64,
+    pub new_expires_at: u64,
+}
+
+/// Structured outcome of a swap, for reconciling trades against Kongswap
+#[derive(CandidType, Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct SwapAudit {
+    /// ICRC-2 approval block on the pay token's ledger
+    pub approval_block: Nat,
+    pub kongswap_tx_id: u64,
+    pub kongswap_request_id: u64,
+    pub pay_amount: Nat,
+    pub receive_amount: Nat,
+    pub price: f64,
+    pub mid_price: f64,
+    /// Slippage reported by Kongswap, in percent
+    pub slippage: f64,
+    /// Kongswap's timestamp for the swap
+    pub kongswap_ts: u64,
+}
+
+impl SwapAudit {
+    pub fn from_swap(approval_block: Nat, reply: &SwapReply) -> Self {
+        Self {
+            approval_block,
+            kongswap_tx_id: reply.tx_id,
+            kongswap_request_id: reply.request_id,
+            pay_amount: reply.pay_amount.clone(),
+            receive_amount: reply.receive_amount.clone(),
+            price: reply.price,
+            mid_price: reply.mid_price,
+            slippage: reply.slippage,
+            kongswap_ts: reply.ts,
+        }
+    }
+}
+
+thread_local! {
+    static RENEWAL_LOG: RefCell<Vec<ApprovalRenewal>> = RefCell::new(Vec::new());
+}
 
 /// Execute a token swap via Kongswap
 ///
@@ -26,7 +82,7 @@ use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BA
 /// - `pay_token`: Token to send (e.g., ckUSDT to buy ALEX)
 /// - `pay_amount`: Amount of pay_token to swap (in token's base units)
 /// - `receive_token`: Token to receive (e.g., ALEX when buying)
-/// - `max_slippage`: Maximum acceptable slippage as percentage (e.g., 5.0 = 5%)
+/// - `max_slippage`: Maximum acceptable slippage
 ///
 /// ## Returns
 /// - `Ok(SwapReply)`: Swap details including actual amounts
@@ -36,9 +92,11 @@ use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BA
 /// 1. **Validate inputs**
 /// 2. **Approve tokens**: Backend approves Kongswap to spend pay_amount
 /// 3. **Query price**: Get expected receive_amount for slippage check
-/// 4. **Execute swap**: Call Kongswap `swap()` with ICRC-2 flow
-/// 5. **Validate slippage**: Ensure actual matches expected within limits
-/// 6. **Log results**: Track swap for rebalance history
+/// 4. **Check deadline**: Re-approve if the approval is about to expire
+/// 5. **Execute swap**: Call Kongswap `swap()` with ICRC-2 flow
+/// 6. **Validate slippage**: Ensure actual matches expected within limits
+/// 7. **Reconcile fill**: Book the pay amount actually consumed
+/// 8. **Log results**: Track swap for rebalance history
 ///
 /// ## Examples
 /// ```rust
@@ -47,7 +105,7 @@ use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BA
 ///     &TrackedToken::ckUSDT,
 ///     Nat::from(1_000_000u64),
 ///     &TrackedToken::ALEX,
-///     2.0  // 2% slippage (percentage form, not decimal)
+///     Slippage::from_percent(2.0)?
 /// ).await?;
 ///
 /// // Sell 10 ALEX for ckUSDT (e8 decimals = 1_000_000_000) with 5% slippage
@@ -55,33 +113,55 @@ use crate::infrastructure::{Result, IcpiError, errors::TradingError, KONGSWAP_BA
 ///     &TrackedToken::ALEX,
 ///     Nat::from(1_000_000_000u64),
 ///     &TrackedToken::ckUSDT,
-///     5.0  // 5% slippage (percentage form, not decimal)
+///     Slippage::from_decimal(0.05)?
 /// ).await?;
 /// ```
 pub async fn execute_swap(
     pay_token: &TrackedToken,
     pay_amount: Nat,
     receive_token: &TrackedToken,
-    max_slippage: f64,
+    max_slippage: Slippage,
 ) -> Result<SwapReply> {
+    execute_swap_audited(pay_token, pay_amount, receive_token, max_slippage).await
+        .map(|(reply, _)| reply)
+}
+
+/// `execute_swap`, also returning the audit record of the swap
+pub async fn execute_swap_audited(
+    pay_token: &TrackedToken,
+    pay_amount: Nat,
+    receive_token: &TrackedToken,
+    max_slippage: Slippage,
+) -> Result<(SwapReply, SwapAudit)> {
+    let result = execute_swap_steps(pay_token, pay_amount, receive_token, max_slippage).await;
+    crate::infrastructure::logging::record_result(crate::infrastructure::logging::Subsystem::Swap, &result);
+    result
+}
+
+async fn execute_swap_steps(
+    pay_token: &TrackedToken,
+    pay_amount: Nat,
+    receive_token: &TrackedToken,
+    max_slippage: Slippage,
+) -> Result<(SwapReply, SwapAudit)> {
     // === STEP 1: Validate Inputs ===
-    validate_swap_params(pay_token, &pay_amount, receive_token, max_slippage)?;
+    validate_swap_params(pay_token, &pay_amount, receive_token)?;
 
-    ic_cdk::println!(
+    crate::log_op!(
         "🔄 Executing swap: {} {} → {} (max slippage: {:.2}%)",
         pay_amount,
         pay_token.to_symbol(),
         receive_token.to_symbol(),
-        max_slippage
+        max_slippage.as_percent()
     );
 
     // === STEP 2: Approve Tokens ===
-    let approval_block = super::approvals::approve_token_for_swap(
+    let approval = approvals::approve_token_for_swap(
         pay_token,
         pay_amount.clone()
     ).await?;
 
-    ic_cdk::println!("✅ Approval complete (block: {})", approval_block);
+    crate::log_op!("✅ Approval complete (block: {})", approval.block_index);
 
     // === STEP 3: Query Expected Output ===
     let expected_receive = query_swap_amounts(
@@ -90,40 +170,46 @@ pub async fn execute_swap(
         receive_token.to_symbol()
     ).await?;
 
-    ic_cdk::println!(
+    crate::log_op!(
         "📊 Expected to receive: {} {}",
         expected_receive,
         receive_token.to_symbol()
     );
 
-    // === STEP 4: Execute Swap ===
+    // === STEP 4: Check Approval Deadline ===
+    // A stalled swap_amounts query can leave the swap landing right at the
+    // approval's expiry, where it fails with an allowance error
+    let approval = ensure_approval_window(pay_token, &pay_amount, receive_token, approval).await?;
+
+    // === STEP 5: Execute Swap ===
     let kongswap_principal = Principal::from_text(KONGSWAP_BACKEND_ID)
         .map_err(|e| IcpiError::Trading(TradingError::KongswapError {
             operation: "get_principal".to_string(),
             message: format!("Invalid Kongswap principal: {}", e),
         }))?;
 
-    let swap_args = SwapArgs {
-        pay_token: pay_token.to_symbol().to_string(),
-        pay_amount: pay_amount.clone(),
-        pay_tx_id: None, // CRITICAL: None = ICRC-2 flow (approval-based)
-        receive_token: receive_token.to_symbol().to_string(),
-        receive_amount: None, // Let Kongswap calculate
-        receive_address: Some(ic_cdk::id().to_text()), // Send to our backend
-        max_slippage: Some(max_slippage),
-        referred_by: None,
-    };
+    let swap_args = build_swap_args(
+        &crate::infrastructure::config::get_config(),
+        pay_token,
+        pay_amount.clone(),
+        receive_token,
+        max_slippage,
+        crate::infrastructure::runtime::canister_id(),
+    );
 
-    ic_cdk::println!("📤 Calling Kongswap swap()...");
+    crate::log_op!(
+        "📤 Calling Kongswap swap() ({}s left on approval)...",
+        approvals::seconds_remaining(approval.expires_at, crate::infrastructure::runtime::time())
+    );
 
-    let (swap_result,): (std::result::Result<SwapReply, String>,) = ic_cdk::call(
+    let (swap_result,): (std::result::Result<SwapReply, String>,) = crate::infrastructure::runtime::call(
         kongswap_principal,
         "swap",
         (swap_args,)
     )
     .await
     .map_err(|(code, msg)| {
-        ic_cdk::println!("❌ Swap call failed: {:?} - {}", code, msg);
+        crate::log_op!("❌ Swap call failed: {:?} - {}", code, msg);
         IcpiError::Trading(TradingError::SwapFailed {
             pay_token: pay_token.to_symbol().to_string(),
             receive_token: receive_token.to_symbol().to_string(),
@@ -133,7 +219,7 @@ pub async fn execute_swap(
     })?;
 
     let swap_reply = swap_result.map_err(|e| {
-        ic_cdk::println!("❌ Swap rejected by Kongswap: {}", e);
+        crate::log_op!("❌ Swap rejected by Kongswap: {}", e);
         IcpiError::Trading(TradingError::SwapFailed {
             pay_token: pay_token.to_symbol().to_string(),
             receive_token: receive_token.to_symbol().to_string(),
@@ -142,17 +228,33 @@ pub async fn execute_swap(
         })
     })?;
 
-    // === STEP 5: Validate Slippage ===
+    // === STEP 6: Validate Slippage ===
     super::slippage::validate_swap_result(
         &expected_receive,
         &swap_reply.receive_amount,
         max_slippage
     )?;
 
-    // === STEP 6: Log Success ===
-    ic_cdk::println!(
+    let now = crate::infrastructure::runtime::time();
+    crate::_5_INFORMATIONAL::costs::record_swap(pay_token, receive_token, &swap_reply);
+    let volume_e6 = if *pay_token == TrackedToken::ckUSDT { &swap_reply.pay_amount } else { &swap_reply.receive_amount };
+    crate::_5_INFORMATIONAL::daily_totals::record_trade(now, volume_e6);
+
+    // === STEP 7: Reconcile Fill ===
+    // Book what Kongswap actually consumed, which may differ from pay_amount
+    super::fills::reconcile_swap(
+        pay_token,
+        receive_token,
+        &pay_amount,
+        &swap_reply,
+        crate::infrastructure::config::get_config().partial_fill_policy(),
+        now,
+    )?;
+
+    // === STEP 8: Log Success ===
+    crate::log_op!(
         "✅ Swap complete: {} {} → {} {} (slippage: {:.4}%, price: {})",
-        pay_amount,
+        swap_reply.pay_amount,
         pay_token.to_symbol(),
         swap_reply.receive_amount,
         receive_token.to_symbol(),
@@ -160,7 +262,81 @@ pub async fn execute_swap(
         swap_reply.price
     );
 
-    Ok(swap_reply)
+    let audit = SwapAudit::from_swap(approval.block_index, &swap_reply);
+    Ok((swap_reply, audit))
+}
+
+/// Arguments for Kongswap `swap()`, naming tokens by their Kongswap symbols
+fn build_swap_args(
+    config: &crate::infrastructure::config::RuntimeConfig,
+    pay_token: &TrackedToken,
+    pay_amount: Nat,
+    receive_token: &TrackedToken,
+    max_slippage: Slippage,
+    receive_address: Principal,
+) -> SwapArgs {
+    SwapArgs {
+        pay_token: config.kongswap_symbol(pay_token),
+        pay_amount,
+        pay_tx_id: None, // CRITICAL: None = ICRC-2 flow (approval-based)
+        receive_token: config.kongswap_symbol(receive_token),
+        receive_amount: None, // Let Kongswap calculate
+        receive_address: Some(receive_address.to_text()), // Send to our backend
+        max_slippage: Some(max_slippage.as_percent()), // Kongswap takes percent
+        referred_by: None,
+    }
+}
+
+/// Re-approve if the approval deadline is within the configured margin
+///
+/// The renewal is recorded in the approval renewal log.
+async fn ensure_approval_window(
+    pay_token: &TrackedToken,
+    pay_amount: &Nat,
+    receive_token: &TrackedToken,
+    approval: SwapApproval,
+) -> Result<SwapApproval> {
+    let now = crate::infrastructure::runtime::time();
+    let min_remaining = crate::infrastructure::config::get_config().approval_min_remaining_seconds();
+    if !approvals::needs_renewal(approval.expires_at, now, min_remaining) {
+        return Ok(approval);
+    }
+
+    let seconds_remaining = approvals::seconds_remaining(approval.expires_at, now);
+    let renewed = approvals::approve_token_for_swap(pay_token, pay_amount.clone()).await?;
+    crate::log_op!(
+        "⏳ Approval for {} {} had {}s left (< {}s), re-approved",
+        pay_amount,
+        pay_token.to_symbol(),
+        seconds_remaining,
+        min_remaining
+    );
+    record_renewal(ApprovalRenewal {
+        timestamp: crate::infrastructure::runtime::time(),
+        pay_token: pay_token.clone(),
+        receive_token: receive_token.clone(),
+        amount: pay_amount.clone(),
+        seconds_remaining,
+        new_expires_at: renewed.expires_at,
+    });
+
+    Ok(renewed)
+}
+
+fn record_renewal(renewal: ApprovalRenewal) {
+    RENEWAL_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        log.push(renewal);
+        let len = log.len();
+        if len > MAX_RENEWAL_LOG {
+            log.drain(0..(len - MAX_RENEWAL_LOG));
+        }
+    });
+}
+
+/// Approvals renewed just before a swap, oldest first
+pub fn get_approval_renewals() -> Vec<ApprovalRenewal> {
+    RENEWAL_LOG.with(|log| log.borrow().clone())
 }
 
 /// Query expected swap output for slippage calculation
@@ -181,16 +357,34 @@ async fn query_swap_amounts(
     pay_amount: Nat,
     receive_symbol: &str,
 ) -> Result<Nat> {
+    query_swap_amounts_reply(pay_symbol, pay_amount, receive_symbol).await
+        .map(|reply| reply.receive_amount)
+}
+
+/// Query the full `swap_amounts` reply (receive amount, price, slippage)
+///
+/// Takes index symbols; they are mapped to Kongswap symbols for the call.
+pub async fn query_swap_amounts_reply(
+    pay_symbol: &str,
+    pay_amount: Nat,
+    receive_symbol: &str,
+) -> Result<SwapAmountsReply> {
     let kongswap_principal = Principal::from_text(KONGSWAP_BACKEND_ID)
         .map_err(|e| IcpiError::Trading(TradingError::KongswapError {
             operation: "get_principal".to_string(),
             message: format!("Invalid Kongswap principal: {}", e),
         }))?;
 
-    let (result,): (SwapAmountsResult,) = ic_cdk::call(
+    let args = swap_amounts_args(
+        &crate::infrastructure::config::get_config(),
+        pay_symbol,
+        pay_amount,
+        receive_symbol,
+    );
+    let (result,): (SwapAmountsResult,) = crate::infrastructure::runtime::call(
         kongswap_principal,
         "swap_amounts",
-        (pay_symbol, pay_amount.clone(), receive_symbol)
+        args
     )
     .await
     .map_err(|(code, msg)| {
@@ -201,7 +395,7 @@ async fn query_swap_amounts(
     })?;
 
     match result {
-        SwapAmountsResult::Ok(reply) => Ok(reply.receive_amount),
+        SwapAmountsResult::Ok(reply) => Ok(reply),
         SwapAmountsResult::Err(e) => {
             Err(IcpiError::Trading(TradingError::KongswapError {
                 operation: "swap_amounts".to_string(),
@@ -211,18 +405,28 @@ async fn query_swap_amounts(
     }
 }
 
+/// Arguments for Kongswap `swap_amounts`, with index symbols mapped to Kongswap's
+fn swap_amounts_args(
+    config: &crate::infrastructure::config::RuntimeConfig,
+    pay_symbol: &str,
+    pay_amount: Nat,
+    receive_symbol: &str,
+) -> (String, Nat, String) {
+    (config.kongswap_symbol_for(pay_symbol), pay_amount, config.kongswap_symbol_for(receive_symbol))
+}
+
 /// Validate swap parameters before execution
 ///
 /// Checks:
 /// - Pay amount > 0
-/// - Max slippage reasonable (0-10%)
 /// - Tokens are different
 /// - Both tokens are tracked
+///
+/// The max slippage range (0-10%) is enforced when the `Slippage` is built.
 fn validate_swap_params(
     pay_token: &TrackedToken,
     pay_amount: &Nat,
     receive_token: &TrackedToken,
-    max_slippage: f64,
 ) -> Result<()> {
     // Check pay amount > 0
     if pay_amount == &Nat::from(0u64) {
@@ -231,16 +435,6 @@ fn validate_swap_params(
         }));
     }
 
-    // Check max slippage is reasonable (expects percentage form: 5.0 = 5%)
-    if max_slippage < 0.0 || max_slippage > 10.0 {
-        return Err(IcpiError::Trading(TradingError::InvalidSwapAmount {
-            reason: format!(
-                "Max slippage must be between 0% and 10%, got {:.2}%",
-                max_slippage  // Already in percentage form
-            ),
-        }));
-    }
-
     // Check tokens are different
     if pay_token.to_symbol() == receive_token.to_symbol() {
         return Err(IcpiError::Trading(TradingError::InvalidSwapAmount {
@@ -261,7 +455,6 @@ mod tests {
             &TrackedToken::ckUSDT,
             &Nat::from(1_000_000u64),
             &TrackedToken::ALEX,
-            2.0  // 2% in percentage form
         );
         assert!(result.is_ok());
     }
@@ -272,20 +465,14 @@ mod tests {
             &TrackedToken::ckUSDT,
             &Nat::from(0u64),
             &TrackedToken::ALEX,
-            2.0  // 2% in percentage form
         );
         assert!(result.is_err());
     }
 
     #[test]
     fn test_validate_swap_params_invalid_slippage() {
-        let result = validate_swap_params(
-            &TrackedToken::ckUSDT,
-            &Nat::from(1_000_000u64),
-            &TrackedToken::ALEX,
-            15.0  // 15% is too high
-        );
-        assert!(result.is_err());
+        // 15% is too high to construct at all
+        assert!(Slippage::from_percent(15.0).is_err());
     }
 
     #[test]
@@ -294,8 +481,74 @@ mod tests {
             &TrackedToken::ALEX,
             &Nat::from(1_000_000u64),
             &TrackedToken::ALEX,
-            2.0  // 2% in percentage form
         );
         assert!(result.is_err());
     }
+
+    fn aliased_config() -> crate::infrastructure::config::RuntimeConfig {
+        crate::infrastructure::config::RuntimeConfig {
+            kongswap_symbols: Some(vec![(TrackedToken::ALEX, "ALEX.ysy5f".to_string())]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_swap_args_use_kongswap_alias() {
+        let args = build_swap_args(
+            &aliased_config(),
+            &TrackedToken::ckUSDT,
+            Nat::from(1_000_000u64),
+            &TrackedToken::ALEX,
+            super::super::slippage::MAX_TRADE_SLIPPAGE,
+            Principal::anonymous(),
+        );
+        assert_eq!(args.pay_token, "ckUSDT");
+        assert_eq!(args.receive_token, "ALEX.ysy5f");
+    }
+
+    #[test]
+    fn test_swap_audit_from_successful_swap() {
+        let reply = SwapReply {
+            tx_id: 9_001,
+            request_id: 77,
+            status: "Success".to_string(),
+            pay_chain: "IC".to_string(),
+            pay_address: String::new(),
+            pay_symbol: "ckUSDT".to_string(),
+            pay_amount: Nat::from(1_000_000u64),
+            receive_chain: "IC".to_string(),
+            receive_address: String::new(),
+            receive_symbol: "ALEX".to_string(),
+            receive_amount: Nat::from(250_000_000u64),
+            mid_price: 2.51,
+            price: 2.5,
+            slippage: 0.4,
+            txs: Vec::new(),
+            transfer_ids: Vec::new(),
+            claim_ids: Vec::new(),
+            ts: 1_700_000_000,
+        };
+
+        let audit = SwapAudit::from_swap(Nat::from(123u64), &reply);
+        assert_eq!(audit, SwapAudit {
+            approval_block: Nat::from(123u64),
+            kongswap_tx_id: 9_001,
+            kongswap_request_id: 77,
+            pay_amount: Nat::from(1_000_000u64),
+            receive_amount: Nat::from(250_000_000u64),
+            price: 2.5,
+            mid_price: 2.51,
+            slippage: 0.4,
+            kongswap_ts: 1_700_000_000,
+        });
+    }
+
+    #[test]
+    fn test_swap_amounts_args_use_kongswap_alias() {
+        let (pay, _, receive) = swap_amounts_args(&aliased_config(), "ALEX", Nat::from(1u64), "ckUSDT");
+        assert_eq!((pay.as_str(), receive.as_str()), ("ALEX.ysy5f", "ckUSDT"));
+
+        let (pay, _, _) = swap_amounts_args(&Default::default(), "ALEX", Nat::from(1u64), "ckUSDT");
+        assert_eq!(pay, "ALEX");
+    }
 }
@@ -0,0 +1,127 @@
+//! Human-readable token amounts
+//!
+//! Raw ledger amounts are integers in each token's smallest unit. These
+//! helpers convert them to and from decimal strings so every client uses
+//! the canister's decimals instead of hard-coding its own.
+//!
+//! Formatting is exact: no rounding, trailing fractional zeros trimmed.
+//! Parsing rejects anything it can't represent exactly, including more
+//! fractional digits than the token has.
+
+use candid::Nat;
+use num_bigint::BigUint;
+use crate::infrastructure::{Result, IcpiError, ValidationError, ICPI_DECIMALS};
+use crate::types::TrackedToken;
+
+/// Decimals of ICPI or a tracked token
+pub fn token_decimals(symbol: &str) -> Result<u32> {
+    if symbol == "ICPI" {
+        return Ok(ICPI_DECIMALS);
+    }
+    TrackedToken::from_symbol(symbol)
+        .map(|token| token.get_decimals() as u32)
+        .map_err(IcpiError::Other)
+}
+
+/// Raw amount as a decimal string, e.g. 1_500_000 with 6 decimals is "1.5"
+pub fn format_units(raw: &Nat, decimals: u32) -> String {
+    let digits = raw.0.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (int_part, frac_part) = padded.split_at(padded.len() - decimals);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        int_part.to_string()
+    } else {
+        format!("{}.{}", int_part, frac_part)
+    }
+}
+
+/// Decimal string as a raw amount, the inverse of `format_units`
+pub fn parse_units(human: &str, decimals: u32) -> Result<Nat> {
+    let invalid = |reason: String| IcpiError::Validation(ValidationError::InvalidAmount {
+        amount: human.to_string(),
+        reason,
+    });
+
+    let (int_part, frac_part) = match human.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, frac_part),
+        None => (human, ""),
+    };
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int_part.is_empty() || !all_digits(int_part) {
+        return Err(invalid("expected digits before the decimal point".to_string()));
+    }
+    if human.contains('.') && frac_part.is_empty() {
+        return Err(invalid("expected digits after the decimal point".to_string()));
+    }
+    if !all_digits(frac_part) {
+        return Err(invalid("expected only digits after the decimal point".to_string()));
+    }
+    if frac_part.len() > decimals as usize {
+        return Err(invalid(format!(
+            "{} fractional digits, token has {} decimals", frac_part.len(), decimals
+        )));
+    }
+
+    let scaled = format!("{}{:0<width$}", int_part, frac_part, width = decimals as usize);
+    BigUint::parse_bytes(scaled.as_bytes(), 10)
+        .map(Nat)
+        .ok_or_else(|| invalid("not a number".to_string()))
+}
+
+/// Format a raw amount of `symbol`
+pub fn format_amount(symbol: &str, raw: &Nat) -> Result<String> {
+    Ok(format_units(raw, token_decimals(symbol)?))
+}
+
+/// Parse a human amount of `symbol`
+pub fn parse_amount(symbol: &str, human: &str) -> Result<Nat> {
+    parse_units(human, token_decimals(symbol)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_parse_round_trip() {
+        let cases: [(&str, u64, &str); 8] = [
+            ("ckUSDT", 1_500_000, "1.5"),
+            ("ckUSDT", 1, "0.000001"),
+            ("ckUSDT", 0, "0"),
+            ("ckUSDT", 100_000_000_000, "100000"),
+            ("ICPI", 123_456_789, "1.23456789"),
+            ("ICPI", 10_000, "0.0001"),
+            ("ALEX", 200_000_000, "2"),
+            ("BOB", 5, "0.00000005"),
+        ];
+        for (symbol, raw, human) in cases {
+            let raw = Nat::from(raw);
+            assert_eq!(format_amount(symbol, &raw).unwrap(), human, "{} {}", symbol, raw);
+            assert_eq!(parse_amount(symbol, human).unwrap(), raw, "{} {}", symbol, human);
+        }
+
+        // Amounts beyond u64 survive the round trip
+        let huge: Nat = Nat::from(u128::MAX);
+        assert_eq!(parse_amount("ICPI", &format_amount("ICPI", &huge).unwrap()).unwrap(), huge);
+    }
+
+    #[test]
+    fn test_parse_accepts_padding_and_rejects_inexact() {
+        assert_eq!(parse_amount("ckUSDT", "1.500000").unwrap(), Nat::from(1_500_000u64));
+        assert_eq!(parse_amount("ckUSDT", "007").unwrap(), Nat::from(7_000_000u64));
+
+        // More precision than the token has is an error, not a rounding
+        assert!(parse_amount("ckUSDT", "0.0000001").is_err());
+        assert!(parse_amount("ICPI", "0.000000001").is_err());
+        for bad in ["", ".5", "5.", "-1", "1,000", "1.2.3", " 1", "1e6"] {
+            assert!(parse_amount("ICPI", bad).is_err(), "{:?} should be rejected", bad);
+        }
+        assert!(parse_amount("DOGE", "1").is_err());
+    }
+}
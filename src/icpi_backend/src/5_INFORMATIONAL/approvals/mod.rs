@@ -0,0 +1,127 @@
+//! Approvals a user needs before minting or burning
+//!
+//! Frontends build the `icrc2_approve` calls straight from this. Amounts
+//! come from the same validator functions that check allowances and amount
+//! bounds, with ledger fees from the fee cache, so an approval built from
+//! here is exactly what the operation draws on.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use crate::infrastructure::{Result, IcpiError, USER_APPROVAL_EXPIRY_SECONDS};
+use crate::infrastructure::ledger_fees;
+use crate::types::{CKUSDT_CANISTER_ID, ICPI_CANISTER_ID};
+use crate::_1_CRITICAL_OPERATIONS::minting::mint_validator;
+use crate::_1_CRITICAL_OPERATIONS::burning::burn_validator;
+
+/// User operation to prepare approvals for
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApprovalOperation {
+    /// `amount` is the ckUSDT deposit (e6)
+    Mint,
+    /// `amount` is the ICPI to burn (e8)
+    Burn,
+}
+
+/// One `icrc2_approve` call to make
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApprovalInstruction {
+    pub token: String,
+    pub ledger: Principal,
+    /// The backend
+    pub spender: Principal,
+    /// Allowance to approve, ledger fees included
+    pub amount: Nat,
+    /// Suggested `expires_at` (ns)
+    pub expires_at: u64,
+}
+
+fn ledger_id(token: &str) -> Result<Principal> {
+    let id = match token {
+        "ckUSDT" => CKUSDT_CANISTER_ID,
+        "ICPI" => ICPI_CANISTER_ID,
+        other => return Err(IcpiError::Other(format!("No ledger known for {}", other))),
+    };
+    Principal::from_text(id)
+        .map_err(|e| IcpiError::Other(format!("Invalid {} ledger id {}: {}", token, id, e)))
+}
+
+/// Approvals `operation` with `amount` needs, given the current ckUSDT fee
+pub fn build_required_approvals(
+    operation: ApprovalOperation,
+    amount: &Nat,
+    ckusdt_fee: u64,
+    spender: Principal,
+    now: u64,
+) -> Result<Vec<ApprovalInstruction>> {
+    let required = match operation {
+        ApprovalOperation::Mint => {
+            mint_validator::validate_mint_amount(amount)?;
+            mint_validator::required_mint_approvals(amount, ckusdt_fee)
+        }
+        ApprovalOperation::Burn => {
+            burn_validator::validate_burn_amount(amount)?;
+            burn_validator::required_burn_approvals(amount, ckusdt_fee)
+        }
+    };
+
+    let expires_at = now.saturating_add(USER_APPROVAL_EXPIRY_SECONDS * 1_000_000_000);
+    required.into_iter()
+        .map(|(token, amount)| Ok(ApprovalInstruction {
+            token: token.to_string(),
+            ledger: ledger_id(token)?,
+            spender,
+            amount,
+            expires_at,
+        }))
+        .collect()
+}
+
+/// Approvals `operation` with `amount` needs right now
+pub fn get_required_approvals(operation: ApprovalOperation, amount: Nat) -> Result<Vec<ApprovalInstruction>> {
+    build_required_approvals(operation, &amount, ledger_fees::ckusdt_fee(), crate::infrastructure::runtime::canister_id(), crate::infrastructure::runtime::time())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::{BurnError, MintError, MINT_FEE_AMOUNT, MIN_BURN_AMOUNT};
+
+    fn backend() -> Principal {
+        Principal::from_text(ICPI_CANISTER_ID).unwrap()
+    }
+
+    #[test]
+    fn test_required_approvals_include_ledger_fees() {
+        let fee = 10_000;
+        let mint = build_required_approvals(ApprovalOperation::Mint, &Nat::from(5_000_000u64), fee, backend(), 7).unwrap();
+        assert_eq!(mint.len(), 1);
+        assert_eq!(mint[0].token, "ckUSDT");
+        assert_eq!(mint[0].ledger, Principal::from_text(CKUSDT_CANISTER_ID).unwrap());
+        assert_eq!(mint[0].spender, backend());
+        // Deposit + mint fee + a ledger fee for each of the two pulls
+        assert_eq!(mint[0].amount, Nat::from(5_000_000u64 + MINT_FEE_AMOUNT + 2 * fee));
+        assert_eq!(mint[0].expires_at, 7 + USER_APPROVAL_EXPIRY_SECONDS * 1_000_000_000);
+
+        let amount = Nat::from(100_000_000u64);
+        let burn = build_required_approvals(ApprovalOperation::Burn, &amount, fee, backend(), 7).unwrap();
+        let tokens: Vec<&str> = burn.iter().map(|a| a.token.as_str()).collect();
+        assert_eq!(tokens, vec!["ckUSDT", "ICPI"]);
+        assert_eq!(burn[0].amount, Nat::from(MINT_FEE_AMOUNT + fee));
+        assert_eq!(burn[1].amount, amount);
+
+        // What the burn validator accepts is exactly what was suggested
+        let fee_approval = Nat::from(MINT_FEE_AMOUNT + ledger_fees::ckusdt_fee());
+        assert!(burn_validator::validate_burn_approvals(Some(&fee_approval), Some(&burn[1].amount), &amount).is_ok());
+    }
+
+    #[test]
+    fn test_required_approvals_respect_amount_limits() {
+        assert!(matches!(
+            build_required_approvals(ApprovalOperation::Mint, &Nat::from(1u64), 10_000, backend(), 0),
+            Err(IcpiError::Mint(MintError::AmountBelowMinimum { .. }))
+        ));
+        assert!(matches!(
+            build_required_approvals(ApprovalOperation::Burn, &Nat::from(MIN_BURN_AMOUNT - 1), 10_000, backend(), 0),
+            Err(IcpiError::Burn(BurnError::AmountBelowMinimum { .. }))
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+//! Build identifiers for post-deploy verification
+//!
+//! The git revision is set by `build.rs` and reads "unknown" when the build
+//! script could not determine it. The interface hash is the SHA-256 of the
+//! candid interface compiled into this wasm (`__export_service`), so a
+//! stale `icpi_backend.did` can't report a matching hash; compare it with
+//! the hash of `dfx canister metadata icpi_backend candid:service`.
+
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const UNKNOWN: &str = "unknown";
+
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ApiInfo {
+    pub version: String,
+    pub git_revision: String,
+    /// SHA-256 (hex) of the compiled candid interface
+    pub interface_hash: String,
+}
+
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+pub fn git_revision() -> &'static str {
+    option_env!("ICPI_GIT_REVISION").unwrap_or(UNKNOWN)
+}
+
+thread_local! {
+    static INTERFACE_HASH: String = hash_interface(&crate::__export_service());
+}
+
+/// SHA-256 (hex) of the candid interface compiled into this wasm
+pub fn interface_hash() -> String {
+    INTERFACE_HASH.with(|hash| hash.clone())
+}
+
+fn hash_interface(service: &str) -> String {
+    Sha256::digest(service.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn get_api_info() -> ApiInfo {
+    ApiInfo {
+        version: version().to_string(),
+        git_revision: git_revision().to_string(),
+        interface_hash: interface_hash(),
+    }
+}
+
+/// Whether the deployed interface matches `expected_hash`
+pub fn verify_interface(expected_hash: &str) -> bool {
+    hash_matches(&interface_hash(), expected_hash)
+}
+
+/// Case-insensitive hex comparison; an unknown hash never matches
+fn hash_matches(actual: &str, expected: &str) -> bool {
+    let expected = expected.trim();
+    actual != UNKNOWN && !expected.is_empty() && actual.eq_ignore_ascii_case(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_matches() {
+        let hash = "ab12cd";
+        assert!(hash_matches(hash, "ab12cd"));
+        assert!(hash_matches(hash, " AB12CD\n"));
+        assert!(!hash_matches(hash, "ab12ce"));
+        assert!(!hash_matches(hash, ""));
+        assert!(!hash_matches(UNKNOWN, UNKNOWN));
+    }
+
+    #[test]
+    fn test_interface_hash_of_compiled_service() {
+        assert_eq!(hash_interface(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+        let hash = interface_hash();
+        assert_eq!(hash, hash_interface(&crate::__export_service()));
+        assert!(verify_interface(&hash.to_uppercase()));
+    }
+}
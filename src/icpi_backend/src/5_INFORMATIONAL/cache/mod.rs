@@ -1,11 +1,36 @@
 //! Cache management for informational queries
+//!
+//! Also runs the cache pre-warmer: a timer that refreshes the caches the
+//! display endpoints read - the index state cache, plus the TVL cache and
+//! the ICP quote rate when they would expire before the next run - so
+//! those endpoints rarely pay for a cold cache.
 
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use num_traits::ToPrimitive;
+use crate::_5_INFORMATIONAL::history::{self, PortfolioSnapshot};
+use crate::infrastructure::Result;
+use crate::infrastructure::constants::{INDEX_STATE_CACHE_TTL_SECONDS, TVL_CACHE_TTL_SECONDS, QUOTE_RATE_CACHE_TTL_SECONDS, MIN_PREWARM_CYCLES};
+use crate::types::TrackedToken;
+
+/// Outcome of the most recent pre-warming runs
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct PrewarmStatus {
+    pub last_run: Option<u64>,
+    /// Last run that refreshed the index state
+    pub last_warmed: Option<u64>,
+    pub runs: u64,
+    /// Runs skipped because cycles were below the pre-warming floor
+    pub skipped_low_cycles: u64,
+    pub last_error: Option<String>,
+}
 
 thread_local! {
     static CACHE_ENTRIES: RefCell<HashMap<String, (Vec<u8>, u64)>> =
         RefCell::new(HashMap::new());
+    static PREWARM_STATUS: RefCell<PrewarmStatus> = RefCell::new(PrewarmStatus::default());
 }
 
 /// Clear all cached entries (including TVL cache)
@@ -16,6 +41,407 @@ pub fn clear_all_caches() {
 
     // Also clear TVL cache
     crate::_3_KONG_LIQUIDITY::tvl::clear_tvl_cache();
+    crate::_5_INFORMATIONAL::nav_quotes::clear_rate_cache();
+    crate::_5_INFORMATIONAL::display::clear_index_state_cache();
+
+    ic_cdk::println!("All caches cleared (including index state, TVL and quote rates)");
+}
+
+/// Whether a cache entry stamped `cached_at` expires before the run after `now`
+pub fn expires_before_next_run(cached_at: Option<u64>, now: u64, ttl_seconds: u64, interval_seconds: u64) -> bool {
+    match cached_at {
+        None => true,
+        Some(at) => now.saturating_add(interval_seconds * 1_000_000_000) >= at.saturating_add(ttl_seconds * 1_000_000_000),
+    }
+}
+
+/// Start (or restart with the configured interval) the cache pre-warmer
+pub fn start_cache_prewarm_timer() {
+    let interval = crate::infrastructure::config::get_config().cache_prewarm_interval_seconds();
+    ic_cdk::println!("🕐 Starting cache pre-warmer (every {}s)", interval);
+
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::CachePrewarm,
+        std::time::Duration::from_secs(interval),
+        false,
+        || ic_cdk::spawn(prewarm()),
+    );
+}
+
+async fn prewarm() {
+    let interval = crate::infrastructure::config::get_config().cache_prewarm_interval_seconds();
+    let now = crate::infrastructure::runtime::time();
+    let tvl_cached_at = crate::_3_KONG_LIQUIDITY::tvl::peek_tvl_cache().map(|(_, at)| at);
+    let refresh_tvl = expires_before_next_run(tvl_cached_at, now, TVL_CACHE_TTL_SECONDS, interval);
+    let icp = crate::_3_KONG_LIQUIDITY::pools::QuoteAsset::ICP;
+    let rate_fetched_at = crate::_5_INFORMATIONAL::nav_quotes::rate_fetched_at(icp);
+    let refresh_rate = expires_before_next_run(rate_fetched_at, now, QUOTE_RATE_CACHE_TTL_SECONDS, interval);
+
+    let cycles = crate::infrastructure::runtime::cycles_balance();
+    let outcome = prewarm_with(
+        cycles,
+        now,
+        async move {
+            let state = crate::_5_INFORMATIONAL::display::refresh_index_state().await?;
+            crate::_1_CRITICAL_OPERATIONS::rebalancing::refresh_projection(&state, now);
+            Ok(state)
+        },
+        async move {
+            if refresh_tvl {
+                crate::_3_KONG_LIQUIDITY::tvl::refresh_kong_locker_tvl().await.map(|_| ())
+            } else {
+                Ok(())
+            }
+        },
+        async move {
+            if refresh_rate {
+                crate::_5_INFORMATIONAL::nav_quotes::refresh_quote_rate(icp, now).await
+            } else {
+                Ok(())
+            }
+        },
+    ).await;
+
+    match outcome {
+        None => ic_cdk::println!("⏭️ Cache pre-warm skipped: {} cycles below floor", cycles),
+        Some(errors) if !errors.is_empty() => {
+            ic_cdk::println!("⚠️ Cache pre-warm incomplete: {}", errors.join("; "));
+        }
+        Some(_) => {}
+    }
+}
+
+/// One pre-warming run with the refresh sources supplied by the caller
+///
+/// Skipped entirely (returning None) when `cycles` is below
+/// `MIN_PREWARM_CYCLES`; otherwise returns the refresh errors.
+async fn prewarm_with<S, T, R, V>(cycles: u128, now: u64, refresh_state: S, refresh_tvl: T, refresh_rate: R) -> Option<Vec<String>>
+where
+    S: std::future::Future<Output = Result<V>>,
+    T: std::future::Future<Output = Result<()>>,
+    R: std::future::Future<Output = Result<()>>,
+{
+    if cycles < MIN_PREWARM_CYCLES {
+        PREWARM_STATUS.with(|s| {
+            let mut s = s.borrow_mut();
+            s.last_run = Some(now);
+            s.skipped_low_cycles += 1;
+        });
+        return None;
+    }
+
+    let (state_result, tvl_result, rate_result) = futures::join!(refresh_state, refresh_tvl, refresh_rate);
+    let state_warmed = state_result.is_ok();
+    let errors: Vec<String> = [
+        state_result.err().map(|e| format!("index state: {}", e)),
+        tvl_result.err().map(|e| format!("TVL: {}", e)),
+        rate_result.err().map(|e| format!("quote rate: {}", e)),
+    ].into_iter().flatten().collect();
+
+    PREWARM_STATUS.with(|s| {
+        let mut s = s.borrow_mut();
+        s.last_run = Some(now);
+        s.runs += 1;
+        if state_warmed {
+            s.last_warmed = Some(now);
+        }
+        s.last_error = (!errors.is_empty()).then(|| errors.join("; "));
+    });
+    Some(errors)
+}
+
+/// Pre-warmer status
+pub fn get_prewarm_status() -> PrewarmStatus {
+    PREWARM_STATUS.with(|s| s.borrow().clone())
+}
+
+/// Validate a pre-warm interval: positive and below the index state TTL
+pub fn validate_prewarm_interval(seconds: u64) -> Result<()> {
+    if seconds == 0 || seconds >= INDEX_STATE_CACHE_TTL_SECONDS {
+        return Err(crate::infrastructure::IcpiError::Other(format!(
+            "Pre-warm interval must be between 1 and {} seconds", INDEX_STATE_CACHE_TTL_SECONDS - 1
+        )));
+    }
+    Ok(())
+}
+
+/// Difference between a cached value and its live recomputation
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct FieldDelta {
+    pub field: String,
+    pub cached: Option<f64>,
+    pub live: Option<f64>,
+    pub delta: Option<f64>,      // live - cached
+    pub delta_pct: Option<f64>,  // delta relative to cached
+}
+
+/// Cached vs live comparison produced by `recompute_and_report`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct CacheAuditReport {
+    pub checked_at: u64,
+    pub tvl_cached_at: Option<u64>,
+    pub snapshot_cached_at: Option<u64>,
+    pub deltas: Vec<FieldDelta>,
+    pub errors: Vec<String>,  // Live queries that failed; their fields have no live value
+}
+
+/// Recompute portfolio value, supply and TVL live and diff them against the caches
+///
+/// Read-only: neither the TVL cache nor the snapshot history is touched.
+pub async fn recompute_and_report() -> CacheAuditReport {
+    let tvl_cache = crate::_3_KONG_LIQUIDITY::tvl::peek_tvl_cache();
+    let snapshot = history::get_latest_snapshot();
+
+    let (tvl_result, value_result, supply_result) = futures::join!(
+        crate::_3_KONG_LIQUIDITY::tvl::compute_live_tvl(),
+        crate::_2_CRITICAL_DATA::portfolio_value::calculate_portfolio_value_with_policy(
+            crate::_3_KONG_LIQUIDITY::pools::PricingPolicy::AllowFallback
+        ),
+        crate::_2_CRITICAL_DATA::supply_tracker::get_icpi_supply_uncached()
+    );
+
+    let mut errors = Vec::new();
+    let live_tvl = tvl_result.map_err(|e| errors.push(format!("TVL: {}", e))).ok();
+    let live_value_e6 = value_result
+        .map_err(|e| errors.push(format!("Portfolio value: {}", e))).ok()
+        .and_then(|v| v.0.to_u64());
+    let live_supply = supply_result.map_err(|e| errors.push(format!("Supply: {}", e))).ok();
+
+    let live_snapshot = match (live_value_e6, live_supply) {
+        (Some(total_value_e6), Some(supply_e8)) => {
+            match history::calculate_nav_e8(total_value_e6, &supply_e8) {
+                Ok(nav_e8) => Some(PortfolioSnapshot {
+                    timestamp: crate::infrastructure::runtime::time(),
+                    total_value_e6,
+                    supply_e8,
+                    nav_e8,
+                    tvl_report: None,
+                    token_weights_bps: None,
+                }),
+                Err(e) => {
+                    errors.push(format!("NAV: {}", e));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let cached = audit_fields(tvl_cache.as_ref().map(|(tvl, _)| tvl.as_slice()), snapshot.as_ref());
+    let live = audit_fields(live_tvl.as_deref(), live_snapshot.as_ref());
+
+    CacheAuditReport {
+        checked_at: crate::infrastructure::runtime::time(),
+        tvl_cached_at: tvl_cache.map(|(_, at)| at),
+        snapshot_cached_at: snapshot.map(|s| s.timestamp),
+        deltas: compute_deltas(&cached, &live),
+        errors,
+    }
+}
+
+/// Flatten TVL and snapshot values into named fields for comparison
+fn audit_fields(
+    tvl: Option<&[(TrackedToken, f64)]>,
+    snapshot: Option<&PortfolioSnapshot>,
+) -> Vec<(String, f64)> {
+    let mut fields = Vec::new();
+
+    if let Some(tvl) = tvl {
+        fields.push(("tvl.total_usd".to_string(), tvl.iter().map(|(_, v)| v).sum()));
+        for (token, usd) in tvl {
+            fields.push((format!("tvl.{}", token.to_symbol()), *usd));
+        }
+    }
+
+    if let Some(s) = snapshot {
+        fields.push(("portfolio.total_value_usd".to_string(), s.total_value_e6 as f64 / 1e6));
+        fields.push(("portfolio.supply".to_string(), nat_to_f64(&s.supply_e8) / 1e8));
+        fields.push(("portfolio.nav_usd".to_string(), s.nav_e8 as f64 / 1e8));
+    }
+
+    fields
+}
+
+fn nat_to_f64(n: &Nat) -> f64 {
+    n.0.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Pair cached and live fields by name, cached order first
+pub fn compute_deltas(cached: &[(String, f64)], live: &[(String, f64)]) -> Vec<FieldDelta> {
+    let lookup = |fields: &[(String, f64)], name: &str| {
+        fields.iter().find(|(f, _)| f == name).map(|(_, v)| *v)
+    };
+
+    let mut names: Vec<&String> = cached.iter().map(|(f, _)| f).collect();
+    for (f, _) in live {
+        if !names.contains(&f) {
+            names.push(f);
+        }
+    }
+
+    names.into_iter().map(|name| {
+        let cached_value = lookup(cached, name);
+        let live_value = lookup(live, name);
+        let delta = cached_value.zip(live_value).map(|(c, l)| l - c);
+        let delta_pct = cached_value.zip(delta)
+            .filter(|(c, _)| *c != 0.0)
+            .map(|(c, d)| d / c * 100.0);
+
+        FieldDelta {
+            field: name.clone(),
+            cached: cached_value,
+            live: live_value,
+            delta,
+            delta_pct,
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, value: f64) -> (String, f64) {
+        (name.to_string(), value)
+    }
+
+    #[test]
+    fn test_deltas_between_stale_cache_and_live() {
+        let cached = vec![field("tvl.total_usd", 1_000.0), field("portfolio.nav_usd", 2.0)];
+        let live = vec![field("tvl.total_usd", 1_100.0), field("portfolio.nav_usd", 2.0)];
+
+        let deltas = compute_deltas(&cached, &live);
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].field, "tvl.total_usd");
+        assert_eq!(deltas[0].delta, Some(100.0));
+        assert_eq!(deltas[0].delta_pct, Some(10.0));
+        assert_eq!(deltas[1].delta, Some(0.0));
+        assert_eq!(deltas[1].delta_pct, Some(0.0));
+    }
+
+    #[test]
+    fn test_deltas_with_missing_sides() {
+        // Empty cache, and a live query that failed
+        let cached = vec![field("portfolio.supply", 0.0), field("tvl.ALEX", 5.0)];
+        let live = vec![field("portfolio.supply", 10.0), field("tvl.total_usd", 7.0)];
+
+        let deltas = compute_deltas(&cached, &live);
+        assert_eq!(deltas.len(), 3);
+        // Zero cached value has a delta but no percentage
+        assert_eq!(deltas[0].delta, Some(10.0));
+        assert_eq!(deltas[0].delta_pct, None);
+        // Cached only
+        assert_eq!((deltas[1].cached, deltas[1].live, deltas[1].delta), (Some(5.0), None, None));
+        // Live only
+        assert_eq!((deltas[2].cached, deltas[2].live, deltas[2].delta), (None, Some(7.0), None));
+    }
+
+    #[test]
+    fn test_audit_fields_from_snapshot_and_tvl() {
+        let tvl = vec![(TrackedToken::ALEX, 30.0), (TrackedToken::ZERO, 10.0)];
+        let snapshot = PortfolioSnapshot {
+            timestamp: 0,
+            total_value_e6: 2_000_000,
+            supply_e8: Nat::from(100_000_000u64),
+            nav_e8: 200_000_000,
+            tvl_report: None,
+            token_weights_bps: None,
+        };
+
+        let fields = audit_fields(Some(&tvl), Some(&snapshot));
+        assert_eq!(fields[0], field("tvl.total_usd", 40.0));
+        assert_eq!(fields[1], field("tvl.ALEX", 30.0));
+        assert!(fields.contains(&field("portfolio.supply", 1.0)));
+        assert!(fields.contains(&field("portfolio.nav_usd", 2.0)));
+        assert!(audit_fields(None, None).is_empty());
+    }
+
+    fn index_state(timestamp: u64) -> crate::types::portfolio::IndexState {
+        crate::types::portfolio::IndexState {
+            total_value: 100.0,
+            current_positions: vec![],
+            target_allocations: vec![],
+            deviations: vec![],
+            timestamp,
+            ckusdt_balance: Nat::from(0u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        }
+    }
+
+    fn warm(cycles: u128, now: u64) -> Option<Vec<String>> {
+        use crate::_5_INFORMATIONAL::display;
+
+        futures::executor::block_on(prewarm_with(
+            cycles,
+            now,
+            async move {
+                let state = display::index_state_with(
+                    async move { Ok(index_state(now)) },
+                    async { Ok(Nat::from(100_000_000u64)) },
+                ).await?;
+                display::store_index_state(state.clone(), now);
+                Ok(state)
+            },
+            async { Ok(()) },
+            async { Ok(()) },
+        ))
+    }
+
+    #[test]
+    fn test_prewarm_updates_cache_timestamp() {
+        use crate::_5_INFORMATIONAL::display::{cached_index_state, index_state_cached_at};
+
+        assert_eq!(warm(MIN_PREWARM_CYCLES, 1_000), Some(vec![]));
+        assert_eq!(index_state_cached_at(), Some(1_000));
+        assert_eq!(history::get_latest_snapshot().unwrap().timestamp, 1_000);
+
+        warm(MIN_PREWARM_CYCLES, 2_000);
+        assert_eq!(index_state_cached_at(), Some(2_000));
+        // The display endpoints read what was warmed
+        assert_eq!(cached_index_state(2_000).map(|s| s.timestamp), Some(2_000));
+        let status = get_prewarm_status();
+        assert_eq!((status.runs, status.last_warmed, status.last_error), (2, Some(2_000), None));
+    }
+
+    #[test]
+    fn test_prewarm_skips_on_low_cycles() {
+        assert_eq!(warm(MIN_PREWARM_CYCLES - 1, 1_000), None);
+        assert!(crate::_5_INFORMATIONAL::display::index_state_cached_at().is_none());
+        assert!(history::get_latest_snapshot().is_none());
+        let status = get_prewarm_status();
+        assert_eq!((status.runs, status.skipped_low_cycles, status.last_run), (0, 1, Some(1_000)));
+    }
+
+    #[test]
+    fn test_prewarm_records_failures() {
+        let errors = futures::executor::block_on(prewarm_with(
+            MIN_PREWARM_CYCLES,
+            5,
+            async { Err::<(), _>(crate::infrastructure::IcpiError::Other("balances down".to_string())) },
+            async { Ok(()) },
+            async { Ok(()) },
+        ));
+        assert_eq!(errors.map(|e| e.len()), Some(1));
+        let status = get_prewarm_status();
+        assert_eq!(status.last_warmed, None);
+        assert!(status.last_error.unwrap().contains("balances down"));
+    }
+
+    #[test]
+    fn test_expires_before_next_run() {
+        let s = 1_000_000_000u64;
+        assert!(expires_before_next_run(None, 0, 3600, 240));
+        // TVL refreshed 10 minutes ago: still warm at the next run
+        assert!(!expires_before_next_run(Some(0), 600 * s, 3600, 240));
+        // Would expire before the next run
+        assert!(expires_before_next_run(Some(0), 3400 * s, 3600, 240));
+    }
 
-    ic_cdk::println!("All caches cleared (including TVL)");
+    #[test]
+    fn test_prewarm_interval_below_ttl() {
+        assert!(validate_prewarm_interval(0).is_err());
+        assert!(validate_prewarm_interval(INDEX_STATE_CACHE_TTL_SECONDS).is_err());
+        assert!(validate_prewarm_interval(INDEX_STATE_CACHE_TTL_SECONDS - 1).is_ok());
+    }
 }
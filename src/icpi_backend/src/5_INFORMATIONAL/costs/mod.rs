@@ -0,0 +1,425 @@
+//! Cost accounting
+//!
+//! Tracks what running the index costs its holders - ledger fees, Kongswap
+//! trading fees and slippage, and cycles - in daily buckets, alongside the
+//! mint/burn fees collected. `build_cost_report` turns the buckets plus the
+//! portfolio snapshot history into totals and an annualized expense ratio.
+//!
+//! Only fees paid in ckUSDT are counted as ledger fees; approvals and
+//! transfers of tracked tokens are not, as their per-token fees aren't known
+//! here.
+
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::cell::RefCell;
+use num_traits::ToPrimitive;
+use crate::infrastructure::{Result, IcpiError, ValidationError, multiply_and_divide, ledger_fees};
+use crate::infrastructure::constants::{
+    MAX_COST_DAYS, CYCLES_SAMPLE_INTERVAL_SECONDS, USD_E6_PER_TRILLION_CYCLES,
+};
+use crate::_5_INFORMATIONAL::history::PortfolioSnapshot;
+use crate::types::TrackedToken;
+use crate::types::kongswap::SwapReply;
+
+const DAY_NANOS: u64 = 86_400_000_000_000;
+
+/// Costs incurred on one day (days since the Unix epoch)
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct CostDay {
+    pub day: u64,
+    pub ledger_fees_e6: u64,
+    pub swap_fees_e6: u64,
+    pub slippage_e6: u64,
+    pub cycles_burned: u64,
+    pub mint_fees_e6: u64,
+    pub burn_fees_e6: u64,
+}
+
+/// Persisted cost accounting state
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct CostLedger {
+    pub days: Vec<CostDay>,  // Oldest first
+    pub last_cycles_balance: Option<u128>,
+}
+
+/// Kinds of cost recorded in ckUSDT e6
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CostKind {
+    LedgerFee,
+    SwapFee,
+    Slippage,
+    MintFee,
+    BurnFee,
+}
+
+/// Cost totals over a window, with an annualized expense ratio
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CostReport {
+    pub window_days: u32,
+    pub days_covered: u32,
+    pub ledger_fees_e6: u64,
+    pub swap_fees_e6: u64,
+    pub slippage_e6: u64,
+    pub cycles_burned: u64,
+    pub cycles_cost_e6: u64,
+    pub operating_cost_e6: u64,  // Ledger + swap fees + slippage + cycles
+    pub mint_fees_e6: u64,       // Paid by minters, not by holders
+    pub burn_fees_e6: u64,       // Paid by burners, not by holders
+    pub average_tvl_e6: Option<u64>,
+    pub annualized_cost_bps: Option<u64>,
+}
+
+/// Ledger fees, trading fees and slippage of one swap, in ckUSDT e6
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapCosts {
+    pub ledger_fees_e6: u64,
+    pub swap_fees_e6: u64,
+    pub slippage_e6: u64,
+}
+
+thread_local! {
+    static COST_LEDGER: RefCell<CostLedger> = RefCell::new(CostLedger::default());
+}
+
+/// Record a ckUSDT-denominated cost for today
+pub fn record_cost(kind: CostKind, amount_e6: u64) {
+    let day = crate::infrastructure::runtime::time() / DAY_NANOS;
+    COST_LEDGER.with(|l| add_cost(&mut l.borrow_mut().days, day, kind, amount_e6));
+}
+
+/// Record the costs of a completed swap
+///
+/// Swaps without a ckUSDT side can't be valued here and are ignored.
+pub fn record_swap(pay_token: &TrackedToken, receive_token: &TrackedToken, reply: &SwapReply) {
+    let pay_is_ckusdt = *pay_token == TrackedToken::ckUSDT;
+    if !pay_is_ckusdt && *receive_token != TrackedToken::ckUSDT {
+        return;
+    }
+
+    let fees = reply.txs.iter()
+        .fold(Nat::from(0u64), |acc, tx| acc + tx.lp_fee.clone() + tx.gas_fee.clone());
+    let costs = swap_costs(pay_is_ckusdt, &reply.pay_amount, &reply.receive_amount, &fees, reply.slippage);
+
+    record_cost(CostKind::LedgerFee, costs.ledger_fees_e6);
+    record_cost(CostKind::SwapFee, costs.swap_fees_e6);
+    record_cost(CostKind::Slippage, costs.slippage_e6);
+}
+
+/// Value a swap's costs in ckUSDT e6
+///
+/// Kongswap takes its fees in the receive token, so for buys they are
+/// converted at the executed rate. Approve and transfer_from each cost one
+/// ledger fee on the pay side.
+pub fn swap_costs(
+    pay_is_ckusdt: bool,
+    pay_amount: &Nat,
+    receive_amount: &Nat,
+    fees_in_receive_token: &Nat,
+    slippage_pct: f64,
+) -> SwapCosts {
+    let (notional_e6, swap_fees_e6, ledger_fees_e6) = if pay_is_ckusdt {
+        let fees_e6 = multiply_and_divide(fees_in_receive_token, pay_amount, receive_amount)
+            .map(|f| nat_to_u64(&f))
+            .unwrap_or(0);
+        (nat_to_u64(pay_amount), fees_e6, 2 * ledger_fees::ckusdt_fee())
+    } else {
+        (nat_to_u64(receive_amount), nat_to_u64(fees_in_receive_token), 0)
+    };
+
+    let slippage_e6 = if slippage_pct.is_finite() && slippage_pct > 0.0 {
+        (notional_e6 as f64 * slippage_pct / 100.0).round() as u64
+    } else {
+        0
+    };
+
+    SwapCosts { ledger_fees_e6, swap_fees_e6, slippage_e6 }
+}
+
+fn nat_to_u64(n: &Nat) -> u64 {
+    n.0.to_u64().unwrap_or(u64::MAX)
+}
+
+fn add_cost(days: &mut Vec<CostDay>, day: u64, kind: CostKind, amount_e6: u64) {
+    if amount_e6 == 0 {
+        return;
+    }
+    let bucket = bucket_for(days, day);
+    let field = match kind {
+        CostKind::LedgerFee => &mut bucket.ledger_fees_e6,
+        CostKind::SwapFee => &mut bucket.swap_fees_e6,
+        CostKind::Slippage => &mut bucket.slippage_e6,
+        CostKind::MintFee => &mut bucket.mint_fees_e6,
+        CostKind::BurnFee => &mut bucket.burn_fees_e6,
+    };
+    *field = field.saturating_add(amount_e6);
+}
+
+/// Today's bucket, appending it (and dropping the oldest) if needed
+fn bucket_for(days: &mut Vec<CostDay>, day: u64) -> &mut CostDay {
+    if days.last().map(|d| d.day) != Some(day) {
+        days.push(CostDay { day, ..CostDay::default() });
+        if days.len() > MAX_COST_DAYS {
+            days.remove(0);
+        }
+    }
+    days.last_mut().expect("bucket just ensured")
+}
+
+/// Cycles burned between two balance samples
+///
+/// A higher balance means a top-up; nothing is counted for that interval.
+pub fn cycles_burned(previous: u128, current: u128) -> u128 {
+    previous.saturating_sub(current)
+}
+
+/// Sample the cycles balance and attribute consumption since the last sample
+pub fn sample_cycles() {
+    let balance = crate::infrastructure::runtime::cycles_balance();
+    let day = crate::infrastructure::runtime::time() / DAY_NANOS;
+    COST_LEDGER.with(|l| {
+        let mut ledger = l.borrow_mut();
+        if let Some(previous) = ledger.last_cycles_balance {
+            let burned = cycles_burned(previous, balance).min(u64::MAX as u128) as u64;
+            if burned > 0 {
+                let bucket = bucket_for(&mut ledger.days, day);
+                bucket.cycles_burned = bucket.cycles_burned.saturating_add(burned);
+            }
+        }
+        ledger.last_cycles_balance = Some(balance);
+    });
+}
+
+/// Start the hourly cycles sampling timer
+pub fn start_cycles_sampling_timer() {
+    crate::infrastructure::timers::register_interval(
+        crate::infrastructure::timers::TimerKind::CyclesSampling,
+        std::time::Duration::from_secs(CYCLES_SAMPLE_INTERVAL_SECONDS),
+        true,
+        sample_cycles,
+    );
+}
+
+/// Build a cost report for the last `window_days` days
+///
+/// Annualization uses the days actually covered by cost data, so a
+/// canister younger than the window isn't flattered by empty days.
+pub fn build_cost_report(
+    days: &[CostDay],
+    snapshots: &[PortfolioSnapshot],
+    window_days: u32,
+    now: u64,
+) -> CostReport {
+    let today = now / DAY_NANOS;
+    let first_day = today.saturating_sub(window_days.saturating_sub(1) as u64);
+    let in_window: Vec<&CostDay> = days.iter().filter(|d| d.day >= first_day && d.day <= today).collect();
+
+    let sum = |f: fn(&CostDay) -> u64| in_window.iter().fold(0u64, |acc, d| acc.saturating_add(f(d)));
+    let ledger_fees_e6 = sum(|d| d.ledger_fees_e6);
+    let swap_fees_e6 = sum(|d| d.swap_fees_e6);
+    let slippage_e6 = sum(|d| d.slippage_e6);
+    let cycles_burned = sum(|d| d.cycles_burned);
+    let cycles_cost_e6 = (cycles_burned as u128 * USD_E6_PER_TRILLION_CYCLES as u128 / 1_000_000_000_000) as u64;
+    let operating_cost_e6 = ledger_fees_e6
+        .saturating_add(swap_fees_e6)
+        .saturating_add(slippage_e6)
+        .saturating_add(cycles_cost_e6);
+
+    let days_covered = in_window.first()
+        .map(|d| (today - d.day + 1) as u32)
+        .unwrap_or(0);
+
+    let window_start = first_day * DAY_NANOS;
+    let tvls: Vec<u64> = snapshots.iter()
+        .filter(|s| s.timestamp >= window_start && s.timestamp <= now)
+        .map(|s| s.total_value_e6)
+        .collect();
+    let average_tvl_e6 = if tvls.is_empty() {
+        None
+    } else {
+        Some((tvls.iter().map(|v| *v as u128).sum::<u128>() / tvls.len() as u128) as u64)
+    };
+
+    let annualized_cost_bps = match average_tvl_e6 {
+        Some(tvl) if tvl > 0 && days_covered > 0 => Some(
+            (operating_cost_e6 as u128 * 10_000 * 365 / (tvl as u128 * days_covered as u128)) as u64
+        ),
+        _ => None,
+    };
+
+    CostReport {
+        window_days,
+        days_covered,
+        ledger_fees_e6,
+        swap_fees_e6,
+        slippage_e6,
+        cycles_burned,
+        cycles_cost_e6,
+        operating_cost_e6,
+        mint_fees_e6: sum(|d| d.mint_fees_e6),
+        burn_fees_e6: sum(|d| d.burn_fees_e6),
+        average_tvl_e6,
+        annualized_cost_bps,
+    }
+}
+
+/// Cost report over the last `window_days` days (1-365)
+///
+/// TVL is averaged from the portfolio snapshot history, which only reaches
+/// back a week.
+pub fn get_cost_report(window_days: u32) -> Result<CostReport> {
+    if window_days == 0 || window_days as usize > MAX_COST_DAYS {
+        return Err(IcpiError::Validation(ValidationError::InvalidAmount {
+            amount: window_days.to_string(),
+            reason: format!("Window must be between 1 and {} days", MAX_COST_DAYS),
+        }));
+    }
+
+    let snapshots = crate::_5_INFORMATIONAL::history::get_snapshot_history();
+    Ok(COST_LEDGER.with(|l| {
+        build_cost_report(&l.borrow().days, &snapshots, window_days, crate::infrastructure::runtime::time())
+    }))
+}
+
+/// Export cost ledger for stable storage
+pub fn export_ledger() -> CostLedger {
+    COST_LEDGER.with(|l| l.borrow().clone())
+}
+
+/// Import cost ledger from stable storage
+pub fn import_ledger(ledger: CostLedger) {
+    COST_LEDGER.with(|l| *l.borrow_mut() = ledger);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::CKUSDT_TRANSFER_FEE;
+
+    const DAY: u64 = DAY_NANOS;
+
+    fn day(day: u64, swap_fees_e6: u64) -> CostDay {
+        CostDay { day, swap_fees_e6, ..CostDay::default() }
+    }
+
+    fn snapshot(timestamp: u64, total_value_e6: u64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp,
+            total_value_e6,
+            supply_e8: Nat::from(0u64),
+            nav_e8: 0,
+            tvl_report: None,
+            token_weights_bps: None,
+        }
+    }
+
+    #[test]
+    fn test_annualized_bps() {
+        // $10 of costs per day on a $100k portfolio for 10 days:
+        // $100 / $100k = 10 bps over 10 days -> 365 bps annualized
+        let days: Vec<CostDay> = (91..=100).map(|d| day(d, 10_000_000)).collect();
+        let snapshots = vec![snapshot(95 * DAY, 100_000_000_000)];
+
+        let report = build_cost_report(&days, &snapshots, 10, 100 * DAY + 1);
+        assert_eq!(report.days_covered, 10);
+        assert_eq!(report.swap_fees_e6, 100_000_000);
+        assert_eq!(report.operating_cost_e6, 100_000_000);
+        assert_eq!(report.average_tvl_e6, Some(100_000_000_000));
+        assert_eq!(report.annualized_cost_bps, Some(365));
+    }
+
+    #[test]
+    fn test_window_filtering() {
+        let days = vec![day(80, 999_000_000), day(98, 1_000_000), day(100, 2_000_000)];
+        let snapshots = vec![
+            snapshot(80 * DAY, 1_000_000),       // Outside the window
+            snapshot(99 * DAY, 10_000_000_000),
+            snapshot(100 * DAY, 30_000_000_000),
+        ];
+
+        let report = build_cost_report(&days, &snapshots, 5, 100 * DAY + 1);
+        assert_eq!(report.swap_fees_e6, 3_000_000);
+        assert_eq!(report.average_tvl_e6, Some(20_000_000_000));
+        // Annualized over the 3 days with data, not the 5-day window
+        assert_eq!(report.days_covered, 3);
+        assert_eq!(report.annualized_cost_bps, Some(3_000_000 * 10_000 * 365 / (20_000_000_000 * 3)));
+    }
+
+    #[test]
+    fn test_fees_and_cycles_split() {
+        let days = vec![CostDay {
+            day: 10,
+            ledger_fees_e6: 20_000,
+            cycles_burned: 2_000_000_000_000,
+            mint_fees_e6: 100_000,
+            burn_fees_e6: 100_000,
+            ..CostDay::default()
+        }];
+
+        let report = build_cost_report(&days, &[], 1, 10 * DAY);
+        assert_eq!(report.cycles_cost_e6, 2 * USD_E6_PER_TRILLION_CYCLES);
+        // Mint/burn fees are reported but not part of the operating cost
+        assert_eq!(report.operating_cost_e6, 20_000 + 2 * USD_E6_PER_TRILLION_CYCLES);
+        assert_eq!((report.mint_fees_e6, report.burn_fees_e6), (100_000, 100_000));
+        // No TVL history, no ratio
+        assert_eq!(report.average_tvl_e6, None);
+        assert_eq!(report.annualized_cost_bps, None);
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let report = build_cost_report(&[], &[], 30, 100 * DAY);
+        assert_eq!(report.days_covered, 0);
+        assert_eq!(report.operating_cost_e6, 0);
+        assert_eq!(report.annualized_cost_bps, None);
+    }
+
+    #[test]
+    fn test_add_cost_buckets_by_day() {
+        let mut days = Vec::new();
+        add_cost(&mut days, 5, CostKind::LedgerFee, 10_000);
+        add_cost(&mut days, 5, CostKind::LedgerFee, 10_000);
+        add_cost(&mut days, 5, CostKind::Slippage, 0);
+        add_cost(&mut days, 6, CostKind::MintFee, 100_000);
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].ledger_fees_e6, 20_000);
+        assert_eq!(days[0].slippage_e6, 0);
+        assert_eq!(days[1].mint_fees_e6, 100_000);
+    }
+
+    #[test]
+    fn test_bucket_retention() {
+        let mut days = Vec::new();
+        for d in 0..(MAX_COST_DAYS as u64 + 5) {
+            add_cost(&mut days, d, CostKind::SwapFee, 1);
+        }
+        assert_eq!(days.len(), MAX_COST_DAYS);
+        assert_eq!(days[0].day, 5);
+    }
+
+    #[test]
+    fn test_swap_costs_sell() {
+        // Sold tokens for 100 ckUSDT, 0.3 ckUSDT fees, 0.5% slippage
+        let costs = swap_costs(false, &Nat::from(1_000u64), &Nat::from(100_000_000u64), &Nat::from(300_000u64), 0.5);
+        assert_eq!(costs, SwapCosts { ledger_fees_e6: 0, swap_fees_e6: 300_000, slippage_e6: 500_000 });
+    }
+
+    #[test]
+    fn test_swap_costs_buy() {
+        // Paid 100 ckUSDT for 50 tokens (e8), fees 0.15 tokens = 0.3 ckUSDT
+        let costs = swap_costs(true, &Nat::from(100_000_000u64), &Nat::from(5_000_000_000u64), &Nat::from(15_000_000u64), 1.0);
+        assert_eq!(costs.swap_fees_e6, 300_000);
+        assert_eq!(costs.slippage_e6, 1_000_000);
+        assert_eq!(costs.ledger_fees_e6, 2 * CKUSDT_TRANSFER_FEE);
+
+        // Negative or nonsense slippage is not a cost
+        assert_eq!(swap_costs(true, &Nat::from(1u64), &Nat::from(1u64), &Nat::from(0u64), -1.0).slippage_e6, 0);
+        assert_eq!(swap_costs(true, &Nat::from(1u64), &Nat::from(0u64), &Nat::from(5u64), f64::NAN).swap_fees_e6, 0);
+    }
+
+    #[test]
+    fn test_cycles_burned_ignores_top_ups() {
+        assert_eq!(cycles_burned(1_000, 400), 600);
+        assert_eq!(cycles_burned(400, 1_000), 0);
+    }
+}
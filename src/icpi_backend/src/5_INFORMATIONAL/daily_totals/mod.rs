@@ -0,0 +1,194 @@
+//! Daily operation totals
+//!
+//! Mints, burns and trades are added to a per-day bucket when they happen,
+//! so `get_daily_aggregates` never rescans the histories. Days are UTC days
+//! since the Unix epoch, derived from `crate::infrastructure::runtime::time()`.
+
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::infrastructure::{Result, IcpiError};
+
+const DAY_NANOS: u64 = 86_400_000_000_000;
+/// Oldest buckets are dropped beyond this many days
+const MAX_DAILY_BUCKETS: usize = 3_650;
+/// Widest range a single query may ask for
+const MAX_QUERY_DAYS: u32 = 366;
+
+/// Operation totals for one UTC day
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct DailyTotals {
+    pub day: u32,
+    pub mints: u32,
+    pub icpi_minted: Nat,
+    pub ckusdt_deposited_e6: u64,
+    pub burns: u32,
+    pub icpi_burned: Nat,
+    pub trades: u32,
+    pub traded_volume_e6: u64,
+}
+
+impl DailyTotals {
+    fn empty(day: u32) -> Self {
+        DailyTotals {
+            day,
+            mints: 0,
+            icpi_minted: Nat::from(0u64),
+            ckusdt_deposited_e6: 0,
+            burns: 0,
+            icpi_burned: Nat::from(0u64),
+            trades: 0,
+            traded_volume_e6: 0,
+        }
+    }
+}
+
+thread_local! {
+    static DAILY_TOTALS: RefCell<HashMap<u32, DailyTotals>> = RefCell::new(HashMap::new());
+}
+
+/// UTC day index of a nanosecond timestamp
+pub fn day_index(timestamp_nanos: u64) -> u32 {
+    (timestamp_nanos / DAY_NANOS) as u32
+}
+
+fn with_bucket(timestamp: u64, f: impl FnOnce(&mut DailyTotals)) {
+    let day = day_index(timestamp);
+    DAILY_TOTALS.with(|totals| {
+        let mut totals = totals.borrow_mut();
+        f(totals.entry(day).or_insert_with(|| DailyTotals::empty(day)));
+
+        if totals.len() > MAX_DAILY_BUCKETS {
+            if let Some(oldest) = totals.keys().min().copied() {
+                totals.remove(&oldest);
+            }
+        }
+    });
+}
+
+/// Count a completed mint
+pub fn record_mint(timestamp: u64, icpi_minted: &Nat, deposit_e6: &Nat) {
+    let deposit_e6 = nat_to_u64(deposit_e6);
+    with_bucket(timestamp, |bucket| {
+        bucket.mints = bucket.mints.saturating_add(1);
+        bucket.icpi_minted += icpi_minted.clone();
+        bucket.ckusdt_deposited_e6 = bucket.ckusdt_deposited_e6.saturating_add(deposit_e6);
+    });
+}
+
+/// Count a burn
+pub fn record_burn(timestamp: u64, icpi_burned: &Nat) {
+    with_bucket(timestamp, |bucket| {
+        bucket.burns = bucket.burns.saturating_add(1);
+        bucket.icpi_burned += icpi_burned.clone();
+    });
+}
+
+/// Count a completed swap worth `volume_e6` ckUSDT
+pub fn record_trade(timestamp: u64, volume_e6: &Nat) {
+    let volume_e6 = nat_to_u64(volume_e6);
+    with_bucket(timestamp, |bucket| {
+        bucket.trades = bucket.trades.saturating_add(1);
+        bucket.traded_volume_e6 = bucket.traded_volume_e6.saturating_add(volume_e6);
+    });
+}
+
+fn nat_to_u64(n: &Nat) -> u64 {
+    use num_traits::ToPrimitive;
+    n.0.to_u64().unwrap_or(u64::MAX)
+}
+
+/// Buckets for `from_day..=to_day`, oldest first; days without activity
+/// are omitted
+pub fn get_daily_aggregates(from_day: u32, to_day: u32) -> Result<Vec<DailyTotals>> {
+    if from_day > to_day {
+        return Err(IcpiError::Other(format!("from_day {} is after to_day {}", from_day, to_day)));
+    }
+    if to_day - from_day >= MAX_QUERY_DAYS {
+        return Err(IcpiError::Other(format!("Range exceeds {} days", MAX_QUERY_DAYS)));
+    }
+
+    let mut days: Vec<DailyTotals> = DAILY_TOTALS.with(|totals| {
+        totals.borrow()
+            .values()
+            .filter(|t| t.day >= from_day && t.day <= to_day)
+            .cloned()
+            .collect()
+    });
+    days.sort_by_key(|t| t.day);
+    Ok(days)
+}
+
+/// Export totals for stable storage
+pub fn export_totals() -> Vec<DailyTotals> {
+    let mut days: Vec<DailyTotals> = DAILY_TOTALS.with(|totals| totals.borrow().values().cloned().collect());
+    days.sort_by_key(|t| t.day);
+    days
+}
+
+/// Import totals from stable storage
+pub fn import_totals(days: Vec<DailyTotals>) {
+    DAILY_TOTALS.with(|totals| {
+        *totals.borrow_mut() = days.into_iter().map(|t| (t.day, t)).collect();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_bucketing_around_midnight() {
+        let midnight = 20_000 * DAY_NANOS; // 2024-10-04T00:00:00Z
+        assert_eq!(day_index(midnight - 1), 19_999);
+        assert_eq!(day_index(midnight), 20_000);
+        assert_eq!(day_index(midnight + DAY_NANOS - 1), 20_000);
+
+        record_mint(midnight - SECOND, &Nat::from(100u64), &Nat::from(1_000_000u64));
+        record_mint(midnight, &Nat::from(200u64), &Nat::from(2_000_000u64));
+        record_burn(midnight + DAY_NANOS - SECOND, &Nat::from(50u64));
+        record_trade(midnight + DAY_NANOS, &Nat::from(10_000_000u64));
+
+        let days = get_daily_aggregates(19_999, 20_001).unwrap();
+        assert_eq!(days.iter().map(|d| d.day).collect::<Vec<_>>(), vec![19_999, 20_000, 20_001]);
+
+        assert_eq!(days[0].mints, 1);
+        assert_eq!(days[0].icpi_minted, Nat::from(100u64));
+        assert_eq!(days[1].mints, 1);
+        assert_eq!(days[1].ckusdt_deposited_e6, 2_000_000);
+        assert_eq!(days[1].burns, 1);
+        assert_eq!(days[1].icpi_burned, Nat::from(50u64));
+        assert_eq!(days[2].trades, 1);
+        assert_eq!(days[2].traded_volume_e6, 10_000_000);
+
+        // Range bounds are inclusive
+        assert_eq!(get_daily_aggregates(20_000, 20_000).unwrap().len(), 1);
+        assert!(get_daily_aggregates(20_001, 20_000).is_err());
+        assert!(get_daily_aggregates(0, MAX_QUERY_DAYS).is_err());
+    }
+
+    #[test]
+    fn test_restored_totals_merge_with_new_activity() {
+        let day = 20_000 * DAY_NANOS;
+        record_burn(day, &Nat::from(10u64));
+        record_trade(day - DAY_NANOS, &Nat::from(5u64));
+        let saved = export_totals();
+
+        // Upgrade: fresh heap, then restore
+        import_totals(Vec::new());
+        assert!(get_daily_aggregates(19_999, 20_000).unwrap().is_empty());
+        import_totals(saved);
+
+        // Same-day activity after the restore adds to the restored bucket
+        record_burn(day + 3_600 * SECOND, &Nat::from(15u64));
+
+        let days = get_daily_aggregates(19_999, 20_000).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].trades, 1);
+        assert_eq!(days[1].burns, 2);
+        assert_eq!(days[1].icpi_burned, Nat::from(25u64));
+    }
+}
@@ -0,0 +1,341 @@
+//! Rebalancing diagnostics - the data behind `debug_rebalancing_state`
+//!
+//! `gather_diagnostics` collects TVL targets, balances, portfolio state,
+//! pricing and rebalancer status once; `format_report` renders the console
+//! text from the same struct that `get_rebalancing_diagnostics` returns.
+//! A section whose source failed carries the error instead of data.
+
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+use crate::_1_CRITICAL_OPERATIONS::rebalancing::RebalancerStatus;
+use crate::_2_CRITICAL_DATA::target_smoothing::SmoothingState;
+use crate::types::portfolio::{IndexState, TvlCalculationReport};
+use crate::types::TrackedToken;
+
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TvlTarget {
+    pub token: TrackedToken,
+    pub usd_value: f64,
+    /// Share of total locked TVL (%)
+    pub percentage: f64,
+}
+
+/// Kong Locker TVL, the source of target allocations
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone)]
+pub struct TvlDiagnostics {
+    pub total_usd: f64,
+    pub targets: Vec<TvlTarget>,
+    pub report: TvlCalculationReport,
+}
+
+/// USD per whole token implied by the portfolio valuation
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ImpliedPrice {
+    pub token: TrackedToken,
+    pub usd_price: f64,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct SmoothingDiagnostics {
+    pub half_life_seconds: u64,
+    pub max_change_pct: f64,
+    /// None until the first smoothing cycle
+    pub state: Option<SmoothingState>,
+    pub max_token_weights: Vec<(TrackedToken, f64)>,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+pub struct RebalancingDiagnostics {
+    pub generated_at: u64,
+    pub tvl: Result<TvlDiagnostics, String>,
+    pub balances: Result<Vec<(String, Nat)>, String>,
+    pub portfolio: Result<IndexState, String>,
+    /// Derived from `portfolio`; empty if it failed
+    pub prices: Vec<ImpliedPrice>,
+    pub rebalancer: RebalancerStatus,
+    pub smoothing: SmoothingDiagnostics,
+}
+
+/// Per-token TVL shares
+pub fn tvl_targets(tvl_data: &[(TrackedToken, f64)]) -> (f64, Vec<TvlTarget>) {
+    let total_usd: f64 = tvl_data.iter().map(|(_, v)| v).sum();
+    let targets = tvl_data.iter()
+        .map(|(token, usd_value)| TvlTarget {
+            token: token.clone(),
+            usd_value: *usd_value,
+            percentage: if total_usd > 0.0 { (usd_value / total_usd) * 100.0 } else { 0.0 },
+        })
+        .collect();
+    (total_usd, targets)
+}
+
+/// Prices implied by position values; zero balances are skipped
+pub fn implied_prices(state: &IndexState) -> Vec<ImpliedPrice> {
+    use num_traits::ToPrimitive;
+
+    state.current_positions.iter()
+        .filter_map(|pos| {
+            let units = pos.balance.0.to_f64()? / 10f64.powi(pos.token.get_decimals() as i32);
+            (units > 0.0).then(|| ImpliedPrice { token: pos.token.clone(), usd_price: pos.usd_value / units })
+        })
+        .collect()
+}
+
+fn smoothing_diagnostics() -> SmoothingDiagnostics {
+    let config = crate::infrastructure::config::get_config();
+    SmoothingDiagnostics {
+        half_life_seconds: config.target_smoothing_half_life_seconds(),
+        max_change_pct: config.max_target_change_pct(),
+        state: crate::_2_CRITICAL_DATA::target_smoothing::get_state(),
+        max_token_weights: TrackedToken::all().iter()
+            .map(|t| (t.clone(), config.max_token_weight(t)))
+            .collect(),
+    }
+}
+
+/// Collect every diagnostic section, fetching fresh (uncached) data
+pub async fn gather_diagnostics() -> RebalancingDiagnostics {
+    let tvl = crate::_3_KONG_LIQUIDITY::tvl::calculate_kong_locker_tvl().await
+        .map(|(tvl_data, report)| {
+            let (total_usd, targets) = tvl_targets(&tvl_data);
+            TvlDiagnostics { total_usd, targets, report }
+        })
+        .map_err(|e| e.to_string());
+    let balances = crate::_2_CRITICAL_DATA::token_queries::get_all_balances_uncached().await
+        .map_err(|e| e.to_string());
+    let portfolio = crate::_2_CRITICAL_DATA::portfolio_value::get_portfolio_state_uncached().await
+        .map_err(|e| e.to_string());
+    let prices = portfolio.as_ref().map(implied_prices).unwrap_or_default();
+
+    RebalancingDiagnostics {
+        generated_at: crate::infrastructure::runtime::time(),
+        tvl,
+        balances,
+        portfolio,
+        prices,
+        rebalancer: crate::_1_CRITICAL_OPERATIONS::rebalancing::get_rebalancer_status(),
+        smoothing: smoothing_diagnostics(),
+    }
+}
+
+/// Human-readable report for the console
+pub fn format_report(diagnostics: &RebalancingDiagnostics) -> String {
+    let mut output = String::new();
+    output.push_str("=== REBALANCING DIAGNOSTIC REPORT ===\n\n");
+
+    // 1. TVL targets from Kong Locker
+    output.push_str("1. Kong Locker TVL (Target Allocations):\n");
+    match &diagnostics.tvl {
+        Ok(tvl) => {
+            let report = &tvl.report;
+            output.push_str(&format!("   Total TVL: ${:.2}\n", tvl.total_usd));
+            output.push_str(&format!(
+                "   Lock canisters: {} ({} failed), LP entries: {} processed, {} skipped\n",
+                report.canisters_total, report.canisters_failed,
+                report.lp_entries_processed, report.lp_entries_skipped
+            ));
+            for target in &tvl.targets {
+                output.push_str(&format!("   {}: ${:.2} ({:.2}%)\n",
+                    target.token.to_symbol(), target.usd_value, target.percentage));
+            }
+        }
+        Err(e) => output.push_str(&format!("   ❌ ERROR: {}\n", e)),
+    }
+    output.push('\n');
+
+    // 2. Current token balances
+    output.push_str("2. Current Token Balances:\n");
+    match &diagnostics.balances {
+        Ok(balances) => {
+            for (symbol, balance) in balances {
+                output.push_str(&format!("   {}: {}\n", symbol, balance));
+            }
+        }
+        Err(e) => output.push_str(&format!("   ❌ ERROR: {}\n", e)),
+    }
+    output.push('\n');
+
+    // 3. Portfolio state
+    output.push_str("3. Portfolio State:\n");
+    match &diagnostics.portfolio {
+        Ok(state) => {
+            output.push_str(&format!("   Total Value: ${:.2}\n", state.total_value));
+            output.push_str(&format!("   Timestamp: {}\n", state.timestamp));
+            output.push_str("   Current Positions:\n");
+            for pos in &state.current_positions {
+                output.push_str(&format!("     {}: ${:.2} ({:.2}%)\n",
+                    pos.token.to_symbol(), pos.usd_value, pos.percentage));
+            }
+            output.push_str("   Implied Prices:\n");
+            for price in &diagnostics.prices {
+                output.push_str(&format!("     {}: ${:.6}\n", price.token.to_symbol(), price.usd_price));
+            }
+            output.push_str("   Target Allocations:\n");
+            for target in &state.target_allocations {
+                let cap = target.weight_cap.map(|c| format!(" [capped at {:.2}%]", c)).unwrap_or_default();
+                output.push_str(&format!("     {}: {:.2}% (${:.2}){}\n",
+                    target.token.to_symbol(), target.target_percentage, target.target_usd_value, cap));
+            }
+            output.push_str("   Deviations:\n");
+            for dev in &state.deviations {
+                output.push_str(&format!("     {}: current={:.2}% target={:.2}% deviation={:.2}% usd_diff=${:.2}\n",
+                    dev.token.to_symbol(), dev.current_pct, dev.target_pct, dev.deviation_pct, dev.usd_difference));
+            }
+        }
+        Err(e) => output.push_str(&format!("   ❌ ERROR: {}\n", e)),
+    }
+    output.push('\n');
+
+    // 4. Rebalancer status
+    let status = &diagnostics.rebalancer;
+    output.push_str("4. Rebalancer Status:\n");
+    output.push_str(&format!("   Timer Active: {}\n", status.timer_active));
+    output.push_str(&format!("   Last Rebalance: {:?}\n", status.last_rebalance));
+    output.push_str(&format!("   Next Rebalance: {:?}\n", status.next_rebalance));
+    output.push_str(&format!("   Recent History Entries: {}\n", status.recent_history.len()));
+    output.push('\n');
+
+    // 5. Target smoothing state
+    let smoothing = &diagnostics.smoothing;
+    output.push_str("5. Target Smoothing:\n");
+    output.push_str(&format!("   Half-life: {}s, Max Change/Cycle: {:.2}pp\n",
+        smoothing.half_life_seconds, smoothing.max_change_pct));
+    match &smoothing.state {
+        Some(state) => {
+            output.push_str(&format!("   Cycles: {}, Last Update: {}\n", state.cycles, state.last_update));
+            for ((token, tvl), (_, target)) in state.smoothed_tvl.iter().zip(&state.targets) {
+                output.push_str(&format!("     {}: smoothed TVL=${:.2} target={:.2}%\n", token.to_symbol(), tvl, target));
+            }
+        }
+        None => output.push_str("   Not initialized\n"),
+    }
+    let caps: Vec<String> = smoothing.max_token_weights.iter()
+        .map(|(t, cap)| format!("{}={:.2}%", t.to_symbol(), cap))
+        .collect();
+    output.push_str(&format!("   Max Token Weights: {}\n", caps.join(", ")));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::portfolio::CurrentPosition;
+    use crate::types::rebalancing::{AllocationDeviation, TargetAllocation};
+
+    fn portfolio() -> IndexState {
+        IndexState {
+            total_value: 150.0,
+            current_positions: vec![
+                CurrentPosition {
+                    token: TrackedToken::ALEX,
+                    balance: Nat::from(200_000_000u64), // 2 ALEX
+                    usd_value: 100.0,
+                    percentage: 66.67,
+                    pool_unavailable_since: None,
+                },
+                CurrentPosition {
+                    token: TrackedToken::ZERO,
+                    balance: Nat::from(0u64),
+                    usd_value: 0.0,
+                    percentage: 0.0,
+                    pool_unavailable_since: None,
+                },
+            ],
+            target_allocations: vec![TargetAllocation {
+                token: TrackedToken::ALEX,
+                target_percentage: 50.0,
+                target_usd_value: 75.0,
+                weight_cap: None,
+            }],
+            deviations: vec![AllocationDeviation {
+                token: TrackedToken::ALEX,
+                current_pct: 66.67,
+                target_pct: 50.0,
+                deviation_pct: 16.67,
+                usd_difference: -25.0,
+                trade_size_usd: 2.5,
+            }],
+            timestamp: 7,
+            ckusdt_balance: Nat::from(50_000_000u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        }
+    }
+
+    fn diagnostics() -> RebalancingDiagnostics {
+        let portfolio = portfolio();
+        let (total_usd, targets) = tvl_targets(&[(TrackedToken::ALEX, 300.0), (TrackedToken::KONG, 100.0)]);
+        RebalancingDiagnostics {
+            generated_at: 9,
+            tvl: Ok(TvlDiagnostics { total_usd, targets, report: TvlCalculationReport::default() }),
+            balances: Ok(vec![("ALEX".to_string(), Nat::from(200_000_000u64))]),
+            prices: implied_prices(&portfolio),
+            portfolio: Ok(portfolio),
+            rebalancer: RebalancerStatus {
+                timer_active: true,
+                last_rebalance: Some(1),
+                next_rebalance: Some(2),
+                next_rebalance_override: None,
+                recent_history: Vec::new(),
+                unpause_cooldown_remaining_seconds: 0,
+                reserved_ckusdt: Nat::from(0u64),
+                last_ckusdt_budget: None,
+                projected_next_action: None,
+                targets_age_seconds: None,
+                last_skip: None,
+            },
+            smoothing: SmoothingDiagnostics {
+                half_life_seconds: 21_600,
+                max_change_pct: 2.0,
+                state: Some(SmoothingState {
+                    smoothed_tvl: vec![(TrackedToken::ALEX, 300.0)],
+                    targets: vec![(TrackedToken::ALEX, 75.0)],
+                    last_update: 3,
+                    cycles: 4,
+                }),
+                max_token_weights: vec![(TrackedToken::ALEX, 50.0)],
+            },
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_fully_populated() {
+        let d = diagnostics();
+
+        let tvl = d.tvl.as_ref().unwrap();
+        assert_eq!(tvl.total_usd, 400.0);
+        assert_eq!(tvl.targets[0], TvlTarget { token: TrackedToken::ALEX, usd_value: 300.0, percentage: 75.0 });
+        assert!(!d.balances.as_ref().unwrap().is_empty());
+        let portfolio = d.portfolio.as_ref().unwrap();
+        assert!(!portfolio.current_positions.is_empty());
+        assert!(!portfolio.target_allocations.is_empty());
+        assert!(!portfolio.deviations.is_empty());
+        // Zero balances have no implied price
+        assert_eq!(d.prices, vec![ImpliedPrice { token: TrackedToken::ALEX, usd_price: 50.0 }]);
+        assert!(d.smoothing.state.is_some());
+        assert!(!d.smoothing.max_token_weights.is_empty());
+
+        let report = format_report(&d);
+        for section in ["1. Kong Locker TVL", "2. Current Token Balances", "3. Portfolio State",
+                        "4. Rebalancer Status", "5. Target Smoothing"] {
+            assert!(report.contains(section), "missing {}", section);
+        }
+        assert!(report.contains("ALEX: $300.00 (75.00%)"));
+        assert!(report.contains("ALEX: $50.000000"));
+        assert!(!report.contains("ERROR"));
+    }
+
+    #[test]
+    fn test_failed_sections_carry_errors() {
+        let mut d = diagnostics();
+        d.tvl = Err("lock canister timeout".to_string());
+        d.portfolio = Err("ledger unavailable".to_string());
+
+        let report = format_report(&d);
+        assert!(report.contains("❌ ERROR: lock canister timeout"));
+        assert!(report.contains("❌ ERROR: ledger unavailable"));
+        assert!(report.contains("4. Rebalancer Status"));
+    }
+}
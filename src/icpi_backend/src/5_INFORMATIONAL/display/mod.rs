@@ -1,9 +1,23 @@
 //! Display module - Index state formatting for UI
 
-use crate::types::portfolio::IndexState;
-use crate::infrastructure::Result;
+use candid::Nat;
+use std::cell::RefCell;
+use crate::types::TrackedToken;
+use crate::types::tokens::TokenDetail;
+use crate::types::portfolio::{IndexState, Ticker};
+use crate::infrastructure::{Result, IcpiError};
+use crate::infrastructure::constants::{INDEX_STATE_CACHE_TTL_SECONDS, TICKER_CHANGE_TOLERANCE_SECONDS};
+use crate::_5_INFORMATIONAL::history::{self, PortfolioSnapshot};
+use crate::_3_KONG_LIQUIDITY::pools::PricingPolicy;
 
-/// Get index state for display (with optional caching)
+const DAY_NANOS: u64 = 86_400_000_000_000;
+
+thread_local! {
+    /// Last computed index state and when it was computed
+    static INDEX_STATE_CACHE: RefCell<Option<(IndexState, u64)>> = RefCell::new(None);
+}
+
+/// Get index state for display, from cache while fresh
 ///
 /// Returns complete portfolio state including:
 /// - Total value in USD
@@ -12,10 +26,405 @@ use crate::infrastructure::Result;
 /// - Allocation deviations
 /// - ckUSDT reserves
 ///
+/// Served from the index state cache while it is younger than
+/// `INDEX_STATE_CACHE_TTL_SECONDS` (the pre-warmer keeps it warm);
+/// otherwise recomputed and cached. Trading paths use
+/// `get_index_state_uncached` instead.
+///
 /// IMPORTANT: Propagates errors instead of silently returning empty state
 /// This ensures callers are aware of failures in portfolio calculation
 pub async fn get_index_state_cached() -> Result<IndexState> {
+    if let Some(state) = cached_index_state(crate::infrastructure::runtime::time()) {
+        return Ok(state);
+    }
+    refresh_index_state().await
+}
+
+/// Recompute the index state, cache it and record a portfolio snapshot
+///
+/// The snapshot (used by the ticker and its 24h change) is only recorded
+/// here, on the update and timer paths; read-only previews compute the
+/// state with `get_index_state_uncached` and leave the history alone.
+pub async fn refresh_index_state() -> Result<IndexState> {
+    refresh_index_state_with_policy(PricingPolicy::AllowFallback).await
+}
+
+/// `refresh_index_state` priced under `policy`
+///
+/// Rebalance cycles refresh with `PrimaryOnly`: a token Kongswap can't
+/// price fails the cycle instead of trading on a fallback oracle price.
+pub async fn refresh_index_state_with_policy(policy: PricingPolicy) -> Result<IndexState> {
+    let now = crate::infrastructure::runtime::time();
+    let state = index_state_with(
+        crate::_2_CRITICAL_DATA::portfolio_value::get_portfolio_state_with_policy(policy),
+        crate::_2_CRITICAL_DATA::supply_tracker::get_icpi_supply_uncached(),
+    ).await?;
+    store_index_state(state.clone(), now);
+    Ok(state)
+}
+
+/// Compute the index state from live balances, bypassing the cache
+///
+/// No side effects: nothing is cached and no snapshot is recorded.
+pub async fn get_index_state_uncached() -> Result<IndexState> {
     // Call the portfolio value module to get real state
     // Propagate errors up so they're visible to API consumers
     crate::_2_CRITICAL_DATA::portfolio_value::get_portfolio_state_uncached().await
 }
+
+/// Cached index state, if it is still fresh at `now`
+pub fn cached_index_state(now: u64) -> Option<IndexState> {
+    INDEX_STATE_CACHE.with(|cache| {
+        cache.borrow().as_ref()
+            .filter(|(_, at)| now.saturating_sub(*at) <= INDEX_STATE_CACHE_TTL_SECONDS * 1_000_000_000)
+            .map(|(state, _)| state.clone())
+    })
+}
+
+/// When the cached index state was computed
+pub fn index_state_cached_at() -> Option<u64> {
+    INDEX_STATE_CACHE.with(|cache| cache.borrow().as_ref().map(|(_, at)| *at))
+}
+
+/// Cache `state` as computed at `now`
+pub(crate) fn store_index_state(state: IndexState, now: u64) {
+    INDEX_STATE_CACHE.with(|cache| *cache.borrow_mut() = Some((state, now)));
+}
+
+/// Drop the cached index state
+pub fn clear_index_state_cache() {
+    INDEX_STATE_CACHE.with(|cache| *cache.borrow_mut() = None);
+}
+
+/// Join an index state with the supply and record it as a portfolio snapshot
+pub(crate) async fn index_state_with<S, U>(state: S, supply: U) -> Result<IndexState>
+where
+    S: std::future::Future<Output = Result<IndexState>>,
+    U: std::future::Future<Output = Result<Nat>>,
+{
+    let (state_result, supply_result) = futures::join!(state, supply);
+    let state = state_result?;
+
+    // Snapshot failures must never break the display path
+    match supply_result {
+        Ok(supply) => match snapshot_from_state(&state, supply) {
+            Ok(snapshot) => history::record_snapshot(snapshot),
+            Err(e) => ic_cdk::println!("⚠️ Skipping portfolio snapshot: {}", e),
+        },
+        Err(e) => ic_cdk::println!("⚠️ Skipping portfolio snapshot, supply query failed: {}", e),
+    }
+
+    Ok(state)
+}
+
+/// Reduce an index state plus supply to a snapshot
+fn snapshot_from_state(state: &IndexState, supply_e8: Nat) -> Result<PortfolioSnapshot> {
+    if !state.total_value.is_finite() || state.total_value < 0.0 {
+        return Err(IcpiError::Other(format!("Invalid total value {}", state.total_value)));
+    }
+
+    let total_value_e6 = (state.total_value * 1_000_000.0).round() as u64;
+    let nav_e8 = history::calculate_nav_e8(total_value_e6, &supply_e8)?;
+
+    Ok(PortfolioSnapshot {
+        timestamp: state.timestamp,
+        total_value_e6,
+        supply_e8,
+        nav_e8,
+        tvl_report: crate::_3_KONG_LIQUIDITY::tvl::get_latest_tvl_report(),
+        token_weights_bps: Some(token_weights_bps(state)),
+    })
+}
+
+/// Held tokens' weights in basis points
+///
+/// Position percentages are already whole basis points (see
+/// `bps_of`); rounding only strips float noise.
+fn token_weights_bps(state: &IndexState) -> Vec<(TrackedToken, u32)> {
+    state.current_positions.iter()
+        .filter(|p| p.percentage.is_finite() && p.percentage > 0.0)
+        .map(|p| (p.token.clone(), (p.percentage * 100.0).round() as u32))
+        .collect()
+}
+
+/// Get the compact ticker from the last computed state
+///
+/// Never makes inter-canister calls, so it is safe to poll frequently.
+pub fn get_ticker() -> Result<Ticker> {
+    let latest = history::get_latest_snapshot()
+        .ok_or_else(|| IcpiError::Other("No index state has been computed yet".to_string()))?;
+
+    let now = crate::infrastructure::runtime::time();
+    let mut ticker = build_ticker(&latest, &history::get_snapshot_history(), now);
+    ticker.status_message = crate::_5_INFORMATIONAL::status_message::get_active_message(now);
+    ticker.drawdown_bps = history::get_nav_extremes().map(|e| e.current_drawdown_bps);
+    Ok(ticker)
+}
+
+/// Build the ticker from the latest snapshot and history
+pub fn build_ticker(latest: &PortfolioSnapshot, history: &[PortfolioSnapshot], now: u64) -> Ticker {
+    let target = latest.timestamp.saturating_sub(DAY_NANOS);
+    let change_24h_bps = if latest.timestamp >= DAY_NANOS {
+        history::find_closest_snapshot(history, target, TICKER_CHANGE_TOLERANCE_SECONDS * 1_000_000_000)
+            .and_then(|old| history::calculate_change_bps(old.nav_e8, latest.nav_e8))
+    } else {
+        None
+    };
+
+    let age = now.saturating_sub(latest.timestamp);
+
+    Ticker {
+        nav_e8: latest.nav_e8,
+        total_value_e6: latest.total_value_e6,
+        supply_e8: latest.supply_e8.clone(),
+        change_24h_bps,
+        last_updated: latest.timestamp,
+        is_stale: age > INDEX_STATE_CACHE_TTL_SECONDS * 1_000_000_000,
+        status_message: None,
+        drawdown_bps: None,
+    }
+}
+
+/// Get the full index table (one row per tracked token) in a single call
+pub async fn list_tracked_tokens_detailed() -> Result<Vec<TokenDetail>> {
+    use crate::_3_KONG_LIQUIDITY::pools::{get_token_price_quote, PricingPolicy};
+
+    let price_futures = TrackedToken::all().iter()
+        .map(|token| async move {
+            let price = get_token_price_quote(token, PricingPolicy::AllowFallback).await
+                .map(|quote| quote.price_usdt);
+            (token.clone(), price)
+        });
+
+    let (state, prices) = futures::join!(
+        get_index_state_cached(),
+        futures::future::join_all(price_futures)
+    );
+    let state = state?;
+
+    let prices: Vec<(TrackedToken, Option<f64>)> = prices.into_iter()
+        .map(|(token, price)| match price {
+            Ok(price) => (token, Some(price)),
+            Err(e) => {
+                ic_cdk::println!("⚠️ No spot price for {}: {}", token.to_symbol(), e);
+                (token, None)
+            }
+        })
+        .collect();
+
+    build_token_details(&state, &prices)
+}
+
+/// Join index state and spot prices into table rows for every tracked token
+///
+/// Tokens missing from the state (no position or target yet) get zero rows.
+pub fn build_token_details(state: &IndexState, prices: &[(TrackedToken, Option<f64>)]) -> Result<Vec<TokenDetail>> {
+    TrackedToken::all().iter()
+        .map(|token| {
+            let position = state.current_positions.iter().find(|p| &p.token == token);
+            let target = state.target_allocations.iter().find(|t| &t.token == token);
+            let price_usd = prices.iter()
+                .find(|(t, _)| t == token)
+                .and_then(|(_, price)| *price);
+
+            Ok(TokenDetail {
+                symbol: token.to_symbol().to_string(),
+                canister_id: token.get_canister_id().map_err(IcpiError::Other)?,
+                decimals: token.get_decimals(),
+                balance: position.map(|p| p.balance.clone()).unwrap_or_else(|| Nat::from(0u64)),
+                usd_value: position.map(|p| p.usd_value).unwrap_or(0.0),
+                current_percentage: position.map(|p| p.percentage).unwrap_or(0.0),
+                target_percentage: target.map(|t| t.target_percentage).unwrap_or(0.0),
+                price_usd,
+                pool_unavailable_since: crate::_3_KONG_LIQUIDITY::pool_status::unavailable_since(token),
+            })
+        })
+        .collect()
+}
+
+/// Current index composition as CSV, one row per tracked token
+pub async fn get_index_composition_csv() -> Result<String> {
+    let state = get_index_state_cached().await?;
+    Ok(build_composition_csv(&state))
+}
+
+/// Format the composition of an index state as CSV
+///
+/// Rows follow `TrackedToken::all()` order, then ckUSDT (no target), so
+/// the percentages sum to 100. Balances are in the token's base units;
+/// percentages are of total portfolio value.
+pub fn build_composition_csv(state: &IndexState) -> String {
+    let mut csv = String::from("token,balance,usd_value,percentage,target\n");
+
+    for token in TrackedToken::all().iter().chain(std::iter::once(&TrackedToken::ckUSDT)) {
+        let position = state.current_positions.iter().find(|p| &p.token == token);
+        let target = state.target_allocations.iter().find(|t| &t.token == token);
+
+        csv.push_str(&format!(
+            "{},{},{:.2},{:.4},{:.4}\n",
+            token.to_symbol(),
+            position.map(|p| p.balance.0.to_string()).unwrap_or_else(|| "0".to_string()),
+            position.map(|p| p.usd_value).unwrap_or(0.0),
+            position.map(|p| p.percentage).unwrap_or(0.0),
+            target.map(|t| t.target_percentage).unwrap_or(0.0),
+        ));
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3_600_000_000_000;
+
+    fn snapshot(timestamp: u64, nav_e8: u64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp,
+            total_value_e6: nav_e8 / 100,
+            supply_e8: Nat::from(100_000_000u64),
+            nav_e8,
+            tvl_report: None,
+            token_weights_bps: None,
+        }
+    }
+
+    #[test]
+    fn test_ticker_24h_change() {
+        let now = 48 * HOUR;
+        let history = vec![
+            snapshot(12 * HOUR, 90_000_000),
+            snapshot(24 * HOUR, 100_000_000),
+            snapshot(36 * HOUR, 110_000_000),
+        ];
+        let latest = snapshot(now, 102_500_000);
+
+        let ticker = build_ticker(&latest, &history, now);
+        assert_eq!(ticker.nav_e8, 102_500_000);
+        assert_eq!(ticker.change_24h_bps, Some(250));
+        assert!(!ticker.is_stale);
+    }
+
+    #[test]
+    fn test_ticker_no_change_without_24h_history() {
+        let now = 48 * HOUR;
+        let history = vec![snapshot(40 * HOUR, 100_000_000)];
+        let latest = snapshot(now, 105_000_000);
+
+        let ticker = build_ticker(&latest, &history, now);
+        assert_eq!(ticker.change_24h_bps, None);
+    }
+
+    #[test]
+    fn test_ticker_staleness() {
+        let latest = snapshot(48 * HOUR, 100_000_000);
+        let ttl = INDEX_STATE_CACHE_TTL_SECONDS * 1_000_000_000;
+
+        assert!(!build_ticker(&latest, &[], 48 * HOUR + ttl).is_stale);
+        assert!(build_ticker(&latest, &[], 48 * HOUR + ttl + 1).is_stale);
+    }
+
+    #[test]
+    fn test_index_state_cache_expires_after_ttl() {
+        let state = IndexState {
+            total_value: 100.0,
+            current_positions: vec![],
+            target_allocations: vec![],
+            deviations: vec![],
+            timestamp: HOUR,
+            ckusdt_balance: Nat::from(0u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        };
+        let ttl = INDEX_STATE_CACHE_TTL_SECONDS * 1_000_000_000;
+
+        assert!(cached_index_state(HOUR).is_none());
+        store_index_state(state, HOUR);
+        assert_eq!(cached_index_state(HOUR + ttl).map(|s| s.timestamp), Some(HOUR));
+        assert!(cached_index_state(HOUR + ttl + 1).is_none());
+
+        clear_index_state_cache();
+        assert_eq!(index_state_cached_at(), None);
+    }
+
+    #[test]
+    fn test_build_token_details_joins_state_and_prices() {
+        use crate::types::portfolio::CurrentPosition;
+        use crate::types::rebalancing::TargetAllocation;
+
+        let state = IndexState {
+            total_value: 100.0,
+            current_positions: vec![
+                CurrentPosition { token: TrackedToken::ALEX, balance: Nat::from(5_000_000_000u64), usd_value: 60.0, percentage: 60.0, pool_unavailable_since: None },
+                CurrentPosition { token: TrackedToken::ckUSDT, balance: Nat::from(40_000_000u64), usd_value: 40.0, percentage: 40.0, pool_unavailable_since: None },
+            ],
+            target_allocations: vec![
+                TargetAllocation { token: TrackedToken::ALEX, target_percentage: 30.0, target_usd_value: 30.0, weight_cap: None },
+                TargetAllocation { token: TrackedToken::KONG, target_percentage: 70.0, target_usd_value: 70.0, weight_cap: None },
+            ],
+            deviations: vec![],
+            timestamp: 1,
+            ckusdt_balance: Nat::from(40_000_000u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        };
+        let prices = vec![(TrackedToken::ALEX, Some(1.2)), (TrackedToken::KONG, None)];
+
+        let rows = build_token_details(&state, &prices).unwrap();
+        let symbols: Vec<&str> = rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["ALEX", "ZERO", "KONG", "BOB"]);
+
+        let alex = &rows[0];
+        assert_eq!(alex.canister_id, TrackedToken::ALEX.get_canister_id().unwrap());
+        assert_eq!(alex.decimals, 8);
+        assert_eq!(alex.balance, Nat::from(5_000_000_000u64));
+        assert_eq!(alex.usd_value, 60.0);
+        assert_eq!(alex.current_percentage, 60.0);
+        assert_eq!(alex.target_percentage, 30.0);
+        assert_eq!(alex.price_usd, Some(1.2));
+
+        // Target but no position, no price
+        let kong = &rows[2];
+        assert_eq!(kong.balance, Nat::from(0u64));
+        assert_eq!(kong.current_percentage, 0.0);
+        assert_eq!(kong.target_percentage, 70.0);
+        assert_eq!(kong.price_usd, None);
+    }
+
+    #[test]
+    fn test_composition_csv() {
+        use crate::types::portfolio::CurrentPosition;
+        use crate::types::rebalancing::TargetAllocation;
+
+        let state = IndexState {
+            total_value: 100.0,
+            current_positions: vec![
+                CurrentPosition { token: TrackedToken::ckUSDT, balance: Nat::from(40_000_000u64), usd_value: 40.0, percentage: 40.0, pool_unavailable_since: None },
+                CurrentPosition { token: TrackedToken::ALEX, balance: Nat::from(5_000_000_000u64), usd_value: 60.0, percentage: 60.0, pool_unavailable_since: None },
+            ],
+            target_allocations: vec![
+                TargetAllocation { token: TrackedToken::ALEX, target_percentage: 30.0, target_usd_value: 30.0, weight_cap: None },
+            ],
+            deviations: vec![],
+            timestamp: 1,
+            ckusdt_balance: Nat::from(40_000_000u64),
+            targets_tvl_timestamp: None,
+            data_quality: None,
+        };
+
+        let csv = build_composition_csv(&state);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "token,balance,usd_value,percentage,target");
+        assert_eq!(lines.len(), 2 + TrackedToken::all().len());
+        assert_eq!(lines[1], "ALEX,5000000000,60.00,60.0000,30.0000");
+        assert_eq!(lines[2], "ZERO,0,0.00,0.0000,0.0000");
+        assert_eq!(lines[5], "ckUSDT,40000000,40.00,40.0000,0.0000");
+        let tokens: Vec<&str> = lines[1..].iter().map(|l| l.split(',').next().unwrap()).collect();
+        assert_eq!(tokens, vec!["ALEX", "ZERO", "KONG", "BOB", "ckUSDT"]);
+
+        // Weights cover the whole portfolio
+        let total: f64 = lines[1..].iter().map(|l| l.split(',').nth(3).unwrap().parse::<f64>().unwrap()).sum();
+        assert!((total - 100.0).abs() < 1e-9);
+    }
+}
@@ -5,12 +5,41 @@ use crate::types::TrackedToken;
 
 /// Get system health status
 pub fn get_health_status() -> HealthStatus {
+    health_status_at(
+        crate::infrastructure::runtime::time(),
+        crate::infrastructure::runtime::cycles_balance(),
+    )
+}
+
+/// Health status as of `now`, with the given cycles balance
+pub fn health_status_at(now: u64, cycles_balance: u128) -> HealthStatus {
     HealthStatus {
-        version: env!("CARGO_PKG_VERSION").to_string(),
+        version: crate::_5_INFORMATIONAL::build_info::version().to_string(),
+        git_revision: crate::_5_INFORMATIONAL::build_info::git_revision().to_string(),
+        interface_hash: crate::_5_INFORMATIONAL::build_info::interface_hash(),
         tracked_tokens: get_tracked_tokens(),
         last_rebalance: Some(0), // TODO: Get from rebalancer state
-        cycles_balance: ic_cdk::api::canister_balance128(),
+        cycles_balance,
+        pending_mints: crate::_1_CRITICAL_OPERATIONS::minting::mint_state::get_pending_count() as u64,
+        max_pending_mints: crate::infrastructure::config::get_config().max_pending_mints(),
+        status_message: crate::_5_INFORMATIONAL::status_message::get_active_message(now),
+        warnings: get_warnings(),
+        reserved_ckusdt: crate::_1_CRITICAL_OPERATIONS::minting::mint_state::reserved_deposits(),
+        free_ckusdt: crate::_1_CRITICAL_OPERATIONS::rebalancing::last_ckusdt_budget().map(|b| b.free),
+        last_save_failure: crate::infrastructure::stable_storage::last_save_failure(),
+    }
+}
+
+/// Operational warnings for ops to act on
+fn get_warnings() -> Vec<String> {
+    let mut warnings = crate::infrastructure::ledger_fees::fee_warnings();
+    if let Some(failure) = crate::infrastructure::stable_storage::last_save_failure() {
+        warnings.push(format!(
+            "Last stable save failed at {} ({}); check stable memory before upgrading",
+            failure.failed_at, failure.reason
+        ));
     }
+    warnings
 }
 
 /// Get list of tracked tokens
@@ -0,0 +1,396 @@
+//! Portfolio snapshot history
+//!
+//! Every computed index state is reduced to a small `PortfolioSnapshot`.
+//! The latest one backs the ticker; a throttled ring buffer of older ones
+//! backs time-based comparisons such as the 24h change. Every recorded
+//! snapshot also updates the running NAV extremes (all-time high/low and
+//! drawdown), which outlive the bounded history. Snapshots also keep each
+//! token's weight, so the history can be sliced per token.
+
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use crate::infrastructure::{Result, IcpiError, multiply_and_divide};
+use crate::infrastructure::constants::{PORTFOLIO_SNAPSHOT_INTERVAL_SECONDS, MAX_PORTFOLIO_SNAPSHOTS};
+use crate::types::portfolio::TvlCalculationReport;
+use crate::types::TrackedToken;
+
+/// Compact record of the index at a point in time
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PortfolioSnapshot {
+    pub timestamp: u64,
+    pub total_value_e6: u64,
+    pub supply_e8: Nat,
+    pub nav_e8: u64,
+    /// TVL calculation behind this snapshot's targets (absent in older snapshots)
+    pub tvl_report: Option<TvlCalculationReport>,
+    /// Each held token's share of total value in basis points (absent in older snapshots)
+    pub token_weights_bps: Option<Vec<(TrackedToken, u32)>>,
+}
+
+/// One token's portfolio weight at a snapshot
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenWeightPoint {
+    pub timestamp: u64,
+    /// Share of total value in percent (0 when not held)
+    pub weight_pct: f64,
+}
+
+/// Running NAV highs, lows and drawdowns
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct NavExtremes {
+    pub ath_nav_e8: u64,
+    pub ath_timestamp: u64,
+    pub atl_nav_e8: u64,
+    pub atl_timestamp: u64,
+    pub current_nav_e8: u64,
+    /// Current NAV below the all-time high, in basis points
+    pub current_drawdown_bps: u64,
+    /// Largest drawdown seen from any high
+    pub max_drawdown_bps: u64,
+    pub updated_at: u64,
+}
+
+thread_local! {
+    static LATEST_SNAPSHOT: RefCell<Option<PortfolioSnapshot>> = RefCell::new(None);
+    static SNAPSHOT_HISTORY: RefCell<VecDeque<PortfolioSnapshot>> = RefCell::new(VecDeque::new());
+    static NAV_EXTREMES: RefCell<Option<NavExtremes>> = RefCell::new(None);
+}
+
+/// NAV per ICPI in e8 USD: total_value_e6 × 1e10 ÷ supply_e8
+///
+/// With zero supply NAV is defined as 1.0 (initial mint is 1 ICPI = 1 ckUSDT).
+pub fn calculate_nav_e8(total_value_e6: u64, supply_e8: &Nat) -> Result<u64> {
+    if supply_e8 == &Nat::from(0u64) {
+        return Ok(100_000_000);
+    }
+
+    let nav = multiply_and_divide(
+        &Nat::from(total_value_e6),
+        &Nat::from(10_000_000_000u64),
+        supply_e8,
+    )?;
+
+    num_traits::ToPrimitive::to_u64(&nav.0).ok_or_else(|| {
+        crate::infrastructure::IcpiError::Calculation(crate::infrastructure::CalculationError::Overflow {
+            operation: format!("nav_e8 for value {} and supply {}", total_value_e6, supply_e8),
+        })
+    })
+}
+
+/// Record a new snapshot
+///
+/// Always replaces the latest snapshot; only appends to the history if the
+/// previous history entry is at least one snapshot interval old.
+pub fn record_snapshot(snapshot: PortfolioSnapshot) {
+    SNAPSHOT_HISTORY.with(|h| {
+        let mut history = h.borrow_mut();
+        if should_append(history.back(), snapshot.timestamp) {
+            history.push_back(snapshot.clone());
+            while history.len() > MAX_PORTFOLIO_SNAPSHOTS {
+                history.pop_front();
+            }
+        }
+    });
+
+    NAV_EXTREMES.with(|e| {
+        let updated = update_extremes(e.borrow().as_ref(), &snapshot);
+        *e.borrow_mut() = Some(updated);
+    });
+
+    LATEST_SNAPSHOT.with(|l| *l.borrow_mut() = Some(snapshot));
+}
+
+/// Drawdown of `nav` below `high` in basis points (0 at or above the high)
+pub fn drawdown_bps(high: u64, nav: u64) -> u64 {
+    if high == 0 || nav >= high {
+        return 0;
+    }
+    ((high - nav) as u128 * 10_000 / high as u128) as u64
+}
+
+/// Fold a new snapshot into the previous extremes
+pub fn update_extremes(previous: Option<&NavExtremes>, snapshot: &PortfolioSnapshot) -> NavExtremes {
+    let nav = snapshot.nav_e8;
+    let ts = snapshot.timestamp;
+
+    let (ath_nav_e8, ath_timestamp, atl_nav_e8, atl_timestamp, max_drawdown_bps) = match previous {
+        None => (nav, ts, nav, ts, 0),
+        Some(p) => {
+            let (ath, ath_ts) = if nav > p.ath_nav_e8 { (nav, ts) } else { (p.ath_nav_e8, p.ath_timestamp) };
+            let (atl, atl_ts) = if nav < p.atl_nav_e8 { (nav, ts) } else { (p.atl_nav_e8, p.atl_timestamp) };
+            (ath, ath_ts, atl, atl_ts, p.max_drawdown_bps)
+        }
+    };
+
+    let current_drawdown_bps = drawdown_bps(ath_nav_e8, nav);
+    NavExtremes {
+        ath_nav_e8,
+        ath_timestamp,
+        atl_nav_e8,
+        atl_timestamp,
+        current_nav_e8: nav,
+        current_drawdown_bps,
+        max_drawdown_bps: max_drawdown_bps.max(current_drawdown_bps),
+        updated_at: ts,
+    }
+}
+
+/// Current NAV extremes, if any snapshot has been recorded
+pub fn get_nav_extremes() -> Option<NavExtremes> {
+    NAV_EXTREMES.with(|e| e.borrow().clone())
+}
+
+/// Export NAV extremes for stable storage
+pub fn export_nav_extremes() -> Option<NavExtremes> {
+    get_nav_extremes()
+}
+
+/// Import NAV extremes from stable storage
+///
+/// State saved before extremes were tracked has none; they are rebuilt
+/// from the (already imported) snapshot history instead.
+pub fn import_nav_extremes(extremes: Option<NavExtremes>) {
+    let extremes = extremes.or_else(|| {
+        get_snapshot_history().iter().fold(None, |acc, s| Some(update_extremes(acc.as_ref(), s)))
+    });
+    NAV_EXTREMES.with(|e| *e.borrow_mut() = extremes);
+}
+
+fn should_append(last: Option<&PortfolioSnapshot>, now: u64) -> bool {
+    match last {
+        Some(last) => now.saturating_sub(last.timestamp) >= PORTFOLIO_SNAPSHOT_INTERVAL_SECONDS * 1_000_000_000,
+        None => true,
+    }
+}
+
+/// Get the most recently recorded snapshot
+pub fn get_latest_snapshot() -> Option<PortfolioSnapshot> {
+    LATEST_SNAPSHOT.with(|l| l.borrow().clone())
+}
+
+/// Get all snapshots in the history (oldest first)
+pub fn get_snapshot_history() -> Vec<PortfolioSnapshot> {
+    SNAPSHOT_HISTORY.with(|h| h.borrow().iter().cloned().collect())
+}
+
+/// Find the snapshot closest to `target` within `tolerance_nanos`
+pub fn find_closest_snapshot(
+    history: &[PortfolioSnapshot],
+    target: u64,
+    tolerance_nanos: u64,
+) -> Option<&PortfolioSnapshot> {
+    history.iter()
+        .filter(|s| s.timestamp.abs_diff(target) <= tolerance_nanos)
+        .min_by_key(|s| s.timestamp.abs_diff(target))
+}
+
+/// Change from `old` to `new` in basis points, truncated toward zero
+pub fn calculate_change_bps(old: u64, new: u64) -> Option<i64> {
+    if old == 0 {
+        return None;
+    }
+    let diff = new as i128 - old as i128;
+    Some((diff * 10_000 / old as i128) as i64)
+}
+
+/// Page of `symbol`'s weight over the snapshot history (oldest first),
+/// with the total number of points
+///
+/// Snapshots recorded before weights were kept are skipped.
+pub fn get_token_weight_history(symbol: &str, offset: u64, limit: u64) -> Result<(Vec<TokenWeightPoint>, u64)> {
+    let token = TrackedToken::from_symbol(symbol).map_err(IcpiError::Other)?;
+    Ok(SNAPSHOT_HISTORY.with(|h| token_weight_series(h.borrow().iter(), &token, offset, limit)))
+}
+
+fn token_weight_series<'a>(
+    snapshots: impl Iterator<Item = &'a PortfolioSnapshot>,
+    token: &TrackedToken,
+    offset: u64,
+    limit: u64,
+) -> (Vec<TokenWeightPoint>, u64) {
+    let points: Vec<TokenWeightPoint> = snapshots
+        .filter_map(|s| {
+            let weights = s.token_weights_bps.as_ref()?;
+            let bps = weights.iter().find(|(t, _)| t == token).map_or(0, |(_, bps)| *bps);
+            Some(TokenWeightPoint { timestamp: s.timestamp, weight_pct: bps as f64 / 100.0 })
+        })
+        .collect();
+
+    let total = points.len() as u64;
+    let page = points.into_iter().skip(offset as usize).take(limit as usize).collect();
+    (page, total)
+}
+
+/// Export history for stable storage
+pub fn export_history() -> Vec<PortfolioSnapshot> {
+    get_snapshot_history()
+}
+
+/// Import history from stable storage
+pub fn import_history(snapshots: Vec<PortfolioSnapshot>) {
+    let latest = snapshots.last().cloned();
+    SNAPSHOT_HISTORY.with(|h| *h.borrow_mut() = snapshots.into_iter().collect());
+    LATEST_SNAPSHOT.with(|l| *l.borrow_mut() = latest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3_600_000_000_000;
+
+    fn snapshot(timestamp: u64, nav_e8: u64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp,
+            total_value_e6: 0,
+            supply_e8: Nat::from(0u64),
+            nav_e8,
+            tvl_report: None,
+            token_weights_bps: None,
+        }
+    }
+
+    #[test]
+    fn test_nav_zero_supply_is_one() {
+        assert_eq!(calculate_nav_e8(0, &Nat::from(0u64)).unwrap(), 100_000_000);
+    }
+
+    #[test]
+    fn test_nav_calculation() {
+        // $1000 TVL, 500 ICPI supply -> $2.00 NAV
+        let nav = calculate_nav_e8(1_000_000_000, &Nat::from(50_000_000_000u64)).unwrap();
+        assert_eq!(nav, 200_000_000);
+    }
+
+    #[test]
+    fn test_change_bps() {
+        assert_eq!(calculate_change_bps(100_000_000, 105_000_000), Some(500));
+        assert_eq!(calculate_change_bps(100_000_000, 95_000_000), Some(-500));
+        assert_eq!(calculate_change_bps(100_000_000, 100_000_000), Some(0));
+        // Truncates toward zero
+        assert_eq!(calculate_change_bps(3, 4), Some(3333));
+        assert_eq!(calculate_change_bps(0, 100), None);
+    }
+
+    #[test]
+    fn test_find_closest_snapshot() {
+        let history = vec![
+            snapshot(0, 1),
+            snapshot(10 * HOUR, 2),
+            snapshot(23 * HOUR, 3),
+            snapshot(25 * HOUR, 4),
+        ];
+
+        // Closest to 24h is 23h (tie broken by first minimum)
+        let found = find_closest_snapshot(&history, 24 * HOUR, 2 * HOUR).unwrap();
+        assert_eq!(found.nav_e8, 3);
+
+        let found = find_closest_snapshot(&history, 11 * HOUR, 2 * HOUR).unwrap();
+        assert_eq!(found.nav_e8, 2);
+
+        // Nothing within tolerance
+        assert!(find_closest_snapshot(&history, 5 * HOUR, 2 * HOUR).is_none());
+    }
+
+    #[test]
+    fn test_token_weight_history() {
+        let weighted = |timestamp, weights: Vec<(TrackedToken, u32)>| PortfolioSnapshot {
+            token_weights_bps: Some(weights),
+            ..snapshot(timestamp, 1)
+        };
+        let interval = PORTFOLIO_SNAPSHOT_INTERVAL_SECONDS * 1_000_000_000;
+
+        // Recorded before weights were kept
+        record_snapshot(snapshot(0, 1));
+        record_snapshot(weighted(interval, vec![(TrackedToken::ALEX, 4_000), (TrackedToken::KONG, 6_000)]));
+        record_snapshot(weighted(2 * interval, vec![(TrackedToken::ALEX, 3_550), (TrackedToken::KONG, 6_450)]));
+        // ALEX fully sold
+        record_snapshot(weighted(3 * interval, vec![(TrackedToken::KONG, 10_000)]));
+
+        let (alex, total) = get_token_weight_history("ALEX", 0, 10).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(alex, vec![
+            TokenWeightPoint { timestamp: interval, weight_pct: 40.0 },
+            TokenWeightPoint { timestamp: 2 * interval, weight_pct: 35.5 },
+            TokenWeightPoint { timestamp: 3 * interval, weight_pct: 0.0 },
+        ]);
+
+        let (kong, total) = get_token_weight_history("KONG", 1, 1).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(kong, vec![TokenWeightPoint { timestamp: 2 * interval, weight_pct: 64.5 }]);
+
+        assert!(get_token_weight_history("KONG", 5, 10).unwrap().0.is_empty());
+        assert!(get_token_weight_history("DOGE", 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_history_throttling() {
+        assert!(should_append(None, 0));
+        let last = snapshot(HOUR, 1);
+        assert!(!should_append(Some(&last), HOUR + 1));
+        let interval = PORTFOLIO_SNAPSHOT_INTERVAL_SECONDS * 1_000_000_000;
+        assert!(should_append(Some(&last), HOUR + interval));
+    }
+
+    fn fold(navs: &[u64]) -> NavExtremes {
+        navs.iter().enumerate()
+            .fold(None, |acc, (i, nav)| Some(update_extremes(acc.as_ref(), &snapshot(i as u64 * HOUR, *nav))))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_extremes_first_snapshot() {
+        let e = fold(&[100_000_000]);
+        assert_eq!((e.ath_nav_e8, e.atl_nav_e8), (100_000_000, 100_000_000));
+        assert_eq!((e.current_drawdown_bps, e.max_drawdown_bps), (0, 0));
+    }
+
+    #[test]
+    fn test_extremes_drawdown_and_recovery() {
+        // 1.00 → 1.20 (high) → 0.90 (25% down) → 1.08 → 1.30 (new high) → 1.17
+        let e = fold(&[100_000_000, 120_000_000, 90_000_000, 108_000_000]);
+        assert_eq!(e.ath_nav_e8, 120_000_000);
+        assert_eq!(e.ath_timestamp, HOUR);
+        assert_eq!(e.atl_nav_e8, 90_000_000);
+        assert_eq!(e.atl_timestamp, 2 * HOUR);
+        assert_eq!(e.current_drawdown_bps, 1_000);
+        assert_eq!(e.max_drawdown_bps, 2_500);
+
+        let e = update_extremes(Some(&e), &snapshot(4 * HOUR, 130_000_000));
+        assert_eq!(e.ath_nav_e8, 130_000_000);
+        assert_eq!(e.ath_timestamp, 4 * HOUR);
+        assert_eq!(e.current_drawdown_bps, 0);
+        // The old drawdown stays the maximum
+        assert_eq!(e.max_drawdown_bps, 2_500);
+
+        let e = update_extremes(Some(&e), &snapshot(5 * HOUR, 117_000_000));
+        assert_eq!(e.current_drawdown_bps, 1_000);
+        assert_eq!(e.atl_nav_e8, 90_000_000);
+    }
+
+    #[test]
+    fn test_extremes_never_recovering() {
+        let e = fold(&[100_000_000, 80_000_000, 50_000_000]);
+        assert_eq!(e.ath_nav_e8, 100_000_000);
+        assert_eq!(e.atl_nav_e8, 50_000_000);
+        assert_eq!(e.current_drawdown_bps, 5_000);
+        assert_eq!(e.max_drawdown_bps, 5_000);
+        assert_eq!(drawdown_bps(0, 0), 0);
+    }
+
+    #[test]
+    fn test_extremes_rebuilt_from_history_on_restore() {
+        import_history(vec![snapshot(0, 100_000_000), snapshot(HOUR, 150_000_000), snapshot(2 * HOUR, 120_000_000)]);
+        import_nav_extremes(None);
+        let e = get_nav_extremes().unwrap();
+        assert_eq!(e.ath_nav_e8, 150_000_000);
+        assert_eq!(e.current_drawdown_bps, 2_000);
+
+        // Persisted extremes win over the (bounded) history
+        let saved = NavExtremes { ath_nav_e8: 200_000_000, ..e.clone() };
+        import_nav_extremes(Some(saved.clone()));
+        assert_eq!(get_nav_extremes(), Some(saved));
+    }
+}
@@ -0,0 +1,256 @@
+//! ICPI holder count
+//!
+//! ICRC-1 ledgers have no holder-count query, so balances are rebuilt by
+//! replaying the ledger's transaction log (archives included) and counting
+//! accounts with a nonzero balance. The replay is incremental: each call
+//! indexes at most `HOLDER_SYNC_MAX_BATCHES` pages past where the last one
+//! stopped. The index lives on the heap and is rebuilt after an upgrade.
+//!
+//! Transfers and approvals that don't record a fee were charged the ledger's
+//! current fee; a fee change since then can leave an emptied account with a
+//! dust balance, so the count is a close estimate rather than exact.
+
+use candid::{CandidType, Deserialize, Nat};
+use num_traits::ToPrimitive;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use crate::infrastructure::{Result, IcpiError, SystemError};
+use crate::infrastructure::constants::{HOLDER_COUNT_CACHE_TTL_SECONDS, HOLDER_SYNC_BATCH_SIZE, HOLDER_SYNC_MAX_BATCHES};
+use crate::infrastructure::ledger_client::icpi_ledger;
+use crate::types::icrc::{LedgerAccount, LedgerTransaction};
+
+/// Holder count as returned by `get_holder_count`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HolderCount {
+    /// Accounts with a nonzero ICPI balance
+    pub holders: u64,
+    /// Ledger transactions replayed into the count
+    pub transactions_indexed: u64,
+    /// Length of the ledger's log when counted; more than indexed while catching up
+    pub log_length: u64,
+    pub computed_at: u64,
+}
+
+/// Balances rebuilt from the transaction log
+#[derive(Clone, Debug, Default)]
+pub struct HolderIndex {
+    /// Only nonzero balances are kept
+    balances: BTreeMap<LedgerAccount, Nat>,
+    next_index: u64,
+}
+
+impl HolderIndex {
+    pub fn holders(&self) -> u64 {
+        self.balances.len() as u64
+    }
+
+    fn credit(&mut self, account: &LedgerAccount, amount: &Nat) {
+        if *amount == Nat::from(0u64) {
+            return;
+        }
+        let balance = self.balances.entry(account.clone()).or_insert_with(|| Nat::from(0u64));
+        *balance += amount.clone();
+    }
+
+    /// Debit, clamping at zero; a zero balance drops the account
+    fn debit(&mut self, account: &LedgerAccount, amount: &Nat) {
+        let emptied = match self.balances.get_mut(account) {
+            Some(balance) if *balance > *amount => {
+                *balance -= amount.clone();
+                false
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if emptied {
+            self.balances.remove(account);
+        }
+    }
+
+    /// Apply the next transaction of the log
+    ///
+    /// `default_fee` is charged when the transaction doesn't record one.
+    pub fn apply(&mut self, tx: &LedgerTransaction, default_fee: &Nat) {
+        if let Some(mint) = &tx.mint {
+            self.credit(&mint.to, &mint.amount);
+        }
+        if let Some(burn) = &tx.burn {
+            self.debit(&burn.from, &burn.amount);
+        }
+        if let Some(transfer) = &tx.transfer {
+            let fee = transfer.fee.as_ref().unwrap_or(default_fee);
+            self.debit(&transfer.from, &(transfer.amount.clone() + fee.clone()));
+            self.credit(&transfer.to, &transfer.amount);
+        }
+        if let Some(approve) = &tx.approve {
+            self.debit(&approve.from, approve.fee.as_ref().unwrap_or(default_fee));
+        }
+        self.next_index += 1;
+    }
+}
+
+thread_local! {
+    static INDEX: RefCell<HolderIndex> = RefCell::new(HolderIndex::default());
+    static CACHED: RefCell<Option<HolderCount>> = RefCell::new(None);
+    static SYNC_IN_PROGRESS: RefCell<bool> = RefCell::new(false);
+}
+
+fn is_fresh(count: &HolderCount, now: u64) -> bool {
+    now.saturating_sub(count.computed_at) < HOLDER_COUNT_CACHE_TTL_SECONDS * 1_000_000_000
+}
+
+fn nat_to_u64(value: &Nat, field: &str) -> Result<u64> {
+    value.0.to_u64()
+        .ok_or_else(|| IcpiError::Other(format!("ICPI ledger {} out of range: {}", field, value)))
+}
+
+/// Replay up to `HOLDER_SYNC_MAX_BATCHES` pages of the log into `index`
+///
+/// Returns the log length reported by the ledger.
+async fn sync(index: &mut HolderIndex) -> Result<u64> {
+    let fee = icpi_ledger::fee().await?;
+    let mut log_length = index.next_index;
+
+    for _ in 0..HOLDER_SYNC_MAX_BATCHES {
+        let page = icpi_ledger::get_transactions(index.next_index, HOLDER_SYNC_BATCH_SIZE).await?;
+        log_length = nat_to_u64(&page.log_length, "log_length")?;
+
+        let mut archived = page.archived_transactions;
+        archived.sort_by(|a, b| a.start.cmp(&b.start));
+        for range in archived {
+            if nat_to_u64(&range.start, "archive start")? != index.next_index {
+                return Err(IcpiError::Other(format!(
+                    "ICPI archive range starts at {}, expected {}", range.start, index.next_index
+                )));
+            }
+            let transactions = icpi_ledger::get_archived_transactions(&range.callback, range.start, range.length).await?;
+            for tx in &transactions {
+                index.apply(tx, &fee);
+            }
+        }
+
+        if !page.transactions.is_empty() && nat_to_u64(&page.first_index, "first_index")? != index.next_index {
+            return Err(IcpiError::Other(format!(
+                "ICPI ledger page starts at {}, expected {}", page.first_index, index.next_index
+            )));
+        }
+        for tx in &page.transactions {
+            index.apply(tx, &fee);
+        }
+
+        if index.next_index >= log_length {
+            break;
+        }
+    }
+    Ok(log_length)
+}
+
+/// Number of ICPI holders, cached for `HOLDER_COUNT_CACHE_TTL_SECONDS`
+pub async fn get_holder_count() -> Result<HolderCount> {
+    let now = crate::infrastructure::runtime::time();
+    if let Some(cached) = CACHED.with(|c| c.borrow().clone()).filter(|c| is_fresh(c, now)) {
+        return Ok(cached);
+    }
+
+    let Some(run_guard) = crate::infrastructure::reentrancy::RunFlagGuard::try_acquire(&SYNC_IN_PROGRESS) else {
+        return CACHED.with(|c| c.borrow().clone()).ok_or_else(|| IcpiError::System(SystemError::OperationInProgress {
+            operation: "holder count".to_string(),
+            user: "system".to_string(),
+        }));
+    };
+
+    // Sync a copy so an interrupted replay never leaves a half-applied page behind
+    let mut index = INDEX.with(|i| i.borrow().clone());
+    let result = sync(&mut index).await;
+    drop(run_guard);
+    let log_length = result?;
+
+    let count = HolderCount {
+        holders: index.holders(),
+        transactions_indexed: index.next_index,
+        log_length,
+        computed_at: crate::infrastructure::runtime::time(),
+    };
+    INDEX.with(|i| *i.borrow_mut() = index);
+    // Keep recounting on every call until the replay has caught up
+    if count.transactions_indexed >= count.log_length {
+        CACHED.with(|c| *c.borrow_mut() = Some(count.clone()));
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+    use crate::types::icrc::{LedgerApprove, LedgerBurn, LedgerMint, LedgerTransfer};
+
+    fn account(text: &str) -> LedgerAccount {
+        LedgerAccount { owner: Principal::from_text(text).unwrap(), subaccount: None }
+    }
+
+    fn tx(kind: &str) -> LedgerTransaction {
+        LedgerTransaction { kind: kind.to_string(), mint: None, burn: None, transfer: None, approve: None, timestamp: 0 }
+    }
+
+    fn mint(to: &LedgerAccount, amount: u64) -> LedgerTransaction {
+        LedgerTransaction { mint: Some(LedgerMint { to: to.clone(), amount: Nat::from(amount), memo: None }), ..tx("mint") }
+    }
+
+    fn burn(from: &LedgerAccount, amount: u64) -> LedgerTransaction {
+        LedgerTransaction { burn: Some(LedgerBurn { from: from.clone(), amount: Nat::from(amount) }), ..tx("burn") }
+    }
+
+    fn transfer(from: &LedgerAccount, to: &LedgerAccount, amount: u64, fee: Option<u64>) -> LedgerTransaction {
+        LedgerTransaction {
+            transfer: Some(LedgerTransfer {
+                from: from.clone(),
+                to: to.clone(),
+                amount: Nat::from(amount),
+                fee: fee.map(Nat::from),
+            }),
+            ..tx("transfer")
+        }
+    }
+
+    #[test]
+    fn test_holder_count_from_transaction_history() {
+        let alice = account("2vxsx-fae");
+        let bob = account("aaaaa-aa");
+        let carol = account("rrkah-fqaaa-aaaaa-aaaaq-cai");
+        let fee = Nat::from(10_000u64);
+        let mut index = HolderIndex::default();
+
+        let history = vec![
+            mint(&alice, 1_000_000),
+            mint(&bob, 500_000),
+            // Alice sends everything to Carol; the ledger's default fee empties her account
+            transfer(&alice, &carol, 990_000, None),
+            // Approval fee comes out of Carol's balance
+            LedgerTransaction { approve: Some(LedgerApprove { from: carol.clone(), fee: None }), ..tx("approve") },
+            // Bob burns his whole balance
+            burn(&bob, 500_000),
+            // A zero mint doesn't create a holder
+            mint(&bob, 0),
+            transfer(&carol, &bob, 100_000, Some(10_000)),
+        ];
+        for t in &history {
+            index.apply(t, &fee);
+        }
+
+        assert_eq!(index.next_index, history.len() as u64);
+        assert_eq!(index.holders(), 2);
+        assert_eq!(index.balances.get(&carol), Some(&Nat::from(870_000u64)));
+        assert_eq!(index.balances.get(&bob), Some(&Nat::from(100_000u64)));
+        assert!(!index.balances.contains_key(&alice));
+    }
+
+    #[test]
+    fn test_holder_count_cache_ttl() {
+        let count = HolderCount { holders: 3, transactions_indexed: 10, log_length: 10, computed_at: 1_000 };
+        assert!(is_fresh(&count, 1_000 + (HOLDER_COUNT_CACHE_TTL_SECONDS * 1_000_000_000) - 1));
+        assert!(!is_fresh(&count, 1_000 + HOLDER_COUNT_CACHE_TTL_SECONDS * 1_000_000_000));
+    }
+}
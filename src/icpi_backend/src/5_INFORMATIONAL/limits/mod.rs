@@ -0,0 +1,309 @@
+//! Per-user mint/burn limits
+//!
+//! Lets frontends pre-validate inputs. Every limit is derived from the same
+//! validator functions and constants that enforce it, so the reported
+//! maxima can't drift from what `complete_mint`/`burn_icpi` accept.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use crate::infrastructure::{Result, IcpiError, MIN_MINT_AMOUNT, MAX_MINT_AMOUNT, MIN_BURN_AMOUNT, MIN_TRADE_SIZE_USD};
+use crate::types::TrackedToken;
+use crate::infrastructure::rate_limiting::{operation_wait_nanos, RateLimitedOperation};
+use crate::_1_CRITICAL_OPERATIONS::minting::mint_validator;
+use crate::_1_CRITICAL_OPERATIONS::burning::burn_validator;
+use crate::_1_CRITICAL_OPERATIONS::operation_status;
+
+/// What determines an operation's current limit
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitConstraint {
+    Paused,
+    AccessDenied,
+    OperationInProgress,
+    RateLimited,
+    MaximumAmount,
+    SupplyCap,
+    Balance,
+}
+
+/// Allowed amount range for one operation
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OperationLimit {
+    pub allowed: bool,
+    pub min_amount: Nat,
+    pub max_amount: Nat,
+    pub binding_constraint: LimitConstraint,
+    pub reason: Option<String>,
+}
+
+/// Current mint and burn limits for a user
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UserLimits {
+    pub user: Principal,
+    /// ckUSDT (e6)
+    pub mint: OperationLimit,
+    /// ICPI (e8)
+    pub burn: OperationLimit,
+    pub timestamp: u64,
+}
+
+/// A token amount in the token's native units
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenAmount {
+    pub symbol: String,
+    pub amount: Nat,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    fn new(token: &TrackedToken, amount: Nat) -> Self {
+        TokenAmount { symbol: token.to_symbol().to_string(), amount, decimals: token.get_decimals() }
+    }
+}
+
+/// Minimum thresholds, as `get_minimums` returns them
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct Minimums {
+    /// Smallest ckUSDT deposit `complete_mint` accepts
+    pub mint_deposit: TokenAmount,
+    /// Smallest ICPI amount `burn_icpi` accepts
+    pub burn: TokenAmount,
+    /// Smallest trade the rebalancer makes, in USD
+    pub trade_size_usd: f64,
+    /// Smallest redemption sent per token; less is left as dust
+    pub redemptions: Vec<TokenAmount>,
+}
+
+/// Minimum thresholds under the current ledger fees
+pub fn get_minimums() -> Minimums {
+    use crate::_1_CRITICAL_OPERATIONS::burning::redemption_calculator::min_redemption;
+
+    let icpi = TokenAmount {
+        symbol: "ICPI".to_string(),
+        amount: Nat::from(MIN_BURN_AMOUNT),
+        decimals: crate::infrastructure::constants::ICPI_DECIMALS as u8,
+    };
+    let redemptions = std::iter::once(&TrackedToken::ckUSDT)
+        .chain(TrackedToken::all())
+        .map(|token| TokenAmount::new(token, min_redemption(
+            crate::infrastructure::ledger_fees::transfer_fee(token.to_symbol())
+        )))
+        .collect();
+
+    Minimums {
+        mint_deposit: TokenAmount::new(&TrackedToken::ckUSDT, Nat::from(MIN_MINT_AMOUNT)),
+        burn: icpi,
+        trade_size_usd: MIN_TRADE_SIZE_USD,
+        redemptions,
+    }
+}
+
+type Blocker = (LimitConstraint, String);
+
+fn blocked(min_amount: u64, (constraint, reason): Blocker) -> OperationLimit {
+    OperationLimit {
+        allowed: false,
+        min_amount: Nat::from(min_amount),
+        max_amount: Nat::from(0u64),
+        binding_constraint: constraint,
+        reason: Some(reason),
+    }
+}
+
+/// Mint limit given the first blocking check (if any)
+pub fn aggregate_mint_limit(blocker: Option<Blocker>) -> OperationLimit {
+    match blocker {
+        Some(blocker) => blocked(MIN_MINT_AMOUNT, blocker),
+        None => OperationLimit {
+            allowed: true,
+            min_amount: Nat::from(MIN_MINT_AMOUNT),
+            max_amount: Nat::from(MAX_MINT_AMOUNT),
+            binding_constraint: LimitConstraint::MaximumAmount,
+            reason: None,
+        },
+    }
+}
+
+/// Burn limit given the first blocking check, current supply and user balance
+///
+/// The maximum is the lower of the per-transaction supply cap and the
+/// user's balance; if that falls below the minimum burn, burning is blocked.
+pub fn aggregate_burn_limit(blocker: Option<Blocker>, supply: &Nat, balance: &Nat) -> Result<OperationLimit> {
+    if let Some(blocker) = blocker {
+        return Ok(blocked(MIN_BURN_AMOUNT, blocker));
+    }
+
+    let supply_cap = burn_validator::max_burn_amount(supply)?;
+    let (max_amount, binding_constraint) = if balance < &supply_cap {
+        (balance.clone(), LimitConstraint::Balance)
+    } else {
+        (supply_cap, LimitConstraint::SupplyCap)
+    };
+
+    let min_amount = Nat::from(MIN_BURN_AMOUNT);
+    if max_amount < min_amount {
+        return Ok(OperationLimit {
+            allowed: false,
+            reason: Some(format!(
+                "Maximum burnable {} is below the minimum burn {}",
+                max_amount, min_amount
+            )),
+            min_amount,
+            max_amount: Nat::from(0u64),
+            binding_constraint,
+        });
+    }
+
+    Ok(OperationLimit {
+        allowed: true,
+        min_amount,
+        max_amount,
+        binding_constraint,
+        reason: None,
+    })
+}
+
+/// First failing check, in enforcement order
+fn first_blocker(checks: Vec<(LimitConstraint, Result<()>)>) -> Option<Blocker> {
+    checks.into_iter()
+        .find_map(|(constraint, check)| check.err().map(|e| (constraint, e.to_string())))
+}
+
+fn rate_limit_check(op: RateLimitedOperation, user: &Principal) -> Result<()> {
+    let wait = operation_wait_nanos(op, user);
+    if wait > 0 {
+        return Err(IcpiError::Other(format!(
+            "Rate limit exceeded. Please wait {} seconds",
+            wait.div_ceil(1_000_000_000)
+        )));
+    }
+    Ok(())
+}
+
+/// Compute current mint and burn limits for `user`
+pub async fn get_user_limits(user: Principal) -> Result<UserLimits> {
+    let (supply, balance) = futures::join!(
+        crate::_2_CRITICAL_DATA::supply_tracker::get_icpi_supply_uncached(),
+        crate::infrastructure::icpi_ledger::balance_of(user)
+    );
+    let (supply, balance) = (supply?, balance?);
+
+    let mint_blocker = first_blocker(vec![
+        (LimitConstraint::Paused, crate::infrastructure::check_not_paused()),
+        (LimitConstraint::AccessDenied, mint_validator::validate_mint_caller(&user)),
+        (LimitConstraint::OperationInProgress, operation_status::check_mint_allowed(&user)),
+        (LimitConstraint::RateLimited, rate_limit_check(RateLimitedOperation::Mint, &user)),
+    ]);
+
+    let burn_blocker = first_blocker(vec![
+        (LimitConstraint::Paused, crate::infrastructure::check_not_paused()),
+        (LimitConstraint::AccessDenied, burn_validator::validate_burn_caller(&user)),
+        (LimitConstraint::OperationInProgress, operation_status::check_burn_allowed(&user)),
+        (LimitConstraint::RateLimited, rate_limit_check(RateLimitedOperation::Burn, &user)),
+    ]);
+
+    Ok(UserLimits {
+        user,
+        mint: aggregate_mint_limit(mint_blocker),
+        burn: aggregate_burn_limit(burn_blocker, &supply, &balance)?,
+        timestamp: crate::infrastructure::runtime::time(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICPI: u64 = 100_000_000;
+
+    #[test]
+    fn test_open_mint_limit_is_max_amount() {
+        let limit = aggregate_mint_limit(None);
+        assert!(limit.allowed);
+        assert_eq!(limit.min_amount, Nat::from(MIN_MINT_AMOUNT));
+        assert_eq!(limit.max_amount, Nat::from(MAX_MINT_AMOUNT));
+        assert_eq!(limit.binding_constraint, LimitConstraint::MaximumAmount);
+    }
+
+    #[test]
+    fn test_blocked_mint_reports_reason() {
+        let limit = aggregate_mint_limit(Some((LimitConstraint::Paused, "paused".to_string())));
+        assert!(!limit.allowed);
+        assert_eq!(limit.max_amount, Nat::from(0u64));
+        assert_eq!(limit.binding_constraint, LimitConstraint::Paused);
+        assert_eq!(limit.reason.as_deref(), Some("paused"));
+    }
+
+    #[test]
+    fn test_burn_limited_by_supply_cap() {
+        // 10% of 1000 ICPI = 100 ICPI, user holds 500
+        let supply = Nat::from(1000 * ICPI);
+        let limit = aggregate_burn_limit(None, &supply, &Nat::from(500 * ICPI)).unwrap();
+        assert!(limit.allowed);
+        assert_eq!(limit.max_amount, Nat::from(100 * ICPI));
+        assert_eq!(limit.binding_constraint, LimitConstraint::SupplyCap);
+    }
+
+    #[test]
+    fn test_burn_limited_by_balance() {
+        let supply = Nat::from(1000 * ICPI);
+        let limit = aggregate_burn_limit(None, &supply, &Nat::from(5 * ICPI)).unwrap();
+        assert!(limit.allowed);
+        assert_eq!(limit.max_amount, Nat::from(5 * ICPI));
+        assert_eq!(limit.binding_constraint, LimitConstraint::Balance);
+    }
+
+    #[test]
+    fn test_burn_below_minimum_is_blocked() {
+        let limit = aggregate_burn_limit(None, &Nat::from(1000 * ICPI), &Nat::from(MIN_BURN_AMOUNT - 1)).unwrap();
+        assert!(!limit.allowed);
+        assert_eq!(limit.binding_constraint, LimitConstraint::Balance);
+        assert!(limit.reason.is_some());
+
+        let limit = aggregate_burn_limit(None, &Nat::from(0u64), &Nat::from(5 * ICPI)).unwrap();
+        assert!(!limit.allowed);
+        assert_eq!(limit.binding_constraint, LimitConstraint::SupplyCap);
+    }
+
+    #[test]
+    fn test_minimums_match_enforcement() {
+        let minimums = get_minimums();
+        assert_eq!(minimums.mint_deposit.amount, Nat::from(MIN_MINT_AMOUNT));
+        assert_eq!(minimums.mint_deposit.decimals, 6);
+        assert_eq!(minimums.burn.amount, Nat::from(MIN_BURN_AMOUNT));
+        assert_eq!(minimums.burn.decimals, 8);
+        assert_eq!(minimums.trade_size_usd, MIN_TRADE_SIZE_USD);
+
+        let below = |n: &Nat| n.clone() - Nat::from(1u64);
+
+        // Every tracked token and ckUSDT, each at its dust threshold
+        use crate::_1_CRITICAL_OPERATIONS::burning::redemption_calculator::redemption_after_fee;
+        assert_eq!(minimums.redemptions.len(), TrackedToken::all().len() + 1);
+        for min in &minimums.redemptions {
+            let fee = crate::infrastructure::ledger_fees::transfer_fee(&min.symbol);
+            assert!(redemption_after_fee(&min.amount, fee).is_some(), "{}", min.symbol);
+            assert!(redemption_after_fee(&below(&min.amount), fee).is_none(), "{}", min.symbol);
+        }
+    }
+
+    #[test]
+    fn test_reported_burn_max_matches_enforcement() {
+        // Odd supply so the 10% cap rounds
+        let supply = Nat::from(1_234_567_891u64);
+        let limit = aggregate_burn_limit(None, &supply, &Nat::from(u64::MAX)).unwrap();
+
+        assert!(burn_validator::validate_burn_limit(&limit.max_amount, &supply).is_ok());
+        let over = limit.max_amount.clone() + Nat::from(1u64);
+        assert!(burn_validator::validate_burn_limit(&over, &supply).is_err());
+    }
+
+    #[test]
+    fn test_first_blocker_respects_order() {
+        let blocker = first_blocker(vec![
+            (LimitConstraint::Paused, Ok(())),
+            (LimitConstraint::AccessDenied, Err(IcpiError::Other("denied".to_string()))),
+            (LimitConstraint::RateLimited, Err(IcpiError::Other("slow down".to_string()))),
+        ]);
+        assert_eq!(blocker, Some((LimitConstraint::AccessDenied, "denied".to_string())));
+
+        assert!(first_blocker(vec![(LimitConstraint::Paused, Ok(()))]).is_none());
+    }
+}
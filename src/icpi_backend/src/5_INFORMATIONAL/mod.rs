@@ -4,9 +4,21 @@
 pub mod display;
 pub mod health;
 pub mod cache;
+pub mod history;
+pub mod limits;
+pub mod costs;
+pub mod status_message;
+pub mod daily_totals;
+pub mod build_info;
+pub mod diagnostics;
+pub mod holders;
+pub mod approvals;
+pub mod amounts;
+pub mod nav_quotes;
+pub mod user_activity;
 
 // Re-export main functions
-pub use display::get_index_state_cached;
+pub use display::{get_index_state_cached, get_ticker};
 pub use health::{get_health_status, get_tracked_tokens};
 pub use cache::clear_all_caches;
 
@@ -0,0 +1,267 @@
+//! Index share price in other quote currencies
+//!
+//! NAV is computed in ckUSDT. Other quotes convert the last computed NAV
+//! and portfolio value through the quote asset's Kongswap rate into
+//! ckUSDT. Rates are cached for `QUOTE_RATE_CACHE_TTL_SECONDS`; when a
+//! refresh fails the last rate is served with `is_stale` set.
+
+use candid::{CandidType, Deserialize, Nat};
+use std::cell::RefCell;
+use crate::_3_KONG_LIQUIDITY::pools::{PriceRate, PriceSource, QuoteAsset};
+use crate::infrastructure::{Result, IcpiError, ValidationError, multiply_and_divide};
+use crate::infrastructure::constants::QUOTE_RATE_CACHE_TTL_SECONDS;
+use crate::types::TrackedToken;
+use crate::types::portfolio::Ticker;
+
+/// Decimals of every quoted NAV, matching `Ticker::nav_e8`
+pub const NAV_QUOTE_DECIMALS: u8 = 8;
+
+/// NAV and portfolio value in one quote currency
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct NavQuote {
+    pub quote_symbol: String,
+    /// Price of one ICPI in the quote, `nav_decimals` decimals
+    pub nav: Nat,
+    pub nav_decimals: u8,
+    /// Portfolio value in the quote's base units
+    pub total_value: Nat,
+    pub value_decimals: u8,
+    /// Quote asset's rate into ckUSDT; None when quoting in ckUSDT
+    pub rate: Option<PriceRate>,
+    pub source: Option<PriceSource>,
+    pub rate_fetched_at: Option<u64>,
+    /// When the NAV was computed
+    pub last_updated: u64,
+    /// NAV older than its cache TTL, or a rate kept after a failed refresh
+    pub is_stale: bool,
+}
+
+/// A quote asset's rate and when it was fetched
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedRate {
+    pub rate: PriceRate,
+    pub fetched_at: u64,
+}
+
+thread_local! {
+    static QUOTE_RATES: RefCell<Vec<(QuoteAsset, CachedRate)>> = RefCell::new(Vec::new());
+}
+
+/// Quote asset for `symbol`; `None` is ckUSDT itself
+pub fn parse_quote_symbol(symbol: &str) -> Result<Option<QuoteAsset>> {
+    let ckusdt = TrackedToken::ckUSDT.to_symbol();
+    match symbol {
+        s if s == ckusdt => Ok(None),
+        s if s == QuoteAsset::ICP.symbol() => Ok(Some(QuoteAsset::ICP)),
+        _ => Err(IcpiError::Validation(ValidationError::UnsupportedQuote {
+            symbol: symbol.to_string(),
+            supported: vec![ckusdt.to_string(), QuoteAsset::ICP.symbol().to_string()],
+        })),
+    }
+}
+
+/// Get the NAV quoted in `symbol` (ckUSDT or ICP)
+pub async fn get_nav_quote(symbol: &str) -> Result<NavQuote> {
+    let asset = parse_quote_symbol(symbol)?;
+    let ticker = crate::_5_INFORMATIONAL::get_ticker()?;
+    let now = crate::infrastructure::runtime::time();
+
+    let rate = match asset {
+        None => None,
+        Some(asset) => Some((asset, quote_rate(asset, now).await?)),
+    };
+    build_nav_quote(&ticker, rate.as_ref().map(|(asset, (rate, stale))| (*asset, rate, *stale)))
+}
+
+/// Rate of `asset`, from cache while fresh; flagged stale if a refresh failed
+async fn quote_rate(asset: QuoteAsset, now: u64) -> Result<(CachedRate, bool)> {
+    let cached = QUOTE_RATES.with(|rates| {
+        rates.borrow().iter().find(|(a, _)| *a == asset).map(|(_, rate)| rate.clone())
+    });
+    if let Some(rate) = cached.as_ref().filter(|r| is_fresh(r, now)) {
+        return Ok((rate.clone(), false));
+    }
+
+    let refreshed = crate::_3_KONG_LIQUIDITY::pools::get_quote_asset_rate(asset).await;
+    let (rate, stale) = rate_after_refresh(cached, refreshed, now)?;
+    if !stale {
+        store_rate(asset, rate.clone());
+    }
+    Ok((rate, stale))
+}
+
+/// Fetch `asset`'s rate and cache it, regardless of the cached one's age
+pub async fn refresh_quote_rate(asset: QuoteAsset, now: u64) -> Result<()> {
+    let rate = crate::_3_KONG_LIQUIDITY::pools::get_quote_asset_rate(asset).await?;
+    store_rate(asset, CachedRate { rate, fetched_at: now });
+    Ok(())
+}
+
+/// When `asset`'s cached rate was fetched
+pub fn rate_fetched_at(asset: QuoteAsset) -> Option<u64> {
+    QUOTE_RATES.with(|rates| {
+        rates.borrow().iter().find(|(a, _)| *a == asset).map(|(_, rate)| rate.fetched_at)
+    })
+}
+
+fn store_rate(asset: QuoteAsset, rate: CachedRate) {
+    QUOTE_RATES.with(|rates| {
+        let mut rates = rates.borrow_mut();
+        rates.retain(|(a, _)| *a != asset);
+        rates.push((asset, rate));
+    });
+}
+
+fn is_fresh(rate: &CachedRate, now: u64) -> bool {
+    now.saturating_sub(rate.fetched_at) <= QUOTE_RATE_CACHE_TTL_SECONDS * 1_000_000_000
+}
+
+/// Rate to serve after a refresh attempt, and whether it is stale
+///
+/// A failed refresh falls back to the last cached rate, if any.
+pub fn rate_after_refresh(cached: Option<CachedRate>, refreshed: Result<PriceRate>, now: u64) -> Result<(CachedRate, bool)> {
+    match (refreshed, cached) {
+        (Ok(rate), _) => Ok((CachedRate { rate, fetched_at: now }, false)),
+        (Err(e), Some(cached)) => {
+            ic_cdk::println!("⚠️ Quote rate refresh failed, serving rate from {}: {}", cached.fetched_at, e);
+            Ok((cached, true))
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Convert a ticker into a quote; `rate` is `None` for ckUSDT
+pub fn build_nav_quote(ticker: &Ticker, rate: Option<(QuoteAsset, &CachedRate, bool)>) -> Result<NavQuote> {
+    let nav_e8 = Nat::from(ticker.nav_e8);
+    let total_value_e6 = Nat::from(ticker.total_value_e6);
+
+    match rate {
+        None => Ok(NavQuote {
+            quote_symbol: TrackedToken::ckUSDT.to_symbol().to_string(),
+            nav: nav_e8,
+            nav_decimals: NAV_QUOTE_DECIMALS,
+            total_value: total_value_e6,
+            value_decimals: TrackedToken::ckUSDT.get_decimals(),
+            rate: None,
+            source: None,
+            rate_fetched_at: None,
+            last_updated: ticker.last_updated,
+            is_stale: ticker.is_stale,
+        }),
+        Some((asset, cached, rate_stale)) => {
+            // The NAV is USD in e8 and the rate is e6 ckUSDT per pay_e8:
+            // nav × pay ÷ (receive × 100)
+            let nav = multiply_and_divide(
+                &nav_e8,
+                &cached.rate.pay_e8,
+                &(cached.rate.receive_e6.clone() * Nat::from(100u64)),
+            )?;
+            let total_value = cached.rate.amount_e8(&total_value_e6)?;
+
+            Ok(NavQuote {
+                quote_symbol: asset.symbol().to_string(),
+                nav,
+                nav_decimals: NAV_QUOTE_DECIMALS,
+                total_value,
+                value_decimals: asset.decimals(),
+                rate: Some(cached.rate.clone()),
+                source: Some(PriceSource::Kongswap),
+                rate_fetched_at: Some(cached.fetched_at),
+                last_updated: ticker.last_updated,
+                is_stale: ticker.is_stale || rate_stale,
+            })
+        }
+    }
+}
+
+/// Drop cached quote rates
+pub fn clear_rate_cache() {
+    QUOTE_RATES.with(|rates| rates.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticker() -> Ticker {
+        Ticker {
+            nav_e8: 150_000_000,          // $1.50 per ICPI
+            total_value_e6: 300_000_000,  // $300
+            supply_e8: Nat::from(20_000_000_000u64),
+            change_24h_bps: None,
+            last_updated: 10,
+            is_stale: false,
+            status_message: None,
+            drawdown_bps: None,
+        }
+    }
+
+    fn icp_rate(fetched_at: u64) -> CachedRate {
+        // 6 ckUSDT per ICP
+        CachedRate {
+            rate: PriceRate::from_swap(Nat::from(6_000_000u64), Nat::from(100_000_000u64)),
+            fetched_at,
+        }
+    }
+
+    #[test]
+    fn test_parse_quote_symbols() {
+        assert_eq!(parse_quote_symbol("ckUSDT").unwrap(), None);
+        assert_eq!(parse_quote_symbol("ICP").unwrap(), Some(QuoteAsset::ICP));
+        for symbol in ["USD", "icp", "ALEX", ""] {
+            match parse_quote_symbol(symbol) {
+                Err(IcpiError::Validation(ValidationError::UnsupportedQuote { symbol: s, supported })) => {
+                    assert_eq!(s, symbol);
+                    assert_eq!(supported, vec!["ckUSDT".to_string(), "ICP".to_string()]);
+                }
+                other => panic!("Expected UnsupportedQuote for {:?}, got {:?}", symbol, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_ckusdt_quote_is_the_ticker() {
+        let quote = build_nav_quote(&ticker(), None).unwrap();
+        assert_eq!(quote.quote_symbol, "ckUSDT");
+        assert_eq!((quote.nav, quote.nav_decimals), (Nat::from(150_000_000u64), 8));
+        assert_eq!((quote.total_value, quote.value_decimals), (Nat::from(300_000_000u64), 6));
+        assert_eq!(quote.rate, None);
+        assert!(!quote.is_stale);
+    }
+
+    #[test]
+    fn test_icp_quote_converts_through_rate() {
+        let rate = icp_rate(5);
+        let quote = build_nav_quote(&ticker(), Some((QuoteAsset::ICP, &rate, false))).unwrap();
+        assert_eq!(quote.quote_symbol, "ICP");
+        // $1.50 at $6 per ICP is 0.25 ICP
+        assert_eq!((quote.nav, quote.nav_decimals), (Nat::from(25_000_000u64), 8));
+        // $300 is 50 ICP
+        assert_eq!((quote.total_value, quote.value_decimals), (Nat::from(5_000_000_000u64), 8));
+        assert_eq!(quote.source, Some(PriceSource::Kongswap));
+        assert_eq!(quote.rate_fetched_at, Some(5));
+        assert!(!quote.is_stale);
+
+        // A stale rate or stale NAV marks the quote stale
+        let quote = build_nav_quote(&ticker(), Some((QuoteAsset::ICP, &rate, true))).unwrap();
+        assert!(quote.is_stale);
+        let stale_ticker = Ticker { is_stale: true, ..ticker() };
+        assert!(build_nav_quote(&stale_ticker, Some((QuoteAsset::ICP, &rate, false))).unwrap().is_stale);
+    }
+
+    #[test]
+    fn test_failed_refresh_serves_last_rate_as_stale() {
+        let down = || Err(IcpiError::Other("kongswap down".to_string()));
+
+        let (rate, stale) = rate_after_refresh(Some(icp_rate(5)), down(), 100).unwrap();
+        assert_eq!(rate, icp_rate(5));
+        assert!(stale);
+
+        assert!(rate_after_refresh(None, down(), 100).is_err());
+
+        let fresh = PriceRate::from_swap(Nat::from(7_000_000u64), Nat::from(100_000_000u64));
+        let (rate, stale) = rate_after_refresh(Some(icp_rate(5)), Ok(fresh.clone()), 100).unwrap();
+        assert_eq!(rate, CachedRate { rate: fresh, fetched_at: 100 });
+        assert!(!stale);
+    }
+}
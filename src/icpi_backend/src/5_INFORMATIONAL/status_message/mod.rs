@@ -0,0 +1,115 @@
+//! Operator status message
+//!
+//! A single banner (planned upgrade, incident in progress) that the
+//! frontend shows from `get_health_status` and the ticker. Persisted across
+//! upgrades so a maintenance notice set before an upgrade is still shown
+//! after it.
+
+use std::cell::RefCell;
+use crate::infrastructure::{Result, IcpiError};
+use crate::infrastructure::constants::MAX_STATUS_MESSAGE_BYTES;
+use crate::types::common::{StatusMessage, StatusSeverity};
+
+thread_local! {
+    static STATUS_MESSAGE: RefCell<Option<StatusMessage>> = RefCell::new(None);
+}
+
+/// Build a status message, rejecting empty or oversized text and past expiries
+pub fn build_message(
+    message: String,
+    severity: StatusSeverity,
+    expires_at: Option<u64>,
+    now: u64,
+) -> Result<StatusMessage> {
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        return Err(IcpiError::Other("Status message must not be empty".to_string()));
+    }
+    if message.len() > MAX_STATUS_MESSAGE_BYTES {
+        return Err(IcpiError::Other(format!(
+            "Status message is {} bytes, max {}", message.len(), MAX_STATUS_MESSAGE_BYTES
+        )));
+    }
+    if let Some(expiry) = expires_at {
+        if expiry <= now {
+            return Err(IcpiError::Other(format!("Expiry {} is not in the future", expiry)));
+        }
+    }
+
+    Ok(StatusMessage { message, severity, set_at: now, expires_at })
+}
+
+/// Whether a message should still be shown at `now`
+pub fn is_active(message: &StatusMessage, now: u64) -> bool {
+    message.expires_at.map_or(true, |expiry| now < expiry)
+}
+
+/// Replace the current status message
+pub fn set_message(message: StatusMessage) {
+    STATUS_MESSAGE.with(|m| *m.borrow_mut() = Some(message));
+}
+
+/// Remove the current status message, returning it
+pub fn clear_message() -> Option<StatusMessage> {
+    STATUS_MESSAGE.with(|m| m.borrow_mut().take())
+}
+
+/// The current message, if set and not expired
+///
+/// An expired message is cleared (the clear only sticks in update calls).
+pub fn get_active_message(now: u64) -> Option<StatusMessage> {
+    STATUS_MESSAGE.with(|m| {
+        let mut m = m.borrow_mut();
+        if m.as_ref().is_some_and(|msg| !is_active(msg, now)) {
+            *m = None;
+        }
+        m.clone()
+    })
+}
+
+/// Export status message for stable storage
+pub fn export_message() -> Option<StatusMessage> {
+    STATUS_MESSAGE.with(|m| m.borrow().clone())
+}
+
+/// Import status message from stable storage
+pub fn import_message(message: Option<StatusMessage>) {
+    STATUS_MESSAGE.with(|m| *m.borrow_mut() = message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_message_validation() {
+        let now = 1_000 * SECOND;
+        let msg = build_message("  Upgrade at 14:00 UTC ".to_string(), StatusSeverity::Info, None, now).unwrap();
+        assert_eq!(msg.message, "Upgrade at 14:00 UTC");
+        assert_eq!(msg.set_at, now);
+
+        assert!(build_message("   ".to_string(), StatusSeverity::Info, None, now).is_err());
+        assert!(build_message("x".repeat(MAX_STATUS_MESSAGE_BYTES + 1), StatusSeverity::Warning, None, now).is_err());
+        assert!(build_message("x".repeat(MAX_STATUS_MESSAGE_BYTES), StatusSeverity::Warning, None, now).is_ok());
+        assert!(build_message("late".to_string(), StatusSeverity::Critical, Some(now), now).is_err());
+    }
+
+    #[test]
+    fn test_expired_message_clears_on_read() {
+        let now = 1_000 * SECOND;
+        let msg = build_message("Incident".to_string(), StatusSeverity::Critical, Some(now + 60 * SECOND), now).unwrap();
+        set_message(msg.clone());
+
+        assert_eq!(get_active_message(now + 59 * SECOND), Some(msg));
+        assert_eq!(get_active_message(now + 60 * SECOND), None);
+        assert_eq!(export_message(), None);
+
+        let pinned = build_message("Pinned".to_string(), StatusSeverity::Info, None, now).unwrap();
+        set_message(pinned.clone());
+        assert_eq!(get_active_message(u64::MAX), Some(pinned.clone()));
+        assert_eq!(clear_message(), Some(pinned));
+        assert_eq!(get_active_message(now), None);
+    }
+}
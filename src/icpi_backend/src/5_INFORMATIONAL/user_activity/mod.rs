@@ -0,0 +1,245 @@
+//! Lifetime mint and burn totals per principal
+//!
+//! Updated when a mint completes or a burn's tokens are distributed.
+//! Memory is bounded by the activity threshold: a principal gets its own
+//! entry with its first operation worth at least the threshold (in ckUSDT
+//! e6), and smaller operations of untracked principals are added to the
+//! `other` bucket. Once tracked, a principal stays tracked.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::infrastructure::config::RuntimeConfig;
+use crate::infrastructure::constants::{MAX_TRACKED_PRINCIPALS, MAX_ACTIVITY_TOP_N};
+
+/// Cumulative activity of one principal, or of the `other` bucket
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UserActivity {
+    pub total_deposited_e6: Nat,
+    pub total_minted_e8: Nat,
+    pub total_burned_e8: Nat,
+    /// ckUSDT value of the tokens burns delivered, quoted at delivery
+    pub total_redeemed_value_e6: Nat,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+impl UserActivity {
+    fn new(now: u64) -> Self {
+        UserActivity {
+            total_deposited_e6: Nat::from(0u64),
+            total_minted_e8: Nat::from(0u64),
+            total_burned_e8: Nat::from(0u64),
+            total_redeemed_value_e6: Nat::from(0u64),
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+
+    /// ckUSDT deposited plus value redeemed, used for ranking
+    pub fn volume_e6(&self) -> Nat {
+        self.total_deposited_e6.clone() + self.total_redeemed_value_e6.clone()
+    }
+}
+
+/// Tracked principals and the aggregate of everyone else
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ActivityState {
+    pub users: Vec<(Principal, UserActivity)>,
+    pub other: Option<UserActivity>,
+}
+
+/// Result of `get_top_activity`
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TopActivity {
+    /// Largest tracked principals by volume, descending
+    pub users: Vec<(Principal, UserActivity)>,
+    pub tracked_principals: u64,
+    pub other: Option<UserActivity>,
+}
+
+#[derive(Default)]
+struct Activity {
+    users: HashMap<Principal, UserActivity>,
+    other: Option<UserActivity>,
+}
+
+thread_local! {
+    static ACTIVITY: RefCell<Activity> = RefCell::new(Activity::default());
+}
+
+/// Count a completed mint
+pub fn record_mint(user: Principal, deposit_e6: &Nat, minted_e8: &Nat, now: u64, config: &RuntimeConfig) {
+    record(user, now, deposit_e6, config.activity_tracking_threshold_e6(), |a| {
+        a.total_deposited_e6 += deposit_e6.clone();
+        a.total_minted_e8 += minted_e8.clone();
+    });
+}
+
+/// Count a burn whose tokens were distributed
+///
+/// `redeemed_value_e6` is the ckUSDT value of what the burn delivered.
+pub fn record_burn(user: Principal, burned_e8: &Nat, redeemed_value_e6: &Nat, now: u64, config: &RuntimeConfig) {
+    record(user, now, redeemed_value_e6, config.activity_tracking_threshold_e6(), |a| {
+        a.total_burned_e8 += burned_e8.clone();
+        a.total_redeemed_value_e6 += redeemed_value_e6.clone();
+    });
+}
+
+/// Apply `update` to `user`'s entry, or to `other` if they aren't tracked
+/// and this operation's volume is below `threshold_e6`
+fn record(user: Principal, now: u64, volume_e6: &Nat, threshold_e6: u64, update: impl FnOnce(&mut UserActivity)) {
+    ACTIVITY.with(|activity| {
+        let mut activity = activity.borrow_mut();
+        let tracked = activity.users.contains_key(&user)
+            || (*volume_e6 >= Nat::from(threshold_e6) && activity.users.len() < MAX_TRACKED_PRINCIPALS);
+
+        let entry = if tracked {
+            activity.users.entry(user).or_insert_with(|| UserActivity::new(now))
+        } else {
+            activity.other.get_or_insert_with(|| UserActivity::new(now))
+        };
+        update(entry);
+        entry.last_seen = now;
+    });
+}
+
+/// A principal's tracked activity; None if untracked
+pub fn get_user_activity(user: &Principal) -> Option<UserActivity> {
+    ACTIVITY.with(|activity| activity.borrow().users.get(user).cloned())
+}
+
+/// The `n` most active tracked principals (capped at `MAX_ACTIVITY_TOP_N`)
+pub fn get_top_activity(n: u32) -> TopActivity {
+    ACTIVITY.with(|activity| {
+        let activity = activity.borrow();
+        let mut users: Vec<_> = activity.users.iter().map(|(p, a)| (*p, a.clone())).collect();
+        users.sort_by(|(pa, a), (pb, b)| b.volume_e6().cmp(&a.volume_e6()).then(pa.cmp(pb)));
+        users.truncate((n as usize).min(MAX_ACTIVITY_TOP_N));
+        TopActivity {
+            users,
+            tracked_principals: activity.users.len() as u64,
+            other: activity.other.clone(),
+        }
+    })
+}
+
+/// Export activity for stable storage
+pub fn export_state() -> ActivityState {
+    ACTIVITY.with(|activity| {
+        let activity = activity.borrow();
+        let mut users: Vec<_> = activity.users.iter().map(|(p, a)| (*p, a.clone())).collect();
+        users.sort_by_key(|(p, _)| *p);
+        ActivityState { users, other: activity.other.clone() }
+    })
+}
+
+/// Import activity from stable storage
+pub fn import_state(state: ActivityState) {
+    ACTIVITY.with(|activity| {
+        *activity.borrow_mut() = Activity {
+            users: state.users.into_iter().collect(),
+            other: state.other,
+        };
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: u64 = 100_000_000; // $100
+
+    fn user(n: u8) -> Principal {
+        Principal::from_slice(&[n])
+    }
+
+    fn config() -> RuntimeConfig {
+        RuntimeConfig { activity_tracking_threshold_e6: Some(THRESHOLD), ..Default::default() }
+    }
+
+    fn mint(n: u8, deposit_e6: u64, now: u64) {
+        record_mint(user(n), &Nat::from(deposit_e6), &Nat::from(deposit_e6 * 100), now, &config());
+    }
+
+    fn burn(n: u8, burned_e8: u64, value_e6: u64, now: u64) {
+        record_burn(user(n), &Nat::from(burned_e8), &Nat::from(value_e6), now, &config());
+    }
+
+    #[test]
+    fn test_totals_accumulate_per_principal() {
+        mint(1, 200_000_000, 10);
+        mint(1, 50_000_000, 20);
+        burn(1, 1_000_000_000, 30_000_000, 30);
+
+        let activity = get_user_activity(&user(1)).unwrap();
+        assert_eq!(activity.total_deposited_e6, Nat::from(250_000_000u64));
+        assert_eq!(activity.total_minted_e8, Nat::from(25_000_000_000u64));
+        assert_eq!(activity.total_burned_e8, Nat::from(1_000_000_000u64));
+        assert_eq!(activity.total_redeemed_value_e6, Nat::from(30_000_000u64));
+        assert_eq!((activity.first_seen, activity.last_seen), (10, 30));
+        assert_eq!(activity.volume_e6(), Nat::from(280_000_000u64));
+    }
+
+    #[test]
+    fn test_small_principals_aggregate_into_other() {
+        // Below the threshold: aggregated
+        mint(2, 99_999_999, 10);
+        burn(3, 10, 5_000_000, 20);
+        assert_eq!(get_user_activity(&user(2)), None);
+        assert_eq!(get_user_activity(&user(3)), None);
+
+        let top = get_top_activity(10);
+        assert_eq!(top.tracked_principals, 0);
+        let other = top.other.unwrap();
+        assert_eq!(other.total_deposited_e6, Nat::from(99_999_999u64));
+        assert_eq!(other.total_redeemed_value_e6, Nat::from(5_000_000u64));
+        assert_eq!((other.first_seen, other.last_seen), (10, 20));
+
+        // An operation at the threshold starts tracking, from that operation on
+        mint(2, THRESHOLD, 30);
+        assert_eq!(get_user_activity(&user(2)).unwrap().total_deposited_e6, Nat::from(THRESHOLD));
+
+        // Tracked principals stay tracked for small operations
+        burn(2, 10, 1, 40);
+        assert_eq!(get_user_activity(&user(2)).unwrap().total_redeemed_value_e6, Nat::from(1u64));
+        assert_eq!(get_top_activity(10).other.unwrap().total_redeemed_value_e6, Nat::from(5_000_000u64));
+    }
+
+    #[test]
+    fn test_threshold_read_from_config() {
+        let low = RuntimeConfig { activity_tracking_threshold_e6: Some(1_000), ..Default::default() };
+        record_mint(user(4), &Nat::from(1_000u64), &Nat::from(100_000u64), 10, &low);
+        record_burn(user(5), &Nat::from(10u64), &Nat::from(999u64), 20, &low);
+
+        assert_eq!(get_user_activity(&user(4)).unwrap().total_minted_e8, Nat::from(100_000u64));
+        assert_eq!(get_user_activity(&user(5)), None);
+        assert_eq!(get_top_activity(10).other.unwrap().total_burned_e8, Nat::from(10u64));
+    }
+
+    #[test]
+    fn test_top_activity_orders_by_volume() {
+        mint(1, 150_000_000, 1);
+        mint(2, 500_000_000, 2);
+        burn(3, 10, 300_000_000, 3);
+
+        let top = get_top_activity(2);
+        assert_eq!(top.tracked_principals, 3);
+        assert_eq!(top.users.iter().map(|(p, _)| *p).collect::<Vec<_>>(), vec![user(2), user(3)]);
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        mint(1, 150_000_000, 1);
+        mint(2, 1, 2);
+        let saved = export_state();
+
+        import_state(ActivityState::default());
+        assert_eq!(get_user_activity(&user(1)), None);
+
+        import_state(saved.clone());
+        assert_eq!(export_state(), saved);
+        assert!(get_user_activity(&user(1)).is_some());
+    }
+}
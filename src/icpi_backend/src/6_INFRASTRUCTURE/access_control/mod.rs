@@ -0,0 +1,195 @@
+//! Access Control Module
+//!
+//! Optional per-principal restrictions on mint and burn (off by default).
+//!
+//! - `Open`: anyone may mint/burn except denylisted principals
+//! - `Allowlist`: only allowlisted principals may mint/burn
+//!
+//! The denylist always applies, in both modes.
+//!
+//! Separately, operators can require an authenticated (non-anonymous) caller
+//! for the `#[update]` read endpoints, which cost inter-canister calls.
+//! Pure queries stay open.
+
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use crate::infrastructure::{Result, IcpiError, ValidationError, SystemError};
+
+/// Mint/burn access mode
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AccessMode {
+    #[default]
+    Open,
+    Allowlist,
+}
+
+/// Access-control lists persisted across upgrades
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct AccessLists {
+    pub allowlist: BTreeSet<Principal>,
+    pub denylist: BTreeSet<Principal>,
+}
+
+thread_local! {
+    static ACCESS_LISTS: RefCell<AccessLists> = RefCell::new(AccessLists::default());
+}
+
+/// Check a principal against a mode and lists
+pub fn check_access(mode: AccessMode, lists: &AccessLists, principal: &Principal) -> Result<()> {
+    if lists.denylist.contains(principal) {
+        return Err(IcpiError::Validation(ValidationError::PrincipalDenylisted {
+            principal: principal.to_text(),
+        }));
+    }
+
+    if mode == AccessMode::Allowlist && !lists.allowlist.contains(principal) {
+        return Err(IcpiError::Validation(ValidationError::PrincipalNotAllowlisted {
+            principal: principal.to_text(),
+        }));
+    }
+
+    Ok(())
+}
+
+/// Check a principal against the current mode and lists
+pub fn check_principal_access(principal: &Principal) -> Result<()> {
+    let mode = crate::infrastructure::config::get_config().access_mode();
+    ACCESS_LISTS.with(|l| check_access(mode, &l.borrow(), principal))
+}
+
+/// Check a caller of an update read endpoint
+pub fn check_read_access(require_authenticated: bool, caller: &Principal) -> Result<()> {
+    if require_authenticated && *caller == Principal::anonymous() {
+        return Err(IcpiError::System(SystemError::Unauthorized {
+            principal: caller.to_text(),
+            required_role: "authenticated caller".to_string(),
+        }));
+    }
+    Ok(())
+}
+
+/// Reject anonymous callers of update read endpoints when configured
+pub fn require_read_access() -> Result<()> {
+    let required = crate::infrastructure::config::get_config().require_authenticated_reads();
+    check_read_access(required, &crate::infrastructure::runtime::caller())
+}
+
+/// Add a principal to the allowlist, returns false if already present
+pub fn add_to_allowlist(principal: Principal) -> bool {
+    ACCESS_LISTS.with(|l| l.borrow_mut().allowlist.insert(principal))
+}
+
+/// Remove a principal from the allowlist, returns false if not present
+pub fn remove_from_allowlist(principal: &Principal) -> bool {
+    ACCESS_LISTS.with(|l| l.borrow_mut().allowlist.remove(principal))
+}
+
+/// Add a principal to the denylist, returns false if already present
+pub fn add_to_denylist(principal: Principal) -> bool {
+    ACCESS_LISTS.with(|l| l.borrow_mut().denylist.insert(principal))
+}
+
+/// Remove a principal from the denylist, returns false if not present
+pub fn remove_from_denylist(principal: &Principal) -> bool {
+    ACCESS_LISTS.with(|l| l.borrow_mut().denylist.remove(principal))
+}
+
+/// Get a copy of the current lists
+pub fn get_access_lists() -> AccessLists {
+    ACCESS_LISTS.with(|l| l.borrow().clone())
+}
+
+/// Import lists from stable storage
+pub fn import_access_lists(lists: AccessLists) {
+    ACCESS_LISTS.with(|l| *l.borrow_mut() = lists);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice() -> Principal {
+        Principal::from_text("67ktx-ln42b-uzmo5-bdiyn-gu62c-cd4h4-a5qt3-2w3rs-cixdl-iaso2-mqe").unwrap()
+    }
+
+    fn bob() -> Principal {
+        Principal::from_text("aaaaa-aa").unwrap()
+    }
+
+    fn lists(allow: &[Principal], deny: &[Principal]) -> AccessLists {
+        AccessLists {
+            allowlist: allow.iter().cloned().collect(),
+            denylist: deny.iter().cloned().collect(),
+        }
+    }
+
+    #[test]
+    fn test_open_mode_allows_unlisted() {
+        assert!(check_access(AccessMode::Open, &lists(&[], &[]), &alice()).is_ok());
+    }
+
+    #[test]
+    fn test_open_mode_allows_allowlisted() {
+        assert!(check_access(AccessMode::Open, &lists(&[alice()], &[]), &alice()).is_ok());
+    }
+
+    #[test]
+    fn test_open_mode_rejects_denylisted() {
+        let result = check_access(AccessMode::Open, &lists(&[], &[alice()]), &alice());
+        assert!(matches!(result, Err(IcpiError::Validation(ValidationError::PrincipalDenylisted { .. }))));
+        // Other principals unaffected
+        assert!(check_access(AccessMode::Open, &lists(&[], &[alice()]), &bob()).is_ok());
+    }
+
+    #[test]
+    fn test_allowlist_mode_allows_allowlisted() {
+        assert!(check_access(AccessMode::Allowlist, &lists(&[alice()], &[]), &alice()).is_ok());
+    }
+
+    #[test]
+    fn test_allowlist_mode_rejects_unlisted() {
+        let result = check_access(AccessMode::Allowlist, &lists(&[alice()], &[]), &bob());
+        assert!(matches!(result, Err(IcpiError::Validation(ValidationError::PrincipalNotAllowlisted { .. }))));
+    }
+
+    #[test]
+    fn test_denylist_overrides_allowlist() {
+        let result = check_access(AccessMode::Allowlist, &lists(&[alice()], &[alice()]), &alice());
+        assert!(matches!(result, Err(IcpiError::Validation(ValidationError::PrincipalDenylisted { .. }))));
+    }
+
+    #[test]
+    fn test_list_mutations() {
+        import_access_lists(AccessLists::default());
+
+        assert!(add_to_allowlist(alice()));
+        assert!(!add_to_allowlist(alice()));
+        assert!(add_to_denylist(bob()));
+        assert!(get_access_lists().allowlist.contains(&alice()));
+        assert!(get_access_lists().denylist.contains(&bob()));
+
+        assert!(remove_from_allowlist(&alice()));
+        assert!(!remove_from_allowlist(&alice()));
+        assert!(remove_from_denylist(&bob()));
+        assert!(get_access_lists().denylist.is_empty());
+    }
+
+    #[test]
+    fn test_anonymous_read_toggle() {
+        let anonymous = Principal::anonymous();
+
+        // Off by default: everyone may read
+        assert!(!crate::infrastructure::config::get_config().require_authenticated_reads());
+        assert!(check_read_access(false, &anonymous).is_ok());
+        assert!(check_read_access(false, &alice()).is_ok());
+
+        // On: anonymous rejected, authenticated callers unaffected
+        assert!(matches!(
+            check_read_access(true, &anonymous),
+            Err(IcpiError::System(SystemError::Unauthorized { .. }))
+        ));
+        assert!(check_read_access(true, &alice()).is_ok());
+    }
+}
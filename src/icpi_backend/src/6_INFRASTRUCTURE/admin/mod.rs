@@ -20,17 +20,20 @@ const ADMIN_PRINCIPALS: &[&str] = &[
     "67ktx-ln42b-uzmo5-bdiyn-gu62c-cd4h4-a5qt3-2w3rs-cixdl-iaso2-mqe",  // Deployer
 ];
 
-/// Require caller is an admin principal
-pub fn require_admin() -> Result<()> {
-    let caller = ic_cdk::caller();
-
-    let is_admin = ADMIN_PRINCIPALS.iter().any(|p| {
+/// Whether `principal` is one of the admin principals
+pub fn is_admin(principal: &Principal) -> bool {
+    ADMIN_PRINCIPALS.iter().any(|p| {
         Principal::from_text(p)
-            .map(|admin| admin == caller)
+            .map(|admin| admin == *principal)
             .unwrap_or(false)
-    });
+    })
+}
+
+/// Require caller is an admin principal
+pub fn require_admin() -> Result<()> {
+    let caller = crate::infrastructure::runtime::caller();
 
-    if is_admin {
+    if is_admin(&caller) {
         Ok(())
     } else {
         Err(IcpiError::System(crate::infrastructure::errors::SystemError::Unauthorized {
@@ -40,37 +43,77 @@ pub fn require_admin() -> Result<()> {
     }
 }
 
-/// Emergency pause state
-///
-/// **CRITICAL UPGRADE BEHAVIOR**: This thread-local state is NOT persisted across canister upgrades.
-///
-/// **What happens on upgrade:**
-/// - Emergency pause resets to `false` (system becomes unpaused)
-/// - Admin must manually re-enable pause after upgrade if still needed
+/// Check that `caller` may read `target`'s records: their own, or any as admin
+pub fn check_self_or_admin(caller: &Principal, target: &Principal) -> Result<()> {
+    if caller == target || is_admin(caller) {
+        return Ok(());
+    }
+    Err(IcpiError::System(crate::infrastructure::errors::SystemError::Unauthorized {
+        principal: caller.to_text(),
+        required_role: format!("self ({}) or admin", target.to_text()),
+    }))
+}
+
+/// Require the caller to be `target` or an admin
 ///
-/// **Impact scenarios:**
-/// 1. Admin activates pause → investigates issue → deploys fix → **pause silently disabled**
-/// 2. Admin activates pause → unrelated upgrade → **pause silently disabled**
+/// Guards every per-user query, so no principal can read another's
+/// mints, burns, claims, failures or limits.
+pub fn require_self_or_admin(target: Principal) -> Result<()> {
+    check_self_or_admin(&crate::infrastructure::runtime::caller(), &target)
+}
+
+/// Resolve an optional per-user query target (default: the caller)
+pub fn resolve_user_target(target: Option<Principal>) -> Result<Principal> {
+    let target = target.unwrap_or_else(crate::infrastructure::runtime::caller);
+    require_self_or_admin(target)?;
+    Ok(target)
+}
+
+/// Emergency pause state
 ///
-/// **Mitigation:**
-/// - Document this behavior clearly (done)
-/// - Admin should check pause state immediately after upgrade
-/// - Consider implementing stable storage in future (Phase 5)
+/// Persisted across upgrades via `StableState::pause`, so a pause set while
+/// investigating an issue survives deploying the fix.
+#[derive(Clone, Debug, Default, PartialEq, candid::CandidType, candid::Deserialize, serde::Serialize)]
+pub struct PauseInfo {
+    pub paused: bool,
+    pub reason: Option<String>,
+    pub paused_at: Option<u64>,
+    pub paused_by: Option<Principal>,
+    /// When the last emergency pause was lifted (starts the rebalance cooldown)
+    pub unpaused_at: Option<u64>,
+}
+
+thread_local! {
+    static EMERGENCY_PAUSE: RefCell<PauseInfo> = RefCell::new(PauseInfo::default());
+}
+
+/// Upgrade quiesce state
 ///
-/// **Why thread-local:**
-/// - Simple implementation for MVP
-/// - Pause is typically temporary (minutes/hours, not days)
-/// - Full stable storage implementation deferred to Phase 5
+/// Unlike the emergency pause this is not persisted: an upgrade ends it.
+#[derive(Clone, Debug, PartialEq, candid::CandidType, candid::Deserialize, serde::Serialize)]
+pub struct QuiesceStatus {
+    pub quiescing: bool,
+    /// When quiescing began (ns)
+    pub since: Option<u64>,
+    /// No mint, burn, rebalance or ckUSDT reserve debit is in flight; safe to upgrade
+    pub drained: bool,
+}
+
 thread_local! {
-    static EMERGENCY_PAUSE: RefCell<bool> = RefCell::new(false);
+    static QUIESCE_SINCE: RefCell<Option<u64>> = const { RefCell::new(None) };
 }
 
 /// Admin action log entry
-#[derive(Clone, candid::CandidType, candid::Deserialize, serde::Serialize)]
+///
+/// `before`/`after` hold the changed setting for config mutations; they are
+/// `None` for plain actions and for entries saved before they existed.
+#[derive(Clone, Debug, PartialEq, candid::CandidType, candid::Deserialize, serde::Serialize)]
 pub struct AdminAction {
     pub timestamp: u64,
     pub admin: Principal,
     pub action: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
 }
 
 /// Admin action log storage
@@ -82,44 +125,180 @@ const MAX_LOG_ENTRIES: usize = 1000;
 
 /// Log an admin action
 pub fn log_admin_action(action: String) {
+    ic_cdk::println!("📝 Admin action: {} by {}", action, crate::infrastructure::runtime::caller());
+    record_admin_action(AdminAction {
+        timestamp: crate::infrastructure::runtime::time(),
+        admin: crate::infrastructure::runtime::caller(),
+        action,
+        before: None,
+        after: None,
+    });
+}
+
+/// Log an admin change with the affected setting before and after
+pub fn log_admin_change(action: String, before: String, after: String) {
+    ic_cdk::println!("📝 Admin change: {} {} -> {} by {}", action, before, after, crate::infrastructure::runtime::caller());
+    record_admin_action(AdminAction {
+        timestamp: crate::infrastructure::runtime::time(),
+        admin: crate::infrastructure::runtime::caller(),
+        action,
+        before: Some(before),
+        after: Some(after),
+    });
+}
+
+/// Append a log entry, keeping only the last 1000
+fn record_admin_action(entry: AdminAction) {
     ADMIN_LOG.with(|log| {
         let mut log = log.borrow_mut();
+        log.push(entry);
 
-        log.push(AdminAction {
-            timestamp: ic_cdk::api::time(),
-            admin: ic_cdk::caller(),
-            action: action.clone(),
-        });
-
-        // Keep only last 1000 entries
         let len = log.len();
         if len > MAX_LOG_ENTRIES {
             log.drain(0..(len - MAX_LOG_ENTRIES));
         }
     });
-
-    ic_cdk::println!("📝 Admin action: {} by {}", action, ic_cdk::caller());
 }
 
-/// Check if system is paused
+/// Check if system is paused or quiescing
 pub fn check_not_paused() -> Result<()> {
+    if EMERGENCY_PAUSE.with(|p| p.borrow().paused) {
+        return Err(IcpiError::System(crate::infrastructure::errors::SystemError::EmergencyPause));
+    }
+    if is_quiescing() {
+        return Err(IcpiError::System(crate::infrastructure::errors::SystemError::Quiescing));
+    }
+    Ok(())
+}
+
+/// Refuse new mints, burns and rebalances ahead of an upgrade
+///
+/// Repeated calls keep the original start time, so operators can call it
+/// again to poll drain status.
+pub fn begin_quiesce(now: u64) -> QuiesceStatus {
+    QUIESCE_SINCE.with(|q| {
+        q.borrow_mut().get_or_insert(now);
+    });
+    quiesce_status(
+        crate::infrastructure::reentrancy::has_active_operations()
+            || crate::_2_CRITICAL_DATA::ckusdt_reserves::has_debits_in_flight()
+    )
+}
+
+/// Accept new operations again
+pub fn end_quiesce() {
+    QUIESCE_SINCE.with(|q| *q.borrow_mut() = None);
+}
+
+/// Whether new operations are being refused for an upgrade
+pub fn is_quiescing() -> bool {
+    QUIESCE_SINCE.with(|q| q.borrow().is_some())
+}
+
+/// Quiesce state given whether any operation is still in flight
+pub fn quiesce_status(active_operations: bool) -> QuiesceStatus {
+    let since = QUIESCE_SINCE.with(|q| *q.borrow());
+    QuiesceStatus {
+        quiescing: since.is_some(),
+        since,
+        drained: !active_operations,
+    }
+}
+
+/// Set or clear the pause flag (clearing also drops the pause details)
+///
+/// Endpoints use `pause_with_reason` / `unpause_at`.
+#[cfg(test)]
+pub fn set_pause(paused: bool) {
     EMERGENCY_PAUSE.with(|p| {
-        if *p.borrow() {
-            Err(IcpiError::System(crate::infrastructure::errors::SystemError::EmergencyPause))
+        let mut p = p.borrow_mut();
+        if paused {
+            p.paused = true;
         } else {
-            Ok(())
+            *p = PauseInfo::default();
         }
-    })
+    });
 }
 
-/// Activate emergency pause
-pub fn set_pause(paused: bool) {
-    EMERGENCY_PAUSE.with(|p| *p.borrow_mut() = paused);
+/// Activate emergency pause, recording why, when and by whom
+pub fn pause_with_reason(reason: Option<String>, paused_by: Principal, now: u64) {
+    EMERGENCY_PAUSE.with(|p| {
+        *p.borrow_mut() = PauseInfo {
+            paused: true,
+            reason,
+            paused_at: Some(now),
+            paused_by: Some(paused_by),
+            unpaused_at: None,
+        };
+    });
+}
+
+/// Pause on detected data corruption, logged as an action of `canister`
+///
+/// An existing pause is left as is so its original reason survives.
+pub fn auto_pause(reason: String, canister: Principal, now: u64) {
+    if is_paused() {
+        return;
+    }
+    ic_cdk::println!("🚨 Auto-pausing: {}", reason);
+    pause_with_reason(Some(reason.clone()), canister, now);
+    record_admin_action(AdminAction {
+        timestamp: now,
+        admin: canister,
+        action: "AUTO_PAUSE".to_string(),
+        before: None,
+        after: Some(reason),
+    });
+}
+
+/// Lift emergency pause, recording when so rebalancing can cool down
+pub fn unpause_at(now: u64) {
+    EMERGENCY_PAUSE.with(|p| {
+        *p.borrow_mut() = PauseInfo {
+            unpaused_at: Some(now),
+            ..PauseInfo::default()
+        };
+    });
+}
+
+/// Seconds of post-unpause cooldown left at `now` (0 if none)
+pub fn cooldown_remaining_seconds(unpaused_at: Option<u64>, now: u64, cooldown_seconds: u64) -> u64 {
+    match unpaused_at {
+        Some(at) => {
+            let elapsed = now.saturating_sub(at) / 1_000_000_000;
+            cooldown_seconds.saturating_sub(elapsed)
+        }
+        None => 0,
+    }
+}
+
+/// Remaining post-unpause cooldown using the current time and config
+pub fn unpause_cooldown_remaining() -> u64 {
+    unpause_cooldown_remaining_at(crate::infrastructure::runtime::time())
+}
+
+/// Seconds of post-unpause cooldown left at `now`
+pub fn unpause_cooldown_remaining_at(now: u64) -> u64 {
+    cooldown_remaining_seconds(
+        get_pause_info().unpaused_at,
+        now,
+        crate::infrastructure::config::get_config().unpause_cooldown_seconds(),
+    )
 }
 
 /// Get current pause state
 pub fn is_paused() -> bool {
-    EMERGENCY_PAUSE.with(|p| *p.borrow())
+    EMERGENCY_PAUSE.with(|p| p.borrow().paused)
+}
+
+/// Get pause state with details
+pub fn get_pause_info() -> PauseInfo {
+    EMERGENCY_PAUSE.with(|p| p.borrow().clone())
+}
+
+/// Restore pause state from stable storage
+pub fn import_pause_info(info: PauseInfo) {
+    EMERGENCY_PAUSE.with(|p| *p.borrow_mut() = info);
 }
 
 /// Get admin action log
@@ -127,6 +306,11 @@ pub fn get_admin_log() -> Vec<AdminAction> {
     ADMIN_LOG.with(|log| log.borrow().clone())
 }
 
+/// Restore the admin log from stable storage
+pub fn import_admin_log(entries: Vec<AdminAction>) {
+    ADMIN_LOG.with(|log| *log.borrow_mut() = entries);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,6 +326,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_self_or_admin() {
+        use crate::infrastructure::errors::SystemError;
+
+        let admin = Principal::from_text(ADMIN_PRINCIPALS[1]).unwrap();
+        let alice = Principal::from_slice(&[1]);
+        let bob = Principal::from_slice(&[2]);
+
+        assert!(check_self_or_admin(&alice, &alice).is_ok());
+        assert!(check_self_or_admin(&admin, &alice).is_ok());
+        match check_self_or_admin(&bob, &alice) {
+            Err(IcpiError::System(SystemError::Unauthorized { principal, .. })) => {
+                assert_eq!(principal, bob.to_text());
+            }
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+        // Anonymous callers only ever see anonymous records
+        assert!(check_self_or_admin(&Principal::anonymous(), &alice).is_err());
+    }
+
+    fn entry(action: &str, change: Option<(&str, &str)>) -> AdminAction {
+        AdminAction {
+            timestamp: 1,
+            admin: Principal::anonymous(),
+            action: action.to_string(),
+            before: change.map(|(b, _)| b.to_string()),
+            after: change.map(|(_, a)| a.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_admin_log_keeps_latest_entries() {
+        import_admin_log(Vec::new());
+        for i in 0..MAX_LOG_ENTRIES + 5 {
+            record_admin_action(entry(&format!("ACTION_{}", i), None));
+        }
+        let log = get_admin_log();
+        assert_eq!(log.len(), MAX_LOG_ENTRIES);
+        assert_eq!(log[0].action, "ACTION_5");
+        import_admin_log(Vec::new());
+    }
+
+    #[test]
+    fn test_admin_log_decodes_entries_without_diff() {
+        // Shape of entries saved before before/after existed
+        #[derive(candid::CandidType)]
+        struct LegacyAdminAction {
+            timestamp: u64,
+            admin: Principal,
+            action: String,
+        }
+        let legacy = vec![LegacyAdminAction {
+            timestamp: 1,
+            admin: Principal::anonymous(),
+            action: "PRICE_ORACLE_SET: None".to_string(),
+        }];
+        let bytes = candid::encode_one(legacy).unwrap();
+        let decoded: Vec<AdminAction> = candid::decode_one(&bytes).unwrap();
+        assert_eq!(decoded, vec![entry("PRICE_ORACLE_SET: None", None)]);
+
+        let current = vec![entry("UNPAUSE_COOLDOWN_SET", Some(("None", "Some(60)")))];
+        let bytes = candid::encode_one(&current).unwrap();
+        assert_eq!(candid::decode_one::<Vec<AdminAction>>(&bytes).unwrap(), current);
+    }
+
     #[test]
     fn test_pause_state_default() {
         // Initial state should be not paused
@@ -168,4 +417,86 @@ mod tests {
         // Reset for other tests
         set_pause(false);
     }
+
+    #[test]
+    fn test_quiesce_refuses_operations_and_reports_drain() {
+        end_quiesce();
+        let status = begin_quiesce(100);
+        assert!(status.quiescing);
+        assert_eq!(status.since, Some(100));
+        // Nothing in flight in tests
+        assert!(status.drained);
+
+        let err = check_not_paused().unwrap_err();
+        assert!(matches!(err, IcpiError::System(crate::infrastructure::errors::SystemError::Quiescing)));
+
+        // Polling again keeps the start time
+        assert_eq!(begin_quiesce(200).since, Some(100));
+        // An in-flight operation is reported as not drained
+        assert!(!quiesce_status(true).drained);
+
+        end_quiesce();
+        assert!(check_not_paused().is_ok());
+        assert_eq!(quiesce_status(false), QuiesceStatus { quiescing: false, since: None, drained: true });
+    }
+
+    #[test]
+    fn test_quiesce_waits_for_reserve_debits() {
+        use candid::Nat;
+        use crate::_2_CRITICAL_DATA::ckusdt_reserves;
+
+        end_quiesce();
+        ckusdt_reserves::import_state(ckusdt_reserves::ReserveState {
+            quarantined: Nat::from(100u64),
+            ..Default::default()
+        });
+        ckusdt_reserves::begin_debit(&Nat::from(60u64)).unwrap();
+        assert!(!begin_quiesce(100).drained);
+
+        ckusdt_reserves::finish_debit(&Nat::from(60u64), None, 1);
+        assert!(begin_quiesce(100).drained);
+        end_quiesce();
+    }
+
+    #[test]
+    fn test_pause_info_round_trip() {
+        let admin = Principal::from_text(ADMIN_PRINCIPALS[1]).unwrap();
+        pause_with_reason(Some("oracle outage".to_string()), admin, 1_700_000_000_000_000_000);
+
+        let saved = get_pause_info();
+        set_pause(false);
+        assert_eq!(get_pause_info(), PauseInfo::default());
+
+        import_pause_info(saved);
+        let restored = get_pause_info();
+        assert!(restored.paused);
+        assert!(is_paused());
+        assert_eq!(restored.reason.as_deref(), Some("oracle outage"));
+        assert_eq!(restored.paused_at, Some(1_700_000_000_000_000_000));
+        assert_eq!(restored.paused_by, Some(admin));
+
+        // Reset for other tests
+        set_pause(false);
+    }
+
+    #[test]
+    fn test_rebalance_suppressed_within_unpause_cooldown() {
+        const SECOND: u64 = 1_000_000_000;
+        let unpaused = 1_700_000_000 * SECOND;
+
+        assert_eq!(cooldown_remaining_seconds(None, unpaused, 300), 0);
+        assert_eq!(cooldown_remaining_seconds(Some(unpaused), unpaused, 300), 300);
+        assert_eq!(cooldown_remaining_seconds(Some(unpaused), unpaused + 120 * SECOND, 300), 180);
+        assert_eq!(cooldown_remaining_seconds(Some(unpaused), unpaused + 300 * SECOND, 300), 0);
+        assert_eq!(cooldown_remaining_seconds(Some(unpaused), unpaused + 3_600 * SECOND, 300), 0);
+        assert_eq!(cooldown_remaining_seconds(Some(unpaused), unpaused, 0), 0);
+
+        // Mints and burns resume immediately
+        unpause_at(unpaused);
+        assert!(check_not_paused().is_ok());
+        assert_eq!(get_pause_info().unpaused_at, Some(unpaused));
+
+        // Reset for other tests
+        set_pause(false);
+    }
 }
@@ -0,0 +1,374 @@
+//! Runtime Configuration Module
+//!
+//! Admin-tunable settings that must survive upgrades. Compile-time defaults
+//! live in `constants`; this module only stores overrides.
+//!
+//! Every field is an `Option` so that adding new settings never breaks
+//! decoding of a `RuntimeConfig` saved by an older canister version.
+
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+use std::cell::RefCell;
+use crate::infrastructure::access_control::AccessMode;
+use crate::infrastructure::rate_limiting::{RateLimitConfig, RateLimitedOperation};
+use crate::_2_CRITICAL_DATA::trade_intensity::IntensityCurve;
+use crate::_4_TRADING_EXECUTION::fills::PartialFillPolicy;
+use crate::_1_CRITICAL_OPERATIONS::burning::token_distributor::ShortfallPolicy;
+use crate::types::TrackedToken;
+
+/// Admin-configurable runtime settings
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct RuntimeConfig {
+    /// Fallback price oracle queried when Kongswap pricing fails
+    pub price_oracle: Option<Principal>,
+    /// Mint/burn access mode (default: Open)
+    pub access_mode: Option<AccessMode>,
+    /// EWMA half-life for TVL-derived targets (0 disables smoothing)
+    pub target_smoothing_half_life_seconds: Option<u64>,
+    /// Max per-cycle target change in percentage points (0 disables the cap)
+    pub max_target_change_pct: Option<f64>,
+    /// Re-read supply before minting and abort on drift (default: enabled)
+    pub mint_supply_recheck: Option<bool>,
+    /// Allowed supply drift between snapshot and mint, in basis points
+    pub mint_supply_tolerance_bps: Option<u64>,
+    /// Serve the last-good TVL when Kong Locker is unreachable (default: disabled)
+    pub tvl_outage_fallback: Option<bool>,
+    /// Re-approve before a swap if fewer seconds than this remain on the approval
+    pub approval_min_remaining_seconds: Option<u64>,
+    /// Cap on non-terminal pending mints across all users
+    pub max_pending_mints: Option<u64>,
+    /// Shorten pending-mint expiry when occupancy is high (default: enabled)
+    pub aggressive_mint_cleanup: Option<bool>,
+    /// Seconds after emergency unpause during which rebalancing stays off
+    pub unpause_cooldown_seconds: Option<u64>,
+    /// How trade size scales with deviation (default: Flat)
+    pub trade_intensity_curve: Option<IntensityCurve>,
+    /// Whether a partially filled swap still succeeds (default: Accept)
+    pub partial_fill_policy: Option<PartialFillPolicy>,
+    /// Compare the recipient's ICPI balance around each ledger mint (default: disabled)
+    pub mint_balance_check: Option<bool>,
+    /// Cap on in-flight `complete_mint` calls across all users
+    pub max_concurrent_mints: Option<u64>,
+    /// Fraction of the instruction limit loops may use before stopping
+    pub instruction_budget_fraction: Option<f64>,
+    /// Reject anonymous callers of `#[update]` read endpoints (default: disabled)
+    pub require_authenticated_reads: Option<bool>,
+    /// Seconds between cache pre-warming runs (kept below the index state TTL)
+    pub cache_prewarm_interval_seconds: Option<u64>,
+    /// Per-token cap on target weight in percent; unlisted tokens use the default
+    pub max_token_weights: Option<Vec<(TrackedToken, f64)>>,
+    /// Symbol Kongswap knows each token by; unlisted tokens use the index symbol
+    pub kongswap_symbols: Option<Vec<(TrackedToken, String)>>,
+    /// Oldest completed TVL generation the rebalancer trades on
+    pub max_tvl_generation_age_seconds: Option<u64>,
+    /// Oldest TVL data the rebalancer trades on targets from (default: 2x the TVL refresh interval)
+    pub max_target_age_seconds: Option<u64>,
+    /// Wait between global operation type switches (default: 60s)
+    pub grace_period_seconds: Option<u64>,
+    /// Cycles balance a rebalance cycle needs before it starts (default: 2T)
+    pub rebalance_cycles_budget: Option<u128>,
+    /// Per-principal windows between attempts (defaults: 1s, 1s, 60s)
+    pub mint_rate_limit_seconds: Option<u64>,
+    pub burn_rate_limit_seconds: Option<u64>,
+    pub manual_rebalance_rate_limit_seconds: Option<u64>,
+    /// Principals exempt from rate limits besides the admins
+    pub rate_limit_bypass: Option<Vec<Principal>>,
+    /// Handling of redemptions the backend's balance no longer covers (default: clamp)
+    pub redemption_shortfall_policy: Option<ShortfallPolicy>,
+    /// Successful lock canister queries a TVL needs to be used (default: 1)
+    pub min_successful_lockers: Option<u64>,
+    /// Operation size (ckUSDT e6) that gets a principal its own activity totals
+    pub activity_tracking_threshold_e6: Option<u64>,
+    /// Emergency pause when supply and TVL contradict each other (default: enabled)
+    pub auto_pause_on_corruption: Option<bool>,
+}
+
+impl RuntimeConfig {
+    pub fn access_mode(&self) -> AccessMode {
+        self.access_mode.unwrap_or_default()
+    }
+
+    pub fn target_smoothing_half_life_seconds(&self) -> u64 {
+        self.target_smoothing_half_life_seconds
+            .unwrap_or(crate::infrastructure::TARGET_SMOOTHING_HALF_LIFE_SECONDS)
+    }
+
+    pub fn max_target_change_pct(&self) -> f64 {
+        self.max_target_change_pct
+            .unwrap_or(crate::infrastructure::MAX_TARGET_CHANGE_PCT_PER_CYCLE)
+    }
+
+    pub fn mint_supply_recheck(&self) -> bool {
+        self.mint_supply_recheck.unwrap_or(true)
+    }
+
+    pub fn mint_supply_tolerance_bps(&self) -> u64 {
+        self.mint_supply_tolerance_bps
+            .unwrap_or(crate::infrastructure::MINT_SUPPLY_TOLERANCE_BPS)
+    }
+
+    pub fn tvl_outage_fallback(&self) -> bool {
+        self.tvl_outage_fallback.unwrap_or(false)
+    }
+
+    pub fn approval_min_remaining_seconds(&self) -> u64 {
+        self.approval_min_remaining_seconds
+            .unwrap_or(crate::infrastructure::APPROVAL_MIN_REMAINING_SECONDS)
+    }
+
+    pub fn max_pending_mints(&self) -> u64 {
+        self.max_pending_mints
+            .unwrap_or(crate::infrastructure::MAX_GLOBAL_PENDING_MINTS)
+    }
+
+    pub fn aggressive_mint_cleanup(&self) -> bool {
+        self.aggressive_mint_cleanup.unwrap_or(true)
+    }
+
+    pub fn unpause_cooldown_seconds(&self) -> u64 {
+        self.unpause_cooldown_seconds
+            .unwrap_or(crate::infrastructure::UNPAUSE_REBALANCE_COOLDOWN_SECONDS)
+    }
+
+    pub fn trade_intensity_curve(&self) -> IntensityCurve {
+        self.trade_intensity_curve.unwrap_or_default()
+    }
+
+    pub fn partial_fill_policy(&self) -> PartialFillPolicy {
+        self.partial_fill_policy.unwrap_or_default()
+    }
+
+    pub fn mint_balance_check(&self) -> bool {
+        self.mint_balance_check.unwrap_or(false)
+    }
+
+    pub fn max_concurrent_mints(&self) -> u64 {
+        self.max_concurrent_mints
+            .unwrap_or(crate::infrastructure::MAX_CONCURRENT_MINTS)
+    }
+
+    pub fn instruction_budget_fraction(&self) -> f64 {
+        self.instruction_budget_fraction
+            .unwrap_or(crate::infrastructure::INSTRUCTION_BUDGET_FRACTION)
+    }
+
+    pub fn require_authenticated_reads(&self) -> bool {
+        self.require_authenticated_reads.unwrap_or(false)
+    }
+
+    pub fn cache_prewarm_interval_seconds(&self) -> u64 {
+        self.cache_prewarm_interval_seconds
+            .unwrap_or(crate::infrastructure::constants::CACHE_PREWARM_INTERVAL_SECONDS)
+    }
+
+    pub fn max_token_weight(&self, token: &TrackedToken) -> f64 {
+        self.max_token_weights.iter().flatten()
+            .find(|(t, _)| t == token)
+            .map(|(_, pct)| *pct)
+            .unwrap_or(crate::infrastructure::MAX_TOKEN_WEIGHT_PCT)
+    }
+
+    pub fn max_tvl_generation_age_seconds(&self) -> u64 {
+        self.max_tvl_generation_age_seconds
+            .unwrap_or(crate::infrastructure::TVL_GENERATION_MAX_AGE_SECONDS)
+    }
+
+    pub fn max_target_age_seconds(&self) -> u64 {
+        self.max_target_age_seconds
+            .unwrap_or(crate::infrastructure::MAX_TARGET_AGE_SECONDS)
+    }
+
+    pub fn grace_period_seconds(&self) -> u64 {
+        self.grace_period_seconds.unwrap_or(crate::infrastructure::GRACE_PERIOD_SECONDS)
+    }
+
+    pub fn rebalance_cycles_budget(&self) -> u128 {
+        self.rebalance_cycles_budget.unwrap_or(crate::infrastructure::REBALANCE_CYCLES_BUDGET)
+    }
+
+    pub fn rate_limit_window_seconds(&self, op: RateLimitedOperation) -> u64 {
+        match op {
+            RateLimitedOperation::Mint => self.mint_rate_limit_seconds
+                .unwrap_or(crate::infrastructure::MINT_RATE_LIMIT_SECONDS),
+            RateLimitedOperation::Burn => self.burn_rate_limit_seconds
+                .unwrap_or(crate::infrastructure::BURN_RATE_LIMIT_SECONDS),
+            RateLimitedOperation::ManualRebalance => self.manual_rebalance_rate_limit_seconds
+                .unwrap_or(crate::infrastructure::MANUAL_REBALANCE_RATE_LIMIT_SECONDS),
+        }
+    }
+
+    pub fn rate_limit_bypass(&self) -> Vec<Principal> {
+        self.rate_limit_bypass.clone().unwrap_or_default()
+    }
+
+    pub fn redemption_shortfall_policy(&self) -> ShortfallPolicy {
+        self.redemption_shortfall_policy.unwrap_or_default()
+    }
+
+    pub fn min_successful_lockers(&self) -> u64 {
+        self.min_successful_lockers.unwrap_or(crate::infrastructure::MIN_SUCCESSFUL_LOCKERS)
+    }
+
+    pub fn activity_tracking_threshold_e6(&self) -> u64 {
+        self.activity_tracking_threshold_e6.unwrap_or(crate::infrastructure::ACTIVITY_TRACKING_THRESHOLD_E6)
+    }
+
+    pub fn auto_pause_on_corruption(&self) -> bool {
+        self.auto_pause_on_corruption.unwrap_or(true)
+    }
+
+    /// Symbol to pass to Kongswap for `token`
+    pub fn kongswap_symbol(&self, token: &TrackedToken) -> String {
+        self.kongswap_symbols.iter().flatten()
+            .find(|(t, _)| t == token)
+            .map(|(_, symbol)| symbol.clone())
+            .unwrap_or_else(|| token.to_symbol().to_string())
+    }
+
+    /// Kongswap symbol for an index symbol; symbols of untracked tokens pass through
+    pub fn kongswap_symbol_for(&self, symbol: &str) -> String {
+        match TrackedToken::from_symbol(symbol) {
+            Ok(token) => self.kongswap_symbol(&token),
+            Err(_) => symbol.to_string(),
+        }
+    }
+}
+
+thread_local! {
+    static CONFIG: RefCell<RuntimeConfig> = RefCell::new(RuntimeConfig::default());
+}
+
+/// Get a copy of the current runtime config
+pub fn get_config() -> RuntimeConfig {
+    CONFIG.with(|c| c.borrow().clone())
+}
+
+/// Mutate the runtime config in place
+pub fn update_config<F: FnOnce(&mut RuntimeConfig)>(f: F) {
+    CONFIG.with(|c| f(&mut c.borrow_mut()));
+}
+
+/// Mutate the runtime config, returning the setting `view` selects before
+/// and after the change, formatted for the admin log
+pub fn update_config_diff<T, V, F>(view: V, f: F) -> (String, String)
+where
+    T: std::fmt::Debug,
+    V: Fn(&RuntimeConfig) -> T,
+    F: FnOnce(&mut RuntimeConfig),
+{
+    let mut diff = (String::new(), String::new());
+    update_config(|config| {
+        diff.0 = format!("{:?}", view(config));
+        f(config);
+        diff.1 = format!("{:?}", view(config));
+    });
+    diff
+}
+
+/// Settings after applying defaults, as reported by `get_effective_config`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct EffectiveConfig {
+    pub rate_limits: RateLimitConfig,
+    pub grace_period_seconds: u64,
+    pub rebalance_cycles_budget: u128,
+}
+
+pub fn get_effective_config() -> EffectiveConfig {
+    let config = get_config();
+    EffectiveConfig {
+        rate_limits: crate::infrastructure::rate_limiting::get_rate_limit_config(),
+        grace_period_seconds: config.grace_period_seconds(),
+        rebalance_cycles_budget: config.rebalance_cycles_budget(),
+    }
+}
+
+/// Export config for stable storage
+pub fn export_config() -> RuntimeConfig {
+    get_config()
+}
+
+/// Import config from stable storage
+pub fn import_config(config: RuntimeConfig) {
+    CONFIG.with(|c| *c.borrow_mut() = config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_has_no_oracle() {
+        assert!(RuntimeConfig::default().price_oracle.is_none());
+    }
+
+    #[test]
+    fn test_update_config_diff_reports_before_and_after() {
+        import_config(RuntimeConfig::default());
+        let (before, after) = update_config_diff(|c| c.unpause_cooldown_seconds, |c| c.unpause_cooldown_seconds = Some(60));
+        assert_eq!((before.as_str(), after.as_str()), ("None", "Some(60)"));
+
+        let (before, after) = update_config_diff(
+            |c| (c.mint_supply_recheck, c.mint_supply_tolerance_bps),
+            |c| c.mint_supply_tolerance_bps = Some(25),
+        );
+        assert_eq!((before.as_str(), after.as_str()), ("(None, None)", "(None, Some(25))"));
+        import_config(RuntimeConfig::default());
+    }
+
+    #[test]
+    fn test_default_access_mode_is_open() {
+        assert_eq!(RuntimeConfig::default().access_mode(), AccessMode::Open);
+    }
+
+    #[test]
+    fn test_default_target_smoothing() {
+        let config = RuntimeConfig::default();
+        assert_eq!(config.target_smoothing_half_life_seconds(), crate::infrastructure::TARGET_SMOOTHING_HALF_LIFE_SECONDS);
+        assert_eq!(config.max_target_change_pct(), crate::infrastructure::MAX_TARGET_CHANGE_PCT_PER_CYCLE);
+    }
+
+    #[test]
+    fn test_max_token_weight_override() {
+        let mut config = RuntimeConfig::default();
+        assert_eq!(config.max_token_weight(&TrackedToken::ALEX), crate::infrastructure::MAX_TOKEN_WEIGHT_PCT);
+
+        config.max_token_weights = Some(vec![(TrackedToken::ALEX, 35.0)]);
+        assert_eq!(config.max_token_weight(&TrackedToken::ALEX), 35.0);
+        assert_eq!(config.max_token_weight(&TrackedToken::BOB), crate::infrastructure::MAX_TOKEN_WEIGHT_PCT);
+    }
+
+    #[test]
+    fn test_kongswap_symbol_alias() {
+        let mut config = RuntimeConfig::default();
+        assert_eq!(config.kongswap_symbol(&TrackedToken::ALEX), "ALEX");
+
+        config.kongswap_symbols = Some(vec![(TrackedToken::ALEX, "ALEX.ysy5f".to_string())]);
+        assert_eq!(config.kongswap_symbol(&TrackedToken::ALEX), "ALEX.ysy5f");
+        assert_eq!(config.kongswap_symbol_for("ALEX"), "ALEX.ysy5f");
+        assert_eq!(config.kongswap_symbol_for("BOB"), "BOB");
+        assert_eq!(config.kongswap_symbol_for("ICP"), "ICP");
+    }
+
+    #[test]
+    fn test_tvl_outage_fallback_off_by_default() {
+        assert!(!RuntimeConfig::default().tvl_outage_fallback());
+    }
+
+    #[test]
+    fn test_default_approval_margin() {
+        assert_eq!(
+            RuntimeConfig::default().approval_min_remaining_seconds(),
+            crate::infrastructure::APPROVAL_MIN_REMAINING_SECONDS
+        );
+    }
+
+    #[test]
+    fn test_update_and_import_config() {
+        let oracle = Principal::from_text("2ipq2-uqaaa-aaaar-qailq-cai").unwrap();
+        update_config(|c| c.price_oracle = Some(oracle));
+        assert_eq!(get_config().price_oracle, Some(oracle));
+
+        import_config(RuntimeConfig::default());
+        assert!(get_config().price_oracle.is_none());
+    }
+}
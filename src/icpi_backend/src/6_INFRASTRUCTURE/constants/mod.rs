@@ -27,20 +27,54 @@ pub const MIN_MINT_AMOUNT: u64 = 100_000; // 0.1 ckUSDT (e6)
 pub const MAX_MINT_AMOUNT: u64 = 100_000_000_000; // 100k ckUSDT
 pub const MINT_TIMEOUT_NANOS: u64 = 180_000_000_000; // 3 minutes
 pub const MINT_FEE_AMOUNT: u64 = 100_000; // 0.1 ckUSDT
+pub const USER_APPROVAL_EXPIRY_SECONDS: u64 = 1800; // Suggested expiry of user mint/burn approvals
+pub const MAX_OPERATION_MEMO_BYTES: usize = 64; // Caller-supplied mint/burn memo
+pub const MINT_SUPPLY_TOLERANCE_BPS: u64 = 100; // Abort mint if supply moved >1% since snapshot
+pub const MAX_GLOBAL_PENDING_MINTS: u64 = 10_000; // Non-terminal mints across all users
+pub const MAX_CONCURRENT_MINTS: u64 = 20; // In-flight complete_mint calls across all users
+pub const INSTRUCTION_LIMIT_PER_MESSAGE: u64 = 40_000_000_000; // Update message instruction limit
+pub const INSTRUCTION_BUDGET_FRACTION: f64 = 0.8; // Loops stop at 80% of the limit
+pub const MINT_RATE_LIMIT_SECONDS: u64 = 1; // Minimum interval between mints from one principal
+pub const BURN_DISTRIBUTION_BATCH_SIZE: usize = 4; // Parallel redemption transfers between budget checks
+pub const AGGRESSIVE_CLEANUP_OCCUPANCY_PCT: f64 = 80.0; // Shorten mint expiry above this occupancy
+pub const MINT_RECOVERY_SCAN_LENGTH: u64 = 2_000; // Recent ICPI ledger transactions searched for an interrupted mint
+pub const MAX_MINT_RECOVERIES: usize = 100; // Post-upgrade mint recovery records kept
 pub const FEE_RECIPIENT: &str = "e454q-riaaa-aaaap-qqcyq-cai";
 
 // ===== Burning Constants =====
 pub const MIN_BURN_AMOUNT: u64 = 11_000; // 0.00011 ICPI (e8)
+pub const MAX_BURN_SUPPLY_BPS: u32 = 1_000; // 10% of supply per burn
 pub const BURN_FEE_BUFFER: u64 = 10_000; // Transfer fee buffer
+pub const BURN_RATE_LIMIT_SECONDS: u64 = 1; // Minimum interval between burns from one principal
+pub const BURN_RETRY_MAX_ATTEMPTS: u32 = 5; // Failed redemption transfer retries before becoming a claim
+pub const BURN_RETRY_BASE_DELAY_SECONDS: u64 = 60; // Doubles per attempt; all retries land well inside the ledger dedup window
+pub const BURN_RETRY_INTERVAL_SECONDS: u64 = 30;
+pub const BURN_RETRY_BATCH_SIZE: usize = 10; // Due retries attempted per timer run
+pub const MAX_BURN_RETRY_EVENTS: usize = 200;
+
+// ===== Display Constants =====
+pub const MAX_STATUS_MESSAGE_BYTES: usize = 280; // Operator banner text
 
 // ===== Rebalancing Constants =====
+pub const UNPAUSE_REBALANCE_COOLDOWN_SECONDS: u64 = 300; // Let caches refresh after emergency unpause
 pub const REBALANCE_INTERVAL_SECONDS: u64 = 3600; // 1 hour
 pub const MIN_DEVIATION_PERCENT: f64 = 1.0; // 1% minimum deviation to trigger
+pub const POKE_COOLDOWN_SECONDS: u64 = 900; // One successful public poke per 15 minutes
+pub const MANUAL_REBALANCE_RATE_LIMIT_SECONDS: u64 = 60; // Per-caller pokes/manual rebalances: 1/minute
+pub const MAX_RATE_LIMIT_WINDOW_SECONDS: u64 = 86_400; // Longest per-principal window `set_rate_limit_window` accepts
+pub const GRACE_PERIOD_SECONDS: u64 = 60; // Between global operation type switches
+pub const REBALANCE_CYCLES_BUDGET: u128 = 2_000_000_000_000; // Skip a cycle below 2T cycles
 pub const TRADE_INTENSITY: f64 = 0.1; // Trade 10% of deviation per hour
+pub const TRADE_INTENSITY_MIN: f64 = 0.05; // Ramped curves: floor for small deviations
+pub const TRADE_INTENSITY_MAX: f64 = 0.3; // Ramped curves: cap for large deviations
+pub const INTENSITY_RAMP_FULL_DEVIATION_PERCENT: f64 = 10.0; // Deviation at which ramps reach the cap
+pub const TARGET_SMOOTHING_HALF_LIFE_SECONDS: u64 = 21_600; // 6 hour EWMA half-life on Kong Locker TVL
+pub const MAX_TARGET_CHANGE_PCT_PER_CYCLE: f64 = 2.0; // Targets move at most 2pp per rebalance cycle
+pub const MAX_TOKEN_WEIGHT_PCT: f64 = 50.0; // Default cap on any one token's target weight
 /// Maximum slippage tolerance for rebalancing trades
 ///
-/// **Value Format**: Percentage (e.g., 5.0 = 5%). Passed directly to Kongswap's
-/// max_slippage parameter without conversion.
+/// **Value Format**: Basis points (500 = 5%). Swaps take it as a
+/// `Slippage`, which converts to Kongswap's percent form at the call.
 ///
 /// Set to 5% to accommodate low liquidity in ALEX/ckUSDT pool on Kongswap.
 /// This is safe because:
@@ -56,8 +90,20 @@ pub const TRADE_INTENSITY: f64 = 0.1; // Trade 10% of deviation per hour
 ///   resulting in 0.05% limit instead of 5% limit
 ///
 /// See: SLIPPAGE_ISSUE_DIAGNOSTIC.md for full analysis
-pub const MAX_SLIPPAGE_PERCENT: f64 = 5.0;
+pub const MAX_SLIPPAGE_BPS: u32 = 500;
+/// Re-approve before swapping if the approval has less than this left
+pub const APPROVAL_MIN_REMAINING_SECONDS: u64 = 120;
+/// Pre-trade probe must show slippage within this fraction of the max,
+/// leaving room for the pool to move between probe and swap
+pub const POOL_DEPTH_SLIPPAGE_HEADROOM: f64 = 0.8;
 pub const MIN_TRADE_SIZE_USD: f64 = 1.0; // $1 minimum trade (lowered for small portfolios)
+pub const DEVIATION_IMBALANCE_TOLERANCE_PCT: f64 = 10.0; // Net overweight, as % of gross deviation, before trading halts
+pub const DUST_SWEEP_THRESHOLD_USD: f64 = 0.10; // Token positions below $0.10 are swept to ckUSDT
+pub const CKUSDT_TRANSFER_FEE: u64 = 10_000; // 0.01 ckUSDT ledger fee
+pub const ICPI_TRANSFER_FEE: u64 = 10_000; // 0.0001 ICPI ledger fee
+pub const UNATTRIBUTED_CKUSDT_THRESHOLD: u64 = 1_000_000; // Quarantine unexplained ckUSDT above $1
+pub const STRAY_DEPOSIT_SWEEP_INTERVAL_SECONDS: u64 = 86_400; // Daily
+pub const MAX_RESERVE_EVENTS: usize = 500;
 
 // ===== Validation Thresholds =====
 pub const MAX_SUPPLY_CHANGE_RATIO: f64 = 1.1; // 10% max supply change
@@ -69,6 +115,40 @@ pub const MAX_REASONABLE_PRICE: f64 = 1_000_000.0; // $1M maximum
 pub const CACHE_DURATION_SHORT: u64 = 30;
 pub const CACHE_DURATION_MEDIUM: u64 = 300;
 pub const CACHE_DURATION_LONG: u64 = 3600;
+pub const INDEX_STATE_CACHE_TTL_SECONDS: u64 = CACHE_DURATION_MEDIUM;
+pub const TVL_CACHE_TTL_SECONDS: u64 = CACHE_DURATION_LONG;
+pub const TVL_REFRESH_INTERVAL_SECONDS: u64 = 1800; // Background refresh keeps the TVL cache warm
+pub const TVL_GENERATION_PAGE_SIZE: usize = 50; // Lock canisters queried per TVL generation step
+pub const TVL_GENERATION_STEP_SECONDS: u64 = 10;
+pub const TVL_GENERATION_MAX_AGE_SECONDS: u64 = 7200; // Rebalancing waits for a TVL generation this recent
+pub const MIN_SUCCESSFUL_LOCKERS: u64 = 1; // Lock canister queries a TVL needs besides the >50% success rate
+pub const MAX_TARGET_AGE_SECONDS: u64 = 2 * TVL_REFRESH_INTERVAL_SECONDS; // Rebalancing refuses targets from older TVL
+pub const CACHE_PREWARM_INTERVAL_SECONDS: u64 = 240; // Inside the index state TTL
+pub const MIN_PREWARM_CYCLES: u128 = 1_000_000_000_000; // Skip pre-warming below 1T cycles
+pub const HOLDER_COUNT_CACHE_TTL_SECONDS: u64 = CACHE_DURATION_LONG;
+pub const QUOTE_RATE_CACHE_TTL_SECONDS: u64 = CACHE_DURATION_MEDIUM; // ICP/ckUSDT rate behind non-ckUSDT NAV quotes
+pub const HOLDER_SYNC_BATCH_SIZE: u64 = 2_000; // Ledger transactions fetched per page
+pub const HOLDER_SYNC_MAX_BATCHES: u64 = 20; // Pages indexed per get_holder_count call
+
+// ===== Portfolio History =====
+pub const PORTFOLIO_SNAPSHOT_INTERVAL_SECONDS: u64 = 600; // At most one history entry per 10 minutes
+pub const MAX_PORTFOLIO_SNAPSHOTS: usize = 1008; // 7 days at 10 minute spacing
+pub const TICKER_CHANGE_TOLERANCE_SECONDS: u64 = 7200; // 24h reference point must be within 2h
+
+// ===== User Activity =====
+pub const ACTIVITY_TRACKING_THRESHOLD_E6: u64 = 100_000_000; // $100 operation before a principal is tracked on its own
+pub const MAX_TRACKED_PRINCIPALS: usize = 10_000; // Further principals go to the aggregate bucket
+pub const MAX_ACTIVITY_TOP_N: usize = 100;
+
+// ===== Cost Accounting =====
+pub const MAX_COST_DAYS: usize = 365; // Daily cost buckets kept
+pub const CYCLES_SAMPLE_INTERVAL_SECONDS: u64 = 3600;
+pub const USD_E6_PER_TRILLION_CYCLES: u64 = 1_340_000; // 1T cycles = 1 XDR ≈ $1.34
+
+// ===== Stable Storage =====
+pub const STABLE_FALLBACK_OFFSET: u64 = 64 * 1024 * 1024; // Fallback state copy, well past any primary save
+pub const STABLE_FALLBACK_RESERVED_BYTES: u64 = 8 * 1024 * 1024; // Grown ahead of time so the fallback needs no new pages
+pub const SAVE_PROBE_INTERVAL_SECONDS: u64 = 6 * 3600;
 
 // ===== Target Allocations (percentages) =====
 pub const TARGET_ALEX_PERCENT: f64 = 25.0;
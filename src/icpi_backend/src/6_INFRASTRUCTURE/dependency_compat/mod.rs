@@ -0,0 +1,229 @@
+//! Candid compatibility probes for external canisters
+//!
+//! A dependency changing its interface shows up as a "failed to decode"
+//! call rejection in the middle of a trade. This module calls every external
+//! method the backend relies on with harmless arguments and records whether
+//! the reply decoded against our local types. Any incompatible method marks
+//! the canister degraded, and minting and rebalancing fail fast until a
+//! later probe decodes cleanly again.
+//!
+//! Probes run after each upgrade and on demand (admin).
+
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::{CandidType, Deserialize, Nat, Principal};
+use ic_cdk::api::call::RejectionCode;
+use std::cell::RefCell;
+use std::time::Duration;
+
+use crate::infrastructure::{Result, IcpiError, SystemError, KONGSWAP_BACKEND_ID, KONG_LOCKER_ID};
+use crate::types::icrc::{Account, Allowance, AllowanceArgs};
+use crate::types::kongswap::{SwapAmountsResult, UserBalancesResult};
+use crate::types::{TrackedToken, CKUSDT_CANISTER_ID, ICPI_CANISTER_ID};
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum CompatStatus {
+    /// The reply decoded against our types
+    Compatible,
+    /// The call went through but the reply didn't decode
+    Incompatible,
+    /// The call failed for another reason; compatibility unknown
+    Unreachable,
+}
+
+/// Probe result for one external method
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct DependencyCompat {
+    pub canister: String,
+    pub method: String,
+    pub status: CompatStatus,
+    /// Rejection message when not compatible
+    pub detail: Option<String>,
+    pub checked_at: u64,
+}
+
+/// Latest compatibility matrix, as reported by `get_dependency_compat`
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CompatReport {
+    pub entries: Vec<DependencyCompat>,
+    pub last_probe_at: Option<u64>,
+    /// Set when any method is incompatible
+    pub degraded: bool,
+}
+
+thread_local! {
+    static REPORT: RefCell<CompatReport> = RefCell::new(CompatReport::default());
+}
+
+/// Classify a call outcome; ic-cdk reports reply decode errors as
+/// `CanisterError` rejections with a "failed to decode" message
+pub fn classify_outcome(outcome: std::result::Result<(), (RejectionCode, String)>) -> (CompatStatus, Option<String>) {
+    match outcome {
+        Ok(()) => (CompatStatus::Compatible, None),
+        Err((RejectionCode::CanisterError, msg)) if msg.contains("failed to decode") => {
+            (CompatStatus::Incompatible, Some(msg))
+        }
+        Err((code, msg)) => (CompatStatus::Unreachable, Some(format!("{:?}: {}", code, msg))),
+    }
+}
+
+/// Build the report from one probe run
+pub fn build_report(entries: Vec<DependencyCompat>, now: u64) -> CompatReport {
+    let degraded = entries.iter().any(|e| e.status == CompatStatus::Incompatible);
+    CompatReport { entries, last_probe_at: Some(now), degraded }
+}
+
+/// `Canister.method` of every incompatible entry
+pub fn incompatible_methods(report: &CompatReport) -> Vec<String> {
+    report.entries.iter()
+        .filter(|e| e.status == CompatStatus::Incompatible)
+        .map(|e| format!("{}.{}", e.canister, e.method))
+        .collect()
+}
+
+async fn probe<A, R>(canister: &str, id: &str, method: &str, args: A) -> DependencyCompat
+where
+    A: ArgumentEncoder,
+    R: for<'a> ArgumentDecoder<'a>,
+{
+    let outcome = match Principal::from_text(id) {
+        Ok(principal) => crate::infrastructure::runtime::call::<A, R>(principal, method, args).await.map(|_| ()),
+        Err(e) => Err((RejectionCode::DestinationInvalid, format!("Invalid principal {}: {}", id, e))),
+    };
+    let (status, detail) = classify_outcome(outcome);
+    DependencyCompat {
+        canister: canister.to_string(),
+        method: method.to_string(),
+        status,
+        detail,
+        checked_at: crate::infrastructure::runtime::time(),
+    }
+}
+
+/// Ledgers probed for the ICRC methods we call
+fn ledgers() -> Vec<(String, String)> {
+    let mut ledgers: Vec<(String, String)> = TrackedToken::all().iter()
+        .filter_map(|t| t.get_canister_id().ok().map(|id| (t.to_symbol().to_string(), id.to_text())))
+        .collect();
+    ledgers.push(("ckUSDT".to_string(), CKUSDT_CANISTER_ID.to_string()));
+    ledgers.push(("ICPI".to_string(), ICPI_CANISTER_ID.to_string()));
+    ledgers
+}
+
+/// Call every dependency once and replace the stored report
+///
+/// Every probe is a read: Kongswap's `swap` is covered through
+/// `swap_amounts`, which shares its token and amount types, rather than
+/// calling the live update. Symbols are literals, not the configured
+/// Kongswap symbols, so an override can't hide an incompatibility.
+pub async fn run_probes() -> CompatReport {
+    let me = crate::infrastructure::runtime::canister_id();
+    let account = Account { owner: me, subaccount: None };
+
+    let (swap_amounts, user_balances, lock_canisters) = futures::join!(
+        probe::<_, (SwapAmountsResult,)>(
+            "kongswap", KONGSWAP_BACKEND_ID, "swap_amounts",
+            ("ckUSDT".to_string(), Nat::from(1_000_000u64), "ALEX".to_string()),
+        ),
+        probe::<_, (UserBalancesResult,)>("kongswap", KONGSWAP_BACKEND_ID, "user_balances", (me.to_text(),)),
+        probe::<_, (Vec<(Principal, Principal)>,)>("kong_locker", KONG_LOCKER_ID, "get_all_lock_canisters", ()),
+    );
+    let mut entries = vec![swap_amounts, user_balances, lock_canisters];
+
+    let ledger_probes = ledgers().into_iter().map(|(name, id)| {
+        let account = account.clone();
+        async move {
+            let balance = probe::<_, (Nat,)>(&name, &id, "icrc1_balance_of", (account.clone(),)).await;
+            let fee = probe::<_, (Nat,)>(&name, &id, "icrc1_fee", ()).await;
+            let allowance = probe::<_, (Allowance,)>(
+                &name, &id, "icrc2_allowance",
+                (AllowanceArgs { account: account.clone(), spender: account },),
+            ).await;
+            vec![balance, fee, allowance]
+        }
+    });
+    entries.extend(futures::future::join_all(ledger_probes).await.into_iter().flatten());
+
+    let report = build_report(entries, crate::infrastructure::runtime::time());
+    let incompatible = incompatible_methods(&report);
+    if incompatible.is_empty() {
+        crate::log_op!("✅ Dependency compatibility probe: {} methods compatible", report.entries.len());
+    } else {
+        crate::log_op!("🚨 Dependency compatibility probe: incompatible {}", incompatible.join(", "));
+    }
+    REPORT.with(|r| *r.borrow_mut() = report.clone());
+    report
+}
+
+/// Probe once, shortly after an upgrade
+pub fn schedule_probe() {
+    ic_cdk_timers::set_timer(Duration::ZERO, || {
+        ic_cdk::spawn(async {
+            run_probes().await;
+        })
+    });
+}
+
+/// Latest compatibility matrix
+pub fn get_report() -> CompatReport {
+    REPORT.with(|r| r.borrow().clone())
+}
+
+fn check_report(report: &CompatReport) -> Result<()> {
+    let incompatible = incompatible_methods(report);
+    if incompatible.is_empty() {
+        return Ok(());
+    }
+    Err(IcpiError::System(SystemError::DependencyIncompatible { methods: incompatible }))
+}
+
+/// Fail fast while a dependency's interface doesn't match ours
+pub fn check_dependencies_compatible() -> Result<()> {
+    REPORT.with(|r| check_report(&r.borrow()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(canister: &str, method: &str, outcome: std::result::Result<(), (RejectionCode, String)>) -> DependencyCompat {
+        let (status, detail) = classify_outcome(outcome);
+        DependencyCompat {
+            canister: canister.to_string(),
+            method: method.to_string(),
+            status,
+            detail,
+            checked_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_failures_mark_degraded() {
+        let healthy = build_report(vec![
+            entry("kongswap", "swap_amounts", Ok(())),
+            // An unreachable dependency says nothing about its interface
+            entry("kong_locker", "get_all_lock_canisters", Err((RejectionCode::SysTransient, "busy".to_string()))),
+        ], 7);
+        assert!(!healthy.degraded);
+        assert_eq!(healthy.entries[1].status, CompatStatus::Unreachable);
+        assert!(check_report(&healthy).is_ok());
+
+        let broken = build_report(vec![
+            entry("kongswap", "swap_amounts", Ok(())),
+            entry("kongswap", "user_balances", Err((
+                RejectionCode::CanisterError,
+                "failed to decode canister response as (UserBalancesResult,): missing field".to_string(),
+            ))),
+            // Other canister errors are traps, not interface changes
+            entry("ALEX", "icrc1_fee", Err((RejectionCode::CanisterError, "trapped".to_string()))),
+        ], 7);
+        assert!(broken.degraded);
+        assert_eq!(broken.last_probe_at, Some(7));
+        assert_eq!(broken.entries[2].status, CompatStatus::Unreachable);
+        match check_report(&broken) {
+            Err(IcpiError::System(SystemError::DependencyIncompatible { methods })) => {
+                assert_eq!(methods, vec!["kongswap.user_balances".to_string()]);
+            }
+            other => panic!("expected DependencyIncompatible, got {:?}", other),
+        }
+    }
+}
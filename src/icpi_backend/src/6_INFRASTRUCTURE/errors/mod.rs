@@ -36,6 +36,9 @@ pub enum IcpiError {
     // Query errors
     Query(QueryError),
 
+    // ICRC ledger rejections
+    Ledger(LedgerError),
+
     // Generic error
     Other(String),
 }
@@ -53,6 +56,9 @@ pub enum MintError {
     LedgerInteractionFailed { operation: String, details: String },
     Unauthorized { principal: String, mint_id: String },
     ProportionalCalculationError { reason: String },
+    SupplyChanged { snapshot: String, current: String, tolerance_bps: u64 },
+    PendingMintCapReached { pending: u64, max: u64 },
+    BalanceNotCredited { expected_increase: String, observed_increase: String },
 }
 
 // Burn-specific errors
@@ -60,14 +66,23 @@ pub enum MintError {
 pub enum BurnError {
     AmountBelowMinimum { amount: String, minimum: String },
     AmountExceedsMaximum { amount: String, maximum: String, percentage_limit: String },
-    InsufficientApproval { required: String, approved: String },
+    /// `required` lists every approval the burn needs, `missing` the tokens short of one
+    InsufficientApproval { required: Vec<RequiredApproval>, missing: Vec<String> },
     InsufficientBalance { required: String, available: String },
-    InsufficientFeeAllowance { required: String, approved: String },
     NoSupply,
     NoRedemptionsPossible { reason: String },
     TokenTransferFailed { token: String, amount: String, reason: String },
 }
 
+// An ICRC-2 approval of the backend an operation depends on
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub struct RequiredApproval {
+    pub token: String,
+    pub required: String,
+    /// None when the allowance couldn't be read
+    pub approved: Option<String>,
+}
+
 // Rebalancing errors
 #[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
 pub enum RebalanceError {
@@ -90,6 +105,7 @@ pub enum TradingError {
     SlippageExceeded { expected: Nat, actual: Nat, max_allowed: f64, actual_slippage: f64 },
     SwapFailed { pay_token: String, receive_token: String, amount: Nat, reason: String },
     InvalidSwapAmount { reason: String },
+    PartialFill { requested: Nat, consumed: Nat },
 }
 
 // Kongswap integration errors
@@ -109,6 +125,12 @@ pub enum ValidationError {
     PriceOutOfBounds { price: String, min: String, max: String },
     RapidChangeDetected { field: String, old_value: String, new_value: String, max_change: String },
     DataInconsistency { reason: String },
+    PrincipalDenylisted { principal: String },
+    PrincipalNotAllowlisted { principal: String },
+    IncompleteValuation { missing_tokens: Vec<String> },
+    MemoTooLong { bytes: u64, max: u64 },
+    DuplicateToken { field: String, value: String, tokens: Vec<String> },
+    UnsupportedQuote { symbol: String, supported: Vec<String> },
 }
 
 // Calculation errors
@@ -118,6 +140,7 @@ pub enum CalculationError {
     DivisionByZero { operation: String },
     ConversionError { from: String, to: String, reason: String },
     PrecisionLoss { operation: String, original: String, result: String },
+    InvalidPortfolioState { reason: String },
 }
 
 // System errors
@@ -127,11 +150,18 @@ pub enum SystemError {
     StateCorrupted { reason: String },
     InterCanisterCallFailed { canister: String, method: String, reason: String },
     OperationInProgress { operation: String, user: String },
+    /// System-wide concurrency cap reached; retry later
+    TooManyConcurrentOperations { operation: String, active: u64, max: u64 },
+    ConflictingOperation { operation: String, operation_id: Option<String> },
     EmergencyPause,
+    /// New operations refused while draining for an upgrade
+    Quiescing,
     // M-4: Global operation coordination errors
     GracePeriodActive { wait_seconds: u64, current_operation: String },
     RebalancingInProgress,
     CriticalOperationInProgress { operation: String },
+    /// An external method's reply no longer decodes against our types
+    DependencyIncompatible { methods: Vec<String> },
 }
 
 // Query errors
@@ -142,6 +172,196 @@ pub enum QueryError {
     Timeout { canister: String, method: String },
 }
 
+// ICRC ledger errors (the call went through but the ledger rejected it)
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub enum LedgerError {
+    InsufficientFunds { ledger: String, balance: String },
+    InsufficientAllowance { ledger: String, allowance: String },
+    Rejected { ledger: String, method: String, reason: String },
+}
+
+/// Where an error originated, as reported to API callers
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ErrorZone {
+    /// The request itself was invalid (bad input, missing approval, not permitted)
+    Caller,
+    /// Zone 1: mint, burn and rebalance orchestration
+    CriticalOperations,
+    /// Zone 2: portfolio data, supply and valuation
+    CriticalData,
+    /// Zone 4: swaps and Kongswap
+    TradingExecution,
+    /// Zone 6: ledgers, inter-canister calls and system state
+    Infrastructure,
+    /// Untyped error; no classification possible
+    Unclassified,
+}
+
+/// Error as returned at the API boundary: the error plus its classification
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct ApiError {
+    pub error: IcpiError,
+    pub zone: ErrorZone,
+    /// Whether the same call may succeed later without the caller changing anything
+    pub retryable: bool,
+    /// Id of the request that failed, for support (mint, burn and claim calls)
+    pub request_id: Option<String>,
+}
+
+/// Result type of canister endpoints
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+impl From<IcpiError> for ApiError {
+    fn from(error: IcpiError) -> Self {
+        let (zone, retryable) = classify(&error);
+        ApiError { error, zone, retryable, request_id: None }
+    }
+}
+
+impl ApiError {
+    /// Attach the id of the request that produced this error
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.request_id = Some(request_id);
+        self
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.request_id {
+            Some(id) => write!(f, "{} (request {})", self.error, id),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// Zone and retryability of an error
+///
+/// Matches every variant without wildcards so a new variant fails to
+/// compile until it is classified here.
+pub fn classify(error: &IcpiError) -> (ErrorZone, bool) {
+    use ErrorZone::*;
+    match error {
+        IcpiError::Mint(e) => match e {
+            MintError::InvalidMintId { .. }
+            | MintError::AmountBelowMinimum { .. }
+            | MintError::AmountAboveMaximum { .. }
+            | MintError::Unauthorized { .. }
+            | MintError::FeeCollectionFailed { .. }
+            | MintError::DepositCollectionFailed { .. } => (Caller, false),
+            MintError::LedgerInteractionFailed { .. }
+            | MintError::SupplyChanged { .. }
+            | MintError::PendingMintCapReached { .. } => (CriticalOperations, true),
+            MintError::RefundFailed { .. }
+            | MintError::InsufficientTVL { .. }
+            | MintError::ProportionalCalculationError { .. }
+            | MintError::BalanceNotCredited { .. } => (CriticalOperations, false),
+        },
+        IcpiError::Burn(e) => match e {
+            BurnError::AmountBelowMinimum { .. }
+            | BurnError::AmountExceedsMaximum { .. }
+            | BurnError::InsufficientApproval { .. }
+            | BurnError::InsufficientBalance { .. } => (Caller, false),
+            BurnError::NoSupply => (CriticalData, false),
+            BurnError::NoRedemptionsPossible { .. } => (CriticalOperations, false),
+            BurnError::TokenTransferFailed { .. } => (CriticalOperations, true),
+        },
+        IcpiError::Rebalance(e) => match e {
+            RebalanceError::TooSoonToRebalance { .. }
+            | RebalanceError::SwapFailed { .. }
+            | RebalanceError::RebalancingInProgress => (CriticalOperations, true),
+            RebalanceError::TimerNotActive
+            | RebalanceError::AllocationCalculationError { .. }
+            | RebalanceError::InsufficientBalance { .. } => (CriticalOperations, false),
+        },
+        IcpiError::Trading(e) => match e {
+            TradingError::InvalidQuote { .. }
+            | TradingError::SlippageTooHigh { .. }
+            | TradingError::ApprovalFailed { .. }
+            | TradingError::KongswapError { .. }
+            | TradingError::SlippageExceeded { .. }
+            | TradingError::SwapFailed { .. }
+            | TradingError::PartialFill { .. } => (TradingExecution, true),
+            TradingError::InvalidTokenCanister { .. }
+            | TradingError::InvalidSwapAmount { .. } => (TradingExecution, false),
+        },
+        IcpiError::Kongswap(e) => match e {
+            KongswapError::BackendUnreachable { .. }
+            | KongswapError::SwapAmountCalculationFailed { .. } => (TradingExecution, true),
+            KongswapError::LiquidityPoolNotFound { .. } => (TradingExecution, false),
+        },
+        IcpiError::Validation(e) => match e {
+            ValidationError::InvalidPrincipal { .. }
+            | ValidationError::InvalidAmount { .. }
+            | ValidationError::PrincipalDenylisted { .. }
+            | ValidationError::PrincipalNotAllowlisted { .. }
+            | ValidationError::MemoTooLong { .. }
+            | ValidationError::UnsupportedQuote { .. } => (Caller, false),
+            ValidationError::PriceOutOfBounds { .. }
+            | ValidationError::RapidChangeDetected { .. }
+            | ValidationError::IncompleteValuation { .. } => (CriticalData, true),
+            ValidationError::SupplyOutOfBounds { .. }
+            | ValidationError::DataInconsistency { .. }
+            | ValidationError::DuplicateToken { .. } => (CriticalData, false),
+        },
+        IcpiError::Calculation(e) => match e {
+            CalculationError::Overflow { .. }
+            | CalculationError::DivisionByZero { .. }
+            | CalculationError::ConversionError { .. }
+            | CalculationError::PrecisionLoss { .. }
+            | CalculationError::InvalidPortfolioState { .. } => (CriticalData, false),
+        },
+        IcpiError::System(e) => match e {
+            SystemError::Unauthorized { .. } => (Caller, false),
+            SystemError::InterCanisterCallFailed { .. }
+            | SystemError::OperationInProgress { .. }
+            | SystemError::TooManyConcurrentOperations { .. }
+            | SystemError::ConflictingOperation { .. }
+            | SystemError::GracePeriodActive { .. }
+            | SystemError::RebalancingInProgress
+            | SystemError::CriticalOperationInProgress { .. }
+            | SystemError::Quiescing => (Infrastructure, true),
+            SystemError::StateCorrupted { .. }
+            | SystemError::EmergencyPause
+            | SystemError::DependencyIncompatible { .. } => (Infrastructure, false),
+        },
+        IcpiError::Query(e) => match e {
+            QueryError::CanisterUnreachable { .. }
+            | QueryError::InvalidResponse { .. }
+            | QueryError::Timeout { .. } => (Infrastructure, true),
+        },
+        IcpiError::Ledger(e) => match e {
+            LedgerError::InsufficientFunds { .. }
+            | LedgerError::InsufficientAllowance { .. } => (Caller, false),
+            LedgerError::Rejected { .. } => (Infrastructure, true),
+        },
+        IcpiError::Other(_) => (Unclassified, false),
+    }
+}
+
+/// Short code naming an error's variant, e.g. `Mint.InsufficientTVL`
+///
+/// Payload fields are left out, so the code is stable across occurrences.
+pub fn error_code(error: &IcpiError) -> String {
+    fn variant(e: &impl std::fmt::Debug) -> String {
+        let debug = format!("{:?}", e);
+        debug.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or_default().to_string()
+    }
+    match error {
+        IcpiError::Mint(e) => format!("Mint.{}", variant(e)),
+        IcpiError::Burn(e) => format!("Burn.{}", variant(e)),
+        IcpiError::Rebalance(e) => format!("Rebalance.{}", variant(e)),
+        IcpiError::Trading(e) => format!("Trading.{}", variant(e)),
+        IcpiError::Kongswap(e) => format!("Kongswap.{}", variant(e)),
+        IcpiError::Validation(e) => format!("Validation.{}", variant(e)),
+        IcpiError::Calculation(e) => format!("Calculation.{}", variant(e)),
+        IcpiError::System(e) => format!("System.{}", variant(e)),
+        IcpiError::Query(e) => format!("Query.{}", variant(e)),
+        IcpiError::Ledger(e) => format!("Ledger.{}", variant(e)),
+        IcpiError::Other(_) => "Other".to_string(),
+    }
+}
+
 // Display implementations
 impl std::fmt::Display for IcpiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -155,6 +375,7 @@ impl std::fmt::Display for IcpiError {
             IcpiError::Calculation(e) => write!(f, "Calculation error: {:?}", e),
             IcpiError::System(e) => write!(f, "System error: {:?}", e),
             IcpiError::Query(e) => write!(f, "Query error: {:?}", e),
+            IcpiError::Ledger(e) => write!(f, "Ledger error: {:?}", e),
             IcpiError::Other(msg) => write!(f, "{}", msg),
         }
     }
@@ -177,3 +398,143 @@ impl From<candid::Error> for IcpiError {
         IcpiError::Other(format!("Candid error: {}", e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s() -> String {
+        String::new()
+    }
+
+    /// One instance of every variant; extend alongside the enums
+    fn every_error() -> Vec<IcpiError> {
+        vec![
+            IcpiError::Mint(MintError::InvalidMintId { id: s() }),
+            IcpiError::Mint(MintError::AmountBelowMinimum { amount: s(), minimum: s() }),
+            IcpiError::Mint(MintError::AmountAboveMaximum { amount: s(), maximum: s() }),
+            IcpiError::Mint(MintError::FeeCollectionFailed { user: s(), reason: s() }),
+            IcpiError::Mint(MintError::DepositCollectionFailed { user: s(), amount: s(), reason: s() }),
+            IcpiError::Mint(MintError::RefundFailed { user: s(), amount: s(), reason: s() }),
+            IcpiError::Mint(MintError::InsufficientTVL { tvl: s(), required: s() }),
+            IcpiError::Mint(MintError::LedgerInteractionFailed { operation: s(), details: s() }),
+            IcpiError::Mint(MintError::Unauthorized { principal: s(), mint_id: s() }),
+            IcpiError::Mint(MintError::ProportionalCalculationError { reason: s() }),
+            IcpiError::Mint(MintError::SupplyChanged { snapshot: s(), current: s(), tolerance_bps: 0 }),
+            IcpiError::Mint(MintError::PendingMintCapReached { pending: 0, max: 0 }),
+            IcpiError::Mint(MintError::BalanceNotCredited { expected_increase: s(), observed_increase: s() }),
+            IcpiError::Burn(BurnError::AmountBelowMinimum { amount: s(), minimum: s() }),
+            IcpiError::Burn(BurnError::AmountExceedsMaximum { amount: s(), maximum: s(), percentage_limit: s() }),
+            IcpiError::Burn(BurnError::InsufficientApproval { required: vec![], missing: vec![] }),
+            IcpiError::Burn(BurnError::InsufficientBalance { required: s(), available: s() }),
+            IcpiError::Burn(BurnError::NoSupply),
+            IcpiError::Burn(BurnError::NoRedemptionsPossible { reason: s() }),
+            IcpiError::Burn(BurnError::TokenTransferFailed { token: s(), amount: s(), reason: s() }),
+            IcpiError::Rebalance(RebalanceError::TimerNotActive),
+            IcpiError::Rebalance(RebalanceError::TooSoonToRebalance { last_time: 0, next_time: 0 }),
+            IcpiError::Rebalance(RebalanceError::AllocationCalculationError { reason: s() }),
+            IcpiError::Rebalance(RebalanceError::SwapFailed { token: s(), amount: s(), reason: s() }),
+            IcpiError::Rebalance(RebalanceError::InsufficientBalance { token: s(), available: s(), required: s() }),
+            IcpiError::Rebalance(RebalanceError::RebalancingInProgress),
+            IcpiError::Trading(TradingError::InvalidQuote { reason: s() }),
+            IcpiError::Trading(TradingError::SlippageTooHigh { expected: s(), actual: s(), max_allowed: s() }),
+            IcpiError::Trading(TradingError::ApprovalFailed { token: s(), amount: s(), reason: s() }),
+            IcpiError::Trading(TradingError::InvalidTokenCanister { token: s(), canister_id: s(), reason: s() }),
+            IcpiError::Trading(TradingError::KongswapError { operation: s(), message: s() }),
+            IcpiError::Trading(TradingError::SlippageExceeded {
+                expected: Nat::from(0u64), actual: Nat::from(0u64), max_allowed: 0.0, actual_slippage: 0.0,
+            }),
+            IcpiError::Trading(TradingError::SwapFailed { pay_token: s(), receive_token: s(), amount: Nat::from(0u64), reason: s() }),
+            IcpiError::Trading(TradingError::InvalidSwapAmount { reason: s() }),
+            IcpiError::Trading(TradingError::PartialFill { requested: Nat::from(0u64), consumed: Nat::from(0u64) }),
+            IcpiError::Kongswap(KongswapError::BackendUnreachable { reason: s() }),
+            IcpiError::Kongswap(KongswapError::LiquidityPoolNotFound { token_a: s(), token_b: s() }),
+            IcpiError::Kongswap(KongswapError::SwapAmountCalculationFailed { reason: s() }),
+            IcpiError::Validation(ValidationError::InvalidPrincipal { principal: s() }),
+            IcpiError::Validation(ValidationError::InvalidAmount { amount: s(), reason: s() }),
+            IcpiError::Validation(ValidationError::SupplyOutOfBounds { supply: s(), max: s() }),
+            IcpiError::Validation(ValidationError::PriceOutOfBounds { price: s(), min: s(), max: s() }),
+            IcpiError::Validation(ValidationError::RapidChangeDetected { field: s(), old_value: s(), new_value: s(), max_change: s() }),
+            IcpiError::Validation(ValidationError::DataInconsistency { reason: s() }),
+            IcpiError::Validation(ValidationError::PrincipalDenylisted { principal: s() }),
+            IcpiError::Validation(ValidationError::PrincipalNotAllowlisted { principal: s() }),
+            IcpiError::Validation(ValidationError::IncompleteValuation { missing_tokens: vec![] }),
+            IcpiError::Validation(ValidationError::MemoTooLong { bytes: 0, max: 0 }),
+            IcpiError::Validation(ValidationError::DuplicateToken { field: s(), value: s(), tokens: vec![] }),
+            IcpiError::Validation(ValidationError::UnsupportedQuote { symbol: s(), supported: vec![] }),
+            IcpiError::Calculation(CalculationError::Overflow { operation: s() }),
+            IcpiError::Calculation(CalculationError::DivisionByZero { operation: s() }),
+            IcpiError::Calculation(CalculationError::ConversionError { from: s(), to: s(), reason: s() }),
+            IcpiError::Calculation(CalculationError::PrecisionLoss { operation: s(), original: s(), result: s() }),
+            IcpiError::Calculation(CalculationError::InvalidPortfolioState { reason: s() }),
+            IcpiError::System(SystemError::Unauthorized { principal: s(), required_role: s() }),
+            IcpiError::System(SystemError::StateCorrupted { reason: s() }),
+            IcpiError::System(SystemError::InterCanisterCallFailed { canister: s(), method: s(), reason: s() }),
+            IcpiError::System(SystemError::OperationInProgress { operation: s(), user: s() }),
+            IcpiError::System(SystemError::TooManyConcurrentOperations { operation: s(), active: 0, max: 0 }),
+            IcpiError::System(SystemError::ConflictingOperation { operation: s(), operation_id: None }),
+            IcpiError::System(SystemError::EmergencyPause),
+            IcpiError::System(SystemError::Quiescing),
+            IcpiError::System(SystemError::GracePeriodActive { wait_seconds: 0, current_operation: s() }),
+            IcpiError::System(SystemError::RebalancingInProgress),
+            IcpiError::System(SystemError::CriticalOperationInProgress { operation: s() }),
+            IcpiError::System(SystemError::DependencyIncompatible { methods: vec![] }),
+            IcpiError::Query(QueryError::CanisterUnreachable { canister: s(), reason: s() }),
+            IcpiError::Query(QueryError::InvalidResponse { canister: s(), method: s(), reason: s() }),
+            IcpiError::Query(QueryError::Timeout { canister: s(), method: s() }),
+            IcpiError::Ledger(LedgerError::InsufficientFunds { ledger: s(), balance: s() }),
+            IcpiError::Ledger(LedgerError::InsufficientAllowance { ledger: s(), allowance: s() }),
+            IcpiError::Ledger(LedgerError::Rejected { ledger: s(), method: s(), reason: s() }),
+            IcpiError::Other(s()),
+        ]
+    }
+
+    #[test]
+    fn test_every_variant_classified() {
+        for error in every_error() {
+            let api = ApiError::from(error.clone());
+            assert_eq!((api.zone, api.retryable), classify(&error));
+            // Caller mistakes never succeed on a plain retry
+            if api.zone == ErrorZone::Caller {
+                assert!(!api.retryable, "{:?} is the caller's fault but retryable", error);
+            }
+        }
+    }
+
+    #[test]
+    fn test_classification_examples() {
+        let kong = IcpiError::Kongswap(KongswapError::BackendUnreachable { reason: s() });
+        assert_eq!(classify(&kong), (ErrorZone::TradingExecution, true));
+
+        let bad_input = IcpiError::Validation(ValidationError::InvalidAmount { amount: s(), reason: s() });
+        assert_eq!(classify(&bad_input), (ErrorZone::Caller, false));
+
+        assert_eq!(classify(&IcpiError::System(SystemError::EmergencyPause)), (ErrorZone::Infrastructure, false));
+        assert_eq!(classify(&IcpiError::Other("Kongswap price query failed".to_string())), (ErrorZone::Unclassified, false));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        for error in every_error() {
+            let code = error_code(&error);
+            assert!(!code.is_empty() && !code.ends_with('.'), "{:?} has code {:?}", error, code);
+            assert!(code.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '_'), "{}", code);
+        }
+
+        assert_eq!(error_code(&IcpiError::System(SystemError::EmergencyPause)), "System.EmergencyPause");
+        let tvl = IcpiError::Mint(MintError::InsufficientTVL { tvl: "0".to_string(), required: s() });
+        assert_eq!(error_code(&tvl), "Mint.InsufficientTVL");
+    }
+
+    #[test]
+    fn test_request_id_leaves_error_unchanged() {
+        let inner = IcpiError::Trading(TradingError::SwapFailed {
+            pay_token: s(), receive_token: s(), amount: Nat::from(0u64), reason: s(),
+        });
+        let api = ApiError::from(inner.clone()).with_request_id("burn-1".to_string());
+        assert_eq!(api.request_id.as_deref(), Some("burn-1"));
+        assert!(matches!(api.error, IcpiError::Trading(TradingError::SwapFailed { .. })));
+        assert_eq!((api.zone, api.retryable), classify(&inner));
+        assert!(api.to_string().ends_with("(request burn-1)"));
+    }
+}
@@ -0,0 +1,85 @@
+//! Instruction budget checks for long-running loops
+//!
+//! A message that exceeds the instruction limit traps, and a trap halfway
+//! through a burn distribution or a multi-leg sweep leaves funds moved with
+//! no record of what's left. Loops consult `InstructionBudget::exhausted`
+//! between steps and stop cleanly once the configured fraction of the limit
+//! is used, persisting whatever remains.
+//!
+//! `performance_counter(0)` counts instructions of the current message only;
+//! every `await` starts a new message, so the check guards the synchronous
+//! work accumulated since the last await.
+
+use crate::infrastructure::{INSTRUCTION_LIMIT_PER_MESSAGE, INSTRUCTION_BUDGET_FRACTION};
+
+/// Share of the per-message instruction limit a loop may use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstructionBudget {
+    limit: u64,
+    fraction: f64,
+}
+
+impl InstructionBudget {
+    /// Budget of `fraction` (clamped to 0..=1) of `limit` instructions
+    pub fn new(limit: u64, fraction: f64) -> Self {
+        let fraction = if fraction.is_finite() { fraction.clamp(0.0, 1.0) } else { INSTRUCTION_BUDGET_FRACTION };
+        InstructionBudget { limit, fraction }
+    }
+
+    /// Budget from the runtime config
+    pub fn from_config() -> Self {
+        Self::new(
+            INSTRUCTION_LIMIT_PER_MESSAGE,
+            crate::infrastructure::config::get_config().instruction_budget_fraction(),
+        )
+    }
+
+    /// Instruction count at which loops should stop
+    pub fn threshold(&self) -> u64 {
+        (self.limit as f64 * self.fraction) as u64
+    }
+
+    /// Whether `used` instructions exhaust the budget
+    pub fn is_exceeded(&self, used: u64) -> bool {
+        used >= self.threshold()
+    }
+
+    /// Check the live instruction counter against the budget
+    pub fn exhausted(&self) -> bool {
+        let used = crate::infrastructure::runtime::instruction_counter();
+        let exceeded = self.is_exceeded(used);
+        if exceeded {
+            crate::log_op!(
+                "⚠️ Instruction budget exhausted: {} used of {} allowed (limit {})",
+                used, self.threshold(), self.limit
+            );
+        }
+        exceeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_is_fraction_of_limit() {
+        let budget = InstructionBudget::new(40_000_000_000, 0.8);
+        assert_eq!(budget.threshold(), 32_000_000_000);
+        assert!(!budget.is_exceeded(31_999_999_999));
+        assert!(budget.is_exceeded(32_000_000_000));
+        assert!(budget.is_exceeded(39_000_000_000));
+    }
+
+    #[test]
+    fn test_fraction_is_clamped() {
+        assert_eq!(InstructionBudget::new(1_000, 1.5).threshold(), 1_000);
+        assert_eq!(InstructionBudget::new(1_000, -0.1).threshold(), 0);
+        // Zero budget stops immediately
+        assert!(InstructionBudget::new(1_000, 0.0).is_exceeded(0));
+        assert_eq!(
+            InstructionBudget::new(1_000, f64::NAN).threshold(),
+            (1_000.0 * INSTRUCTION_BUDGET_FRACTION) as u64
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! ckUSDT ledger client
+//!
+//! Used for mint deposits, mint/burn fees, refunds and the backend's own
+//! ckUSDT holdings.
+
+use candid::{Nat, Principal};
+use crate::infrastructure::Result;
+use crate::infrastructure::constants::CKUSDT_CANISTER_ID;
+use crate::types::icrc::Allowance;
+use super::Ledger;
+
+pub(crate) const LEDGER: Ledger = Ledger {
+    name: "ckUSDT ledger",
+    canister_id: CKUSDT_CANISTER_ID,
+};
+
+/// ckUSDT balance of `owner`
+pub async fn balance_of(owner: Principal) -> Result<Nat> {
+    super::balance_of(&LEDGER, owner).await
+}
+
+/// ckUSDT ledger transfer fee
+pub async fn fee() -> Result<Nat> {
+    super::fee(&LEDGER).await
+}
+
+/// ckUSDT allowance `owner` has granted to the backend
+pub async fn allowance(owner: Principal) -> Result<Allowance> {
+    super::allowance(&LEDGER, owner).await
+}
+
+/// Send ckUSDT from the backend to `to`
+pub async fn transfer(to: Principal, amount: Nat, memo: &[u8]) -> Result<Nat> {
+    super::transfer(&LEDGER, to, amount, memo).await
+}
+
+/// Pull ckUSDT from `from` into the backend via ICRC-2
+pub async fn transfer_from(from: Principal, amount: Nat, memo: &[u8]) -> Result<Nat> {
+    super::transfer_from(&LEDGER, from, amount, memo).await
+}
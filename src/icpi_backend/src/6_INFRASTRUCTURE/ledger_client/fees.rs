@@ -0,0 +1,154 @@
+//! Cached ledger transfer fees
+//!
+//! Refunds, redemption dust thresholds, approval padding and reserve
+//! accounting all depend on the ICPI and ckUSDT ledger fees. The fees are
+//! fetched with `icrc1_fee` (admin refresh) and cached; until the first
+//! fetch the compiled defaults apply.
+
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use std::cell::RefCell;
+use crate::infrastructure::{Result, IcpiError};
+use crate::infrastructure::errors::QueryError;
+use crate::infrastructure::constants::{BURN_FEE_BUFFER, CKUSDT_TRANSFER_FEE, ICPI_TRANSFER_FEE};
+use crate::types::TrackedToken;
+
+/// Fees last fetched from the ledgers (e6 for ckUSDT, e8 for ICPI)
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct LedgerFees {
+    pub icpi: Option<u64>,
+    pub ckusdt: Option<u64>,
+    pub fetched_at: Option<u64>,
+}
+
+thread_local! {
+    static FEES: RefCell<LedgerFees> = RefCell::new(LedgerFees::default());
+}
+
+/// ckUSDT transfer fee, cached or compiled default
+pub fn ckusdt_fee() -> u64 {
+    FEES.with(|f| f.borrow().ckusdt).unwrap_or(CKUSDT_TRANSFER_FEE)
+}
+
+/// ICPI transfer fee, cached or compiled default
+pub fn icpi_fee() -> u64 {
+    FEES.with(|f| f.borrow().icpi).unwrap_or(ICPI_TRANSFER_FEE)
+}
+
+/// Transfer fee for a token by symbol
+///
+/// Only ckUSDT is fetched; other tracked tokens keep the standard buffer.
+pub fn transfer_fee(symbol: &str) -> u64 {
+    if symbol == TrackedToken::ckUSDT.to_symbol() {
+        ckusdt_fee()
+    } else {
+        BURN_FEE_BUFFER
+    }
+}
+
+/// Current transfer fee of a tracked token's own ledger (not cached)
+pub async fn token_fee(token: &TrackedToken) -> Result<u64> {
+    let ledger = token.get_canister_id().map_err(IcpiError::Other)?;
+    let result: std::result::Result<(candid::Nat,), _> =
+        crate::infrastructure::runtime::call(ledger, "icrc1_fee", ()).await;
+    let (fee,) = result.map_err(|(code, msg)| IcpiError::Query(QueryError::CanisterUnreachable {
+        canister: format!("{} ledger", token.to_symbol()),
+        reason: format!("{:?}: {}", code, msg),
+    }))?;
+
+    num_traits::ToPrimitive::to_u64(&fee.0).ok_or_else(|| IcpiError::Other(
+        format!("{} ledger fee {} does not fit in u64", token.to_symbol(), fee)
+    ))
+}
+
+/// Fetch both ledger fees and update the cache
+///
+/// A ledger that fails to answer keeps its previous cached value.
+pub async fn refresh_fees() -> Result<LedgerFees> {
+    let (icpi, ckusdt) = futures::join!(
+        super::icpi_ledger::fee(),
+        super::ckusdt_ledger::fee(),
+    );
+
+    let previous = get_fees();
+    let fees = LedgerFees {
+        icpi: fetched_fee("ICPI", icpi).or(previous.icpi),
+        ckusdt: fetched_fee("ckUSDT", ckusdt).or(previous.ckusdt),
+        fetched_at: Some(crate::infrastructure::runtime::time()),
+    };
+    import_fees(fees.clone());
+
+    for warning in fee_warnings() {
+        crate::log_op!("⚠️ {}", warning);
+    }
+    Ok(fees)
+}
+
+fn fetched_fee(name: &str, result: Result<candid::Nat>) -> Option<u64> {
+    match result {
+        Ok(fee) => {
+            let fee = num_traits::ToPrimitive::to_u64(&fee.0);
+            if fee.is_none() {
+                crate::log_op!("⚠️ {} ledger fee does not fit in u64, keeping cached value", name);
+            }
+            fee
+        }
+        Err(e) => {
+            crate::log_op!("⚠️ Failed to fetch {} ledger fee: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Health warnings for fetched fees that differ from the compiled defaults
+pub fn fee_warnings() -> Vec<String> {
+    let fees = get_fees();
+    let mut warnings = Vec::new();
+    if let Some(fee) = fees.ckusdt.filter(|f| *f != CKUSDT_TRANSFER_FEE) {
+        warnings.push(format!("ckUSDT ledger fee is {} (compiled default {})", fee, CKUSDT_TRANSFER_FEE));
+    }
+    if let Some(fee) = fees.icpi.filter(|f| *f != ICPI_TRANSFER_FEE) {
+        warnings.push(format!("ICPI ledger fee is {} (compiled default {})", fee, ICPI_TRANSFER_FEE));
+    }
+    warnings
+}
+
+/// Current cached fees
+pub fn get_fees() -> LedgerFees {
+    FEES.with(|f| f.borrow().clone())
+}
+
+/// Export fees for stable storage
+pub fn export_fees() -> LedgerFees {
+    get_fees()
+}
+
+/// Import fees from stable storage
+pub fn import_fees(fees: LedgerFees) {
+    FEES.with(|f| *f.borrow_mut() = fees);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_until_fetched() {
+        assert_eq!(ckusdt_fee(), CKUSDT_TRANSFER_FEE);
+        assert_eq!(icpi_fee(), ICPI_TRANSFER_FEE);
+        assert!(fee_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_changed_fee_is_used_and_flagged() {
+        import_fees(LedgerFees { icpi: Some(ICPI_TRANSFER_FEE), ckusdt: Some(20_000), fetched_at: Some(1) });
+
+        assert_eq!(ckusdt_fee(), 20_000);
+        assert_eq!(transfer_fee("ckUSDT"), 20_000);
+        assert_eq!(transfer_fee("ALEX"), BURN_FEE_BUFFER);
+
+        let warnings = fee_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("ckUSDT"));
+    }
+}
@@ -0,0 +1,158 @@
+//! ICPI ledger client
+//!
+//! The backend is the ICPI minting account: `transfer` out of it mints and
+//! `transfer_from` into it burns.
+//!
+//! Because any transfer out of the backend's account creates unbacked ICPI,
+//! `transfer` requires a one-shot `MintAuthorization` issued by the mint path
+//! for that exact recipient and amount.
+
+use candid::{Nat, Principal};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+use crate::infrastructure::{Result, IcpiError, SystemError};
+use crate::infrastructure::constants::ICPI_CANISTER_ID;
+use super::Ledger;
+
+pub(crate) const LEDGER: Ledger = Ledger {
+    name: "ICPI ledger",
+    canister_id: ICPI_CANISTER_ID,
+};
+
+/// Permission to mint `amount` ICPI to `recipient`, usable once
+///
+/// Not `Clone`; `transfer` consumes it and checks it is still outstanding.
+#[derive(Debug)]
+pub struct MintAuthorization {
+    nonce: u64,
+    recipient: Principal,
+    amount: Nat,
+}
+
+thread_local! {
+    static NEXT_NONCE: Cell<u64> = const { Cell::new(0) };
+    static OUTSTANDING: RefCell<BTreeSet<u64>> = RefCell::new(BTreeSet::new());
+}
+
+impl MintAuthorization {
+    /// Issue an authorization; only the mint path should call this
+    pub(crate) fn issue(recipient: Principal, amount: Nat) -> Self {
+        let nonce = NEXT_NONCE.with(|n| {
+            let nonce = n.get();
+            n.set(nonce + 1);
+            nonce
+        });
+        OUTSTANDING.with(|o| o.borrow_mut().insert(nonce));
+        MintAuthorization { nonce, recipient, amount }
+    }
+
+    /// Consume the authorization for a transfer of `amount` to `to`
+    fn redeem(self, to: Principal, amount: &Nat) -> Result<()> {
+        let outstanding = OUTSTANDING.with(|o| o.borrow_mut().remove(&self.nonce));
+        if outstanding && self.recipient == to && self.amount == *amount {
+            return Ok(());
+        }
+
+        ic_cdk::println!(
+            "🚨 Blocked unauthorized ICPI mint transfer: {} to {} (authorized: {} to {}, outstanding: {})",
+            amount, to, self.amount, self.recipient, outstanding
+        );
+        Err(IcpiError::System(SystemError::Unauthorized {
+            principal: to.to_text(),
+            required_role: "authorized mint".to_string(),
+        }))
+    }
+}
+
+/// ICPI balance of `owner`
+pub async fn balance_of(owner: Principal) -> Result<Nat> {
+    super::balance_of(&LEDGER, owner).await
+}
+
+/// Total ICPI supply
+pub async fn total_supply() -> Result<Nat> {
+    super::total_supply(&LEDGER).await
+}
+
+/// ICPI allowance `owner` has granted to the backend
+pub async fn allowance(owner: Principal) -> Result<super::Allowance> {
+    super::allowance(&LEDGER, owner).await
+}
+
+/// ICPI ledger transfer fee
+pub async fn fee() -> Result<Nat> {
+    super::fee(&LEDGER).await
+}
+
+/// Page of the ICPI transaction log
+pub async fn get_transactions(start: u64, length: u64) -> Result<crate::types::icrc::GetTransactionsResponse> {
+    super::get_transactions(&LEDGER, start, length).await
+}
+
+/// ICPI transactions held by an archive canister
+pub async fn get_archived_transactions(
+    callback: &crate::types::icrc::ArchiveCallback,
+    start: Nat,
+    length: Nat,
+) -> Result<Vec<crate::types::icrc::LedgerTransaction>> {
+    super::get_archived_transactions(&LEDGER, callback, start, length).await
+}
+
+/// Mint ICPI to `to`
+///
+/// Rejected unless `auth` was issued for this recipient and amount and
+/// hasn't been used.
+pub async fn transfer(auth: MintAuthorization, to: Principal, amount: Nat, memo: &[u8]) -> Result<Nat> {
+    auth.redeem(to, &amount)?;
+    super::transfer(&LEDGER, to, amount, memo).await
+}
+
+/// Burn ICPI by pulling it from `from` into the minting account
+pub async fn transfer_from(from: Principal, amount: Nat, memo: &[u8]) -> Result<Nat> {
+    super::transfer_from(&LEDGER, from, amount, memo).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(text: &str) -> Principal {
+        Principal::from_text(text).unwrap()
+    }
+
+    #[test]
+    fn test_mint_authorization_is_one_shot() {
+        let alice = principal("2vxsx-fae");
+        let amount = Nat::from(100_000_000u64);
+
+        let auth = MintAuthorization::issue(alice, amount.clone());
+        let nonce = auth.nonce;
+        assert!(auth.redeem(alice, &amount).is_ok());
+
+        // Reconstructing a spent authorization doesn't mint again
+        let replayed = MintAuthorization { nonce, recipient: alice, amount: amount.clone() };
+        assert!(replayed.redeem(alice, &amount).is_err());
+    }
+
+    #[test]
+    fn test_unauthorized_mint_transfer_rejected() {
+        let alice = principal("2vxsx-fae");
+        let bob = principal("aaaaa-aa");
+        let amount = Nat::from(100_000_000u64);
+
+        // Wrong recipient
+        let auth = MintAuthorization::issue(alice, amount.clone());
+        assert!(matches!(
+            auth.redeem(bob, &amount),
+            Err(IcpiError::System(SystemError::Unauthorized { .. }))
+        ));
+
+        // Wrong amount
+        let auth = MintAuthorization::issue(alice, amount.clone());
+        assert!(auth.redeem(alice, &Nat::from(200_000_000u64)).is_err());
+
+        // Never issued
+        let forged = MintAuthorization { nonce: u64::MAX, recipient: alice, amount: amount.clone() };
+        assert!(forged.redeem(alice, &amount).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Ledger Client Module
+//!
+//! Typed async wrappers around the ICRC-1/ICRC-2 ledgers the backend talks
+//! to directly (ICPI and ckUSDT). Callers pass principals and amounts; this
+//! module handles principal parsing, argument construction, idempotency
+//! keys and error mapping.
+//!
+//! Error mapping:
+//! - Failed query calls → `QueryError::CanisterUnreachable`
+//! - Failed update calls → `SystemError::InterCanisterCallFailed`
+//! - Ledger rejections → `LedgerError`
+//!
+//! Transfers always set `created_at_time`, so an identical transfer repeated
+//! within the ledger's dedup window is reported as `Duplicate` and treated
+//! as success with the original block index.
+
+pub mod icpi_ledger;
+pub mod ckusdt_ledger;
+pub mod fees;
+
+use candid::{Nat, Principal};
+use crate::infrastructure::{Result, IcpiError, LedgerError, SystemError};
+use crate::infrastructure::errors::QueryError;
+use crate::types::icrc::{
+    Account, Allowance, AllowanceArgs, ArchiveCallback, GetTransactionsRequest, GetTransactionsResponse,
+    LedgerTransaction, TransactionRange, TransferArgs, TransferError, TransferFromArgs, TransferFromError,
+    TransferResult,
+};
+
+/// Identifies a ledger canister
+pub(crate) struct Ledger {
+    pub name: &'static str,
+    pub canister_id: &'static str,
+}
+
+impl Ledger {
+    fn principal(&self) -> Result<Principal> {
+        Principal::from_text(self.canister_id)
+            .map_err(|e| IcpiError::System(SystemError::StateCorrupted {
+                reason: format!("Invalid {} principal {}: {}", self.name, self.canister_id, e),
+            }))
+    }
+}
+
+fn account(owner: Principal) -> Account {
+    Account { owner, subaccount: None }
+}
+
+fn query_failed(ledger: &Ledger, code: ic_cdk::api::call::RejectionCode, msg: String) -> IcpiError {
+    IcpiError::Query(QueryError::CanisterUnreachable {
+        canister: ledger.name.to_string(),
+        reason: format!("{:?}: {}", code, msg),
+    })
+}
+
+fn update_failed(ledger: &Ledger, method: &str, code: ic_cdk::api::call::RejectionCode, msg: String) -> IcpiError {
+    IcpiError::System(SystemError::InterCanisterCallFailed {
+        canister: ledger.name.to_string(),
+        method: method.to_string(),
+        reason: format!("{:?}: {}", code, msg),
+    })
+}
+
+/// Balance of `owner`'s default account
+pub(crate) async fn balance_of(ledger: &Ledger, owner: Principal) -> Result<Nat> {
+    let result: std::result::Result<(Nat,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "icrc1_balance_of",
+        (account(owner),)
+    ).await;
+
+    result.map(|(balance,)| balance)
+        .map_err(|(code, msg)| query_failed(ledger, code, msg))
+}
+
+/// Total supply of the ledger's token
+pub(crate) async fn total_supply(ledger: &Ledger) -> Result<Nat> {
+    let result: std::result::Result<(Nat,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "icrc1_total_supply",
+        ()
+    ).await;
+
+    result.map(|(supply,)| supply)
+        .map_err(|(code, msg)| query_failed(ledger, code, msg))
+}
+
+/// Current transfer fee of the ledger
+pub(crate) async fn fee(ledger: &Ledger) -> Result<Nat> {
+    let result: std::result::Result<(Nat,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "icrc1_fee",
+        ()
+    ).await;
+
+    result.map(|(fee,)| fee)
+        .map_err(|(code, msg)| query_failed(ledger, code, msg))
+}
+
+/// Page of the ledger's transaction log starting at `start`
+pub(crate) async fn get_transactions(ledger: &Ledger, start: u64, length: u64) -> Result<GetTransactionsResponse> {
+    let request = GetTransactionsRequest { start: Nat::from(start), length: Nat::from(length) };
+    let result: std::result::Result<(GetTransactionsResponse,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "get_transactions",
+        (request,)
+    ).await;
+
+    result.map(|(response,)| response)
+        .map_err(|(code, msg)| query_failed(ledger, code, msg))
+}
+
+/// Transactions `start..start + length` from the archive behind `callback`
+pub(crate) async fn get_archived_transactions(
+    ledger: &Ledger,
+    callback: &ArchiveCallback,
+    start: Nat,
+    length: Nat,
+) -> Result<Vec<LedgerTransaction>> {
+    let result: std::result::Result<(TransactionRange,), _> = crate::infrastructure::runtime::call(
+        callback.0.principal,
+        &callback.0.method,
+        (GetTransactionsRequest { start, length },)
+    ).await;
+
+    result.map(|(range,)| range.transactions)
+        .map_err(|(code, msg)| query_failed(ledger, code, msg))
+}
+
+/// Allowance `owner` has granted to this canister
+pub(crate) async fn allowance(ledger: &Ledger, owner: Principal) -> Result<Allowance> {
+    let args = AllowanceArgs {
+        account: account(owner),
+        spender: account(crate::infrastructure::runtime::canister_id()),
+    };
+
+    let result: std::result::Result<(Allowance,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "icrc2_allowance",
+        (args,)
+    ).await;
+
+    result.map(|(allowance,)| allowance)
+        .map_err(|(code, msg)| query_failed(ledger, code, msg))
+}
+
+/// Transfer from this canister to `to`, returns the block index
+pub(crate) async fn transfer(ledger: &Ledger, to: Principal, amount: Nat, memo: &[u8]) -> Result<Nat> {
+    let args = TransferArgs {
+        from_subaccount: None,
+        to: account(to),
+        amount,
+        fee: None,
+        memo: Some(memo.to_vec()),
+        created_at_time: Some(crate::infrastructure::runtime::time()),
+    };
+
+    let result: std::result::Result<(TransferResult,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "icrc1_transfer",
+        (args,)
+    ).await;
+
+    match result {
+        Ok((TransferResult::Ok(block),)) => Ok(block),
+        Ok((TransferResult::Err(e),)) => map_transfer_error(ledger.name, e),
+        Err((code, msg)) => Err(update_failed(ledger, "icrc1_transfer", code, msg)),
+    }
+}
+
+/// Pull `amount` from `from` into this canister via ICRC-2, returns the block index
+pub(crate) async fn transfer_from(ledger: &Ledger, from: Principal, amount: Nat, memo: &[u8]) -> Result<Nat> {
+    let args = TransferFromArgs {
+        from: account(from),
+        to: account(crate::infrastructure::runtime::canister_id()),
+        amount,
+        fee: None,
+        memo: Some(memo.to_vec()),
+        created_at_time: Some(crate::infrastructure::runtime::time()),
+    };
+
+    let result: std::result::Result<(std::result::Result<Nat, TransferFromError>,), _> = crate::infrastructure::runtime::call(
+        ledger.principal()?,
+        "icrc2_transfer_from",
+        (args,)
+    ).await;
+
+    match result {
+        Ok((Ok(block),)) => Ok(block),
+        Ok((Err(e),)) => map_transfer_from_error(ledger.name, e),
+        Err((code, msg)) => Err(update_failed(ledger, "icrc2_transfer_from", code, msg)),
+    }
+}
+
+/// Map an ICRC-1 transfer error; duplicates resolve to the original block
+fn map_transfer_error(ledger: &str, error: TransferError) -> Result<Nat> {
+    match error {
+        TransferError::Duplicate { duplicate_of } => {
+            ic_cdk::println!("ℹ️ {} transfer already applied at block {}", ledger, duplicate_of);
+            Ok(duplicate_of)
+        }
+        TransferError::InsufficientFunds { balance } => {
+            Err(IcpiError::Ledger(LedgerError::InsufficientFunds {
+                ledger: ledger.to_string(),
+                balance: balance.to_string(),
+            }))
+        }
+        other => Err(IcpiError::Ledger(LedgerError::Rejected {
+            ledger: ledger.to_string(),
+            method: "icrc1_transfer".to_string(),
+            reason: format!("{:?}", other),
+        })),
+    }
+}
+
+/// Map an ICRC-2 transfer_from error; duplicates resolve to the original block
+fn map_transfer_from_error(ledger: &str, error: TransferFromError) -> Result<Nat> {
+    match error {
+        TransferFromError::Duplicate { duplicate_of } => {
+            ic_cdk::println!("ℹ️ {} transfer_from already applied at block {}", ledger, duplicate_of);
+            Ok(duplicate_of)
+        }
+        TransferFromError::InsufficientFunds { balance } => {
+            Err(IcpiError::Ledger(LedgerError::InsufficientFunds {
+                ledger: ledger.to_string(),
+                balance: balance.to_string(),
+            }))
+        }
+        TransferFromError::InsufficientAllowance { allowance } => {
+            Err(IcpiError::Ledger(LedgerError::InsufficientAllowance {
+                ledger: ledger.to_string(),
+                allowance: allowance.to_string(),
+            }))
+        }
+        other => Err(IcpiError::Ledger(LedgerError::Rejected {
+            ledger: ledger.to_string(),
+            method: "icrc2_transfer_from".to_string(),
+            reason: format!("{:?}", other),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_principals_parse() {
+        assert!(icpi_ledger::LEDGER.principal().is_ok());
+        assert!(ckusdt_ledger::LEDGER.principal().is_ok());
+    }
+
+    #[test]
+    fn test_transfer_errors_map_to_ledger_errors() {
+        let result = map_transfer_error("ICPI ledger", TransferError::InsufficientFunds {
+            balance: Nat::from(5u64),
+        });
+        assert!(matches!(result, Err(IcpiError::Ledger(LedgerError::InsufficientFunds { .. }))));
+
+        let result = map_transfer_error("ICPI ledger", TransferError::TooOld);
+        assert!(matches!(result, Err(IcpiError::Ledger(LedgerError::Rejected { .. }))));
+    }
+
+    #[test]
+    fn test_transfer_from_insufficient_allowance() {
+        let result = map_transfer_from_error("ICPI ledger", TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(100u64),
+        });
+        match result {
+            Err(IcpiError::Ledger(LedgerError::InsufficientAllowance { allowance, .. })) => {
+                assert_eq!(allowance, "100");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -1,6 +1,206 @@
 //! Logging module
+//!
+//! Public updates (mint, burn, rebalance cycle) run under a short request
+//! id. `log_op!` and `log_operation` prefix lines with it, so interleaved
+//! output from concurrent calls can be told apart, and the id is attached
+//! to the records the call writes and to the `ApiError` it returns.
+
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use crate::infrastructure::Result;
+
+/// Log a line prefixed with the current request id, if any
+#[macro_export]
+macro_rules! log_op {
+    ($($arg:tt)*) => {
+        match $crate::infrastructure::logging::current_request_id() {
+            Some(id) => ic_cdk::println!("[{}] {}", id, format!($($arg)*)),
+            None => ic_cdk::println!($($arg)*),
+        }
+    };
+}
 
 /// Log an operation
 pub fn log_operation(operation: &str, details: &str) {
-    ic_cdk::println!("[{}] {}", operation, details);
+    match current_request_id() {
+        Some(id) => ic_cdk::println!("[{}] [{}] {}", id, operation, details),
+        None => ic_cdk::println!("[{}] {}", operation, details),
+    }
+}
+
+thread_local! {
+    static CURRENT_REQUEST_ID: RefCell<Option<String>> = RefCell::new(None);
+    static REQUEST_COUNTER: Cell<u64> = Cell::new(0);
+}
+
+/// Id of the request currently executing, if any
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.with(|id| id.borrow().clone())
+}
+
+/// Generate a short request id such as `mint-3f9a21bc`
+pub fn new_request_id(kind: &str) -> String {
+    new_request_id_at(kind, crate::infrastructure::runtime::time())
+}
+
+/// `new_request_id` at time `now`
+pub fn new_request_id_at(kind: &str, now: u64) -> String {
+    let sequence = REQUEST_COUNTER.with(|counter| {
+        let next = counter.get().wrapping_add(1);
+        counter.set(next);
+        next
+    });
+    let mut seed = now.to_le_bytes().to_vec();
+    seed.extend_from_slice(&sequence.to_le_bytes());
+    format!("{}-{:08x}", kind, crate::infrastructure::memo::fnv1a_64(&seed) as u32)
+}
+
+/// Future that installs its request id for every poll
+///
+/// Other calls can run while this one is suspended at an await, so the id
+/// can't simply be set once at the start; it is set on each resumption and
+/// the previous value restored afterwards.
+pub struct WithRequestId<F: Future> {
+    id: String,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithRequestId<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let previous = CURRENT_REQUEST_ID.with(|id| id.replace(Some(self.id.clone())));
+        let result = self.inner.as_mut().poll(cx);
+        CURRENT_REQUEST_ID.with(|id| *id.borrow_mut() = previous);
+        result
+    }
+}
+
+/// Run `operation` under `id`
+pub fn with_request_id<F: Future>(id: String, operation: F) -> WithRequestId<F> {
+    WithRequestId { id, inner: Box::pin(operation) }
+}
+
+/// Run `operation` under a new request id, returning the id with its result
+///
+/// Endpoints attach the id to a failure with `ApiError::with_request_id`;
+/// the `IcpiError` itself is left as is.
+pub async fn run_request<T, F>(kind: &str, operation: F) -> (String, Result<T>)
+where
+    F: Future<Output = Result<T>>,
+{
+    let id = new_request_id(kind);
+    let result = with_request_id(id.clone(), operation).await;
+    (id, result)
+}
+
+/// Subsystems tracked on the last-error board
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Subsystem {
+    Pricing,
+    Tvl,
+    Swap,
+    Mint,
+    Burn,
+    Rebalance,
+}
+
+/// Most recent failure of a subsystem, as returned by `get_subsystem_errors`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SubsystemError {
+    pub subsystem: Subsystem,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+thread_local! {
+    // One entry per subsystem; a newer failure replaces the older one
+    static LAST_ERRORS: RefCell<BTreeMap<Subsystem, SubsystemError>> = RefCell::new(BTreeMap::new());
+}
+
+/// Record a subsystem failure
+pub fn record_error(subsystem: Subsystem, message: String) {
+    record_error_at(subsystem, message, crate::infrastructure::runtime::time());
+}
+
+/// Record a subsystem failure that happened at `now`
+pub fn record_error_at(subsystem: Subsystem, message: String, now: u64) {
+    let entry = SubsystemError { subsystem, message, timestamp: now };
+    LAST_ERRORS.with(|errors| errors.borrow_mut().insert(subsystem, entry));
+}
+
+/// Record the error of a failed result; successes leave the board untouched
+pub fn record_result<T>(subsystem: Subsystem, result: &Result<T>) {
+    if let Err(e) = result {
+        record_error(subsystem, e.to_string());
+    }
+}
+
+/// `record_result` for a result obtained at `now`
+pub fn record_result_at<T>(subsystem: Subsystem, result: &Result<T>, now: u64) {
+    if let Err(e) = result {
+        record_error_at(subsystem, e.to_string(), now);
+    }
+}
+
+/// Last error of every subsystem that has failed, in subsystem order
+pub fn get_subsystem_errors() -> Vec<SubsystemError> {
+    LAST_ERRORS.with(|errors| errors.borrow().values().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::IcpiError;
+
+    #[test]
+    fn test_request_id_scoped_to_its_future() {
+        assert_eq!(current_request_id(), None);
+
+        let seen = futures::executor::block_on(with_request_id("mint-1".to_string(), async {
+            current_request_id()
+        }));
+        assert_eq!(seen.as_deref(), Some("mint-1"));
+        assert_eq!(current_request_id(), None);
+
+        // Nested operations see their own id and restore the outer one
+        let (inner, outer) = futures::executor::block_on(with_request_id("burn-1".to_string(), async {
+            let inner = with_request_id("swap-1".to_string(), async { current_request_id() }).await;
+            (inner, current_request_id())
+        }));
+        assert_eq!(inner.as_deref(), Some("swap-1"));
+        assert_eq!(outer.as_deref(), Some("burn-1"));
+    }
+
+    #[test]
+    fn test_request_id_format() {
+        let id = new_request_id_at("mint", 1_000);
+        assert!(id.starts_with("mint-"));
+        assert_eq!(id.len(), "mint-".len() + 8);
+
+        // Unique within the same instant
+        assert_ne!(new_request_id_at("mint", 1_000), new_request_id_at("mint", 1_000));
+    }
+
+    #[test]
+    fn test_failure_updates_subsystem_last_error() {
+        record_result_at::<()>(Subsystem::Swap, &Ok(()), 1);
+        assert!(get_subsystem_errors().iter().all(|e| e.subsystem != Subsystem::Swap));
+
+        record_result_at::<()>(Subsystem::Swap, &Err(IcpiError::Other("slippage exceeded".to_string())), 2);
+        record_result_at::<()>(Subsystem::Tvl, &Err(IcpiError::Other("kong locker unreachable".to_string())), 3);
+        record_result_at::<()>(Subsystem::Swap, &Err(IcpiError::Other("pool not found".to_string())), 4);
+
+        let errors = get_subsystem_errors();
+        let swap: Vec<_> = errors.iter().filter(|e| e.subsystem == Subsystem::Swap).collect();
+        assert_eq!(swap.len(), 1);
+        assert_eq!(swap[0].message, "pool not found");
+        assert_eq!(swap[0].timestamp, 4);
+        assert!(errors.iter().any(|e| e.subsystem == Subsystem::Tvl && e.message == "kong locker unreachable"));
+    }
 }
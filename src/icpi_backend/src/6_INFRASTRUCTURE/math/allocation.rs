@@ -0,0 +1,134 @@
+//! Target weight capping
+//!
+//! Pure functions on target percentages; no I/O.
+
+/// Tolerance for treating a weight as sitting at its cap
+const CAP_EPSILON: f64 = 1e-9;
+
+/// Cap each weight at `caps[i]`, redistributing the excess to the
+/// uncapped weights in proportion to their size until none exceeds its cap
+///
+/// The total is preserved. Excess that would land on uncapped weights which
+/// are all zero is split evenly between them. No weight ever ends above its
+/// cap: if the caps sum to less than the total, no assignment satisfies
+/// them, and every weight is set to its cap, leaving the result short of
+/// the total (target validation then refuses it).
+pub fn cap_weights(weights: &[f64], caps: &[f64]) -> Vec<f64> {
+    assert_eq!(weights.len(), caps.len(), "one cap per weight");
+    let total: f64 = weights.iter().sum();
+    let caps: Vec<f64> = caps.iter().map(|c| c.max(0.0)).collect();
+    let cap_total: f64 = caps.iter().sum();
+
+    if !total.is_finite() || total <= 0.0 {
+        return weights.to_vec();
+    }
+    if cap_total <= total + CAP_EPSILON {
+        return caps;
+    }
+
+    let mut result = weights.to_vec();
+    let mut capped = vec![false; weights.len()];
+    // Each pass caps at least one more weight, so this always settles
+    for _ in 0..=weights.len() {
+        let mut excess = 0.0;
+        for i in 0..result.len() {
+            if result[i] > caps[i] {
+                excess += result[i] - caps[i];
+                result[i] = caps[i];
+            }
+            if result[i] >= caps[i] - CAP_EPSILON {
+                capped[i] = true;
+            }
+        }
+        if excess <= CAP_EPSILON {
+            break;
+        }
+
+        let open: Vec<usize> = (0..result.len()).filter(|&i| !capped[i]).collect();
+        let base: f64 = open.iter().map(|&i| result[i]).sum();
+        for &i in &open {
+            let share = if base > 0.0 { result[i] / base } else { 1.0 / open.len() as f64 };
+            result[i] += excess * share;
+        }
+    }
+
+    // Redistribution rounding can leave a weight a hair over its cap
+    for (weight, cap) in result.iter_mut().zip(&caps) {
+        *weight = weight.min(*cap);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_weights_under_caps_unchanged() {
+        let weights = [40.0, 30.0, 20.0, 10.0];
+        assert_close(&cap_weights(&weights, &[50.0; 4]), &weights);
+    }
+
+    #[test]
+    fn test_excess_redistributed_proportionally() {
+        // 30pp over the cap goes 3:1 to the two uncapped tokens with weight
+        let capped = cap_weights(&[80.0, 15.0, 5.0, 0.0], &[50.0; 4]);
+        assert_close(&capped, &[50.0, 37.5, 12.5, 0.0]);
+        assert!((capped.iter().sum::<f64>() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_redistribution_iterates_until_stable() {
+        // The first pass pushes the second token over its own 30% cap
+        let capped = cap_weights(&[70.0, 20.0, 10.0, 0.0], &[40.0, 30.0, 50.0, 50.0]);
+        assert_close(&capped, &[40.0, 30.0, 30.0, 0.0]);
+    }
+
+    #[test]
+    fn test_excess_split_evenly_across_zero_weights() {
+        let capped = cap_weights(&[100.0, 0.0, 0.0, 0.0], &[50.0; 4]);
+        assert_close(&capped, &[50.0, 50.0 / 3.0, 50.0 / 3.0, 50.0 / 3.0]);
+    }
+
+    #[test]
+    fn test_all_tokens_capped() {
+        // Caps sum to exactly the total: every token ends at its cap
+        assert_close(&cap_weights(&[70.0, 10.0, 10.0, 10.0], &[25.0; 4]), &[25.0; 4]);
+
+        // Caps can't hold: every token sits at its cap, short of the total
+        let capped = cap_weights(&[70.0, 10.0, 10.0, 10.0], &[20.0, 20.0, 20.0, 20.0]);
+        assert_close(&capped, &[20.0; 4]);
+        let capped = cap_weights(&[70.0, 30.0], &[30.0, 10.0]);
+        assert_close(&capped, &[30.0, 10.0]);
+    }
+
+    #[test]
+    fn test_no_weight_ends_above_its_cap() {
+        let cases: [(&[f64], &[f64]); 5] = [
+            (&[80.0, 15.0, 5.0, 0.0], &[50.0; 4]),
+            (&[70.0, 20.0, 10.0, 0.0], &[40.0, 30.0, 50.0, 50.0]),
+            (&[100.0 / 3.0, 100.0 / 3.0, 100.0 / 3.0], &[33.3, 33.4, 40.0]),
+            (&[60.0, 25.0, 10.0, 5.0], &[30.0, 30.0, 25.0, 15.0]),
+            (&[90.0, 10.0], &[20.0, 20.0]),
+        ];
+        for (weights, caps) in cases {
+            let capped = cap_weights(weights, caps);
+            for (weight, cap) in capped.iter().zip(caps) {
+                assert!(weight <= cap, "{:?} exceeds caps {:?}", capped, caps);
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_and_zero_weights() {
+        assert!(cap_weights(&[], &[]).is_empty());
+        assert_close(&cap_weights(&[0.0, 0.0], &[50.0, 50.0]), &[0.0, 0.0]);
+    }
+}
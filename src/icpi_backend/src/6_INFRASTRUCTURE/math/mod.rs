@@ -2,6 +2,7 @@
 //! No I/O, no async - deterministic calculations only
 
 pub mod pure_math;
+pub mod allocation;
 
 pub use pure_math::{
     multiply_and_divide,
@@ -9,4 +10,9 @@ pub use pure_math::{
     calculate_mint_amount,
     calculate_redemptions,
     calculate_trade_size,
+    calculate_sell_amount,
+    bps_of,
+    apply_bps,
+    BPS_SCALE,
 };
+pub use allocation::cap_weights;
@@ -28,6 +28,37 @@ pub fn multiply_and_divide(a: &Nat, b: &Nat, c: &Nat) -> Result<Nat> {
     biguint_to_nat(result)
 }
 
+/// Basis points in a whole (100 bps = 1%)
+pub const BPS_SCALE: u32 = 10_000;
+
+/// `part` as basis points of `whole`, rounded to the nearest (half up)
+///
+/// Not capped at `BPS_SCALE`: a part larger than the whole measures over
+/// 10,000 bps.
+pub fn bps_of(part: &Nat, whole: &Nat) -> Result<u32> {
+    if whole == &Nat::from(0u64) {
+        return Err(IcpiError::Calculation(CalculationError::DivisionByZero {
+            operation: format!("bps of {} in {}", part, whole),
+        }));
+    }
+
+    // round(part × 10,000 ÷ whole) = floor((2 × part × 10,000 + whole) ÷ (2 × whole))
+    let whole_big = nat_to_biguint(whole);
+    let numerator = nat_to_biguint(part) * BigUint::from(2 * BPS_SCALE) + &whole_big;
+    let bps = numerator / (whole_big * BigUint::from(2u32));
+
+    bps.to_u32().ok_or_else(|| IcpiError::Calculation(CalculationError::Overflow {
+        operation: format!("bps of {} in {} = {}", part, whole, bps),
+    }))
+}
+
+/// `bps` basis points of `amount`, rounded down
+///
+/// Rounding down means a share never exceeds what was asked for.
+pub fn apply_bps(amount: &Nat, bps: u32) -> Result<Nat> {
+    multiply_and_divide(amount, &Nat::from(bps), &Nat::from(BPS_SCALE))
+}
+
 /// Convert between different decimal places
 pub fn convert_decimals(
     amount: &Nat,
@@ -150,26 +181,63 @@ pub fn calculate_redemptions(
     Ok(redemptions)
 }
 
-/// Calculate rebalancing trade size
-pub fn calculate_trade_size(
-    deviation_usd: f64,
-    trade_intensity: f64,
-    min_trade_size: f64,
-) -> Result<Nat> {
-    if deviation_usd <= 0.0 {
-        return Ok(Nat::from(0u64));
+/// Calculate rebalancing trade size in ckUSDT e6
+///
+/// The intensity is rounded to the nearest basis point and applied to the
+/// deviation with `apply_bps`, so the size rounds down. The sign of the
+/// deviation is ignored.
+pub fn calculate_trade_size(deviation_usd: f64, trade_intensity: f64) -> Result<Nat> {
+    if !deviation_usd.is_finite() || !trade_intensity.is_finite() || trade_intensity < 0.0 {
+        return Err(IcpiError::Calculation(CalculationError::ConversionError {
+            from: format!("{} × {}", deviation_usd, trade_intensity),
+            to: "trade size e6".to_string(),
+            reason: "Deviation and intensity must be finite, intensity non-negative".to_string(),
+        }));
     }
 
-    let trade_size = deviation_usd * trade_intensity;
+    // Convert to e6 decimals (ckUSDT)
+    let deviation_e6 = Nat::from((deviation_usd.abs() * 1_000_000.0).round() as u128);
+    let intensity_bps = (trade_intensity * BPS_SCALE as f64).round() as u32;
+    apply_bps(&deviation_e6, intensity_bps)
+}
 
-    if trade_size < min_trade_size {
-        return Ok(Nat::from(0u64));
+/// Calculate token base units to sell for a USD value, clamped to what the balance can pay
+///
+/// The price is the exact quote ratio: `receive_e6` ckUSDT for `pay_units`
+/// of the token (the raw `swap_amounts` reply). `fee` is the ledger fees
+/// the sell itself pays out of the balance.
+///
+/// # Formula
+/// floor(usd_e6 × pay_units ÷ receive_e6), then min(balance - fee)
+///
+/// The only f64 step is converting the requested USD value to e6; the
+/// price never passes through a float, and the amount rounds down so a sell
+/// never exceeds the requested value or the available balance.
+pub fn calculate_sell_amount(
+    usd_value: f64,
+    receive_e6: &Nat,
+    pay_units: &Nat,
+    balance: &Nat,
+    fee: &Nat,
+) -> Result<Nat> {
+    if !usd_value.is_finite() || usd_value < 0.0 {
+        return Err(IcpiError::Calculation(CalculationError::ConversionError {
+            from: usd_value.to_string(),
+            to: "usd_e6".to_string(),
+            reason: "USD value must be finite and non-negative".to_string(),
+        }));
+    }
+    if *receive_e6 == Nat::from(0u64) || *pay_units == Nat::from(0u64) {
+        return Err(IcpiError::Calculation(CalculationError::DivisionByZero {
+            operation: format!("sell amount at rate {} / {}", receive_e6, pay_units),
+        }));
     }
 
-    // Convert to e6 decimals (ckUSDT)
-    let trade_size_e6 = (trade_size * 1_000_000.0) as u64;
+    let usd_e6 = Nat::from((usd_value * 1_000_000.0).round() as u128);
+    let amount = multiply_and_divide(&usd_e6, pay_units, receive_e6)?;
+    let sellable = if balance > fee { balance.clone() - fee.clone() } else { Nat::from(0u64) };
 
-    Ok(Nat::from(trade_size_e6))
+    Ok(if amount > sellable { sellable } else { amount })
 }
 
 // ===== Helper Functions =====
@@ -416,22 +484,172 @@ mod tests {
 
     #[test]
     fn test_trade_size_zero_deviation() {
-        let result = calculate_trade_size(0.0, 0.1, 1.0).unwrap();
+        let result = calculate_trade_size(0.0, 0.1).unwrap();
         assert_eq!(result, Nat::from(0u64));
     }
 
     #[test]
-    fn test_trade_size_below_minimum() {
-        // Deviation $5, intensity 10%, min $1 → trade $0.50 → returns 0
-        let result = calculate_trade_size(5.0, 0.1, 1.0).unwrap();
-        assert_eq!(result, Nat::from(0u64));
+    fn test_trade_size_ignores_sign() {
+        // Deviation $100 either way, intensity 10% → trade $10
+        assert_eq!(calculate_trade_size(100.0, 0.1).unwrap(), Nat::from(10_000_000u64));
+        assert_eq!(calculate_trade_size(-100.0, 0.1).unwrap(), Nat::from(10_000_000u64));
+    }
+
+    #[test]
+    fn test_bps_of_rounds_to_nearest() {
+        assert_eq!(bps_of(&Nat::from(1u64), &Nat::from(3u64)).unwrap(), 3_333);
+        assert_eq!(bps_of(&Nat::from(2u64), &Nat::from(3u64)).unwrap(), 6_667);
+        // Exactly half a basis point rounds up
+        assert_eq!(bps_of(&Nat::from(1u64), &Nat::from(20_000u64)).unwrap(), 1);
+        assert_eq!(bps_of(&Nat::from(1u64), &Nat::from(20_001u64)).unwrap(), 0);
+        assert_eq!(bps_of(&Nat::from(0u64), &Nat::from(5u64)).unwrap(), 0);
+        // Parts above the whole aren't capped
+        assert_eq!(bps_of(&Nat::from(3u64), &Nat::from(2u64)).unwrap(), 15_000);
+        // Beyond u64 without loss
+        let whole = Nat::from(u128::MAX);
+        assert_eq!(bps_of(&(whole.clone() / Nat::from(10u64)), &whole).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_bps_of_errors() {
+        assert!(matches!(
+            bps_of(&Nat::from(1u64), &Nat::from(0u64)),
+            Err(IcpiError::Calculation(CalculationError::DivisionByZero { .. }))
+        ));
+        assert!(matches!(
+            bps_of(&Nat::from(u64::MAX), &Nat::from(1u64)),
+            Err(IcpiError::Calculation(CalculationError::Overflow { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_apply_bps_rounds_down() {
+        assert_eq!(apply_bps(&Nat::from(1_000u64), 250).unwrap(), Nat::from(25u64));
+        assert_eq!(apply_bps(&Nat::from(999u64), 1_000).unwrap(), Nat::from(99u64));
+        assert_eq!(apply_bps(&Nat::from(1u64), 9_999).unwrap(), Nat::from(0u64));
+        assert_eq!(apply_bps(&Nat::from(7u64), BPS_SCALE).unwrap(), Nat::from(7u64));
+        assert_eq!(apply_bps(&Nat::from(7u64), 0).unwrap(), Nat::from(0u64));
+        assert_eq!(
+            apply_bps(&(Nat::from(u128::MAX) * Nat::from(10u64)), 1_000).unwrap(),
+            Nat::from(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn test_apply_then_measure_round_trips_within_one_bps() {
+        // Deterministic pseudo-random amounts and rates (xorshift)
+        let mut seed = 0x9E37_79B9_7F4A_7C15u64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        for _ in 0..10_000 {
+            // From one basis point's worth up to ~1.8e19 base units
+            let amount = BPS_SCALE as u64 + next() % (u64::MAX - BPS_SCALE as u64);
+            let bps = (next() % (2 * BPS_SCALE as u64 + 1)) as u32;
+
+            let share = apply_bps(&Nat::from(amount), bps).unwrap();
+            assert!(share <= Nat::from(amount) * Nat::from(bps) / Nat::from(BPS_SCALE));
+            let measured = bps_of(&share, &Nat::from(amount)).unwrap();
+            assert!(measured.abs_diff(bps) <= 1, "{} bps of {} measured {}", bps, amount, measured);
+        }
+    }
+
+    #[test]
+    fn test_trade_size_rounds_intensity_to_bps() {
+        // 10% intensity as float noise is still 1,000 bps
+        let result = calculate_trade_size(100.0, 0.1 * 3.0 / 3.0).unwrap();
+        assert_eq!(result, Nat::from(10_000_000u64));
+        // $33.333333 × 10% rounds down to the e6 unit
+        let result = calculate_trade_size(33.333_333, 0.1).unwrap();
+        assert_eq!(result, Nat::from(3_333_333u64));
+
+        assert!(calculate_trade_size(f64::INFINITY, 0.1).is_err());
+        assert!(calculate_trade_size(100.0, -0.1).is_err());
+    }
+
+    fn e8(n: u64) -> Nat {
+        Nat::from(n * 100_000_000)
+    }
+
+    #[test]
+    fn test_sell_amount_rounds_down() {
+        let balance = Nat::from(u64::MAX);
+        // $1 at $3 per token with 8 decimals = 0.33333333... tokens
+        let amount = calculate_sell_amount(1.0, &Nat::from(3_000_000u64), &e8(1), &balance, &Nat::from(0u64)).unwrap();
+        assert_eq!(amount, Nat::from(33_333_333u64));
+    }
+
+    #[test]
+    fn test_sell_amount_large_values_exceed_u64() {
+        // $1M of an 18-decimal token priced at $0.001 = 1e9 tokens = 1e27 base units
+        let balance = Nat::from(BigUint::from(10u32).pow(28));
+        let one_token = Nat::from(BigUint::from(10u32).pow(18));
+        let amount = calculate_sell_amount(1_000_000.0, &Nat::from(1_000u64), &one_token, &balance, &Nat::from(0u64)).unwrap();
+        assert_eq!(amount, Nat::from(BigUint::from(10u32).pow(27)));
+    }
+
+    #[test]
+    fn test_sell_amount_clamped_to_balance() {
+        let balance = Nat::from(50_000_000u64);
+        let one_dollar = Nat::from(1_000_000u64);
+        // $100 at $1 would be 100 tokens, only 0.5 held
+        let amount = calculate_sell_amount(100.0, &one_dollar, &e8(1), &balance, &Nat::from(0u64)).unwrap();
+        assert_eq!(amount, balance);
+
+        let zero = Nat::from(0u64);
+        assert_eq!(calculate_sell_amount(100.0, &one_dollar, &e8(1), &zero, &Nat::from(0u64)).unwrap(), zero);
+    }
+
+    #[test]
+    fn test_sell_amount_leaves_room_for_fee() {
+        let balance = Nat::from(50_000_000u64);
+        let fee = Nat::from(20_000u64);
+        let one_dollar = Nat::from(1_000_000u64);
+        // Selling the whole position keeps back what the ledger charges
+        let amount = calculate_sell_amount(100.0, &one_dollar, &e8(1), &balance, &fee).unwrap();
+        assert_eq!(amount, Nat::from(49_980_000u64));
+
+        // A sell that fits beside the fee is not reduced
+        let amount = calculate_sell_amount(0.25, &one_dollar, &e8(1), &balance, &fee).unwrap();
+        assert_eq!(amount, Nat::from(25_000_000u64));
+
+        // A balance that can't cover the fee sells nothing
+        assert_eq!(calculate_sell_amount(100.0, &one_dollar, &e8(1), &fee, &fee).unwrap(), Nat::from(0u64));
+    }
+
+    #[test]
+    fn test_sell_amount_rejects_bad_inputs() {
+        let balance = Nat::from(1_000u64);
+        let one_dollar = Nat::from(1_000_000u64);
+        assert!(calculate_sell_amount(f64::NAN, &one_dollar, &e8(1), &balance, &Nat::from(0u64)).is_err());
+        assert!(calculate_sell_amount(-1.0, &one_dollar, &e8(1), &balance, &Nat::from(0u64)).is_err());
+        assert!(calculate_sell_amount(1.0, &Nat::from(0u64), &e8(1), &balance, &Nat::from(0u64)).is_err());
+        assert!(calculate_sell_amount(1.0, &one_dollar, &Nat::from(0u64), &balance, &Nat::from(0u64)).is_err());
     }
 
     #[test]
-    fn test_trade_size_above_minimum() {
-        // Deviation $100, intensity 10%, min $1 → trade $10 → returns 10_000_000 e6
-        let result = calculate_trade_size(100.0, 0.1, 1.0).unwrap();
-        assert_eq!(result, Nat::from(10_000_000u64)); // $10 in e6
+    fn test_sell_amount_exact_where_float_price_drifts() {
+        let balance = Nat::from(u128::MAX);
+        // Kongswap quote: 1 e6 unit of ckUSDT for 3 tokens, $0.000000333...
+        let receive_e6 = Nat::from(1u64);
+        let pay = e8(3);
+        // Float path: the price rounded to 12 decimals loses the repeating tail
+        let price_e12 = ((1.0 / 1_000_000.0) / 3.0 * 1e12_f64).round() as u64;
+        assert_eq!(price_e12, 333_333);
+        let float_amount = multiply_and_divide(&Nat::from(1_000_000u64), &Nat::from(100_000_000_000_000u64), &Nat::from(price_e12)).unwrap();
+
+        // $1 buys exactly 3M tokens; the float price oversells
+        let exact = calculate_sell_amount(1.0, &receive_e6, &pay, &balance, &Nat::from(0u64)).unwrap();
+        assert_eq!(exact, e8(3_000_000));
+        assert!(float_amount > exact);
+
+        // 0.1 has no exact f64 form; the quote ratio is exact
+        // $10 at $0.10 (100_000 e6 per token) is exactly 100 tokens
+        assert_eq!(calculate_sell_amount(10.0, &Nat::from(100_000u64), &e8(1), &balance, &Nat::from(0u64)).unwrap(), e8(100));
     }
 
     #[test]
@@ -0,0 +1,90 @@
+//! Operation memos
+//!
+//! Integrators can tag mints and burns with a short memo. The memo itself
+//! is stored with the operation; ledger transfers we initiate carry a hash
+//! of it so ledger entries can be matched back to the tagged operation
+//! (ICRC-1 memos are limited to 32 bytes). The ICPI mint itself carries a
+//! hash of the mint id instead, which mint recovery matches on.
+
+use crate::infrastructure::{Result, IcpiError, ValidationError};
+use crate::infrastructure::constants::MAX_OPERATION_MEMO_BYTES;
+
+/// Validate a caller-supplied memo
+///
+/// Candid `text` is already valid UTF-8; only the length is checked here.
+pub fn validate_memo(memo: Option<String>) -> Result<Option<String>> {
+    match memo {
+        Some(m) if m.len() > MAX_OPERATION_MEMO_BYTES => {
+            Err(IcpiError::Validation(ValidationError::MemoTooLong {
+                bytes: m.len() as u64,
+                max: MAX_OPERATION_MEMO_BYTES as u64,
+            }))
+        }
+        other => Ok(other),
+    }
+}
+
+/// Ledger memo for a transfer belonging to an operation
+///
+/// `base` is used as-is when there's no memo; otherwise the memo's hash is
+/// appended (`base` must be at most 14 bytes to stay within 32).
+pub fn ledger_memo(base: &str, memo: Option<&str>) -> String {
+    match memo {
+        Some(m) => format!("{} {}", base, ledger_tag(m)),
+        None => base.to_string(),
+    }
+}
+
+/// The hash tag `ledger_memo` appends for `memo`, e.g. `#3f9a21bc0d4e5f60`
+pub fn ledger_tag(memo: &str) -> String {
+    format!("#{:016x}", fnv1a_64(memo.as_bytes()))
+}
+
+/// 64-bit FNV-1a - stable across builds, unlike `DefaultHasher`
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memo_length_limit() {
+        assert_eq!(validate_memo(None).unwrap(), None);
+        let max = "a".repeat(MAX_OPERATION_MEMO_BYTES);
+        assert_eq!(validate_memo(Some(max.clone())).unwrap(), Some(max));
+
+        let too_long = "a".repeat(MAX_OPERATION_MEMO_BYTES + 1);
+        assert!(matches!(
+            validate_memo(Some(too_long)),
+            Err(IcpiError::Validation(ValidationError::MemoTooLong { bytes: 65, max: 64 }))
+        ));
+
+        // Limit is in bytes, not characters
+        let multibyte = "é".repeat(MAX_OPERATION_MEMO_BYTES / 2 + 1);
+        assert!(validate_memo(Some(multibyte)).is_err());
+    }
+
+    #[test]
+    fn test_ledger_memo_fits_icrc1_limit() {
+        assert_eq!(ledger_memo("ICPI mint", None), "ICPI mint");
+
+        let tagged = ledger_memo("ICPI redeem", Some(&"x".repeat(MAX_OPERATION_MEMO_BYTES)));
+        assert!(tagged.starts_with("ICPI redeem #"));
+        assert!(tagged.ends_with(&ledger_tag(&"x".repeat(MAX_OPERATION_MEMO_BYTES))));
+        assert!(tagged.len() <= 32);
+
+        // Same memo, same tag
+        assert_eq!(ledger_memo("ICPI mint", Some("q3")), ledger_memo("ICPI mint", Some("q3")));
+        assert_ne!(ledger_memo("ICPI mint", Some("q3")), ledger_memo("ICPI mint", Some("q4")));
+    }
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
@@ -11,10 +11,20 @@ pub mod rate_limiting;
 pub mod reentrancy;
 pub mod stable_storage;
 pub mod admin;
+pub mod config;
+pub mod access_control;
+pub mod ledger_client;
+pub mod timers;
+pub mod memo;
+pub mod instruction_budget;
+pub mod dependency_compat;
+pub mod runtime;
 
 // Re-export commonly used items
 pub use constants::*;
-pub use errors::{IcpiError, Result, MintError, BurnError, RebalanceError, ValidationError, CalculationError, TradingError, KongswapError, SystemError};
-pub use math::{multiply_and_divide, convert_decimals, calculate_mint_amount};
+pub use errors::{IcpiError, Result, ApiError, ApiResult, ErrorZone, MintError, BurnError, RebalanceError, ValidationError, CalculationError, TradingError, KongswapError, SystemError, LedgerError, RequiredApproval};
+pub use math::{multiply_and_divide, convert_decimals, calculate_mint_amount, bps_of, apply_bps};
 pub use reentrancy::{MintGuard, BurnGuard};
-pub use admin::{require_admin, check_not_paused, log_admin_action, set_pause, is_paused, get_admin_log, AdminAction};
+pub use instruction_budget::InstructionBudget;
+pub use admin::{require_admin, require_self_or_admin, resolve_user_target, check_not_paused, log_admin_action, log_admin_change, is_paused, get_admin_log, AdminAction, PauseInfo, QuiesceStatus};
+pub use ledger_client::{icpi_ledger, ckusdt_ledger, fees as ledger_fees};
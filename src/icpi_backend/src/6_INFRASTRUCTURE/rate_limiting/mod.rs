@@ -1,11 +1,49 @@
 //! Rate limiting module
+//!
+//! Per-principal windows for mint, burn and manual rebalance, configurable
+//! through `RuntimeConfig`. Admin principals and the configured bypass list
+//! are never rate-limited, so operators can act during an incident.
 
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
 use crate::infrastructure::Result;
+use crate::infrastructure::constants::MAX_RATE_LIMIT_WINDOW_SECONDS;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Operations with a per-principal rate limit
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitedOperation {
+    Mint,
+    Burn,
+    /// Public pokes; admin-triggered rebalances are exempt like every admin call
+    ManualRebalance,
+}
+
+impl RateLimitedOperation {
+    pub fn key(&self, caller: &Principal) -> String {
+        match self {
+            RateLimitedOperation::Mint => format!("mint_{}", caller),
+            RateLimitedOperation::Burn => format!("burn_{}", caller),
+            RateLimitedOperation::ManualRebalance => format!("poke_{}", caller),
+        }
+    }
+}
+
+/// Effective rate-limit settings, as reported by `get_effective_config`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RateLimitConfig {
+    pub mint_window_seconds: u64,
+    pub burn_window_seconds: u64,
+    pub manual_rebalance_window_seconds: u64,
+    /// Exempt in addition to the admin principals
+    pub bypass: Vec<Principal>,
+}
+
 const MAX_ENTRIES: usize = 10000;
-const CLEANUP_INTERVAL: u64 = 3600_000_000_000; // 1 hour in nanoseconds
+const CLEANUP_INTERVAL: u64 = 3600_000_000_000; // 1 hour in nanoseconds; also the minimum entry retention
 const CLEANUP_THRESHOLD: usize = 1000; // Cleanup when we have more than 1000 entries
 
 thread_local! {
@@ -13,9 +51,95 @@ thread_local! {
     static LAST_CLEANUP: RefCell<u64> = RefCell::new(0);
 }
 
+/// Reject an attempt made within `limit_nanos` of the last one
+fn check_window(last: Option<u64>, now: u64, limit_nanos: u64) -> Result<()> {
+    if let Some(last_time) = last {
+        let elapsed = now.saturating_sub(last_time);
+        if elapsed < limit_nanos {
+            return Err(crate::infrastructure::IcpiError::Other(
+                format!("Rate limit exceeded. Please wait {} seconds",
+                    (limit_nanos - elapsed) / NANOS_PER_SECOND)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `caller` skips rate limits: admins and the bypass list
+pub fn is_exempt(caller: &Principal, bypass: &[Principal]) -> bool {
+    crate::infrastructure::admin::is_admin(caller) || bypass.contains(caller)
+}
+
+/// Validate a configured window: at most `MAX_RATE_LIMIT_WINDOW_SECONDS`
+pub fn validate_window(seconds: u64) -> Result<()> {
+    if seconds > MAX_RATE_LIMIT_WINDOW_SECONDS {
+        return Err(crate::infrastructure::IcpiError::Other(format!(
+            "Rate limit window must be at most {} seconds", MAX_RATE_LIMIT_WINDOW_SECONDS
+        )));
+    }
+    Ok(())
+}
+
+fn window_nanos(seconds: u64) -> u64 {
+    seconds.saturating_mul(NANOS_PER_SECOND)
+}
+
+/// How long cleanup keeps an entry: an hour, or the longest window if longer
+fn retention_nanos(window_seconds: &[u64]) -> u64 {
+    window_seconds.iter()
+        .map(|seconds| window_nanos(*seconds))
+        .fold(CLEANUP_INTERVAL, u64::max)
+}
+
+fn configured_retention_nanos() -> u64 {
+    let config = crate::infrastructure::config::get_config();
+    retention_nanos(&[
+        config.rate_limit_window_seconds(RateLimitedOperation::Mint),
+        config.rate_limit_window_seconds(RateLimitedOperation::Burn),
+        config.rate_limit_window_seconds(RateLimitedOperation::ManualRebalance),
+    ])
+}
+
+/// Drop entries last seen more than `retention` before `now`; returns how many
+fn evict_expired(limits: &mut HashMap<String, u64>, now: u64, retention: u64) -> usize {
+    let cutoff = now.saturating_sub(retention);
+    let before_count = limits.len();
+    limits.retain(|_, &mut time| time > cutoff);
+    before_count - limits.len()
+}
+
+/// Check and record `caller`'s attempt at `op` against its configured window
+pub fn check_operation_rate_limit(op: RateLimitedOperation, caller: &Principal) -> Result<()> {
+    let config = crate::infrastructure::config::get_config();
+    if is_exempt(caller, &config.rate_limit_bypass()) {
+        return Ok(());
+    }
+    check_rate_limit(&op.key(caller), window_nanos(config.rate_limit_window_seconds(op)))
+}
+
+/// Nanoseconds until `caller` may attempt `op` again (0 if now or exempt)
+pub fn operation_wait_nanos(op: RateLimitedOperation, caller: &Principal) -> u64 {
+    let config = crate::infrastructure::config::get_config();
+    if is_exempt(caller, &config.rate_limit_bypass()) {
+        return 0;
+    }
+    rate_limit_wait_nanos(&op.key(caller), window_nanos(config.rate_limit_window_seconds(op)))
+}
+
+/// Current windows and bypass list
+pub fn get_rate_limit_config() -> RateLimitConfig {
+    let config = crate::infrastructure::config::get_config();
+    RateLimitConfig {
+        mint_window_seconds: config.rate_limit_window_seconds(RateLimitedOperation::Mint),
+        burn_window_seconds: config.rate_limit_window_seconds(RateLimitedOperation::Burn),
+        manual_rebalance_window_seconds: config.rate_limit_window_seconds(RateLimitedOperation::ManualRebalance),
+        bypass: config.rate_limit_bypass(),
+    }
+}
+
 /// Check rate limit for an operation
 pub fn check_rate_limit(key: &str, limit_nanos: u64) -> Result<()> {
-    let now = ic_cdk::api::time();
+    let now = crate::infrastructure::runtime::time();
 
     RATE_LIMITS.with(|limits| {
         let mut limits = limits.borrow_mut();
@@ -30,16 +154,15 @@ pub fn check_rate_limit(key: &str, limit_nanos: u64) -> Result<()> {
             // Check if periodic cleanup is due
             LAST_CLEANUP.with(|last| {
                 let last_cleanup_time = *last.borrow();
-                now - last_cleanup_time > CLEANUP_INTERVAL
+                now.saturating_sub(last_cleanup_time) > CLEANUP_INTERVAL
             })
         } else {
             false
         };
 
         if should_cleanup {
-            let cutoff = now - CLEANUP_INTERVAL;
             let before_count = limits.len();
-            limits.retain(|_, &mut time| time > cutoff);
+            evict_expired(&mut limits, now, configured_retention_nanos());
             let after_count = limits.len();
 
             // Update last cleanup time
@@ -55,25 +178,30 @@ pub fn check_rate_limit(key: &str, limit_nanos: u64) -> Result<()> {
             );
         }
 
-        if let Some(last_time) = limits.get(key) {
-            if now - last_time < limit_nanos {
-                return Err(crate::infrastructure::IcpiError::Other(
-                    format!("Rate limit exceeded. Please wait {} seconds",
-                        (limit_nanos - (now - last_time)) / 1_000_000_000)
-                ));
-            }
-        }
+        check_window(limits.get(key).copied(), now, limit_nanos)?;
 
         limits.insert(key.to_string(), now);
         Ok(())
     })
 }
 
+/// Nanoseconds until `key` may pass `check_rate_limit` again (0 if now)
+///
+/// Read-only: unlike `check_rate_limit` this doesn't record an attempt.
+pub fn rate_limit_wait_nanos(key: &str, limit_nanos: u64) -> u64 {
+    let now = crate::infrastructure::runtime::time();
+    RATE_LIMITS.with(|limits| {
+        limits.borrow().get(key)
+            .map(|last_time| limit_nanos.saturating_sub(now.saturating_sub(*last_time)))
+            .unwrap_or(0)
+    })
+}
+
 /// Manually trigger rate limit cleanup
 /// This can be called periodically by a timer to ensure cleanup happens
 /// even if there's low activity
 pub fn periodic_cleanup() {
-    let now = ic_cdk::api::time();
+    let now = crate::infrastructure::runtime::time();
 
     RATE_LIMITS.with(|limits| {
         let mut limits = limits.borrow_mut();
@@ -82,9 +210,8 @@ pub fn periodic_cleanup() {
             return;
         }
 
-        let cutoff = now - CLEANUP_INTERVAL;
         let before_count = limits.len();
-        limits.retain(|_, &mut time| time > cutoff);
+        evict_expired(&mut limits, now, configured_retention_nanos());
         let after_count = limits.len();
 
         if before_count > after_count {
@@ -101,4 +228,65 @@ pub fn periodic_cleanup() {
     LAST_CLEANUP.with(|last| {
         *last.borrow_mut() = now;
     });
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = NANOS_PER_SECOND;
+
+    #[test]
+    fn test_window_enforced() {
+        let now = 100 * SECOND;
+        assert!(check_window(None, now, 60 * SECOND).is_ok());
+        assert!(check_window(Some(now - 59 * SECOND), now, 60 * SECOND).is_err());
+        assert!(check_window(Some(now - 60 * SECOND), now, 60 * SECOND).is_ok());
+        // A zero window never blocks
+        assert!(check_window(Some(now), now, 0).is_ok());
+    }
+
+    #[test]
+    fn test_admins_and_bypass_list_exempt() {
+        let admin = Principal::from_text("67ktx-ln42b-uzmo5-bdiyn-gu62c-cd4h4-a5qt3-2w3rs-cixdl-iaso2-mqe").unwrap();
+        let operator = Principal::from_slice(&[9]);
+        let user = Principal::from_slice(&[1]);
+
+        assert!(is_exempt(&admin, &[]));
+        assert!(is_exempt(&operator, &[operator]));
+        assert!(!is_exempt(&user, &[operator]));
+    }
+
+    #[test]
+    fn test_operation_keys_distinct() {
+        let user = Principal::from_slice(&[1]);
+        assert_eq!(RateLimitedOperation::Mint.key(&user), format!("mint_{}", user));
+        assert_ne!(RateLimitedOperation::Burn.key(&user), RateLimitedOperation::ManualRebalance.key(&user));
+    }
+
+    #[test]
+    fn test_window_bounds() {
+        assert!(validate_window(0).is_ok());
+        assert!(validate_window(MAX_RATE_LIMIT_WINDOW_SECONDS).is_ok());
+        assert!(validate_window(MAX_RATE_LIMIT_WINDOW_SECONDS + 1).is_err());
+        // Windows stored before the bound saturate instead of overflowing
+        assert_eq!(window_nanos(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_long_window_survives_cleanup() {
+        let window = 2 * 3600;
+        let last = 1_000 * SECOND;
+        let now = last + 90 * 60 * SECOND;
+        let retention = retention_nanos(&[1, window, 60]);
+        assert_eq!(retention, window * SECOND);
+
+        let mut limits = HashMap::from([("burn_a".to_string(), last), ("mint_b".to_string(), 0)]);
+        assert_eq!(evict_expired(&mut limits, now, retention), 1);
+
+        // Still inside the two hour window after the cleanup
+        assert!(check_window(limits.get("burn_a").copied(), now, window * SECOND).is_err());
+
+        // Short windows keep the hour retention
+        assert_eq!(retention_nanos(&[1, 60]), CLEANUP_INTERVAL);
+    }
+}
@@ -5,12 +5,15 @@
 //!
 //! ### Layer 1: Per-User Guards (MintGuard, BurnGuard)
 //! - Prevents single user from initiating multiple concurrent operations
-//! - Allows different users to operate simultaneously
+//! - Allows different users to operate simultaneously, up to a system-wide
+//!   cap on in-flight mints (`MintGuard::acquire_within`)
 //! - Fine-grained concurrency control
 //!
 //! ### Layer 2: Global Operation Coordination (GlobalOperation)
-//! - Prevents rebalancing during active mints/burns
-//! - Enforces grace period between operation type switches
+//! - Prevents rebalancing during active mints/burns; holding a MintGuard or
+//!   BurnGuard keeps the global state at Minting/Burning
+//! - Enforces grace period between operation type switches, unless the
+//!   caller is the one whose operation ended last and nobody else is active
 //! - Coarse-grained system-wide coordination
 //!
 //! Example: User A and User B can mint simultaneously (Layer 1 allows),
@@ -47,9 +50,6 @@ impl GlobalOperation {
     }
 }
 
-/// Grace period between operation type switches (60 seconds)
-const GRACE_PERIOD_NANOS: u64 = 60_000_000_000;
-
 thread_local! {
     /// Track active minting operations by user
     static ACTIVE_MINTS: RefCell<HashSet<Principal>> = RefCell::new(HashSet::new());
@@ -62,34 +62,60 @@ thread_local! {
 
     /// Timestamp when last operation ended (for grace period)
     static LAST_OPERATION_END_TIME: RefCell<u64> = RefCell::new(0);
+
+    /// Caller whose operation ended last (None for timer-driven operations)
+    static LAST_OPERATION_ENDED_BY: RefCell<Option<Principal>> = RefCell::new(None);
+
+    /// Type of the operation that ended last (for grace period)
+    static LAST_ENDED_OPERATION: RefCell<GlobalOperation> = RefCell::new(GlobalOperation::Idle);
 }
 
 /// Guard for minting operations
+///
+/// Also holds the global Minting state until dropped.
 pub struct MintGuard {
     user: Principal,
 }
 
 impl MintGuard {
-    /// Acquire a mint guard for the user
+    /// Acquire a mint guard for the user, subject to the configured
+    /// system-wide cap on in-flight mints
     pub fn acquire(user: Principal) -> Result<Self> {
-        let acquired = ACTIVE_MINTS.with(|mints| {
+        Self::acquire_within(user, crate::infrastructure::config::get_config().max_concurrent_mints())
+    }
+
+    /// Acquire a mint guard unless `max_active` mints are already in flight
+    ///
+    /// Bounds the external calls a burst of users can trigger at once; a
+    /// rejected caller should retry later.
+    pub fn acquire_within(user: Principal, max_active: u64) -> Result<Self> {
+        ACTIVE_MINTS.with(|mints| {
             let mut mints = mints.borrow_mut();
             if mints.contains(&user) {
-                false // Already minting
-            } else {
-                mints.insert(user);
-                true
+                return Err(IcpiError::System(SystemError::OperationInProgress {
+                    operation: "mint".to_string(),
+                    user: user.to_text(),
+                }));
             }
-        });
-
-        if acquired {
-            Ok(MintGuard { user })
-        } else {
-            Err(IcpiError::System(SystemError::OperationInProgress {
-                operation: "mint".to_string(),
-                user: user.to_text(),
-            }))
+            let active = mints.len() as u64;
+            if active >= max_active {
+                return Err(IcpiError::System(SystemError::TooManyConcurrentOperations {
+                    operation: "mint".to_string(),
+                    active,
+                    max: max_active,
+                }));
+            }
+            mints.insert(user);
+            Ok(())
+        })?;
+
+        // Blocked by rebalancing or the grace period: release the user slot
+        if let Err(e) = try_start_global_operation_for(GlobalOperation::Minting, Some(user)) {
+            ACTIVE_MINTS.with(|mints| mints.borrow_mut().remove(&user));
+            return Err(e);
         }
+
+        Ok(MintGuard { user })
     }
 }
 
@@ -98,10 +124,13 @@ impl Drop for MintGuard {
         ACTIVE_MINTS.with(|mints| {
             mints.borrow_mut().remove(&self.user);
         });
+        end_global_operation_for(GlobalOperation::Minting, Some(self.user));
     }
 }
 
 /// Guard for burning operations
+///
+/// Also holds the global Burning state until dropped.
 pub struct BurnGuard {
     user: Principal,
 }
@@ -119,14 +148,20 @@ impl BurnGuard {
             }
         });
 
-        if acquired {
-            Ok(BurnGuard { user })
-        } else {
-            Err(IcpiError::System(SystemError::OperationInProgress {
+        if !acquired {
+            return Err(IcpiError::System(SystemError::OperationInProgress {
                 operation: "burn".to_string(),
                 user: user.to_text(),
-            }))
+            }));
         }
+
+        // Blocked by rebalancing or the grace period: release the user slot
+        if let Err(e) = try_start_global_operation_for(GlobalOperation::Burning, Some(user)) {
+            ACTIVE_BURNS.with(|burns| burns.borrow_mut().remove(&user));
+            return Err(e);
+        }
+
+        Ok(BurnGuard { user })
     }
 }
 
@@ -135,38 +170,59 @@ impl Drop for BurnGuard {
         ACTIVE_BURNS.with(|burns| {
             burns.borrow_mut().remove(&self.user);
         });
+        end_global_operation_for(GlobalOperation::Burning, Some(self.user));
     }
 }
 
+/// Check whether the user currently holds a MintGuard
+pub fn is_minting(user: &Principal) -> bool {
+    ACTIVE_MINTS.with(|mints| mints.borrow().contains(user))
+}
+
+/// Check whether the user currently holds a BurnGuard
+pub fn is_burning(user: &Principal) -> bool {
+    ACTIVE_BURNS.with(|burns| burns.borrow().contains(user))
+}
+
 // === GLOBAL OPERATION COORDINATION FUNCTIONS ===
 
 /// Try to start a global operation
 ///
 /// This enforces:
-/// 1. Grace period between different operation types
+/// 1. Grace period between different operation types, measured from the
+///    end of the last operation
 /// 2. Rebalancing blocked during mints/burns
 /// 3. Mints/burns can coexist (per-user guards handle conflicts)
 ///
 /// Returns Ok if operation can proceed, Err if blocked
 pub fn try_start_global_operation(op: GlobalOperation) -> Result<()> {
+    try_start_global_operation_for(op, None)
+}
+
+/// Try to start a global operation on behalf of `caller`
+///
+/// Same as `try_start_global_operation`, but the grace period is waived
+/// when `caller` ended the last operation and no one else is active.
+pub fn try_start_global_operation_for(op: GlobalOperation, caller: Option<Principal>) -> Result<()> {
     CURRENT_GLOBAL_OPERATION.with(|current| {
         let current_op = *current.borrow();
 
-        // Check grace period (except when transitioning from Idle)
-        if current_op != GlobalOperation::Idle && current_op != op {
-            LAST_OPERATION_END_TIME.with(|last| {
-                let last_end = *last.borrow();
-                let now = ic_cdk::api::time();
-
-                if last_end > 0 && now > last_end && (now - last_end) < GRACE_PERIOD_NANOS {
-                    let wait_seconds = (GRACE_PERIOD_NANOS - (now - last_end)) / 1_000_000_000;
-                    return Err(IcpiError::System(SystemError::GracePeriodActive {
-                        wait_seconds,
-                        current_operation: current_op.as_str().to_string(),
-                    }));
-                }
-                Ok(())
-            })?;
+        // Check grace period when switching type, from a running operation
+        // or from the one that ended last
+        let previous_op = match current_op {
+            GlobalOperation::Idle => LAST_ENDED_OPERATION.with(|last| *last.borrow()),
+            running => running,
+        };
+        if previous_op != GlobalOperation::Idle && op != GlobalOperation::Idle && previous_op != op {
+            let mut active_users: Vec<Principal> = ACTIVE_MINTS.with(|m| m.borrow().iter().copied().collect());
+            active_users.extend(ACTIVE_BURNS.with(|b| b.borrow().iter().copied().collect::<Vec<_>>()));
+            check_grace_period(
+                previous_op,
+                LAST_OPERATION_END_TIME.with(|last| *last.borrow()),
+                crate::infrastructure::runtime::time(),
+                crate::infrastructure::config::get_config().grace_period_seconds(),
+                same_caller_exempt(caller, LAST_OPERATION_ENDED_BY.with(|by| *by.borrow()), &active_users),
+            )?;
         }
 
         // Check operation conflicts
@@ -221,11 +277,55 @@ pub fn try_start_global_operation(op: GlobalOperation) -> Result<()> {
     })
 }
 
+/// Reject switching away from `current_op` within the grace period after
+/// the last operation ended, unless `exempt`
+fn check_grace_period(
+    current_op: GlobalOperation,
+    last_end: u64,
+    now: u64,
+    grace_period_seconds: u64,
+    exempt: bool,
+) -> Result<()> {
+    let grace_period_nanos = grace_period_seconds.saturating_mul(1_000_000_000);
+    if exempt || last_end == 0 || now <= last_end || now - last_end >= grace_period_nanos {
+        return Ok(());
+    }
+    let wait_seconds = (grace_period_nanos - (now - last_end)) / 1_000_000_000;
+    Err(IcpiError::System(SystemError::GracePeriodActive {
+        wait_seconds,
+        current_operation: current_op.as_str().to_string(),
+    }))
+}
+
+/// Whether `caller` ended the last operation and is the only user with one active
+///
+/// Back-to-back operations by one caller don't conflict with each other.
+fn same_caller_exempt(caller: Option<Principal>, last_ended_by: Option<Principal>, active_users: &[Principal]) -> bool {
+    match caller {
+        Some(caller) => last_ended_by == Some(caller) && active_users.iter().all(|u| *u == caller),
+        None => false,
+    }
+}
+
+/// Record that `op` ended now, on behalf of `caller`
+fn record_operation_end(op: GlobalOperation, caller: Option<Principal>) {
+    LAST_OPERATION_END_TIME.with(|last| {
+        *last.borrow_mut() = crate::infrastructure::runtime::time();
+    });
+    LAST_OPERATION_ENDED_BY.with(|by| *by.borrow_mut() = caller);
+    LAST_ENDED_OPERATION.with(|last| *last.borrow_mut() = op);
+}
+
 /// End a global operation
 ///
 /// Call this when operation completes (success or failure)
 /// Records timestamp for grace period enforcement
 pub fn end_global_operation(op: GlobalOperation) {
+    end_global_operation_for(op, None)
+}
+
+/// End a global operation started on behalf of `caller`
+pub fn end_global_operation_for(op: GlobalOperation, caller: Option<Principal>) {
     CURRENT_GLOBAL_OPERATION.with(|current| {
         let current_op = *current.borrow();
 
@@ -236,39 +336,21 @@ pub fn end_global_operation(op: GlobalOperation) {
             (GlobalOperation::Rebalancing, GlobalOperation::Rebalancing) => {
                 *current.borrow_mut() = GlobalOperation::Idle;
 
-                LAST_OPERATION_END_TIME.with(|last| {
-                    *last.borrow_mut() = ic_cdk::api::time();
-                });
+                record_operation_end(op, caller);
 
                 ic_cdk::println!("🔓 Global operation ended: {:?}", op);
             },
 
             // Ending mint/burn: check if any other mints/burns still active
-            (GlobalOperation::Minting, GlobalOperation::Minting) => {
-                let has_active_mints = ACTIVE_MINTS.with(|m| !m.borrow().is_empty());
-                let has_active_burns = ACTIVE_BURNS.with(|b| !b.borrow().is_empty());
-
-                if !has_active_mints && !has_active_burns {
-                    *current.borrow_mut() = GlobalOperation::Idle;
-
-                    LAST_OPERATION_END_TIME.with(|last| {
-                        *last.borrow_mut() = ic_cdk::api::time();
-                    });
-
-                    ic_cdk::println!("🔓 Global operation ended: all mints/burns complete");
-                }
-            },
-
-            (GlobalOperation::Burning, GlobalOperation::Burning) => {
+            // (they share the state, which keeps the type of whichever started first)
+            (GlobalOperation::Minting | GlobalOperation::Burning, GlobalOperation::Minting | GlobalOperation::Burning) => {
                 let has_active_mints = ACTIVE_MINTS.with(|m| !m.borrow().is_empty());
                 let has_active_burns = ACTIVE_BURNS.with(|b| !b.borrow().is_empty());
 
                 if !has_active_mints && !has_active_burns {
                     *current.borrow_mut() = GlobalOperation::Idle;
 
-                    LAST_OPERATION_END_TIME.with(|last| {
-                        *last.borrow_mut() = ic_cdk::api::time();
-                    });
+                    record_operation_end(op, caller);
 
                     ic_cdk::println!("🔓 Global operation ended: all mints/burns complete");
                 }
@@ -297,12 +379,45 @@ pub fn has_active_operations() -> bool {
     has_mints || has_burns || current_op != GlobalOperation::Idle
 }
 
+// === SINGLE-RUN FLAGS ===
+
+/// Guard for a thread-local "already running" flag of a background task
+///
+/// Clears the flag on drop, so a run that traps mid-await (its future is
+/// dropped during cleanup) can't leave the task blocked for good.
+pub struct RunFlagGuard {
+    flag: &'static std::thread::LocalKey<RefCell<bool>>,
+}
+
+impl RunFlagGuard {
+    /// Set `flag`, or None if a run already holds it
+    pub fn try_acquire(flag: &'static std::thread::LocalKey<RefCell<bool>>) -> Option<Self> {
+        let already_running = flag.with(|f| std::mem::replace(&mut *f.borrow_mut(), true));
+        if already_running {
+            None
+        } else {
+            Some(RunFlagGuard { flag })
+        }
+    }
+}
+
+impl Drop for RunFlagGuard {
+    fn drop(&mut self) {
+        self.flag.with(|f| *f.borrow_mut() = false);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infrastructure::runtime::mock::MockRuntime;
+
+    const SECOND: u64 = 1_000_000_000;
+    const START: u64 = 1_700_000_000 * SECOND;
 
     #[test]
     fn test_mint_guard_prevents_reentrancy() {
+        let _runtime = MockRuntime::install(START);
         let user = Principal::anonymous();
 
         // First guard should succeed
@@ -321,6 +436,7 @@ mod tests {
 
     #[test]
     fn test_burn_guard_prevents_reentrancy() {
+        let _runtime = MockRuntime::install(START);
         let user = Principal::anonymous();
 
         // First guard should succeed
@@ -339,6 +455,7 @@ mod tests {
 
     #[test]
     fn test_different_users_can_mint_concurrently() {
+        let _runtime = MockRuntime::install(START);
         let user1 = Principal::from_text("2vxsx-fae").unwrap();
         let user2 = Principal::from_text("aaaaa-aa").unwrap();
 
@@ -350,6 +467,43 @@ mod tests {
         assert!(ACTIVE_MINTS.with(|m| m.borrow().len() == 2));
     }
 
+    #[test]
+    fn test_concurrent_mint_cap() {
+        let _runtime = MockRuntime::install(START);
+        let users: Vec<Principal> = (0..4u8).map(|i| Principal::from_slice(&[i])).collect();
+
+        let guards: Vec<MintGuard> = users[..3].iter()
+            .map(|u| MintGuard::acquire_within(*u, 3).expect("within cap"))
+            .collect();
+        assert_eq!(ACTIVE_MINTS.with(|m| m.borrow().len()), 3);
+
+        // Cap plus one is rejected
+        let result = MintGuard::acquire_within(users[3], 3);
+        assert!(matches!(
+            result,
+            Err(IcpiError::System(SystemError::TooManyConcurrentOperations { active: 3, max: 3, .. }))
+        ));
+        assert_eq!(ACTIVE_MINTS.with(|m| m.borrow().len()), 3);
+
+        // A finished mint frees a slot
+        drop(guards);
+        assert!(MintGuard::acquire_within(users[3], 3).is_ok());
+    }
+
+    #[test]
+    fn test_guard_lookup_helpers() {
+        let _runtime = MockRuntime::install(START);
+        let user = Principal::from_text("2vxsx-fae").unwrap();
+        assert!(!is_burning(&user));
+
+        let guard = BurnGuard::acquire(user).unwrap();
+        assert!(is_burning(&user));
+        assert!(!is_minting(&user));
+
+        drop(guard);
+        assert!(!is_burning(&user));
+    }
+
     // === M-4: Global Operation Coordination Tests ===
 
     #[test]
@@ -374,6 +528,7 @@ mod tests {
 
     #[test]
     fn test_minting_blocks_rebalancing() {
+        let _runtime = MockRuntime::install(START);
         // Reset state
         CURRENT_GLOBAL_OPERATION.with(|c| *c.borrow_mut() = GlobalOperation::Idle);
 
@@ -388,6 +543,7 @@ mod tests {
 
     #[test]
     fn test_rebalancing_blocks_minting() {
+        let _runtime = MockRuntime::install(START);
         // Reset state
         CURRENT_GLOBAL_OPERATION.with(|c| *c.borrow_mut() = GlobalOperation::Idle);
 
@@ -402,6 +558,7 @@ mod tests {
 
     #[test]
     fn test_minting_and_burning_can_coexist() {
+        let _runtime = MockRuntime::install(START);
         // Reset state
         CURRENT_GLOBAL_OPERATION.with(|c| *c.borrow_mut() = GlobalOperation::Idle);
 
@@ -432,6 +589,7 @@ mod tests {
 
     #[test]
     fn test_end_rebalancing_clears_state() {
+        let _runtime = MockRuntime::install(START);
         // Reset state
         CURRENT_GLOBAL_OPERATION.with(|c| *c.borrow_mut() = GlobalOperation::Idle);
 
@@ -456,6 +614,7 @@ mod tests {
 
     #[test]
     fn test_has_active_operations() {
+        let _runtime = MockRuntime::install(START);
         // Reset state
         CURRENT_GLOBAL_OPERATION.with(|c| *c.borrow_mut() = GlobalOperation::Idle);
         ACTIVE_MINTS.with(|m| m.borrow_mut().clear());
@@ -480,4 +639,110 @@ mod tests {
         assert_eq!(GlobalOperation::Burning.as_str(), "burning");
         assert_eq!(GlobalOperation::Rebalancing.as_str(), "rebalancing");
     }
+
+    #[test]
+    fn test_grace_period_duration_configurable() {
+        let ended = 1_000 * SECOND;
+        // 30s after the end: blocked with a 60s grace period, clear with 20s
+        let err = check_grace_period(GlobalOperation::Minting, ended, ended + 30 * SECOND, 60, false).unwrap_err();
+        assert!(matches!(err, IcpiError::System(SystemError::GracePeriodActive { wait_seconds: 30, .. })));
+        assert!(check_grace_period(GlobalOperation::Minting, ended, ended + 30 * SECOND, 20, false).is_ok());
+        // A zero grace period never blocks
+        assert!(check_grace_period(GlobalOperation::Minting, ended, ended + 1, 0, false).is_ok());
+        // Nothing has ended yet
+        assert!(check_grace_period(GlobalOperation::Minting, 0, 30 * SECOND, 60, false).is_ok());
+    }
+
+    #[test]
+    fn test_same_caller_exempt_from_grace_period() {
+        let alice = Principal::from_slice(&[1]);
+        let bob = Principal::from_slice(&[2]);
+
+        assert!(same_caller_exempt(Some(alice), Some(alice), &[]));
+        assert!(same_caller_exempt(Some(alice), Some(alice), &[alice]));
+        // Someone else ended the last operation, or is still active
+        assert!(!same_caller_exempt(Some(alice), Some(bob), &[]));
+        assert!(!same_caller_exempt(Some(alice), Some(alice), &[alice, bob]));
+        // Timer-driven operations have no caller to match
+        assert!(!same_caller_exempt(None, None, &[]));
+
+        let ended = 1_000 * SECOND;
+        let exempt = same_caller_exempt(Some(alice), Some(alice), &[alice]);
+        assert!(check_grace_period(GlobalOperation::Burning, ended, ended + SECOND, 60, exempt).is_ok());
+    }
+
+    #[test]
+    fn test_guards_hold_global_operation() {
+        let _runtime = MockRuntime::install(START);
+        let alice = Principal::from_slice(&[1]);
+        let bob = Principal::from_slice(&[2]);
+
+        let mint = MintGuard::acquire(alice).unwrap();
+        assert_eq!(get_current_operation(), GlobalOperation::Minting);
+
+        // Rebalancing waits for the mint
+        assert!(matches!(
+            try_start_global_operation(GlobalOperation::Rebalancing),
+            Err(IcpiError::System(SystemError::CriticalOperationInProgress { .. }))
+        ));
+
+        // Bob's burn joins the running mint; the state clears when both finish
+        let burn = BurnGuard::acquire(bob).unwrap();
+        drop(mint);
+        assert_eq!(get_current_operation(), GlobalOperation::Minting);
+        drop(burn);
+        assert_eq!(get_current_operation(), GlobalOperation::Idle);
+    }
+
+    #[test]
+    fn test_guards_blocked_by_rebalancing() {
+        let _runtime = MockRuntime::install(START);
+        let alice = Principal::from_slice(&[1]);
+
+        try_start_global_operation(GlobalOperation::Rebalancing).unwrap();
+        assert!(matches!(MintGuard::acquire(alice), Err(IcpiError::System(SystemError::RebalancingInProgress))));
+        assert!(matches!(BurnGuard::acquire(alice), Err(IcpiError::System(SystemError::RebalancingInProgress))));
+
+        // The rejected guards didn't keep their user slots
+        assert!(!is_minting(&alice));
+        assert!(!is_burning(&alice));
+    }
+
+    #[test]
+    fn test_run_flag_cleared_on_drop() {
+        thread_local! {
+            static RUNNING: RefCell<bool> = const { RefCell::new(false) };
+        }
+
+        let guard = RunFlagGuard::try_acquire(&RUNNING).unwrap();
+        assert!(RUNNING.with(|f| *f.borrow()));
+        assert!(RunFlagGuard::try_acquire(&RUNNING).is_none());
+
+        // Dropping the guard (as when a trapped run's future is dropped) clears it
+        drop(guard);
+        assert!(!RUNNING.with(|f| *f.borrow()));
+        assert!(RunFlagGuard::try_acquire(&RUNNING).is_some());
+    }
+
+    #[test]
+    fn test_guards_same_caller_exempt_from_grace_period() {
+        let runtime = MockRuntime::install(START);
+        let alice = Principal::from_slice(&[1]);
+        let bob = Principal::from_slice(&[2]);
+
+        drop(MintGuard::acquire(alice).unwrap());
+        runtime.advance_seconds(1);
+
+        // Alice ended the mint, so she may burn straight away; Bob waits
+        assert!(matches!(
+            BurnGuard::acquire(bob),
+            Err(IcpiError::System(SystemError::GracePeriodActive { .. }))
+        ));
+        assert!(!is_burning(&bob));
+        drop(BurnGuard::acquire(alice).unwrap());
+
+        // Once the grace period has passed anyone may switch
+        runtime.advance_seconds(crate::infrastructure::GRACE_PERIOD_SECONDS);
+        assert!(MintGuard::acquire(bob).is_ok());
+    }
 }
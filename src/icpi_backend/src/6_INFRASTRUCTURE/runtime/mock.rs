@@ -0,0 +1,550 @@
+//! In-memory IC for native tests
+//!
+//! `MockRuntime` keeps a settable clock and answers the calls the backend
+//! makes: ICRC-1/ICRC-2 ledgers, Kongswap (`swap_amounts`, `swap`,
+//! `user_balances`) and Kong Locker (`get_all_lock_canisters`). Arguments
+//! and replies cross the seam Candid-encoded, as on the IC.
+//!
+//! Ledgers don't deduplicate and Kongswap fills every swap in full at the
+//! listed price with zero slippage. Any other call is rejected with
+//! `DestinationInvalid`; `reject` makes a specific method fail.
+
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::{Nat, Principal};
+use ic_cdk::api::call::{CallResult, RejectionCode};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{CallFuture, Runtime};
+use crate::infrastructure::{ICPI_BACKEND_ID, KONGSWAP_BACKEND_ID, KONG_LOCKER_ID};
+use crate::types::icrc::{
+    Account, Allowance, AllowanceArgs, ApproveArgs, ApproveError, ApproveResult, TransferArgs, TransferError,
+    TransferFromArgs, TransferFromError, TransferResult,
+};
+use crate::types::kongswap::{
+    LPBalancesReply, SwapAmountsReply, SwapAmountsResult, SwapArgs, SwapReply, UserBalancesReply, UserBalancesResult,
+};
+
+const SECOND: u64 = 1_000_000_000;
+
+fn zero() -> Nat {
+    Nat::from(0u64)
+}
+
+/// An ICRC-1/ICRC-2 ledger
+pub struct MockLedger {
+    pub fee: Nat,
+    /// Transfers out of this account mint and transfers into it burn, fee-free
+    pub minting_account: Option<Principal>,
+    balances: HashMap<Principal, Nat>,
+    /// (owner, spender) → (amount, expires_at)
+    allowances: HashMap<(Principal, Principal), (Nat, Option<u64>)>,
+    next_block: u64,
+}
+
+impl MockLedger {
+    fn new(fee: u64, minting_account: Option<Principal>) -> Self {
+        MockLedger {
+            fee: Nat::from(fee),
+            minting_account,
+            balances: HashMap::new(),
+            allowances: HashMap::new(),
+            next_block: 0,
+        }
+    }
+
+    pub fn balance(&self, owner: &Principal) -> Nat {
+        self.balances.get(owner).cloned().unwrap_or_else(zero)
+    }
+
+    pub fn total_supply(&self) -> Nat {
+        self.balances.values().fold(zero(), |total, balance| total + balance.clone())
+    }
+
+    pub fn allowance(&self, owner: Principal, spender: Principal, now: u64) -> Nat {
+        match self.allowances.get(&(owner, spender)) {
+            Some((amount, expires_at)) if !expires_at.is_some_and(|at| at <= now) => amount.clone(),
+            _ => zero(),
+        }
+    }
+
+    fn credit(&mut self, owner: Principal, amount: &Nat) {
+        let balance = self.balance(&owner) + amount.clone();
+        self.balances.insert(owner, balance);
+    }
+
+    /// Debit `amount`, or return the balance that falls short
+    fn debit(&mut self, owner: Principal, amount: &Nat) -> std::result::Result<(), Nat> {
+        let balance = self.balance(&owner);
+        if balance < *amount {
+            return Err(balance);
+        }
+        self.balances.insert(owner, balance - amount.clone());
+        Ok(())
+    }
+
+    fn block(&mut self) -> Nat {
+        self.next_block += 1;
+        Nat::from(self.next_block - 1)
+    }
+
+    fn is_minting(&self, account: Principal) -> bool {
+        self.minting_account == Some(account)
+    }
+
+    fn fee_between(&self, from: Principal, to: Principal) -> Nat {
+        if self.is_minting(from) || self.is_minting(to) {
+            zero()
+        } else {
+            self.fee.clone()
+        }
+    }
+
+    fn transfer(&mut self, from: Principal, args: TransferArgs) -> TransferResult {
+        let to = args.to.owner;
+        let fee = self.fee_between(from, to);
+        if args.fee.as_ref().is_some_and(|f| *f != fee) {
+            return TransferResult::Err(TransferError::BadFee { expected_fee: fee });
+        }
+        if !self.is_minting(from) {
+            if let Err(balance) = self.debit(from, &(args.amount.clone() + fee)) {
+                return TransferResult::Err(TransferError::InsufficientFunds { balance });
+            }
+        }
+        if !self.is_minting(to) {
+            self.credit(to, &args.amount);
+        }
+        TransferResult::Ok(self.block())
+    }
+
+    fn transfer_from(&mut self, spender: Principal, args: TransferFromArgs, now: u64) -> std::result::Result<Nat, TransferFromError> {
+        let (from, to) = (args.from.owner, args.to.owner);
+        let fee = self.fee_between(from, to);
+        if args.fee.as_ref().is_some_and(|f| *f != fee) {
+            return Err(TransferFromError::BadFee { expected_fee: fee });
+        }
+        let required = args.amount.clone() + fee;
+        let allowance = self.allowance(from, spender, now);
+        if allowance < required {
+            return Err(TransferFromError::InsufficientAllowance { allowance });
+        }
+        if let Err(balance) = self.debit(from, &required) {
+            return Err(TransferFromError::InsufficientFunds { balance });
+        }
+        let expires_at = self.allowances.get(&(from, spender)).and_then(|(_, at)| *at);
+        self.allowances.insert((from, spender), (allowance - required, expires_at));
+        if !self.is_minting(to) {
+            self.credit(to, &args.amount);
+        }
+        Ok(self.block())
+    }
+
+    fn approve(&mut self, owner: Principal, args: ApproveArgs, now: u64) -> ApproveResult {
+        if args.expires_at.is_some_and(|at| at <= now) {
+            return ApproveResult::Err(ApproveError::Expired { ledger_time: now });
+        }
+        let fee = self.fee.clone();
+        if let Err(balance) = self.debit(owner, &fee) {
+            return ApproveResult::Err(ApproveError::InsufficientFunds { balance });
+        }
+        self.allowances.insert((owner, args.spender.owner), (args.amount, args.expires_at));
+        ApproveResult::Ok(self.block())
+    }
+
+    fn handle(&mut self, caller: Principal, method: &str, args: &[u8], now: u64) -> CallResult<Vec<u8>> {
+        match method {
+            "icrc1_balance_of" => {
+                let (account,): (Account,) = decode(method, args)?;
+                reply((self.balance(&account.owner),))
+            }
+            "icrc1_total_supply" => reply((self.total_supply(),)),
+            "icrc1_fee" => reply((self.fee.clone(),)),
+            "icrc2_allowance" => {
+                let (args,): (AllowanceArgs,) = decode(method, args)?;
+                let allowance = self.allowance(args.account.owner, args.spender.owner, now);
+                reply((Allowance { allowance, expires_at: None },))
+            }
+            "icrc1_transfer" => {
+                let (args,): (TransferArgs,) = decode(method, args)?;
+                reply((self.transfer(caller, args),))
+            }
+            "icrc2_transfer_from" => {
+                let (args,): (TransferFromArgs,) = decode(method, args)?;
+                reply((self.transfer_from(caller, args, now),))
+            }
+            "icrc2_approve" => {
+                let (args,): (ApproveArgs,) = decode(method, args)?;
+                reply((self.approve(caller, args, now),))
+            }
+            _ => Err(unknown_method(method)),
+        }
+    }
+}
+
+/// A token tradable on the mock Kongswap
+struct Listing {
+    ledger: Principal,
+    decimals: u32,
+    /// ckUSDT (e6) paid for one whole token
+    price_e6: Nat,
+}
+
+/// One LP position held by a lock canister
+#[derive(Clone)]
+pub struct LpPosition {
+    pub symbol_0: String,
+    pub usd_amount_0: f64,
+    pub symbol_1: String,
+    pub usd_amount_1: f64,
+}
+
+#[derive(Default)]
+struct MockKongswap {
+    listings: HashMap<String, Listing>,
+    /// Lock canister (text) → LP positions
+    positions: HashMap<String, Vec<LpPosition>>,
+    next_tx: u64,
+}
+
+impl MockKongswap {
+    fn listing(&self, symbol: &str) -> std::result::Result<&Listing, String> {
+        self.listings.get(symbol).ok_or_else(|| format!("Token {} not found", symbol))
+    }
+
+    /// Amount of `receive_symbol` paid for `pay_amount` of `pay_symbol`
+    fn quote(&self, pay_symbol: &str, pay_amount: &Nat, receive_symbol: &str) -> std::result::Result<Nat, String> {
+        let pay = self.listing(pay_symbol)?;
+        let receive = self.listing(receive_symbol)?;
+        let value_e6 = pay_amount.clone() * pay.price_e6.clone() / Nat::from(10u64.pow(pay.decimals));
+        Ok(value_e6 * Nat::from(10u64.pow(receive.decimals)) / receive.price_e6.clone())
+    }
+
+    fn swap_amounts(&self, pay_symbol: String, pay_amount: Nat, receive_symbol: String) -> SwapAmountsResult {
+        match self.quote(&pay_symbol, &pay_amount, &receive_symbol) {
+            Ok(receive_amount) => {
+                let price = price_of(&pay_amount, &receive_amount);
+                SwapAmountsResult::Ok(SwapAmountsReply {
+                    pay_symbol,
+                    receive_symbol,
+                    pay_amount,
+                    receive_amount,
+                    mid_price: price,
+                    price,
+                    slippage: 0.0,
+                })
+            }
+            Err(e) => SwapAmountsResult::Err(e),
+        }
+    }
+
+    fn user_balances(&self, lock_id: &str) -> UserBalancesResult {
+        let positions = self.positions.get(lock_id).cloned().unwrap_or_default();
+        UserBalancesResult::Ok(positions.into_iter().map(|p| UserBalancesReply::LP(LPBalancesReply {
+            name: format!("{}_{} LP Token", p.symbol_0, p.symbol_1),
+            symbol: format!("{}_{}", p.symbol_0, p.symbol_1),
+            lp_token_id: 0,
+            balance: 1.0,
+            usd_balance: p.usd_amount_0 + p.usd_amount_1,
+            chain_0: "IC".to_string(),
+            symbol_0: p.symbol_0,
+            address_0: String::new(),
+            amount_0: 0.0,
+            usd_amount_0: p.usd_amount_0,
+            chain_1: "IC".to_string(),
+            symbol_1: p.symbol_1,
+            address_1: String::new(),
+            amount_1: 0.0,
+            usd_amount_1: p.usd_amount_1,
+            ts: 0,
+        })).collect())
+    }
+}
+
+/// Price of the pay token in receive tokens, for display fields only
+fn price_of(pay_amount: &Nat, receive_amount: &Nat) -> f64 {
+    use num_traits::ToPrimitive;
+    let pay = pay_amount.0.to_f64().unwrap_or(0.0);
+    let receive = receive_amount.0.to_f64().unwrap_or(0.0);
+    if pay > 0.0 { receive / pay } else { 0.0 }
+}
+
+/// Runtime with a settable clock and in-memory dependencies
+pub struct MockRuntime {
+    now: Cell<u64>,
+    canister: Principal,
+    caller: Cell<Principal>,
+    cycles: Cell<u128>,
+    ledgers: RefCell<HashMap<Principal, MockLedger>>,
+    kongswap: RefCell<MockKongswap>,
+    lock_canisters: RefCell<Vec<(Principal, Principal)>>,
+    rejections: RefCell<HashMap<(Principal, String), String>>,
+}
+
+impl MockRuntime {
+    /// Install a fresh mock for this thread, with the clock at `now`
+    ///
+    /// The canister runs as the ICPI backend with 10T cycles; the caller is
+    /// anonymous until `set_caller`.
+    pub fn install(now: u64) -> Rc<MockRuntime> {
+        let runtime = Rc::new(MockRuntime {
+            now: Cell::new(now),
+            canister: Principal::from_text(ICPI_BACKEND_ID).unwrap(),
+            caller: Cell::new(Principal::anonymous()),
+            cycles: Cell::new(10_000_000_000_000),
+            ledgers: RefCell::new(HashMap::new()),
+            kongswap: RefCell::new(MockKongswap::default()),
+            lock_canisters: RefCell::new(Vec::new()),
+            rejections: RefCell::new(HashMap::new()),
+        });
+        super::set_runtime(runtime.clone());
+        runtime
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now.get()
+    }
+
+    pub fn set_time(&self, now: u64) {
+        self.now.set(now);
+    }
+
+    pub fn advance_seconds(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds * SECOND);
+    }
+
+    pub fn set_caller(&self, caller: Principal) {
+        self.caller.set(caller);
+    }
+
+    pub fn set_cycles(&self, cycles: u128) {
+        self.cycles.set(cycles);
+    }
+
+    /// Add a ledger with transfer fee `fee`
+    pub fn add_ledger(&self, ledger: Principal, fee: u64, minting_account: Option<Principal>) {
+        self.ledgers.borrow_mut().insert(ledger, MockLedger::new(fee, minting_account));
+    }
+
+    /// Credit `amount` to `owner` outside of any transfer
+    pub fn deposit(&self, ledger: Principal, owner: Principal, amount: u64) {
+        self.with_ledger(ledger, |l| l.credit(owner, &Nat::from(amount)));
+    }
+
+    /// Set an allowance from `owner` to `spender`, without charging a fee
+    pub fn approve(&self, ledger: Principal, owner: Principal, spender: Principal, amount: u64) {
+        self.with_ledger(ledger, |l| {
+            l.allowances.insert((owner, spender), (Nat::from(amount), None));
+        });
+    }
+
+    pub fn balance(&self, ledger: Principal, owner: Principal) -> Nat {
+        self.with_ledger(ledger, |l| l.balance(&owner))
+    }
+
+    pub fn total_supply(&self, ledger: Principal) -> Nat {
+        self.with_ledger(ledger, |l| l.total_supply())
+    }
+
+    pub fn with_ledger<T>(&self, ledger: Principal, f: impl FnOnce(&mut MockLedger) -> T) -> T {
+        let mut ledgers = self.ledgers.borrow_mut();
+        let ledger = ledgers.get_mut(&ledger).expect("mock ledger not added");
+        f(ledger)
+    }
+
+    /// List `symbol` on Kongswap at `price_e6` ckUSDT per whole token
+    pub fn list_token(&self, symbol: &str, ledger: Principal, decimals: u32, price_e6: u64) {
+        self.kongswap.borrow_mut().listings.insert(symbol.to_string(), Listing {
+            ledger,
+            decimals,
+            price_e6: Nat::from(price_e6),
+        });
+    }
+
+    /// Register a lock canister owned by `user` with its LP positions
+    pub fn add_lock_canister(&self, user: Principal, lock: Principal, positions: Vec<LpPosition>) {
+        self.lock_canisters.borrow_mut().push((user, lock));
+        self.kongswap.borrow_mut().positions.insert(lock.to_text(), positions);
+    }
+
+    /// Make calls of `method` on `canister` fail with `message`
+    pub fn reject(&self, canister: Principal, method: &str, message: &str) {
+        self.rejections.borrow_mut().insert((canister, method.to_string()), message.to_string());
+    }
+
+    fn dispatch(&self, canister: Principal, method: &str, args: &[u8]) -> CallResult<Vec<u8>> {
+        if let Some(message) = self.rejections.borrow().get(&(canister, method.to_string())) {
+            return Err((RejectionCode::CanisterReject, message.clone()));
+        }
+
+        let now = self.now.get();
+        if let Some(ledger) = self.ledgers.borrow_mut().get_mut(&canister) {
+            return ledger.handle(self.canister, method, args, now);
+        }
+        if canister == Principal::from_text(KONGSWAP_BACKEND_ID).unwrap() {
+            return self.handle_kongswap(method, args);
+        }
+        if canister == Principal::from_text(KONG_LOCKER_ID).unwrap() && method == "get_all_lock_canisters" {
+            return reply((self.lock_canisters.borrow().clone(),));
+        }
+        Err((RejectionCode::DestinationInvalid, format!("mock: no canister {} serving {}", canister, method)))
+    }
+
+    fn handle_kongswap(&self, method: &str, args: &[u8]) -> CallResult<Vec<u8>> {
+        match method {
+            "swap_amounts" => {
+                let (pay_symbol, pay_amount, receive_symbol): (String, Nat, String) = decode(method, args)?;
+                reply((self.kongswap.borrow().swap_amounts(pay_symbol, pay_amount, receive_symbol),))
+            }
+            "swap" => {
+                let (args,): (SwapArgs,) = decode(method, args)?;
+                reply((self.swap(args),))
+            }
+            "user_balances" => {
+                let (lock_id,): (String,) = decode(method, args)?;
+                reply((self.kongswap.borrow().user_balances(&lock_id),))
+            }
+            _ => Err(unknown_method(method)),
+        }
+    }
+
+    /// ICRC-2 swap: pull the pay token from the backend, send the receive token
+    fn swap(&self, args: SwapArgs) -> std::result::Result<SwapReply, String> {
+        let kongswap = Principal::from_text(KONGSWAP_BACKEND_ID).unwrap();
+        let now = self.now.get();
+        let (pay_ledger, receive_ledger, receive_amount, tx_id) = {
+            let mut kong = self.kongswap.borrow_mut();
+            let receive_amount = kong.quote(&args.pay_token, &args.pay_amount, &args.receive_token)?;
+            let pay_ledger = kong.listing(&args.pay_token)?.ledger;
+            let receive_ledger = kong.listing(&args.receive_token)?.ledger;
+            kong.next_tx += 1;
+            (pay_ledger, receive_ledger, receive_amount, kong.next_tx)
+        };
+        let receive_to = match &args.receive_address {
+            Some(address) => Principal::from_text(address).map_err(|e| e.to_string())?,
+            None => self.canister,
+        };
+
+        let pull = TransferFromArgs {
+            from: Account { owner: self.canister, subaccount: None },
+            to: Account { owner: kongswap, subaccount: None },
+            amount: args.pay_amount.clone(),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        };
+        self.with_ledger(pay_ledger, |l| l.transfer_from(kongswap, pull, now))
+            .map_err(|e| format!("transfer_from failed: {:?}", e))?;
+        self.with_ledger(receive_ledger, |l| l.credit(receive_to, &receive_amount));
+
+        let price = price_of(&args.pay_amount, &receive_amount);
+        Ok(SwapReply {
+            tx_id,
+            request_id: tx_id,
+            status: "Success".to_string(),
+            pay_chain: "IC".to_string(),
+            pay_address: self.canister.to_text(),
+            pay_symbol: args.pay_token,
+            pay_amount: args.pay_amount,
+            receive_chain: "IC".to_string(),
+            receive_address: receive_to.to_text(),
+            receive_symbol: args.receive_token,
+            receive_amount,
+            mid_price: price,
+            price,
+            slippage: 0.0,
+            txs: Vec::new(),
+            transfer_ids: Vec::new(),
+            claim_ids: Vec::new(),
+            ts: now,
+        })
+    }
+}
+
+impl Runtime for MockRuntime {
+    fn time(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn canister_id(&self) -> Principal {
+        self.canister
+    }
+
+    fn caller(&self) -> Principal {
+        self.caller.get()
+    }
+
+    fn cycles_balance(&self) -> u128 {
+        self.cycles.get()
+    }
+
+    fn instruction_counter(&self) -> u64 {
+        0
+    }
+
+    fn call_raw(&self, canister: Principal, method: &str, args: Vec<u8>) -> CallFuture {
+        Box::pin(std::future::ready(self.dispatch(canister, method, &args)))
+    }
+}
+
+fn decode<T: for<'a> ArgumentDecoder<'a>>(method: &str, args: &[u8]) -> CallResult<T> {
+    candid::decode_args(args)
+        .map_err(|e| (RejectionCode::CanisterError, format!("mock {}: failed to decode arguments: {}", method, e)))
+}
+
+fn reply<T: ArgumentEncoder>(values: T) -> CallResult<Vec<u8>> {
+    Ok(candid::encode_args(values).expect("mock reply encodes"))
+}
+
+fn unknown_method(method: &str) -> (RejectionCode, String) {
+    (RejectionCode::DestinationInvalid, format!("mock: method {} not found", method))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id])
+    }
+
+    #[test]
+    fn test_mock_ledger_round_trip() {
+        let runtime = MockRuntime::install(1_700_000_000 * SECOND);
+        let ledger = principal(1);
+        let alice = principal(2);
+        runtime.add_ledger(ledger, 10_000, None);
+        runtime.deposit(ledger, runtime.canister, 1_000_000);
+
+        let args = TransferArgs {
+            from_subaccount: None,
+            to: Account { owner: alice, subaccount: None },
+            amount: Nat::from(250_000u64),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        };
+        let (result,): (TransferResult,) =
+            futures::executor::block_on(super::super::call(ledger, "icrc1_transfer", (args,))).unwrap();
+        assert!(matches!(result, TransferResult::Ok(_)));
+        assert_eq!(runtime.balance(ledger, alice), Nat::from(250_000u64));
+        assert_eq!(runtime.balance(ledger, runtime.canister), Nat::from(740_000u64));
+
+        // Unknown methods are rejected like a missing canister method
+        let missing: CallResult<(Nat,)> =
+            futures::executor::block_on(super::super::call(ledger, "icrc9_nothing", ()));
+        assert!(matches!(missing, Err((RejectionCode::DestinationInvalid, _))));
+    }
+
+    #[test]
+    fn test_mock_reply_type_mismatch_fails_to_decode() {
+        let runtime = MockRuntime::install(SECOND);
+        let ledger = principal(1);
+        runtime.add_ledger(ledger, 10_000, None);
+
+        let result: CallResult<(String,)> = futures::executor::block_on(super::super::call(ledger, "icrc1_fee", ()));
+        let (code, message) = result.unwrap_err();
+        assert_eq!(code, RejectionCode::CanisterError);
+        assert!(message.contains("failed to decode"));
+    }
+}
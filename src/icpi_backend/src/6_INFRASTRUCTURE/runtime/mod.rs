@@ -0,0 +1,128 @@
+//! Runtime seam - clock, identity and inter-canister calls
+//!
+//! Everything the backend reads from the IC system API goes through this
+//! module instead of `ic_cdk` directly: the time, this canister's id, the
+//! message caller, the cycles balance, the instruction counter and
+//! inter-canister calls. On the IC these forward to `ic_cdk`; native tests
+//! install a `mock::MockRuntime` with a settable clock and in-memory
+//! ledgers, Kongswap and Kong Locker, so whole flows run under
+//! `futures::executor::block_on`.
+//!
+//! Calls are encoded and decoded with Candid on both sides of the seam, so
+//! a mock reply that doesn't match our types fails the same way a changed
+//! dependency does on the IC.
+
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::Principal;
+use ic_cdk::api::call::{CallResult, RejectionCode};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Reply of a raw inter-canister call
+pub type CallFuture = Pin<Box<dyn Future<Output = CallResult<Vec<u8>>>>>;
+
+/// The system API surface the backend uses
+pub trait Runtime {
+    /// Current time in nanoseconds since the epoch
+    fn time(&self) -> u64;
+    /// Principal of this canister
+    fn canister_id(&self) -> Principal;
+    /// Caller of the current message
+    fn caller(&self) -> Principal;
+    /// Cycles held by this canister
+    fn cycles_balance(&self) -> u128;
+    /// Instructions executed in the current message
+    fn instruction_counter(&self) -> u64;
+    /// Call `method` on `canister` with Candid-encoded `args`
+    fn call_raw(&self, canister: Principal, method: &str, args: Vec<u8>) -> CallFuture;
+}
+
+/// The IC system API
+struct IcRuntime;
+
+impl Runtime for IcRuntime {
+    fn time(&self) -> u64 {
+        ic_cdk::api::time()
+    }
+
+    fn canister_id(&self) -> Principal {
+        ic_cdk::id()
+    }
+
+    fn caller(&self) -> Principal {
+        ic_cdk::caller()
+    }
+
+    fn cycles_balance(&self) -> u128 {
+        ic_cdk::api::canister_balance128()
+    }
+
+    fn instruction_counter(&self) -> u64 {
+        ic_cdk::api::performance_counter(0)
+    }
+
+    fn call_raw(&self, canister: Principal, method: &str, args: Vec<u8>) -> CallFuture {
+        Box::pin(ic_cdk::api::call::call_raw(canister, method, args, 0))
+    }
+}
+
+thread_local! {
+    static RUNTIME: RefCell<Rc<dyn Runtime>> = RefCell::new(Rc::new(IcRuntime));
+}
+
+fn current() -> Rc<dyn Runtime> {
+    RUNTIME.with(|runtime| runtime.borrow().clone())
+}
+
+/// Replace the runtime for this thread (tests only)
+#[cfg(any(test, feature = "testing"))]
+pub fn set_runtime(runtime: Rc<dyn Runtime>) {
+    RUNTIME.with(|current| *current.borrow_mut() = runtime);
+}
+
+/// Current time in nanoseconds since the epoch
+pub fn time() -> u64 {
+    current().time()
+}
+
+/// Principal of this canister
+pub fn canister_id() -> Principal {
+    current().canister_id()
+}
+
+/// Caller of the current message
+pub fn caller() -> Principal {
+    current().caller()
+}
+
+/// Cycles held by this canister
+pub fn cycles_balance() -> u128 {
+    current().cycles_balance()
+}
+
+/// Instructions executed in the current message
+pub fn instruction_counter() -> u64 {
+    current().instruction_counter()
+}
+
+/// Call `method` on `id`, with the same signature and errors as `ic_cdk::call`
+///
+/// Encoding failures and replies that don't decode as `R` are reported as
+/// `CanisterError` rejections; the decode message contains "failed to
+/// decode", which the dependency probes rely on.
+pub async fn call<T: ArgumentEncoder, R: for<'a> ArgumentDecoder<'a>>(
+    id: Principal,
+    method: &str,
+    args: T,
+) -> CallResult<R> {
+    let args = candid::encode_args(args)
+        .map_err(|e| (RejectionCode::CanisterError, format!("failed to encode arguments: {}", e)))?;
+    let reply = current().call_raw(id, method, args).await?;
+    candid::decode_args(&reply)
+        .map_err(|e| (RejectionCode::CanisterError, format!("failed to decode canister response: {}", e)))
+}
@@ -0,0 +1,163 @@
+//! Secondary copy of the upgrade state in raw stable memory
+//!
+//! Written only when the primary `stable_save` fails. The region at
+//! `STABLE_FALLBACK_OFFSET` holds an 8-byte magic, the payload length
+//! (u64 LE), its CRC-32 (u32 LE) and the Candid-encoded payload. Its pages
+//! are reserved while the canister runs, so writing it needs no growth.
+
+use ic_stable_structures::Memory;
+use crate::infrastructure::{Result, IcpiError, SystemError};
+
+const MAGIC: &[u8; 8] = b"ICPIFB01";
+pub const HEADER_LEN: u64 = 20;
+const WASM_PAGE_SIZE: u64 = 65_536;
+
+/// CRC-32 (IEEE) of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Header followed by the payload, as laid out in stable memory
+pub fn encode_region(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN as usize + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&crc32(payload).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Write `payload` at `offset` as a checksummed region
+pub fn write_region<M: Memory>(memory: &M, offset: u64, payload: &[u8]) -> Result<()> {
+    write_at(memory, offset, &encode_region(payload))
+}
+
+/// Write raw `bytes` at `offset`, growing the memory as needed
+pub fn write_at<M: Memory>(memory: &M, offset: u64, bytes: &[u8]) -> Result<()> {
+    reserve_region(memory, offset, bytes.len() as u64)?;
+    memory.write(offset, bytes);
+    Ok(())
+}
+
+/// Grow the memory to cover `len` bytes at `offset`, writing nothing
+pub fn reserve_region<M: Memory>(memory: &M, offset: u64, len: u64) -> Result<()> {
+    let needed_pages = (offset + len).div_ceil(WASM_PAGE_SIZE);
+    let current_pages = memory.size();
+    if needed_pages > current_pages && memory.grow(needed_pages - current_pages) < 0 {
+        return Err(IcpiError::Other(format!(
+            "Could not grow stable memory to {} pages", needed_pages
+        )));
+    }
+    Ok(())
+}
+
+/// Read back a payload written by `write_region`
+///
+/// Fails if no region was written or the checksum doesn't match.
+pub fn read_region<M: Memory>(memory: &M, offset: u64) -> Result<Vec<u8>> {
+    let available = memory.size() * WASM_PAGE_SIZE;
+    if available < offset + HEADER_LEN {
+        return Err(IcpiError::Other("No fallback state in stable memory".to_string()));
+    }
+
+    let mut header = [0u8; HEADER_LEN as usize];
+    memory.read(offset, &mut header);
+    if header[..8] != MAGIC[..] {
+        return Err(IcpiError::Other("No fallback state in stable memory".to_string()));
+    }
+    let len = u64::from_le_bytes(header[8..16].try_into().expect("8-byte slice"));
+    let checksum = u32::from_le_bytes(header[16..20].try_into().expect("4-byte slice"));
+
+    let start = offset + HEADER_LEN;
+    if start.checked_add(len).map_or(true, |end| end > available) {
+        return Err(IcpiError::System(SystemError::StateCorrupted {
+            reason: format!("Fallback state length {} runs past stable memory", len),
+        }));
+    }
+    let mut payload = vec![0u8; len as usize];
+    memory.read(start, &mut payload);
+
+    let actual = crc32(&payload);
+    if actual != checksum {
+        return Err(IcpiError::System(SystemError::StateCorrupted {
+            reason: format!("Fallback state checksum {:08x} doesn't match header {:08x}", actual, checksum),
+        }));
+    }
+    Ok(payload)
+}
+
+/// Erase the region's magic so a stale copy is never restored
+pub fn clear_region<M: Memory>(memory: &M, offset: u64) {
+    if memory.size() * WASM_PAGE_SIZE >= offset + HEADER_LEN {
+        memory.write(offset, &[0u8; 8]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_stable_structures::VectorMemory;
+
+    const OFFSET: u64 = 3 * WASM_PAGE_SIZE + 100;
+
+    #[test]
+    fn test_crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_region_round_trip() {
+        let memory = VectorMemory::default();
+        assert!(read_region(&memory, OFFSET).is_err());
+
+        let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        write_region(&memory, OFFSET, &payload).unwrap();
+        assert_eq!(read_region(&memory, OFFSET).unwrap(), payload);
+
+        // A shorter rewrite replaces the longer one
+        write_region(&memory, OFFSET, b"small").unwrap();
+        assert_eq!(read_region(&memory, OFFSET).unwrap(), b"small".to_vec());
+
+        clear_region(&memory, OFFSET);
+        assert!(read_region(&memory, OFFSET).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_payload_is_rejected() {
+        let memory = VectorMemory::default();
+        write_region(&memory, OFFSET, b"pending mints").unwrap();
+
+        // Flip one payload byte
+        let mut byte = [0u8; 1];
+        memory.read(OFFSET + HEADER_LEN + 3, &mut byte);
+        memory.write(OFFSET + HEADER_LEN + 3, &[byte[0] ^ 0x01]);
+
+        match read_region(&memory, OFFSET) {
+            Err(IcpiError::System(SystemError::StateCorrupted { reason })) => {
+                assert!(reason.contains("checksum"));
+            }
+            other => panic!("Expected StateCorrupted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_oversized_length_is_rejected() {
+        let memory = VectorMemory::default();
+        write_region(&memory, OFFSET, b"pending mints").unwrap();
+        memory.write(OFFSET + 8, &u64::MAX.to_le_bytes());
+
+        assert!(matches!(
+            read_region(&memory, OFFSET),
+            Err(IcpiError::System(SystemError::StateCorrupted { .. }))
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+//! Timer registry
+//!
+//! Every recurring timer is registered through here so that calling a
+//! `start_*_timer` function twice (init, post_upgrade, admin restarts)
+//! replaces the live timer instead of adding a second one firing the
+//! same job.
+
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use ic_cdk_timers::TimerId;
+
+/// Background jobs driven by timers
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimerKind {
+    Rebalancing,
+    TvlRefresh,
+    TvlGeneration,
+    StrayDepositSweep,
+    MintCleanup,
+    CyclesSampling,
+    CachePrewarm,
+    BurnTransferRetry,
+    SaveProbe,
+}
+
+/// A registered timer, as reported by `get_timers`
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TimerInfo {
+    pub kind: TimerKind,
+    pub interval_seconds: u64,
+    pub registered_at: u64,
+}
+
+/// Timer ids per kind; generic over the id so the bookkeeping is testable
+struct TimerRegistry<Id> {
+    entries: BTreeMap<TimerKind, (Vec<Id>, TimerInfo)>,
+}
+
+impl<Id> Default for TimerRegistry<Id> {
+    fn default() -> Self {
+        Self { entries: BTreeMap::new() }
+    }
+}
+
+impl<Id> TimerRegistry<Id> {
+    /// Remove a kind, returning the ids that must be cleared
+    fn take(&mut self, kind: TimerKind) -> Vec<Id> {
+        self.entries.remove(&kind).map(|(ids, _)| ids).unwrap_or_default()
+    }
+
+    fn insert(&mut self, ids: Vec<Id>, info: TimerInfo) {
+        self.entries.insert(info.kind, (ids, info));
+    }
+
+    fn contains(&self, kind: TimerKind) -> bool {
+        self.entries.contains_key(&kind)
+    }
+
+    fn list(&self) -> Vec<TimerInfo> {
+        self.entries.values().map(|(_, info)| info.clone()).collect()
+    }
+}
+
+thread_local! {
+    static TIMERS: RefCell<TimerRegistry<TimerId>> = RefCell::new(TimerRegistry::default());
+}
+
+/// Register a recurring timer, cancelling any existing timer of the same kind
+///
+/// With `run_immediately`, `job` also runs once as soon as possible.
+pub fn register_interval<F>(kind: TimerKind, interval: Duration, run_immediately: bool, job: F)
+where
+    F: Fn() + Clone + 'static,
+{
+    let previous = TIMERS.with(|t| t.borrow_mut().take(kind));
+    if !previous.is_empty() {
+        ic_cdk::println!("🕐 Replacing existing {:?} timer", kind);
+        for id in previous {
+            ic_cdk_timers::clear_timer(id);
+        }
+    }
+
+    let mut ids = Vec::with_capacity(2);
+    if run_immediately {
+        ids.push(ic_cdk_timers::set_timer(Duration::ZERO, job.clone()));
+    }
+    ids.push(ic_cdk_timers::set_timer_interval(interval, job));
+
+    let info = TimerInfo {
+        kind,
+        interval_seconds: interval.as_secs(),
+        registered_at: crate::infrastructure::runtime::time(),
+    };
+    TIMERS.with(|t| t.borrow_mut().insert(ids, info));
+}
+
+/// Whether a timer of this kind is registered
+pub fn is_registered(kind: TimerKind) -> bool {
+    TIMERS.with(|t| t.borrow().contains(kind))
+}
+
+/// All registered timers
+pub fn get_timers() -> Vec<TimerInfo> {
+    TIMERS.with(|t| t.borrow().list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(kind: TimerKind, interval_seconds: u64) -> TimerInfo {
+        TimerInfo { kind, interval_seconds, registered_at: 0 }
+    }
+
+    #[test]
+    fn test_reregistration_replaces_existing_timer() {
+        let mut registry: TimerRegistry<u32> = TimerRegistry::default();
+
+        assert!(registry.take(TimerKind::Rebalancing).is_empty());
+        registry.insert(vec![1], info(TimerKind::Rebalancing, 3600));
+        registry.insert(vec![2, 3], info(TimerKind::TvlRefresh, 600));
+
+        // Second registration hands back the live ids to clear
+        assert_eq!(registry.take(TimerKind::Rebalancing), vec![1]);
+        registry.insert(vec![4], info(TimerKind::Rebalancing, 3600));
+
+        let timers = registry.list();
+        assert_eq!(timers.len(), 2);
+        assert_eq!(timers.iter().filter(|t| t.kind == TimerKind::Rebalancing).count(), 1);
+        assert_eq!(registry.take(TimerKind::Rebalancing), vec![4]);
+        assert_eq!(registry.take(TimerKind::TvlRefresh), vec![2, 3]);
+        assert!(!registry.contains(TimerKind::TvlRefresh));
+    }
+}
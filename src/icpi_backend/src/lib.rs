@@ -36,61 +36,233 @@ use infrastructure_6 as infrastructure;
 // Types module (existing)
 mod types;
 
+/// Backend internals for the scenario tests under `tests/`
+///
+/// Only built with the `testing` feature, alongside `runtime::mock`.
+#[cfg(feature = "testing")]
+pub mod testing {
+    pub use crate::_1_CRITICAL_OPERATIONS::{burning, minting, rebalancing};
+    pub use crate::_1_CRITICAL_OPERATIONS::{burn_icpi, complete_mint, initiate_mint, perform_rebalance};
+    pub use crate::_3_KONG_LIQUIDITY::tvl;
+    pub use crate::_5_INFORMATIONAL::{display, history};
+    pub use crate::infrastructure::{constants, runtime, Result};
+    pub use crate::types::TrackedToken;
+}
+
 use candid::{candid_method, Nat, Principal};
 use ic_cdk::{init, pre_upgrade, post_upgrade, query, update};
-use infrastructure::{Result, IcpiError};
+use infrastructure::{ApiError, ApiResult as Result, IcpiError};
 
 // ===== PUBLIC API =====
 
 #[update]
 #[candid_method(update)]
-async fn initiate_mint(amount: Nat) -> Result<String> {
-    let caller = ic_cdk::caller();
-    _1_CRITICAL_OPERATIONS::minting::initiate_mint(caller, amount).await
+async fn initiate_mint(amount: Nat, memo: Option<String>) -> Result<String> {
+    let caller = infrastructure::runtime::caller();
+    let (request_id, result) = infrastructure::logging::run_request(
+        "mint",
+        _1_CRITICAL_OPERATIONS::minting::initiate_mint(caller, amount, memo),
+    ).await;
+    infrastructure::logging::record_result(infrastructure::logging::Subsystem::Mint, &result);
+    result.map_err(|e| ApiError::from(e).with_request_id(request_id))
 }
 
 #[update]
 #[candid_method(update)]
 async fn complete_mint(mint_id: String) -> Result<Nat> {
-    let caller = ic_cdk::caller();
-    _1_CRITICAL_OPERATIONS::minting::complete_mint(caller, mint_id).await
+    let caller = infrastructure::runtime::caller();
+    let (request_id, result) = infrastructure::logging::run_request(
+        "mint",
+        _1_CRITICAL_OPERATIONS::minting::complete_mint(caller, mint_id),
+    ).await;
+    infrastructure::logging::record_result(infrastructure::logging::Subsystem::Mint, &result);
+    result.map_err(|e| ApiError::from(e).with_request_id(request_id))
+}
+
+/// Burn ICPI for a proportional share of the portfolio
+///
+/// `max_tokens` caps the distinct tokens received: all but the most
+/// valuable are swapped to ckUSDT first. None delivers every token.
+#[update]
+#[candid_method(update)]
+async fn burn_icpi(amount: Nat, memo: Option<String>, max_tokens: Option<u32>) -> Result<_1_CRITICAL_OPERATIONS::burning::BurnResult> {
+    let caller = infrastructure::runtime::caller();
+    let (request_id, result) = infrastructure::logging::run_request(
+        "burn",
+        _1_CRITICAL_OPERATIONS::burning::burn_icpi(caller, amount, memo, max_tokens),
+    ).await;
+    infrastructure::logging::record_result(infrastructure::logging::Subsystem::Burn, &result);
+    result.map_err(|e| ApiError::from(e).with_request_id(request_id))
+}
+
+/// Burn ICPI and receive the redemption as ckUSDT in one call
+///
+/// Needs the same approvals as `burn_icpi`. If the quoted legs can't
+/// guarantee `min_ckusdt_out`, nothing is swapped and tokens are delivered
+/// in kind.
+#[update]
+#[candid_method(update)]
+async fn exit_position(amount: Nat, min_ckusdt_out: Nat) -> Result<_1_CRITICAL_OPERATIONS::burning::exit::ExitResult> {
+    let caller = infrastructure::runtime::caller();
+    let (request_id, result) = infrastructure::logging::run_request(
+        "burn",
+        _1_CRITICAL_OPERATIONS::burning::exit::exit_position(caller, amount, min_ckusdt_out),
+    ).await;
+    infrastructure::logging::record_result(infrastructure::logging::Subsystem::Burn, &result);
+    result.map_err(|e| ApiError::from(e).with_request_id(request_id))
+}
+
+/// The caller's mints; admins may pass another principal as `target`
+#[query]
+#[candid_method(query)]
+fn get_my_mints(target: Option<Principal>) -> Result<Vec<_1_CRITICAL_OPERATIONS::minting::PendingMint>> {
+    let user = infrastructure::resolve_user_target(target)?;
+    Ok(_1_CRITICAL_OPERATIONS::minting::get_user_mints(&user))
+}
+
+/// The caller's burns; admins may pass another principal as `target`
+#[query]
+#[candid_method(query)]
+fn get_my_burns(target: Option<Principal>) -> Result<Vec<_1_CRITICAL_OPERATIONS::burning::burn_history::BurnRecord>> {
+    let user = infrastructure::resolve_user_target(target)?;
+    Ok(_1_CRITICAL_OPERATIONS::burning::burn_history::get_user_burns(&user))
+}
+
+/// The caller's lifetime mint and burn totals; None below the tracking threshold
+///
+/// Admins may pass another principal as `target`.
+#[query]
+#[candid_method(query)]
+fn get_my_activity(target: Option<Principal>) -> Result<Option<_5_INFORMATIONAL::user_activity::UserActivity>> {
+    let user = infrastructure::resolve_user_target(target)?;
+    Ok(_5_INFORMATIONAL::user_activity::get_user_activity(&user))
+}
+
+/// The `n` most active principals and the aggregate of untracked ones (admin only)
+#[query]
+#[candid_method(query)]
+fn get_top_activity(n: u32) -> Result<_5_INFORMATIONAL::user_activity::TopActivity> {
+    infrastructure::require_admin()?;
+    Ok(_5_INFORMATIONAL::user_activity::get_top_activity(n))
+}
+
+/// Why the caller's last mint and burn failed, if they did
+///
+/// Admins may pass another principal as `target`.
+#[query]
+#[candid_method(query)]
+fn get_my_last_failures(target: Option<Principal>) -> Result<_1_CRITICAL_OPERATIONS::operation_status::last_failure::LastFailures> {
+    let user = infrastructure::resolve_user_target(target)?;
+    Ok(_1_CRITICAL_OPERATIONS::operation_status::last_failure::get_last_failures(&user))
+}
+
+/// Redemptions owed to the caller from burns that stopped early
+///
+/// Admins may pass another principal as `target`.
+#[query]
+#[candid_method(query)]
+fn get_my_redemption_claims(target: Option<Principal>) -> Result<Vec<_1_CRITICAL_OPERATIONS::burning::claims::RedemptionClaim>> {
+    let user = infrastructure::resolve_user_target(target)?;
+    Ok(_1_CRITICAL_OPERATIONS::burning::claims::get_user_claims(&user))
 }
 
+/// Transfer the caller's outstanding redemption claims
 #[update]
 #[candid_method(update)]
-async fn burn_icpi(amount: Nat) -> Result<_1_CRITICAL_OPERATIONS::burning::BurnResult> {
-    let caller = ic_cdk::caller();
-    _1_CRITICAL_OPERATIONS::burning::burn_icpi(caller, amount).await
+async fn claim_redemptions() -> Result<_1_CRITICAL_OPERATIONS::burning::claims::ClaimResult> {
+    let caller = infrastructure::runtime::caller();
+    let (request_id, result) = infrastructure::logging::run_request(
+        "burn",
+        _1_CRITICAL_OPERATIONS::burning::claims::claim_redemptions(caller),
+    ).await;
+    infrastructure::logging::record_result(infrastructure::logging::Subsystem::Burn, &result);
+    result.map_err(|e| ApiError::from(e).with_request_id(request_id))
 }
 
 #[update]
 #[candid_method(update)]
-async fn perform_rebalance() -> Result<String> {
+async fn perform_rebalance(override_stale_targets: Option<bool>) -> Result<String> {
     require_admin()?;
-    _1_CRITICAL_OPERATIONS::rebalancing::perform_rebalance().await
+    _1_CRITICAL_OPERATIONS::rebalancing::perform_rebalance(override_stale_targets.unwrap_or(false))
+        .await
+        .map_err(ApiError::from)
 }
 
 #[update]
 #[candid_method(update)]
 async fn trigger_manual_rebalance() -> Result<String> {
     require_admin()?;
-    _1_CRITICAL_OPERATIONS::rebalancing::trigger_manual_rebalance().await
+    _1_CRITICAL_OPERATIONS::rebalancing::trigger_manual_rebalance().await.map_err(ApiError::from)
 }
 
 #[update]
 #[candid_method(update)]
 async fn get_index_state() -> Result<types::portfolio::IndexState> {
-    _5_INFORMATIONAL::display::get_index_state_cached().await
+    infrastructure::access_control::require_read_access()?;
+    _5_INFORMATIONAL::display::refresh_index_state().await.map_err(ApiError::from)
 }
 
+/// Index state from the cache while fresh (kept warm by the cache pre-warmer)
+///
 /// NOTE (PR #8 Review): Reviewer suggested this should be #[query] for cached reads
-/// However, the underlying implementation makes inter-canister calls (get_portfolio_state_uncached)
-/// which requires #[update]. The "cached" name is misleading - there's no actual cache implementation yet.
-/// TODO: Implement real caching in Phase 2/3, then convert to #[query]
+/// However, a cache miss recomputes through inter-canister calls (get_portfolio_state_uncached)
+/// which requires #[update].
 #[update]
 #[candid_method(update)]
 async fn get_index_state_cached() -> Result<types::portfolio::IndexState> {
-    _5_INFORMATIONAL::display::get_index_state_cached().await
+    infrastructure::access_control::require_read_access()?;
+    _5_INFORMATIONAL::display::get_index_state_cached().await.map_err(ApiError::from)
+}
+
+/// Compact NAV/value/24h-change payload for frequent polling
+///
+/// Served from the last computed index state; `is_stale` is set when that
+/// state is older than its cache TTL.
+#[query]
+#[candid_method(query)]
+fn get_ticker() -> Result<types::portfolio::Ticker> {
+    _5_INFORMATIONAL::get_ticker().map_err(ApiError::from)
+}
+
+/// NAV and portfolio value quoted in `quote_symbol` (`ckUSDT` or `ICP`)
+///
+/// Converts the ticker's NAV through a cached Kongswap rate; an update
+/// because refreshing the rate calls Kongswap.
+#[update]
+#[candid_method(update)]
+async fn get_nav_quote(quote_symbol: String) -> Result<_5_INFORMATIONAL::nav_quotes::NavQuote> {
+    infrastructure::access_control::require_read_access()?;
+    _5_INFORMATIONAL::nav_quotes::get_nav_quote(&quote_symbol).await.map_err(ApiError::from)
+}
+
+/// All-time high/low NAV and drawdown figures (e8 NAV, bps)
+#[query]
+#[candid_method(query)]
+fn get_nav_extremes() -> Option<_5_INFORMATIONAL::history::NavExtremes> {
+    _5_INFORMATIONAL::history::get_nav_extremes()
+}
+
+/// A token's portfolio weight over the snapshot history, oldest first
+///
+/// Returns a page of points and the total number of points; a point with
+/// 0% means the token wasn't held at that snapshot.
+#[query]
+#[candid_method(query)]
+fn get_token_weight_history(
+    symbol: String,
+    offset: u64,
+    limit: u64,
+) -> Result<(Vec<_5_INFORMATIONAL::history::TokenWeightPoint>, u64)> {
+    _5_INFORMATIONAL::history::get_token_weight_history(&symbol, offset, limit).map_err(ApiError::from)
+}
+
+/// Number of accounts holding ICPI, rebuilt from the ledger's transaction log
+///
+/// Cached for an hour; an update because counting calls the ledger.
+#[update]
+#[candid_method(update)]
+async fn get_holder_count() -> Result<_5_INFORMATIONAL::holders::HolderCount> {
+    _5_INFORMATIONAL::holders::get_holder_count().await.map_err(ApiError::from)
 }
 
 #[query]
@@ -99,12 +271,128 @@ fn get_health_status() -> types::common::HealthStatus {
     _5_INFORMATIONAL::health::get_health_status()
 }
 
+/// Crate version, git revision and candid interface hash of the running wasm
+#[query]
+#[candid_method(query)]
+fn get_api_info() -> _5_INFORMATIONAL::build_info::ApiInfo {
+    _5_INFORMATIONAL::build_info::get_api_info()
+}
+
+/// True if the deployed candid interface hash equals `expected_hash` (hex SHA-256)
+#[query]
+#[candid_method(query)]
+fn verify_interface(expected_hash: String) -> bool {
+    _5_INFORMATIONAL::build_info::verify_interface(&expected_hash)
+}
+
 #[query]
 #[candid_method(query)]
 fn get_tracked_tokens() -> Vec<String> {
     _5_INFORMATIONAL::health::get_tracked_tokens()
 }
 
+/// Run a rebalance cycle now if the portfolio has drifted (anyone may call)
+///
+/// Returns `NotNeeded` with the reason when the last cycle is recent, no
+/// deviation exceeds the deadband, or another poke ran within 15 minutes.
+#[update]
+#[candid_method(update)]
+async fn poke_rebalance() -> Result<_1_CRITICAL_OPERATIONS::rebalancing::poke::PokeOutcome> {
+    _1_CRITICAL_OPERATIONS::rebalancing::poke::poke_rebalance(infrastructure::runtime::caller()).await.map_err(ApiError::from)
+}
+
+/// Index table in one call: per tracked token metadata, balance, value,
+/// current and target allocation, and spot price
+#[update]
+#[candid_method(update)]
+async fn list_tracked_tokens_detailed() -> Result<Vec<types::tokens::TokenDetail>> {
+    infrastructure::access_control::require_read_access()?;
+    _5_INFORMATIONAL::display::list_tracked_tokens_detailed().await.map_err(ApiError::from)
+}
+
+/// Current index composition as CSV (token, balance, usd_value, percentage, target)
+///
+/// Update rather than query: building the index state makes inter-canister calls.
+#[update]
+#[candid_method(update)]
+async fn get_index_composition_csv() -> Result<String> {
+    infrastructure::access_control::require_read_access()?;
+    _5_INFORMATIONAL::display::get_index_composition_csv().await.map_err(ApiError::from)
+}
+
+/// Swap token positions worth less than the dust threshold to ckUSDT (admin only)
+#[update]
+#[candid_method(update)]
+async fn sweep_dust() -> Result<_1_CRITICAL_OPERATIONS::rebalancing::dust_sweep::DustSweepReport> {
+    require_admin()?;
+    infrastructure::log_admin_action("DUST_SWEEP_TRIGGERED".to_string());
+    _1_CRITICAL_OPERATIONS::rebalancing::dust_sweep::sweep_dust().await.map_err(ApiError::from)
+}
+
+/// Get the dust sweep log (admin only)
+#[query]
+#[candid_method(query)]
+fn get_dust_sweep_log() -> Result<Vec<_1_CRITICAL_OPERATIONS::rebalancing::dust_sweep::DustSweepRecord>> {
+    require_admin()?;
+    Ok(_1_CRITICAL_OPERATIONS::rebalancing::dust_sweep::get_sweep_log())
+}
+
+/// ckUSDT reserve accounting and unattributed deposit events (admin only)
+#[query]
+#[candid_method(query)]
+fn get_ckusdt_reserves() -> Result<_2_CRITICAL_DATA::ckusdt_reserves::ReserveState> {
+    require_admin()?;
+    Ok(_2_CRITICAL_DATA::ckusdt_reserves::get_state())
+}
+
+/// Refund quarantined ckUSDT to its sender (admin only)
+///
+/// `to` receives `amount`; the ledger fee is also deducted from quarantine.
+#[update]
+#[candid_method(update)]
+async fn admin_refund_unattributed(to: Principal, amount: Nat) -> Result<Nat> {
+    require_admin()?;
+    infrastructure::log_admin_action(format!("UNATTRIBUTED_REFUND: {} ckUSDT to {}", amount, to));
+    _1_CRITICAL_OPERATIONS::stray_deposits::refund_unattributed(to, amount).await.map_err(ApiError::from)
+}
+
+/// Forward collected mint/burn fees out of the backend (admin only)
+///
+/// `to` receives `amount`; the ledger fee is also deducted from the fees.
+#[update]
+#[candid_method(update)]
+async fn admin_forward_fees(to: Principal, amount: Nat) -> Result<Nat> {
+    require_admin()?;
+    infrastructure::log_admin_action(format!("FEES_FORWARD: {} ckUSDT to {}", amount, to));
+    _1_CRITICAL_OPERATIONS::stray_deposits::forward_fees(to, amount).await.map_err(ApiError::from)
+}
+
+/// Gross ckUSDT balance and the net amount counted in TVL
+#[update]
+#[candid_method(update)]
+async fn get_ckusdt_balance_breakdown() -> Result<_2_CRITICAL_DATA::ckusdt_reserves::CkusdtBalance> {
+    infrastructure::access_control::require_read_access()?;
+    _2_CRITICAL_DATA::token_queries::get_ckusdt_breakdown().await.map_err(ApiError::from)
+}
+
+/// Release quarantined ckUSDT into the portfolio (admin only)
+#[update]
+#[candid_method(update)]
+fn admin_release_unattributed(amount: Nat) -> Result<()> {
+    require_admin()?;
+    _1_CRITICAL_OPERATIONS::stray_deposits::release_unattributed(amount.clone())?;
+    infrastructure::log_admin_action(format!("UNATTRIBUTED_RELEASE: {} ckUSDT", amount));
+    Ok(())
+}
+
+/// Explain the next rebalance action: deviations, ckUSDT, thresholds and reasoning
+#[update]
+#[candid_method(update)]
+async fn get_rebalance_action_explanation() -> Result<_1_CRITICAL_OPERATIONS::rebalancing::RebalanceExplanation> {
+    infrastructure::access_control::require_read_access()?;
+    _1_CRITICAL_OPERATIONS::rebalancing::get_rebalance_action_explanation().await.map_err(ApiError::from)
+}
+
 #[query]
 #[candid_method(query)]
 fn get_rebalancer_status() -> _1_CRITICAL_OPERATIONS::rebalancing::RebalancerStatus {
@@ -118,11 +406,43 @@ fn get_trade_history() -> Vec<_1_CRITICAL_OPERATIONS::rebalancing::RebalanceReco
     _1_CRITICAL_OPERATIONS::rebalancing::get_full_trade_history()
 }
 
-/// Get paginated trade history
+/// Export a page of the full trade history, for migration via `import_trade_history`
+///
+/// Pages are capped at 500 records; follow `next_offset` until None.
+#[query]
+#[candid_method(query)]
+fn export_trade_history_chunked(offset: u64, limit: u64) -> _1_CRITICAL_OPERATIONS::rebalancing::TradeHistoryChunk {
+    _1_CRITICAL_OPERATIONS::rebalancing::export_trade_history_chunked(offset, limit)
+}
+
+/// Import exported trade history (admin only, system must be paused)
+#[update]
+#[candid_method(update)]
+fn import_trade_history(
+    records: Vec<_1_CRITICAL_OPERATIONS::rebalancing::RebalanceRecord>,
+    mode: _1_CRITICAL_OPERATIONS::rebalancing::HistoryImportMode,
+) -> Result<u64> {
+    require_admin()?;
+    let count = records.len();
+    let total = _1_CRITICAL_OPERATIONS::rebalancing::import_trade_history(records, mode)?;
+    infrastructure::log_admin_action(format!("TRADE_HISTORY_IMPORT: {} records ({:?}), {} total", count, mode, total));
+    Ok(total)
+}
+
+/// Get paginated trade history, optionally only the records matching `filter`
+///
+/// The count returned is of the matching records.
 #[query]
 #[candid_method(query)]
-fn get_trade_history_paginated(offset: u64, limit: u64) -> (Vec<_1_CRITICAL_OPERATIONS::rebalancing::RebalanceRecord>, u64) {
-    _1_CRITICAL_OPERATIONS::rebalancing::get_trade_history_paginated(offset, limit)
+fn get_trade_history_paginated(
+    offset: u64,
+    limit: u64,
+    filter: Option<_1_CRITICAL_OPERATIONS::rebalancing::TradeHistoryFilter>,
+) -> (Vec<_1_CRITICAL_OPERATIONS::rebalancing::RebalanceRecord>, u64) {
+    match filter {
+        Some(filter) => _1_CRITICAL_OPERATIONS::rebalancing::get_trade_history_filtered(&filter, offset, limit),
+        None => _1_CRITICAL_OPERATIONS::rebalancing::get_trade_history_paginated(offset, limit),
+    }
 }
 
 #[update]
@@ -132,7 +452,7 @@ fn clear_caches() -> Result<String> {
     require_admin()?;
 
     _5_INFORMATIONAL::cache::clear_all_caches();
-    ic_cdk::println!("Admin {} cleared all caches", ic_cdk::caller());
+    ic_cdk::println!("Admin {} cleared all caches", infrastructure::runtime::caller());
     Ok("Caches cleared".to_string())
 }
 
@@ -150,35 +470,126 @@ fn clear_caches() -> Result<String> {
 // ===== ADDITIONAL API ENDPOINTS =====
 
 /// BUGFIX (PR #8 Review): Use getter function instead of direct PENDING_MINTS access
+///
+/// Only the mint's owner or an admin may check it.
 #[query]
 #[candid_method(query)]
 fn check_mint_status(mint_id: String) -> Result<_1_CRITICAL_OPERATIONS::minting::MintStatus> {
-    _1_CRITICAL_OPERATIONS::minting::mint_state::get_mint_status(&mint_id)?
-        .ok_or(infrastructure::IcpiError::Other(format!("Mint {} not found", mint_id)))
+    let mint = _1_CRITICAL_OPERATIONS::minting::mint_state::get_pending_mint(&mint_id)?
+        .ok_or_else(|| IcpiError::Other(format!("Mint {} not found", mint_id)))?;
+    infrastructure::require_self_or_admin(mint.user)?;
+    Ok(mint.status)
+}
+
+/// Caller's in-flight mint/burn state and any conflict blocking a new operation
+#[query]
+#[candid_method(query)]
+fn get_operation_status() -> _1_CRITICAL_OPERATIONS::operation_status::OperationStatus {
+    _1_CRITICAL_OPERATIONS::operation_status::get_operation_status(&infrastructure::runtime::caller())
+}
+
+/// Current mint (ckUSDT e6) and burn (ICPI e8) limits for a user
+///
+/// Update call: the burn limit depends on the user's ICPI balance and supply.
+#[update]
+#[candid_method(update)]
+async fn get_user_limits(user: Principal) -> Result<_5_INFORMATIONAL::limits::UserLimits> {
+    infrastructure::access_control::require_read_access()?;
+    infrastructure::require_self_or_admin(user)?;
+    _5_INFORMATIONAL::limits::get_user_limits(user).await.map_err(ApiError::from)
+}
+
+/// Minimum mint deposit, burn, rebalance trade and per-token redemption
+#[query]
+#[candid_method(query)]
+fn get_minimums() -> _5_INFORMATIONAL::limits::Minimums {
+    _5_INFORMATIONAL::limits::get_minimums()
+}
+
+/// Raw amount of `symbol` (ICPI or a tracked token) as a decimal string
+#[query]
+#[candid_method(query)]
+fn format_amount(symbol: String, raw: Nat) -> Result<String> {
+    _5_INFORMATIONAL::amounts::format_amount(&symbol, &raw).map_err(ApiError::from)
+}
+
+/// Decimal string of `symbol` as a raw amount; rejects inexact input
+#[query]
+#[candid_method(query)]
+fn parse_amount(symbol: String, human: String) -> Result<Nat> {
+    _5_INFORMATIONAL::amounts::parse_amount(&symbol, &human).map_err(ApiError::from)
+}
+
+/// `icrc2_approve` calls a mint or burn of `amount` needs, ledger fees included
+#[query]
+#[candid_method(query)]
+fn get_required_approvals(
+    operation: _5_INFORMATIONAL::approvals::ApprovalOperation,
+    amount: Nat,
+) -> Result<Vec<_5_INFORMATIONAL::approvals::ApprovalInstruction>> {
+    _5_INFORMATIONAL::approvals::get_required_approvals(operation, amount).map_err(ApiError::from)
+}
+
+/// Estimate how minting with `amount` ckUSDT (e6) would move the index
+///
+/// Read-only: post-mint supply, NAV, the depositor's share, the ckUSDT
+/// reserve and the rebalance buys it would trigger. Figures are estimates
+/// tied to `snapshot_at`.
+#[update]
+#[candid_method(update)]
+async fn simulate_mint_impact(amount: Nat) -> Result<_1_CRITICAL_OPERATIONS::minting::impact::MintImpact> {
+    infrastructure::access_control::require_read_access()?;
+    _1_CRITICAL_OPERATIONS::minting::impact::simulate_mint_impact(amount).await.map_err(ApiError::from)
+}
+
+/// Estimated rebalance cycles and time until the portfolio is within tolerance
+///
+/// Simulates the rebalancer at current prices with full fills; a planning
+/// estimate, not a schedule.
+#[update]
+#[candid_method(update)]
+async fn get_convergence_estimate() -> Result<_1_CRITICAL_OPERATIONS::rebalancing::simulation::ConvergenceEstimate> {
+    infrastructure::access_control::require_read_access()?;
+    _1_CRITICAL_OPERATIONS::rebalancing::simulation::estimate_convergence().await.map_err(ApiError::from)
 }
 
+/// Kong Locker TVL summary, recomputed only when the TVL cache has expired
 #[update]
 #[candid_method(update)]
 async fn get_tvl_summary() -> Result<types::portfolio::TvlSummary> {
-    // Calculate TVL from Kong Locker
-    let tvl_data = _3_KONG_LIQUIDITY::tvl::calculate_kong_locker_tvl().await?;
+    infrastructure::access_control::require_read_access()?;
+    _3_KONG_LIQUIDITY::tvl::get_tvl_summary().await.map_err(ApiError::from)
+}
 
-    // Calculate total and percentages
-    let total_tvl: f64 = tvl_data.iter().map(|(_, v)| v).sum();
+/// Kong Locker TVL summary served from cache (kept warm by a background timer)
+#[query]
+#[candid_method(query)]
+fn get_tvl_summary_cached() -> Result<types::portfolio::TvlSummary> {
+    _3_KONG_LIQUIDITY::tvl::get_cached_tvl_summary().map_err(ApiError::from)
+}
 
-    let tokens: Vec<types::portfolio::TokenTvl> = tvl_data.iter().map(|(token, usd_value)| {
-        types::portfolio::TokenTvl {
-            token: token.clone(),
-            tvl_usd: *usd_value,  // Fixed field name to match .did file
-            percentage: if total_tvl > 0.0 { (usd_value / total_tvl) * 100.0 } else { 0.0 },
-        }
-    }).collect();
+/// Recompute Kong Locker TVL now, bypassing the cache (admin only)
+#[update]
+#[candid_method(update)]
+async fn refresh_tvl_summary() -> Result<types::portfolio::TvlSummary> {
+    require_admin()?;
+    _3_KONG_LIQUIDITY::tvl::refresh_kong_locker_tvl().await?;
+    _3_KONG_LIQUIDITY::tvl::get_cached_tvl_summary().map_err(ApiError::from)
+}
+
+/// Progress of the incremental TVL calculation and age of the last completed generation
+#[query]
+#[candid_method(query)]
+fn get_tvl_generation_status() -> _3_KONG_LIQUIDITY::tvl::generation::TvlGenerationStatus {
+    _3_KONG_LIQUIDITY::tvl::generation::get_generation_status(infrastructure::runtime::time())
+}
 
-    Ok(types::portfolio::TvlSummary {
-        total_tvl_usd: total_tvl,
-        tokens: tokens,  // Fixed field name to match .did file
-        timestamp: ic_cdk::api::time(),
-    })
+/// Report from the latest Kong Locker TVL calculation (admin only)
+#[query]
+#[candid_method(query)]
+fn get_tvl_calculation_report() -> Result<Option<types::portfolio::TvlCalculationReport>> {
+    require_admin()?;
+    Ok(_3_KONG_LIQUIDITY::tvl::get_latest_tvl_report())
 }
 
 #[query]
@@ -207,13 +618,13 @@ fn get_token_metadata() -> Result<Vec<types::tokens::TokenMetadata>> {
 #[query]
 #[candid_method(query)]
 fn get_canister_id() -> Principal {
-    ic_cdk::id()
+    infrastructure::runtime::canister_id()
 }
 
 #[query]
 #[candid_method(query)]
 fn get_cycles_balance() -> Nat {
-    Nat::from(ic_cdk::api::canister_balance128())
+    Nat::from(infrastructure::runtime::cycles_balance())
 }
 
 // ===== ICRC1 TOKEN STANDARD ENDPOINTS =====
@@ -239,7 +650,7 @@ fn icrc1_decimals() -> u8 {
 #[query]
 #[candid_method(query)]
 fn icrc1_fee() -> Nat {
-    Nat::from(10_000u64) // 0.0001 ICPI
+    Nat::from(infrastructure::ledger_fees::icpi_fee())
 }
 
 #[query]
@@ -249,7 +660,7 @@ fn icrc1_metadata() -> Vec<(String, types::icrc::MetadataValue)> {
         ("icrc1:name".to_string(), types::icrc::MetadataValue::Text("Internet Computer Portfolio Index".to_string())),
         ("icrc1:symbol".to_string(), types::icrc::MetadataValue::Text("ICPI".to_string())),
         ("icrc1:decimals".to_string(), types::icrc::MetadataValue::Nat(Nat::from(8u64))),
-        ("icrc1:fee".to_string(), types::icrc::MetadataValue::Nat(Nat::from(10_000u64))),
+        ("icrc1:fee".to_string(), types::icrc::MetadataValue::Nat(Nat::from(infrastructure::ledger_fees::icpi_fee()))),
     ]
 }
 
@@ -277,22 +688,28 @@ fn init() {
     // Start rebalancing timer
     _1_CRITICAL_OPERATIONS::rebalancing::start_rebalancing_timer();
 
+    // Keep the Kong Locker TVL cache warm for cached queries
+    _3_KONG_LIQUIDITY::tvl::start_tvl_refresh_timer();
+
+    // Baseline ckUSDT reserves, then check daily for stray deposits
+    _1_CRITICAL_OPERATIONS::stray_deposits::start_stray_deposit_sweep_timer();
+
     // Start mint cleanup timer to prevent memory leak
     // Runs every hour to clean up completed mints older than 24 hours
-    ic_cdk_timers::set_timer_interval(
-        std::time::Duration::from_secs(3600), // 1 hour
-        || {
-            ic_cdk::spawn(async {
-                match _1_CRITICAL_OPERATIONS::minting::mint_state::cleanup_expired_mints() {
-                    Ok(count) if count > 0 => {
-                        ic_cdk::println!("🧹 Periodic cleanup: removed {} expired mints", count);
-                    }
-                    Ok(_) => {}, // No mints to clean
-                    Err(e) => ic_cdk::println!("⚠️ Periodic cleanup failed: {}", e),
-                }
-            });
-        }
-    );
+    _1_CRITICAL_OPERATIONS::minting::mint_state::start_cleanup_timer();
+
+    // Hourly cycles sampling for the cost report
+    _5_INFORMATIONAL::costs::start_cycles_sampling_timer();
+
+    // Refresh display caches ahead of their TTL
+    _5_INFORMATIONAL::cache::start_cache_prewarm_timer();
+
+    // Retry redemption transfers that failed during burns
+    _1_CRITICAL_OPERATIONS::burning::transfer_retries::start_retry_timer();
+
+    // Keep the stable fallback region reserved and check upgrades can save
+    infrastructure::stable_storage::reserve_fallback_region();
+    infrastructure::stable_storage::start_save_probe_timer();
 }
 
 #[pre_upgrade]
@@ -301,11 +718,9 @@ fn pre_upgrade() {
     ic_cdk::println!("ICPI Backend Pre-Upgrade");
     ic_cdk::println!("===================================");
 
-    let pending_mints = _1_CRITICAL_OPERATIONS::minting::mint_state::export_state();
-    let trade_history = _1_CRITICAL_OPERATIONS::rebalancing::export_history_for_stable();
-    let trade_count = trade_history.len();
-
-    infrastructure::stable_storage::save_state(pending_mints, trade_history);
+    let state = infrastructure::stable_storage::capture_state();
+    let trade_count = state.trade_history.len();
+    infrastructure::stable_storage::save_state(state);
 
     ic_cdk::println!("✅ State saved to stable storage ({} trades)", trade_count);
 }
@@ -316,37 +731,25 @@ fn post_upgrade() {
     ic_cdk::println!("ICPI Backend Post-Upgrade");
     ic_cdk::println!("===================================");
 
-    let (pending_mints, trade_history) = infrastructure::stable_storage::restore_state();
-    let trade_count = trade_history.len();
-    _1_CRITICAL_OPERATIONS::minting::mint_state::import_state(pending_mints);
-    _1_CRITICAL_OPERATIONS::rebalancing::load_history_from_stable(trade_history);
+    let state = infrastructure::stable_storage::restore_state();
+    let trade_count = state.trade_history.len();
+    infrastructure::stable_storage::apply_state(state);
 
-    match _1_CRITICAL_OPERATIONS::minting::mint_state::cleanup_expired_mints() {
-        Ok(count) => {
-            if count > 0 {
-                ic_cdk::println!("🧹 Cleaned up {} expired mints after upgrade", count);
-            }
-        }
-        Err(e) => ic_cdk::println!("⚠️  Failed to cleanup expired mints: {}", e),
-    }
+    // Resolves mints left mid-complete_mint, then cleans up expired ones
+    _1_CRITICAL_OPERATIONS::minting::recovery::schedule_recovery();
 
     _1_CRITICAL_OPERATIONS::rebalancing::start_rebalancing_timer();
+    _3_KONG_LIQUIDITY::tvl::start_tvl_refresh_timer();
+    _1_CRITICAL_OPERATIONS::stray_deposits::start_stray_deposit_sweep_timer();
 
     // Restart mint cleanup timer after upgrade
-    ic_cdk_timers::set_timer_interval(
-        std::time::Duration::from_secs(3600), // 1 hour
-        || {
-            ic_cdk::spawn(async {
-                match _1_CRITICAL_OPERATIONS::minting::mint_state::cleanup_expired_mints() {
-                    Ok(count) if count > 0 => {
-                        ic_cdk::println!("🧹 Periodic cleanup: removed {} expired mints", count);
-                    }
-                    Ok(_) => {}, // No mints to clean
-                    Err(e) => ic_cdk::println!("⚠️ Periodic cleanup failed: {}", e),
-                }
-            });
-        }
-    );
+    _1_CRITICAL_OPERATIONS::minting::mint_state::start_cleanup_timer();
+    _5_INFORMATIONAL::costs::start_cycles_sampling_timer();
+    _5_INFORMATIONAL::cache::start_cache_prewarm_timer();
+    _1_CRITICAL_OPERATIONS::burning::transfer_retries::start_retry_timer();
+    infrastructure::stable_storage::reserve_fallback_region();
+    infrastructure::stable_storage::start_save_probe_timer();
+    infrastructure::dependency_compat::schedule_probe();
 
     ic_cdk::println!("✅ Backend upgraded successfully ({} trades restored)", trade_count);
 }
@@ -354,7 +757,7 @@ fn post_upgrade() {
 // ===== HELPER FUNCTIONS =====
 
 /// Verify caller is an admin principal (uses admin module)
-fn require_admin() -> Result<()> {
+fn require_admin() -> infrastructure::Result<()> {
     infrastructure::require_admin()
 }
 
@@ -371,96 +774,111 @@ fn require_admin() -> Result<()> {
 #[candid_method(update)]
 async fn debug_rebalancing_state() -> Result<String> {
     require_admin()?;
+    let diagnostics = _5_INFORMATIONAL::diagnostics::gather_diagnostics().await;
+    Ok(_5_INFORMATIONAL::diagnostics::format_report(&diagnostics))
+}
 
-    let mut output = String::new();
-    output.push_str("=== REBALANCING DIAGNOSTIC REPORT ===\n\n");
-
-    // 1. Get TVL targets from Kong Locker
-    output.push_str("1. Kong Locker TVL (Target Allocations):\n");
-    match _3_KONG_LIQUIDITY::tvl::calculate_kong_locker_tvl().await {
-        Ok(tvl_data) => {
-            let total_tvl: f64 = tvl_data.iter().map(|(_, v)| v).sum();
-            output.push_str(&format!("   Total TVL: ${:.2}\n", total_tvl));
-            for (token, usd_value) in &tvl_data {
-                let percentage = if total_tvl > 0.0 { (usd_value / total_tvl) * 100.0 } else { 0.0 };
-                output.push_str(&format!("   {}: ${:.2} ({:.2}%)\n", token.to_symbol(), usd_value, percentage));
-            }
-        }
-        Err(e) => output.push_str(&format!("   ❌ ERROR: {}\n", e)),
-    }
-    output.push_str("\n");
-
-    // 2. Get current token balances
-    output.push_str("2. Current Token Balances:\n");
-    match _2_CRITICAL_DATA::token_queries::get_all_balances_uncached().await {
-        Ok(balances) => {
-            for (symbol, balance) in balances {
-                output.push_str(&format!("   {}: {}\n", symbol, balance));
-            }
-        }
-        Err(e) => output.push_str(&format!("   ❌ ERROR: {}\n", e)),
-    }
-    output.push_str("\n");
-
-    // 3. Get portfolio state
-    output.push_str("3. Portfolio State:\n");
-    match _2_CRITICAL_DATA::portfolio_value::get_portfolio_state_uncached().await {
-        Ok(state) => {
-            output.push_str(&format!("   Total Value: ${:.2}\n", state.total_value));
-            output.push_str(&format!("   Timestamp: {}\n", state.timestamp));
-            output.push_str("   Current Positions:\n");
-            for pos in &state.current_positions {
-                output.push_str(&format!("     {}: ${:.2} ({:.2}%)\n",
-                    pos.token.to_symbol(), pos.usd_value, pos.percentage));
-            }
-            output.push_str("   Target Allocations:\n");
-            for target in &state.target_allocations {
-                output.push_str(&format!("     {}: {:.2}% (${:.2})\n",
-                    target.token.to_symbol(), target.target_percentage, target.target_usd_value));
-            }
-            output.push_str("   Deviations:\n");
-            for dev in &state.deviations {
-                output.push_str(&format!("     {}: current={:.2}% target={:.2}% deviation={:.2}% usd_diff=${:.2}\n",
-                    dev.token.to_symbol(), dev.current_pct, dev.target_pct, dev.deviation_pct, dev.usd_difference));
-            }
-        }
-        Err(e) => output.push_str(&format!("   ❌ ERROR: {}\n", e)),
-    }
-    output.push_str("\n");
-
-    // 4. Get rebalancer status
-    output.push_str("4. Rebalancer Status:\n");
-    let status = _1_CRITICAL_OPERATIONS::rebalancing::get_rebalancer_status();
-    output.push_str(&format!("   Timer Active: {}\n", status.timer_active));
-    output.push_str(&format!("   Last Rebalance: {:?}\n", status.last_rebalance));
-    output.push_str(&format!("   Next Rebalance: {:?}\n", status.next_rebalance));
-    output.push_str(&format!("   Recent History Entries: {}\n", status.recent_history.len()));
-
-    Ok(output)
+/// Structured form of `debug_rebalancing_state` for monitoring (admin only)
+///
+/// Sections whose data source failed carry the error instead of data.
+#[update]
+#[candid_method(update)]
+async fn get_rebalancing_diagnostics() -> Result<_5_INFORMATIONAL::diagnostics::RebalancingDiagnostics> {
+    require_admin()?;
+    Ok(_5_INFORMATIONAL::diagnostics::gather_diagnostics().await)
 }
 
 /// Emergency pause - stops all minting and burning
+///
+/// An empty `reason` is stored as no reason.
 #[update]
 #[candid_method(update)]
-fn emergency_pause() -> Result<()> {
+fn emergency_pause(reason: String) -> Result<()> {
     infrastructure::require_admin()?;
-    infrastructure::set_pause(true);
-    infrastructure::log_admin_action("EMERGENCY_PAUSE_ACTIVATED".to_string());
+    let reason = Some(reason.trim().to_string()).filter(|r| !r.is_empty());
+    let before = infrastructure::admin::get_pause_info();
+    infrastructure::admin::pause_with_reason(reason.clone(), infrastructure::runtime::caller(), infrastructure::runtime::time());
+    infrastructure::log_admin_change(
+        "EMERGENCY_PAUSE_ACTIVATED".to_string(),
+        format!("paused={} reason={:?}", before.paused, before.reason),
+        format!("paused=true reason={:?}", reason),
+    );
     ic_cdk::println!("🚨 EMERGENCY PAUSE ACTIVATED");
     Ok(())
 }
 
 /// Resume operations after emergency pause
+///
+/// Mints and burns resume immediately; automated rebalancing waits out the
+/// configured cooldown so caches can refresh first.
 #[update]
 #[candid_method(update)]
 fn emergency_unpause() -> Result<()> {
     infrastructure::require_admin()?;
-    infrastructure::set_pause(false);
-    infrastructure::log_admin_action("EMERGENCY_PAUSE_DEACTIVATED".to_string());
+    let before = infrastructure::admin::get_pause_info();
+    infrastructure::admin::unpause_at(infrastructure::runtime::time());
+    infrastructure::log_admin_change(
+        "EMERGENCY_PAUSE_DEACTIVATED".to_string(),
+        format!("paused={} reason={:?}", before.paused, before.reason),
+        "paused=false".to_string(),
+    );
     ic_cdk::println!("✅ EMERGENCY PAUSE DEACTIVATED");
     Ok(())
 }
 
+/// Refuse new mints, burns and rebalances ahead of an upgrade (admin only)
+///
+/// In-flight operations finish normally. Returns whether they have all
+/// drained; call again to poll, and upgrade once `drained` is true. Ended by
+/// `end_quiesce` or by the upgrade itself.
+#[update]
+#[candid_method(update)]
+fn begin_quiesce() -> Result<infrastructure::QuiesceStatus> {
+    infrastructure::require_admin()?;
+    let was_quiescing = infrastructure::admin::is_quiescing();
+    let status = infrastructure::admin::begin_quiesce(infrastructure::runtime::time());
+    if !was_quiescing {
+        infrastructure::log_admin_action("QUIESCE_STARTED".to_string());
+    }
+    Ok(status)
+}
+
+/// Accept new operations again after `begin_quiesce` (admin only)
+#[update]
+#[candid_method(update)]
+fn end_quiesce() -> Result<()> {
+    infrastructure::require_admin()?;
+    infrastructure::admin::end_quiesce();
+    infrastructure::log_admin_action("QUIESCE_ENDED".to_string());
+    Ok(())
+}
+
+/// Show a banner to users via `get_health_status` and the ticker (admin only)
+///
+/// Replaces any existing message. `expires_at` (ns) hides it automatically.
+#[update]
+#[candid_method(update)]
+fn set_status_message(message: String, severity: types::StatusSeverity, expires_at: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let status = _5_INFORMATIONAL::status_message::build_message(message, severity, expires_at, infrastructure::runtime::time())?;
+    infrastructure::log_admin_action(format!(
+        "STATUS_MESSAGE_SET: [{:?}] {} (expires: {:?})", status.severity, status.message, status.expires_at
+    ));
+    _5_INFORMATIONAL::status_message::set_message(status);
+    Ok(())
+}
+
+/// Remove the user-facing banner (admin only)
+#[update]
+#[candid_method(update)]
+fn clear_status_message() -> Result<()> {
+    infrastructure::require_admin()?;
+    if let Some(previous) = _5_INFORMATIONAL::status_message::clear_message() {
+        infrastructure::log_admin_action(format!("STATUS_MESSAGE_CLEARED: {}", previous.message));
+    }
+    Ok(())
+}
+
 /// Check if system is currently paused
 #[query]
 #[candid_method(query)]
@@ -468,6 +886,13 @@ fn is_emergency_paused() -> bool {
     infrastructure::is_paused()
 }
 
+/// Pause state with reason, time and pausing principal
+#[query]
+#[candid_method(query)]
+fn get_pause_info() -> infrastructure::PauseInfo {
+    infrastructure::admin::get_pause_info()
+}
+
 /// Get admin action log (admin only)
 #[query]
 #[candid_method(query)]
@@ -476,6 +901,725 @@ fn get_admin_action_log() -> Result<Vec<infrastructure::AdminAction>> {
     Ok(infrastructure::get_admin_log())
 }
 
+/// A user's last mint and burn failures (the user themselves or admin)
+#[query]
+#[candid_method(query)]
+fn get_last_failures(user: Principal) -> Result<_1_CRITICAL_OPERATIONS::operation_status::last_failure::LastFailures> {
+    infrastructure::require_self_or_admin(user)?;
+    Ok(_1_CRITICAL_OPERATIONS::operation_status::last_failure::get_last_failures(&user))
+}
+
+/// Set or clear the fallback price oracle (admin only)
+///
+/// The oracle is queried only when Kongswap pricing fails, and never for
+/// mint/burn valuation.
+#[update]
+#[candid_method(update)]
+fn set_price_oracle(oracle: Option<Principal>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.price_oracle.map(|p| p.to_text()), |c| c.price_oracle = oracle);
+    infrastructure::log_admin_change("PRICE_ORACLE_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set target smoothing parameters (admin only)
+///
+/// `None` restores the compiled default for that parameter.
+#[update]
+#[candid_method(update)]
+fn set_target_smoothing(half_life_seconds: Option<u64>, max_change_pct: Option<f64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if let Some(pct) = max_change_pct {
+        if !pct.is_finite() || !(0.0..=100.0).contains(&pct) {
+            return Err(IcpiError::Other(format!("Invalid max target change {}", pct)).into());
+        }
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| (c.target_smoothing_half_life_seconds, c.max_target_change_pct),
+        |c| {
+            c.target_smoothing_half_life_seconds = half_life_seconds;
+            c.max_target_change_pct = max_change_pct;
+        },
+    );
+    infrastructure::log_admin_change("TARGET_SMOOTHING_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the maximum target weight of one token (admin only)
+///
+/// `None` restores the default cap for that token.
+#[update]
+#[candid_method(update)]
+fn set_max_token_weight(token: types::TrackedToken, max_pct: Option<f64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if token == types::TrackedToken::ckUSDT {
+        return Err(IcpiError::Other("ckUSDT has no target weight".to_string()).into());
+    }
+    if let Some(pct) = max_pct {
+        if !pct.is_finite() || pct <= 0.0 || pct > 100.0 {
+            return Err(IcpiError::Other(format!("Invalid max token weight {}", pct)).into());
+        }
+    }
+    // Caps below 100% in total can't all hold; targets would fail validation
+    let config = infrastructure::config::get_config();
+    let cap_total: f64 = types::TrackedToken::all().iter()
+        .map(|t| if *t == token {
+            max_pct.unwrap_or(infrastructure::MAX_TOKEN_WEIGHT_PCT)
+        } else {
+            config.max_token_weight(t)
+        })
+        .sum();
+    if cap_total < 100.0 {
+        return Err(IcpiError::Other(format!("Max token weights would sum to {:.2}%, below 100%", cap_total)).into());
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.max_token_weights.clone(),
+        |c| {
+            let mut caps: Vec<(types::TrackedToken, f64)> = c.max_token_weights.take().unwrap_or_default()
+                .into_iter()
+                .filter(|(t, _)| *t != token)
+                .collect();
+            if let Some(pct) = max_pct {
+                caps.push((token, pct));
+            }
+            c.max_token_weights = if caps.is_empty() { None } else { Some(caps) };
+        },
+    );
+    infrastructure::log_admin_change("MAX_TOKEN_WEIGHT_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the per-principal window for a rate-limited operation (admin only)
+///
+/// At most one day (`MAX_RATE_LIMIT_WINDOW_SECONDS`). `None` restores the
+/// default; `Some(0)` disables the limit.
+#[update]
+#[candid_method(update)]
+fn set_rate_limit_window(
+    operation: infrastructure::rate_limiting::RateLimitedOperation,
+    seconds: Option<u64>,
+) -> Result<()> {
+    use infrastructure::rate_limiting::RateLimitedOperation;
+
+    infrastructure::require_admin()?;
+    if let Some(s) = seconds {
+        infrastructure::rate_limiting::validate_window(s)?;
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| (operation, c.rate_limit_window_seconds(operation)),
+        |c| match operation {
+            RateLimitedOperation::Mint => c.mint_rate_limit_seconds = seconds,
+            RateLimitedOperation::Burn => c.burn_rate_limit_seconds = seconds,
+            RateLimitedOperation::ManualRebalance => c.manual_rebalance_rate_limit_seconds = seconds,
+        },
+    );
+    infrastructure::log_admin_change("RATE_LIMIT_WINDOW_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the principals exempt from rate limits besides the admins (admin only)
+///
+/// `None` clears the list.
+#[update]
+#[candid_method(update)]
+fn set_rate_limit_bypass(principals: Option<Vec<Principal>>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.rate_limit_bypass.clone(),
+        |c| c.rate_limit_bypass = principals,
+    );
+    infrastructure::log_admin_change("RATE_LIMIT_BYPASS_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the cycles balance a rebalance cycle needs before it starts (admin only)
+///
+/// `None` restores the default.
+#[update]
+#[candid_method(update)]
+fn set_rebalance_cycles_budget(cycles: Option<u128>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.rebalance_cycles_budget,
+        |c| c.rebalance_cycles_budget = cycles,
+    );
+    infrastructure::log_admin_change("REBALANCE_CYCLES_BUDGET_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the wait between global operation type switches (admin only)
+///
+/// `None` restores the default. A caller switching straight from their own
+/// operation is never made to wait.
+#[update]
+#[candid_method(update)]
+fn set_grace_period(seconds: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.grace_period_seconds,
+        |c| c.grace_period_seconds = seconds,
+    );
+    infrastructure::log_admin_change("GRACE_PERIOD_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the oldest completed TVL generation the rebalancer trades on (admin only)
+///
+/// `None` restores the default.
+#[update]
+#[candid_method(update)]
+fn set_max_tvl_generation_age(max_age_seconds: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if max_age_seconds == Some(0) {
+        return Err(IcpiError::Other("Max TVL generation age must be positive".to_string()).into());
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.max_tvl_generation_age_seconds,
+        |c| c.max_tvl_generation_age_seconds = max_age_seconds,
+    );
+    infrastructure::log_admin_change("TVL_GENERATION_MAX_AGE_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Schedule the next rebalance cycle at `timestamp` (ns) for one cycle (admin only)
+#[update]
+#[candid_method(update)]
+fn set_next_rebalance_time(timestamp: u64) -> Result<()> {
+    infrastructure::require_admin()?;
+    let before = _1_CRITICAL_OPERATIONS::rebalancing::get_rebalancer_status().next_rebalance;
+    _1_CRITICAL_OPERATIONS::rebalancing::set_next_rebalance_time(timestamp)?;
+    infrastructure::log_admin_change(
+        "NEXT_REBALANCE_TIME_SET".to_string(),
+        format!("{:?}", before),
+        format!("{:?}", Some(timestamp)),
+    );
+    Ok(())
+}
+
+/// Set the oldest TVL data the rebalancer trades on targets from (admin only)
+///
+/// `None` restores the default (2x the TVL refresh interval).
+#[update]
+#[candid_method(update)]
+fn set_max_target_age(max_age_seconds: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if max_age_seconds == Some(0) {
+        return Err(IcpiError::Other("Max target age must be positive".to_string()).into());
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.max_target_age_seconds,
+        |c| c.max_target_age_seconds = max_age_seconds,
+    );
+    infrastructure::log_admin_change("MAX_TARGET_AGE_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the symbol Kongswap knows a token by (admin only)
+///
+/// Used in all swap, quote and price calls and to match LP positions.
+/// `None` restores the index symbol.
+#[update]
+#[candid_method(update)]
+fn set_kongswap_symbol(token: types::TrackedToken, symbol: Option<String>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if let Some(symbol) = &symbol {
+        if symbol.trim().is_empty() || symbol.trim() != symbol {
+            return Err(IcpiError::Other(format!("Invalid Kongswap symbol {:?}", symbol)).into());
+        }
+    }
+    let apply = |c: &mut infrastructure::config::RuntimeConfig| {
+        let mut symbols: Vec<(types::TrackedToken, String)> = c.kongswap_symbols.take().unwrap_or_default()
+            .into_iter()
+            .filter(|(t, _)| *t != token)
+            .collect();
+        if let Some(symbol) = symbol.clone() {
+            symbols.push((token.clone(), symbol));
+        }
+        c.kongswap_symbols = if symbols.is_empty() { None } else { Some(symbols) };
+    };
+
+    // Reject an alias that would make two tokens share a Kongswap symbol
+    let mut proposed = infrastructure::config::get_config();
+    apply(&mut proposed);
+    let mut tokens = types::TrackedToken::all_vec();
+    tokens.push(types::TrackedToken::ckUSDT);
+    _2_CRITICAL_DATA::validation::check_token_identities(&tokens, &proposed)?;
+
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.kongswap_symbols.clone(),
+        apply,
+    );
+    infrastructure::log_admin_change("KONGSWAP_SYMBOL_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Configure the supply re-check before ledger mint (admin only)
+///
+/// `None` restores the compiled default for that parameter.
+#[update]
+#[candid_method(update)]
+fn set_mint_supply_recheck(enabled: Option<bool>, tolerance_bps: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if let Some(bps) = tolerance_bps {
+        if bps > 10_000 {
+            return Err(IcpiError::Other(format!("Invalid supply tolerance {} bps", bps)).into());
+        }
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| (c.mint_supply_recheck, c.mint_supply_tolerance_bps),
+        |c| {
+            c.mint_supply_recheck = enabled;
+            c.mint_supply_tolerance_bps = tolerance_bps;
+        },
+    );
+    infrastructure::log_admin_change("MINT_SUPPLY_RECHECK_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Enable or disable the post-mint balance check (admin only)
+///
+/// `None` restores the default (disabled). Costs two extra ledger queries
+/// per mint.
+#[update]
+#[candid_method(update)]
+fn set_mint_balance_check(enabled: Option<bool>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.mint_balance_check, |c| c.mint_balance_check = enabled);
+    infrastructure::log_admin_change("MINT_BALANCE_CHECK_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Select how trade size scales with deviation (admin only)
+///
+/// `None` restores the default flat intensity.
+#[update]
+#[candid_method(update)]
+fn set_trade_intensity_curve(curve: Option<_2_CRITICAL_DATA::trade_intensity::IntensityCurve>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.trade_intensity_curve, |c| c.trade_intensity_curve = curve);
+    infrastructure::log_admin_change("TRADE_INTENSITY_CURVE_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Choose how partially filled swaps are handled (admin only)
+///
+/// `None` restores the default (accept and reconcile).
+#[update]
+#[candid_method(update)]
+fn set_partial_fill_policy(policy: Option<_4_TRADING_EXECUTION::fills::PartialFillPolicy>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.partial_fill_policy, |c| c.partial_fill_policy = policy);
+    infrastructure::log_admin_change("PARTIAL_FILL_POLICY_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Choose how burns handle a redemption the backend's balance no longer covers (admin only)
+///
+/// `None` restores the default (clamp to the available balance).
+#[update]
+#[candid_method(update)]
+fn set_redemption_shortfall_policy(policy: Option<_1_CRITICAL_OPERATIONS::burning::token_distributor::ShortfallPolicy>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.redemption_shortfall_policy,
+        |c| c.redemption_shortfall_policy = policy,
+    );
+    infrastructure::log_admin_change("REDEMPTION_SHORTFALL_POLICY_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the post-unpause rebalancing cooldown in seconds (admin only)
+///
+/// `None` restores the default (5 minutes); 0 disables the cooldown.
+#[update]
+#[candid_method(update)]
+fn set_unpause_cooldown(seconds: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.unpause_cooldown_seconds, |c| c.unpause_cooldown_seconds = seconds);
+    infrastructure::log_admin_change("UNPAUSE_COOLDOWN_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Configure the global pending-mint cap and aggressive cleanup (admin only)
+///
+/// Aggressive cleanup shortens mint expiry while occupancy is above 80%.
+/// `None` restores the compiled default for that parameter.
+#[update]
+#[candid_method(update)]
+fn set_pending_mint_limits(max_pending: Option<u64>, aggressive_cleanup: Option<bool>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if max_pending == Some(0) {
+        return Err(IcpiError::Other("Pending mint cap must be positive".to_string()).into());
+    }
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| (c.max_pending_mints, c.aggressive_mint_cleanup),
+        |c| {
+            c.max_pending_mints = max_pending;
+            c.aggressive_mint_cleanup = aggressive_cleanup;
+        },
+    );
+    infrastructure::log_admin_change("PENDING_MINT_LIMITS_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the cache pre-warming interval in seconds (admin only)
+///
+/// Must stay below the index state cache TTL; `None` restores the default
+/// (240). Restarts the pre-warm timer.
+#[update]
+#[candid_method(update)]
+fn set_cache_prewarm_interval(seconds: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if let Some(s) = seconds {
+        _5_INFORMATIONAL::cache::validate_prewarm_interval(s)?;
+    }
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.cache_prewarm_interval_seconds, |c| c.cache_prewarm_interval_seconds = seconds);
+    _5_INFORMATIONAL::cache::start_cache_prewarm_timer();
+    infrastructure::log_admin_change("CACHE_PREWARM_INTERVAL_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Cache pre-warmer runs, skips and last error
+#[query]
+#[candid_method(query)]
+fn get_cache_prewarm_status() -> _5_INFORMATIONAL::cache::PrewarmStatus {
+    _5_INFORMATIONAL::cache::get_prewarm_status()
+}
+
+/// Set the share of the instruction limit loops may use (admin only)
+///
+/// Burn distributions and dust sweeps stop once it is used; `None` restores
+/// the default (0.8).
+#[update]
+#[candid_method(update)]
+fn set_instruction_budget_fraction(fraction: Option<f64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if let Some(f) = fraction {
+        if !(f > 0.0 && f <= 1.0) {
+            return Err(IcpiError::Other(format!("Invalid instruction budget fraction {}", f)).into());
+        }
+    }
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.instruction_budget_fraction, |c| c.instruction_budget_fraction = fraction);
+    infrastructure::log_admin_change("INSTRUCTION_BUDGET_FRACTION_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Cap in-flight `complete_mint` calls across all users (admin only)
+///
+/// Completes over the cap are rejected with a retry-later error.
+/// `None` restores the default (20).
+#[update]
+#[candid_method(update)]
+fn set_max_concurrent_mints(max: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if max == Some(0) {
+        return Err(IcpiError::Other("Concurrent mint cap must be positive".to_string()).into());
+    }
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.max_concurrent_mints, |c| c.max_concurrent_mints = max);
+    infrastructure::log_admin_change("MAX_CONCURRENT_MINTS_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Enable or disable serving the last-good TVL during a Kong Locker outage (admin only)
+///
+/// Affects TVL display and rebalancing targets only; results are flagged stale.
+/// `None` restores the default (disabled).
+#[update]
+#[candid_method(update)]
+fn set_tvl_outage_fallback(enabled: Option<bool>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.tvl_outage_fallback, |c| c.tvl_outage_fallback = enabled);
+    infrastructure::log_admin_change("TVL_OUTAGE_FALLBACK_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Enable or disable the emergency pause on supply/TVL inconsistency (admin only)
+///
+/// `None` restores the default (enabled).
+#[update]
+#[candid_method(update)]
+fn set_auto_pause_on_corruption(enabled: Option<bool>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.auto_pause_on_corruption,
+        |c| c.auto_pause_on_corruption = enabled,
+    );
+    infrastructure::log_admin_change("AUTO_PAUSE_ON_CORRUPTION_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set the operation size (ckUSDT e6) that gets a principal its own
+/// activity totals (admin only)
+///
+/// Smaller operations of untracked principals are aggregated. `None`
+/// restores the default ($100).
+#[update]
+#[candid_method(update)]
+fn set_activity_tracking_threshold(threshold_e6: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(
+        |c| c.activity_tracking_threshold_e6,
+        |c| c.activity_tracking_threshold_e6 = threshold_e6,
+    );
+    infrastructure::log_admin_change("ACTIVITY_TRACKING_THRESHOLD_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set how many lock canister queries must succeed for a TVL to be used (admin only)
+///
+/// Applies on top of the >50% success rate, so a sparse period can't let a
+/// single locker define the index. `None` restores the default (1).
+#[update]
+#[candid_method(update)]
+fn set_min_successful_lockers(min: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    if min == Some(0) {
+        return Err(IcpiError::Other("Minimum successful lockers must be positive".to_string()).into());
+    }
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.min_successful_lockers, |c| c.min_successful_lockers = min);
+    infrastructure::log_admin_change("MIN_SUCCESSFUL_LOCKERS_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set how many seconds must remain on a swap approval before it is
+/// renewed ahead of the swap (admin only)
+///
+/// Must be below the 15-minute approval expiry. `None` restores the default.
+#[update]
+#[candid_method(update)]
+fn set_approval_min_remaining_seconds(seconds: Option<u64>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let expiry_seconds = _4_TRADING_EXECUTION::approvals::APPROVAL_EXPIRY_NANOS / 1_000_000_000;
+    if let Some(s) = seconds {
+        if s >= expiry_seconds {
+            return Err(infrastructure::IcpiError::Validation(infrastructure::ValidationError::InvalidAmount {
+                amount: s.to_string(),
+                reason: format!("Must be below the {}s approval expiry", expiry_seconds),
+            }).into());
+        }
+    }
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.approval_min_remaining_seconds, |c| c.approval_min_remaining_seconds = seconds);
+    infrastructure::log_admin_change("APPROVAL_MIN_REMAINING_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Get runtime configuration overrides (unset fields use compiled defaults)
+#[query]
+#[candid_method(query)]
+fn get_config() -> infrastructure::config::RuntimeConfig {
+    infrastructure::config::get_config()
+}
+
+/// Settings in force after applying compiled defaults, including rate limits
+#[query]
+#[candid_method(query)]
+fn get_effective_config() -> infrastructure::config::EffectiveConfig {
+    infrastructure::config::get_effective_config()
+}
+
+/// Require an authenticated caller for update read endpoints (admin only)
+///
+/// Covers the reads that make inter-canister calls (`get_index_state`,
+/// `get_tvl_summary`, ...); queries stay open. `None` restores the default
+/// (disabled).
+#[update]
+#[candid_method(update)]
+fn set_require_authenticated_reads(enabled: Option<bool>) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.require_authenticated_reads, |c| c.require_authenticated_reads = enabled);
+    infrastructure::log_admin_change("REQUIRE_AUTHENTICATED_READS_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Set mint/burn access mode (admin only)
+#[update]
+#[candid_method(update)]
+fn set_access_mode(mode: infrastructure::access_control::AccessMode) -> Result<()> {
+    infrastructure::require_admin()?;
+    let (before, after) = infrastructure::config::update_config_diff(|c| c.access_mode, |c| c.access_mode = Some(mode));
+    infrastructure::log_admin_change("ACCESS_MODE_SET".to_string(), before, after);
+    Ok(())
+}
+
+/// Allow a principal to mint/burn in Allowlist mode (admin only)
+#[update]
+#[candid_method(update)]
+fn add_to_allowlist(principal: Principal) -> Result<()> {
+    infrastructure::require_admin()?;
+    if infrastructure::access_control::add_to_allowlist(principal) {
+        infrastructure::log_admin_action(format!("ALLOWLIST_ADD: {}", principal));
+    }
+    Ok(())
+}
+
+/// Remove a principal from the allowlist (admin only)
+#[update]
+#[candid_method(update)]
+fn remove_from_allowlist(principal: Principal) -> Result<()> {
+    infrastructure::require_admin()?;
+    if infrastructure::access_control::remove_from_allowlist(&principal) {
+        infrastructure::log_admin_action(format!("ALLOWLIST_REMOVE: {}", principal));
+    }
+    Ok(())
+}
+
+/// Block a principal from minting/burning in every mode (admin only)
+#[update]
+#[candid_method(update)]
+fn add_to_denylist(principal: Principal) -> Result<()> {
+    infrastructure::require_admin()?;
+    if infrastructure::access_control::add_to_denylist(principal) {
+        infrastructure::log_admin_action(format!("DENYLIST_ADD: {}", principal));
+    }
+    Ok(())
+}
+
+/// Remove a principal from the denylist (admin only)
+#[update]
+#[candid_method(update)]
+fn remove_from_denylist(principal: Principal) -> Result<()> {
+    infrastructure::require_admin()?;
+    if infrastructure::access_control::remove_from_denylist(&principal) {
+        infrastructure::log_admin_action(format!("DENYLIST_REMOVE: {}", principal));
+    }
+    Ok(())
+}
+
+/// Get allowlist and denylist contents (admin only)
+#[query]
+#[candid_method(query)]
+fn get_access_lists() -> Result<infrastructure::access_control::AccessLists> {
+    infrastructure::require_admin()?;
+    Ok(infrastructure::access_control::get_access_lists())
+}
+
+/// Failed burn transfers awaiting automatic retry, and permanent failures (admin only)
+#[query]
+#[candid_method(query)]
+fn get_burn_retry_queue() -> Result<_1_CRITICAL_OPERATIONS::burning::transfer_retries::RetryQueueState> {
+    infrastructure::require_admin()?;
+    Ok(_1_CRITICAL_OPERATIONS::burning::transfer_retries::get_queue())
+}
+
+/// Mints left mid-`complete_mint` by an upgrade and how each was resolved (admin only)
+#[query]
+#[candid_method(query)]
+fn get_mint_recoveries() -> Result<Vec<_1_CRITICAL_OPERATIONS::minting::recovery::MintRecovery>> {
+    infrastructure::require_admin()?;
+    Ok(_1_CRITICAL_OPERATIONS::minting::recovery::get_mint_recoveries())
+}
+
+/// Latest Candid compatibility matrix of external dependencies
+#[query]
+#[candid_method(query)]
+fn get_dependency_compat() -> infrastructure::dependency_compat::CompatReport {
+    infrastructure::dependency_compat::get_report()
+}
+
+/// Re-probe every external method for Candid compatibility (admin only)
+///
+/// Clears the degraded flag once every probed reply decodes again.
+#[update]
+#[candid_method(update)]
+async fn probe_dependency_compat() -> Result<infrastructure::dependency_compat::CompatReport> {
+    infrastructure::require_admin()?;
+    Ok(infrastructure::dependency_compat::run_probes().await)
+}
+
+/// Fees, slippage and cycles over the last `window_days` days, with an
+/// annualized expense ratio against average portfolio value
+#[query]
+#[candid_method(query)]
+fn get_cost_report(window_days: u32) -> Result<_5_INFORMATIONAL::costs::CostReport> {
+    _5_INFORMATIONAL::costs::get_cost_report(window_days).map_err(ApiError::from)
+}
+
+/// Swap approvals renewed because they were close to expiry (admin only)
+#[query]
+#[candid_method(query)]
+fn get_approval_renewals() -> Result<Vec<_4_TRADING_EXECUTION::swaps::ApprovalRenewal>> {
+    require_admin()?;
+    Ok(_4_TRADING_EXECUTION::swaps::get_approval_renewals())
+}
+
+/// Full backend backup: persisted state, config, metrics and composition (admin only)
+///
+/// An update rather than a query because the composition is computed live.
+#[update]
+#[candid_method(update)]
+async fn export_full_state() -> Result<infrastructure::stable_storage::FullStateExport> {
+    require_admin()?;
+    infrastructure::log_admin_action("FULL_STATE_EXPORTED".to_string());
+    Ok(infrastructure::stable_storage::export_full_state().await)
+}
+
+/// Restore a backup from `export_full_state` (admin only, requires emergency pause)
+#[update]
+#[candid_method(update)]
+fn import_full_state(export: infrastructure::stable_storage::FullStateExport) -> Result<()> {
+    require_admin()?;
+    let exported_at = export.exported_at;
+    infrastructure::stable_storage::import_full_state(export)?;
+    infrastructure::log_admin_action(format!("FULL_STATE_IMPORTED: exported_at {}", exported_at));
+    Ok(())
+}
+
+/// Mint, burn and trade totals per UTC day (days since the Unix epoch, inclusive)
+#[query]
+#[candid_method(query)]
+fn get_daily_aggregates(from_day: u32, to_day: u32) -> Result<Vec<_5_INFORMATIONAL::daily_totals::DailyTotals>> {
+    _5_INFORMATIONAL::daily_totals::get_daily_aggregates(from_day, to_day).map_err(ApiError::from)
+}
+
+/// Swaps whose consumed pay amount differed from the request (admin only)
+#[query]
+#[candid_method(query)]
+fn get_fill_discrepancies() -> Result<Vec<_4_TRADING_EXECUTION::fills::FillDiscrepancy>> {
+    require_admin()?;
+    Ok(_4_TRADING_EXECUTION::fills::get_fill_discrepancies())
+}
+
+/// Re-fetch the ICPI and ckUSDT ledger fees (admin only)
+///
+/// Refunds, redemption dust thresholds and approval padding use the
+/// cached fees; a fee that differs from the compiled default shows up as a
+/// health warning.
+#[update]
+#[candid_method(update)]
+async fn refresh_ledger_fees() -> Result<infrastructure::ledger_fees::LedgerFees> {
+    require_admin()?;
+    let before = infrastructure::ledger_fees::export_fees();
+    let fees = infrastructure::ledger_fees::refresh_fees().await?;
+    infrastructure::log_admin_change("LEDGER_FEES_REFRESHED".to_string(), format!("{:?}", before), format!("{:?}", fees));
+    Ok(fees)
+}
+
+/// Most recent failure of each subsystem (admin only)
+#[query]
+#[candid_method(query)]
+fn get_subsystem_errors() -> Result<Vec<infrastructure::logging::SubsystemError>> {
+    require_admin()?;
+    Ok(infrastructure::logging::get_subsystem_errors())
+}
+
+/// Active background timers and their intervals (admin only)
+#[query]
+#[candid_method(query)]
+fn get_timers() -> Result<Vec<infrastructure::timers::TimerInfo>> {
+    require_admin()?;
+    Ok(infrastructure::timers::get_timers())
+}
+
+/// Compare cached TVL and portfolio snapshot against a live recompute (admin only)
+///
+/// Read-only: the caches are not updated with the live values.
+#[update]
+#[candid_method(update)]
+async fn recompute_and_report() -> Result<_5_INFORMATIONAL::cache::CacheAuditReport> {
+    infrastructure::require_admin()?;
+    Ok(_5_INFORMATIONAL::cache::recompute_and_report().await)
+}
+
 /// Clear all caches (admin only)
 #[update]
 #[candid_method(update)]
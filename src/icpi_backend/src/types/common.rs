@@ -2,13 +2,54 @@ use candid::{CandidType, Deserialize};
 use serde::Serialize;
 use rust_decimal::Decimal;
 
+/// A failed primary stable save, kept until a later save succeeds
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SaveFailure {
+    pub reason: String,
+    pub failed_at: u64,
+    /// Whether the state was restored from the fallback copy
+    pub restored_from_fallback: bool,
+}
+
 // Health status
 #[derive(CandidType, Deserialize, Serialize, Debug)]
 pub struct HealthStatus {
     pub version: String,
+    /// Commit the running wasm was built from
+    pub git_revision: String,
+    /// SHA-256 (hex) of the candid interface
+    pub interface_hash: String,
     pub tracked_tokens: Vec<String>,
     pub last_rebalance: Option<u64>,
     pub cycles_balance: u128,
+    /// Non-terminal pending mints across all users
+    pub pending_mints: u64,
+    pub max_pending_mints: u64,
+    pub status_message: Option<StatusMessage>,
+    /// Operational warnings, e.g. a ledger fee that differs from the compiled default
+    pub warnings: Vec<String>,
+    /// ckUSDT held for unsettled mint deposits (e6)
+    pub reserved_ckusdt: candid::Nat,
+    /// Spendable ckUSDT as of the last rebalance cycle (e6)
+    pub free_ckusdt: Option<candid::Nat>,
+    /// Set when the last upgrade's primary stable save failed
+    pub last_save_failure: Option<SaveFailure>,
+}
+
+// Operator banner shown by the frontend (maintenance, incidents)
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum StatusSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct StatusMessage {
+    pub message: String,
+    pub severity: StatusSeverity,
+    pub set_at: u64,
+    pub expires_at: Option<u64>,  // Hidden once passed
 }
 
 // Error recovery types
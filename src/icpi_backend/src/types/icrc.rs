@@ -124,4 +124,80 @@ pub enum MetadataValue {
 pub struct StandardRecord {
     pub name: String,
     pub url: String,
-}
\ No newline at end of file
+}
+// Account as recorded in ledger transactions
+#[derive(CandidType, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LedgerAccount {
+    pub owner: Principal,
+    pub subaccount: Option<serde_bytes::ByteBuf>,
+}
+
+// ICRC1 ledger `get_transactions` arguments
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct GetTransactionsRequest {
+    pub start: Nat,
+    pub length: Nat,
+}
+
+// Ledger transaction; fields we don't use are left out
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct LedgerTransaction {
+    pub kind: String,
+    pub mint: Option<LedgerMint>,
+    pub burn: Option<LedgerBurn>,
+    pub transfer: Option<LedgerTransfer>,
+    pub approve: Option<LedgerApprove>,
+    pub timestamp: u64,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct LedgerMint {
+    pub to: LedgerAccount,
+    pub amount: Nat,
+    pub memo: Option<serde_bytes::ByteBuf>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct LedgerBurn {
+    pub from: LedgerAccount,
+    pub amount: Nat,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct LedgerTransfer {
+    pub from: LedgerAccount,
+    pub to: LedgerAccount,
+    pub amount: Nat,
+    pub fee: Option<Nat>,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct LedgerApprove {
+    pub from: LedgerAccount,
+    pub fee: Option<Nat>,
+}
+
+// Transactions served by an archive canister
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct TransactionRange {
+    pub transactions: Vec<LedgerTransaction>,
+}
+
+candid::define_function!(pub ArchiveCallback : (GetTransactionsRequest) -> (TransactionRange) query);
+
+// Range of transactions moved to an archive canister
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct ArchivedRange {
+    pub start: Nat,
+    pub length: Nat,
+    pub callback: ArchiveCallback,
+}
+
+// ICRC1 ledger `get_transactions` response
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct GetTransactionsResponse {
+    pub log_length: Nat,
+    pub first_index: Nat,
+    pub transactions: Vec<LedgerTransaction>,
+    pub archived_transactions: Vec<ArchivedRange>,
+}
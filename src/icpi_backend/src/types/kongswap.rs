@@ -126,7 +126,8 @@ pub struct LPBalancesReply {
     pub ts: u64,
 }
 
-// Kongswap user_balances response types
+// Kongswap user_balances response types, as published in kong_backend.did:
+// `type UserBalancesReply = variant { LP : LPBalancesReply };`
 #[derive(CandidType, Deserialize, Debug)]
 pub enum UserBalancesReply {
     LP(LPBalancesReply),
@@ -6,12 +6,12 @@ pub mod rebalancing;
 pub mod common;
 
 // Re-export commonly used types
-pub use tokens::{TrackedToken, TokenMetadata, ICPI_CANISTER_ID, CKUSDT_CANISTER_ID};
+pub use tokens::{TrackedToken, TokenMetadata, TokenDetail, ICPI_CANISTER_ID, CKUSDT_CANISTER_ID};
 pub use icrc::{Account, TransferArgs, TransferResult};
 pub use kongswap::{SwapArgs, SwapReply, SwapAmountsReply, SwapAmountsResult, LPBalancesReply, UserBalancesReply, UserBalancesResult, TxId};
 pub use portfolio::{CurrentPosition, IndexState};
 pub use rebalancing::{TargetAllocation, AllocationDeviation, RebalanceAction};
-pub use common::HealthStatus;
+pub use common::{HealthStatus, StatusMessage, StatusSeverity};
 
 // Constants
 pub const KONG_LOCKER_ID: &str = "eazgb-giaaa-aaaap-qqc2q-cai";
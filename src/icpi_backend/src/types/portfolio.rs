@@ -9,6 +9,7 @@ pub struct CurrentPosition {
     pub balance: Nat,           // Raw token balance with proper decimals
     pub usd_value: f64,         // USD value (using f64 for Candid compatibility)
     pub percentage: f64,        // Percentage of portfolio
+    pub pool_unavailable_since: Option<u64>, // Set while the token's Kongswap pool is gone
 }
 
 // Combined state for rebalancing decisions
@@ -20,6 +21,29 @@ pub struct IndexState {
     pub deviations: Vec<super::rebalancing::AllocationDeviation>,
     pub timestamp: u64,
     pub ckusdt_balance: Nat,  // Track available ckUSDT for rebalancing
+    pub targets_tvl_timestamp: Option<u64>,  // When the TVL behind target_allocations was computed
+    pub data_quality: Option<DataQuality>,   // None in states exported before it was tracked
+}
+
+// How complete and fresh the inputs behind an IndexState were
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataQuality {
+    Live,      // Every balance and price fetched fresh from its primary source
+    Degraded,  // A balance is missing or a price came from the fallback oracle
+    Cached,    // Complete, but targets come from last-good TVL after a Kong Locker failure
+}
+
+// Compact payload for frequent polling (NAV widget)
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone)]
+pub struct Ticker {
+    pub nav_e8: u64,                  // USD per ICPI, 8 decimals
+    pub total_value_e6: u64,          // Portfolio value in ckUSDT decimals
+    pub supply_e8: Nat,
+    pub change_24h_bps: Option<i64>,  // None until 24h of history exists
+    pub last_updated: u64,
+    pub is_stale: bool,               // Cached state older than its TTL
+    pub status_message: Option<super::common::StatusMessage>,
+    pub drawdown_bps: Option<u64>,    // NAV below its all-time high
 }
 
 // Cached data structures
@@ -42,6 +66,27 @@ pub struct TvlSummary {
     pub total_tvl_usd: f64,
     pub tokens: Vec<TokenTvl>,  // Renamed from token_tvls to match .did file
     pub timestamp: u64,
+    /// Served from the last-good TVL during a Kong Locker outage
+    pub is_stale: bool,
+}
+
+/// One tracked token's share of a TVL calculation
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TokenTvlContribution {
+    pub token: TrackedToken,
+    pub tvl_usd: f64,
+    pub lp_positions: u64,  // LP sides that contributed to tvl_usd
+}
+
+/// How a Kong Locker TVL calculation arrived at its numbers
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct TvlCalculationReport {
+    pub computed_at: u64,
+    pub canisters_total: u64,
+    pub canisters_failed: u64,        // Query failed or Kongswap returned an error
+    pub lp_entries_processed: u64,    // LP positions containing a tracked token
+    pub lp_entries_skipped: u64,      // Non-LP balances, unknown variants, untracked pools
+    pub per_token_contributions: Vec<TokenTvlContribution>,
 }
 
 // Aliases for .did file compatibility (all-caps TVL)
@@ -8,6 +8,8 @@ pub struct TargetAllocation {
     pub token: TrackedToken,
     pub target_percentage: f64,
     pub target_usd_value: f64,
+    /// Max weight cap that lowered this target (None if the target was under its cap)
+    pub weight_cap: Option<f64>,
 }
 
 // Allocation deviation
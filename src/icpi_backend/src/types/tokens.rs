@@ -86,4 +86,21 @@ pub struct TokenMetadata {
     pub symbol: String,
     pub canister_id: Principal,
     pub decimals: u8,
-}
\ No newline at end of file
+}
+
+/// One row of the index table: metadata, holdings, allocation and price
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone)]
+pub struct TokenDetail {
+    pub symbol: String,
+    pub canister_id: Principal,
+    pub decimals: u8,
+    /// Backend balance in token decimals
+    pub balance: candid::Nat,
+    pub usd_value: f64,
+    pub current_percentage: f64,
+    pub target_percentage: f64,
+    /// None if no price source answered
+    pub price_usd: Option<f64>,
+    /// Set while the token's Kongswap pool is gone
+    pub pool_unavailable_since: Option<u64>,
+}
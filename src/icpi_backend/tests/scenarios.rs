@@ -0,0 +1,212 @@
+//! End-to-end scenarios against the mock runtime
+//!
+//! Each test installs a `MockRuntime` (ledgers, Kongswap and Kong Locker in
+//! memory) and drives the real mint, rebalance and burn entry points under
+//! `futures::executor::block_on`. The index starts with 100 ICPI
+//! outstanding against 100 ckUSDT of reserves.
+//!
+//! Built against `icpi_backend::testing`, which needs the `testing`
+//! feature (enabled for this crate's tests through its dev-dependency).
+
+use candid::{Nat, Principal};
+use futures::executor::block_on;
+use std::rc::Rc;
+
+use icpi_backend::testing::{self as backend, TrackedToken};
+use icpi_backend::testing::constants::{CKUSDT_TRANSFER_FEE, ICPI_LEDGER_ID, MINT_FEE_AMOUNT};
+use icpi_backend::testing::minting::{get_user_mints, MintStatus};
+use icpi_backend::testing::runtime::mock::{LpPosition, MockRuntime};
+
+const SECOND: u64 = 1_000_000_000;
+const E6: u64 = 1_000_000;
+const E8: u64 = 100_000_000;
+
+struct Scenario {
+    runtime: Rc<MockRuntime>,
+    backend: Principal,
+    user: Principal,
+    ckusdt: Principal,
+    icpi: Principal,
+}
+
+impl Scenario {
+    fn user_balance(&self, ledger: Principal) -> Nat {
+        self.runtime.balance(ledger, self.user)
+    }
+
+    fn backend_balance(&self, ledger: Principal) -> Nat {
+        self.runtime.balance(ledger, self.backend)
+    }
+}
+
+fn ledger(token: &TrackedToken) -> Principal {
+    token.get_canister_id().unwrap()
+}
+
+fn lp(symbol: &str, usd: f64) -> LpPosition {
+    LpPosition {
+        symbol_0: symbol.to_string(),
+        usd_amount_0: usd,
+        symbol_1: "ckUSDT".to_string(),
+        usd_amount_1: usd,
+    }
+}
+
+fn setup() -> Scenario {
+    let runtime = MockRuntime::install(1_700_000_000 * SECOND);
+    let backend = backend::runtime::canister_id();
+    let user = Principal::from_slice(&[1]);
+    let ckusdt = ledger(&TrackedToken::ckUSDT);
+    let icpi = Principal::from_text(ICPI_LEDGER_ID).unwrap();
+
+    runtime.add_ledger(ckusdt, CKUSDT_TRANSFER_FEE, None);
+    // The backend is the ICPI minting account
+    runtime.add_ledger(icpi, 10_000, Some(backend));
+    for token in TrackedToken::all() {
+        runtime.add_ledger(ledger(token), 10_000, None);
+    }
+
+    runtime.deposit(icpi, Principal::from_slice(&[2]), 100 * E8);
+    runtime.deposit(ckusdt, backend, 100 * E6);
+
+    runtime.deposit(ckusdt, user, 50 * E6);
+    runtime.approve(ckusdt, user, backend, 50 * E6);
+    runtime.approve(icpi, user, backend, 100 * E8);
+
+    runtime.list_token("ckUSDT", ckusdt, 6, E6);
+    runtime.list_token("ALEX", ledger(&TrackedToken::ALEX), 8, 200_000);
+    runtime.list_token("ZERO", ledger(&TrackedToken::ZERO), 8, 50_000);
+    runtime.list_token("KONG", ledger(&TrackedToken::KONG), 8, 10_000);
+    runtime.list_token("BOB", ledger(&TrackedToken::BOB), 8, 500_000);
+
+    // Targets: ALEX 40%, ZERO 30%, KONG 20%, BOB 10%
+    runtime.add_lock_canister(
+        Principal::from_slice(&[3]),
+        Principal::from_slice(&[4]),
+        vec![lp("ALEX", 40_000.0), lp("ZERO", 30_000.0), lp("KONG", 20_000.0), lp("BOB", 10_000.0)],
+    );
+
+    Scenario { runtime, backend, user, ckusdt, icpi }
+}
+
+fn mint(s: &Scenario, amount: u64) -> backend::Result<Nat> {
+    let mint_id = block_on(backend::initiate_mint(s.user, Nat::from(amount), None))?;
+    block_on(backend::complete_mint(s.user, mint_id))
+}
+
+/// Build a TVL generation, then run a manual rebalance cycle on it
+fn rebalance() -> backend::Result<String> {
+    use backend::tvl::generation;
+
+    block_on(generation::begin_generation()).unwrap();
+    block_on(generation::step_generation()).unwrap();
+    block_on(backend::perform_rebalance(false))
+}
+
+#[test]
+fn test_mint_rebalance_burn() {
+    let s = setup();
+
+    // Mint 10 ckUSDT against a snapshot TVL of 100 ckUSDT plus the 0.1 fee
+    let minted = mint(&s, 10 * E6).unwrap();
+    assert_eq!(minted, Nat::from(999_000_999u64));
+    assert_eq!(s.user_balance(s.icpi), minted);
+    assert_eq!(s.backend_balance(s.ckusdt), Nat::from(110 * E6 + MINT_FEE_AMOUNT));
+
+    // All reserves are ckUSDT, so the cycle buys the most underweight token
+    s.runtime.advance_seconds(120);
+    let ckusdt_before = s.backend_balance(s.ckusdt);
+    let outcome = rebalance().unwrap();
+    assert!(outcome.starts_with("Bought"), "unexpected outcome: {}", outcome);
+    let alex = ledger(&TrackedToken::ALEX);
+    assert!(s.backend_balance(alex) > Nat::from(0u64));
+    assert!(s.backend_balance(s.ckusdt) < ckusdt_before);
+
+    // Burn half the position: the user gets a share of ckUSDT and ALEX
+    s.runtime.advance_seconds(120);
+    let supply_before = s.runtime.total_supply(s.icpi);
+    let user_ckusdt_before = s.user_balance(s.ckusdt);
+    let burned = Nat::from(5 * E8);
+    let result = block_on(backend::burn_icpi(s.user, burned.clone(), None, None)).unwrap();
+
+    assert!(result.failed_transfers.is_empty());
+    assert_eq!(s.runtime.total_supply(s.icpi), supply_before - burned.clone());
+    assert_eq!(s.user_balance(s.icpi), minted - burned);
+
+    let mut symbols: Vec<&str> = result.successful_transfers.iter().map(|(symbol, _)| symbol.as_str()).collect();
+    symbols.sort();
+    assert_eq!(symbols, vec!["ALEX", "ckUSDT"]);
+    for (symbol, amount) in &result.successful_transfers {
+        if symbol == "ckUSDT" {
+            // Net of the burn fee and its transfer_from fee
+            let fee_paid = Nat::from(MINT_FEE_AMOUNT + CKUSDT_TRANSFER_FEE);
+            assert_eq!(s.user_balance(s.ckusdt), user_ckusdt_before.clone() - fee_paid + amount.clone());
+        } else {
+            assert_eq!(s.user_balance(alex), amount.clone());
+        }
+    }
+}
+
+#[test]
+fn test_mint_refunded_when_ledger_mint_fails() {
+    let s = setup();
+    s.runtime.reject(s.icpi, "icrc1_transfer", "ledger stopped");
+
+    assert!(mint(&s, 10 * E6).is_err());
+
+    let mints = get_user_mints(&s.user);
+    assert_eq!(mints.len(), 1);
+    assert!(matches!(mints[0].status, MintStatus::FailedRefunded(_)), "status: {:?}", mints[0].status);
+
+    // The deposit came back less the refund's ledger fee; the mint fee is kept
+    let fees_paid = MINT_FEE_AMOUNT + 3 * CKUSDT_TRANSFER_FEE;
+    assert_eq!(s.user_balance(s.ckusdt), Nat::from(50 * E6 - fees_paid));
+    assert_eq!(s.backend_balance(s.ckusdt), Nat::from(100 * E6 + MINT_FEE_AMOUNT));
+    assert_eq!(s.runtime.total_supply(s.icpi), Nat::from(100 * E8));
+}
+
+#[test]
+fn test_burn_with_failed_transfer_queues_retry() {
+    let s = setup();
+    let alex = ledger(&TrackedToken::ALEX);
+    s.runtime.deposit(s.icpi, s.user, 5 * E8);
+    s.runtime.deposit(alex, s.backend, 1_000 * E8);
+    s.runtime.reject(alex, "icrc1_transfer", "ledger stopped");
+
+    let result = block_on(backend::burn_icpi(s.user, Nat::from(5 * E8), None, None)).unwrap();
+
+    // ckUSDT is delivered; ALEX fails and is queued for retry
+    assert_eq!(result.successful_transfers.len(), 1);
+    assert_eq!(result.successful_transfers[0].0, "ckUSDT");
+    assert_eq!(result.failed_transfers.len(), 1);
+    assert_eq!(result.failed_transfers[0].0, "ALEX");
+    assert_eq!(s.user_balance(alex), Nat::from(0u64));
+
+    let queue = backend::burning::transfer_retries::get_queue();
+    assert_eq!(queue.entries.len(), 1);
+    assert_eq!(queue.entries[0].user, s.user);
+    assert_eq!(queue.entries[0].token, "ALEX");
+    assert_eq!(queue.entries[0].amount, result.failed_transfers[0].1);
+
+    // The ICPI was burned regardless
+    assert_eq!(s.user_balance(s.icpi), Nat::from(0u64));
+    assert_eq!(s.runtime.total_supply(s.icpi), Nat::from(100 * E8));
+}
+
+#[test]
+fn test_previews_do_not_record_snapshots() {
+    use backend::tvl::generation;
+    use backend::{display, history};
+
+    let _s = setup();
+    block_on(generation::begin_generation()).unwrap();
+    block_on(generation::step_generation()).unwrap();
+
+    block_on(backend::minting::impact::simulate_mint_impact(Nat::from(10 * E6))).unwrap();
+    block_on(display::get_index_state_uncached()).unwrap();
+    assert!(history::get_latest_snapshot().is_none());
+
+    // The update path records one
+    block_on(display::refresh_index_state()).unwrap();
+    assert!(history::get_latest_snapshot().is_some());
+}
@@ -14,6 +14,12 @@ export interface UserTokenBalance {
   error?: string           // Error message if balance query failed
 }
 
+// Message for a backend ApiError: the IcpiError variant and whether a retry may succeed
+const apiErrorMessage = (err: any): string => {
+  const detail = JSON.stringify(err.error, (_, value) => (typeof value === 'bigint' ? value.toString() : value))
+  return err.retryable ? `${detail} (temporary, please retry)` : detail
+}
+
 // Define the query keys
 export const QUERY_KEYS = {
   INDEX_STATE: 'indexState',
@@ -115,7 +121,7 @@ export const useIndexState = (actor: Actor | null) => {
         return result.Ok
       } else if ('Err' in result) {
         console.error('get_index_state_cached returned error:', result.Err)
-        throw new Error(result.Err)
+        throw new Error(apiErrorMessage(result.Err))
       }
       throw new Error('Unexpected result format')
     },
@@ -183,7 +189,7 @@ export const useTVLData = (actor: Actor | null) => {
       if ('Ok' in result) {
         return result.Ok
       } else if ('Err' in result) {
-        throw new Error(result.Err)
+        throw new Error(apiErrorMessage(result.Err))
       }
       throw new Error('Unexpected result format')
     },
@@ -294,10 +300,10 @@ export const useMintICPI = (actor: Actor | null, agent: HttpAgent | null) => {
       }
 
       // Phase 1: Initiate mint (returns mint_id)
-      const initResult = await actor.initiate_mint(amountRaw)
+      const initResult = await actor.initiate_mint(amountRaw, [])
 
       if ('Err' in initResult) {
-        throw new Error(initResult.Err)
+        throw new Error(apiErrorMessage(initResult.Err))
       }
       const mintId = initResult.Ok
 
@@ -305,7 +311,7 @@ export const useMintICPI = (actor: Actor | null, agent: HttpAgent | null) => {
       const completeResult = await actor.complete_mint(mintId)
 
       if ('Err' in completeResult) {
-        throw new Error(completeResult.Err)
+        throw new Error(apiErrorMessage(completeResult.Err))
       }
 
       return completeResult.Ok
@@ -386,10 +392,10 @@ export const useRedeemICPI = (actor: Actor | null, agent: HttpAgent | null) => {
       }
 
       // Step 3: Call atomic burn_icpi (backend will verify burn and send redemption tokens)
-      const burnResult = await actor.burn_icpi(amountRaw)
+      const burnResult = await actor.burn_icpi(amountRaw, [], [])
 
       if ('Err' in burnResult) {
-        throw new Error(burnResult.Err)
+        throw new Error(apiErrorMessage(burnResult.Err))
       }
 
       return burnResult.Ok
@@ -505,7 +511,7 @@ export const useTokenMetadata = (actor: Actor | null) => {
       if ('Ok' in result) {
         return result.Ok
       } else if ('Err' in result) {
-        throw new Error(result.Err)
+        throw new Error(apiErrorMessage(result.Err))
       }
       throw new Error('Unexpected result format')
     },
@@ -553,7 +559,7 @@ export const useActualAllocations = (
       // Unwrap Result types
       if (!('Ok' in tokenMetadataResult) || !('Ok' in tvlDataResult)) {
         throw new Error(
-          'Err' in tokenMetadataResult ? tokenMetadataResult.Err :
+          'Err' in tokenMetadataResult ? apiErrorMessage(tokenMetadataResult.Err) :
           'Err' in tvlDataResult ? tvlDataResult.Err :
           'Failed to fetch data'
         )
@@ -599,7 +605,7 @@ export const useActualAllocations = (
         // Fallback: call it if somehow not cached yet
         const indexStateResult = await icpiActor.get_index_state()
         if (!('Ok' in indexStateResult)) {
-          throw new Error('Err' in indexStateResult ? indexStateResult.Err : 'Failed to get index state')
+          throw new Error('Err' in indexStateResult ? apiErrorMessage(indexStateResult.Err) : 'Failed to get index state')
         }
         indexState = indexStateResult.Ok
       }
@@ -701,7 +707,7 @@ export const useUserWalletBalances = (
       const tokenMetadataResult = await actor.get_token_metadata()
 
       if (!('Ok' in tokenMetadataResult)) {
-        throw new Error('Err' in tokenMetadataResult ? tokenMetadataResult.Err : 'Failed to fetch token metadata')
+        throw new Error('Err' in tokenMetadataResult ? apiErrorMessage(tokenMetadataResult.Err) : 'Failed to fetch token metadata')
       }
       const trackedTokensMetadata = tokenMetadataResult.Ok
 